@@ -87,6 +87,22 @@ impl NodeDebugClient {
 
         Ok(response.json()?)
     }
+
+    /// Retrieves the equivocation evidence recorded by the consensus of the node.
+    pub fn get_equivocation_evidence(&self) -> Result<serde_json::Value> {
+        let mut url = self.url.clone();
+        url.set_path("consensus/equivocation-evidence");
+        let response = self.client.get(url).send()?;
+
+        if !response.status().is_success() {
+            anyhow::bail!(
+                "Error querying equivocation evidence: {}",
+                response.status()
+            );
+        }
+
+        Ok(response.json()?)
+    }
 }
 
 /// Implement default utility client for AsyncNodeDebugInterface
@@ -4,16 +4,20 @@
 //! Debug interface to access information in a specific node.
 
 use diem_config::config::NodeConfig;
+use diem_infallible::RwLock;
 use diem_logger::{info, json_log, Filter, Logger};
 use diem_metrics::json_metrics::get_git_rev;
 use serde::{Deserialize, Serialize};
 use std::{net::SocketAddr, sync::Arc};
 use tokio::runtime::{Builder, Runtime};
-use warp::Filter as _;
+use warp::{http::StatusCode, Filter as _};
+
+/// Source of the JSON document served by a route of the debug interface.
+pub type JsonSource = Box<dyn Fn() -> anyhow::Result<serde_json::Value> + Send + Sync>;
 
-#[derive(Debug)]
 pub struct NodeDebugService {
     runtime: Runtime,
+    equivocation_evidence: Arc<RwLock<Option<JsonSource>>>,
 }
 
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
@@ -83,16 +87,59 @@ impl NodeDebugService {
         };
         let node_info_route = warp::path("node-info").map(move || warp::reply::json(&node_info));
 
-        let routes = log.or(warp::get().and(metrics.or(events).or(node_info_route)));
+        // Get /consensus/equivocation-evidence (evidence of equivocating validators recorded by
+        // consensus, to be exported for governance)
+        let equivocation_evidence: Arc<RwLock<Option<JsonSource>>> = Arc::new(RwLock::new(None));
+        let evidence_source = equivocation_evidence.clone();
+        let equivocation_evidence_route =
+            warp::path!("consensus" / "equivocation-evidence").map(move || {
+                match evidence_source.read().as_ref().map(|source| source()) {
+                    Some(Ok(evidence)) => {
+                        warp::reply::with_status(warp::reply::json(&evidence), StatusCode::OK)
+                    }
+                    Some(Err(e)) => warp::reply::with_status(
+                        warp::reply::json(&e.to_string()),
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                    ),
+                    None => warp::reply::with_status(
+                        warp::reply::json(&"Consensus is not running"),
+                        StatusCode::SERVICE_UNAVAILABLE,
+                    ),
+                }
+            });
+
+        let routes = log.or(warp::get().and(
+            metrics
+                .or(events)
+                .or(node_info_route)
+                .or(equivocation_evidence_route),
+        ));
 
         runtime
             .handle()
             .spawn(async move { warp::serve(routes).bind(address).await });
 
-        Self { runtime }
+        Self {
+            runtime,
+            equivocation_evidence,
+        }
     }
 
     pub fn runtime(&self) -> &Runtime {
         &self.runtime
     }
+
+    /// Sets the source of the equivocation evidence, which is only available once consensus has
+    /// started.
+    pub fn set_equivocation_evidence_source(&self, source: JsonSource) {
+        *self.equivocation_evidence.write() = Some(source);
+    }
+}
+
+impl std::fmt::Debug for NodeDebugService {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("NodeDebugService")
+            .field("runtime", &self.runtime)
+            .finish()
+    }
 }
@@ -364,6 +364,12 @@ impl Bucket {
         }
     }
 
+    /// Tells us if the bucket is full, i.e. if it's in the same state as a new one
+    pub fn is_full(&mut self) -> bool {
+        self.refill();
+        self.tokens >= self.size
+    }
+
     /// Add new tokens
     /// Ensures bucket doesn't overfill
     fn add_tokens(&mut self, new_tokens: usize) {
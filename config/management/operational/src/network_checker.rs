@@ -28,9 +28,9 @@ use netcore::transport::tcp::{resolve_and_connect, TcpSocket};
 use network::{
     noise::{HandshakeAuthMode, NoiseUpgrader},
     protocols::wire::handshake::v1::ProtocolIdSet,
-    transport::{upgrade_outbound, UpgradeContext, SUPPORTED_MESSAGING_PROTOCOL},
+    transport::{upgrade_outbound, UpgradeContext, SUPPORTED_MESSAGING_PROTOCOLS},
 };
-use std::{collections::BTreeMap, sync::Arc};
+use std::sync::Arc;
 use structopt::StructOpt;
 use tokio::{runtime::Runtime, time::Duration};
 
@@ -215,8 +215,10 @@ fn build_upgrade_context(
     let network_context = NetworkContext::new(RoleType::FullNode, network_id, peer_id);
 
     // Let's make sure some protocol can be connected.  In the future we may want to allow for specifics
-    let mut supported_protocols = BTreeMap::new();
-    supported_protocols.insert(SUPPORTED_MESSAGING_PROTOCOL, ProtocolIdSet::all_known());
+    let supported_protocols = SUPPORTED_MESSAGING_PROTOCOLS
+        .iter()
+        .map(|version| (*version, ProtocolIdSet::all_known()))
+        .collect();
 
     // Build the noise and network handshake, without running a full Noise server with listener
     Arc::new(UpgradeContext::new(
@@ -250,6 +250,8 @@ pub fn strip_address(address: &NetworkAddress) -> NetworkAddress {
                     | Protocol::Ip6(_)
                     | Protocol::Memory(_)
                     | Protocol::Tcp(_)
+                    | Protocol::Udp(_)
+                    | Protocol::Quic
             )
         })
         .cloned()
@@ -17,6 +17,7 @@ pub struct ConsensusConfig {
     // Timeout for consensus to pull transactions from mempool and get a response (in milliseconds)
     pub mempool_txn_pull_timeout_ms: u64,
     pub round_initial_timeout_ms: u64,
+    pub round_timeout_strategy: RoundTimeoutStrategy,
     pub proposer_type: ConsensusProposerType,
     pub safety_rules: SafetyRulesConfig,
     // Only sync committed transactions but not vote for any pending blocks. This is useful when
@@ -41,6 +42,7 @@ impl Default for ConsensusConfig {
             mempool_txn_pull_timeout_ms: 1000,
             mempool_executed_txn_timeout_ms: 1000,
             round_initial_timeout_ms: 1000,
+            round_timeout_strategy: RoundTimeoutStrategy::Exponential,
             proposer_type: ConsensusProposerType::LeaderReputation(LeaderReputationConfig {
                 active_weights: 99,
                 inactive_weights: 1,
@@ -61,6 +63,47 @@ impl ConsensusConfig {
     }
 }
 
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum RoundTimeoutStrategy {
+    // The round timeout starts at round_initial_timeout_ms and grows exponentially with the
+    // number of rounds since the last commit
+    Exponential,
+    // The base round timeout is derived from the QC formation latency recently observed for the
+    // round leader, and grows exponentially with the number of rounds since the last commit
+    Adaptive(AdaptiveRoundTimeoutConfig),
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdaptiveRoundTimeoutConfig {
+    // Number of recent QC formation latencies kept per leader
+    pub window_size: usize,
+    // Number of latencies required from a leader before using its own window, the latencies
+    // of all the leaders are used until then
+    pub min_leader_samples: usize,
+    // Percentile (between 0 and 100) of the observed latencies used as the base timeout
+    pub percentile: f64,
+    // Added to the latency percentile to absorb jitter
+    pub margin_ms: u64,
+    // Bounds of the base round timeout
+    pub min_timeout_ms: u64,
+    pub max_timeout_ms: u64,
+}
+
+impl Default for AdaptiveRoundTimeoutConfig {
+    fn default() -> AdaptiveRoundTimeoutConfig {
+        AdaptiveRoundTimeoutConfig {
+            window_size: 100,
+            min_leader_samples: 10,
+            percentile: 99.0,
+            margin_ms: 200,
+            min_timeout_ms: 500,
+            max_timeout_ms: 5000,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 #[serde(rename_all = "snake_case", tag = "type")]
 pub enum ConsensusProposerType {
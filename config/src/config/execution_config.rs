@@ -12,6 +12,7 @@ use std::{
 };
 
 const GENESIS_DEFAULT: &str = "genesis.blob";
+const MISMATCH_DIR_DEFAULT: &str = "execution_mismatches";
 
 #[derive(Clone, Deserialize, PartialEq, Serialize)]
 #[serde(default, deny_unknown_fields)]
@@ -23,6 +24,15 @@ pub struct ExecutionConfig {
     pub service: ExecutionCorrectnessService,
     pub backend: SecureBackend,
     pub network_timeout_ms: u64,
+    /// Selects how the VM executes blocks (see `ExecutionMode`).
+    pub execution_mode: ExecutionMode,
+    /// Where blocks whose sequential and parallel outputs differ are dumped in shadow mode.
+    /// Relative paths are resolved against the node's data directory.
+    pub mismatch_dump_dir: PathBuf,
+    /// Bounds on the code cache of the Move VM.
+    pub code_cache: CodeCacheConfig,
+    #[serde(skip)]
+    data_dir: PathBuf,
 }
 
 impl std::fmt::Debug for ExecutionConfig {
@@ -40,8 +50,13 @@ impl std::fmt::Debug for ExecutionConfig {
         )?;
         write!(
             f,
-            ", sign_vote_proposal: {:?}, service: {:?}, backend: {:?} }}",
-            self.sign_vote_proposal, self.service, self.backend
+            ", sign_vote_proposal: {:?}, service: {:?}, backend: {:?}, execution_mode: {:?}, \
+             code_cache: {:?} }}",
+            self.sign_vote_proposal,
+            self.service,
+            self.backend,
+            self.execution_mode,
+            self.code_cache
         )?;
         self.service.fmt(f)
     }
@@ -57,6 +72,10 @@ impl Default for ExecutionConfig {
             sign_vote_proposal: true,
             // Default value of 30 seconds for the network timeout.
             network_timeout_ms: 30_000,
+            execution_mode: ExecutionMode::Sequential,
+            mismatch_dump_dir: PathBuf::from(MISMATCH_DIR_DEFAULT),
+            code_cache: CodeCacheConfig::default(),
+            data_dir: PathBuf::from("/opt/diem/data"),
         }
     }
 }
@@ -92,11 +111,49 @@ impl ExecutionConfig {
 
     pub fn set_data_dir(&mut self, data_dir: PathBuf) {
         if let SecureBackend::OnDiskStorage(backend) = &mut self.backend {
-            backend.set_data_dir(data_dir);
+            backend.set_data_dir(data_dir.clone());
+        }
+        self.data_dir = data_dir;
+    }
+
+    pub fn mismatch_dump_dir(&self) -> PathBuf {
+        if self.mismatch_dump_dir.is_relative() {
+            self.data_dir.join(&self.mismatch_dump_dir)
+        } else {
+            self.mismatch_dump_dir.clone()
         }
     }
 }
 
+/// Defines how the VM should execute blocks
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecutionMode {
+    /// Transactions are executed one after the other.
+    Sequential,
+    /// Transactions are executed in parallel, with dependencies discovered during execution.
+    Parallel,
+    /// Blocks are executed both sequentially and in parallel. The sequential outputs are used,
+    /// and any difference with the parallel outputs is logged, counted and dumped to disk.
+    /// Both executions run one after the other on the commit path, so block execution takes
+    /// roughly twice as long as in sequential mode.
+    Shadow,
+}
+
+/// Bounds on the modules and scripts kept loaded by the Move VM. When a bound is exceeded, the
+/// least recently used code is evicted and loaded again from storage when needed. Missing bounds
+/// are unlimited.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CodeCacheConfig {
+    /// The maximum number of modules.
+    pub max_modules: Option<usize>,
+    /// The maximum total size of the modules, in serialized bytes.
+    pub max_module_bytes: Option<usize>,
+    /// The maximum number of scripts.
+    pub max_scripts: Option<usize>,
+}
+
 /// Defines how execution correctness should be run
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "snake_case", tag = "type")]
@@ -155,6 +212,48 @@ mod test {
         assert_eq!(config.genesis, Some(fake_genesis));
     }
 
+    #[test]
+    fn test_mismatch_dump_dir() {
+        let (mut config, path) = generate_config();
+        config.set_data_dir(path.path().to_path_buf());
+        assert_eq!(
+            config.mismatch_dump_dir(),
+            path.path().join(MISMATCH_DIR_DEFAULT)
+        );
+
+        config.mismatch_dump_dir = PathBuf::from("/tmp/mismatches");
+        assert_eq!(config.mismatch_dump_dir(), PathBuf::from("/tmp/mismatches"));
+    }
+
+    #[test]
+    fn test_execution_mode_serialization() {
+        let config = ExecutionConfig {
+            execution_mode: ExecutionMode::Shadow,
+            ..ExecutionConfig::default()
+        };
+        let serialized = serde_yaml::to_string(&config).unwrap();
+        assert!(serialized.contains("execution_mode: shadow"));
+        let deserialized: ExecutionConfig = serde_yaml::from_str(&serialized).unwrap();
+        assert_eq!(deserialized.execution_mode, ExecutionMode::Shadow);
+    }
+
+    #[test]
+    fn test_code_cache_deserialization() {
+        let code_cache: CodeCacheConfig = serde_yaml::from_str("max_modules: 100").unwrap();
+        assert_eq!(
+            code_cache,
+            CodeCacheConfig {
+                max_modules: Some(100),
+                max_module_bytes: None,
+                max_scripts: None,
+            }
+        );
+        assert_eq!(
+            ExecutionConfig::default().code_cache,
+            CodeCacheConfig::default()
+        );
+    }
+
     fn generate_config() -> (ExecutionConfig, TempPath) {
         let temp_dir = TempPath::new();
         temp_dir.create_as_dir().expect("error creating tempdir");
@@ -45,6 +45,15 @@ pub const MAX_FRAME_SIZE: usize = 8 * 1024 * 1024; /* 8 MiB */
 pub const CONNECTION_BACKOFF_BASE: u64 = 2;
 pub const IP_BYTE_BUCKET_RATE: usize = 102400 /* 100 KiB */;
 pub const IP_BYTE_BUCKET_SIZE: usize = IP_BYTE_BUCKET_RATE;
+pub const PEER_BYTE_BUCKET_RATE: usize = 1024 * 1024 /* 1 MiB */;
+pub const PEER_BYTE_BUCKET_SIZE: usize = MAX_FRAME_SIZE;
+pub const PEER_MESSAGE_BUCKET_RATE: usize = 500;
+pub const PEER_MESSAGE_BUCKET_SIZE: usize = 2 * PEER_MESSAGE_BUCKET_RATE;
+pub const MAX_PEER_ROTATIONS: usize = 1;
+pub const SLOW_PEER_LATENCY_MS: u64 = 500;
+pub const PEER_EXCHANGE_INTERVAL_MS: u64 = 60_000; /* 1 minute */
+pub const PEER_EXCHANGE_MAX_PEERS_PER_SOURCE: usize = 16;
+pub const PEER_EXCHANGE_MAX_DISCOVERED_PEERS: usize = 64;
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(default, deny_unknown_fields)]
@@ -98,6 +107,16 @@ pub struct NetworkConfig {
     pub inbound_rate_limit_config: Option<RateLimitConfig>,
     // Outbound rate limiting configuration, if not specified, no rate limiting
     pub outbound_rate_limit_config: Option<RateLimitConfig>,
+    // Budgets for the messages received from each peer, if not specified, no quotas
+    pub inbound_peer_quota_config: Option<PeerQuotaConfig>,
+    // Budgets for the messages sent to each peer, if not specified, no quotas
+    pub outbound_peer_quota_config: Option<PeerQuotaConfig>,
+    // Maximum number of slow outbound peers disconnected per connectivity check
+    // to make room for better ones, 0 disables rotation
+    pub max_peer_rotations: usize,
+    // Average healthcheck round trip time above which an outbound peer is
+    // considered slow and may be rotated out
+    pub slow_peer_latency_ms: u64,
 }
 
 impl Default for NetworkConfig {
@@ -132,6 +151,10 @@ impl NetworkConfig {
             max_inbound_connections: MAX_INBOUND_CONNECTIONS,
             inbound_rate_limit_config: None,
             outbound_rate_limit_config: None,
+            inbound_peer_quota_config: None,
+            outbound_peer_quota_config: None,
+            max_peer_rotations: MAX_PEER_ROTATIONS,
+            slow_peer_latency_ms: SLOW_PEER_LATENCY_MS,
         };
         config.prepare_identity();
         config
@@ -190,6 +213,15 @@ impl NetworkConfig {
     /// Per convenience, so that NetworkId isn't needed to be specified for `validator_networks`
     pub fn load_validator_network(&mut self) -> Result<(), Error> {
         self.network_id = NetworkId::Validator;
+        if self
+            .discovery_methods()
+            .iter()
+            .any(|method| matches!(method, DiscoveryMethod::PeerExchange(_)))
+        {
+            return Err(Error::InvariantViolation(
+                "Peer exchange discovery is not supported on the validator network".to_string(),
+            ));
+        }
         self.load()
     }
 
@@ -304,9 +336,33 @@ impl NetworkConfig {
 pub enum DiscoveryMethod {
     Onchain,
     File(PathBuf, Duration),
+    PeerExchange(PeerExchangeConfig),
     None,
 }
 
+/// Configuration for discovering peers by exchanging the addresses of reachable
+/// peers with our outbound peers. Only supported on fullnode networks.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PeerExchangeConfig {
+    // Interval to send our reachable peers to connected peers
+    pub interval_ms: u64,
+    // Maximum number of peers accepted from a single outbound peer
+    pub max_peers_per_source: usize,
+    // Maximum number of peers discovered through peer exchange
+    pub max_discovered_peers: usize,
+}
+
+impl Default for PeerExchangeConfig {
+    fn default() -> Self {
+        Self {
+            interval_ms: PEER_EXCHANGE_INTERVAL_MS,
+            max_peers_per_source: PEER_EXCHANGE_MAX_PEERS_PER_SOURCE,
+            max_discovered_peers: PEER_EXCHANGE_MAX_DISCOVERED_PEERS,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "snake_case", tag = "type")]
 pub enum Identity {
@@ -371,6 +427,42 @@ impl Default for RateLimitConfig {
     }
 }
 
+/// Message and byte budgets enforced on every peer, in a single direction. The
+/// budgets of a peer are kept across its connections.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PeerQuotaConfig {
+    /// Budget shared by all the protocols of a peer
+    pub peer: QuotaLimits,
+    /// Additional budgets for specific protocols of a peer, keyed by protocol
+    /// name (e.g. "MempoolDirectSend")
+    pub protocols: HashMap<String, QuotaLimits>,
+}
+
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QuotaLimits {
+    /// Maximum number of bytes/s
+    pub byte_bucket_rate: usize,
+    /// Maximum burst of bytes, messages larger than this are always rejected
+    pub byte_bucket_size: usize,
+    /// Maximum number of messages/s
+    pub message_bucket_rate: usize,
+    /// Maximum burst of messages
+    pub message_bucket_size: usize,
+}
+
+impl Default for QuotaLimits {
+    fn default() -> Self {
+        Self {
+            byte_bucket_rate: PEER_BYTE_BUCKET_RATE,
+            byte_bucket_size: PEER_BYTE_BUCKET_SIZE,
+            message_bucket_rate: PEER_MESSAGE_BUCKET_RATE,
+            message_bucket_size: PEER_MESSAGE_BUCKET_SIZE,
+        }
+    }
+}
+
 pub type PeerSet = HashMap<PeerId, Peer>;
 
 // TODO: Combine with RoleType?
@@ -27,6 +27,10 @@ pub struct StateSyncConfig {
     pub sync_request_timeout_ms: u64,
     // interval used for checking state synchronization progress
     pub tick_interval_ms: u64,
+    // The configuration of the state sync v2 driver
+    pub state_sync_driver: StateSyncDriverConfig,
+    // The configuration of the storage service
+    pub storage_service: StorageServiceConfig,
 }
 
 impl Default for StateSyncConfig {
@@ -41,6 +45,98 @@ impl Default for StateSyncConfig {
             multicast_timeout_ms: 30_000,
             sync_request_timeout_ms: 60_000,
             tick_interval_ms: 100,
+            state_sync_driver: StateSyncDriverConfig::default(),
+            storage_service: StorageServiceConfig::default(),
+        }
+    }
+}
+
+/// The bootstrapping mode used by the state sync v2 driver to catch up to the
+/// latest epoch ending ledger info advertised by the network.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BootstrappingMode {
+    ApplyTransactionOutputsFromGenesis, // Apply transaction outputs (starting at genesis)
+    DownloadLatestAccountStates, // Download the account states (at the latest epoch ending version)
+    ExecuteTransactionsFromGenesis, // Execute transactions (starting at genesis)
+}
+
+/// The syncing mode used by the state sync v2 driver to stay up-to-date once
+/// bootstrapping has completed.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContinuousSyncingMode {
+    ApplyTransactionOutputs, // Apply transaction outputs to stay up-to-date
+    ExecuteTransactions,     // Execute transactions to stay up-to-date
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StateSyncDriverConfig {
+    // The mode by which to bootstrap
+    pub bootstrapping_mode: BootstrappingMode,
+    // The mode by which to sync after bootstrapping
+    pub continuous_syncing_mode: ContinuousSyncingMode,
+    // If state sync v2 should be used instead of state sync v1
+    pub enable_state_sync_v2: bool,
+    // The interval (ms) at which to check state sync progress
+    pub progress_check_interval_ms: u64,
+    // The maximum time (ms) to wait for a data stream notification before
+    // dropping the stream and creating a new one
+    pub max_stream_wait_time_ms: u64,
+    // The timeout (ms) for mempool to acknowledge a commit notification
+    pub mempool_commit_ack_timeout_ms: u64,
+}
+
+impl Default for StateSyncDriverConfig {
+    fn default() -> Self {
+        Self {
+            bootstrapping_mode: BootstrappingMode::ApplyTransactionOutputsFromGenesis,
+            continuous_syncing_mode: ContinuousSyncingMode::ApplyTransactionOutputs,
+            enable_state_sync_v2: false,
+            progress_check_interval_ms: 100,
+            max_stream_wait_time_ms: 5_000,
+            mempool_commit_ack_timeout_ms: 5_000,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageServiceConfig {
+    // The maximum number of account states per chunk
+    pub max_account_states_chunk_size: u64,
+    // The maximum number of concurrent storage server tasks
+    pub max_concurrent_requests: u64,
+    // The maximum number of epoch ending ledger infos per chunk
+    pub max_epoch_chunk_size: u64,
+    // The maximum number of responses held in the LRU cache before eviction
+    pub max_lru_cache_size: u64,
+    // The maximum number of bytes to send in a single network message. Chunks
+    // that exceed this size are shrunk by the server before being sent.
+    pub max_network_chunk_bytes: u64,
+    // The maximum time (ms) the server holds a data subscription before dropping it
+    pub max_subscription_period_ms: u64,
+    // The maximum number of transactions per chunk
+    pub max_transaction_chunk_size: u64,
+    // The maximum number of transaction outputs per chunk
+    pub max_transaction_output_chunk_size: u64,
+    // The interval (ms) at which to refresh (and expire) data subscriptions
+    pub subscription_refresh_interval_ms: u64,
+}
+
+impl Default for StorageServiceConfig {
+    fn default() -> Self {
+        Self {
+            max_account_states_chunk_size: 1000,
+            max_concurrent_requests: 100,
+            max_epoch_chunk_size: 1000,
+            max_lru_cache_size: 100,
+            max_network_chunk_bytes: 1024 * 1024, // 1 MiB
+            max_subscription_period_ms: 5_000,
+            max_transaction_chunk_size: 1000,
+            max_transaction_output_chunk_size: 1000,
+            subscription_refresh_interval_ms: 100,
         }
     }
 }
@@ -0,0 +1,168 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    block::Block,
+    common::{Author, Round},
+    vote::Vote,
+};
+use anyhow::{ensure, Context};
+use diem_crypto::{hash::CryptoHash, HashValue};
+use diem_types::epoch_state::EpochState;
+use serde::{Deserialize, Serialize};
+use short_hex_str::AsShortHexStr;
+use std::fmt;
+
+/// Two conflicting messages signed by the same author for the same (epoch, round).
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub enum ConflictingMessages {
+    /// Two votes carrying different LedgerInfos.
+    Votes(Vote, Vote),
+    /// Two different proposals.
+    Proposals(Block, Block),
+}
+
+/// EquivocationEvidence is a self-contained proof that a validator signed two conflicting
+/// messages in the same round. It carries the validator set of the epoch so that it can be
+/// verified offline (e.g., by governance tooling) without access to the ledger.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct EquivocationEvidence {
+    messages: ConflictingMessages,
+    epoch_state: EpochState,
+}
+
+impl fmt::Display for EquivocationEvidence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "EquivocationEvidence[kind: {}, author: ", self.kind())?;
+        match self.author() {
+            Some(author) => write!(f, "{}", author.short_str())?,
+            None => write!(f, "NIL")?,
+        }
+        write!(f, ", epoch: {}, round: {}]", self.epoch(), self.round())
+    }
+}
+
+impl EquivocationEvidence {
+    pub fn new_from_votes(first: Vote, second: Vote, epoch_state: EpochState) -> Self {
+        Self {
+            messages: ConflictingMessages::Votes(first, second),
+            epoch_state,
+        }
+    }
+
+    pub fn new_from_proposals(first: Block, second: Block, epoch_state: EpochState) -> Self {
+        Self {
+            messages: ConflictingMessages::Proposals(first, second),
+            epoch_state,
+        }
+    }
+
+    pub fn messages(&self) -> &ConflictingMessages {
+        &self.messages
+    }
+
+    pub fn epoch_state(&self) -> &EpochState {
+        &self.epoch_state
+    }
+
+    /// Short label of the kind of equivocation, used for metrics and logs.
+    pub fn kind(&self) -> &'static str {
+        match &self.messages {
+            ConflictingMessages::Votes(..) => "vote",
+            ConflictingMessages::Proposals(..) => "proposal",
+        }
+    }
+
+    /// The author of the conflicting messages (taken from the first message). Nil and genesis
+    /// blocks have no author, such evidence is rejected by verify().
+    pub fn author(&self) -> Option<Author> {
+        match &self.messages {
+            ConflictingMessages::Votes(first, _) => Some(first.author()),
+            ConflictingMessages::Proposals(first, _) => first.author(),
+        }
+    }
+
+    pub fn epoch(&self) -> u64 {
+        match &self.messages {
+            ConflictingMessages::Votes(first, _) => first.epoch(),
+            ConflictingMessages::Proposals(first, _) => first.epoch(),
+        }
+    }
+
+    pub fn round(&self) -> Round {
+        match &self.messages {
+            ConflictingMessages::Votes(first, _) => first.vote_data().proposed().round(),
+            ConflictingMessages::Proposals(first, _) => first.round(),
+        }
+    }
+
+    /// Unique identifier of the evidence, used as the storage key. It doesn't depend on the order
+    /// of the two messages, so (A, B) and (B, A) are recorded only once.
+    pub fn id(&self) -> HashValue {
+        let (mut first, mut second) = match &self.messages {
+            ConflictingMessages::Votes(first, second) => (to_bytes(first), to_bytes(second)),
+            ConflictingMessages::Proposals(first, second) => (to_bytes(first), to_bytes(second)),
+        };
+        if first > second {
+            std::mem::swap(&mut first, &mut second);
+        }
+        HashValue::sha3_256_of(&to_bytes(&(self.kind(), first, second)))
+    }
+
+    /// Verifies that both messages are correctly signed by the same author of the attached
+    /// epoch, target the same (epoch, round) and are in conflict with each other.
+    pub fn verify(&self) -> anyhow::Result<()> {
+        let verifier = &self.epoch_state.verifier;
+        match &self.messages {
+            ConflictingMessages::Votes(first, second) => {
+                ensure!(
+                    first.author() == second.author(),
+                    "Votes are from different authors"
+                );
+                ensure!(
+                    (first.epoch(), first.vote_data().proposed().round())
+                        == (second.epoch(), second.vote_data().proposed().round()),
+                    "Votes are for different (epoch, round)"
+                );
+                ensure!(
+                    first.ledger_info().hash() != second.ledger_info().hash(),
+                    "Votes are not conflicting"
+                );
+                first
+                    .verify(verifier)
+                    .context("Failed to verify first vote")?;
+                second
+                    .verify(verifier)
+                    .context("Failed to verify second vote")?;
+            }
+            ConflictingMessages::Proposals(first, second) => {
+                ensure!(
+                    first.author().is_some() && first.author() == second.author(),
+                    "Proposals are from different authors"
+                );
+                ensure!(
+                    (first.epoch(), first.round()) == (second.epoch(), second.round()),
+                    "Proposals are for different (epoch, round)"
+                );
+                ensure!(first.id() != second.id(), "Proposals are not conflicting");
+                first
+                    .validate_signature(verifier)
+                    .context("Failed to verify first proposal")?;
+                second
+                    .validate_signature(verifier)
+                    .context("Failed to verify second proposal")?;
+            }
+        }
+        ensure!(
+            self.epoch() == self.epoch_state.epoch,
+            "Evidence epoch {} doesn't match validator set epoch {}",
+            self.epoch(),
+            self.epoch_state.epoch
+        );
+        Ok(())
+    }
+}
+
+fn to_bytes<T: Serialize>(value: &T) -> Vec<u8> {
+    bcs::to_bytes(value).expect("EquivocationEvidence serialization failed")
+}
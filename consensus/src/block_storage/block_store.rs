@@ -426,9 +426,7 @@ impl BlockStore {
     /// Replace the highest timeout certificate in case the given one has a higher round.
     /// In case a timeout certificate is updated, persist it to storage.
     pub fn insert_timeout_certificate(&self, tc: Arc<TimeoutCertificate>) -> anyhow::Result<()> {
-        let cur_tc_round = self
-            .highest_2chain_timeout_cert()
-            .map_or(0, |tc| tc.round());
+        let cur_tc_round = self.highest_timeout_cert().map_or(0, |tc| tc.round());
         if tc.round() <= cur_tc_round {
             return Ok(());
         }
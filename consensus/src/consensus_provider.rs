@@ -6,12 +6,13 @@ use crate::{
     epoch_manager::EpochManager,
     network::NetworkTask,
     network_interface::{ConsensusNetworkEvents, ConsensusNetworkSender},
-    persistent_liveness_storage::StorageWriteProxy,
+    persistent_liveness_storage::{PersistentLivenessStorage, StorageWriteProxy},
     state_computer::ExecutionProxy,
     txn_manager::MempoolProxy,
     util::time_service::ClockTimeService,
 };
 use consensus_notifications::ConsensusNotificationSender;
+use debug_interface::node_debug_service::JsonSource;
 use diem_config::config::NodeConfig;
 use diem_logger::prelude::*;
 use diem_mempool::ConsensusRequest;
@@ -23,7 +24,9 @@ use std::sync::Arc;
 use storage_interface::default_protocol::DbReaderWriter;
 use tokio::runtime::{self, Runtime};
 
-/// Helper function to start consensus based on configuration and return the runtime
+/// Helper function to start consensus based on configuration and return the runtime, along with
+/// the source of the equivocation evidence recorded so far, serialized to JSON so that it can be
+/// exported through the node debug interface for governance tooling
 pub fn start_consensus(
     node_config: &NodeConfig,
     mut network_sender: ConsensusNetworkSender,
@@ -33,13 +36,18 @@ pub fn start_consensus(
     diem_db: DbReaderWriter,
     reconfig_events: ReconfigNotificationListener,
     peer_metadata_storage: Arc<PeerMetadataStorage>,
-) -> Runtime {
+) -> (Runtime, JsonSource) {
     let runtime = runtime::Builder::new_multi_thread()
         .thread_name("consensus")
         .enable_all()
         .build()
         .expect("Failed to create Tokio runtime!");
     let storage = Arc::new(StorageWriteProxy::new(node_config, diem_db.reader.clone()));
+    let evidence_storage = storage.clone();
+    let equivocation_evidence: JsonSource = Box::new(move || {
+        let evidence = evidence_storage.retrieve_equivocation_evidence()?;
+        Ok(serde_json::to_value(evidence)?)
+    });
     let txn_manager = Arc::new(MempoolProxy::new(
         consensus_to_mempool_sender,
         node_config.consensus.mempool_poll_count,
@@ -78,5 +86,5 @@ pub fn start_consensus(
     runtime.spawn(epoch_mgr.start(timeout_receiver, network_receiver));
 
     debug!("Consensus started.");
-    runtime
+    (runtime, equivocation_evidence)
 }
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use super::*;
+use crate::test_utils::random_equivocation_evidence;
 use consensus_types::block::block_test_utils::certificate_for_genesis;
 use diem_temppath::TempPath;
 
@@ -73,3 +74,17 @@ fn test_delete_block_and_qc() {
     assert_eq!(db.get_blocks().unwrap().len(), 0);
     assert_eq!(db.get_quorum_certificates().unwrap().len(), 0);
 }
+
+#[test]
+fn test_equivocation_evidence() {
+    let tmp_dir = TempPath::new();
+    let db = ConsensusDB::new(&tmp_dir);
+
+    assert!(db.get_equivocation_evidence().unwrap().is_empty());
+
+    let evidence = random_equivocation_evidence();
+    db.save_equivocation_evidence(&evidence).unwrap();
+    // saving the same evidence twice keeps a single record
+    db.save_equivocation_evidence(&evidence).unwrap();
+    assert_eq!(db.get_equivocation_evidence().unwrap(), vec![evidence]);
+}
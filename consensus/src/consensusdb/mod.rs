@@ -8,16 +8,19 @@ mod schema;
 use crate::{
     consensusdb::schema::{
         block::BlockSchema,
+        equivocation_evidence::EquivocationEvidenceSchema,
         quorum_certificate::QCSchema,
         single_entry::{SingleEntryKey, SingleEntrySchema},
     },
     error::DbError,
 };
 use anyhow::Result;
-use consensus_types::{block::Block, quorum_cert::QuorumCert};
+use consensus_types::{
+    block::Block, equivocation_evidence::EquivocationEvidence, quorum_cert::QuorumCert,
+};
 use diem_crypto::HashValue;
 use diem_logger::prelude::*;
-use schema::{BLOCK_CF_NAME, QC_CF_NAME, SINGLE_ENTRY_CF_NAME};
+use schema::{BLOCK_CF_NAME, EQUIVOCATION_EVIDENCE_CF_NAME, QC_CF_NAME, SINGLE_ENTRY_CF_NAME};
 use schemadb::{Options, ReadOptions, SchemaBatch, DB, DEFAULT_CF_NAME};
 use std::{collections::HashMap, iter::Iterator, path::Path, time::Instant};
 
@@ -32,6 +35,7 @@ impl ConsensusDB {
             BLOCK_CF_NAME,
             QC_CF_NAME,
             SINGLE_ENTRY_CF_NAME,
+            EQUIVOCATION_EVIDENCE_CF_NAME,
         ];
 
         let path = db_root_path.as_ref().join("consensusdb");
@@ -141,6 +145,30 @@ impl ConsensusDB {
         self.commit(batch)
     }
 
+    /// Persist the evidence of an equivocating validator. The evidence is never pruned so that
+    /// it can be exported later on.
+    pub fn save_equivocation_evidence(
+        &self,
+        evidence: &EquivocationEvidence,
+    ) -> Result<(), DbError> {
+        let mut batch = SchemaBatch::new();
+        batch.put::<EquivocationEvidenceSchema>(&evidence.id(), evidence)?;
+        self.commit(batch)
+    }
+
+    /// Get all the equivocation evidence ever recorded, ordered by (epoch, round).
+    pub fn get_equivocation_evidence(&self) -> Result<Vec<EquivocationEvidence>, DbError> {
+        let mut iter = self
+            .db
+            .iter::<EquivocationEvidenceSchema>(ReadOptions::default())?;
+        iter.seek_to_first();
+        let mut evidence = iter
+            .map(|item| item.map(|(_id, evidence)| evidence))
+            .collect::<Result<Vec<_>>>()?;
+        evidence.sort_by_key(|e| (e.epoch(), e.round()));
+        Ok(evidence)
+    }
+
     /// Write the whole schema batch including all data necessary to mutate the ledger
     /// state of some transaction by leveraging rocksdb atomicity support.
     fn commit(&self, batch: SchemaBatch) -> Result<(), DbError> {
@@ -0,0 +1,50 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines physical storage schema for the equivocation evidence observed by
+//! consensus.
+//!
+//! Serialized evidence bytes identified by the evidence id.
+//! ```text
+//! |<---key---->|<--------value-------->|
+//! | evidence_id | EquivocationEvidence |
+//! ```
+
+use super::EQUIVOCATION_EVIDENCE_CF_NAME;
+use anyhow::Result;
+use consensus_types::equivocation_evidence::EquivocationEvidence;
+use diem_crypto::HashValue;
+use schemadb::{
+    define_schema,
+    schema::{KeyCodec, ValueCodec},
+};
+
+define_schema!(
+    EquivocationEvidenceSchema,
+    HashValue,
+    EquivocationEvidence,
+    EQUIVOCATION_EVIDENCE_CF_NAME
+);
+
+impl KeyCodec<EquivocationEvidenceSchema> for HashValue {
+    fn encode_key(&self) -> Result<Vec<u8>> {
+        Ok(self.to_vec())
+    }
+
+    fn decode_key(data: &[u8]) -> Result<Self> {
+        Ok(HashValue::from_slice(data)?)
+    }
+}
+
+impl ValueCodec<EquivocationEvidenceSchema> for EquivocationEvidence {
+    fn encode_value(&self) -> Result<Vec<u8>> {
+        Ok(bcs::to_bytes(self)?)
+    }
+
+    fn decode_value(data: &[u8]) -> Result<Self> {
+        Ok(bcs::from_bytes(data)?)
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,12 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use crate::test_utils::random_equivocation_evidence;
+use schemadb::schema::assert_encode_decode;
+
+#[test]
+fn test_encode_decode() {
+    let evidence = random_equivocation_evidence();
+    assert_encode_decode::<EquivocationEvidenceSchema>(&evidence.id(), &evidence);
+}
@@ -98,6 +98,16 @@ pub static COMMITTED_VOTES_IN_WINDOW: Lazy<IntGauge> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Count of the equivocation evidence recorded since last restart, by kind (vote or proposal).
+pub static EQUIVOCATION_EVIDENCE_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_consensus_equivocation_evidence_count",
+        "Count of the equivocation evidence recorded since last restart, by kind.",
+        &["kind"]
+    )
+    .unwrap()
+});
+
 //////////////////////
 // RoundState COUNTERS
 //////////////////////
@@ -145,6 +155,37 @@ pub static ROUND_TIMEOUT_MS: Lazy<IntGauge> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Histogram of the round timeouts chosen by the local round_state.
+pub static ROUND_TIMEOUT_CHOSEN_S: Lazy<DurationHistogram> = Lazy::new(|| {
+    DurationHistogram::new(
+        register_histogram!(
+            "diem_consensus_round_timeout_chosen_s",
+            "Histogram of the round timeouts chosen by the local round_state."
+        )
+        .unwrap(),
+    )
+});
+
+/// Histogram of the time between the start of a round and the QC of this round being observed.
+pub static QC_FORMATION_LATENCY_S: Lazy<DurationHistogram> = Lazy::new(|| {
+    DurationHistogram::new(
+        register_histogram!(
+            "diem_consensus_qc_formation_latency_s",
+            "Histogram of the time between the start of a round and the QC of this round being observed."
+        )
+        .unwrap(),
+    )
+});
+
+/// The base round timeout derived from the observed QC latencies (adaptive timeouts only).
+pub static ADAPTIVE_BASE_ROUND_TIMEOUT_MS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_consensus_adaptive_base_round_timeout_ms",
+        "The base round timeout derived from the observed QC latencies (adaptive timeouts only)."
+    )
+    .unwrap()
+});
+
 ////////////////////////
 // SYNC MANAGER COUNTERS
 ////////////////////////
@@ -16,7 +16,10 @@ use crate::{
         proposer_election::ProposerElection,
         rotating_proposer_election::{choose_leader, RotatingProposer},
         round_proposer_election::RoundProposer,
-        round_state::{ExponentialTimeInterval, RoundState, RoundStateLogSchema},
+        round_state::{
+            AdaptiveTimeInterval, ExponentialTimeInterval, RoundState, RoundStateLogSchema,
+            RoundTimeInterval,
+        },
     },
     logging::{LogEvent, LogSchema},
     metrics_safety_rules::MetricsSafetyRules,
@@ -33,7 +36,9 @@ use consensus_types::{
     common::{Author, Round},
     epoch_retrieval::EpochRetrievalRequest,
 };
-use diem_config::config::{ConsensusConfig, ConsensusProposerType, NodeConfig};
+use diem_config::config::{
+    ConsensusConfig, ConsensusProposerType, NodeConfig, RoundTimeoutStrategy,
+};
 use diem_infallible::{duration_since_epoch, Mutex};
 use diem_logger::prelude::*;
 use diem_metrics::monitor;
@@ -153,15 +158,28 @@ impl EpochManager {
         &self,
         time_service: Arc<dyn TimeService>,
         timeout_sender: channel::Sender<Round>,
+        onchain_config: &OnChainConsensusConfig,
     ) -> RoundState {
         // 1.5^6 ~= 11
         // Timeout goes from initial_timeout to initial_timeout*11 in 6 steps
-        let time_interval = Box::new(ExponentialTimeInterval::new(
-            Duration::from_millis(self.config.round_initial_timeout_ms),
-            1.2,
-            6,
-        ));
-        RoundState::new(time_interval, time_service, timeout_sender)
+        let initial_timeout = Duration::from_millis(self.config.round_initial_timeout_ms);
+        let time_interval: Box<dyn RoundTimeInterval> = match &self.config.round_timeout_strategy {
+            RoundTimeoutStrategy::Exponential => {
+                Box::new(ExponentialTimeInterval::new(initial_timeout, 1.2, 6))
+            }
+            RoundTimeoutStrategy::Adaptive(config) => Box::new(AdaptiveTimeInterval::new(
+                config.clone(),
+                initial_timeout,
+                1.2,
+                6,
+            )),
+        };
+        RoundState::new(
+            time_interval,
+            time_service,
+            timeout_sender,
+            onchain_config.two_chain(),
+        )
     }
 
     /// Create a proposer election handler based on proposers
@@ -395,8 +413,11 @@ impl EpochManager {
         }
 
         info!(epoch = epoch, "Create RoundState");
-        let round_state =
-            self.create_round_state(self.time_service.clone(), self.timeout_sender.clone());
+        let round_state = self.create_round_state(
+            self.time_service.clone(),
+            self.timeout_sender.clone(),
+            &onchain_config,
+        );
 
         info!(epoch = epoch, "Create ProposerElection");
         let proposer_election = self.create_proposer_election(&epoch_state, &onchain_config);
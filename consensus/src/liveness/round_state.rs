@@ -3,14 +3,26 @@
 
 use crate::{
     counters,
+    liveness::proposer_election::ProposerElection,
     pending_votes::{PendingVotes, VoteReceptionResult},
     util::time_service::{SendTask, TimeService},
 };
-use consensus_types::{common::Round, sync_info::SyncInfo, vote::Vote};
+use consensus_types::{
+    block::Block,
+    common::{Author, Round},
+    sync_info::SyncInfo,
+    vote::Vote,
+};
+use diem_config::config::AdaptiveRoundTimeoutConfig;
 use diem_logger::{prelude::*, Schema};
 use diem_types::validator_verifier::ValidatorVerifier;
 use serde::Serialize;
-use std::{fmt, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    sync::Arc,
+    time::Duration,
+};
 
 /// A reason for starting a new round: introduced for monitoring / debug purposes.
 #[derive(Serialize, Eq, Debug, PartialEq)]
@@ -62,6 +74,37 @@ pub trait RoundTimeInterval: Send + Sync + 'static {
     /// to calculate the round duration of round 6 and the highest committed round is 3 (meaning
     /// the highest round to commit a block is round 5, then the round index is 0.
     fn get_round_duration(&self, round_index_after_committed_qc: usize) -> Duration;
+
+    /// Return the duration for a round led by the given leader. Intervals that don't depend on
+    /// the leader fall back to `get_round_duration`.
+    fn get_leader_round_duration(
+        &self,
+        _leader: Author,
+        round_index_after_committed_qc: usize,
+    ) -> Duration {
+        self.get_round_duration(round_index_after_committed_qc)
+    }
+
+    /// Record the time it took to form a QC in a round led by the given leader.
+    fn record_qc_latency(&mut self, _leader: Author, _latency: Duration) {}
+}
+
+/// Multiplies the base duration by exponent_base^pow.
+fn exponential_backoff(base_ms: u64, exponent_base: f64, pow: u32) -> Duration {
+    let base_multiplier = exponent_base.powf(f64::from(pow));
+    let duration_ms = ((base_ms as f64) * base_multiplier).ceil() as u64;
+    Duration::from_millis(duration_ms)
+}
+
+fn check_backoff_params(exponent_base: f64, max_exponent: usize) {
+    assert!(
+        max_exponent < 32,
+        "max_exponent for RoundStateTimeInterval should be <32"
+    );
+    assert!(
+        exponent_base.powf(max_exponent as f64).ceil() < f64::from(std::u32::MAX),
+        "Maximum interval multiplier should be less then u32::Max"
+    );
 }
 
 /// Round durations increase exponentially
@@ -88,14 +131,7 @@ impl ExponentialTimeInterval {
     }
 
     pub fn new(base: Duration, exponent_base: f64, max_exponent: usize) -> Self {
-        assert!(
-            max_exponent < 32,
-            "max_exponent for RoundStateTimeInterval should be <32"
-        );
-        assert!(
-            exponent_base.powf(max_exponent as f64).ceil() < f64::from(std::u32::MAX),
-            "Maximum interval multiplier should be less then u32::Max"
-        );
+        check_backoff_params(exponent_base, max_exponent);
         ExponentialTimeInterval {
             base_ms: base.as_millis() as u64, // any reasonable ms timeout fits u64 perfectly
             exponent_base,
@@ -107,9 +143,108 @@ impl ExponentialTimeInterval {
 impl RoundTimeInterval for ExponentialTimeInterval {
     fn get_round_duration(&self, round_index_after_committed_qc: usize) -> Duration {
         let pow = round_index_after_committed_qc.min(self.max_exponent) as u32;
-        let base_multiplier = self.exponent_base.powf(f64::from(pow));
-        let duration_ms = ((self.base_ms as f64) * base_multiplier).ceil() as u64;
-        Duration::from_millis(duration_ms)
+        exponential_backoff(self.base_ms, self.exponent_base, pow)
+    }
+}
+
+/// Round durations follow the observed latency of forming QCs
+/// Basically time interval is base * mul^power, like ExponentialTimeInterval, except that
+/// base is the configured percentile of the recent QC formation latencies of the round leader
+/// (or of all the leaders if not enough are known for it) plus a margin, bounded by min/max.
+/// The initial base is used until enough latencies are observed.
+pub struct AdaptiveTimeInterval {
+    config: AdaptiveRoundTimeoutConfig,
+    // Base duration used until enough QC latencies are observed.
+    initial_base_ms: u64,
+    // By how much we increase interval every time
+    exponent_base: f64,
+    // Maximum time interval won't exceed base * mul^max_pow.
+    max_exponent: usize,
+    // Recent QC formation latencies of all leaders.
+    latencies_ms: VecDeque<u64>,
+    // Recent QC formation latencies of every leader.
+    leader_latencies_ms: HashMap<Author, VecDeque<u64>>,
+}
+
+impl AdaptiveTimeInterval {
+    pub fn new(
+        config: AdaptiveRoundTimeoutConfig,
+        initial_base: Duration,
+        exponent_base: f64,
+        max_exponent: usize,
+    ) -> Self {
+        check_backoff_params(exponent_base, max_exponent);
+        assert!(
+            (0.0..=100.0).contains(&config.percentile),
+            "percentile for AdaptiveTimeInterval should be between 0 and 100"
+        );
+        assert!(
+            config.min_timeout_ms <= config.max_timeout_ms,
+            "min_timeout_ms for AdaptiveTimeInterval should not exceed max_timeout_ms"
+        );
+        AdaptiveTimeInterval {
+            config,
+            initial_base_ms: initial_base.as_millis() as u64,
+            exponent_base,
+            max_exponent,
+            latencies_ms: VecDeque::new(),
+            leader_latencies_ms: HashMap::new(),
+        }
+    }
+
+    /// The base duration of a round led by the given leader (if known).
+    fn base_ms(&self, leader: Option<&Author>) -> u64 {
+        let min_samples = self.config.min_leader_samples.max(1);
+        let window = leader
+            .and_then(|leader| self.leader_latencies_ms.get(leader))
+            .filter(|window| window.len() >= min_samples)
+            .or_else(|| Some(&self.latencies_ms).filter(|window| window.len() >= min_samples));
+        match window {
+            Some(window) => {
+                let mut latencies: Vec<_> = window.iter().copied().collect();
+                latencies.sort_unstable();
+                let rank = ((self.config.percentile / 100.0) * (latencies.len() - 1) as f64).round()
+                    as usize;
+                (latencies[rank] + self.config.margin_ms)
+                    .clamp(self.config.min_timeout_ms, self.config.max_timeout_ms)
+            }
+            None => self.initial_base_ms,
+        }
+    }
+
+    fn round_duration(&self, leader: Option<&Author>, round_index: usize) -> Duration {
+        let base_ms = self.base_ms(leader);
+        counters::ADAPTIVE_BASE_ROUND_TIMEOUT_MS.set(base_ms as i64);
+        let pow = round_index.min(self.max_exponent) as u32;
+        exponential_backoff(base_ms, self.exponent_base, pow)
+    }
+}
+
+impl RoundTimeInterval for AdaptiveTimeInterval {
+    fn get_round_duration(&self, round_index_after_committed_qc: usize) -> Duration {
+        self.round_duration(None, round_index_after_committed_qc)
+    }
+
+    fn get_leader_round_duration(
+        &self,
+        leader: Author,
+        round_index_after_committed_qc: usize,
+    ) -> Duration {
+        self.round_duration(Some(&leader), round_index_after_committed_qc)
+    }
+
+    fn record_qc_latency(&mut self, leader: Author, latency: Duration) {
+        let window_size = self.config.window_size.max(1);
+        let latency_ms = latency.as_millis() as u64;
+        for window in [
+            &mut self.latencies_ms,
+            self.leader_latencies_ms.entry(leader).or_default(),
+        ] {
+            if window.len() >= window_size {
+                window.pop_front();
+            }
+            window.push_back(latency_ms);
+        }
     }
 }
 
@@ -135,6 +270,9 @@ pub struct RoundState {
     // inform the RoundState about certain committed rounds (e.g., NIL blocks): in this case the
     // committed round in RoundState might lag behind the committed round of a block tree.
     highest_committed_round: Round,
+    // Number of consecutive certified rounds required to commit a block in the current epoch
+    // (2 for the 2-chain commit rule, 3 for the 3-chain commit rule).
+    commit_chain_length: Round,
     // Current round is max{highest_qc, highest_tc} + 1.
     current_round: Round,
     // The deadline for the next local timeout event. It is reset every time a new round start, or
@@ -149,6 +287,13 @@ pub struct RoundState {
     pending_votes: PendingVotes,
     // Vote sent locally for the current round.
     vote_sent: Option<Vote>,
+    // Proposals received for the current round, used to detect equivocating proposers.
+    proposals_seen: HashMap<Author, Block>,
+    // The leader of the current round.
+    current_round_leader: Option<Author>,
+    // The time the current round started at, used to measure the QC formation latency.
+    // Represents as Duration since UNIX_EPOCH.
+    current_round_start: Duration,
 }
 
 #[derive(Default, Schema)]
@@ -180,6 +325,7 @@ impl RoundState {
         time_interval: Box<dyn RoundTimeInterval>,
         time_service: Arc<dyn TimeService>,
         timeout_sender: channel::Sender<Round>,
+        two_chain: bool,
     ) -> Self {
         // Our counters are initialized lazily, so they're not going to appear in
         // Prometheus if some conditions never happen. Invoking get() function enforces creation.
@@ -190,12 +336,16 @@ impl RoundState {
         Self {
             time_interval,
             highest_committed_round: 0,
+            commit_chain_length: if two_chain { 2 } else { 3 },
             current_round: 0,
             current_round_deadline: time_service.get_current_timestamp(),
+            current_round_start: time_service.get_current_timestamp(),
             time_service,
             timeout_sender,
             pending_votes: PendingVotes::new(),
             vote_sent: None,
+            proposals_seen: HashMap::new(),
+            current_round_leader: None,
         }
     }
 
@@ -223,16 +373,35 @@ impl RoundState {
 
     /// Notify the RoundState about the potentially new QC, TC, and highest committed round.
     /// Note that some of these values might not be available by the caller.
-    pub fn process_certificates(&mut self, sync_info: SyncInfo) -> Option<NewRoundEvent> {
+    /// The proposer election is used to attribute the QC formation latency and the timeout of
+    /// the new round to their leaders.
+    pub fn process_certificates(
+        &mut self,
+        sync_info: SyncInfo,
+        proposer_election: &dyn ProposerElection,
+    ) -> Option<NewRoundEvent> {
         if sync_info.highest_ordered_round() > self.highest_committed_round {
             self.highest_committed_round = sync_info.highest_ordered_round();
         }
         let new_round = sync_info.highest_round() + 1;
         if new_round > self.current_round {
+            let now = self.time_service.get_current_timestamp();
+            if self.current_round > 0 && sync_info.highest_certified_round() == self.current_round {
+                // A QC was formed for the round we were in.
+                let latency = now.saturating_sub(self.current_round_start);
+                counters::QC_FORMATION_LATENCY_S.observe_duration(latency);
+                let leader = self
+                    .current_round_leader
+                    .unwrap_or_else(|| proposer_election.get_valid_proposer(self.current_round));
+                self.time_interval.record_qc_latency(leader, latency);
+            }
             // Start a new round.
             self.current_round = new_round;
+            self.current_round_start = now;
+            self.current_round_leader = Some(proposer_election.get_valid_proposer(new_round));
             self.pending_votes = PendingVotes::new();
             self.vote_sent = None;
+            self.proposals_seen.clear();
             let timeout = self.setup_timeout();
             // The new round reason is QCReady in case both QC.round + 1 == new_round, otherwise
             // it's Timeout and TC.round + 1 == new_round.
@@ -277,6 +446,23 @@ impl RoundState {
         self.vote_sent.clone()
     }
 
+    /// Record a proposal received for the current round. In case the same author already
+    /// proposed a different block in this round, return the previously seen proposal.
+    pub fn record_proposal(&mut self, proposal: &Block) -> Option<Block> {
+        let author = proposal.author()?;
+        if proposal.round() != self.current_round {
+            return None;
+        }
+        match self.proposals_seen.get(&author) {
+            Some(previous) if previous.id() != proposal.id() => Some(previous.clone()),
+            Some(_) => None,
+            None => {
+                self.proposals_seen.insert(author, proposal.clone());
+                None
+            }
+        }
+    }
+
     /// Setup the timeout task and return the duration of the current timeout
     fn setup_timeout(&mut self) -> Duration {
         let timeout_sender = self.timeout_sender.clone();
@@ -295,18 +481,24 @@ impl RoundState {
     fn setup_deadline(&mut self) -> Duration {
         let round_index_after_committed_round = {
             if self.highest_committed_round == 0 {
-                // Genesis doesn't require the 3-chain rule for commit, hence start the index at
-                // the round after genesis.
+                // Genesis doesn't require the commit rule, hence start the index at the round
+                // after genesis.
                 self.current_round - 1
-            } else if self.current_round < self.highest_committed_round + 3 {
+            } else if self.current_round < self.highest_committed_round + self.commit_chain_length {
                 0
             } else {
-                self.current_round - self.highest_committed_round - 3
+                self.current_round - self.highest_committed_round - self.commit_chain_length
             }
         } as usize;
-        let timeout = self
-            .time_interval
-            .get_round_duration(round_index_after_committed_round);
+        let timeout = match self.current_round_leader {
+            Some(leader) => self
+                .time_interval
+                .get_leader_round_duration(leader, round_index_after_committed_round),
+            None => self
+                .time_interval
+                .get_round_duration(round_index_after_committed_round),
+        };
+        counters::ROUND_TIMEOUT_CHOSEN_S.observe_duration(timeout);
         let now = self.time_service.get_current_timestamp();
         debug!(
             round = self.current_round,
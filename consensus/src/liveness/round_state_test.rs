@@ -2,16 +2,25 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    liveness::round_state::{
-        ExponentialTimeInterval, NewRoundEvent, NewRoundReason, RoundState, RoundTimeInterval,
+    liveness::{
+        rotating_proposer_election::RotatingProposer,
+        round_state::{
+            AdaptiveTimeInterval, ExponentialTimeInterval, NewRoundEvent, NewRoundReason,
+            RoundState, RoundTimeInterval,
+        },
     },
     util::mock_time_service::SimulatedTimeService,
 };
 
 use consensus_types::{
-    common::Round, quorum_cert::QuorumCert, sync_info::SyncInfo, timeout::Timeout,
-    timeout_certificate::TimeoutCertificate, vote_data::VoteData,
+    common::{Author, Round},
+    quorum_cert::QuorumCert,
+    sync_info::SyncInfo,
+    timeout::Timeout,
+    timeout_certificate::TimeoutCertificate,
+    vote_data::VoteData,
 };
+use diem_config::config::AdaptiveRoundTimeoutConfig;
 use diem_crypto::HashValue;
 use diem_types::{
     block_info::BlockInfo,
@@ -33,13 +42,102 @@ fn test_round_time_interval() {
     assert_eq!(6750, interval.get_round_duration(1000).as_millis());
 }
 
+#[test]
+fn test_adaptive_round_time_interval() {
+    let config = AdaptiveRoundTimeoutConfig {
+        window_size: 4,
+        min_leader_samples: 2,
+        percentile: 90.0,
+        margin_ms: 100,
+        min_timeout_ms: 200,
+        max_timeout_ms: 1000,
+    };
+    let mut interval = AdaptiveTimeInterval::new(config, Duration::from_millis(3000), 2.0, 2);
+    let (fast_leader, slow_leader) = (Author::random(), Author::random());
+
+    // Not enough latencies observed, the initial base is used
+    assert_eq!(3000, interval.get_round_duration(0).as_millis());
+    interval.record_qc_latency(fast_leader, Duration::from_millis(10));
+    assert_eq!(
+        3000,
+        interval
+            .get_leader_round_duration(fast_leader, 0)
+            .as_millis()
+    );
+
+    // Base is bounded by the minimum, and grows exponentially
+    interval.record_qc_latency(fast_leader, Duration::from_millis(20));
+    assert_eq!(
+        200,
+        interval
+            .get_leader_round_duration(fast_leader, 0)
+            .as_millis()
+    );
+    assert_eq!(
+        800,
+        interval
+            .get_leader_round_duration(fast_leader, 1000)
+            .as_millis()
+    );
+
+    // A leader without enough latencies uses the latencies of all the leaders
+    interval.record_qc_latency(slow_leader, Duration::from_millis(500));
+    interval.record_qc_latency(fast_leader, Duration::from_millis(30));
+    assert_eq!(
+        // 90th percentile of [10, 20, 500, 30] + margin
+        600,
+        interval
+            .get_leader_round_duration(slow_leader, 0)
+            .as_millis()
+    );
+
+    // Base is bounded by the maximum
+    interval.record_qc_latency(slow_leader, Duration::from_millis(5000));
+    assert_eq!(
+        1000,
+        interval
+            .get_leader_round_duration(slow_leader, 0)
+            .as_millis()
+    );
+}
+
+#[test]
+/// The round timeout starts growing once the rounds since the last commit exceed the commit rule
+fn test_round_timeout_follows_commit_rule() {
+    let interval = || {
+        Box::new(ExponentialTimeInterval::new(
+            Duration::from_millis(100),
+            2.0,
+            6,
+        ))
+    };
+    let (mut three_chain, _) = make_round_state_with_commit_rule(interval(), false);
+    let (mut two_chain, _) = make_round_state_with_commit_rule(interval(), true);
+
+    // Round 13 could have committed the block of round 11 under the 2-chain rule only
+    let sync_info = generate_sync_info(Some(12), None, Some(10));
+    let three_chain_event = three_chain
+        .process_certificates(sync_info.clone(), &proposer_election())
+        .unwrap();
+    let two_chain_event = two_chain
+        .process_certificates(sync_info, &proposer_election())
+        .unwrap();
+    assert_eq!(three_chain_event.round, 13);
+    assert_eq!(three_chain_event.timeout, Duration::from_millis(100));
+    assert_eq!(two_chain_event.round, 13);
+    assert_eq!(two_chain_event.timeout, Duration::from_millis(200));
+}
+
 #[tokio::test]
 /// Verify that RoundState properly outputs local timeout events upon timeout
 async fn test_basic_timeout() {
     let (mut pm, mut timeout_rx) = make_round_state();
 
     // jump start the round_state
-    pm.process_certificates(generate_sync_info(Some(0), None, None));
+    pm.process_certificates(
+        generate_sync_info(Some(0), None, None),
+        &proposer_election(),
+    );
     for _ in 0..2 {
         let round = timeout_rx.next().await.unwrap();
         // Here we just test timeout send retry,
@@ -55,35 +153,68 @@ fn test_round_event_generation() {
     // Happy path with new QC
     expect_qc(
         2,
-        pm.process_certificates(generate_sync_info(Some(1), None, None)),
+        pm.process_certificates(
+            generate_sync_info(Some(1), None, None),
+            &proposer_election(),
+        ),
     );
     // Old QC does not generate anything
     assert!(pm
-        .process_certificates(generate_sync_info(Some(1), None, None))
+        .process_certificates(
+            generate_sync_info(Some(1), None, None),
+            &proposer_election()
+        )
         .is_none());
     // A TC for a higher round
     expect_timeout(
         3,
-        pm.process_certificates(generate_sync_info(None, Some(2), None)),
+        pm.process_certificates(
+            generate_sync_info(None, Some(2), None),
+            &proposer_election(),
+        ),
     );
     // In case both QC and TC are present choose the one with the higher value
     expect_timeout(
         4,
-        pm.process_certificates(generate_sync_info(Some(2), Some(3), None)),
+        pm.process_certificates(
+            generate_sync_info(Some(2), Some(3), None),
+            &proposer_election(),
+        ),
     );
     // In case both QC and TC are present with the same value, choose QC
     expect_qc(
         5,
-        pm.process_certificates(generate_sync_info(Some(4), Some(4), None)),
+        pm.process_certificates(
+            generate_sync_info(Some(4), Some(4), None),
+            &proposer_election(),
+        ),
     );
 }
 
+fn proposer_election() -> RotatingProposer {
+    RotatingProposer::new(vec![Author::random()], 1)
+}
+
 fn make_round_state() -> (RoundState, channel::Receiver<Round>) {
-    let time_interval = Box::new(ExponentialTimeInterval::fixed(Duration::from_millis(2)));
+    make_round_state_with_commit_rule(
+        Box::new(ExponentialTimeInterval::fixed(Duration::from_millis(2))),
+        false,
+    )
+}
+
+fn make_round_state_with_commit_rule(
+    time_interval: Box<dyn RoundTimeInterval>,
+    two_chain: bool,
+) -> (RoundState, channel::Receiver<Round>) {
     let simulated_time = SimulatedTimeService::auto_advance_until(Duration::from_millis(4));
     let (timeout_tx, timeout_rx) = channel::new_test(1_024);
     (
-        RoundState::new(time_interval, Arc::new(simulated_time), timeout_tx),
+        RoundState::new(
+            time_interval,
+            Arc::new(simulated_time),
+            timeout_tx,
+            two_chain,
+        ),
         timeout_rx,
     )
 }
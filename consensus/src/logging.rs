@@ -18,6 +18,7 @@ pub struct LogSchema {
 pub enum LogEvent {
     CommitViaBlock,
     CommitViaSync,
+    Equivocation,
     HelpPeerSync,
     NewEpoch,
     NewRound,
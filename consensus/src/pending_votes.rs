@@ -33,7 +33,8 @@ pub enum VoteReceptionResult {
     /// The very same vote message has been processed in past.
     DuplicateVote,
     /// The very same author has already voted for another proposal in this round (equivocation).
-    EquivocateVote,
+    /// Carries the previously seen vote so that the evidence can be recorded.
+    EquivocateVote(Box<Vote>),
     /// This block has just been certified after adding the vote.
     NewQuorumCertificate(Arc<QuorumCert>),
     /// The vote completes a new TimeoutCertificate
@@ -104,7 +105,7 @@ impl PendingVotes {
                     previous_vote = previously_seen_vote
                 );
 
-                return VoteReceptionResult::EquivocateVote;
+                return VoteReceptionResult::EquivocateVote(Box::new(previously_seen_vote.clone()));
             }
         }
 
@@ -263,11 +264,12 @@ impl fmt::Display for PendingVotes {
 mod tests {
     use super::{PendingVotes, VoteReceptionResult};
     use consensus_types::{
-        block::block_test_utils::certificate_for_genesis, vote::Vote, vote_data::VoteData,
+        block::block_test_utils::certificate_for_genesis,
+        equivocation_evidence::EquivocationEvidence, vote::Vote, vote_data::VoteData,
     };
     use diem_crypto::HashValue;
     use diem_types::{
-        block_info::BlockInfo, ledger_info::LedgerInfo,
+        block_info::BlockInfo, epoch_state::EpochState, ledger_info::LedgerInfo,
         validator_verifier::random_validator_verifier,
     };
 
@@ -322,7 +324,7 @@ mod tests {
         );
         assert_eq!(
             pending_votes.insert_vote(&vote_data_2_author_0, &validator),
-            VoteReceptionResult::EquivocateVote
+            VoteReceptionResult::EquivocateVote(Box::new(vote_data_1_author_0))
         );
 
         // a different author voting for a different result -> VoteAdded
@@ -351,6 +353,59 @@ mod tests {
         };
     }
 
+    #[test]
+    /// Verify that an equivocating vote produces verifiable evidence
+    fn test_equivocation_evidence() {
+        ::diem_logger::Logger::init_for_testing();
+
+        let (signers, validator) = random_validator_verifier(4, Some(2), false);
+        let epoch_state = EpochState {
+            epoch: 1,
+            verifier: validator.clone(),
+        };
+        let mut pending_votes = PendingVotes::new();
+
+        let vote_1 = Vote::new(
+            random_vote_data(),
+            signers[0].author(),
+            random_ledger_info(),
+            &signers[0],
+        );
+        let vote_2 = Vote::new(
+            random_vote_data(),
+            signers[0].author(),
+            random_ledger_info(),
+            &signers[0],
+        );
+        assert_eq!(
+            pending_votes.insert_vote(&vote_1, &validator),
+            VoteReceptionResult::VoteAdded(1)
+        );
+        let previous_vote = match pending_votes.insert_vote(&vote_2, &validator) {
+            VoteReceptionResult::EquivocateVote(previous_vote) => *previous_vote,
+            _ => panic!("Equivocation not detected."),
+        };
+        assert_eq!(previous_vote, vote_1);
+
+        let evidence = EquivocationEvidence::new_from_votes(
+            previous_vote.clone(),
+            vote_2.clone(),
+            epoch_state.clone(),
+        );
+        assert_eq!(evidence.author(), Some(signers[0].author()));
+        assert_eq!(evidence.round(), 1);
+        evidence.verify().unwrap();
+
+        // the order of the conflicting votes doesn't matter for the identity of the evidence
+        let swapped =
+            EquivocationEvidence::new_from_votes(vote_2, previous_vote, epoch_state.clone());
+        assert_eq!(evidence.id(), swapped.id());
+
+        // the same vote twice is not an evidence of equivocation
+        let evidence = EquivocationEvidence::new_from_votes(vote_1.clone(), vote_1, epoch_state);
+        assert!(evidence.verify().is_err());
+    }
+
     #[test]
     /// Verify that votes are properly aggregated to TC based on their rounds
     fn test_tc_aggregation() {
@@ -4,9 +4,9 @@
 use crate::{consensusdb::ConsensusDB, epoch_manager::LivenessStorageData, error::DbError};
 use anyhow::{format_err, Context, Result};
 use consensus_types::{
-    block::Block, common::Author, quorum_cert::QuorumCert,
-    timeout_2chain::TwoChainTimeoutCertificate, timeout_certificate::TimeoutCertificate,
-    vote::Vote, vote_data::VoteData,
+    block::Block, common::Author, equivocation_evidence::EquivocationEvidence,
+    quorum_cert::QuorumCert, timeout_2chain::TwoChainTimeoutCertificate,
+    timeout_certificate::TimeoutCertificate, vote::Vote, vote_data::VoteData,
 };
 use diem_config::config::NodeConfig;
 use diem_crypto::{ed25519::Ed25519Signature, HashValue};
@@ -55,6 +55,12 @@ pub trait PersistentLivenessStorage: Send + Sync {
         highest_timeout_cert: &TwoChainTimeoutCertificate,
     ) -> Result<()>;
 
+    /// Persist the evidence of an equivocating validator, it's kept across epochs.
+    fn save_equivocation_evidence(&self, evidence: &EquivocationEvidence) -> Result<()>;
+
+    /// Retrieve all the equivocation evidence persisted so far.
+    fn retrieve_equivocation_evidence(&self) -> Result<Vec<EquivocationEvidence>>;
+
     /// Retrieve a epoch change proof for SafetyRules so it can instantiate its
     /// ValidatorVerifier.
     fn retrieve_epoch_change_proof(&self, version: u64) -> Result<EpochChangeProof>;
@@ -485,6 +491,14 @@ impl PersistentLivenessStorage for StorageWriteProxy {
             .save_highest_2chain_timeout_certificate(bcs::to_bytes(highest_timeout_cert)?)?)
     }
 
+    fn save_equivocation_evidence(&self, evidence: &EquivocationEvidence) -> Result<()> {
+        Ok(self.db.save_equivocation_evidence(evidence)?)
+    }
+
+    fn retrieve_equivocation_evidence(&self) -> Result<Vec<EquivocationEvidence>> {
+        Ok(self.db.get_equivocation_evidence()?)
+    }
+
     fn retrieve_epoch_change_proof(&self, version: u64) -> Result<EpochChangeProof> {
         let (_, proofs, _) = self
             .diem_db
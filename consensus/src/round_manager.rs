@@ -26,6 +26,7 @@ use consensus_types::{
     block::Block,
     block_retrieval::{BlockRetrievalResponse, BlockRetrievalStatus},
     common::{Author, Round},
+    equivocation_evidence::EquivocationEvidence,
     experimental::{commit_decision::CommitDecision, commit_vote::CommitVote},
     proposal_msg::ProposalMsg,
     quorum_cert::QuorumCert,
@@ -264,6 +265,23 @@ impl RoundManager {
         self.onchain_config.two_chain()
     }
 
+    /// The commit rule is selected per epoch by the on-chain consensus config: timeout
+    /// certificates of the other commit rule can't be used to advance rounds in this epoch.
+    fn ensure_commit_rule(&self, sync_info: &SyncInfo) -> anyhow::Result<()> {
+        if self.two_chain() {
+            ensure!(
+                sync_info.highest_timeout_certificate().is_none(),
+                "[RoundManager] Received 3-chain timeout certificate in a 2-chain epoch"
+            );
+        } else {
+            ensure!(
+                sync_info.highest_2chain_timeout_cert().is_none(),
+                "[RoundManager] Received 2-chain timeout certificate in a 3-chain epoch"
+            );
+        }
+        Ok(())
+    }
+
     fn decoupled_execution(&self) -> bool {
         self.onchain_config.decoupled_execution()
     }
@@ -385,6 +403,7 @@ impl RoundManager {
         author: Author,
         help_remote: bool,
     ) -> anyhow::Result<()> {
+        self.ensure_commit_rule(sync_info)?;
         let local_sync_info = self.block_store.sync_info();
         if help_remote && local_sync_info.has_newer_certificates(sync_info) {
             counters::SYNC_INFO_MSGS_SENT_COUNT.inc();
@@ -578,7 +597,10 @@ impl RoundManager {
     /// This function is called only after all the dependencies of the given QC have been retrieved.
     async fn process_certificates(&mut self) -> anyhow::Result<()> {
         let sync_info = self.block_store.sync_info();
-        if let Some(new_round_event) = self.round_state.process_certificates(sync_info) {
+        if let Some(new_round_event) = self
+            .round_state
+            .process_certificates(sync_info, self.proposer_election.as_ref())
+        {
             self.process_new_round_event(new_round_event).await?;
         }
         Ok(())
@@ -601,6 +623,19 @@ impl RoundManager {
             block_parent_hash = proposal.quorum_cert().certified_block().id(),
         );
 
+        if self
+            .proposer_election
+            .is_valid_proposer(author, proposal.round())
+        {
+            if let Some(previous_proposal) = self.round_state.record_proposal(&proposal) {
+                self.record_equivocation(EquivocationEvidence::new_from_proposals(
+                    previous_proposal,
+                    proposal.clone(),
+                    self.epoch_state.clone(),
+                ));
+            }
+        }
+
         ensure!(
             self.proposer_election.is_valid_proposal(&proposal),
             "[RoundManager] Proposer {} for block {} is not a valid proposer for this round",
@@ -743,6 +778,13 @@ impl RoundManager {
                 vote,
                 next_round
             );
+        } else {
+            ensure!(
+                vote.two_chain_timeout().is_some() == self.two_chain(),
+                "[RoundManager] Received {}, but its timeout doesn't follow the commit rule of epoch {}",
+                vote,
+                self.epoch_state.epoch
+            );
         }
         let block_id = vote.vote_data().proposed().id();
         // Check if the block already had a QC
@@ -765,10 +807,41 @@ impl RoundManager {
             VoteReceptionResult::New2ChainTimeoutCertificate(tc) => {
                 self.new_2chain_tc_aggregated(tc).await
             }
+            VoteReceptionResult::EquivocateVote(previous_vote) => {
+                self.record_equivocation(EquivocationEvidence::new_from_votes(
+                    *previous_vote,
+                    vote.clone(),
+                    self.epoch_state.clone(),
+                ));
+                Ok(())
+            }
             _ => Ok(()),
         }
     }
 
+    /// Persist the evidence of an equivocating validator and expose it through the metrics and
+    /// the debug interface events. Failing to persist it doesn't stop the round processing.
+    fn record_equivocation(&self, evidence: EquivocationEvidence) {
+        counters::EQUIVOCATION_EVIDENCE_COUNT
+            .with_label_values(&[evidence.kind()])
+            .inc();
+        let mut log = self.new_log(LogEvent::Equivocation);
+        if let Some(author) = evidence.author() {
+            log = log.remote_peer(author);
+        }
+        error!(log, "{}", evidence);
+        event!("equivocation_evidence",
+            "author": evidence.author(),
+            "epoch": evidence.epoch(),
+            "round": evidence.round(),
+            "kind": evidence.kind(),
+            "evidence": evidence,
+        );
+        if let Err(e) = self.storage.save_equivocation_evidence(&evidence) {
+            error!(error = ?e, "[RoundManager] Fail to persist equivocation evidence");
+        }
+    }
+
     async fn new_qc_aggregated(
         &mut self,
         qc: Arc<QuorumCert>,
@@ -857,7 +930,10 @@ impl RoundManager {
     pub async fn start(&mut self, last_vote_sent: Option<Vote>) {
         let new_round_event = self
             .round_state
-            .process_certificates(self.block_store.sync_info())
+            .process_certificates(
+                self.block_store.sync_info(),
+                self.proposer_election.as_ref(),
+            )
             .expect("Can not jump start a round_state from existing certificates.");
         if let Some(vote) = last_vote_sent {
             self.round_state.record_vote(vote);
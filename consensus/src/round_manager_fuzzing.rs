@@ -91,7 +91,7 @@ fn create_round_state() -> RoundState {
     let time_interval = Box::new(ExponentialTimeInterval::fixed(base_timeout));
     let (round_timeout_sender, _) = channel::new_test(1_024);
     let time_service = Arc::new(SimulatedTimeService::new());
-    RoundState::new(time_interval, time_service, round_timeout_sender)
+    RoundState::new(time_interval, time_service, round_timeout_sender, false)
 }
 
 // Creates an RoundManager for fuzzing
@@ -13,7 +13,7 @@ use crate::{
     network::{IncomingBlockRetrievalRequest, NetworkSender},
     network_interface::{ConsensusMsg, ConsensusNetworkEvents, ConsensusNetworkSender},
     network_tests::{NetworkPlayground, TwinId},
-    persistent_liveness_storage::RecoveryData,
+    persistent_liveness_storage::{PersistentLivenessStorage, RecoveryData},
     round_manager::RoundManager,
     test_utils::{
         consensus_runtime, timed_block_on, MockStateComputer, MockStorage, MockTransactionManager,
@@ -32,6 +32,7 @@ use consensus_types::{
     proposal_msg::ProposalMsg,
     sync_info::SyncInfo,
     timeout::Timeout,
+    timeout_2chain::{TwoChainTimeout, TwoChainTimeoutCertificate},
     timeout_certificate::TimeoutCertificate,
     vote_msg::VoteMsg,
 };
@@ -42,7 +43,7 @@ use diem_secure_storage::Storage;
 use diem_types::{
     epoch_state::EpochState,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
-    on_chain_config::OnChainConsensusConfig,
+    on_chain_config::{ConsensusConfigV1, OnChainConsensusConfig},
     validator_signer::ValidatorSigner,
     validator_verifier::random_validator_verifier,
     waypoint::Waypoint,
@@ -78,14 +79,15 @@ pub struct NodeSetup {
     commit_cb_receiver: mpsc::UnboundedReceiver<LedgerInfoWithSignatures>,
     _state_sync_receiver: mpsc::UnboundedReceiver<Payload>,
     id: usize,
+    onchain_config: OnChainConsensusConfig,
 }
 
 impl NodeSetup {
-    fn create_round_state(time_service: Arc<dyn TimeService>) -> RoundState {
+    fn create_round_state(time_service: Arc<dyn TimeService>, two_chain: bool) -> RoundState {
         let base_timeout = Duration::new(60, 0);
         let time_interval = Box::new(ExponentialTimeInterval::fixed(base_timeout));
         let (round_timeout_sender, _) = channel::new_test(1_024);
-        RoundState::new(time_interval, time_service, round_timeout_sender)
+        RoundState::new(time_interval, time_service, round_timeout_sender, two_chain)
     }
 
     fn create_proposer_election(author: Author) -> Box<dyn ProposerElection + Send + Sync> {
@@ -96,6 +98,20 @@ impl NodeSetup {
         playground: &mut NetworkPlayground,
         executor: Handle,
         num_nodes: usize,
+    ) -> Vec<Self> {
+        Self::create_nodes_with_onchain_config(
+            playground,
+            executor,
+            num_nodes,
+            OnChainConsensusConfig::default(),
+        )
+    }
+
+    fn create_nodes_with_onchain_config(
+        playground: &mut NetworkPlayground,
+        executor: Handle,
+        num_nodes: usize,
+        onchain_config: OnChainConsensusConfig,
     ) -> Vec<Self> {
         let (signers, validators) = random_validator_verifier(num_nodes, None, false);
         let proposer_author = signers[0].author();
@@ -137,6 +153,7 @@ impl NodeSetup {
                 initial_data,
                 safety_rules_manager,
                 id,
+                onchain_config.clone(),
             ));
         }
         nodes
@@ -151,6 +168,7 @@ impl NodeSetup {
         initial_data: RecoveryData,
         safety_rules_manager: SafetyRulesManager,
         id: usize,
+        onchain_config: OnChainConsensusConfig,
     ) -> Self {
         let epoch_state = EpochState {
             epoch: 1,
@@ -206,7 +224,7 @@ impl NodeSetup {
             1,
         );
 
-        let round_state = Self::create_round_state(time_service);
+        let round_state = Self::create_round_state(time_service, onchain_config.two_chain());
         let proposer_election = Self::create_proposer_election(proposer_author);
         let mut safety_rules =
             MetricsSafetyRules::new(safety_rules_manager.client(), storage.clone());
@@ -222,7 +240,7 @@ impl NodeSetup {
             network,
             storage.clone(),
             false,
-            OnChainConsensusConfig::default(),
+            onchain_config.clone(),
         );
         block_on(round_manager.start(last_vote_sent));
         Self {
@@ -236,6 +254,7 @@ impl NodeSetup {
             commit_cb_receiver,
             _state_sync_receiver,
             id,
+            onchain_config,
         }
     }
 
@@ -253,6 +272,7 @@ impl NodeSetup {
             recover_data,
             self.safety_rules_manager,
             self.id,
+            self.onchain_config,
         )
     }
 
@@ -390,6 +410,47 @@ fn no_vote_on_old_proposal() {
     });
 }
 
+#[test]
+/// Two different proposals from the same proposer in the same round are recorded as evidence.
+fn equivocating_proposal_recorded() {
+    let mut runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.handle().clone());
+    let mut nodes = NodeSetup::create_nodes(&mut playground, runtime.handle().clone(), 1);
+    let node = &mut nodes[0];
+    let genesis_qc = certificate_for_genesis();
+    let first_block = Block::new_proposal(vec![], 1, 1, genesis_qc.clone(), &node.signer);
+    let second_block = Block::new_proposal(vec![], 1, 2, genesis_qc, &node.signer);
+    timed_block_on(&mut runtime, async {
+        // clear the message queue
+        node.next_proposal().await;
+
+        node.round_manager
+            .process_proposal(first_block.clone())
+            .await
+            .unwrap();
+        // processing the same proposal twice isn't an equivocation
+        node.round_manager
+            .process_proposal(first_block)
+            .await
+            .unwrap_err();
+        assert!(node
+            .storage
+            .retrieve_equivocation_evidence()
+            .unwrap()
+            .is_empty());
+
+        node.round_manager
+            .process_proposal(second_block)
+            .await
+            .unwrap_err();
+        let evidence = node.storage.retrieve_equivocation_evidence().unwrap();
+        assert_eq!(evidence.len(), 1);
+        assert_eq!(evidence[0].kind(), "proposal");
+        assert_eq!(evidence[0].author(), Some(node.signer.author()));
+        evidence[0].verify().unwrap();
+    });
+}
+
 #[test]
 /// We don't vote for proposals that 'skips' rounds
 /// After that when we then receive proposal for correct round, we vote for it
@@ -550,6 +611,156 @@ fn new_round_on_timeout_certificate() {
     });
 }
 
+fn two_chain_onchain_config() -> OnChainConsensusConfig {
+    OnChainConsensusConfig::V1(ConsensusConfigV1 { two_chain: true })
+}
+
+#[test]
+/// In a 2-chain epoch the proposal carrying a 2-chain timeout certificate for the previous round
+/// is voted on.
+fn new_round_on_2chain_timeout_certificate() {
+    let mut runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.handle().clone());
+    let mut node = NodeSetup::create_nodes_with_onchain_config(
+        &mut playground,
+        runtime.handle().clone(),
+        1,
+        two_chain_onchain_config(),
+    )
+    .pop()
+    .unwrap();
+    let genesis_qc = certificate_for_genesis();
+    let block_skip_round = Block::new_proposal(vec![], 2, 2, genesis_qc.clone(), &node.signer);
+    let timeout = TwoChainTimeout::new(1, 1, genesis_qc.clone());
+    let timeout_signature = timeout.sign(&node.signer);
+
+    let mut tc = TwoChainTimeoutCertificate::new(timeout.clone());
+    tc.add(node.signer.author(), timeout, timeout_signature);
+
+    timed_block_on(&mut runtime, async {
+        // Start round 1 and clear the message queue
+        node.next_proposal().await;
+        let skip_round_proposal = ProposalMsg::new(
+            block_skip_round.clone(),
+            SyncInfo::new(genesis_qc.clone(), genesis_qc.clone(), None, Some(tc)),
+        );
+        node.round_manager
+            .process_proposal_msg(skip_round_proposal)
+            .await
+            .unwrap();
+        // The TC starts round 2 and the node proposes for it
+        assert_eq!(node.next_proposal().await.proposal().round(), 2);
+        let vote_msg = node.next_vote().await;
+        assert_eq!(
+            vote_msg.vote().vote_data().proposed().id(),
+            block_skip_round.id()
+        );
+        assert!(node.block_store.highest_2chain_timeout_cert().is_some());
+    });
+}
+
+#[test]
+/// Timeout certificates of the 3-chain commit rule are rejected in a 2-chain epoch.
+fn no_new_round_on_3chain_timeout_certificate_in_2chain_epoch() {
+    let mut runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.handle().clone());
+    let mut node = NodeSetup::create_nodes_with_onchain_config(
+        &mut playground,
+        runtime.handle().clone(),
+        1,
+        two_chain_onchain_config(),
+    )
+    .pop()
+    .unwrap();
+    let genesis_qc = certificate_for_genesis();
+    let block_skip_round = Block::new_proposal(vec![], 2, 2, genesis_qc.clone(), &node.signer);
+    let timeout = Timeout::new(1, 1);
+    let timeout_signature = timeout.sign(&node.signer);
+
+    let mut tc = TimeoutCertificate::new(timeout);
+    tc.add_signature(node.signer.author(), timeout_signature);
+
+    timed_block_on(&mut runtime, async {
+        let skip_round_proposal = ProposalMsg::new(
+            block_skip_round,
+            SyncInfo::new(genesis_qc.clone(), genesis_qc.clone(), Some(tc), None),
+        );
+        assert!(node
+            .round_manager
+            .process_proposal_msg(skip_round_proposal)
+            .await
+            .is_err());
+        assert!(node.block_store.highest_timeout_cert().is_none());
+    });
+}
+
+#[test]
+/// In a 2-chain epoch the block is committed by the vote on its direct child.
+fn commit_on_two_chain() {
+    let mut runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.handle().clone());
+    let mut nodes = NodeSetup::create_nodes_with_onchain_config(
+        &mut playground,
+        runtime.handle().clone(),
+        1,
+        two_chain_onchain_config(),
+    );
+    let node = &mut nodes[0];
+    timed_block_on(&mut runtime, async {
+        let proposal_msg = node.next_proposal().await;
+        let b1_id = proposal_msg.proposal().id();
+        node.round_manager
+            .process_proposal_msg(proposal_msg)
+            .await
+            .unwrap();
+        let vote_msg = node.next_vote().await;
+        // The vote on round 1 can only commit genesis
+        assert_eq!(vote_msg.vote().ledger_info().commit_info().round(), 0);
+        node.round_manager.process_vote_msg(vote_msg).await.unwrap();
+
+        let proposal_msg = node.next_proposal().await;
+        assert_eq!(proposal_msg.proposal().round(), 2);
+        node.round_manager
+            .process_proposal_msg(proposal_msg)
+            .await
+            .unwrap();
+        let vote_msg = node.next_vote().await;
+        // Round 2 directly follows round 1, hence the vote commits b1
+        assert_eq!(vote_msg.vote().ledger_info().commit_info().id(), b1_id);
+    });
+}
+
+#[test]
+/// In a 2-chain epoch the timeout vote carries a 2-chain timeout with the highest QC.
+fn two_chain_timeout_vote_on_timeout() {
+    let mut runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.handle().clone());
+    let mut nodes = NodeSetup::create_nodes_with_onchain_config(
+        &mut playground,
+        runtime.handle().clone(),
+        1,
+        two_chain_onchain_config(),
+    );
+    let node = &mut nodes[0];
+    timed_block_on(&mut runtime, async {
+        node.next_proposal().await;
+        node.round_manager
+            .process_local_timeout(1)
+            .await
+            .unwrap_err();
+        let vote_msg = node.next_vote().await;
+        let vote = vote_msg.vote();
+        assert!(vote.is_timeout());
+        assert!(vote.timeout_signature().is_none());
+        let (timeout, _) = vote.two_chain_timeout().unwrap();
+        assert_eq!(timeout.round(), 1);
+        assert_eq!(
+            timeout.quorum_cert(),
+            node.block_store.highest_quorum_cert().as_ref()
+        );
+    });
+}
+
 #[test]
 fn response_on_block_retrieval() {
     let mut runtime = consensus_runtime();
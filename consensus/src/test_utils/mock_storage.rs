@@ -9,8 +9,9 @@ use crate::{
 };
 use anyhow::Result;
 use consensus_types::{
-    block::Block, quorum_cert::QuorumCert, timeout_2chain::TwoChainTimeoutCertificate,
-    timeout_certificate::TimeoutCertificate, vote::Vote,
+    block::Block, equivocation_evidence::EquivocationEvidence, quorum_cert::QuorumCert,
+    timeout_2chain::TwoChainTimeoutCertificate, timeout_certificate::TimeoutCertificate,
+    vote::Vote,
 };
 use diem_crypto::HashValue;
 use diem_infallible::Mutex;
@@ -37,6 +38,9 @@ pub struct MockSharedStorage {
     pub highest_timeout_certificate: Mutex<Option<TimeoutCertificate>>,
     pub highest_2chain_timeout_certificate: Mutex<Option<TwoChainTimeoutCertificate>>,
     pub validator_set: ValidatorSet,
+
+    // Accountability state
+    pub equivocation_evidence: Mutex<HashMap<HashValue, EquivocationEvidence>>,
 }
 
 impl MockSharedStorage {
@@ -49,6 +53,7 @@ impl MockSharedStorage {
             highest_timeout_certificate: Mutex::new(None),
             highest_2chain_timeout_certificate: Mutex::new(None),
             validator_set,
+            equivocation_evidence: Mutex::new(HashMap::new()),
         }
     }
 }
@@ -234,6 +239,26 @@ impl PersistentLivenessStorage for MockStorage {
         Ok(())
     }
 
+    fn save_equivocation_evidence(&self, evidence: &EquivocationEvidence) -> Result<()> {
+        self.shared_storage
+            .equivocation_evidence
+            .lock()
+            .insert(evidence.id(), evidence.clone());
+        Ok(())
+    }
+
+    fn retrieve_equivocation_evidence(&self) -> Result<Vec<EquivocationEvidence>> {
+        let mut evidence: Vec<_> = self
+            .shared_storage
+            .equivocation_evidence
+            .lock()
+            .values()
+            .cloned()
+            .collect();
+        evidence.sort_by_key(|e| (e.epoch(), e.round()));
+        Ok(evidence)
+    }
+
     fn retrieve_epoch_change_proof(&self, version: u64) -> Result<EpochChangeProof> {
         let lis = self
             .shared_storage
@@ -313,6 +338,14 @@ impl PersistentLivenessStorage for EmptyStorage {
         Ok(())
     }
 
+    fn save_equivocation_evidence(&self, _: &EquivocationEvidence) -> Result<()> {
+        Ok(())
+    }
+
+    fn retrieve_equivocation_evidence(&self) -> Result<Vec<EquivocationEvidence>> {
+        Ok(vec![])
+    }
+
     fn retrieve_epoch_change_proof(&self, _version: u64) -> Result<EpochChangeProof> {
         unimplemented!()
     }
@@ -5,13 +5,19 @@ use crate::block_storage::{BlockReader, BlockStore};
 use consensus_types::{
     block::{block_test_utils::certificate_for_genesis, Block},
     common::Round,
+    equivocation_evidence::EquivocationEvidence,
     executed_block::ExecutedBlock,
     quorum_cert::QuorumCert,
     sync_info::SyncInfo,
+    vote::Vote,
+    vote_data::VoteData,
 };
 use diem_crypto::HashValue;
 use diem_logger::Level;
-use diem_types::{ledger_info::LedgerInfo, validator_signer::ValidatorSigner};
+use diem_types::{
+    epoch_state::EpochState, ledger_info::LedgerInfo, validator_signer::ValidatorSigner,
+    validator_verifier::random_validator_verifier,
+};
 use std::{future::Future, sync::Arc, time::Duration};
 use tokio::{runtime, time::timeout};
 
@@ -184,6 +190,29 @@ pub fn placeholder_sync_info() -> SyncInfo {
     )
 }
 
+/// Two conflicting votes from the same validator for round 1 of epoch 1.
+pub fn random_equivocation_evidence() -> EquivocationEvidence {
+    let (signers, verifier) = random_validator_verifier(1, None, false);
+    let vote_data = VoteData::new(BlockInfo::random(1), BlockInfo::random(0));
+    let mut votes = (0..2).map(|_| {
+        let ledger_info = LedgerInfo::new(
+            BlockInfo::new(1, 0, HashValue::random(), HashValue::random(), 0, 0, None),
+            HashValue::zero(),
+        );
+        Vote::new(
+            vote_data.clone(),
+            signers[0].author(),
+            ledger_info,
+            &signers[0],
+        )
+    });
+    EquivocationEvidence::new_from_votes(
+        votes.next().unwrap(),
+        votes.next().unwrap(),
+        EpochState { epoch: 1, verifier },
+    )
+}
+
 fn nocapture() -> bool {
     ::std::env::args().any(|arg| arg == "--nocapture")
 }
@@ -9,6 +9,7 @@ use crate::{
 };
 use consensus_types::{block::Block, common::Round};
 use diem_config::config::ConsensusProposerType::{FixedProposer, RotatingProposer, RoundProposer};
+use diem_types::on_chain_config::{ConsensusConfigV1, OnChainConsensusConfig};
 use futures::StreamExt;
 use std::collections::HashMap;
 
@@ -33,6 +34,7 @@ fn basic_start_test() {
         &mut playground,
         RotatingProposer,
         None,
+        OnChainConsensusConfig::default(),
     );
     let genesis = Block::make_genesis_block_from_ledger_info(&nodes[0].storage.get_ledger_info());
     timed_block_on(&mut runtime, async {
@@ -85,6 +87,7 @@ fn drop_config_test() {
         &mut playground,
         FixedProposer,
         None,
+        OnChainConsensusConfig::default(),
     );
 
     // 4 honest nodes
@@ -141,6 +144,7 @@ fn twins_vote_dedup_test() {
         &mut playground,
         RotatingProposer,
         None,
+        OnChainConsensusConfig::default(),
     );
 
     // 4 honest nodes
@@ -208,6 +212,7 @@ fn twins_proposer_test() {
         &mut playground,
         RoundProposer(HashMap::new()),
         Some(round_proposers),
+        OnChainConsensusConfig::default(),
     );
 
     // 4 honest nodes
@@ -272,6 +277,35 @@ fn twins_proposer_test() {
 /// Run the test:
 /// cargo xtest -p consensus twins_commit_test -- --nocapture
 fn twins_commit_test() {
+    run_twins_commit_test(OnChainConsensusConfig::default());
+}
+
+#[test]
+/// This test checks that with the 2-chain commit rule, when n0 and twin0 propose
+/// for a round, only one of the two proposals gets committed
+///
+/// Setup:
+///
+/// Network of 4 nodes (n0, n1, n2, n3), and 1 twin (twin0),
+/// running with the 2-chain commit rule
+///
+/// Test:
+///
+/// Let n0 (and implicitly twin0) be proposers
+/// Pull out enough votes so a commit can be formed
+/// Check that the commit of n0 and twin0 matches
+///
+/// Run the test:
+/// cargo xtest -p consensus twins_2chain_commit_test -- --nocapture
+fn twins_2chain_commit_test() {
+    run_twins_commit_test(OnChainConsensusConfig::V1(ConsensusConfigV1 {
+        two_chain: true,
+    }));
+}
+
+/// Starts n0 (and implicitly twin0) as the proposers of the first rounds and checks that n0 and
+/// twin0 commit the same block, with the commit rule selected by `consensus_config`.
+fn run_twins_commit_test(consensus_config: OnChainConsensusConfig) {
     let mut runtime = consensus_runtime();
     let mut playground = NetworkPlayground::new(runtime.handle().clone());
     let num_nodes = 4;
@@ -291,6 +325,7 @@ fn twins_commit_test() {
         &mut playground,
         RoundProposer(HashMap::new()),
         Some(round_proposers),
+        consensus_config,
     );
     runtime.spawn(playground.start());
 
@@ -23,7 +23,7 @@ use diem_config::{
 use diem_mempool::mocks::MockSharedMempool;
 use diem_types::{
     ledger_info::LedgerInfoWithSignatures,
-    on_chain_config::{OnChainConfig, OnChainConfigPayload, ValidatorSet},
+    on_chain_config::{OnChainConfig, OnChainConfigPayload, OnChainConsensusConfig, ValidatorSet},
     validator_info::ValidatorInfo,
     waypoint::Waypoint,
 };
@@ -61,6 +61,7 @@ impl SMRNode {
         config: NodeConfig,
         storage: Arc<MockStorage>,
         twin_id: TwinId,
+        onchain_consensus_config: &OnChainConsensusConfig,
     ) -> Self {
         let (network_reqs_tx, network_reqs_rx) = diem_channel::new(QueueStyle::FIFO, 8, None);
         let (connection_reqs_tx, _) = diem_channel::new(QueueStyle::FIFO, 8, None);
@@ -97,6 +98,11 @@ impl SMRNode {
             ValidatorSet::CONFIG_ID,
             bcs::to_bytes(storage.get_validator_set()).unwrap(),
         );
+        // The consensus config is stored on-chain as bcs serialized bytes
+        configs.insert(
+            OnChainConsensusConfig::CONFIG_ID,
+            bcs::to_bytes(&bcs::to_bytes(onchain_consensus_config).unwrap()).unwrap(),
+        );
         let payload = OnChainConfigPayload::new(1, Arc::new(configs));
         reconfig_sender
             .push(
@@ -156,6 +162,7 @@ impl SMRNode {
         playground: &mut NetworkPlayground,
         proposer_type: ConsensusProposerType,
         round_proposers_idx: Option<HashMap<Round, usize>>,
+        onchain_consensus_config: OnChainConsensusConfig,
     ) -> Vec<Self> {
         assert!(num_nodes >= num_twins);
         let ValidatorSwarm {
@@ -234,7 +241,13 @@ impl SMRNode {
 
             let twin_id = TwinId { id: smr_id, author };
 
-            smr_nodes.push(Self::start(playground, config, storage, twin_id));
+            smr_nodes.push(Self::start(
+                playground,
+                config,
+                storage,
+                twin_id,
+                &onchain_consensus_config,
+            ));
         }
         smr_nodes
     }
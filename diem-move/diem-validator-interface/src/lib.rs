@@ -8,7 +8,7 @@ pub use crate::storage_interface::DBDebuggerInterface;
 pub use json_rpc_interface::JsonRpcDebuggerInterface;
 
 use anyhow::{anyhow, Result};
-use diem_state_view::StateView;
+use diem_state_view::{StateView, StateViewId};
 use diem_types::{
     access_path::AccessPath,
     account_address::AccountAddress,
@@ -112,6 +112,12 @@ impl<'a> DebuggerStateView<'a> {
 }
 
 impl<'a> StateView for DebuggerStateView<'a> {
+    fn id(&self) -> StateViewId {
+        StateViewId::ChunkExecution {
+            first_version: self.version,
+        }
+    }
+
     fn get(&self, access_path: &AccessPath) -> Result<Option<Vec<u8>>> {
         if self.version == 0 {
             return Ok(None);
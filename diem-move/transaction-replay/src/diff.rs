@@ -0,0 +1,255 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Replaying transactions under two different VM/framework configurations and diffing their
+//! outputs, e.g. to find every transaction whose behavior changes with a candidate framework
+//! release before it gets deployed.
+
+use anyhow::{format_err, Result};
+use diem_state_view::{StateView, StateViewId};
+use diem_types::{
+    access_path::{self, AccessPath},
+    contract_event::ContractEvent,
+    transaction::{Transaction, TransactionOutput, TransactionStatus, Version},
+    write_set::WriteOp,
+};
+use diem_vm::{DiemVM, VMExecutor};
+use move_binary_format::file_format::CompiledModule;
+use move_core_types::language_storage::ModuleId;
+use std::{collections::BTreeMap, fmt, path::PathBuf, str::FromStr};
+
+/// Where the Diem Framework modules used to execute transactions come from.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FrameworkSource {
+    /// The modules stored on chain at the version being replayed.
+    OnChain,
+    /// A release bundle from `diem-framework-releases`, e.g. `current` or `release-1.4.0-rc0`.
+    Release(String),
+    /// A directory of compiled (`.mv`) modules.
+    Directory(PathBuf),
+}
+
+impl FrameworkSource {
+    /// Loads the modules overriding the on-chain framework, keyed by module id.
+    pub fn load_modules(&self) -> Result<BTreeMap<ModuleId, Vec<u8>>> {
+        let blobs = match self {
+            FrameworkSource::OnChain => return Ok(BTreeMap::new()),
+            FrameworkSource::Release(name) => {
+                diem_framework_releases::load_modules_from_release(name)?
+            }
+            FrameworkSource::Directory(path) => {
+                diem_framework_releases::load_modules_from_paths(&[path.clone()])
+            }
+        };
+        blobs
+            .into_iter()
+            .map(|blob| {
+                let module = CompiledModule::deserialize(&blob)
+                    .map_err(|err| format_err!("Invalid module in {}: {:?}", self, err))?;
+                Ok((module.self_id(), blob))
+            })
+            .collect()
+    }
+}
+
+/// Parses `on-chain`, `release:<name>` or a path to a directory of compiled modules.
+impl FromStr for FrameworkSource {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(if s == "on-chain" {
+            FrameworkSource::OnChain
+        } else if let Some(name) = s.strip_prefix("release:") {
+            FrameworkSource::Release(name.to_owned())
+        } else {
+            FrameworkSource::Directory(PathBuf::from(s))
+        })
+    }
+}
+
+impl fmt::Display for FrameworkSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FrameworkSource::OnChain => write!(f, "on-chain"),
+            FrameworkSource::Release(name) => write!(f, "release:{}", name),
+            FrameworkSource::Directory(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// A VM/framework configuration to replay transactions with.
+#[derive(Clone, Debug, Default)]
+pub struct ReplayConfig {
+    /// Modules to use instead of the ones stored on chain. Empty to use the on-chain framework.
+    framework_overrides: BTreeMap<ModuleId, Vec<u8>>,
+    /// Whether to execute with the parallel executor instead of the sequential one.
+    parallel: bool,
+}
+
+impl ReplayConfig {
+    pub fn new(framework: &FrameworkSource, parallel: bool) -> Result<Self> {
+        Ok(Self {
+            framework_overrides: framework.load_modules()?,
+            parallel,
+        })
+    }
+
+    pub fn with_framework_overrides(mut self, modules: BTreeMap<ModuleId, Vec<u8>>) -> Self {
+        self.framework_overrides = modules;
+        self
+    }
+
+    pub fn execute_block(
+        &self,
+        txns: Vec<Transaction>,
+        state_view: &impl StateView,
+    ) -> Result<Vec<TransactionOutput>> {
+        let state_view = FrameworkOverrideView {
+            base: state_view,
+            modules: &self.framework_overrides,
+        };
+        if self.parallel {
+            DiemVM::execute_block_parallel(txns, &state_view)
+        } else {
+            DiemVM::execute_block(txns, &state_view)
+        }
+        .map_err(|err| format_err!("Unexpected VM Error: {:?}", err))
+    }
+}
+
+/// A state view serving the overridden modules instead of the ones stored in `base`.
+struct FrameworkOverrideView<'a, S> {
+    base: &'a S,
+    modules: &'a BTreeMap<ModuleId, Vec<u8>>,
+}
+
+impl<'a, S: StateView> StateView for FrameworkOverrideView<'a, S> {
+    fn id(&self) -> StateViewId {
+        self.base.id()
+    }
+
+    fn get(&self, access_path: &AccessPath) -> Result<Option<Vec<u8>>> {
+        if let access_path::Path::Code(module_id) = access_path.get_path() {
+            if let Some(blob) = self.modules.get(&module_id) {
+                return Ok(Some(blob.clone()));
+            }
+        }
+        self.base.get(access_path)
+    }
+
+    fn is_genesis(&self) -> bool {
+        self.base.is_genesis()
+    }
+}
+
+/// A write set entry written differently by the two executions. A missing operation means the
+/// access path was not written at all by that execution.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WriteSetEntryDiff {
+    pub access_path: AccessPath,
+    pub base: Option<WriteOp>,
+    pub candidate: Option<WriteOp>,
+}
+
+/// An event emitted differently by the two executions, matched by its index in the output. A
+/// missing event means that execution emitted fewer events.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EventDiff {
+    pub index: usize,
+    pub base: Option<ContractEvent>,
+    pub candidate: Option<ContractEvent>,
+}
+
+/// How the outputs of a transaction differ between the base and the candidate configurations.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TransactionDiff {
+    pub version: Version,
+    /// The (base, candidate) statuses, if they differ.
+    pub status: Option<(TransactionStatus, TransactionStatus)>,
+    /// The (base, candidate) gas used, if it differs.
+    pub gas_used: Option<(u64, u64)>,
+    pub write_set: Vec<WriteSetEntryDiff>,
+    pub events: Vec<EventDiff>,
+}
+
+impl TransactionDiff {
+    /// Diffs the outputs of the transaction at `version`, or returns None if they are identical.
+    pub fn new(
+        version: Version,
+        base: &TransactionOutput,
+        candidate: &TransactionOutput,
+    ) -> Option<Self> {
+        let status = if base.status() != candidate.status() {
+            Some((base.status().clone(), candidate.status().clone()))
+        } else {
+            None
+        };
+        let gas_used = if base.gas_used() != candidate.gas_used() {
+            Some((base.gas_used(), candidate.gas_used()))
+        } else {
+            None
+        };
+
+        let mut write_set: BTreeMap<&AccessPath, (Option<&WriteOp>, Option<&WriteOp>)> =
+            BTreeMap::new();
+        for (access_path, op) in base.write_set() {
+            write_set.entry(access_path).or_default().0 = Some(op);
+        }
+        for (access_path, op) in candidate.write_set() {
+            write_set.entry(access_path).or_default().1 = Some(op);
+        }
+        let write_set: Vec<_> = write_set
+            .into_iter()
+            .filter(|(_, (base, candidate))| base != candidate)
+            .map(|(access_path, (base, candidate))| WriteSetEntryDiff {
+                access_path: access_path.clone(),
+                base: base.cloned(),
+                candidate: candidate.cloned(),
+            })
+            .collect();
+
+        let num_events = std::cmp::max(base.events().len(), candidate.events().len());
+        let events: Vec<_> = (0..num_events)
+            .filter_map(|index| {
+                let base = base.events().get(index);
+                let candidate = candidate.events().get(index);
+                if base == candidate {
+                    return None;
+                }
+                Some(EventDiff {
+                    index,
+                    base: base.cloned(),
+                    candidate: candidate.cloned(),
+                })
+            })
+            .collect();
+
+        if status.is_none() && gas_used.is_none() && write_set.is_empty() && events.is_empty() {
+            None
+        } else {
+            Some(Self {
+                version,
+                status,
+                gas_used,
+                write_set,
+                events,
+            })
+        }
+    }
+}
+
+/// Diffs the outputs of a range of transactions starting at version `begin`. Both slices must
+/// have the same length.
+pub fn diff_outputs(
+    begin: Version,
+    base: &[TransactionOutput],
+    candidate: &[TransactionOutput],
+) -> Vec<TransactionDiff> {
+    base.iter()
+        .zip(candidate.iter())
+        .enumerate()
+        .filter_map(|(offset, (base, candidate))| {
+            TransactionDiff::new(begin + offset as u64, base, candidate)
+        })
+        .collect()
+}
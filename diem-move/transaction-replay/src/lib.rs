@@ -1,11 +1,12 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::diff::{diff_outputs, ReplayConfig, TransactionDiff};
 use anyhow::{anyhow, bail, format_err, Result};
 use diem_resource_viewer::{AnnotatedAccountStateBlob, AnnotatedMoveStruct, DiemValueAnnotator};
 use diem_state_view::StateView;
 use diem_types::{
-    access_path,
+    access_path::{self, AccessPath},
     account_address::AccountAddress,
     account_config::diem_root_address,
     account_state::AccountState,
@@ -21,15 +22,21 @@ use diem_vm::{
     convert_changeset_and_events, data_cache::RemoteStorage, logging::AdapterLogSchema, DiemVM,
     VMExecutor,
 };
+use difference::Changeset;
 use move_binary_format::{errors::VMResult, file_format::CompiledModule};
 use move_cli::sandbox::utils::on_disk_state_view::OnDiskStateView;
 use move_core_types::{effects::ChangeSet as MoveChanges, language_storage::TypeTag};
 use move_lang::{compiled_unit::AnnotatedCompiledUnit, Compiler, Flags};
 use move_vm_runtime::{move_vm::MoveVM, session::Session};
 use move_vm_test_utils::DeltaStorage;
-use move_vm_types::gas_schedule::GasStatus;
+use move_vm_types::{
+    gas_profiler::{self, GasProfile},
+    gas_schedule::GasStatus,
+};
 use std::path::{Path, PathBuf};
 
+pub mod diff;
+
 #[cfg(test)]
 mod unit_tests;
 
@@ -68,6 +75,22 @@ impl DiemDebugger {
             .map_err(|err| format_err!("Unexpected VM Error: {:?}", err))
     }
 
+    /// Replays the transaction at `version` on the state it was committed on, attributing the
+    /// gas it consumes to call stacks.
+    pub fn profile_gas_at_version(
+        &self,
+        version: Version,
+    ) -> Result<(TransactionOutput, GasProfile)> {
+        let txns = self.debugger.get_committed_transactions(version, 1)?;
+        gas_profiler::start_gas_profiling();
+        let outputs = self.execute_transactions_at_version(version, txns);
+        let profile = gas_profiler::finish_gas_profiling().unwrap_or_default();
+        let output = outputs?
+            .pop()
+            .ok_or_else(|| format_err!("No output for transaction {}", version))?;
+        Ok((output, profile))
+    }
+
     pub fn execute_past_transactions(
         &self,
         mut begin: Version,
@@ -119,6 +142,112 @@ impl DiemDebugger {
         Ok(ret)
     }
 
+    /// Replays `limit` transactions starting at version `begin` with both the `base` and the
+    /// `candidate` configurations, and returns the transactions whose outputs differ.
+    pub fn diff_past_transactions(
+        &self,
+        mut begin: Version,
+        mut limit: u64,
+        base: &ReplayConfig,
+        candidate: &ReplayConfig,
+    ) -> Result<Vec<TransactionDiff>> {
+        let mut txns = self.debugger.get_committed_transactions(begin, limit)?;
+        let mut ret = vec![];
+        while limit != 0 {
+            println!(
+                "Starting epoch diff at {:?}, {:?} transactions remaining",
+                begin, limit
+            );
+            let state_view = DebuggerStateView::new(&*self.debugger, begin);
+            let base_outputs = base.execute_block(txns.clone(), &state_view)?;
+            let candidate_outputs = candidate.execute_block(txns.clone(), &state_view)?;
+
+            // Both executions stop at the first reconfiguration either of them hits, as the
+            // transactions after it need to be executed with the new epoch's state.
+            let epoch_len = base_outputs
+                .iter()
+                .zip(candidate_outputs.iter())
+                .position(|(base, candidate)| {
+                    is_reconfiguration(base) || is_reconfiguration(candidate)
+                })
+                .map_or(txns.len(), |index| index + 1);
+            ret.append(&mut diff_outputs(
+                begin,
+                &base_outputs[..epoch_len],
+                &candidate_outputs[..epoch_len],
+            ));
+
+            begin += epoch_len as u64;
+            limit -= epoch_len as u64;
+            txns = txns.split_off(epoch_len);
+        }
+        Ok(ret)
+    }
+
+    /// Renders `diff` for humans, annotating the resources and events it contains with the type
+    /// layouts at its version.
+    pub fn pretty_print_transaction_diff(&self, diff: &TransactionDiff) -> Result<String> {
+        let state_view = DebuggerStateView::new(&*self.debugger, diff.version);
+        let remote_storage = RemoteStorage::new(&state_view);
+        let annotator = DiemValueAnnotator::new(&remote_storage);
+
+        let annotate_op = |access_path: &AccessPath, op: &Option<WriteOp>| -> String {
+            match op {
+                None => "<not written>".to_owned(),
+                Some(WriteOp::Deletion) => "<deleted>".to_owned(),
+                Some(WriteOp::Value(bytes)) => annotator
+                    .view_access_path(access_path.clone(), bytes)
+                    .map(|value| value.to_string())
+                    .unwrap_or_else(|_| hex::encode(bytes)),
+            }
+        };
+        let annotate_event = |event: &Option<ContractEvent>| -> String {
+            match event {
+                None => "<not emitted>".to_owned(),
+                Some(event) => match annotator.view_contract_event(event) {
+                    Ok(value) => format!(
+                        "key: {}, sequence number: {}, data: {}",
+                        event.key(),
+                        event.sequence_number(),
+                        value
+                    ),
+                    Err(_) => format!("{:?}", event),
+                },
+            }
+        };
+
+        let mut out = format!("Transaction Version: {}\n", diff.version);
+        if let Some((base, candidate)) = &diff.status {
+            out += &format!("Status: {:?} => {:?}\n", base, candidate);
+        }
+        if let Some((base, candidate)) = &diff.gas_used {
+            out += &format!("Gas used: {} => {}\n", base, candidate);
+        }
+        for entry in &diff.write_set {
+            out += &format!(
+                "Write set entry {}:\n{}\n",
+                entry.access_path,
+                Changeset::new(
+                    &annotate_op(&entry.access_path, &entry.base),
+                    &annotate_op(&entry.access_path, &entry.candidate),
+                    "\n"
+                )
+            );
+        }
+        for event in &diff.events {
+            out += &format!(
+                "Event {}:\n{}\n",
+                event.index,
+                Changeset::new(
+                    &annotate_event(&event.base),
+                    &annotate_event(&event.candidate),
+                    "\n"
+                )
+            );
+        }
+        Ok(out)
+    }
+
     pub fn execute_writeset_at_version(
         &self,
         version: Version,
@@ -2,7 +2,10 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use anyhow::{bail, Result};
-use diem_transaction_replay::DiemDebugger;
+use diem_transaction_replay::{
+    diff::{FrameworkSource, ReplayConfig},
+    DiemDebugger,
+};
 use diem_types::{
     account_address::AccountAddress,
     event::EventKey,
@@ -10,7 +13,12 @@ use diem_types::{
 };
 use difference::Changeset;
 use move_core_types::effects::ChangeSet;
-use std::{fs, path::PathBuf};
+use move_vm_runtime::execution_trace::{self, TraceFormat};
+use std::{
+    fs::{self, File},
+    io::BufWriter,
+    path::PathBuf,
+};
 use structopt::StructOpt;
 
 #[derive(Debug, StructOpt)]
@@ -24,6 +32,14 @@ struct Opt {
     /// If true, persist the effects of replaying transactions via `cmd` to disk in a format understood by the Move CLI
     #[structopt(short = "s", global = true)]
     save_write_sets: bool,
+    /// If set, write a structured trace of every Move instruction executed by `cmd` to this file.
+    /// Transactions executed in parallel are not traced.
+    #[structopt(long, global = true, parse(from_os_str))]
+    trace: Option<PathBuf>,
+    /// The format of the trace: one JSON event per line (`json`), or length-prefixed BCS records
+    /// (`bcs`).
+    #[structopt(long, global = true, default_value = "json")]
+    trace_format: TraceFormat,
     #[structopt(subcommand)] // Note that we mark a field as a subcommand
     cmd: Command,
 }
@@ -33,6 +49,37 @@ enum Command {
     /// Replay transactions starting from version `start` to `start + limit`.
     #[structopt(name = "replay-transactions")]
     ReplayTransactions { start: Version, limit: u64 },
+    /// Replay transactions from version `start` to `start + limit` with two VM/framework
+    /// configurations and print every transaction whose output differs.
+    #[structopt(name = "diff-transactions")]
+    DiffTransactions {
+        start: Version,
+        limit: u64,
+        /// Framework of the base configuration: `on-chain`, `release:<name>` or a directory of
+        /// compiled modules.
+        #[structopt(long, default_value = "on-chain")]
+        base: FrameworkSource,
+        /// Framework of the candidate configuration: `on-chain`, `release:<name>` or a directory
+        /// of compiled modules.
+        #[structopt(long)]
+        candidate: FrameworkSource,
+        /// Execute the base configuration with the parallel executor.
+        #[structopt(long)]
+        base_parallel: bool,
+        /// Execute the candidate configuration with the parallel executor.
+        #[structopt(long)]
+        candidate_parallel: bool,
+    },
+    /// Profile the gas consumed by the transactions from version `start` to `start + limit`. The
+    /// call stacks of each transaction are written to `<output-dir>/<version>.folded`, in the
+    /// folded stacks format of flamegraph tools.
+    #[structopt(name = "profile-gas")]
+    ProfileGas {
+        start: Version,
+        limit: u64,
+        #[structopt(long, parse(from_os_str), default_value = "gas-profiles")]
+        output_dir: PathBuf,
+    },
     /// Replay the last `txns` committed transactions.
     #[structopt(name = "replay-recent-transactions")]
     ReplayRecentTransactions { txns: u64 },
@@ -100,6 +147,13 @@ fn main() -> Result<()> {
 
     println!("Connection Succeeded");
 
+    if let Some(trace) = &opt.trace {
+        execution_trace::set_trace_sink(
+            opt.trace_format
+                .writer(BufWriter::new(File::create(trace)?)),
+        );
+    }
+
     match opt.cmd {
         Command::ReplayTransactions { start, limit } => {
             println!(
@@ -107,6 +161,51 @@ fn main() -> Result<()> {
                 debugger.execute_past_transactions(start, limit, opt.save_write_sets)
             );
         }
+        Command::DiffTransactions {
+            start,
+            limit,
+            base,
+            candidate,
+            base_parallel,
+            candidate_parallel,
+        } => {
+            let diffs = debugger.diff_past_transactions(
+                start,
+                limit,
+                &ReplayConfig::new(&base, base_parallel)?,
+                &ReplayConfig::new(&candidate, candidate_parallel)?,
+            )?;
+            for diff in &diffs {
+                println!("{}", debugger.pretty_print_transaction_diff(diff)?);
+            }
+            println!(
+                "{} out of {} transactions differ between {} and {}",
+                diffs.len(),
+                limit,
+                base,
+                candidate
+            );
+        }
+        Command::ProfileGas {
+            start,
+            limit,
+            output_dir,
+        } => {
+            fs::create_dir_all(&output_dir)?;
+            for version in start..start + limit {
+                let (output, profile) = debugger.profile_gas_at_version(version)?;
+                let path = output_dir.join(format!("{}.folded", version));
+                fs::write(&path, profile.to_folded_stacks())?;
+                println!(
+                    "Transaction Version: {}, status: {:?}, gas used: {}, profile: {}",
+                    version,
+                    output.status(),
+                    output.gas_used(),
+                    path.display()
+                );
+                print!("{}", profile.summary_table());
+            }
+        }
         Command::ReplayRecentTransactions { txns } => {
             let latest_version = debugger
                 .get_latest_version()
@@ -233,5 +332,9 @@ fn main() -> Result<()> {
             )
         ),
     }
+
+    if let Some(mut sink) = execution_trace::take_trace_sink() {
+        sink.finish()?;
+    }
     Ok(())
 }
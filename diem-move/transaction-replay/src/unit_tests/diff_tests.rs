@@ -0,0 +1,174 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::diff::{diff_outputs, EventDiff, FrameworkSource, TransactionDiff, WriteSetEntryDiff};
+use diem_types::{
+    access_path::AccessPath,
+    account_address::AccountAddress,
+    account_config::AccountResource,
+    contract_event::ContractEvent,
+    event::EventKey,
+    transaction::{TransactionOutput, TransactionStatus},
+    vm_status::{KeptVMStatus, StatusCode},
+    write_set::{WriteOp, WriteSetMut},
+};
+use move_core_types::{language_storage::TypeTag, move_resource::MoveStructType};
+use std::path::PathBuf;
+
+fn output(
+    writes: Vec<(AccessPath, WriteOp)>,
+    events: Vec<ContractEvent>,
+    gas_used: u64,
+) -> TransactionOutput {
+    TransactionOutput::new(
+        WriteSetMut::new(writes).freeze().unwrap(),
+        events,
+        gas_used,
+        TransactionStatus::Keep(KeptVMStatus::Executed),
+    )
+}
+
+fn access_path(address: AccountAddress) -> AccessPath {
+    AccessPath::new(
+        address,
+        AccessPath::resource_access_vec(AccountResource::struct_tag()),
+    )
+}
+
+fn event(seq: u64) -> ContractEvent {
+    ContractEvent::new(
+        EventKey::new_from_address(&AccountAddress::random(), 0),
+        seq,
+        TypeTag::U64,
+        vec![],
+    )
+}
+
+#[test]
+fn test_identical_outputs() {
+    let outputs = vec![
+        output(vec![], vec![], 1),
+        output(
+            vec![(access_path(AccountAddress::random()), WriteOp::Deletion)],
+            vec![event(0)],
+            2,
+        ),
+    ];
+    assert!(diff_outputs(10, &outputs, &outputs).is_empty());
+}
+
+#[test]
+fn test_diff_outputs() {
+    let (a, b, c) = (
+        access_path(AccountAddress::random()),
+        access_path(AccountAddress::random()),
+        access_path(AccountAddress::random()),
+    );
+    let (e0, e1) = (event(0), event(1));
+    let base = vec![
+        output(vec![], vec![], 1),
+        output(
+            vec![
+                (a.clone(), WriteOp::Value(vec![1])),
+                (b.clone(), WriteOp::Deletion),
+            ],
+            vec![e0.clone(), e1.clone()],
+            2,
+        ),
+    ];
+    let candidate = vec![
+        output(vec![], vec![], 1),
+        output(
+            vec![
+                (a.clone(), WriteOp::Value(vec![2])),
+                (c.clone(), WriteOp::Value(vec![3])),
+            ],
+            vec![e0],
+            5,
+        ),
+    ];
+
+    let mut expected_write_set = vec![
+        WriteSetEntryDiff {
+            access_path: a,
+            base: Some(WriteOp::Value(vec![1])),
+            candidate: Some(WriteOp::Value(vec![2])),
+        },
+        WriteSetEntryDiff {
+            access_path: b,
+            base: Some(WriteOp::Deletion),
+            candidate: None,
+        },
+        WriteSetEntryDiff {
+            access_path: c,
+            base: None,
+            candidate: Some(WriteOp::Value(vec![3])),
+        },
+    ];
+    expected_write_set.sort_by(|x, y| x.access_path.cmp(&y.access_path));
+    assert_eq!(
+        diff_outputs(10, &base, &candidate),
+        vec![TransactionDiff {
+            version: 11,
+            status: None,
+            gas_used: Some((2, 5)),
+            write_set: expected_write_set,
+            events: vec![EventDiff {
+                index: 1,
+                base: Some(e1),
+                candidate: None,
+            }],
+        }]
+    );
+}
+
+#[test]
+fn test_diff_status() {
+    let base = output(vec![], vec![], 1);
+    let candidate = TransactionOutput::new(
+        base.write_set().clone(),
+        vec![],
+        1,
+        TransactionStatus::Discard(StatusCode::SEQUENCE_NUMBER_TOO_OLD),
+    );
+    assert_eq!(
+        TransactionDiff::new(3, &base, &candidate),
+        Some(TransactionDiff {
+            version: 3,
+            status: Some((base.status().clone(), candidate.status().clone())),
+            gas_used: None,
+            write_set: vec![],
+            events: vec![],
+        })
+    );
+}
+
+#[test]
+fn test_parse_framework_source() {
+    assert_eq!(
+        "on-chain".parse::<FrameworkSource>().unwrap(),
+        FrameworkSource::OnChain
+    );
+    assert_eq!(
+        "release:release-1.4.0-rc0"
+            .parse::<FrameworkSource>()
+            .unwrap(),
+        FrameworkSource::Release("release-1.4.0-rc0".to_owned())
+    );
+    assert_eq!(
+        "path/to/modules".parse::<FrameworkSource>().unwrap(),
+        FrameworkSource::Directory(PathBuf::from("path/to/modules"))
+    );
+}
+
+#[test]
+fn test_load_release_modules() {
+    assert!(FrameworkSource::OnChain.load_modules().unwrap().is_empty());
+    let modules = FrameworkSource::Release("current".to_owned())
+        .load_modules()
+        .unwrap();
+    assert_eq!(
+        modules.len(),
+        diem_framework_releases::current_module_blobs().len()
+    );
+}
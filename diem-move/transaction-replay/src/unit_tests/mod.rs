@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 mod bisection_tests;
+mod diff_tests;
 
 use crate::DiemValidatorInterface;
 use anyhow::{bail, Result};
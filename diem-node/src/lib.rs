@@ -7,8 +7,10 @@ use debug_interface::node_debug_service::NodeDebugService;
 use diem_api::runtime::bootstrap as bootstrap_api;
 use diem_config::{
     config::{NetworkConfig, NodeConfig, PersistableConfig},
+    network_id::NetworkId,
     utils::get_genesis_txn,
 };
+use diem_data_client::diemnet::DiemNetDataClient;
 use diem_infallible::RwLock;
 use diem_json_rpc::bootstrap_from_config as bootstrap_rpc;
 use diem_logger::{prelude::*, Logger};
@@ -31,9 +33,10 @@ use futures::{channel::mpsc::channel, executor::block_on};
 use network::application::storage::PeerMetadataStorage;
 use network_builder::builder::NetworkBuilder;
 use state_sync_v1::bootstrapper::StateSyncBootstrapper;
+use state_sync_v2::driver_factory::DriverFactory;
 use std::{
     boxed::Box,
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     convert::TryFrom,
     io::Write,
     net::ToSocketAddrs,
@@ -47,6 +50,11 @@ use std::{
 };
 use storage_interface::default_protocol::DbReaderWriter;
 use storage_service::start_storage_service_with_db;
+use storage_service_client::{StorageServiceClient, StorageServiceNetworkSender};
+use storage_service_notifications::StorageServiceNotificationListener;
+use storage_service_server::{
+    network::StorageServiceNetworkEvents, StorageReader, StorageServiceServer,
+};
 use tokio::runtime::{Builder, Runtime};
 use tokio_stream::wrappers::IntervalStream;
 
@@ -57,13 +65,44 @@ const MEMPOOL_NETWORK_CHANNEL_BUFFER_SIZE: usize = 1_024;
 pub struct DiemHandle {
     _api: Runtime,
     _mempool: Runtime,
-    _state_sync_bootstrapper: StateSyncBootstrapper,
+    _state_sync_runtimes: StateSyncRuntimes,
     _network_runtimes: Vec<Runtime>,
     _consensus_runtime: Option<Runtime>,
     _debug: NodeDebugService,
     _backup: Runtime,
 }
 
+/// The state sync components of the node. State sync v2 is only used if it is
+/// enabled in the config (see `StateSyncDriverConfig::enable_state_sync_v2`).
+#[allow(clippy::large_enum_variant)]
+enum StateSyncRuntimes {
+    V1(StateSyncBootstrapper),
+    V2 {
+        driver_factory: DriverFactory,
+        _storage_service_runtime: Runtime,
+        _data_client_runtime: Runtime,
+    },
+}
+
+impl StateSyncRuntimes {
+    /// Blocks until state sync has been initialized, i.e., until the node has
+    /// synced at least up to its waypoint.
+    fn block_until_initialized(&self) {
+        match self {
+            StateSyncRuntimes::V1(state_sync_bootstrapper) => {
+                let state_sync_client = state_sync_bootstrapper.create_client();
+                block_on(state_sync_client.wait_until_initialized())
+                    .expect("State sync initialization failure");
+            }
+            StateSyncRuntimes::V2 { driver_factory, .. } => {
+                let driver_client = driver_factory.create_driver_client();
+                block_on(driver_client.notify_once_bootstrapped())
+                    .expect("State sync v2 bootstrapping failure");
+            }
+        }
+    }
+}
+
 pub fn start(config: &NodeConfig, log_file: Option<PathBuf>) {
     crash_handler::setup_panic_handler();
 
@@ -200,8 +239,54 @@ fn fetch_chain_id(db: &DbReaderWriter) -> ChainId {
         .chain_id()
 }
 
-fn setup_chunk_executor(db: DbReaderWriter) -> Box<dyn ChunkExecutor> {
-    Box::new(Executor::<DpnProto, DiemVM>::new(db))
+fn setup_chunk_executor(db: DbReaderWriter, node_config: &NodeConfig) -> Box<dyn ChunkExecutor> {
+    Box::new(Executor::<DpnProto, DiemVM>::new_with_config(
+        db,
+        &node_config.execution,
+    ))
+}
+
+/// Starts the storage service server (on a dedicated runtime) to serve the
+/// storage service requests of all networks.
+fn setup_state_sync_storage_service(
+    node_config: &NodeConfig,
+    network_events: Vec<StorageServiceNetworkEvents>,
+    storage_service_listener: StorageServiceNotificationListener,
+    db_rw: &DbReaderWriter,
+) -> Runtime {
+    let storage_service_runtime = Builder::new_multi_thread()
+        .thread_name("storage-service")
+        .enable_all()
+        .build()
+        .expect("Failed to start the storage service runtime!");
+    let storage_service_server = StorageServiceServer::new(
+        node_config.state_sync.storage_service.clone(),
+        storage_service_runtime.handle().clone(),
+        StorageReader::new(Arc::clone(&db_rw.reader)),
+        TimeService::real(),
+        storage_service_listener,
+        StorageServiceNetworkEvents::merge(network_events),
+    );
+    storage_service_runtime.spawn(storage_service_server.start());
+    storage_service_runtime
+}
+
+/// Creates the Diem data client and spawns its data summary poller on a
+/// dedicated runtime.
+fn setup_diem_data_client(
+    network_senders: HashMap<NetworkId, StorageServiceNetworkSender>,
+    peer_metadata_storage: Arc<PeerMetadataStorage>,
+) -> (DiemNetDataClient, Runtime) {
+    let data_client_runtime = Builder::new_multi_thread()
+        .thread_name("diem-data-client")
+        .enable_all()
+        .build()
+        .expect("Failed to start the diem data client runtime!");
+    let storage_service_client = StorageServiceClient::new(network_senders, peer_metadata_storage);
+    let (diem_data_client, data_summary_poller) =
+        DiemNetDataClient::new(TimeService::real(), storage_service_client);
+    data_client_runtime.spawn(data_summary_poller.start());
+    (diem_data_client, data_client_runtime)
 }
 
 fn setup_debug_interface(config: &NodeConfig, logger: Option<Arc<Logger>>) -> NodeDebugService {
@@ -305,14 +390,20 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
     );
 
     instant = Instant::now();
-    let chunk_executor = setup_chunk_executor(db_rw.clone());
+    let chunk_executor = setup_chunk_executor(db_rw.clone(), node_config);
     debug!(
         "ChunkExecutor setup in {} ms",
         instant.elapsed().as_millis()
     );
     let chain_id = fetch_chain_id(&db_rw);
+    let enable_state_sync_v2 = node_config
+        .state_sync
+        .state_sync_driver
+        .enable_state_sync_v2;
     let mut network_runtimes = vec![];
     let mut state_sync_network_handles = vec![];
+    let mut storage_service_network_senders = HashMap::new();
+    let mut storage_service_network_events = vec![];
     let mut mempool_network_handles = vec![];
     let mut consensus_network_handles = None;
 
@@ -381,9 +472,20 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
         let network_id = network_config.network_id;
 
         // Create the endpoints to connect the Network to State Sync.
-        let (state_sync_sender, state_sync_events) =
-            network_builder.add_p2p_service(&state_sync_v1::network::network_endpoint_config());
-        state_sync_network_handles.push((network_id, state_sync_sender, state_sync_events));
+        if enable_state_sync_v2 {
+            let storage_service_sender: StorageServiceNetworkSender =
+                network_builder.add_client(&storage_service_client::network_endpoint_config());
+            storage_service_network_senders.insert(network_id, storage_service_sender);
+            storage_service_network_events.push(
+                network_builder
+                    .add_service(&storage_service_server::network::network_endpoint_config()),
+            );
+        } else {
+            let (mut state_sync_sender, state_sync_events) =
+                network_builder.add_p2p_service(&state_sync_v1::network::network_endpoint_config());
+            state_sync_sender.initialize(network_id, peer_metadata_storage.clone());
+            state_sync_network_handles.push((network_id, state_sync_sender, state_sync_events));
+        }
 
         // Create the endpoints to connect the Network to mempool.
         let (mempool_sender, mempool_events) = network_builder.add_p2p_service(
@@ -423,17 +525,48 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
             node_config.state_sync.client_commit_timeout_ms,
         );
 
-    // Create state sync bootstrapper
-    let state_sync_bootstrapper = StateSyncBootstrapper::bootstrap(
-        state_sync_network_handles,
-        mempool_notifier,
-        consensus_listener,
-        Arc::clone(&db_rw.reader),
-        chunk_executor,
-        node_config,
-        genesis_waypoint,
-        event_subscription_service,
-    );
+    // Create the state sync runtimes
+    let state_sync_runtimes = if enable_state_sync_v2 {
+        let (storage_service_notifier, storage_service_listener) =
+            storage_service_notifications::new_storage_service_notifier_listener_pair();
+        let storage_service_runtime = setup_state_sync_storage_service(
+            node_config,
+            storage_service_network_events,
+            storage_service_listener,
+            &db_rw,
+        );
+        let (diem_data_client, data_client_runtime) = setup_diem_data_client(
+            storage_service_network_senders,
+            peer_metadata_storage.clone(),
+        );
+        let driver_factory = DriverFactory::create_and_spawn_driver(
+            node_config,
+            genesis_waypoint,
+            db_rw.clone(),
+            chunk_executor,
+            mempool_notifier,
+            storage_service_notifier,
+            consensus_listener,
+            event_subscription_service,
+            diem_data_client,
+        );
+        StateSyncRuntimes::V2 {
+            driver_factory,
+            _storage_service_runtime: storage_service_runtime,
+            _data_client_runtime: data_client_runtime,
+        }
+    } else {
+        StateSyncRuntimes::V1(StateSyncBootstrapper::bootstrap(
+            state_sync_network_handles,
+            mempool_notifier,
+            consensus_listener,
+            Arc::clone(&db_rw.reader),
+            chunk_executor,
+            node_config,
+            genesis_waypoint,
+            event_subscription_service,
+        ))
+    };
     let (mp_client_sender, mp_client_events) = channel(AC_SMP_CHANNEL_BUFFER_SIZE);
 
     let api_runtime = if node_config.api.enabled {
@@ -463,20 +596,17 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
     // network provider -> consensus -> state synchronizer -> network provider.  This has resulted
     // in a deadlock as observed in GitHub issue #749.
     if let Some((consensus_network_sender, consensus_network_events)) = consensus_network_handles {
-        let state_sync_client = state_sync_bootstrapper.create_client();
-
         // Make sure that state synchronizer is caught up at least to its waypoint
         // (in case it's present). There is no sense to start consensus prior to that.
         // TODO: Note that we need the networking layer to be able to discover & connect to the
         // peers with potentially outdated network identity public keys.
         debug!("Wait until state sync is initialized");
-        block_on(state_sync_client.wait_until_initialized())
-            .expect("State sync initialization failure");
+        state_sync_runtimes.block_until_initialized();
         debug!("State sync initialization complete.");
 
         // Initialize and start consensus.
         instant = Instant::now();
-        consensus_runtime = Some(start_consensus(
+        let (runtime, equivocation_evidence) = start_consensus(
             node_config,
             consensus_network_sender,
             consensus_network_events,
@@ -486,7 +616,9 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
             consensus_reconfig_subscription
                 .expect("Consensus requires a reconfiguration subscription!"),
             peer_metadata_storage,
-        ));
+        );
+        debug_if.set_equivocation_evidence_source(equivocation_evidence);
+        consensus_runtime = Some(runtime);
         debug!("Consensus started in {} ms", instant.elapsed().as_millis());
     }
 
@@ -499,7 +631,7 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
     DiemHandle {
         _network_runtimes: network_runtimes,
         _mempool: mempool,
-        _state_sync_bootstrapper: state_sync_bootstrapper,
+        _state_sync_runtimes: state_sync_runtimes,
         _consensus_runtime: consensus_runtime,
         _debug: debug_if,
         _backup: backup_service,
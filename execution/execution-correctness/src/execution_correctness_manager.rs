@@ -9,7 +9,7 @@ use crate::{
     serializer::{SerializerClient, SerializerService},
     thread::ThreadService,
 };
-use diem_config::config::{ExecutionCorrectnessService, NodeConfig};
+use diem_config::config::{ExecutionConfig, ExecutionCorrectnessService, NodeConfig};
 use diem_crypto::ed25519::Ed25519PrivateKey;
 use diem_global_constants::EXECUTION_KEY;
 use diem_secure_storage::{CryptoStorage, Storage};
@@ -69,13 +69,21 @@ impl ExecutionCorrectnessManager {
         let storage_address = config.storage.address;
         let timeout_ms = config.storage.timeout_ms;
         match &config.execution.service {
-            ExecutionCorrectnessService::Local => Self::new_local(local_db, execution_prikey),
-            ExecutionCorrectnessService::Serializer => {
-                Self::new_serializer(storage_address, execution_prikey, timeout_ms)
-            }
-            ExecutionCorrectnessService::Thread => {
-                Self::new_thread(storage_address, execution_prikey, timeout_ms)
+            ExecutionCorrectnessService::Local => {
+                Self::new_local(local_db, execution_prikey, &config.execution)
             }
+            ExecutionCorrectnessService::Serializer => Self::new_serializer(
+                storage_address,
+                execution_prikey,
+                timeout_ms,
+                &config.execution,
+            ),
+            ExecutionCorrectnessService::Thread => Self::new_thread(
+                storage_address,
+                execution_prikey,
+                timeout_ms,
+                &config.execution,
+            ),
             _ => unreachable!(
                 "Unimplemented ExecutionCorrectnessService: {:?}",
                 config.execution.service
@@ -83,8 +91,15 @@ impl ExecutionCorrectnessManager {
         }
     }
 
-    pub fn new_local(db: DbReaderWriter, execution_prikey: Option<Ed25519PrivateKey>) -> Self {
-        let block_executor = Box::new(Executor::<DpnProto, DiemVM>::new(db));
+    pub fn new_local(
+        db: DbReaderWriter,
+        execution_prikey: Option<Ed25519PrivateKey>,
+        execution_config: &ExecutionConfig,
+    ) -> Self {
+        let block_executor = Box::new(Executor::<DpnProto, DiemVM>::new_with_config(
+            db,
+            execution_config,
+        ));
         Self {
             internal_execution_correctness: ExecutionCorrectnessWrapper::Local(Arc::new(
                 LocalService::new(block_executor, execution_prikey),
@@ -103,10 +118,12 @@ impl ExecutionCorrectnessManager {
         storage_address: SocketAddr,
         execution_prikey: Option<Ed25519PrivateKey>,
         timeout: u64,
+        execution_config: &ExecutionConfig,
     ) -> Self {
-        let block_executor = Box::new(Executor::<DpnProto, DiemVM>::new(DbReaderWriter::new(
-            StorageClient::new(&storage_address, timeout),
-        )));
+        let block_executor = Box::new(Executor::<DpnProto, DiemVM>::new_with_config(
+            DbReaderWriter::new(StorageClient::new(&storage_address, timeout)),
+            execution_config,
+        ));
         let serializer_service = SerializerService::new(block_executor, execution_prikey);
         Self {
             internal_execution_correctness: ExecutionCorrectnessWrapper::Serializer(Arc::new(
@@ -119,8 +136,14 @@ impl ExecutionCorrectnessManager {
         storage_address: SocketAddr,
         execution_prikey: Option<Ed25519PrivateKey>,
         network_timeout: u64,
+        execution_config: &ExecutionConfig,
     ) -> Self {
-        let thread = ThreadService::new(storage_address, execution_prikey, network_timeout);
+        let thread = ThreadService::new(
+            storage_address,
+            execution_prikey,
+            network_timeout,
+            execution_config.clone(),
+        );
         Self {
             internal_execution_correctness: ExecutionCorrectnessWrapper::Thread(thread),
         }
@@ -4,6 +4,7 @@
 use crate::serializer::{
     ExecutionCorrectnessInput, SerializerClient, SerializerService, TSerializerClient,
 };
+use diem_config::config::ExecutionConfig;
 use diem_crypto::ed25519::Ed25519PrivateKey;
 use diem_infallible::Mutex;
 use diem_logger::warn;
@@ -33,10 +34,12 @@ pub fn execute(
     listen_addr: SocketAddr,
     prikey: Option<Ed25519PrivateKey>,
     network_timeout: u64,
+    execution_config: &ExecutionConfig,
 ) {
-    let block_executor = Box::new(Executor::<DpnProto, DiemVM>::new(DbReaderWriter::new(
-        StorageClient::new(&storage_addr, network_timeout),
-    )));
+    let block_executor = Box::new(Executor::<DpnProto, DiemVM>::new_with_config(
+        DbReaderWriter::new(StorageClient::new(&storage_addr, network_timeout)),
+        execution_config,
+    ));
     let serializer_service = SerializerService::new(block_executor, prikey);
     let mut network_server = NetworkServer::new("execution", listen_addr, network_timeout);
 
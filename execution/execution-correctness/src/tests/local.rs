@@ -19,7 +19,7 @@ fn test() {
 fn execution_correctness(
     enable_signing: bool,
 ) -> (Box<dyn ExecutionCorrectness>, Option<Ed25519PublicKey>) {
-    let (_config, _handle, db_rw) = start_storage_service();
+    let (config, _handle, db_rw) = start_storage_service();
     let (prikey, pubkey) = if enable_signing {
         let prikey = Ed25519PrivateKey::generate_for_testing();
         let pubkey = Ed25519PublicKey::from(&prikey);
@@ -27,6 +27,7 @@ fn execution_correctness(
     } else {
         (None, None)
     };
-    let execution_correctness_manager = ExecutionCorrectnessManager::new_local(db_rw, prikey);
+    let execution_correctness_manager =
+        ExecutionCorrectnessManager::new_local(db_rw, prikey, &config.execution);
     (execution_correctness_manager.client(), pubkey)
 }
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use criterion::{criterion_group, criterion_main, measurement::Measurement, BatchSize, Criterion};
+use diem_types::account_config::XUS_NAME;
 use executor_benchmark::{
     create_storage_service_and_executor, transaction_executor::TransactionExecutor,
     transaction_generator::TransactionGenerator,
@@ -29,11 +30,12 @@ fn executor_benchmark<M: Measurement + 'static>(c: &mut Criterion<M>) {
     let (commit_tx, _commit_rx) = std::sync::mpsc::channel();
 
     let mut executor = TransactionExecutor::new(executor, parent_block_id, 0, Some(commit_tx));
-    let txns = generator.gen_account_creations(SMALL_BLOCK_SIZE);
+    let txns =
+        generator.gen_account_creations(SMALL_BLOCK_SIZE, false /* add_all_currencies */);
     for txn_block in txns {
         executor.execute_block(txn_block);
     }
-    let txns = generator.gen_mint_transactions(INITIAL_BALANCE, SMALL_BLOCK_SIZE);
+    let txns = generator.gen_mint_transactions(INITIAL_BALANCE, SMALL_BLOCK_SIZE, XUS_NAME);
     for txn_block in txns {
         executor.execute_block(txn_block);
     }
@@ -31,6 +31,7 @@ pub fn run(
     block_size: usize,
     db_dir: impl AsRef<Path>,
     prune_window: Option<u64>,
+    currencies: &[String],
 ) {
     println!("Initializing...");
 
@@ -62,19 +63,22 @@ pub fn run(
     let (block_sender, block_receiver) = mpsc::sync_channel(50 /* bound */);
 
     // Set a progressing bar
-    let bar = Arc::new(ProgressBar::new(num_accounts as u64 * 2));
+    let bar = Arc::new(ProgressBar::new(
+        num_accounts as u64 * (1 + currencies.len() as u64),
+    ));
     bar.set_style(
         ProgressStyle::default_bar().template("[{elapsed}] {bar:100.cyan/blue} {percent}%"),
     );
     let exe_thread_bar = Arc::clone(&bar);
 
     // Spawn two threads to run transaction generator and executor separately.
+    let currencies = currencies.to_vec();
     let gen_thread = std::thread::Builder::new()
         .name("txn_generator".to_string())
         .spawn(move || {
             let mut generator =
                 TransactionGenerator::new_with_sender(genesis_key, num_accounts, block_sender);
-            generator.run_mint(init_account_balance, block_size);
+            generator.run_mint(init_account_balance, block_size, &currencies);
             generator
         })
         .expect("Failed to spawn transaction generator thread.");
@@ -2,15 +2,18 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod db_generator;
+pub mod report;
 pub mod transaction_committer;
 pub mod transaction_executor;
 pub mod transaction_generator;
+pub mod workload;
 
 use crate::{
-    transaction_committer::TransactionCommitter, transaction_executor::TransactionExecutor,
-    transaction_generator::TransactionGenerator,
+    report::BenchmarkReport, transaction_committer::TransactionCommitter,
+    transaction_executor::TransactionExecutor, transaction_generator::TransactionGenerator,
+    workload::Workload,
 };
-use diem_config::config::{NodeConfig, RocksdbConfig};
+use diem_config::config::{ExecutionMode, NodeConfig, RocksdbConfig};
 use diem_logger::prelude::*;
 use diem_types::protocol_spec::DpnProto;
 use diem_vm::DiemVM;
@@ -40,22 +43,29 @@ pub fn create_storage_service_and_executor(
     );
 
     let _handle = start_storage_service_with_db(config, db.clone());
-    let executor = Executor::new(DbReaderWriter::new(StorageClient::new(
-        &config.storage.address,
-        config.storage.timeout_ms,
-    )));
+    let executor = Executor::new_with_config(
+        DbReaderWriter::new(StorageClient::new(
+            &config.storage.address,
+            config.storage.timeout_ms,
+        )),
+        &config.execution,
+    );
 
     (db, executor)
 }
 
-/// Runs the benchmark with given parameters.
+/// Runs the benchmark with given parameters, and returns a report of the run.
+#[allow(clippy::too_many_arguments)]
 pub fn run_benchmark(
     block_size: usize,
-    num_transfer_blocks: usize,
+    num_blocks: usize,
     source_dir: impl AsRef<Path>,
     checkpoint_dir: impl AsRef<Path>,
     verify: bool,
-) {
+    workload: Workload,
+    execution_mode: ExecutionMode,
+    report_label: Option<String>,
+) -> BenchmarkReport {
     // Create rocksdb checkpoint.
     if checkpoint_dir.as_ref().exists() {
         fs::remove_dir_all(checkpoint_dir.as_ref().join("diemdb")).unwrap_or(());
@@ -77,6 +87,8 @@ pub fn run_benchmark(
 
     let (mut config, genesis_key) = diem_genesis_tool::test_config();
     config.storage.dir = checkpoint_dir.as_ref().to_path_buf();
+    config.execution.execution_mode = execution_mode;
+    config.execution.mismatch_dump_dir = checkpoint_dir.as_ref().join("execution_mismatches");
 
     let (db, executor) = create_storage_service_and_executor(&config);
     let parent_block_id = executor.committed_block_id();
@@ -88,13 +100,15 @@ pub fn run_benchmark(
 
     let mut generator =
         TransactionGenerator::new_with_metafile(genesis_key, block_sender, source_dir);
+    generator.load_system_sequence_numbers(db.as_ref());
     let start_version = generator.version();
+    let workload_description = workload.mix.to_string();
 
     // Spawn two threads to run transaction generator and executor separately.
     let gen_thread = std::thread::Builder::new()
         .name("txn_generator".to_string())
         .spawn(move || {
-            generator.run_transfer(block_size, num_transfer_blocks);
+            generator.run_workload(&workload, block_size, num_blocks);
             generator
         })
         .expect("Failed to spawn transaction generator thread.");
@@ -119,6 +133,7 @@ pub fn run_benchmark(
             let mut committer =
                 TransactionCommitter::new(executor_2, start_version, commit_receiver);
             committer.run();
+            committer
         })
         .expect("Failed to spawn transaction committer thread.");
 
@@ -127,16 +142,28 @@ pub fn run_benchmark(
     generator.drop_sender();
     // Wait until all transactions are committed.
     exe_thread.join().unwrap();
-    commit_thread.join().unwrap();
+    let committer = commit_thread.join().unwrap();
 
     // Do a sanity check on the sequence number to make sure all transactions are committed.
     if verify {
         generator.verify_sequence_number(db.as_ref());
     }
+
+    BenchmarkReport::new(
+        report_label,
+        workload_description,
+        execution_mode,
+        block_size,
+        committer.block_times(),
+        committer.num_committed_txns(),
+        committer.elapsed(),
+    )
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::workload::Workload;
+    use diem_config::config::ExecutionMode;
     use diem_temppath::TempPath;
 
     #[test]
@@ -152,14 +179,58 @@ mod tests {
             5,  /* block_size */
             storage_dir.as_ref(),
             None, /* prune_window */
+            &[diem_types::account_config::XUS_NAME.to_owned()],
         );
 
-        super::run_benchmark(
+        let report = super::run_benchmark(
             5, /* block_size */
-            5, /* num_transfer_blocks */
+            5, /* num_blocks */
             storage_dir.as_ref(),
             checkpoint_dir,
             false,
+            Workload::default(),
+            ExecutionMode::Sequential,
+            None, /* report_label */
+        );
+        assert_eq!(report.num_blocks, 5);
+        assert_eq!(report.num_txns, 25);
+    }
+
+    #[test]
+    fn test_benchmark_workload_mix() {
+        let storage_dir = TempPath::new();
+        let checkpoint_dir = TempPath::new();
+        storage_dir.create_as_dir().unwrap();
+        checkpoint_dir.create_as_dir().unwrap();
+
+        crate::db_generator::run(
+            25, /* num_accounts */
+            10, /* init_account_balance */
+            5,  /* block_size */
+            storage_dir.as_ref(),
+            None, /* prune_window */
+            &[diem_types::account_config::XUS_NAME.to_owned()],
+        );
+
+        let workload = Workload {
+            mix: "p2p,hot_account,zipfian,module_publish,large_write_set"
+                .parse()
+                .unwrap(),
+            num_hot_accounts: 2,
+            ..Workload::default()
+        };
+        let report = super::run_benchmark(
+            10, /* block_size */
+            5,  /* num_blocks */
+            storage_dir.as_ref(),
+            checkpoint_dir,
+            true, /* verify */
+            workload,
+            ExecutionMode::Parallel,
+            Some("test".to_owned()),
         );
+        assert_eq!(report.num_txns, 50);
+        assert_eq!(report.execution_mode, ExecutionMode::Parallel);
+        assert!(report.execute.p99_ms >= report.execute.p50_ms);
     }
 }
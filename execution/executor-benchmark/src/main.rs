@@ -1,7 +1,9 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use std::path::PathBuf;
+use diem_config::config::ExecutionMode;
+use executor_benchmark::workload::{Workload, WorkloadMix};
+use std::{fs, path::PathBuf};
 use structopt::StructOpt;
 
 #[derive(Debug, StructOpt)]
@@ -27,13 +29,17 @@ enum Command {
 
         #[structopt(long)]
         prune_window: Option<u64>,
-    },
-    RunExecutor {
+
         #[structopt(
             long,
-            default_value = "1000",
-            about = "number of transfer blocks to run"
+            default_value = "XUS",
+            use_delimiter = true,
+            about = "Currencies to fund the accounts with"
         )]
+        currencies: Vec<String>,
+    },
+    RunExecutor {
+        #[structopt(long, default_value = "1000", about = "number of blocks to run")]
         blocks: usize,
 
         #[structopt(long, parse(from_os_str))]
@@ -47,9 +53,64 @@ enum Command {
             about = "Verify sequence number of all the accounts after execution finishes"
         )]
         verify: bool,
+
+        #[structopt(
+            long,
+            default_value = "p2p",
+            about = "Weighted mix of workload profiles, e.g. p2p=70,hot_account=20,module_publish=10"
+        )]
+        workload: WorkloadMix,
+
+        #[structopt(long, default_value = "1", about = "Number of hot accounts")]
+        num_hot_accounts: usize,
+
+        #[structopt(long, default_value = "1.0", about = "Exponent of the zipfian senders")]
+        zipf_exponent: f64,
+
+        #[structopt(
+            long,
+            default_value = "1024",
+            about = "Size in bytes of the published modules"
+        )]
+        module_size: usize,
+
+        #[structopt(
+            long,
+            default_value = "XUS",
+            use_delimiter = true,
+            about = "Currencies of the multi_currency transfers"
+        )]
+        currencies: Vec<String>,
+
+        #[structopt(
+            long,
+            default_value = "sequential",
+            parse(try_from_str = parse_execution_mode),
+            about = "VM execution mode: sequential, parallel or shadow"
+        )]
+        execution_mode: ExecutionMode,
+
+        #[structopt(
+            long,
+            parse(from_os_str),
+            about = "Write a JSON report of the run to this file"
+        )]
+        report: Option<PathBuf>,
+
+        #[structopt(long, about = "Label identifying the run in the report")]
+        report_label: Option<String>,
     },
 }
 
+fn parse_execution_mode(s: &str) -> Result<ExecutionMode, String> {
+    match s {
+        "sequential" => Ok(ExecutionMode::Sequential),
+        "parallel" => Ok(ExecutionMode::Parallel),
+        "shadow" => Ok(ExecutionMode::Shadow),
+        _ => Err(format!("Unknown execution mode '{}'", s)),
+    }
+}
+
 fn main() {
     let opt = Opt::from_args();
 
@@ -64,6 +125,7 @@ fn main() {
             num_accounts,
             init_account_balance,
             prune_window,
+            currencies,
         } => {
             executor_benchmark::db_generator::run(
                 num_accounts,
@@ -71,6 +133,7 @@ fn main() {
                 opt.block_size,
                 data_dir,
                 prune_window,
+                &currencies,
             );
         }
         Command::RunExecutor {
@@ -78,15 +141,39 @@ fn main() {
             data_dir,
             checkpoint_dir,
             verify,
+            workload,
+            num_hot_accounts,
+            zipf_exponent,
+            module_size,
+            currencies,
+            execution_mode,
+            report,
+            report_label,
         } => {
             diem_logger::Logger::new().init();
-            executor_benchmark::run_benchmark(
+            let workload = Workload {
+                mix: workload,
+                num_hot_accounts,
+                zipf_exponent,
+                module_size,
+                currencies,
+            };
+            let benchmark_report = executor_benchmark::run_benchmark(
                 opt.block_size,
                 blocks,
                 data_dir,
                 checkpoint_dir,
                 verify,
+                workload,
+                execution_mode,
+                report_label,
             );
+            let json =
+                serde_json::to_string_pretty(&benchmark_report).expect("Report should serialize.");
+            match report {
+                Some(path) => fs::write(path, json).expect("Failed to write the report."),
+                None => println!("{}", json),
+            }
         }
     }
 }
@@ -0,0 +1,125 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use diem_config::config::ExecutionMode;
+use serde::Serialize;
+use std::time::Duration;
+
+/// The time spent in each stage of the pipeline for a single block.
+#[derive(Clone, Copy, Debug)]
+pub struct BlockTimes {
+    pub execute: Duration,
+    pub commit: Duration,
+    /// The part of `commit` spent updating the Jellyfish Merkle tree.
+    pub jmt_update: Duration,
+}
+
+/// Latency statistics (in milliseconds) of a pipeline stage, over all the blocks of a run.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct StageLatency {
+    pub mean_ms: f64,
+    pub p50_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+impl StageLatency {
+    pub fn from_samples(samples: impl Iterator<Item = Duration>) -> Self {
+        let mut samples: Vec<f64> = samples.map(|d| d.as_nanos() as f64 / 1_000_000.0).collect();
+        if samples.is_empty() {
+            return Self {
+                mean_ms: 0.0,
+                p50_ms: 0.0,
+                p99_ms: 0.0,
+                max_ms: 0.0,
+            };
+        }
+        samples.sort_by(|a, b| a.partial_cmp(b).expect("Durations are never NaN"));
+
+        // Nearest-rank percentile.
+        let percentile = |p: f64| {
+            let rank = (p / 100.0 * samples.len() as f64).ceil() as usize;
+            samples[rank.max(1) - 1]
+        };
+        Self {
+            mean_ms: samples.iter().sum::<f64>() / samples.len() as f64,
+            p50_ms: percentile(50.0),
+            p99_ms: percentile(99.0),
+            max_ms: samples[samples.len() - 1],
+        }
+    }
+}
+
+/// A machine-readable summary of a benchmark run, so that runs can be compared across commits.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct BenchmarkReport {
+    /// Free-form label identifying the run (e.g., the commit being benchmarked).
+    pub label: Option<String>,
+    pub workload: String,
+    pub execution_mode: ExecutionMode,
+    pub block_size: usize,
+    pub num_blocks: usize,
+    pub num_txns: usize,
+    pub elapsed_secs: f64,
+    pub tps: f64,
+    pub execute: StageLatency,
+    pub commit: StageLatency,
+    pub jmt_update: StageLatency,
+}
+
+impl BenchmarkReport {
+    pub fn new(
+        label: Option<String>,
+        workload: String,
+        execution_mode: ExecutionMode,
+        block_size: usize,
+        block_times: &[BlockTimes],
+        num_txns: usize,
+        elapsed: Duration,
+    ) -> Self {
+        let elapsed_secs = elapsed.as_secs_f64();
+        Self {
+            label,
+            workload,
+            execution_mode,
+            block_size,
+            num_blocks: block_times.len(),
+            num_txns,
+            elapsed_secs,
+            tps: if elapsed_secs > 0.0 {
+                num_txns as f64 / elapsed_secs
+            } else {
+                0.0
+            },
+            execute: StageLatency::from_samples(block_times.iter().map(|t| t.execute)),
+            commit: StageLatency::from_samples(block_times.iter().map(|t| t.commit)),
+            jmt_update: StageLatency::from_samples(block_times.iter().map(|t| t.jmt_update)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stage_latency() {
+        let latency = StageLatency::from_samples((1..=100).rev().map(Duration::from_millis));
+        assert_eq!(
+            latency,
+            StageLatency {
+                mean_ms: 50.5,
+                p50_ms: 50.0,
+                p99_ms: 99.0,
+                max_ms: 100.0,
+            }
+        );
+
+        let latency = StageLatency::from_samples(std::iter::once(Duration::from_millis(7)));
+        assert_eq!(latency.p50_ms, 7.0);
+        assert_eq!(latency.p99_ms, 7.0);
+
+        let latency = StageLatency::from_samples(std::iter::empty());
+        assert_eq!(latency.max_ms, 0.0);
+    }
+}
@@ -1,6 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::report::BlockTimes;
 use diem_crypto::hash::HashValue;
 use diem_logger::prelude::*;
 use diem_types::{
@@ -10,7 +11,7 @@ use diem_types::{
     transaction::Version,
 };
 use diem_vm::DiemVM;
-use diemdb::metrics::DIEM_STORAGE_API_LATENCY_SECONDS;
+use diemdb::metrics::{DIEM_STORAGE_API_LATENCY_SECONDS, DIEM_STORAGE_OTHER_TIMERS_SECONDS};
 use executor::{
     metrics::{
         DIEM_EXECUTOR_COMMIT_BLOCKS_SECONDS, DIEM_EXECUTOR_EXECUTE_BLOCK_SECONDS,
@@ -46,8 +47,11 @@ pub(crate) fn gen_li_with_sigs(
 
 pub struct TransactionCommitter {
     executor: Arc<Executor<DpnProto, DiemVM>>,
+    start_version: Version,
     version: Version,
     block_receiver: mpsc::Receiver<(HashValue, HashValue, Instant, Instant, Duration, usize)>,
+    block_times: Vec<BlockTimes>,
+    elapsed: Duration,
 }
 
 impl TransactionCommitter {
@@ -57,12 +61,29 @@ impl TransactionCommitter {
         block_receiver: mpsc::Receiver<(HashValue, HashValue, Instant, Instant, Duration, usize)>,
     ) -> Self {
         Self {
+            start_version: version,
             version,
             executor,
             block_receiver,
+            block_times: vec![],
+            elapsed: Duration::default(),
         }
     }
 
+    /// The time spent in each stage for every committed block.
+    pub fn block_times(&self) -> &[BlockTimes] {
+        &self.block_times
+    }
+
+    pub fn num_committed_txns(&self) -> usize {
+        (self.version - self.start_version) as usize
+    }
+
+    /// The time between the start of the benchmark and the last commit.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
     pub fn run(&mut self) {
         info!("Start with version: {}", self.version);
 
@@ -77,23 +98,41 @@ impl TransactionCommitter {
         {
             self.version += num_txns as u64;
             let commit_start = std::time::Instant::now();
+            let jmt_update_start = jmt_update_seconds();
             let ledger_info_with_sigs = gen_li_with_sigs(block_id, root_hash, self.version);
             self.executor
                 .commit_blocks(vec![block_id], ledger_info_with_sigs)
                 .unwrap();
+            let commit_time = Instant::now().duration_since(commit_start);
+
+            self.block_times.push(BlockTimes {
+                execute: execution_time,
+                commit: commit_time,
+                jmt_update: Duration::from_secs_f64(
+                    (jmt_update_seconds() - jmt_update_start).max(0.0),
+                ),
+            });
+            self.elapsed = global_start_time.elapsed();
 
             report_block(
                 self.version,
                 global_start_time,
                 execution_start_time,
                 execution_time,
-                Instant::now().duration_since(commit_start),
+                commit_time,
                 num_txns,
             );
         }
     }
 }
 
+/// The accumulated time spent updating the Jellyfish Merkle tree when saving transactions.
+fn jmt_update_seconds() -> f64 {
+    DIEM_STORAGE_OTHER_TIMERS_SECONDS
+        .with_label_values(&["save_transactions_jmt_update"])
+        .get_sample_sum()
+}
+
 fn report_block(
     version: Version,
     global_start_time: Instant,
@@ -1,6 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::workload::{Workload, WorkloadProfile, ZipfSampler};
 use diem_crypto::{
     ed25519::{Ed25519PrivateKey, Ed25519PublicKey},
     PrivateKey, SigningKey, Uniform,
@@ -11,17 +12,20 @@ use diem_transaction_builder::stdlib::{
 use diem_types::{
     account_address::AccountAddress,
     account_config::{
-        testnet_dd_account_address, treasury_compliance_account_address, xus_tag, AccountResource,
-        XUS_NAME,
+        diem_root_address, from_currency_code_string, testnet_dd_account_address,
+        treasury_compliance_account_address, type_tag_for_currency_code, xus_tag, AccountResource,
+        CORE_CODE_ADDRESS, XUS_NAME,
     },
     chain_id::ChainId,
     protocol_spec::DpnProto,
     transaction::{
-        authenticator::AuthenticationKey, RawTransaction, Script, SignedTransaction, Transaction,
-        Version,
+        authenticator::AuthenticationKey, Module, RawTransaction, SignedTransaction, Transaction,
+        TransactionPayload, Version,
     },
 };
-use rand::{rngs::StdRng, SeedableRng};
+use move_binary_format::file_format::{empty_module, Constant, SignatureToken};
+use move_core_types::{identifier::Identifier, language_storage::TypeTag};
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
 use std::{
     convert::TryFrom,
@@ -78,6 +82,14 @@ pub struct TransactionGenerator {
     /// Used to mint accounts.
     genesis_key: Ed25519PrivateKey,
 
+    /// The next sequence numbers of the system accounts signing with `genesis_key`.
+    diem_root_sequence_number: u64,
+    tc_sequence_number: u64,
+    dd_sequence_number: u64,
+
+    /// The sender distribution of the `Zipfian` workload, keyed by the bits of its exponent.
+    zipf_sampler: Option<(u64, ZipfSampler)>,
+
     /// Record the number of txns generated.
     version: Version,
 
@@ -127,6 +139,10 @@ impl TransactionGenerator {
         Self {
             accounts,
             genesis_key,
+            diem_root_sequence_number: 0,
+            tc_sequence_number: 0,
+            dd_sequence_number: 0,
+            zipf_sampler: None,
             version: 0,
             rng,
             block_sender,
@@ -149,12 +165,31 @@ impl TransactionGenerator {
         Self {
             accounts: txn_gen_base.accounts,
             genesis_key,
+            diem_root_sequence_number: 0,
+            tc_sequence_number: 0,
+            dd_sequence_number: 0,
+            zipf_sampler: None,
             version: txn_gen_base.version,
             rng,
             block_sender: Some(block_sender),
         }
     }
 
+    /// Reads the sequence numbers of the system accounts from storage. This is needed before
+    /// generating transactions sent by these accounts on top of an existing DB.
+    pub fn load_system_sequence_numbers(&mut self, db: &dyn DbReader<DpnProto>) {
+        let sequence_number = |address| {
+            let blob = db
+                .get_latest_account_state(address)
+                .expect("Failed to query storage.")
+                .expect("Account must exist.");
+            AccountResource::try_from(&blob).unwrap().sequence_number()
+        };
+        self.diem_root_sequence_number = sequence_number(diem_root_address());
+        self.tc_sequence_number = sequence_number(treasury_compliance_account_address());
+        self.dd_sequence_number = sequence_number(testnet_dd_account_address());
+    }
+
     // Write metadata
     pub fn write_meta<P: AsRef<Path>>(self, path: &P) {
         let metadata = bcs::to_bytes(&TransactionGeneratorBase::from(self)).unwrap();
@@ -167,10 +202,19 @@ impl TransactionGenerator {
         self.version
     }
 
-    pub fn run_mint(&mut self, init_account_balance: u64, block_size: usize) {
+    /// Creates and funds the accounts. If more than one currency is given, the accounts are
+    /// created with a balance in every currency, and funded in each of the given ones.
+    pub fn run_mint(
+        &mut self,
+        init_account_balance: u64,
+        block_size: usize,
+        currencies: &[String],
+    ) {
         assert!(self.block_sender.is_some());
-        self.gen_account_creations(block_size);
-        self.gen_mint_transactions(init_account_balance, block_size);
+        self.gen_account_creations(block_size, currencies.len() > 1);
+        for currency in currencies {
+            self.gen_mint_transactions(init_account_balance, block_size, currency);
+        }
     }
 
     pub fn run_transfer(&mut self, block_size: usize, num_transfer_blocks: usize) {
@@ -178,27 +222,38 @@ impl TransactionGenerator {
         self.gen_transfer_transactions(block_size, num_transfer_blocks);
     }
 
-    pub fn gen_account_creations(&mut self, block_size: usize) -> Vec<Vec<Transaction>> {
+    pub fn run_workload(&mut self, workload: &Workload, block_size: usize, num_blocks: usize) {
+        assert!(self.block_sender.is_some());
+        self.gen_workload_transactions(workload, block_size, num_blocks);
+    }
+
+    pub fn gen_account_creations(
+        &mut self,
+        block_size: usize,
+        add_all_currencies: bool,
+    ) -> Vec<Vec<Transaction>> {
         let tc_account = treasury_compliance_account_address();
         let mut txn_block = vec![];
 
-        for (i, block) in self.accounts.chunks(block_size).enumerate() {
+        for block in self.accounts.chunks(block_size) {
             let mut transactions = Vec::with_capacity(block_size);
-            for (j, account) in block.iter().enumerate() {
+            for account in block {
                 let txn = create_transaction(
                     tc_account,
-                    (i * block_size + j) as u64,
+                    self.tc_sequence_number,
                     &self.genesis_key,
                     self.genesis_key.public_key(),
-                    encode_create_parent_vasp_account_script(
+                    TransactionPayload::Script(encode_create_parent_vasp_account_script(
                         xus_tag(),
                         0,
                         account.address,
                         account.auth_key_prefix(),
                         vec![],
-                        false, /* add all currencies */
-                    ),
+                        add_all_currencies,
+                    )),
+                    XUS_NAME,
                 );
+                self.tc_sequence_number += 1;
                 transactions.push(txn);
             }
             self.version += transactions.len() as Version;
@@ -211,31 +266,35 @@ impl TransactionGenerator {
         txn_block
     }
 
-    /// Generates transactions that allocate `init_account_balance` to every account.
+    /// Generates transactions that allocate `init_account_balance` of `currency` to every
+    /// account.
     pub fn gen_mint_transactions(
         &mut self,
         init_account_balance: u64,
         block_size: usize,
+        currency: &str,
     ) -> Vec<Vec<Transaction>> {
         let testnet_dd_account = testnet_dd_account_address();
         let mut txn_block = vec![];
 
-        for (i, block) in self.accounts.chunks(block_size).enumerate() {
+        for block in self.accounts.chunks(block_size) {
             let mut transactions = Vec::with_capacity(block_size);
-            for (j, account) in block.iter().enumerate() {
+            for account in block {
                 let txn = create_transaction(
                     testnet_dd_account,
-                    (i * block_size + j) as u64,
+                    self.dd_sequence_number,
                     &self.genesis_key,
                     self.genesis_key.public_key(),
-                    encode_peer_to_peer_with_metadata_script(
-                        xus_tag(),
+                    TransactionPayload::Script(encode_peer_to_peer_with_metadata_script(
+                        currency_tag(currency),
                         account.address,
                         init_account_balance,
                         vec![],
                         vec![],
-                    ),
+                    )),
+                    currency,
                 );
+                self.dd_sequence_number += 1;
                 transactions.push(txn);
             }
             self.version += transactions.len() as Version;
@@ -254,33 +313,23 @@ impl TransactionGenerator {
         &mut self,
         block_size: usize,
         num_blocks: usize,
+    ) -> Vec<Vec<Transaction>> {
+        self.gen_workload_transactions(&Workload::default(), block_size, num_blocks)
+    }
+
+    /// Generates blocks of transactions following the profiles mix of `workload`.
+    pub fn gen_workload_transactions(
+        &mut self,
+        workload: &Workload,
+        block_size: usize,
+        num_blocks: usize,
     ) -> Vec<Vec<Transaction>> {
         let mut txn_block = vec![];
         for _i in 0..num_blocks {
             let mut transactions = Vec::with_capacity(block_size);
             for _j in 0..block_size {
-                let indices = rand::seq::index::sample(&mut self.rng, self.accounts.len(), 2);
-                let sender_idx = indices.index(0);
-                let receiver_idx = indices.index(1);
-
-                let sender = &self.accounts[sender_idx];
-                let receiver = &self.accounts[receiver_idx];
-                let txn = create_transaction(
-                    sender.address,
-                    sender.sequence_number,
-                    &sender.private_key,
-                    sender.public_key.clone(),
-                    encode_peer_to_peer_with_metadata_script(
-                        xus_tag(),
-                        receiver.address,
-                        1, /* amount */
-                        vec![],
-                        vec![],
-                    ),
-                );
-                transactions.push(txn);
-
-                self.accounts[sender_idx].sequence_number += 1;
+                let profile = workload.mix.sample(&mut self.rng);
+                transactions.push(self.gen_workload_transaction(workload, profile));
             }
             self.version += transactions.len() as Version;
 
@@ -293,6 +342,114 @@ impl TransactionGenerator {
         txn_block
     }
 
+    fn gen_workload_transaction(
+        &mut self,
+        workload: &Workload,
+        profile: WorkloadProfile,
+    ) -> Transaction {
+        let num_accounts = self.accounts.len();
+        match profile {
+            WorkloadProfile::P2p => {
+                let (sender_idx, receiver_idx) = self.sample_account_pair();
+                self.gen_transfer(sender_idx, receiver_idx, XUS_NAME)
+            }
+            WorkloadProfile::HotAccount => {
+                let num_hot_accounts = workload.num_hot_accounts.max(1).min(num_accounts - 1);
+                let sender_idx = self.rng.gen_range(num_hot_accounts..num_accounts);
+                let receiver_idx = self.rng.gen_range(0..num_hot_accounts);
+                self.gen_transfer(sender_idx, receiver_idx, XUS_NAME)
+            }
+            WorkloadProfile::Zipfian => {
+                let exponent = workload.zipf_exponent;
+                if !matches!(&self.zipf_sampler, Some((bits, _)) if *bits == exponent.to_bits()) {
+                    self.zipf_sampler =
+                        Some((exponent.to_bits(), ZipfSampler::new(num_accounts, exponent)));
+                }
+                let (_, sampler) = self.zipf_sampler.as_ref().expect("Initialized above");
+                let sender_idx = sampler.sample(&mut self.rng);
+                let mut receiver_idx = self.rng.gen_range(0..num_accounts - 1);
+                if receiver_idx >= sender_idx {
+                    receiver_idx += 1;
+                }
+                self.gen_transfer(sender_idx, receiver_idx, XUS_NAME)
+            }
+            WorkloadProfile::MultiCurrency => {
+                let (sender_idx, receiver_idx) = self.sample_account_pair();
+                let currency_idx = self.rng.gen_range(0..workload.currencies.len());
+                let currency = workload.currencies[currency_idx].clone();
+                self.gen_transfer(sender_idx, receiver_idx, &currency)
+            }
+            WorkloadProfile::ModulePublish => {
+                let sequence_number = self.diem_root_sequence_number;
+                self.diem_root_sequence_number += 1;
+                let module = padded_module(
+                    Identifier::new(format!("Bench{}", sequence_number)).unwrap(),
+                    workload.module_size,
+                );
+                create_transaction(
+                    diem_root_address(),
+                    sequence_number,
+                    &self.genesis_key,
+                    self.genesis_key.public_key(),
+                    TransactionPayload::Module(module),
+                    XUS_NAME,
+                )
+            }
+            WorkloadProfile::LargeWriteSet => {
+                let private_key = Ed25519PrivateKey::generate(&mut self.rng);
+                let public_key = private_key.public_key();
+                let sequence_number = self.tc_sequence_number;
+                self.tc_sequence_number += 1;
+                create_transaction(
+                    treasury_compliance_account_address(),
+                    sequence_number,
+                    &self.genesis_key,
+                    self.genesis_key.public_key(),
+                    TransactionPayload::Script(encode_create_parent_vasp_account_script(
+                        xus_tag(),
+                        0,
+                        diem_types::account_address::from_public_key(&public_key),
+                        AuthenticationKey::ed25519(&public_key).prefix().to_vec(),
+                        vec![],
+                        true, /* add all currencies */
+                    )),
+                    XUS_NAME,
+                )
+            }
+        }
+    }
+
+    fn sample_account_pair(&mut self) -> (usize, usize) {
+        let indices = rand::seq::index::sample(&mut self.rng, self.accounts.len(), 2);
+        (indices.index(0), indices.index(1))
+    }
+
+    fn gen_transfer(
+        &mut self,
+        sender_idx: usize,
+        receiver_idx: usize,
+        currency: &str,
+    ) -> Transaction {
+        let sender = &self.accounts[sender_idx];
+        let receiver = &self.accounts[receiver_idx];
+        let txn = create_transaction(
+            sender.address,
+            sender.sequence_number,
+            &sender.private_key,
+            sender.public_key.clone(),
+            TransactionPayload::Script(encode_peer_to_peer_with_metadata_script(
+                currency_tag(currency),
+                receiver.address,
+                1, /* amount */
+                vec![],
+                vec![],
+            )),
+            currency,
+        );
+        self.accounts[sender_idx].sequence_number += 1;
+        txn
+    }
+
     /// Verifies the sequence numbers in storage match what we have locally.
     pub fn verify_sequence_number(&self, db: &dyn DbReader<DpnProto>) {
         for account in &self.accounts {
@@ -312,23 +469,46 @@ impl TransactionGenerator {
     }
 }
 
+fn currency_tag(currency: &str) -> TypeTag {
+    type_tag_for_currency_code(
+        from_currency_code_string(currency).expect("Currency code should be valid."),
+    )
+}
+
+/// Returns a module named `name` (published at the core code address) whose constant pool is
+/// padded with `size` bytes.
+fn padded_module(name: Identifier, size: usize) -> Module {
+    let mut module = empty_module();
+    module.identifiers[0] = name;
+    module.address_identifiers[0] = CORE_CODE_ADDRESS;
+    module.constant_pool.push(Constant {
+        type_: SignatureToken::Vector(Box::new(SignatureToken::U8)),
+        data: bcs::to_bytes(&vec![0u8; size]).unwrap(),
+    });
+
+    let mut code = vec![];
+    module.serialize(&mut code).unwrap();
+    Module::new(code)
+}
+
 fn create_transaction(
     sender: AccountAddress,
     sequence_number: u64,
     private_key: &Ed25519PrivateKey,
     public_key: Ed25519PublicKey,
-    program: Script,
+    payload: TransactionPayload,
+    gas_currency_code: &str,
 ) -> Transaction {
     let now = diem_infallible::duration_since_epoch();
     let expiration_time = now.as_secs() + 3600;
 
-    let raw_txn = RawTransaction::new_script(
+    let raw_txn = RawTransaction::new(
         sender,
         sequence_number,
-        program,
-        1_000_000,           /* max_gas_amount */
-        0,                   /* gas_unit_price */
-        XUS_NAME.to_owned(), /* gas_currency_code */
+        payload,
+        1_000_000, /* max_gas_amount */
+        0,         /* gas_unit_price */
+        gas_currency_code.to_owned(),
         expiration_time,
         ChainId::test(),
     );
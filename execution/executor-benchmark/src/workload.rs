@@ -0,0 +1,264 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use rand::Rng;
+use serde::Serialize;
+use std::{fmt, str::FromStr};
+
+/// The kinds of transactions the benchmark can generate.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkloadProfile {
+    /// Transfers between uniformly random pairs of accounts.
+    P2p,
+    /// Transfers from random accounts to a small set of hot accounts, so that most transactions
+    /// in a block contend on the same resources.
+    HotAccount,
+    /// Transfers whose senders follow a Zipfian distribution, i.e., a few accounts send most of
+    /// the transactions.
+    Zipfian,
+    /// Transfers in a random currency (out of the configured currencies), with gas paid in the
+    /// same currency.
+    MultiCurrency,
+    /// Publishing of new modules by the diem root account.
+    ModulePublish,
+    /// Creation of new accounts holding every currency, each writing many resources.
+    LargeWriteSet,
+}
+
+impl WorkloadProfile {
+    pub const ALL: [WorkloadProfile; 6] = [
+        WorkloadProfile::P2p,
+        WorkloadProfile::HotAccount,
+        WorkloadProfile::Zipfian,
+        WorkloadProfile::MultiCurrency,
+        WorkloadProfile::ModulePublish,
+        WorkloadProfile::LargeWriteSet,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WorkloadProfile::P2p => "p2p",
+            WorkloadProfile::HotAccount => "hot_account",
+            WorkloadProfile::Zipfian => "zipfian",
+            WorkloadProfile::MultiCurrency => "multi_currency",
+            WorkloadProfile::ModulePublish => "module_publish",
+            WorkloadProfile::LargeWriteSet => "large_write_set",
+        }
+    }
+}
+
+impl FromStr for WorkloadProfile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        WorkloadProfile::ALL
+            .iter()
+            .find(|profile| profile.as_str() == s)
+            .copied()
+            .ok_or_else(|| {
+                format!(
+                    "Unknown workload profile '{}', expected one of: {}",
+                    s,
+                    WorkloadProfile::ALL
+                        .iter()
+                        .map(WorkloadProfile::as_str)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })
+    }
+}
+
+/// A weighted mix of workload profiles: each generated transaction picks its profile at random,
+/// proportionally to the weights. Parsed from strings like
+/// `p2p=70,hot_account=20,module_publish=10` (a profile without a weight gets a weight of 1).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WorkloadMix {
+    profiles: Vec<(WorkloadProfile, u32)>,
+    total_weight: u32,
+}
+
+impl WorkloadMix {
+    pub fn new(profiles: Vec<(WorkloadProfile, u32)>) -> Result<Self, String> {
+        let total_weight = profiles.iter().map(|(_, weight)| weight).sum();
+        if total_weight == 0 {
+            return Err("A workload mix needs at least one profile with a non-zero weight".into());
+        }
+        Ok(Self {
+            profiles,
+            total_weight,
+        })
+    }
+
+    pub fn profiles(&self) -> impl Iterator<Item = WorkloadProfile> + '_ {
+        self.profiles
+            .iter()
+            .filter(|(_, weight)| *weight > 0)
+            .map(|(profile, _)| *profile)
+    }
+
+    pub fn contains(&self, profile: WorkloadProfile) -> bool {
+        self.profiles().any(|p| p == profile)
+    }
+
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> WorkloadProfile {
+        let mut point = rng.gen_range(0..self.total_weight);
+        for (profile, weight) in &self.profiles {
+            if point < *weight {
+                return *profile;
+            }
+            point -= weight;
+        }
+        unreachable!("The sampled point is always below the total weight")
+    }
+}
+
+impl Default for WorkloadMix {
+    fn default() -> Self {
+        Self::new(vec![(WorkloadProfile::P2p, 1)]).expect("P2p has a non-zero weight")
+    }
+}
+
+impl FromStr for WorkloadMix {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let profiles = s
+            .split(',')
+            .map(|entry| {
+                let mut parts = entry.trim().splitn(2, '=');
+                let profile = parts.next().unwrap_or_default().parse()?;
+                let weight = match parts.next() {
+                    Some(weight) => weight
+                        .parse()
+                        .map_err(|e| format!("Invalid weight '{}': {}", weight, e))?,
+                    None => 1,
+                };
+                Ok((profile, weight))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Self::new(profiles)
+    }
+}
+
+impl fmt::Display for WorkloadMix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let entries: Vec<_> = self
+            .profiles
+            .iter()
+            .map(|(profile, weight)| format!("{}={}", profile.as_str(), weight))
+            .collect();
+        write!(f, "{}", entries.join(","))
+    }
+}
+
+/// Parameters of the workload profiles.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Workload {
+    pub mix: WorkloadMix,
+    /// The number of accounts receiving all the transfers of the `HotAccount` profile.
+    pub num_hot_accounts: usize,
+    /// The exponent of the Zipfian sender distribution. Higher values concentrate the senders
+    /// on fewer accounts.
+    pub zipf_exponent: f64,
+    /// The size (in bytes) of the constant padding of each module published by the
+    /// `ModulePublish` profile.
+    pub module_size: usize,
+    /// The currencies used by the `MultiCurrency` profile. Every account must hold a balance in
+    /// each of them (see `db_generator::run`).
+    pub currencies: Vec<String>,
+}
+
+impl Default for Workload {
+    fn default() -> Self {
+        Self {
+            mix: WorkloadMix::default(),
+            num_hot_accounts: 1,
+            zipf_exponent: 1.0,
+            module_size: 1024,
+            currencies: vec![diem_types::account_config::XUS_NAME.to_owned()],
+        }
+    }
+}
+
+/// Samples account indices in `[0, num_accounts)` with a Zipfian distribution, i.e., the
+/// probability of index `i` is proportional to `1 / (i + 1)^exponent`.
+pub struct ZipfSampler {
+    cdf: Vec<f64>,
+}
+
+impl ZipfSampler {
+    pub fn new(num_accounts: usize, exponent: f64) -> Self {
+        let mut cdf = Vec::with_capacity(num_accounts);
+        let mut sum = 0.0;
+        for i in 0..num_accounts {
+            sum += 1.0 / ((i + 1) as f64).powf(exponent);
+            cdf.push(sum);
+        }
+        for value in cdf.iter_mut() {
+            *value /= sum;
+        }
+        Self { cdf }
+    }
+
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> usize {
+        let point: f64 = rng.gen();
+        let index = self.cdf.partition_point(|value| *value <= point);
+        std::cmp::min(index, self.cdf.len() - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_parse_workload_mix() {
+        let mix: WorkloadMix = "p2p=70, hot_account=20,module_publish".parse().unwrap();
+        assert_eq!(
+            mix,
+            WorkloadMix::new(vec![
+                (WorkloadProfile::P2p, 70),
+                (WorkloadProfile::HotAccount, 20),
+                (WorkloadProfile::ModulePublish, 1),
+            ])
+            .unwrap()
+        );
+        assert_eq!(mix.to_string(), "p2p=70,hot_account=20,module_publish=1");
+        assert!(mix.contains(WorkloadProfile::ModulePublish));
+        assert!(!mix.contains(WorkloadProfile::Zipfian));
+
+        assert!("transfers".parse::<WorkloadMix>().is_err());
+        assert!("p2p=x".parse::<WorkloadMix>().is_err());
+        assert!("p2p=0".parse::<WorkloadMix>().is_err());
+    }
+
+    #[test]
+    fn test_sample_workload_mix() {
+        let mix: WorkloadMix = "p2p=0,zipfian=3".parse().unwrap();
+        let mut rng = StdRng::from_seed([0u8; 32]);
+        for _ in 0..100 {
+            assert_eq!(mix.sample(&mut rng), WorkloadProfile::Zipfian);
+        }
+        assert_eq!(
+            mix.profiles().collect::<Vec<_>>(),
+            vec![WorkloadProfile::Zipfian]
+        );
+    }
+
+    #[test]
+    fn test_zipf_sampler() {
+        let sampler = ZipfSampler::new(100, 1.5);
+        let mut rng = StdRng::from_seed([0u8; 32]);
+        let mut counts = vec![0; 100];
+        for _ in 0..10_000 {
+            counts[sampler.sample(&mut rng)] += 1;
+        }
+        // The first account is by far the most popular one.
+        assert!(counts[0] > counts[1]);
+        assert!(counts[0] > 10_000 / 3);
+        assert!(counts[1] > counts[50]);
+    }
+}
@@ -114,7 +114,12 @@ where
                         "Injected error in vm_execute_block"
                     )))
                 });
-                V::execute_block(transactions.clone(), &state_view).map_err(anyhow::Error::from)?
+                self.execute_vm_block(
+                    StateViewId::BlockExecution { block_id },
+                    parent_block_executed_trees.version(),
+                    &transactions,
+                    &state_view,
+                )?
             };
 
             let status: Vec<_> = vm_outputs
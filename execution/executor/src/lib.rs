@@ -7,19 +7,21 @@ use std::{
     collections::{hash_map, HashMap, HashSet},
     convert::TryFrom,
     marker::PhantomData,
+    path::PathBuf,
     sync::Arc,
 };
 
 use anyhow::{bail, ensure, format_err, Result};
 use fail::fail_point;
 
+use diem_config::config::{ExecutionConfig, ExecutionMode};
 use diem_crypto::{
     hash::{CryptoHash, EventAccumulatorHasher, TransactionAccumulatorHasher},
     HashValue,
 };
 use diem_infallible::{RwLock, RwLockReadGuard};
 use diem_logger::prelude::*;
-use diem_state_view::StateViewId;
+use diem_state_view::{StateView, StateViewId};
 use diem_types::{
     account_address::{AccountAddress, HashAccountAddress},
     account_state::AccountState,
@@ -36,7 +38,7 @@ use diem_types::{
     },
     write_set::{WriteOp, WriteSet, WriteSetMut},
 };
-use diem_vm::VMExecutor;
+use diem_vm::{CacheConfig, VMExecutor};
 use executor_types::{Error, ExecutedTrees, ProcessedVMOutput, ProofReader, TransactionData};
 use storage_interface::{
     default_protocol::DbReaderWriter, state_view::VerifiedStateView, TreeState,
@@ -44,7 +46,11 @@ use storage_interface::{
 
 use crate::{
     logging::{LogEntry, LogSchema},
-    metrics::DIEM_EXECUTOR_ERRORS,
+    metrics::{
+        DIEM_EXECUTOR_ERRORS, DIEM_EXECUTOR_SHADOW_EXECUTION_MISMATCHES,
+        DIEM_EXECUTOR_VM_EXECUTE_BLOCK_PARALLEL_SECONDS,
+    },
+    shadow_execution::{compare_outputs, MismatchDump},
     speculation_cache::SpeculationCache,
 };
 
@@ -56,6 +62,9 @@ mod logging;
 pub mod metrics;
 #[cfg(test)]
 mod mock_vm;
+pub mod shadow_execution;
+#[cfg(test)]
+mod shadow_execution_test;
 mod speculation_cache;
 
 mod block_executor_impl;
@@ -69,6 +78,9 @@ type SparseMerkleProof = diem_types::proof::SparseMerkleProof<AccountStateBlob>;
 pub struct Executor<PS, V> {
     db: DbReaderWriter,
     cache: RwLock<SpeculationCache>,
+    execution_mode: ExecutionMode,
+    code_cache_config: CacheConfig,
+    mismatch_dump_dir: PathBuf,
     phantom: PhantomData<(PS, V)>,
 }
 
@@ -83,6 +95,11 @@ where
 
     /// Constructs an `Executor`.
     pub fn new(db: DbReaderWriter) -> Self {
+        Self::new_with_config(db, &ExecutionConfig::default())
+    }
+
+    /// Constructs an `Executor` executing blocks in the mode selected by `config`.
+    pub fn new_with_config(db: DbReaderWriter, config: &ExecutionConfig) -> Self {
         let startup_info = db
             .reader
             .get_startup_info()
@@ -92,6 +109,13 @@ where
         Self {
             db,
             cache: RwLock::new(SpeculationCache::new_with_startup_info(startup_info)),
+            execution_mode: config.execution_mode,
+            code_cache_config: CacheConfig {
+                max_modules: config.code_cache.max_modules.unwrap_or(usize::MAX),
+                max_module_bytes: config.code_cache.max_module_bytes.unwrap_or(usize::MAX),
+                max_scripts: config.code_cache.max_scripts.unwrap_or(usize::MAX),
+            },
+            mismatch_dump_dir: config.mismatch_dump_dir(),
             phantom: PhantomData,
         }
     }
@@ -110,10 +134,76 @@ where
         Self {
             db,
             cache: RwLock::new(SpeculationCache::new_for_db_bootstrapping(tree_state)),
+            execution_mode: ExecutionMode::Sequential,
+            code_cache_config: CacheConfig::default(),
+            mismatch_dump_dir: PathBuf::new(),
             phantom: PhantomData,
         }
     }
 
+    /// Executes `transactions` with the VM, in the configured execution mode. In shadow mode,
+    /// the sequential outputs are returned and the parallel outputs are only compared to them.
+    /// Both executions, the comparison and any mismatch dump happen inline, before this returns.
+    fn execute_vm_block(
+        &self,
+        id: StateViewId,
+        base_version: Option<Version>,
+        transactions: &[Transaction],
+        state_view: &impl StateView,
+    ) -> Result<Vec<TransactionOutput>> {
+        match self.execution_mode {
+            ExecutionMode::Sequential => Ok(V::execute_block_with_cache_config(
+                transactions.to_vec(),
+                state_view,
+                self.code_cache_config,
+            )?),
+            ExecutionMode::Parallel => {
+                let _timer = DIEM_EXECUTOR_VM_EXECUTE_BLOCK_PARALLEL_SECONDS.start_timer();
+                Ok(V::execute_block_parallel_with_cache_config(
+                    transactions.to_vec(),
+                    state_view,
+                    self.code_cache_config,
+                )?)
+            }
+            ExecutionMode::Shadow => {
+                let sequential_outputs = V::execute_block_with_cache_config(
+                    transactions.to_vec(),
+                    state_view,
+                    self.code_cache_config,
+                );
+                let parallel_outputs = {
+                    let _timer = DIEM_EXECUTOR_VM_EXECUTE_BLOCK_PARALLEL_SECONDS.start_timer();
+                    V::execute_block_parallel_with_cache_config(
+                        transactions.to_vec(),
+                        state_view,
+                        self.code_cache_config,
+                    )
+                };
+                if let Some(mismatch) = compare_outputs(&sequential_outputs, &parallel_outputs) {
+                    DIEM_EXECUTOR_SHADOW_EXECUTION_MISMATCHES
+                        .with_label_values(&[mismatch.kind.as_str()])
+                        .inc();
+                    let dump = MismatchDump {
+                        base_version,
+                        transactions: transactions.to_vec(),
+                        sequential_outputs,
+                        parallel_outputs,
+                    };
+                    let dump_path = dump.write_to_dir(&self.mismatch_dump_dir, id);
+                    error!(
+                        LogSchema::new(LogEntry::ShadowExecution),
+                        mismatch = ?mismatch,
+                        dump_path = ?dump_path,
+                        "Sequential and parallel execution outputs differ"
+                    );
+                    Ok(dump.sequential_outputs?)
+                } else {
+                    Ok(sequential_outputs?)
+                }
+            }
+        }
+    }
+
     /// In case there is a new LI to be added to a LedgerStore, verify and return it.
     fn find_chunk_li(
         verified_target_li: LedgerInfoWithSignatures,
@@ -513,7 +603,12 @@ where
             );
             outputs
         } else {
-            V::execute_block(transactions.clone(), &state_view)?
+            self.execute_vm_block(
+                StateViewId::ChunkExecution { first_version },
+                read_lock.synced_trees().version(),
+                &transactions,
+                &state_view,
+            )?
         };
 
         // Since other validators have committed these transactions, their status should all be
@@ -49,5 +49,6 @@ impl LogSchema {
 pub enum LogEntry {
     ChunkExecutor,
     BlockExecutor,
+    ShadowExecution,
     SpeculationCache,
 }
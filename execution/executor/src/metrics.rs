@@ -1,7 +1,10 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use diem_metrics::{register_histogram, register_int_counter, Histogram, IntCounter};
+use diem_metrics::{
+    register_histogram, register_int_counter, register_int_counter_vec, Histogram, IntCounter,
+    IntCounterVec,
+};
 use once_cell::sync::Lazy;
 
 pub static DIEM_EXECUTOR_EXECUTE_AND_COMMIT_CHUNK_SECONDS: Lazy<Histogram> = Lazy::new(|| {
@@ -34,6 +37,28 @@ pub static DIEM_EXECUTOR_VM_EXECUTE_BLOCK_SECONDS: Lazy<Histogram> = Lazy::new(|
     .unwrap()
 });
 
+pub static DIEM_EXECUTOR_VM_EXECUTE_BLOCK_PARALLEL_SECONDS: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        // metric name
+        "diem_executor_vm_execute_block_parallel_seconds",
+        // metric description
+        "The time spent in seconds of parallel vm block execution in Diem executor"
+    )
+    .unwrap()
+});
+
+pub static DIEM_EXECUTOR_SHADOW_EXECUTION_MISMATCHES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        // metric name
+        "diem_executor_shadow_execution_mismatches",
+        // metric description
+        "Number of blocks whose sequential and parallel execution outputs differ, by kind",
+        // metric labels (dimensions)
+        &["kind"]
+    )
+    .unwrap()
+});
+
 pub static DIEM_EXECUTOR_ERRORS: Lazy<IntCounter> = Lazy::new(|| {
     register_int_counter!("diem_executor_error_total", "Cumulative number of errors").unwrap()
 });
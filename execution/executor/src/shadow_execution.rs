@@ -0,0 +1,125 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Support for shadow execution, where every block is executed both sequentially and in parallel.
+//! The sequential outputs are the ones used, while the parallel outputs are only compared against
+//! them so that parallel execution can be validated on production nodes before being relied upon.
+
+use anyhow::Result;
+use diem_state_view::StateViewId;
+use diem_types::transaction::{Transaction, TransactionOutput, Version};
+use move_core_types::vm_status::VMStatus;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The part of the outputs in which sequential and parallel execution first disagreed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MismatchKind {
+    /// Only one of the executions failed, or both failed with different errors.
+    Error,
+    /// The executions returned a different number of outputs.
+    NumOutputs,
+    Status,
+    GasUsed,
+    WriteSet,
+    Events,
+}
+
+impl MismatchKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MismatchKind::Error => "error",
+            MismatchKind::NumOutputs => "num_outputs",
+            MismatchKind::Status => "status",
+            MismatchKind::GasUsed => "gas_used",
+            MismatchKind::WriteSet => "write_set",
+            MismatchKind::Events => "events",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct OutputMismatch {
+    pub kind: MismatchKind,
+    /// The index (in the block) of the first transaction whose outputs differ, if any.
+    pub txn_index: Option<usize>,
+}
+
+/// Compares the results of sequential and parallel execution and returns the first difference
+/// found, or None if they are identical.
+pub fn compare_outputs(
+    sequential: &Result<Vec<TransactionOutput>, VMStatus>,
+    parallel: &Result<Vec<TransactionOutput>, VMStatus>,
+) -> Option<OutputMismatch> {
+    let (sequential, parallel) = match (sequential, parallel) {
+        (Ok(sequential), Ok(parallel)) => (sequential, parallel),
+        (Err(sequential), Err(parallel)) if sequential == parallel => return None,
+        _ => {
+            return Some(OutputMismatch {
+                kind: MismatchKind::Error,
+                txn_index: None,
+            })
+        }
+    };
+    if sequential.len() != parallel.len() {
+        return Some(OutputMismatch {
+            kind: MismatchKind::NumOutputs,
+            txn_index: None,
+        });
+    }
+
+    sequential.iter().zip(parallel.iter()).enumerate().find_map(
+        |(txn_index, (sequential, parallel))| {
+            let kind = if sequential.status() != parallel.status() {
+                MismatchKind::Status
+            } else if sequential.gas_used() != parallel.gas_used() {
+                MismatchKind::GasUsed
+            } else if sequential.write_set() != parallel.write_set() {
+                MismatchKind::WriteSet
+            } else if sequential.events() != parallel.events() {
+                MismatchKind::Events
+            } else {
+                return None;
+            };
+            Some(OutputMismatch {
+                kind,
+                txn_index: Some(txn_index),
+            })
+        },
+    )
+}
+
+/// Everything needed to reproduce a mismatch: the transactions, the version of the state they
+/// were executed on, and the results of both executions.
+#[derive(Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct MismatchDump {
+    pub base_version: Option<Version>,
+    pub transactions: Vec<Transaction>,
+    pub sequential_outputs: Result<Vec<TransactionOutput>, VMStatus>,
+    pub parallel_outputs: Result<Vec<TransactionOutput>, VMStatus>,
+}
+
+impl MismatchDump {
+    /// Writes the dump (BCS encoded) to a new file in `dir`, and returns the path of the file.
+    pub fn write_to_dir(&self, dir: &Path, id: StateViewId) -> Result<PathBuf> {
+        let file_name = match id {
+            StateViewId::BlockExecution { block_id } => format!("block_{:x}.bcs", block_id),
+            StateViewId::ChunkExecution { first_version } => {
+                format!("chunk_{}.bcs", first_version)
+            }
+            _ => format!("version_{}.bcs", self.base_version.map_or(0, |v| v + 1)),
+        };
+        let path = dir.join(file_name);
+
+        fs::create_dir_all(dir)?;
+        fs::write(&path, bcs::to_bytes(self)?)?;
+        Ok(path)
+    }
+
+    pub fn read_from_file(path: &Path) -> Result<Self> {
+        Ok(bcs::from_bytes(&fs::read(path)?)?)
+    }
+}
@@ -0,0 +1,109 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::shadow_execution::{compare_outputs, MismatchDump, MismatchKind, OutputMismatch};
+use diem_crypto::HashValue;
+use diem_state_view::StateViewId;
+use diem_temppath::TempPath;
+use diem_types::{
+    transaction::{ChangeSet, Transaction, TransactionOutput, TransactionStatus, WriteSetPayload},
+    vm_status::{KeptVMStatus, StatusCode, VMStatus},
+    write_set::WriteSet,
+};
+
+fn output(gas_used: u64) -> TransactionOutput {
+    TransactionOutput::new(
+        WriteSet::default(),
+        vec![],
+        gas_used,
+        TransactionStatus::Keep(KeptVMStatus::Executed),
+    )
+}
+
+#[test]
+fn test_identical_outputs() {
+    let outputs = Ok(vec![output(1), output(2)]);
+    assert_eq!(compare_outputs(&outputs, &outputs.clone()), None);
+
+    let error = Err(VMStatus::Error(
+        StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR,
+    ));
+    assert_eq!(compare_outputs(&error, &error.clone()), None);
+}
+
+#[test]
+fn test_mismatched_outputs() {
+    let sequential = Ok(vec![output(1), output(2), output(3)]);
+
+    let parallel = Ok(vec![output(1), output(5), output(3)]);
+    assert_eq!(
+        compare_outputs(&sequential, &parallel),
+        Some(OutputMismatch {
+            kind: MismatchKind::GasUsed,
+            txn_index: Some(1),
+        })
+    );
+
+    let discarded = TransactionOutput::new(
+        WriteSet::default(),
+        vec![],
+        3,
+        TransactionStatus::Discard(StatusCode::UNKNOWN_VALIDATION_STATUS),
+    );
+    let parallel = Ok(vec![output(1), output(2), discarded]);
+    assert_eq!(
+        compare_outputs(&sequential, &parallel),
+        Some(OutputMismatch {
+            kind: MismatchKind::Status,
+            txn_index: Some(2),
+        })
+    );
+
+    let parallel = Ok(vec![output(1), output(2)]);
+    assert_eq!(
+        compare_outputs(&sequential, &parallel),
+        Some(OutputMismatch {
+            kind: MismatchKind::NumOutputs,
+            txn_index: None,
+        })
+    );
+
+    let parallel = Err(VMStatus::Error(
+        StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR,
+    ));
+    assert_eq!(
+        compare_outputs(&sequential, &parallel),
+        Some(OutputMismatch {
+            kind: MismatchKind::Error,
+            txn_index: None,
+        })
+    );
+}
+
+#[test]
+fn test_mismatch_dump_round_trip() {
+    let dir = TempPath::new();
+    let dump = MismatchDump {
+        base_version: Some(10),
+        transactions: vec![Transaction::GenesisTransaction(WriteSetPayload::Direct(
+            ChangeSet::new(WriteSet::default(), vec![]),
+        ))],
+        sequential_outputs: Ok(vec![output(1)]),
+        parallel_outputs: Ok(vec![output(2)]),
+    };
+
+    let block_id = HashValue::random();
+    let path = dump
+        .write_to_dir(dir.path(), StateViewId::BlockExecution { block_id })
+        .unwrap();
+    assert_eq!(path, dir.path().join(format!("block_{:x}.bcs", block_id)));
+    assert_eq!(MismatchDump::read_from_file(&path).unwrap(), dump);
+
+    let path = dump
+        .write_to_dir(
+            dir.path(),
+            StateViewId::ChunkExecution { first_version: 11 },
+        )
+        .unwrap();
+    assert_eq!(path, dir.path().join("chunk_11.bcs"));
+}
@@ -473,6 +473,15 @@ impl SourceMap {
             .ok_or_else(|| format_err!("Unable to get function source map"))
     }
 
+    /// Returns the source maps of all the functions, in order of definition.
+    pub fn function_source_maps(
+        &self,
+    ) -> impl Iterator<Item = (FunctionDefinitionIndex, &FunctionSourceMap)> {
+        self.function_map
+            .iter()
+            .map(|(idx, function_source_map)| (FunctionDefinitionIndex(*idx), function_source_map))
+    }
+
     pub fn get_struct_source_map(
         &self,
         struct_def_idx: StructDefinitionIndex,
@@ -5,9 +5,18 @@ module DiemFramework::DiemVMConfig {
     use DiemFramework::DiemTimestamp;
     use DiemFramework::Roles;
     use Std::Errors;
+    use Std::Vector;
 
     /// The provided gas constants were inconsistent.
     const EGAS_CONSTANT_INCONSISTENCY: u64 = 0;
+    /// The provided native schedule does not have exactly one cost per native function.
+    const ENATIVE_SCHEDULE_INVALID: u64 = 1;
+
+    /// The number of native functions charged by the VM, i.e., `NUMBER_OF_NATIVE_FUNCTIONS` in
+    /// `move-binary-format`. It must be raised with every native function added to the VM.
+    const NUMBER_OF_NATIVE_FUNCTIONS: u64 = 22;
+    /// The size of a BCS-serialized native function cost, made of two `u64`s.
+    const NATIVE_COST_SIZE: u64 = 16;
 
     /// The struct to hold config data needed to operate the DiemVM.
     struct DiemVMConfig has copy, drop, store {
@@ -208,6 +217,53 @@ module DiemFramework::DiemVMConfig {
         ensures old(DiemConfig::spec_has_config()) == DiemConfig::spec_has_config();
     }
 
+    /// Replaces the native function schedule, i.e., the BCS-serialized costs of the native
+    /// functions ordered by their native cost index in the VM.
+    ///
+    /// This is how a chain gets the costs of natives added after its genesis: the VM reports an
+    /// invariant violation for a native without a cost. E.g., a chain created with the 18 natives
+    /// preceding `String` must publish a schedule which also has the costs of `STRING_CHECK_UTF8`,
+    /// `STRING_IS_CHAR_BOUNDARY`, `STRING_SUB_STRING` and `STRING_INDEX_OF` (indices 18 to 21)
+    /// before any transaction uses `Std::String`.
+    public fun set_native_schedule(dr_account: &signer, native_schedule: vector<u8>) {
+        DiemTimestamp::assert_operating();
+        Roles::assert_diem_root(dr_account);
+        // The schedule is a vector of `NUMBER_OF_NATIVE_FUNCTIONS` costs: its length prefix fits
+        // in a single byte.
+        assert(
+            Vector::length(&native_schedule) == 1 + NUMBER_OF_NATIVE_FUNCTIONS * NATIVE_COST_SIZE,
+            Errors::invalid_argument(ENATIVE_SCHEDULE_INVALID)
+        );
+        assert(
+            (*Vector::borrow(&native_schedule, 0) as u64) == NUMBER_OF_NATIVE_FUNCTIONS,
+            Errors::invalid_argument(ENATIVE_SCHEDULE_INVALID)
+        );
+
+        let config = DiemConfig::get<DiemVMConfig>();
+        config.gas_schedule.native_schedule = native_schedule;
+
+        DiemConfig::set(dr_account, config);
+    }
+    spec set_native_schedule {
+        include DiemTimestamp::AbortsIfNotOperating;
+        /// No one can update DiemVMConfig except for the Diem Root account [[H11]][PERMISSION].
+        include Roles::AbortsIfNotDiemRoot{account: dr_account};
+        include DiemConfig::SetAbortsIf<DiemVMConfig>{account: dr_account };
+        aborts_if len(native_schedule) != 1 + NUMBER_OF_NATIVE_FUNCTIONS * NATIVE_COST_SIZE
+            with Errors::INVALID_ARGUMENT;
+        aborts_if native_schedule[0] != NUMBER_OF_NATIVE_FUNCTIONS with Errors::INVALID_ARGUMENT;
+        let config = DiemConfig::spec_get_config<DiemVMConfig>();
+        ensures DiemConfig::spec_is_published<DiemVMConfig>();
+        ensures DiemConfig::get<DiemVMConfig>() == DiemVMConfig {
+            gas_schedule: GasSchedule {
+                instruction_schedule: config.gas_schedule.instruction_schedule,
+                native_schedule,
+                gas_constants: config.gas_schedule.gas_constants,
+            }
+        };
+        ensures old(DiemConfig::spec_has_config()) == DiemConfig::spec_has_config();
+    }
+
     spec module { } // Switch documentation context to module level.
 
     /// # Initialization
@@ -237,6 +293,6 @@ module DiemFramework::DiemVMConfig {
                 old(global<DiemConfig<DiemVMConfig>>(@DiemRoot));
     }
     spec module {
-        apply DiemVMConfigRemainsSame to * except set_gas_constants;
+        apply DiemVMConfigRemainsSame to * except set_gas_constants, set_native_schedule;
     }
 }
@@ -104,6 +104,42 @@ module DiemFramework::SystemAdministrationScripts {
         )
     }
 
+    /// # Summary
+    /// Updates the native function gas schedule stored on chain and used by the VM for gas
+    /// metering. This transaction can only be sent from the Diem Root account.
+    ///
+    /// # Technical Description
+    /// Replaces the native schedule of the `DiemVMConfig` on-chain config and emits a
+    /// `DiemConfig::NewEpochEvent` to trigger a reconfiguration of the system. This is how chains
+    /// created before a native function was added get a cost for it: the VM cannot run a native
+    /// without an entry in this schedule. See `DiemVMConfig::set_native_schedule` for the upgrade of
+    /// a chain created before the `String` natives.
+    ///
+    /// # Parameters
+    /// | Name              | Type         | Description                                                                                         |
+    /// | ------            | ------       | -------------                                                                                       |
+    /// | `dr_account`      | `signer`     | Signer of the sending account. Must be the Diem Root account.                                       |
+    /// | `sliding_nonce`   | `u64`        | The `sliding_nonce` (see: `SlidingNonce`) to be used for this transaction.                          |
+    /// | `native_schedule` | `vector<u8>` | The BCS-serialized costs of the native functions, ordered by the native cost index used by the VM. |
+    ///
+    /// # Common Abort Conditions
+    /// | Error Category             | Error Reason                             | Description                                                                                |
+    /// | ----------------           | --------------                           | -------------                                                                              |
+    /// | `Errors::NOT_PUBLISHED`    | `SlidingNonce::ESLIDING_NONCE`           | A `SlidingNonce` resource is not published under `account`.                                |
+    /// | `Errors::INVALID_ARGUMENT` | `SlidingNonce::ENONCE_TOO_OLD`           | The `sliding_nonce` is too old and it's impossible to determine if it's duplicated or not. |
+    /// | `Errors::INVALID_ARGUMENT` | `SlidingNonce::ENONCE_TOO_NEW`           | The `sliding_nonce` is too far in the future.                                              |
+    /// | `Errors::INVALID_ARGUMENT` | `SlidingNonce::ENONCE_ALREADY_RECORDED`  | The `sliding_nonce` has been previously recorded.                                          |
+    /// | `Errors::REQUIRES_ADDRESS` | `CoreAddresses::EDIEM_ROOT`              | `account` is not the Diem Root account.                                                    |
+    /// | `Errors::INVALID_ARGUMENT` | `DiemVMConfig::ENATIVE_SCHEDULE_INVALID` | `native_schedule` does not have exactly one cost per native function of the VM.            |
+    public(script) fun set_native_schedule(
+        dr_account: signer,
+        sliding_nonce: u64,
+        native_schedule: vector<u8>,
+    ) {
+        SlidingNonce::record_nonce_or_abort(&dr_account, sliding_nonce);
+        DiemVMConfig::set_native_schedule(&dr_account, native_schedule)
+    }
+
     ///  # Summary
     /// Initializes the Diem consensus config that is stored on-chain.  This
     /// transaction can only be sent from the Diem Root account.
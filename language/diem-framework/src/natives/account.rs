@@ -23,7 +23,7 @@ pub fn native_create_signer(
     debug_assert!(arguments.len() == 1);
 
     let address = pop_arg!(arguments, AccountAddress);
-    let cost = native_gas(context.cost_table(), NativeCostIndex::CREATE_SIGNER, 0);
+    let cost = native_gas(context.cost_table(), NativeCostIndex::CREATE_SIGNER, 0)?;
     Ok(NativeResult::ok(cost, smallvec![Value::signer(address)]))
 }
 
@@ -37,6 +37,6 @@ pub fn native_destroy_signer(
     debug_assert!(ty_args.is_empty());
     debug_assert!(arguments.len() == 1);
 
-    let cost = native_gas(context.cost_table(), NativeCostIndex::DESTROY_SIGNER, 0);
+    let cost = native_gas(context.cost_table(), NativeCostIndex::DESTROY_SIGNER, 0)?;
     Ok(NativeResult::ok(cost, smallvec![]))
 }
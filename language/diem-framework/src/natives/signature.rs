@@ -28,7 +28,7 @@ pub fn native_ed25519_publickey_validation(
         context.cost_table(),
         NativeCostIndex::ED25519_VALIDATE_KEY,
         key_bytes.len(),
-    );
+    )?;
 
     // This deserialization performs point-on-curve and small subgroup checks
     let valid = ed25519::Ed25519PublicKey::try_from(&key_bytes[..]).is_ok();
@@ -51,7 +51,7 @@ pub fn native_ed25519_signature_verification(
         context.cost_table(),
         NativeCostIndex::ED25519_VERIFY,
         msg.len(),
-    );
+    )?;
 
     let sig = match ed25519::Ed25519Signature::try_from(signature.as_slice()) {
         Ok(sig) => sig,
@@ -0,0 +1,57 @@
+#[test_only]
+module DiemFramework::DiemVMConfigTests {
+    use DiemFramework::DiemVMConfig;
+    use DiemFramework::Genesis;
+    use Std::Vector;
+
+    // A serialized native schedule with `count` costs, and `len` costs in its length prefix.
+    fun native_schedule(len: u8, count: u64): vector<u8> {
+        let native_schedule = Vector::singleton(len);
+        let i = 0;
+        while (i < count * 16) {
+            Vector::push_back(&mut native_schedule, 0);
+            i = i + 1;
+        };
+        native_schedule
+    }
+
+    #[test(account = @0x1)]
+    #[expected_failure(abort_code = 257)]
+    fun set_native_schedule_before_genesis(account: signer) {
+        DiemVMConfig::set_native_schedule(&account, native_schedule(22, 22));
+    }
+
+    #[test(account = @0x2, tc = @TreasuryCompliance, dr = @DiemRoot)]
+    #[expected_failure(abort_code = 2)]
+    fun invalid_native_schedule_setting_address(account: signer, tc: signer, dr: signer) {
+        Genesis::setup(&dr, &tc);
+        DiemVMConfig::set_native_schedule(&account, native_schedule(22, 22));
+    }
+
+    #[test(tc = @TreasuryCompliance, dr = @DiemRoot)]
+    #[expected_failure(abort_code = 263)]
+    fun set_native_schedule_missing_costs(tc: signer, dr: signer) {
+        Genesis::setup(&dr, &tc);
+        DiemVMConfig::set_native_schedule(&dr, native_schedule(18, 18));
+    }
+
+    #[test(tc = @TreasuryCompliance, dr = @DiemRoot)]
+    #[expected_failure(abort_code = 263)]
+    fun set_native_schedule_inconsistent_length(tc: signer, dr: signer) {
+        Genesis::setup(&dr, &tc);
+        DiemVMConfig::set_native_schedule(&dr, native_schedule(21, 22));
+    }
+
+    #[test(tc = @TreasuryCompliance, dr = @DiemRoot)]
+    #[expected_failure(abort_code = 263)]
+    fun set_native_schedule_empty(tc: signer, dr: signer) {
+        Genesis::setup(&dr, &tc);
+        DiemVMConfig::set_native_schedule(&dr, x"");
+    }
+
+    #[test(tc = @TreasuryCompliance, dr = @DiemRoot)]
+    fun set_native_schedule(tc: signer, dr: signer) {
+        Genesis::setup(&dr, &tc);
+        DiemVMConfig::set_native_schedule(&dr, native_schedule(22, 22));
+    }
+}
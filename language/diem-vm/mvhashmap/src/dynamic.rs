@@ -0,0 +1,101 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::Version;
+use std::{
+    collections::{btree_map::BTreeMap, hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    sync::{Arc, RwLock},
+};
+
+/// The number of times a transaction has been (re-)executed.
+pub type Incarnation = usize;
+
+// The number of shards to split the keys into. Each shard is protected by its
+// own lock, so this bounds the contention between concurrent writers.
+const NUM_SHARDS: usize = 256;
+
+// A single shard of the map: key -> version -> (incarnation, value). Values are
+// shared (rather than cloned) with readers.
+type Shard<K, V> = RwLock<HashMap<K, BTreeMap<Version, (Incarnation, Arc<V>)>>>;
+
+/// A multi-version hash map where entries are created on demand (i.e., there's
+/// no need to know the write set of each transaction upfront).
+//
+//  Unlike `MVHashMap`, entries can be written any number of times, by any
+//  thread: each write is tagged with the incarnation of the transaction that
+//  performed it. This allows readers to record exactly which write they
+//  observed, and to later validate that the write is still the latest one.
+//
+pub struct DynamicMVHashMap<K, V> {
+    shards: Vec<Shard<K, V>>,
+}
+
+impl<K: Hash + Clone + Eq, V> DynamicMVHashMap<K, V> {
+    pub fn new() -> Self {
+        Self {
+            shards: (0..NUM_SHARDS)
+                .map(|_| RwLock::new(HashMap::new()))
+                .collect(),
+        }
+    }
+
+    fn get_shard(&self, key: &K) -> &Shard<K, V> {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        &self.shards[(hasher.finish() as usize) % NUM_SHARDS]
+    }
+
+    /// Write `data` to `key` at `version`, overwriting any value written by a
+    /// previous incarnation of the same transaction.
+    pub fn write(&self, key: &K, version: Version, incarnation: Incarnation, data: V) {
+        let mut shard = self.get_shard(key).write().unwrap();
+        shard
+            .entry(key.clone())
+            .or_default()
+            .insert(version, (incarnation, Arc::new(data)));
+    }
+
+    /// Remove the value (if any) written to `key` at `version`. This is used
+    /// when a re-executed transaction no longer writes to `key`.
+    pub fn remove(&self, key: &K, version: Version) {
+        let mut shard = self.get_shard(key).write().unwrap();
+        if let Some(tree) = shard.get_mut(key) {
+            tree.remove(&version);
+            if tree.is_empty() {
+                shard.remove(key);
+            }
+        }
+    }
+
+    /// Get the value of `key` written by the highest transaction below `version`,
+    /// together with the version and incarnation of the writer. Returns None if
+    /// no transaction below `version` has written to `key`.
+    pub fn read(&self, key: &K, version: Version) -> Option<(Version, Incarnation, Arc<V>)> {
+        let shard = self.get_shard(key).read().unwrap();
+        shard
+            .get(key)?
+            .range(0..version)
+            .next_back()
+            .map(|(entry_version, (incarnation, data))| {
+                (*entry_version, *incarnation, Arc::clone(data))
+            })
+    }
+
+    /// Get the version and incarnation of the highest transaction below
+    /// `version` that has written to `key` (if any).
+    pub fn latest_writer(&self, key: &K, version: Version) -> Option<(Version, Incarnation)> {
+        let shard = self.get_shard(key).read().unwrap();
+        shard
+            .get(key)?
+            .range(0..version)
+            .next_back()
+            .map(|(entry_version, (incarnation, _))| (*entry_version, *incarnation))
+    }
+}
+
+impl<K: Hash + Clone + Eq, V> Default for DynamicMVHashMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -9,9 +9,12 @@ use std::{
     hash::Hash,
 };
 
+mod dynamic;
 #[cfg(test)]
 mod unit_tests;
 
+pub use dynamic::{DynamicMVHashMap, Incarnation};
+
 /// A structure that holds placeholders for each write to the database
 //
 //  The structure is created by one thread creating the scheduling, and
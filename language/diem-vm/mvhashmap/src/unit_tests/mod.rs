@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use super::*;
+use std::sync::Arc;
 
 mod proptest_types;
 
@@ -56,3 +57,40 @@ fn create_write_read_placeholder_struct() {
     let r1 = mvtbl.read(&ap2, 25);
     assert_eq!(Ok(&Some(vec![0, 0, 0])), r1);
 }
+
+#[test]
+fn dynamic_write_read_remove() {
+    let ap1 = b"/foo/b".to_vec();
+    let ap2 = b"/foo/c".to_vec();
+
+    let mvtbl = DynamicMVHashMap::new();
+
+    // Reads of keys that haven't been written go to the DB
+    assert_eq!(None, mvtbl.read(&ap1, 5));
+
+    // Reads at a version return the previous versions, not this version
+    mvtbl.write(&ap1, 10, 0, vec![0, 0, 0]);
+    assert_eq!(None, mvtbl.read(&ap1, 10));
+    assert_eq!(Some((10, 0, Arc::new(vec![0, 0, 0]))), mvtbl.read(&ap1, 15));
+    assert_eq!(Some((10, 0)), mvtbl.latest_writer(&ap1, 15));
+    assert_eq!(None, mvtbl.latest_writer(&ap2, 15));
+
+    // Reads return the highest write below the version
+    mvtbl.write(&ap1, 12, 0, vec![1]);
+    assert_eq!(Some((12, 0, Arc::new(vec![1]))), mvtbl.read(&ap1, 15));
+    assert_eq!(Some((10, 0, Arc::new(vec![0, 0, 0]))), mvtbl.read(&ap1, 12));
+
+    // Re-executions overwrite the entry and bump the incarnation
+    mvtbl.write(&ap1, 12, 1, vec![2]);
+    assert_eq!(Some((12, 1, Arc::new(vec![2]))), mvtbl.read(&ap1, 15));
+
+    // Removed entries are no longer visible
+    mvtbl.remove(&ap1, 12);
+    assert_eq!(Some((10, 0)), mvtbl.latest_writer(&ap1, 15));
+    mvtbl.remove(&ap1, 10);
+    assert_eq!(None, mvtbl.read(&ap1, 15));
+
+    // Removing missing entries is a no-op
+    mvtbl.remove(&ap2, 10);
+    assert_eq!(None, mvtbl.read(&ap2, 15));
+}
@@ -8,39 +8,109 @@ use crate::{
     task::{ExecutionStatus, ExecutorTask, ReadWriteSetInferencer, Transaction, TransactionOutput},
 };
 use anyhow::{bail, Result as AResult};
-use mvhashmap::{MVHashMap, Version};
+use mvhashmap::{DynamicMVHashMap, Incarnation, MVHashMap, Version};
 use num_cpus;
 use rayon::{prelude::*, scope};
 use std::{
     cmp::{max, min},
     hash::Hash,
     marker::PhantomData,
+    ops::Deref,
     sync::{
         atomic::{AtomicBool, Ordering},
-        Arc,
+        Arc, Mutex,
     },
 };
 
+/// A single read performed by a transaction during optimistic execution: the
+/// key and the version and incarnation of the write that was observed (None if
+/// the value was read from storage).
+pub(crate) struct ReadDescriptor<K> {
+    pub key: K,
+    pub writer: Option<(Version, Incarnation)>,
+}
+
+enum MapView<'a, K, V> {
+    // Reads from a map built from the inferred write sets, blocking on
+    // dependencies that haven't been computed yet.
+    Static {
+        map: &'a MVHashMap<K, V>,
+        scheduler: &'a Scheduler,
+        has_unexpected_read: AtomicBool,
+    },
+    // Reads whatever is currently in the map, recording each read so that it
+    // can be validated after execution.
+    Optimistic {
+        map: &'a DynamicMVHashMap<K, V>,
+        reads: Mutex<Vec<ReadDescriptor<K>>>,
+    },
+}
+
+/// A value read through an `MVHashMapView`: either borrowed from the map built from the
+/// inferred write sets, or shared with the map used by optimistic execution.
+pub enum ReadValue<'a, V> {
+    Borrowed(&'a V),
+    Shared(Arc<V>),
+}
+
+impl<'a, V> Deref for ReadValue<'a, V> {
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        match self {
+            ReadValue::Borrowed(value) => value,
+            ReadValue::Shared(value) => value,
+        }
+    }
+}
+
 pub struct MVHashMapView<'a, K, V> {
-    map: &'a MVHashMap<K, V>,
     version: Version,
-    scheduler: &'a Scheduler,
-    has_unexpected_read: AtomicBool,
+    view: MapView<'a, K, V>,
 }
 
 impl<'a, K: Hash + Clone + Eq, V> MVHashMapView<'a, K, V> {
-    pub fn read(&self, key: &K) -> AResult<Option<&V>> {
-        match self.map.read(key, self.version) {
-            Ok(v) => Ok(Some(v)),
-            Err(None) => Ok(None),
-            Err(Some(dep_idx)) => {
-                // Don't start execution transaction `self.version` until `dep_idx` is computed.
-                if !self.scheduler.add_dependency(self.version, dep_idx) {
-                    // dep_idx is already executed, push `self.version` to ready queue.
-                    self.scheduler.add_transaction(self.version);
+    pub(crate) fn new_optimistic(map: &'a DynamicMVHashMap<K, V>, version: Version) -> Self {
+        Self {
+            version,
+            view: MapView::Optimistic {
+                map,
+                reads: Mutex::new(vec![]),
+            },
+        }
+    }
+
+    pub fn read(&self, key: &K) -> AResult<Option<ReadValue<V>>> {
+        match &self.view {
+            MapView::Static {
+                map,
+                scheduler,
+                has_unexpected_read,
+            } => match map.read(key, self.version) {
+                Ok(v) => Ok(Some(ReadValue::Borrowed(v))),
+                Err(None) => Ok(None),
+                Err(Some(dep_idx)) => {
+                    // Don't start execution transaction `self.version` until `dep_idx` is computed.
+                    if !scheduler.add_dependency(self.version, dep_idx) {
+                        // dep_idx is already executed, push `self.version` to ready queue.
+                        scheduler.add_transaction(self.version);
+                    }
+                    has_unexpected_read.fetch_or(true, Ordering::Relaxed);
+                    bail!("Read dependency is not computed, retry later")
                 }
-                self.has_unexpected_read.fetch_or(true, Ordering::Relaxed);
-                bail!("Read dependency is not computed, retry later")
+            },
+            MapView::Optimistic { map, reads } => {
+                let (writer, value) = match map.read(key, self.version) {
+                    Some((version, incarnation, value)) => {
+                        (Some((version, incarnation)), Some(ReadValue::Shared(value)))
+                    }
+                    None => (None, None),
+                };
+                reads.lock().unwrap().push(ReadDescriptor {
+                    key: key.clone(),
+                    writer,
+                });
+                Ok(value)
             }
         }
     }
@@ -50,7 +120,21 @@ impl<'a, K: Hash + Clone + Eq, V> MVHashMapView<'a, K, V> {
     }
 
     pub fn has_unexpected_read(&self) -> bool {
-        self.has_unexpected_read.load(Ordering::Relaxed)
+        match &self.view {
+            MapView::Static {
+                has_unexpected_read,
+                ..
+            } => has_unexpected_read.load(Ordering::Relaxed),
+            MapView::Optimistic { .. } => false,
+        }
+    }
+
+    /// Returns all reads recorded by an optimistic view.
+    pub(crate) fn take_reads(self) -> Vec<ReadDescriptor<K>> {
+        match self.view {
+            MapView::Static { .. } => vec![],
+            MapView::Optimistic { reads, .. } => reads.into_inner().unwrap(),
+        }
     }
 }
 
@@ -159,10 +243,12 @@ where
 
                         // Process the output of a transaction
                         let view = MVHashMapView {
-                            map: &versioned_data_cache,
                             version: idx,
-                            scheduler: &scheduler,
-                            has_unexpected_read: AtomicBool::new(false),
+                            view: MapView::Static {
+                                map: &versioned_data_cache,
+                                scheduler: &scheduler,
+                                has_unexpected_read: AtomicBool::new(false),
+                            },
                         };
                         let execute_result = task.execute_transaction(&view, txn);
                         if view.has_unexpected_read() {
@@ -3,6 +3,7 @@
 
 pub mod errors;
 pub mod executor;
+pub mod optimistic_executor;
 mod outcome_array;
 #[cfg(any(test, feature = "fuzzing"))]
 pub mod proptest_types;
@@ -0,0 +1,222 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    errors::*,
+    executor::{MVHashMapView, ReadDescriptor},
+    task::{ExecutionStatus, ExecutorTask, Transaction, TransactionOutput},
+};
+use mvhashmap::{DynamicMVHashMap, Incarnation, Version};
+use num_cpus;
+use rayon::scope;
+use std::{
+    cmp::{max, min},
+    collections::HashSet,
+    marker::PhantomData,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+/// The result of the latest execution (i.e., incarnation) of a transaction.
+struct ExecutionRecord<K, O, E> {
+    incarnation: Incarnation,
+    reads: Vec<ReadDescriptor<K>>,
+    keys_written: Vec<K>,
+    status: Option<ExecutionStatus<O, E>>,
+}
+
+impl<K, O, E> ExecutionRecord<K, O, E> {
+    fn new() -> Self {
+        Self {
+            incarnation: 0,
+            reads: vec![],
+            keys_written: vec![],
+            status: None,
+        }
+    }
+}
+
+type Records<T, E> = Vec<
+    Mutex<
+        ExecutionRecord<
+            <T as Transaction>::Key,
+            <E as ExecutorTask>::Output,
+            <E as ExecutorTask>::Error,
+        >,
+    >,
+>;
+
+/// A parallel transaction executor that doesn't require the read and write sets of the
+/// transactions upfront, i.e., dependencies are discovered dynamically.
+///
+/// All transactions are first executed optimistically (in parallel) against a multi-version
+/// data structure, recording the version and incarnation of every value read. Transactions are
+/// then validated in order: if any value read by a transaction has since been overwritten (or
+/// removed) by a lower transaction, the transaction is re-executed. As all lower transactions
+/// have been validated at that point, a single re-execution is always sufficient.
+///
+/// Every incarnation is executed by a fresh task: a task may cache what it read (e.g. the code
+/// loaded by a VM) without going through the `MVHashMapView` again, and such reads would escape
+/// validation if a task was reused across transactions.
+pub struct OptimisticTransactionExecutor<T: Transaction, E: ExecutorTask> {
+    num_cpus: usize,
+    phantom: PhantomData<(T, E)>,
+}
+
+impl<T, E> OptimisticTransactionExecutor<T, E>
+where
+    T: Transaction,
+    E: ExecutorTask<T = T>,
+{
+    pub fn new() -> Self {
+        Self {
+            num_cpus: num_cpus::get(),
+            phantom: PhantomData,
+        }
+    }
+
+    pub fn execute_transactions_parallel(
+        &self,
+        task_initial_arguments: E::Argument,
+        signature_verified_block: Vec<T>,
+    ) -> Result<Vec<E::Output>, E::Error> {
+        if signature_verified_block.is_empty() {
+            return Ok(vec![]);
+        }
+        let num_txns = signature_verified_block.len();
+        let versioned_data_cache = DynamicMVHashMap::new();
+        let records: Records<T, E> = (0..num_txns)
+            .map(|_| Mutex::new(ExecutionRecord::new()))
+            .collect();
+
+        // Optimistically execute all transactions in parallel.
+        let execution_marker = AtomicUsize::new(0);
+        scope(|s| {
+            // Ensure we have at least 50 tx per thread.
+            let compute_cpus = max(1, min(1 + (num_txns / 50), self.num_cpus - 1));
+            for _ in 0..compute_cpus {
+                s.spawn(|_| loop {
+                    let idx = execution_marker.fetch_add(1, Ordering::Relaxed);
+                    if idx >= num_txns {
+                        break;
+                    }
+                    Self::execute_transaction(
+                        task_initial_arguments,
+                        &versioned_data_cache,
+                        &signature_verified_block[idx],
+                        idx,
+                        &records[idx],
+                    );
+                });
+            }
+        });
+
+        // Validate the transactions in order, re-executing those that read stale values.
+        let mut num_txns_to_commit = num_txns;
+        for (idx, record) in records.iter().enumerate() {
+            if !Self::validate_reads(&versioned_data_cache, idx, &record.lock().unwrap().reads) {
+                Self::execute_transaction(
+                    task_initial_arguments,
+                    &versioned_data_cache,
+                    &signature_verified_block[idx],
+                    idx,
+                    record,
+                );
+            }
+
+            match &record.lock().unwrap().status {
+                Some(ExecutionStatus::Success(_)) => (),
+                Some(ExecutionStatus::SkipRest(_)) => {
+                    num_txns_to_commit = idx + 1;
+                    break;
+                }
+                Some(ExecutionStatus::Abort(err)) => return Err(Error::UserError(err.clone())),
+                None => return Err(Error::InvariantViolation),
+            }
+        }
+
+        let mut final_results = Vec::with_capacity(num_txns);
+        for record in records.into_iter().take(num_txns_to_commit) {
+            match record.into_inner().unwrap().status {
+                Some(ExecutionStatus::Success(output))
+                | Some(ExecutionStatus::SkipRest(output)) => final_results.push(output),
+                _ => return Err(Error::InvariantViolation),
+            }
+        }
+        final_results.resize_with(num_txns, E::Output::skip_output);
+
+        // Dropping large structures is expensive -- do this is a separate thread.
+        ::std::thread::spawn(move || {
+            drop(signature_verified_block); // Explicit drops to measure their cost.
+            drop(versioned_data_cache);
+        });
+
+        Ok(final_results)
+    }
+
+    /// Executes (or re-executes) the transaction at `idx`, replacing the writes of its previous
+    /// incarnation in the versioned data cache and recording the reads of the new incarnation.
+    fn execute_transaction(
+        task_initial_arguments: E::Argument,
+        versioned_data_cache: &DynamicMVHashMap<T::Key, T::Value>,
+        txn: &T,
+        idx: Version,
+        record: &Mutex<ExecutionRecord<T::Key, E::Output, E::Error>>,
+    ) {
+        let mut record = record.lock().unwrap();
+        let incarnation = record.incarnation + 1;
+
+        let task = E::init(task_initial_arguments);
+        let view = MVHashMapView::new_optimistic(versioned_data_cache, idx);
+        let status = task.execute_transaction(&view, txn);
+        let reads = view.take_reads();
+
+        // Publish the writes of the new incarnation and remove any stale writes
+        let writes = match &status {
+            ExecutionStatus::Success(output) | ExecutionStatus::SkipRest(output) => {
+                output.get_writes()
+            }
+            ExecutionStatus::Abort(_) => vec![],
+        };
+        let keys_written: Vec<_> = writes.iter().map(|(key, _)| key.clone()).collect();
+        for (key, value) in writes {
+            versioned_data_cache.write(&key, idx, incarnation, value);
+        }
+        let new_keys: HashSet<_> = keys_written.iter().collect();
+        for key in record.keys_written.iter() {
+            if !new_keys.contains(key) {
+                versioned_data_cache.remove(key, idx);
+            }
+        }
+
+        *record = ExecutionRecord {
+            incarnation,
+            reads,
+            keys_written,
+            status: Some(status),
+        };
+    }
+
+    /// Returns true iff every read still observes the latest write below `idx`.
+    fn validate_reads(
+        versioned_data_cache: &DynamicMVHashMap<T::Key, T::Value>,
+        idx: Version,
+        reads: &[ReadDescriptor<T::Key>],
+    ) -> bool {
+        reads
+            .iter()
+            .all(|read| versioned_data_cache.latest_writer(&read.key, idx) == read.writer)
+    }
+}
+
+impl<T, E> Default for OptimisticTransactionExecutor<T, E>
+where
+    T: Transaction,
+    E: ExecutorTask<T = T>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -3,6 +3,7 @@
 
 use crate::{
     executor::ParallelTransactionExecutor,
+    optimistic_executor::OptimisticTransactionExecutor,
     proptest_types::types::{
         ExpectedOutput, ImpreciseInferencer, Inferencer, Task, Transaction, TransactionGen,
     },
@@ -16,6 +17,7 @@ fn run_transactions<K, V>(
     abort_transactions: Vec<Index>,
     skip_rest_transactions: Vec<Index>,
     imprecise_read: bool,
+    optimistic: bool,
 ) -> bool
 where
     K: Hash + Clone + Debug + Eq + Send + Sync + PartialOrd + Ord + 'static,
@@ -38,7 +40,10 @@ where
 
     let baseline = ExpectedOutput::generate_baseline(&transactions);
 
-    let output = if imprecise_read {
+    let output = if optimistic {
+        OptimisticTransactionExecutor::<Transaction<K, V>, Task<K, V>>::new()
+            .execute_transactions_parallel((), transactions)
+    } else if imprecise_read {
         ParallelTransactionExecutor::<Transaction<K, V>, Task<K, V>, ImpreciseInferencer<K, V>>::new(
             ImpreciseInferencer::new(),
         )
@@ -62,7 +67,7 @@ proptest! {
         abort_transactions in vec(any::<Index>(), 0),
         skip_rest_transactions in vec(any::<Index>(), 0),
     ) {
-        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, false));
+        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, false, false));
     }
 
     #[test]
@@ -72,7 +77,7 @@ proptest! {
         abort_transactions in vec(any::<Index>(), 5),
         skip_rest_transactions in vec(any::<Index>(), 0),
     ) {
-        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, false));
+        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, false, false));
     }
 
     #[test]
@@ -82,7 +87,7 @@ proptest! {
         abort_transactions in vec(any::<Index>(), 0),
         skip_rest_transactions in vec(any::<Index>(), 5),
     ) {
-        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, false));
+        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, false, false));
     }
 
 
@@ -93,7 +98,7 @@ proptest! {
         abort_transactions in vec(any::<Index>(), 5),
         skip_rest_transactions in vec(any::<Index>(), 5),
     ) {
-        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, false));
+        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, false, false));
     }
 
     #[test]
@@ -103,6 +108,26 @@ proptest! {
         abort_transactions in vec(any::<Index>(), 5),
         skip_rest_transactions in vec(any::<Index>(), 5),
     ) {
-        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, true));
+        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, true, false));
+    }
+
+    #[test]
+    fn optimistic_no_early_termination(
+        universe in vec(any::<[u8; 32]>(), 100),
+        transaction_gen in vec(any::<TransactionGen<[u8;32]>>(), 5000).no_shrink(),
+        abort_transactions in vec(any::<Index>(), 0),
+        skip_rest_transactions in vec(any::<Index>(), 0),
+    ) {
+        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, false, true));
+    }
+
+    #[test]
+    fn optimistic_mixed_transactions(
+        universe in vec(any::<[u8; 32]>(), 100),
+        transaction_gen in vec(any::<TransactionGen<[u8;32]>>(), 5000).no_shrink(),
+        abort_transactions in vec(any::<Index>(), 5),
+        skip_rest_transactions in vec(any::<Index>(), 5),
+    ) {
+        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, false, true));
     }
 }
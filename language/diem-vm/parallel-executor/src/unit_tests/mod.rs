@@ -3,6 +3,7 @@
 
 use crate::{
     executor::ParallelTransactionExecutor,
+    optimistic_executor::OptimisticTransactionExecutor,
     proptest_types::types::{ExpectedOutput, Inferencer, Task, Transaction},
 };
 use rand::random;
@@ -19,8 +20,12 @@ where
         ParallelTransactionExecutor::<Transaction<K, V>, Task<K, V>, Inferencer<K, V>>::new(
             Inferencer::new(),
         )
-        .execute_transactions_parallel((), transactions);
+        .execute_transactions_parallel((), transactions.clone());
+    assert!(baseline.check_output(&output));
 
+    // Executing without the inferred read and write sets must yield the same results
+    let output = OptimisticTransactionExecutor::<Transaction<K, V>, Task<K, V>>::new()
+        .execute_transactions_parallel((), transactions);
     assert!(baseline.check_output(&output))
 }
 
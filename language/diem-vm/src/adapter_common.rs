@@ -3,7 +3,8 @@
 
 use crate::{counters::*, create_access_path, data_cache::StateViewCache};
 use anyhow::Result;
-use diem_state_view::StateView;
+use diem_crypto::{hash::CryptoHash, HashValue};
+use diem_state_view::{StateView, StateViewId};
 use diem_types::{
     account_address::AccountAddress,
     account_config::{self, RoleId},
@@ -13,7 +14,10 @@ use diem_types::{
     vm_status::{StatusCode, VMStatus},
 };
 use move_core_types::{move_resource::MoveStructType, resolver::MoveResolver};
-use move_vm_runtime::session::Session;
+use move_vm_runtime::{
+    execution_trace::{self, TraceEvent, TransactionTrace},
+    session::Session,
+};
 
 use crate::logging::AdapterLogSchema;
 use diem_logger::prelude::*;
@@ -196,6 +200,13 @@ pub(crate) fn execute_block_impl<A: VMAdapter, S: StateView>(
         transactions.len()
     );
 
+    // The hashes are only needed to delimit the transactions in the execution trace.
+    let transaction_hashes: Option<Vec<HashValue>> = if execution_trace::is_tracing() {
+        Some(transactions.iter().map(CryptoHash::hash).collect())
+    } else {
+        None
+    };
+
     let signature_verified_block: Vec<PreprocessedTransaction>;
     {
         // Verify the signatures of all the transactions in parallel.
@@ -222,8 +233,17 @@ pub(crate) fn execute_block_impl<A: VMAdapter, S: StateView>(
             debug!(log_context, "Retry after reconfiguration");
             continue;
         };
+        let transaction_trace = transaction_hashes
+            .as_ref()
+            .map(|hashes| transaction_trace(data_cache.id(), idx, hashes[idx]));
+        if let Some(transaction_trace) = &transaction_trace {
+            execution_trace::record(TraceEvent::TransactionBegin(transaction_trace.clone()));
+        }
         let (vm_status, output, sender) =
             adapter.execute_single_transaction(&txn, data_cache, &log_context)?;
+        if let Some(transaction_trace) = transaction_trace {
+            execution_trace::record(TraceEvent::TransactionEnd(transaction_trace));
+        }
         if !output.status().is_discarded() {
             data_cache.push_write_set(output.write_set());
         } else {
@@ -254,6 +274,19 @@ pub(crate) fn execute_block_impl<A: VMAdapter, S: StateView>(
     Ok(result)
 }
 
+/// Identifies the transaction at index `idx` of a block in the execution trace. The version is
+/// only known when the block is a chunk of committed transactions.
+fn transaction_trace(state_view_id: StateViewId, idx: usize, hash: HashValue) -> TransactionTrace {
+    let version = match state_view_id {
+        StateViewId::ChunkExecution { first_version } => Some(first_version + idx as u64),
+        _ => None,
+    };
+    TransactionTrace {
+        version,
+        hash: hash.to_hex(),
+    }
+}
+
 /// Transactions after signature checking:
 /// Waypoints and BlockPrologues are not signed and are unaffected by signature checking,
 /// but a user transaction or writeset transaction is transformed to a SignatureCheckedTransaction.
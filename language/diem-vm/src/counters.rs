@@ -2,8 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use diem_metrics::{
-    register_histogram, register_int_counter, register_int_counter_vec, Histogram, IntCounter,
-    IntCounterVec,
+    register_histogram, register_int_counter, register_int_counter_vec, register_int_gauge_vec,
+    Histogram, IntCounter, IntCounterVec, IntGaugeVec,
 };
 use once_cell::sync::Lazy;
 
@@ -70,3 +70,46 @@ pub static TXN_GAS_USAGE: Lazy<Histogram> =
 pub static CRITICAL_ERRORS: Lazy<IntCounter> = Lazy::new(|| {
     register_int_counter!("diem_vm_critical_errors", "Number of critical errors").unwrap()
 });
+
+/// Count the lookups in the code cache of the Move VM, with a "binary" label (module or script)
+/// and a "result" label (hit or miss).
+pub static MOVE_VM_CACHE_LOOKUPS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_vm_move_vm_cache_lookups",
+        "Number of lookups in the code cache of the Move VM",
+        &["binary", "result"]
+    )
+    .unwrap()
+});
+
+/// Count the binaries evicted from the code cache of the Move VM, with a "binary" label.
+pub static MOVE_VM_CACHE_EVICTIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_vm_move_vm_cache_evictions",
+        "Number of binaries evicted from the code cache of the Move VM",
+        &["binary"]
+    )
+    .unwrap()
+});
+
+/// The serialized size of the binaries in the code cache of the Move VM of the last block
+/// executed, with a "binary" label.
+pub static MOVE_VM_CACHE_BYTES: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "diem_vm_move_vm_cache_bytes",
+        "Serialized size of the binaries in the code cache of the Move VM",
+        &["binary"]
+    )
+    .unwrap()
+});
+
+/// The estimated memory used by the code cache of the Move VM of the last block executed, with a
+/// "cache" label (module, script or type_info).
+pub static MOVE_VM_CACHE_MEMORY: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "diem_vm_move_vm_cache_memory_bytes",
+        "Estimated memory used by the code cache of the Move VM",
+        &["cache"]
+    )
+    .unwrap()
+});
@@ -15,6 +15,7 @@ use crate::{
     },
     errors::expect_only_successful_execution,
     logging::AdapterLogSchema,
+    parallel_executor::ParallelDiemVM,
     script_to_script_function,
     system_module_names::*,
     transaction_metadata::TransactionMetadata,
@@ -44,7 +45,10 @@ use move_core_types::{
     transaction_argument::convert_txn_args,
     value::{serialize_values, MoveValue},
 };
-use move_vm_runtime::session::Session;
+use move_vm_runtime::{
+    move_vm::{CacheConfig, CacheStats},
+    session::Session,
+};
 use move_vm_types::gas_schedule::GasStatus;
 use std::{
     collections::HashSet,
@@ -59,6 +63,11 @@ impl DiemVM {
         Self(DiemVMImpl::new(state))
     }
 
+    /// Creates a VM whose code cache is bounded by `cache_config`.
+    pub fn new_with_cache_config<S: StateView>(state: &S, cache_config: CacheConfig) -> Self {
+        Self(DiemVMImpl::new_with_cache_config(state, cache_config))
+    }
+
     pub fn new_for_validation<S: StateView>(state: &S) -> Self {
         info!(
             AdapterLogSchema::new(state.id(), 0),
@@ -689,15 +698,69 @@ impl DiemVM {
     pub fn execute_block_and_keep_vm_status(
         transactions: Vec<Transaction>,
         state_view: &impl StateView,
+    ) -> Result<Vec<(VMStatus, TransactionOutput)>, VMStatus> {
+        Self::execute_block_and_keep_vm_status_with_cache_config(
+            transactions,
+            state_view,
+            CacheConfig::default(),
+        )
+    }
+
+    fn execute_block_and_keep_vm_status_with_cache_config(
+        transactions: Vec<Transaction>,
+        state_view: &impl StateView,
+        cache_config: CacheConfig,
     ) -> Result<Vec<(VMStatus, TransactionOutput)>, VMStatus> {
         let mut state_view_cache = StateViewCache::new(state_view);
         let count = transactions.len();
-        let vm = DiemVM::new(&state_view_cache);
+        let vm = DiemVM::new_with_cache_config(&state_view_cache, cache_config);
         let res = adapter_common::execute_block_impl(&vm, transactions, &mut state_view_cache)?;
         // Record the histogram count for transactions per block.
         BLOCK_TRANSACTION_COUNT.observe(count as f64);
+        Self::record_cache_stats(&vm.internals().move_vm().cache_stats());
         Ok(res)
     }
+
+    // The Move VM only lives for the block, so its stats are the block's
+    fn record_cache_stats(stats: &CacheStats) {
+        for (binary, hits, misses, evictions, bytes, memory) in [
+            (
+                "module",
+                stats.module_hits,
+                stats.module_misses,
+                stats.modules_evicted,
+                stats.cached_module_bytes,
+                stats.cached_module_memory,
+            ),
+            (
+                "script",
+                stats.script_hits,
+                stats.script_misses,
+                stats.scripts_evicted,
+                stats.cached_script_bytes,
+                stats.cached_script_memory,
+            ),
+        ] {
+            MOVE_VM_CACHE_LOOKUPS
+                .with_label_values(&[binary, "hit"])
+                .inc_by(hits);
+            MOVE_VM_CACHE_LOOKUPS
+                .with_label_values(&[binary, "miss"])
+                .inc_by(misses);
+            MOVE_VM_CACHE_EVICTIONS
+                .with_label_values(&[binary])
+                .inc_by(evictions);
+            MOVE_VM_CACHE_BYTES
+                .with_label_values(&[binary])
+                .set(bytes as i64);
+            MOVE_VM_CACHE_MEMORY
+                .with_label_values(&[binary])
+                .set(memory as i64);
+        }
+        MOVE_VM_CACHE_MEMORY
+            .with_label_values(&["type_info"])
+            .set(stats.cached_type_info_memory as i64);
+    }
 }
 
 // Executor external API
@@ -710,6 +773,14 @@ impl VMExecutor for DiemVM {
     fn execute_block(
         transactions: Vec<Transaction>,
         state_view: &impl StateView,
+    ) -> Result<Vec<TransactionOutput>, VMStatus> {
+        Self::execute_block_with_cache_config(transactions, state_view, CacheConfig::default())
+    }
+
+    fn execute_block_with_cache_config(
+        transactions: Vec<Transaction>,
+        state_view: &impl StateView,
+        cache_config: CacheConfig,
     ) -> Result<Vec<TransactionOutput>, VMStatus> {
         fail_point!("move_adapter::execute_block", |_| {
             Err(VMStatus::Error(
@@ -717,12 +788,47 @@ impl VMExecutor for DiemVM {
             ))
         });
 
-        let output = Self::execute_block_and_keep_vm_status(transactions, state_view)?;
+        let output = Self::execute_block_and_keep_vm_status_with_cache_config(
+            transactions,
+            state_view,
+            cache_config,
+        )?;
         Ok(output
             .into_iter()
             .map(|(_vm_status, txn_output)| txn_output)
             .collect())
     }
+
+    /// Execute a block of `transactions` in parallel, discovering the dependencies between
+    /// transactions during execution (see `ParallelDiemVM::execute_block_optimistic`).
+    fn execute_block_parallel(
+        transactions: Vec<Transaction>,
+        state_view: &impl StateView,
+    ) -> Result<Vec<TransactionOutput>, VMStatus> {
+        Self::execute_block_parallel_with_cache_config(
+            transactions,
+            state_view,
+            CacheConfig::default(),
+        )
+    }
+
+    fn execute_block_parallel_with_cache_config(
+        transactions: Vec<Transaction>,
+        state_view: &impl StateView,
+        cache_config: CacheConfig,
+    ) -> Result<Vec<TransactionOutput>, VMStatus> {
+        fail_point!("move_adapter::execute_block", |_| {
+            Err(VMStatus::Error(
+                StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR,
+            ))
+        });
+
+        let count = transactions.len();
+        let output =
+            ParallelDiemVM::execute_block_optimistic(transactions, state_view, cache_config)?;
+        BLOCK_TRANSACTION_COUNT.observe(count as f64);
+        Ok(output)
+    }
 }
 
 // VMValidator external API
@@ -37,8 +37,15 @@ use move_core_types::{
     resolver::MoveResolver,
     value::{serialize_values, MoveValue},
 };
-use move_vm_runtime::{logging::expect_no_verification_errors, move_vm::MoveVM, session::Session};
-use move_vm_types::gas_schedule::{calculate_intrinsic_gas, GasStatus};
+use move_vm_runtime::{
+    logging::expect_no_verification_errors,
+    move_vm::{CacheConfig, MoveVM},
+    session::Session,
+};
+use move_vm_types::{
+    gas_profiler,
+    gas_schedule::{calculate_intrinsic_gas, GasStatus},
+};
 use std::{convert::TryFrom, sync::Arc};
 
 #[derive(Clone)]
@@ -53,7 +60,12 @@ pub struct DiemVMImpl {
 impl DiemVMImpl {
     #[allow(clippy::new_without_default)]
     pub fn new<S: StateView>(state: &S) -> Self {
-        let inner = MoveVM::new(diem_natives())
+        Self::new_with_cache_config(state, CacheConfig::default())
+    }
+
+    /// Creates a VM whose code cache is bounded by `cache_config`.
+    pub fn new_with_cache_config<S: StateView>(state: &S, cache_config: CacheConfig) -> Self {
+        let inner = MoveVM::new_with_cache_config(diem_natives(), cache_config)
             .expect("should be able to create Move VM; check if there are duplicated natives");
         let mut vm = Self {
             move_vm: Arc::new(inner),
@@ -565,7 +577,10 @@ pub(crate) fn charge_global_write_gas_usage<R: MoveResolver>(
             .mul(gas_status.cost_table().gas_constants.default_account_size)
             .get();
     gas_status
-        .deduct_gas(InternalGasUnits::new(total_cost))
+        .deduct_gas_attributed(
+            InternalGasUnits::new(total_cost),
+            gas_profiler::STORAGE_WRITES_FRAME,
+        )
         .map_err(|p_err| p_err.finish(Location::Undefined).into_vm_status())
 }
 
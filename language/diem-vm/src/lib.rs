@@ -126,6 +126,7 @@ pub mod transaction_metadata;
 mod unit_tests;
 
 pub use crate::{diem_vm::DiemVM, diem_vm_impl::convert_changeset_and_events};
+pub use move_vm_runtime::move_vm::CacheConfig;
 
 use diem_state_view::StateView;
 use diem_types::{
@@ -160,6 +161,37 @@ pub trait VMExecutor: Send + Sync {
         transactions: Vec<Transaction>,
         state_view: &impl StateView,
     ) -> Result<Vec<TransactionOutput>, VMStatus>;
+
+    /// Executes a block of transactions in parallel and returns output for each one of them. The
+    /// outputs must be identical to the ones of `execute_block`. VMs without a parallel
+    /// implementation execute the block sequentially.
+    fn execute_block_parallel(
+        transactions: Vec<Transaction>,
+        state_view: &impl StateView,
+    ) -> Result<Vec<TransactionOutput>, VMStatus> {
+        Self::execute_block(transactions, state_view)
+    }
+
+    /// Executes a block of transactions like `execute_block`, with the code cache of the VM
+    /// bounded by `cache_config`. VMs without a code cache ignore the bounds.
+    fn execute_block_with_cache_config(
+        transactions: Vec<Transaction>,
+        state_view: &impl StateView,
+        _cache_config: CacheConfig,
+    ) -> Result<Vec<TransactionOutput>, VMStatus> {
+        Self::execute_block(transactions, state_view)
+    }
+
+    /// Executes a block of transactions like `execute_block_parallel`, with the code cache of
+    /// every VM executing the block bounded by `cache_config`. VMs without a code cache ignore
+    /// the bounds.
+    fn execute_block_parallel_with_cache_config(
+        transactions: Vec<Transaction>,
+        state_view: &impl StateView,
+        _cache_config: CacheConfig,
+    ) -> Result<Vec<TransactionOutput>, VMStatus> {
+        Self::execute_block_parallel(transactions, state_view)
+    }
 }
 
 /// Get the AccessPath to a resource stored under `address` with type name `tag`
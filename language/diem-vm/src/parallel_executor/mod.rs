@@ -12,11 +12,11 @@ use crate::{
     parallel_executor::{
         read_write_set_analyzer::ReadWriteSetAnalysisWrapper, vm_wrapper::DiemVMWrapper,
     },
-    VMExecutor,
 };
 use diem_parallel_executor::{
     errors::Error,
     executor::ParallelTransactionExecutor,
+    optimistic_executor::OptimisticTransactionExecutor,
     task::{Transaction as PTransaction, TransactionOutput as PTransactionOutput},
 };
 use diem_state_view::StateView;
@@ -26,6 +26,7 @@ use diem_types::{
     write_set::{WriteOp, WriteSet},
 };
 use move_core_types::vm_status::{StatusCode, VMStatus};
+use move_vm_runtime::move_vm::CacheConfig;
 use rayon::prelude::*;
 use read_write_set_dynamic::NormalizedReadWriteSetAnalysis;
 
@@ -67,6 +68,10 @@ impl PTransactionOutput for DiemTransactionOutput {
 pub struct ParallelDiemVM();
 
 impl ParallelDiemVM {
+    /// Executes the block in parallel using the read and write sets inferred by the given
+    /// analysis. If the analysis can't bound a transaction in the block, the block is executed
+    /// optimistically instead (see `execute_block_optimistic`) and the analysis error is
+    /// returned alongside the outputs.
     pub fn execute_block<S: StateView>(
         analysis_result: &NormalizedReadWriteSetAnalysis,
         transactions: Vec<Transaction>,
@@ -89,8 +94,10 @@ impl ParallelDiemVM {
             DiemVMWrapper<S>,
             ReadWriteSetAnalysisWrapper<RemoteStorage<S>>,
         >::new(analyzer)
-        .execute_transactions_parallel(state_view, signature_verified_block)
-        {
+        .execute_transactions_parallel(
+            (state_view, CacheConfig::default()),
+            signature_verified_block,
+        ) {
             Ok(results) => Ok((
                 results
                     .into_iter()
@@ -98,13 +105,42 @@ impl ParallelDiemVM {
                     .collect(),
                 None,
             )),
-            Err(err @ Error::InferencerError) | Err(err @ Error::UnestimatedWrite) => {
-                Ok((DiemVM::execute_block(transactions, state_view)?, Some(err)))
-            }
+            Err(err @ Error::InferencerError) | Err(err @ Error::UnestimatedWrite) => Ok((
+                Self::execute_block_optimistic(transactions, state_view, CacheConfig::default())?,
+                Some(err),
+            )),
             Err(Error::InvariantViolation) => Err(VMStatus::Error(
                 StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR,
             )),
             Err(Error::UserError(err)) => Err(err),
         }
     }
+
+    /// Executes the block in parallel without a prior read and write set analysis. Dependencies
+    /// between transactions are discovered during execution, and transactions that read stale
+    /// values are re-executed. The code cache of every VM executing the block is bounded by
+    /// `cache_config`.
+    pub fn execute_block_optimistic<S: StateView>(
+        transactions: Vec<Transaction>,
+        state_view: &S,
+        cache_config: CacheConfig,
+    ) -> Result<Vec<TransactionOutput>, VMStatus> {
+        let signature_verified_block: Vec<PreprocessedTransaction> = transactions
+            .into_par_iter()
+            .map(preprocess_transaction::<DiemVM>)
+            .collect();
+
+        match OptimisticTransactionExecutor::<PreprocessedTransaction, DiemVMWrapper<S>>::new()
+            .execute_transactions_parallel((state_view, cache_config), signature_verified_block)
+        {
+            Ok(results) => Ok(results
+                .into_iter()
+                .map(DiemTransactionOutput::into)
+                .collect()),
+            Err(Error::UserError(err)) => Err(err),
+            Err(_) => Err(VMStatus::Error(
+                StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR,
+            )),
+        }
+    }
 }
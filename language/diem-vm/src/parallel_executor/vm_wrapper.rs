@@ -15,6 +15,7 @@ use diem_parallel_executor::{
 use diem_state_view::StateView;
 use diem_types::{access_path::AccessPath, write_set::WriteOp};
 use move_core_types::vm_status::VMStatus;
+use move_vm_runtime::move_vm::CacheConfig;
 
 pub(crate) struct DiemVMWrapper<'a, S> {
     vm: DiemVM,
@@ -25,12 +26,12 @@ impl<'a, S: 'a + StateView> ExecutorTask for DiemVMWrapper<'a, S> {
     type T = PreprocessedTransaction;
     type Output = DiemTransactionOutput;
     type Error = VMStatus;
-    type Argument = &'a S;
+    type Argument = (&'a S, CacheConfig);
 
-    fn init(argument: &'a S) -> Self {
+    fn init((base_view, cache_config): (&'a S, CacheConfig)) -> Self {
         Self {
-            vm: DiemVM::new(argument),
-            base_view: argument,
+            vm: DiemVM::new_with_cache_config(base_view, cache_config),
+            base_view,
         }
     }
 
@@ -0,0 +1,64 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use diem_crypto::hash::CryptoHash;
+use diem_types::transaction::{Transaction, TransactionStatus};
+use language_e2e_tests::{common_transactions::peer_to_peer_txn, executor::FakeExecutor};
+use move_vm_runtime::execution_trace::{self, TraceBuffer, TraceEvent, TransactionTrace};
+
+#[test]
+fn trace_delimits_transactions() {
+    let mut executor = FakeExecutor::from_genesis_file();
+    let sender = executor.create_raw_account_data(1_000_000, 10);
+    let receiver = executor.create_raw_account_data(100_000, 10);
+    executor.add_account_data(&sender);
+    executor.add_account_data(&receiver);
+    let txns = vec![
+        peer_to_peer_txn(sender.account(), receiver.account(), 10, 1_000),
+        peer_to_peer_txn(sender.account(), receiver.account(), 11, 1_000),
+    ];
+
+    let buffer = TraceBuffer::new();
+    execution_trace::set_trace_sink(Box::new(buffer.clone()));
+    let outputs = executor.execute_block(txns.clone()).unwrap();
+    execution_trace::take_trace_sink();
+    for output in &outputs {
+        assert!(matches!(output.status(), TransactionStatus::Keep(_)));
+    }
+
+    // The state view of the fake executor is not a chunk of committed transactions, so the
+    // versions are unknown.
+    let expected: Vec<_> = txns
+        .into_iter()
+        .map(|txn| TransactionTrace {
+            version: None,
+            hash: Transaction::UserTransaction(txn).hash().to_hex(),
+        })
+        .collect();
+    let events = buffer.events();
+    let delimiters: Vec<_> = events
+        .iter()
+        .filter(|event| {
+            matches!(
+                event,
+                TraceEvent::TransactionBegin(_) | TraceEvent::TransactionEnd(_)
+            )
+        })
+        .cloned()
+        .collect();
+    assert_eq!(
+        delimiters,
+        vec![
+            TraceEvent::TransactionBegin(expected[0].clone()),
+            TraceEvent::TransactionEnd(expected[0].clone()),
+            TraceEvent::TransactionBegin(expected[1].clone()),
+            TraceEvent::TransactionEnd(expected[1].clone()),
+        ]
+    );
+    // Every instruction is executed within a transaction.
+    assert_eq!(events.first(), delimiters.first());
+    assert_eq!(events.last(), delimiters.last());
+    assert!(events
+        .iter()
+        .any(|event| matches!(event, TraceEvent::Instruction(_))));
+}
@@ -14,6 +14,7 @@ use language_e2e_tests::{
     account::{self, Account},
     assert_prologue_parity,
     common_transactions::peer_to_peer_txn,
+    compile::compile_script,
     current_function_name,
     executor::FakeExecutor,
     test_with_different_versions, transaction_status_eq,
@@ -22,6 +23,7 @@ use language_e2e_tests::{
 use move_core_types::{
     identifier::Identifier, language_storage::ModuleId, transaction_argument::convert_txn_args,
 };
+use move_vm_types::gas_schedule::{NativeCostIndex, INITIAL_GAS_SCHEDULE};
 
 #[test]
 fn initial_diem_version() {
@@ -226,3 +228,83 @@ fn update_consensus_config() {
     }
     }
 }
+
+#[test]
+fn native_schedule_upgrade() {
+    // The release bundle predates `set_native_schedule` and `String`: build them from the sources.
+    let mut executor = FakeExecutor::from_fresh_genesis();
+    let dr_account = Account::new_diem_root();
+    let sender = executor.create_raw_account_data(1_000_000, 10);
+    executor.add_account_data(&sender);
+
+    let set_native_schedule_txn = |seq_num: u64, native_schedule: Vec<u8>| {
+        dr_account
+            .transaction()
+            .script_function(ScriptFunction::new(
+                ModuleId::new(
+                    CORE_CODE_ADDRESS,
+                    Identifier::new("SystemAdministrationScripts").unwrap(),
+                ),
+                Identifier::new("set_native_schedule").unwrap(),
+                vec![],
+                convert_txn_args(&[
+                    TransactionArgument::U64(seq_num - 1),
+                    TransactionArgument::U8Vector(native_schedule),
+                ]),
+            ))
+            .sequence_number(seq_num)
+            .sign()
+    };
+
+    // The native schedule of a chain created before the String natives existed is rejected.
+    let mut native_table = INITIAL_GAS_SCHEDULE.native_table.clone();
+    native_table.truncate(NativeCostIndex::STRING_CHECK_UTF8 as usize);
+    let output = executor.execute_transaction(set_native_schedule_txn(
+        1,
+        bcs::to_bytes(&native_table).unwrap(),
+    ));
+    assert!(matches!(
+        output.status(),
+        &TransactionStatus::Keep(KeptVMStatus::MoveAbort(_, 263))
+    ));
+
+    // The native schedule of the VM has a cost for every native, including the String ones.
+    let output = executor.execute_and_apply(set_native_schedule_txn(
+        1,
+        bcs::to_bytes(&INITIAL_GAS_SCHEDULE.native_table).unwrap(),
+    ));
+    assert_eq!(
+        output.status(),
+        &TransactionStatus::Keep(KeptVMStatus::Executed)
+    );
+    executor.new_block();
+
+    let string_module = diem_framework::modules()
+        .into_iter()
+        .filter(|module| module.self_id().name().as_str() == "String")
+        .collect();
+    let utf8_script = compile_script(
+        r#"
+import 0x1.String;
+
+main() {
+  let s: String.String;
+  s = String.utf8(h"616263");
+  return;
+}
+"#,
+        string_module,
+    );
+    let output = executor.execute_and_apply(
+        sender
+            .account()
+            .transaction()
+            .script(utf8_script)
+            .sequence_number(10)
+            .sign(),
+    );
+    assert_eq!(
+        output.status(),
+        &TransactionStatus::Keep(KeptVMStatus::Executed)
+    );
+}
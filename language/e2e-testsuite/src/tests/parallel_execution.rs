@@ -4,14 +4,28 @@
 use crate::tests::peer_to_peer::{check_and_apply_transfer_output, create_cyclic_transfers};
 use diem_crypto::{ed25519::Ed25519PrivateKey, HashValue, PrivateKey, Uniform};
 use diem_framework_releases::current_modules;
+use diem_transaction_builder::stdlib::encode_peer_to_peer_with_metadata_script_function;
 use diem_types::{
+    account_config,
     block_metadata::BlockMetadata,
-    on_chain_config::{OnChainConfig, ValidatorSet},
-    transaction::{authenticator::AuthenticationKey, Transaction, TransactionStatus},
+    on_chain_config::{OnChainConfig, VMPublishingOption, ValidatorSet},
+    transaction::{
+        authenticator::AuthenticationKey, Module, Script, ScriptFunction, SignedTransaction,
+        Transaction, TransactionArgument, TransactionOutput, TransactionStatus,
+    },
     vm_status::{KeptVMStatus, StatusCode},
 };
-use diem_vm::{parallel_executor::ParallelDiemVM, read_write_set_analysis::add_on_functions_list};
-use language_e2e_tests::{account, common_transactions::rotate_key_txn, executor::FakeExecutor};
+use diem_vm::{
+    parallel_executor::ParallelDiemVM, read_write_set_analysis::add_on_functions_list, CacheConfig,
+    DiemVM, VMExecutor,
+};
+use language_e2e_tests::{
+    account::{self, Account},
+    common_transactions::{peer_to_peer_txn, rotate_key_txn},
+    compile::{compile_module, compile_script},
+    executor::FakeExecutor,
+};
+use move_core_types::{identifier::Identifier, language_storage::ModuleId};
 use read_write_set::analyze;
 
 #[test]
@@ -58,6 +72,49 @@ fn peer_to_peer_with_prologue_parallel() {
     check_and_apply_transfer_output(&mut executor, &txns_info, &results)
 }
 
+#[test]
+fn peer_to_peer_with_prologue_optimistic() {
+    let mut executor = FakeExecutor::from_fresh_genesis();
+    let account_size = 1000usize;
+    let initial_balance = 2_000_000u64;
+    let initial_seq_num = 10u64;
+    let accounts = executor.create_accounts(account_size, initial_balance, initial_seq_num);
+
+    // set up the transactions
+    let transfer_amount = 1_000;
+
+    // insert a block prologue transaction
+    let (txns_info, transfer_txns) = create_cyclic_transfers(&executor, &accounts, transfer_amount);
+
+    let mut txns = transfer_txns
+        .into_iter()
+        .map(Transaction::UserTransaction)
+        .collect::<Vec<_>>();
+    let validator_set = ValidatorSet::fetch_config(executor.get_state_view())
+        .expect("Unable to retrieve the validator set from storage");
+    let new_block = BlockMetadata::new(
+        HashValue::zero(),
+        0,
+        1,
+        vec![],
+        *validator_set.payload()[0].account_address(),
+    );
+
+    txns.insert(0, Transaction::BlockMetadata(new_block));
+
+    // execute the block without any read/write set analysis
+    let mut results = ParallelDiemVM::execute_block_optimistic(
+        txns,
+        executor.get_state_view(),
+        CacheConfig::default(),
+    )
+    .unwrap();
+
+    results.remove(0);
+
+    check_and_apply_transfer_output(&mut executor, &txns_info, &results)
+}
+
 #[test]
 fn rotate_ed25519_key() {
     let balance = 1_000_000;
@@ -121,3 +178,302 @@ fn rotate_ed25519_key() {
         &TransactionStatus::Keep(KeptVMStatus::Executed),
     );
 }
+
+// A payment script which is not part of the framework, and so unknown to the read/write set
+// analysis.
+fn pay_script() -> Vec<u8> {
+    let code = "
+    import 0x1.DiemAccount;
+    import 0x1.XUS;
+
+    main(account: signer, payee: address, amount: u64) {
+      let with_cap: DiemAccount.WithdrawCapability;
+      with_cap = DiemAccount.extract_withdraw_capability(&account);
+      DiemAccount.pay_from<XUS.XUS>(&with_cap, move(payee), move(amount), h\"\", h\"\");
+      DiemAccount.restore_withdraw_capability(move(with_cap));
+      return;
+    }
+";
+    compile_script(code, vec![]).code().to_vec()
+}
+
+fn pay_txn(
+    code: &[u8],
+    sender: &Account,
+    payee: &Account,
+    seq_num: u64,
+    amount: u64,
+) -> SignedTransaction {
+    sender
+        .transaction()
+        .script(Script::new(
+            code.to_vec(),
+            vec![],
+            vec![
+                TransactionArgument::Address(*payee.address()),
+                TransactionArgument::U64(amount),
+            ],
+        ))
+        .sequence_number(seq_num)
+        .sign()
+}
+
+fn block_prologue(executor: &FakeExecutor) -> Transaction {
+    let validator_set = ValidatorSet::fetch_config(executor.get_state_view())
+        .expect("Unable to retrieve the validator set from storage");
+    Transaction::BlockMetadata(BlockMetadata::new(
+        HashValue::zero(),
+        0,
+        1,
+        vec![],
+        *validator_set.payload()[0].account_address(),
+    ))
+}
+
+#[test]
+fn unanalyzable_scripts_with_prologue_parallel() {
+    let mut executor = FakeExecutor::from_genesis_file();
+    let accounts = executor.create_accounts(100, 2_000_000, 10);
+
+    // every account pays the next one with a script the analysis can't bound
+    let code = pay_script();
+    let mut txns = vec![block_prologue(&executor)];
+    txns.extend(accounts.iter().enumerate().map(|(i, sender)| {
+        let payee = &accounts[(i + 1) % accounts.len()];
+        Transaction::UserTransaction(pay_txn(&code, sender, payee, 10, 1_000))
+    }));
+
+    let analyze_result = analyze(current_modules().iter())
+        .unwrap()
+        .normalize_all_scripts(add_on_functions_list());
+
+    // the block is executed optimistically instead of sequentially, with the same outputs
+    let (results, parallel_status) =
+        ParallelDiemVM::execute_block(&analyze_result, txns.clone(), executor.get_state_view())
+            .unwrap();
+    assert!(parallel_status.is_some());
+    assert_eq!(
+        results,
+        DiemVM::execute_block(txns, executor.get_state_view()).unwrap()
+    );
+    for output in &results[1..] {
+        assert_eq!(
+            output.status(),
+            &TransactionStatus::Keep(KeptVMStatus::Executed),
+        );
+    }
+}
+
+// Executes the block optimistically and sequentially from the same state, checks that both
+// executions have the same outputs and returns them.
+fn execute_optimistic_and_sequential(
+    executor: &FakeExecutor,
+    txns: Vec<Transaction>,
+) -> Vec<TransactionOutput> {
+    let optimistic_outputs = ParallelDiemVM::execute_block_optimistic(
+        txns.clone(),
+        executor.get_state_view(),
+        CacheConfig::default(),
+    )
+    .unwrap();
+    let sequential_outputs = DiemVM::execute_block(txns, executor.get_state_view()).unwrap();
+    assert_eq!(optimistic_outputs, sequential_outputs);
+    sequential_outputs
+}
+
+fn assert_executed(outputs: &[TransactionOutput]) {
+    for output in outputs {
+        assert_eq!(
+            output.status(),
+            &TransactionStatus::Keep(KeptVMStatus::Executed),
+        );
+    }
+}
+
+#[test]
+fn scripts_optimistic() {
+    let mut executor = FakeExecutor::from_genesis_file();
+    let accounts = executor.create_accounts(100, 2_000_000, 10);
+
+    // every account pays the next one with a custom script, then pays the first account with a
+    // framework script, so that most transactions conflict on the first account
+    let code = pay_script();
+    let mut txns = vec![block_prologue(&executor)];
+    for (i, sender) in accounts.iter().enumerate() {
+        let payee = &accounts[(i + 1) % accounts.len()];
+        txns.push(Transaction::UserTransaction(pay_txn(
+            &code, sender, payee, 10, 1_000,
+        )));
+        txns.push(Transaction::UserTransaction(peer_to_peer_txn(
+            sender,
+            &accounts[0],
+            11,
+            1_000,
+        )));
+    }
+
+    let outputs = execute_optimistic_and_sequential(&executor, txns);
+    assert_executed(&outputs[1..]);
+}
+
+#[test]
+fn script_functions_optimistic() {
+    let mut executor = FakeExecutor::from_genesis_file();
+    let accounts = executor.create_accounts(100, 2_000_000, 10);
+
+    // every account pays the next one with a script function, then pays the first account with
+    // a script
+    let code = pay_script();
+    let mut txns = vec![block_prologue(&executor)];
+    for (i, sender) in accounts.iter().enumerate() {
+        let payee = &accounts[(i + 1) % accounts.len()];
+        txns.push(Transaction::UserTransaction(
+            sender
+                .transaction()
+                .payload(encode_peer_to_peer_with_metadata_script_function(
+                    account_config::xus_tag(),
+                    *payee.address(),
+                    1_000,
+                    vec![],
+                    vec![],
+                ))
+                .sequence_number(10)
+                .sign(),
+        ));
+        txns.push(Transaction::UserTransaction(pay_txn(
+            &code,
+            sender,
+            &accounts[0],
+            11,
+            1_000,
+        )));
+    }
+
+    let outputs = execute_optimistic_and_sequential(&executor, txns);
+    assert_executed(&outputs[1..]);
+}
+
+// A module published by `publisher`, with a `pay` script function making a payment.
+fn pay_module(publisher: &Account) -> Module {
+    let program = format!(
+        "
+        module 0x{}.M {{
+            import 0x1.DiemAccount;
+            import 0x1.XUS;
+
+            public(script) pay(account: signer, payee: address, amount: u64) {{
+                let with_cap: DiemAccount.WithdrawCapability;
+                with_cap = DiemAccount.extract_withdraw_capability(&account);
+                DiemAccount.pay_from<XUS.XUS>(
+                    &with_cap,
+                    move(payee),
+                    move(amount),
+                    h\"\",
+                    h\"\"
+                );
+                DiemAccount.restore_withdraw_capability(move(with_cap));
+                return;
+            }}
+        }}
+        ",
+        publisher.address(),
+    );
+    compile_module(&program).1
+}
+
+// Calls the `pay` script function of `pay_module` to pay the publisher.
+fn call_pay_module_txn(publisher: &Account, sender: &Account) -> Transaction {
+    Transaction::UserTransaction(
+        sender
+            .transaction()
+            .script_function(ScriptFunction::new(
+                ModuleId::new(*publisher.address(), Identifier::new("M").unwrap()),
+                Identifier::new("pay").unwrap(),
+                vec![],
+                vec![
+                    bcs::to_bytes(publisher.address()).unwrap(),
+                    bcs::to_bytes(&1_000u64).unwrap(),
+                ],
+            ))
+            .sequence_number(10)
+            .sign(),
+    )
+}
+
+#[test]
+fn module_publishing_optimistic() {
+    let mut executor = FakeExecutor::from_genesis_with_options(VMPublishingOption::open());
+    let publisher = executor.create_raw_account_data(2_000_000, 10);
+    executor.add_account_data(&publisher);
+    let publisher = publisher.into_account();
+    let accounts = executor.create_accounts(50, 2_000_000, 10);
+
+    // the module is called in the same block it is published in, both before and after it
+    // exists
+    let mut txns = vec![
+        block_prologue(&executor),
+        call_pay_module_txn(&publisher, &accounts[0]),
+        Transaction::UserTransaction(
+            publisher
+                .transaction()
+                .module(pay_module(&publisher))
+                .sequence_number(10)
+                .sign(),
+        ),
+    ];
+    txns.extend(
+        accounts[1..]
+            .iter()
+            .map(|sender| call_pay_module_txn(&publisher, sender)),
+    );
+
+    let outputs = execute_optimistic_and_sequential(&executor, txns);
+    assert_ne!(
+        outputs[1].status(),
+        &TransactionStatus::Keep(KeptVMStatus::Executed),
+    );
+    assert_executed(&outputs[2..]);
+}
+
+#[test]
+fn module_publishing_reexecuted_optimistic() {
+    let mut executor = FakeExecutor::from_genesis_with_options(VMPublishingOption::open());
+    let publisher = executor.create_raw_account_data(2_000_000, 10);
+    executor.add_account_data(&publisher);
+    let publisher = publisher.into_account();
+    let accounts = executor.create_accounts(50, 2_000_000, 10);
+
+    // The publisher first spends its sequence number on a payment, so the publishing
+    // transaction is discarded once it reads the payment's writes. Executed optimistically
+    // against the state before the payment, it publishes the module anyway, and the callers
+    // after it must not keep using that module once it is re-executed.
+    let mut txns = vec![
+        block_prologue(&executor),
+        Transaction::UserTransaction(peer_to_peer_txn(&publisher, &accounts[0], 10, 1_000)),
+        Transaction::UserTransaction(
+            publisher
+                .transaction()
+                .module(pay_module(&publisher))
+                .sequence_number(10)
+                .sign(),
+        ),
+    ];
+    txns.extend(
+        accounts
+            .iter()
+            .map(|sender| call_pay_module_txn(&publisher, sender)),
+    );
+
+    let outputs = execute_optimistic_and_sequential(&executor, txns);
+    assert_executed(&outputs[1..2]);
+    assert_eq!(
+        outputs[2].status(),
+        &TransactionStatus::Discard(StatusCode::SEQUENCE_NUMBER_TOO_OLD),
+    );
+    for output in &outputs[3..] {
+        assert_ne!(
+            output.status(),
+            &TransactionStatus::Keep(KeptVMStatus::Executed),
+        );
+    }
+}
@@ -245,10 +245,11 @@ impl CostTable {
         &self.instruction_table[(instr_index - 1) as usize]
     }
 
+    /// The cost of the native at `native_index`, or `None` if the table has no entry for it (e.g.,
+    /// an on-chain gas schedule published before the native was added).
     #[inline]
-    pub fn native_cost(&self, native_index: u8) -> &GasCost {
-        precondition!(native_index < (self.native_table.len() as u8));
-        &self.native_table[native_index as usize]
+    pub fn native_cost(&self, native_index: u8) -> Option<&GasCost> {
+        self.native_table.get(native_index as usize)
     }
 }
 
@@ -638,6 +638,20 @@ fn call_native_function(
                 state.record_access(args[0], Access::Read, func_env)
             }
         }
+        ("String", "internal_check_utf8")
+        | ("String", "internal_is_char_boundary")
+        | ("String", "internal_sub_string") => {
+            if state.locals.local_exists(args[0], func_env) {
+                state.record_access(args[0], Access::Read, func_env)
+            }
+        }
+        ("String", "internal_index_of") => {
+            for arg in &args[..2] {
+                if state.locals.local_exists(*arg, func_env) {
+                    state.record_access(*arg, Access::Read, func_env)
+                }
+            }
+        }
         ("Vector", "pop_back") => {
             if state.locals.local_exists(args[0], func_env) {
                 // this will look at vector length. record as read of an index
@@ -3,6 +3,7 @@
 
 //! This file implements the statement interpretation part of the stackless bytecode interpreter.
 
+use memchr::memmem;
 use num::{BigInt, ToPrimitive, Zero};
 use std::{collections::BTreeMap, rc::Rc};
 
@@ -266,6 +267,42 @@ impl<'env> FunctionContext<'env> {
                 let res = self.native_hash_sha3_256(dummy_state.del_value(0));
                 Ok(vec![res])
             }
+            (DIEM_CORE_ADDR, "String", "internal_check_utf8") => {
+                if cfg!(debug_assertions) {
+                    assert_eq!(srcs.len(), 1);
+                }
+                let res = self.native_string_check_utf8(dummy_state.del_value(0));
+                Ok(vec![res])
+            }
+            (DIEM_CORE_ADDR, "String", "internal_is_char_boundary") => {
+                if cfg!(debug_assertions) {
+                    assert_eq!(srcs.len(), 2);
+                }
+                let res = self.native_string_is_char_boundary(
+                    dummy_state.del_value(0),
+                    dummy_state.del_value(1),
+                );
+                Ok(vec![res])
+            }
+            (DIEM_CORE_ADDR, "String", "internal_sub_string") => {
+                if cfg!(debug_assertions) {
+                    assert_eq!(srcs.len(), 3);
+                }
+                self.native_string_sub_string(
+                    dummy_state.del_value(0),
+                    dummy_state.del_value(1),
+                    dummy_state.del_value(2),
+                )
+                .map(|res| vec![res])
+            }
+            (DIEM_CORE_ADDR, "String", "internal_index_of") => {
+                if cfg!(debug_assertions) {
+                    assert_eq!(srcs.len(), 2);
+                }
+                let res =
+                    self.native_string_index_of(dummy_state.del_value(0), dummy_state.del_value(1));
+                Ok(vec![res])
+            }
             (DIEM_CORE_ADDR, "BCS", "to_bytes") => {
                 if cfg!(debug_assertions) {
                     assert_eq!(srcs.len(), 1);
@@ -2026,6 +2063,73 @@ impl<'env> FunctionContext<'env> {
         TypedValue::mk_vector(elem_ty, hashed)
     }
 
+    fn native_string_check_utf8(&self, bytes_val: TypedValue) -> TypedValue {
+        if cfg!(debug_assertions) {
+            assert_eq!(self.ty_args.len(), 0);
+            // NOTE: this function accepts a value instead of a reference!
+            // This is different from the Move native implementation.
+            assert!(bytes_val.get_ty().is_vector_of(&BaseType::mk_u8()));
+        }
+        let bytes = into_bytes(bytes_val);
+        TypedValue::mk_bool(std::str::from_utf8(&bytes).is_ok())
+    }
+
+    fn native_string_is_char_boundary(
+        &self,
+        bytes_val: TypedValue,
+        index_val: TypedValue,
+    ) -> TypedValue {
+        if cfg!(debug_assertions) {
+            assert_eq!(self.ty_args.len(), 0);
+            assert!(bytes_val.get_ty().is_vector_of(&BaseType::mk_u8()));
+        }
+        let bytes = into_bytes(bytes_val);
+        let index = index_val.into_u64() as usize;
+        // the start and the end of the bytes are boundaries, and in between a character starts at
+        // any byte which is not a continuation byte (i.e., 0b10xxxxxx)
+        let res = match bytes.get(index) {
+            _ if index == 0 => true,
+            Some(byte) => (*byte as i8) >= -0x40,
+            None => index == bytes.len(),
+        };
+        TypedValue::mk_bool(res)
+    }
+
+    fn native_string_sub_string(
+        &self,
+        bytes_val: TypedValue,
+        start_val: TypedValue,
+        end_val: TypedValue,
+    ) -> Result<TypedValue, AbortInfo> {
+        if cfg!(debug_assertions) {
+            assert_eq!(self.ty_args.len(), 0);
+            assert!(bytes_val.get_ty().is_vector_of(&BaseType::mk_u8()));
+        }
+        let bytes = into_bytes(bytes_val);
+        let start = start_val.into_u64() as usize;
+        let end = end_val.into_u64() as usize;
+        bytes
+            .get(start..end)
+            .map(|sub| {
+                let sub_val = sub.iter().copied().map(TypedValue::mk_u8).collect();
+                TypedValue::mk_vector(BaseType::mk_u8(), sub_val)
+            })
+            .ok_or_else(|| self.usr_abort(INDEX_OUT_OF_BOUNDS))
+    }
+
+    fn native_string_index_of(&self, bytes_val: TypedValue, sub_val: TypedValue) -> TypedValue {
+        if cfg!(debug_assertions) {
+            assert_eq!(self.ty_args.len(), 0);
+            assert!(bytes_val.get_ty().is_vector_of(&BaseType::mk_u8()));
+            assert!(sub_val.get_ty().is_vector_of(&BaseType::mk_u8()));
+        }
+        let bytes = into_bytes(bytes_val);
+        let sub = into_bytes(sub_val);
+        // same linear-time search as the `String::internal_index_of` native of the Move VM
+        let index = memmem::find(&bytes, &sub).unwrap_or(bytes.len());
+        TypedValue::mk_u64(index as u64)
+    }
+
     fn native_bcs_to_bytes(&self, object: TypedValue) -> Result<TypedValue, AbortInfo> {
         if cfg!(debug_assertions) {
             assert_eq!(self.ty_args.len(), 1);
@@ -2240,6 +2344,19 @@ impl<'env> FunctionContext<'env> {
     }
 }
 
+//**************************************************************************************************
+// Utilities
+//**************************************************************************************************
+
+/// Convert a `vector<u8>` value into its bytes
+fn into_bytes(bytes_val: TypedValue) -> Vec<u8> {
+    bytes_val
+        .into_vector()
+        .into_iter()
+        .map(|e| e.into_u8())
+        .collect()
+}
+
 //**************************************************************************************************
 // Entrypoint
 //**************************************************************************************************
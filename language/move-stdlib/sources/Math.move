@@ -0,0 +1,162 @@
+/// Integer math utilities which are not provided by the Move language: minimum and maximum,
+/// multiplication followed by division without intermediate overflow, integer square root, and
+/// exponentiation.
+module Std::Math {
+    use Std::Errors;
+
+    ///> TODO: This is a basic constant and should be provided somewhere centrally in the framework.
+    const MAX_U64: u128 = 18446744073709551615;
+    const MAX_U128: u128 = 340282366920938463463374607431768211455;
+
+    /// A division by zero was encountered
+    const EDIVISION_BY_ZERO: u64 = 1;
+    /// The result would be too large to be held in its integer type
+    const EOVERFLOW: u64 = 2;
+
+    /// Returns the larger of `a` and `b`.
+    public fun max(a: u64, b: u64): u64 {
+        if (a >= b) a else b
+    }
+    spec max {
+        aborts_if false;
+        ensures a >= b ==> result == a;
+        ensures a < b ==> result == b;
+    }
+
+    /// Returns the smaller of `a` and `b`.
+    public fun min(a: u64, b: u64): u64 {
+        if (a < b) a else b
+    }
+    spec min {
+        aborts_if false;
+        ensures a < b ==> result == a;
+        ensures a >= b ==> result == b;
+    }
+
+    /// Returns the larger of `a` and `b`.
+    public fun max_u128(a: u128, b: u128): u128 {
+        if (a >= b) a else b
+    }
+    spec max_u128 {
+        aborts_if false;
+        ensures a >= b ==> result == a;
+        ensures a < b ==> result == b;
+    }
+
+    /// Returns the smaller of `a` and `b`.
+    public fun min_u128(a: u128, b: u128): u128 {
+        if (a < b) a else b
+    }
+    spec min_u128 {
+        aborts_if false;
+        ensures a < b ==> result == a;
+        ensures a >= b ==> result == b;
+    }
+
+    /// Returns `a * b / c`, truncating any fractional part. The product is computed with 128 bits,
+    /// so this only aborts if `c` is zero or if the quotient does not fit into a `u64`.
+    public fun mul_div(a: u64, b: u64, c: u64): u64 {
+        assert(c != 0, Errors::invalid_argument(EDIVISION_BY_ZERO));
+        // The product of two 64 bit values has 128 bits and cannot overflow.
+        let quotient = (a as u128) * (b as u128) / (c as u128);
+        assert(quotient <= MAX_U64, Errors::limit_exceeded(EOVERFLOW));
+        (quotient as u64)
+    }
+    spec mul_div {
+        pragma opaque;
+        include MulDivAbortsIf;
+        ensures result == spec_mul_div(a, b, c);
+    }
+    spec schema MulDivAbortsIf {
+        a: num;
+        b: num;
+        c: num;
+        aborts_if c == 0 with Errors::INVALID_ARGUMENT;
+        aborts_if spec_mul_div(a, b, c) > MAX_U64 with Errors::LIMIT_EXCEEDED;
+    }
+    spec fun spec_mul_div(a: num, b: num, c: num): num {
+        a * b / c
+    }
+
+    /// Returns the integer square root of `x`, i.e. the largest `r` such that `r * r <= x`.
+    public fun sqrt(x: u128): u128 {
+        // Computes the root one bit at a time, starting with the highest power of four which
+        // is not larger than `x`. `res + bit` stays below 2^128 as `res < 2^127` and
+        // `bit <= 2^126`.
+        let res = 0;
+        let bit = 1 << 126;
+        while ({spec {
+            invariant bit <= 85070591730234615865843651857942052864;
+        };
+            (bit > x)}) {
+            bit = bit >> 2;
+        };
+        while ({spec {
+            invariant bit <= 85070591730234615865843651857942052864;
+            invariant res < 170141183460469231731687303715884105728;
+        };
+            (bit != 0)}) {
+            if (x >= res + bit) {
+                x = x - (res + bit);
+                res = (res >> 1) + bit;
+            } else {
+                res = res >> 1;
+            };
+            bit = bit >> 2;
+        };
+        res
+    }
+    /// The absence of aborts is verified. The characterization of the result is non-linear, which
+    /// none of our SMT solvers supports with reliable efficiency, so it is only assumed by callers.
+    spec sqrt {
+        pragma opaque;
+        aborts_if false;
+        ensures [abstract] result * result <= x;
+        ensures [abstract] (result + 1) * (result + 1) > x;
+    }
+
+    /// Returns `base` to the power of `exp`. Aborts if the result does not fit into a `u128`.
+    public fun pow(base: u64, exp: u8): u128 {
+        let b = (base as u128);
+        let result = 1;
+        let i = 0;
+        while ({spec {
+            invariant i <= exp;
+            invariant result == spec_pow(b, i);
+        };
+            (i < exp)}) {
+            assert(b == 0 || result <= MAX_U128 / b, Errors::limit_exceeded(EOVERFLOW));
+            result = result * b;
+            i = i + 1;
+        };
+        result
+    }
+    spec pow {
+        pragma opaque;
+        include PowAbortsIf;
+        ensures result == spec_pow(base, exp);
+    }
+    spec schema PowAbortsIf {
+        base: num;
+        exp: num;
+        aborts_if spec_pow(base, exp) > MAX_U128 with Errors::LIMIT_EXCEEDED;
+    }
+    spec fun spec_pow(base: num, exp: num): num {
+        if (exp == 0) {
+            1
+        } else {
+            base * spec_pow(base, exp - 1)
+        }
+    }
+
+    spec module {} // switch documentation context back to module level
+
+    spec module {
+        pragma aborts_if_is_strict;
+
+        // `spec_pow` grows with the exponent for a positive base. Proving this requires induction,
+        // so it is stated as an axiom. It shows that when `pow` aborts early, the final result
+        // would have overflowed as well.
+        axiom forall b: num, i: num, j: num where b >= 1 && 0 <= i && i <= j: spec_pow(b, i) <= spec_pow(b, j);
+    }
+}
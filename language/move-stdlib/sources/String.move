@@ -0,0 +1,172 @@
+/// The `String` module defines the `String` type which represents UTF-8 encoded strings, and the
+/// operations on them. Indices into a `String` are byte offsets, which must fall on character
+/// boundaries.
+///
+/// The natives of this module are declared both in the Move runtime and in the Move prover's
+/// prelude.
+module Std::String {
+    use Std::Errors;
+    use Std::Option::{Self, Option};
+    use Std::Vector;
+
+    /// A `String` holds a sequence of bytes which is guaranteed to be valid UTF-8.
+    struct String has copy, drop, store {
+        bytes: vector<u8>,
+    }
+
+    /// The bytes are not valid UTF-8.
+    const EINVALID_UTF8: u64 = 1;
+    /// The index is out of range, or does not fall on a character boundary.
+    const EINVALID_INDEX: u64 = 2;
+
+    /// Creates a new string from a sequence of bytes.
+    /// Aborts if the bytes are not valid UTF-8.
+    public fun utf8(bytes: vector<u8>): String {
+        assert(internal_check_utf8(&bytes), Errors::invalid_argument(EINVALID_UTF8));
+        String{bytes}
+    }
+    spec utf8 {
+        pragma opaque;
+        aborts_if !internal_check_utf8(bytes) with Errors::INVALID_ARGUMENT;
+        ensures result == String{bytes};
+    }
+
+    /// Tries to create a new string from a sequence of bytes.
+    /// Returns `None` if the bytes are not valid UTF-8.
+    public fun try_utf8(bytes: vector<u8>): Option<String> {
+        if (internal_check_utf8(&bytes)) {
+            Option::some(String{bytes})
+        } else {
+            Option::none()
+        }
+    }
+    spec try_utf8 {
+        pragma opaque;
+        aborts_if false;
+        ensures result == (if (internal_check_utf8(bytes)) Option::spec_some(String{bytes}) else Option::spec_none());
+    }
+
+    /// Returns a reference to the bytes of `s`.
+    public fun bytes(s: &String): &vector<u8> {
+        &s.bytes
+    }
+    spec bytes {
+        pragma opaque;
+        aborts_if false;
+        ensures result == s.bytes;
+    }
+
+    /// Returns `true` if `s` is empty.
+    public fun is_empty(s: &String): bool {
+        Vector::is_empty(&s.bytes)
+    }
+    spec is_empty {
+        pragma opaque;
+        aborts_if false;
+        ensures result == (len(s.bytes) == 0);
+    }
+
+    /// Returns the length of `s` in bytes.
+    public fun length(s: &String): u64 {
+        Vector::length(&s.bytes)
+    }
+    spec length {
+        pragma opaque;
+        aborts_if false;
+        ensures result == len(s.bytes);
+    }
+
+    /// Appends `r` to `s`.
+    public fun append(s: &mut String, r: String) {
+        let String { bytes } = r;
+        Vector::append(&mut s.bytes, bytes)
+    }
+    spec append {
+        pragma opaque;
+        aborts_if false;
+        ensures s.bytes == concat(old(s.bytes), r.bytes);
+    }
+
+    /// Appends the bytes `bytes` to `s`.
+    /// Aborts if the bytes are not valid UTF-8.
+    public fun append_utf8(s: &mut String, bytes: vector<u8>) {
+        append(s, utf8(bytes))
+    }
+    spec append_utf8 {
+        pragma opaque;
+        aborts_if !internal_check_utf8(bytes) with Errors::INVALID_ARGUMENT;
+        ensures s.bytes == concat(old(s.bytes), bytes);
+    }
+
+    /// Inserts `o` into `s` at the byte index `at`.
+    /// Aborts if `at` is past the end of `s`, or does not fall on a character boundary.
+    public fun insert(s: &mut String, at: u64, o: String) {
+        let bytes = &s.bytes;
+        assert(
+            at <= Vector::length(bytes) && internal_is_char_boundary(bytes, at),
+            Errors::invalid_argument(EINVALID_INDEX)
+        );
+        let l = length(s);
+        let front = sub_string(s, 0, at);
+        let end = sub_string(s, at, l);
+        append(&mut front, o);
+        append(&mut front, end);
+        *s = front;
+    }
+    spec insert {
+        pragma opaque;
+        aborts_if at > len(s.bytes) with Errors::INVALID_ARGUMENT;
+        aborts_if !internal_is_char_boundary(s.bytes, at) with Errors::INVALID_ARGUMENT;
+        ensures s.bytes == concat(concat(old(s.bytes)[0..at], o.bytes), old(s.bytes)[at..len(old(s.bytes))]);
+    }
+
+    /// Returns the sub-string of `s` between the byte indices `i` (included) and `j` (excluded).
+    /// Aborts if the indices are not in order, are past the end of `s`, or do not fall on
+    /// character boundaries.
+    public fun sub_string(s: &String, i: u64, j: u64): String {
+        let bytes = &s.bytes;
+        let l = Vector::length(bytes);
+        assert(
+            j <= l && i <= j && internal_is_char_boundary(bytes, i) && internal_is_char_boundary(bytes, j),
+            Errors::invalid_argument(EINVALID_INDEX)
+        );
+        String{bytes: internal_sub_string(bytes, i, j)}
+    }
+    spec sub_string {
+        pragma opaque;
+        include SubStringAbortsIf;
+        ensures result.bytes == s.bytes[i..j];
+    }
+    spec schema SubStringAbortsIf {
+        s: String;
+        i: u64;
+        j: u64;
+        aborts_if j > len(s.bytes) || i > j with Errors::INVALID_ARGUMENT;
+        aborts_if !internal_is_char_boundary(s.bytes, i) with Errors::INVALID_ARGUMENT;
+        aborts_if !internal_is_char_boundary(s.bytes, j) with Errors::INVALID_ARGUMENT;
+    }
+
+    /// Returns the byte index of the first occurrence of `r` in `s`, or the length of `s` if `r`
+    /// does not occur in `s`.
+    public fun index_of(s: &String, r: &String): u64 {
+        internal_index_of(&s.bytes, &r.bytes)
+    }
+    spec index_of {
+        pragma opaque;
+        aborts_if false;
+        ensures result == internal_index_of(s.bytes, r.bytes);
+        ensures result <= len(s.bytes);
+    }
+
+    // Native API
+    native fun internal_check_utf8(v: &vector<u8>): bool;
+    native fun internal_is_char_boundary(v: &vector<u8>, i: u64): bool;
+    native fun internal_sub_string(v: &vector<u8>, i: u64, j: u64): vector<u8>;
+    native fun internal_index_of(v: &vector<u8>, r: &vector<u8>): u64;
+
+    spec module {} // switch documentation context back to module level
+
+    spec module {
+        pragma aborts_if_is_strict;
+    }
+}
@@ -33,7 +33,7 @@ pub fn native_to_bytes(
     };
     let serialized_value = match serialized_value_opt {
         None => {
-            let cost = native_gas(context.cost_table(), NativeCostIndex::BCS_TO_BYTES, 1);
+            let cost = native_gas(context.cost_table(), NativeCostIndex::BCS_TO_BYTES, 1)?;
             return Ok(NativeResult::err(cost, NFE_BCS_SERIALIZATION_FAILURE));
         }
         Some(serialized_value) => serialized_value,
@@ -44,7 +44,7 @@ pub fn native_to_bytes(
         context.cost_table(),
         NativeCostIndex::BCS_TO_BYTES,
         serialized_value.len(),
-    );
+    )?;
 
     Ok(NativeResult::ok(
         cost,
@@ -31,7 +31,7 @@ pub fn write_to_event_store(
         context.cost_table(),
         NativeCostIndex::EMIT_EVENT,
         msg.size().get() as usize,
-    );
+    )?;
 
     if !context.save_event(guid, seq_num, ty, msg)? {
         return Ok(NativeResult::err(cost, 0));
@@ -5,6 +5,7 @@ pub mod bcs;
 pub mod event;
 pub mod hash;
 pub mod signer;
+pub mod string;
 pub mod vector;
 
 #[cfg(feature = "testing")]
@@ -23,6 +24,14 @@ pub fn all_natives(move_std_addr: AccountAddress) -> NativeFunctionTable {
         ("Hash", "sha2_256", hash::native_sha2_256),
         ("Hash", "sha3_256", hash::native_sha3_256),
         ("Signer", "borrow_address", signer::native_borrow_address),
+        ("String", "internal_check_utf8", string::native_check_utf8),
+        (
+            "String",
+            "internal_is_char_boundary",
+            string::native_is_char_boundary,
+        ),
+        ("String", "internal_sub_string", string::native_sub_string),
+        ("String", "internal_index_of", string::native_index_of),
         ("Vector", "length", vector::native_length),
         ("Vector", "empty", vector::native_empty),
         ("Vector", "borrow", vector::native_borrow),
@@ -0,0 +1,130 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use memchr::memmem;
+use move_binary_format::errors::{PartialVMError, PartialVMResult};
+use move_core_types::vm_status::StatusCode;
+use move_vm_runtime::native_functions::NativeContext;
+use move_vm_types::{
+    gas_schedule::NativeCostIndex,
+    loaded_data::runtime_types::Type,
+    natives::function::{native_gas, NativeResult},
+    pop_arg,
+    values::{Value, VectorRef, INDEX_OUT_OF_BOUNDS},
+};
+use smallvec::smallvec;
+use std::collections::VecDeque;
+
+// The natives below operate on the bytes of a `String`. The checks that make these operations
+// valid (e.g., that indices fall on character boundaries) are done by the Move functions of the
+// `String` module, which abort with the appropriate error codes.
+
+pub fn native_check_utf8(
+    context: &mut NativeContext,
+    _ty_args: Vec<Type>,
+    mut arguments: VecDeque<Value>,
+) -> PartialVMResult<NativeResult> {
+    debug_assert!(_ty_args.is_empty());
+    debug_assert!(arguments.len() == 1);
+
+    let s_arg = pop_arg!(arguments, VectorRef);
+    let s_ref = s_arg.as_bytes_ref()?;
+
+    let cost = native_gas(
+        context.cost_table(),
+        NativeCostIndex::STRING_CHECK_UTF8,
+        s_ref.len(),
+    )?;
+
+    let ok = std::str::from_utf8(s_ref.as_slice()).is_ok();
+    Ok(NativeResult::ok(cost, smallvec![Value::bool(ok)]))
+}
+
+pub fn native_is_char_boundary(
+    context: &mut NativeContext,
+    _ty_args: Vec<Type>,
+    mut arguments: VecDeque<Value>,
+) -> PartialVMResult<NativeResult> {
+    debug_assert!(_ty_args.is_empty());
+    debug_assert!(arguments.len() == 2);
+
+    let i = pop_arg!(arguments, u64) as usize;
+    let s_arg = pop_arg!(arguments, VectorRef);
+    let s_ref = s_arg.as_bytes_ref()?;
+
+    let cost = native_gas(
+        context.cost_table(),
+        NativeCostIndex::STRING_IS_CHAR_BOUNDARY,
+        1,
+    )?;
+
+    // As for `str::is_char_boundary`, the start and the end of the bytes are boundaries, and in
+    // between a character starts at any byte which is not a continuation byte (i.e., of the form
+    // 0b10xxxxxx).
+    let ok = match s_ref.get(i) {
+        _ if i == 0 => true,
+        Some(byte) => (*byte as i8) >= -0x40,
+        None => i == s_ref.len(),
+    };
+    Ok(NativeResult::ok(cost, smallvec![Value::bool(ok)]))
+}
+
+pub fn native_sub_string(
+    context: &mut NativeContext,
+    _ty_args: Vec<Type>,
+    mut arguments: VecDeque<Value>,
+) -> PartialVMResult<NativeResult> {
+    debug_assert!(_ty_args.is_empty());
+    debug_assert!(arguments.len() == 3);
+
+    let j = pop_arg!(arguments, u64) as usize;
+    let i = pop_arg!(arguments, u64) as usize;
+    let s_arg = pop_arg!(arguments, VectorRef);
+    let s_ref = s_arg.as_bytes_ref()?;
+
+    let bytes = s_ref.get(i..j).ok_or_else(|| {
+        PartialVMError::new(StatusCode::ABORTED).with_sub_status(INDEX_OUT_OF_BOUNDS)
+    })?;
+    let cost = native_gas(
+        context.cost_table(),
+        NativeCostIndex::STRING_SUB_STRING,
+        bytes.len(),
+    )?;
+
+    Ok(NativeResult::ok(
+        cost,
+        smallvec![Value::vector_u8(bytes.to_vec())],
+    ))
+}
+
+pub fn native_index_of(
+    context: &mut NativeContext,
+    _ty_args: Vec<Type>,
+    mut arguments: VecDeque<Value>,
+) -> PartialVMResult<NativeResult> {
+    debug_assert!(_ty_args.is_empty());
+    debug_assert!(arguments.len() == 2);
+
+    let r_arg = pop_arg!(arguments, VectorRef);
+    let r_ref = r_arg.as_bytes_ref()?;
+    let s_arg = pop_arg!(arguments, VectorRef);
+    let s_ref = s_arg.as_bytes_ref()?;
+
+    let cost = native_gas(
+        context.cost_table(),
+        NativeCostIndex::STRING_INDEX_OF,
+        s_ref.len() + r_ref.len(),
+    )?;
+
+    let pos = index_of(s_ref.as_slice(), r_ref.as_slice());
+    Ok(NativeResult::ok(cost, smallvec![Value::u64(pos as u64)]))
+}
+
+/// The index of the first occurrence of `r` in `s`, or the length of `s` if there is none.
+///
+/// The search runs in time linear in the lengths of `s` and `r`, which the gas charged for
+/// `index_of` relies on: a naive search is quadratic on inputs such as `r = "aa..ab"` and
+/// `s = "aa..a"`.
+pub(crate) fn index_of(s: &[u8], r: &[u8]) -> usize {
+    memmem::find(s, r).unwrap_or(s.len())
+}
@@ -29,3 +29,22 @@ fn check_that_the_errmap_is_updated() {
         "Generated errmap differ from the one checked in"
     );
 }
+
+#[test]
+fn string_index_of_is_linear_on_adversarial_inputs() {
+    use crate::natives::string::index_of;
+
+    // A naive search compares most of `r` at every position of `s`, i.e., ~10^11 byte
+    // comparisons here, which would not finish in the time of a test.
+    let s = vec![b'a'; 1 << 20];
+    let mut r = vec![b'a'; 1 << 17];
+    r.push(b'b');
+    assert_eq!(index_of(&s, &r), s.len());
+
+    let mut t = s.clone();
+    t.push(b'b');
+    assert_eq!(index_of(&t, &r), s.len() - (r.len() - 1));
+
+    assert_eq!(index_of(&s, &[]), 0);
+    assert_eq!(index_of(&[], &r), 0);
+}
@@ -0,0 +1,66 @@
+#[test_only]
+module Std::MathTests {
+    use Std::Math;
+
+    #[test]
+    fun test_min_max() {
+        assert(Math::max(1, 2) == 2, 0);
+        assert(Math::max(2, 1) == 2, 1);
+        assert(Math::min(1, 2) == 1, 2);
+        assert(Math::min(2, 1) == 1, 3);
+        assert(Math::max(3, 3) == 3, 4);
+        assert(Math::min(3, 3) == 3, 5);
+        assert(Math::max_u128(1, 340282366920938463463374607431768211455) == 340282366920938463463374607431768211455, 6);
+        assert(Math::min_u128(1, 340282366920938463463374607431768211455) == 1, 7);
+    }
+
+    #[test]
+    fun test_mul_div() {
+        assert(Math::mul_div(6, 4, 3) == 8, 0);
+        assert(Math::mul_div(7, 1, 2) == 3, 1);
+        assert(Math::mul_div(0, 5, 1) == 0, 2);
+        // The intermediate product does not fit into a u64.
+        assert(Math::mul_div(18446744073709551615, 18446744073709551615, 18446744073709551615) == 18446744073709551615, 3);
+        assert(Math::mul_div(18446744073709551615, 2, 4) == 9223372036854775807, 4);
+    }
+
+    #[test]
+    #[expected_failure(abort_code = 263)]
+    fun test_mul_div_by_zero() {
+        Math::mul_div(1, 1, 0);
+    }
+
+    #[test]
+    #[expected_failure(abort_code = 520)]
+    fun test_mul_div_overflow() {
+        Math::mul_div(18446744073709551615, 2, 1);
+    }
+
+    #[test]
+    fun test_sqrt() {
+        assert(Math::sqrt(0) == 0, 0);
+        assert(Math::sqrt(1) == 1, 1);
+        assert(Math::sqrt(3) == 1, 2);
+        assert(Math::sqrt(4) == 2, 3);
+        assert(Math::sqrt(99) == 9, 4);
+        assert(Math::sqrt(100) == 10, 5);
+        assert(Math::sqrt(340282366920938463463374607431768211455) == 18446744073709551615, 6);
+    }
+
+    #[test]
+    fun test_pow() {
+        assert(Math::pow(0, 0) == 1, 0);
+        assert(Math::pow(0, 5) == 0, 1);
+        assert(Math::pow(1, 255) == 1, 2);
+        assert(Math::pow(2, 10) == 1024, 3);
+        assert(Math::pow(3, 5) == 243, 4);
+        assert(Math::pow(2, 127) == 170141183460469231731687303715884105728, 5);
+        assert(Math::pow(18446744073709551615, 2) == 340282366920938463426481119284349108225, 6);
+    }
+
+    #[test]
+    #[expected_failure(abort_code = 520)]
+    fun test_pow_overflow() {
+        Math::pow(2, 128);
+    }
+}
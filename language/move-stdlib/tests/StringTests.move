@@ -0,0 +1,110 @@
+#[test_only]
+module Std::StringTests {
+    use Std::String;
+    use Std::Option;
+
+    #[test]
+    fun test_valid_utf8() {
+        let sparkle_heart = x"f09f9296";
+        let s = String::utf8(sparkle_heart);
+        assert(String::length(&s) == 4, 22);
+        assert(!String::is_empty(&s), 23);
+    }
+
+    #[test]
+    #[expected_failure(abort_code = 263)]
+    fun test_invalid_utf8() {
+        let no_sparkle_heart = x"009f9296";
+        let s = String::utf8(no_sparkle_heart);
+        assert(String::length(&s) == 1, 22);
+    }
+
+    #[test]
+    fun test_try_utf8() {
+        assert(Option::is_some(&String::try_utf8(b"abc")), 22);
+        assert(Option::is_none(&String::try_utf8(x"c0")), 23);
+    }
+
+    #[test]
+    fun test_empty() {
+        let s = String::utf8(b"");
+        assert(String::is_empty(&s), 22);
+        assert(String::length(&s) == 0, 23);
+    }
+
+    #[test]
+    fun test_sub_string() {
+        let s = String::utf8(b"abcd");
+        let sub = String::sub_string(&s, 2, 4);
+        assert(sub == String::utf8(b"cd"), 22);
+        let sub = String::sub_string(&s, 0, 0);
+        assert(String::is_empty(&sub), 23);
+    }
+
+    #[test]
+    #[expected_failure(abort_code = 519)]
+    fun test_sub_string_invalid_boundary() {
+        let sparkle_heart = x"f09f9296";
+        let s = String::utf8(sparkle_heart);
+        let _sub = String::sub_string(&s, 1, 4);
+    }
+
+    #[test]
+    #[expected_failure(abort_code = 519)]
+    fun test_sub_string_invalid_index() {
+        let s = String::utf8(b"abcd");
+        let _sub = String::sub_string(&s, 4, 5);
+    }
+
+    #[test]
+    #[expected_failure(abort_code = 519)]
+    fun test_sub_string_out_of_order() {
+        let s = String::utf8(b"abcd");
+        let _sub = String::sub_string(&s, 3, 2);
+    }
+
+    #[test]
+    fun test_index_of() {
+        let s = String::utf8(b"abcd");
+        let r = String::utf8(b"bc");
+        let p = String::utf8(b"bd");
+        let e = String::utf8(b"");
+        assert(String::index_of(&s, &r) == 1, 22);
+        assert(String::index_of(&s, &p) == 4, 23);
+        assert(String::index_of(&s, &e) == 0, 24);
+    }
+
+    #[test]
+    fun test_append() {
+        let s = String::utf8(b"abcd");
+        String::append(&mut s, String::utf8(b"ef"));
+        assert(copy s == String::utf8(b"abcdef"), 22);
+        String::append_utf8(&mut s, b"gh");
+        assert(*String::bytes(&s) == b"abcdefgh", 23);
+    }
+
+    #[test]
+    #[expected_failure(abort_code = 263)]
+    fun test_append_invalid_utf8() {
+        let s = String::utf8(b"abcd");
+        String::append_utf8(&mut s, x"c0");
+    }
+
+    #[test]
+    fun test_insert() {
+        let s = String::utf8(b"abcd");
+        String::insert(&mut s, 1, String::utf8(b"xy"));
+        assert(copy s == String::utf8(b"axybcd"), 22);
+        String::insert(&mut s, 6, String::utf8(b"z"));
+        assert(copy s == String::utf8(b"axybcdz"), 23);
+        String::insert(&mut s, 0, String::utf8(b"w"));
+        assert(s == String::utf8(b"waxybcdz"), 24);
+    }
+
+    #[test]
+    #[expected_failure(abort_code = 519)]
+    fun test_insert_invalid_index() {
+        let s = String::utf8(b"abcd");
+        String::insert(&mut s, 5, String::utf8(b"xy"));
+    }
+}
@@ -0,0 +1,141 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::compiler::{as_module, compile_units};
+use move_core_types::{
+    account_address::AccountAddress,
+    identifier::Identifier,
+    language_storage::{ModuleId, StructTag, TypeTag},
+    value::{serialize_values, MoveValue},
+};
+use move_vm_runtime::{
+    execution_trace::{
+        set_trace_sink, take_trace_sink, BcsTraceWriter, JsonTraceWriter, ResourceAccessKind,
+        ResourceAccessTrace, TraceBuffer, TraceEvent, TraceFormat, TraceSink,
+    },
+    move_vm::MoveVM,
+};
+use move_vm_test_utils::InMemoryStorage;
+use move_vm_types::gas_schedule::GasStatus;
+
+const TEST_ADDR: AccountAddress = AccountAddress::new([42; AccountAddress::LENGTH]);
+
+fn trace_functions(functions: &[(&str, MoveValue)]) -> Vec<TraceEvent> {
+    let code = r#"
+        module {{ADDR}}::M {
+            struct Foo has key { a: bool }
+            public fun flip(addr: address) acquires Foo {
+                let f_ref = borrow_global_mut<Foo>(addr);
+                f_ref.a = !f_ref.a;
+            }
+            public fun publish(addr: &signer) {
+                move_to(addr, Foo { a: true} )
+            }
+        }
+    "#;
+
+    let code = code.replace("{{ADDR}}", &format!("0x{}", TEST_ADDR.to_string()));
+    let mut units = compile_units(&code).unwrap();
+    let m = as_module(units.pop().unwrap());
+    let mut blob = vec![];
+    m.serialize(&mut blob).unwrap();
+
+    let mut storage = InMemoryStorage::new();
+    let module_id = ModuleId::new(TEST_ADDR, Identifier::new("M").unwrap());
+    storage.publish_or_overwrite_module(module_id.clone(), blob);
+
+    let vm = MoveVM::new(vec![]).unwrap();
+    let mut sess = vm.new_session(&storage);
+    let mut gas_status = GasStatus::new_unmetered();
+
+    let buffer = TraceBuffer::new();
+    assert!(set_trace_sink(Box::new(buffer.clone())).is_none());
+    for (function, arg) in functions {
+        sess.execute_function(
+            &module_id,
+            &Identifier::new(*function).unwrap(),
+            vec![],
+            serialize_values(&vec![arg.clone()]),
+            &mut gas_status,
+        )
+        .unwrap();
+    }
+    assert!(take_trace_sink().is_some());
+    buffer.events()
+}
+
+fn foo_tag() -> TypeTag {
+    TypeTag::Struct(StructTag {
+        address: TEST_ADDR,
+        module: Identifier::new("M").unwrap(),
+        name: Identifier::new("Foo").unwrap(),
+        type_params: vec![],
+    })
+}
+
+#[test]
+fn trace_instructions_and_resource_accesses() {
+    let account = AccountAddress::random();
+    let events = trace_functions(&[
+        ("publish", MoveValue::Signer(account)),
+        ("flip", MoveValue::Address(account)),
+    ]);
+
+    let instructions: Vec<_> = events
+        .iter()
+        .filter_map(|event| match event {
+            TraceEvent::Instruction(instruction) => Some(instruction),
+            _ => None,
+        })
+        .collect();
+    let first = instructions[0];
+    assert_eq!(first.call_depth, 0);
+    assert_eq!(first.function, format!("0x{}::M::publish", TEST_ADDR));
+    assert_eq!(first.pc, 0);
+    assert_eq!(first.operand_stack_size, 0);
+    assert!(first.locals[0].is_some());
+    assert!(instructions
+        .iter()
+        .any(|instruction| instruction.function.ends_with("::M::flip")
+            && instruction.instruction.starts_with("MutBorrowGlobal")));
+
+    let accesses: Vec<_> = events
+        .iter()
+        .filter_map(|event| match event {
+            TraceEvent::ResourceAccess(access) => Some(access.clone()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        accesses,
+        vec![
+            ResourceAccessTrace {
+                kind: ResourceAccessKind::MoveTo,
+                address: account,
+                resource_type: foo_tag(),
+            },
+            ResourceAccessTrace {
+                kind: ResourceAccessKind::MutBorrow,
+                address: account,
+                resource_type: foo_tag(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn trace_format_round_trip() {
+    let events = trace_functions(&[("publish", MoveValue::Signer(AccountAddress::random()))]);
+
+    let mut json = vec![];
+    let mut writer = JsonTraceWriter::new(&mut json);
+    events.iter().for_each(|event| writer.record(event));
+    writer.finish().unwrap();
+    assert_eq!(TraceFormat::Json.read(json.as_slice()).unwrap(), events);
+
+    let mut bcs = vec![];
+    let mut writer = BcsTraceWriter::new(&mut bcs);
+    events.iter().for_each(|event| writer.record(event));
+    writer.finish().unwrap();
+    assert_eq!(TraceFormat::Bcs.read(bcs.as_slice()).unwrap(), events);
+}
@@ -0,0 +1,101 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::compiler::{as_module, compile_units};
+use move_core_types::{
+    account_address::AccountAddress,
+    gas_schedule::{GasAlgebra, GasUnits},
+    identifier::Identifier,
+    language_storage::ModuleId,
+    value::{serialize_values, MoveValue},
+};
+use move_vm_runtime::move_vm::MoveVM;
+use move_vm_test_utils::InMemoryStorage;
+use move_vm_types::{
+    gas_profiler::{self, GasProfile},
+    gas_schedule::{GasStatus, INITIAL_GAS_SCHEDULE},
+};
+
+const TEST_ADDR: AccountAddress = AccountAddress::new([42; AccountAddress::LENGTH]);
+
+fn profile_run(gas_budget: u64) -> (bool, GasProfile) {
+    let code = r#"
+        module {{ADDR}}::M {
+            struct Foo has key { a: u64 }
+            fun add(x: u64): u64 {
+                x + 1
+            }
+            public fun run(s: &signer) {
+                let i = 0;
+                while (i < 1000) i = add(i);
+                move_to(s, Foo { a: i })
+            }
+        }
+    "#;
+
+    let code = code.replace("{{ADDR}}", &format!("0x{}", TEST_ADDR.to_string()));
+    let mut units = compile_units(&code).unwrap();
+    let m = as_module(units.pop().unwrap());
+    let mut blob = vec![];
+    m.serialize(&mut blob).unwrap();
+
+    let mut storage = InMemoryStorage::new();
+    let module_id = ModuleId::new(TEST_ADDR, Identifier::new("M").unwrap());
+    storage.publish_or_overwrite_module(module_id.clone(), blob);
+
+    let vm = MoveVM::new(vec![]).unwrap();
+    let mut sess = vm.new_session(&storage);
+
+    gas_profiler::start_gas_profiling();
+    let mut gas_status = GasStatus::new(&INITIAL_GAS_SCHEDULE, GasUnits::new(gas_budget));
+    let result = sess.execute_function(
+        &module_id,
+        &Identifier::new("run").unwrap(),
+        vec![],
+        serialize_values(&vec![MoveValue::Signer(AccountAddress::random())]),
+        &mut gas_status,
+    );
+    (
+        result.is_ok(),
+        gas_profiler::finish_gas_profiling().unwrap(),
+    )
+}
+
+#[test]
+fn profile_call_stacks() {
+    let (success, profile) = profile_run(1_000_000);
+    assert!(success);
+
+    let run = format!("0x{}::M::run", TEST_ADDR);
+    let add = format!("0x{}::M::add", TEST_ADDR);
+    let gas_of = |stack: &[&str]| {
+        profile
+            .stacks()
+            .find(|(s, _)| s.iter().map(String::as_str).eq(stack.iter().copied()))
+            .map_or(0, |(_, gas)| gas)
+    };
+    assert!(gas_of(&[&run]) > 0);
+    assert!(gas_of(&[&run, &add]) > 0);
+    assert!(gas_of(&[&run, "[storage] MOVE_TO"]) > 0);
+    assert_eq!(profile.stacks().count(), 3);
+
+    let summary = profile.summary();
+    assert_eq!(summary[0].function, run);
+    assert_eq!(summary[0].inclusive, profile.total_gas());
+    assert!(profile
+        .to_folded_stacks()
+        .contains(&format!("{};{} ", run, add)));
+}
+
+#[test]
+fn profile_out_of_gas() {
+    let (success, profile) = profile_run(1);
+    assert!(!success);
+    // The gas consumed until execution ran out of gas is still attributed.
+    let budget = INITIAL_GAS_SCHEDULE
+        .gas_constants
+        .to_internal_units(GasUnits::new(1))
+        .get();
+    assert_eq!(profile.total_gas(), budget);
+    assert!(!gas_profiler::is_profiling());
+}
@@ -8,7 +8,7 @@ use move_core_types::{
     identifier::{IdentStr, Identifier},
     language_storage::ModuleId,
 };
-use move_vm_runtime::move_vm::MoveVM;
+use move_vm_runtime::move_vm::{CacheConfig, MoveVM};
 use move_vm_test_utils::InMemoryStorage;
 use move_vm_types::gas_schedule::GasStatus;
 use std::{path::PathBuf, sync::Arc, thread};
@@ -24,6 +24,10 @@ struct Adapter {
 
 impl Adapter {
     fn new(store: InMemoryStorage) -> Self {
+        Self::new_with_cache_config(store, CacheConfig::default())
+    }
+
+    fn new_with_cache_config(store: InMemoryStorage, cache_config: CacheConfig) -> Self {
         let functions = vec![
             (
                 ModuleId::new(WORKING_ACCOUNT, Identifier::new("A").unwrap()),
@@ -48,7 +52,7 @@ impl Adapter {
         ];
         Self {
             store,
-            vm: Arc::new(MoveVM::new(vec![]).unwrap()),
+            vm: Arc::new(MoveVM::new_with_cache_config(vec![], cache_config).unwrap()),
             functions,
         }
     }
@@ -143,3 +147,95 @@ fn load_concurrent_many() {
     // makes 150 threads
     adapter.call_functions_async(30);
 }
+
+#[test]
+fn cache_stats() {
+    let data_store = InMemoryStorage::new();
+    let mut adapter = Adapter::new(data_store);
+    let modules = get_modules();
+    adapter.publish_modules(modules);
+    adapter.call_functions();
+    let first_run = adapter.vm.cache_stats();
+    assert!(first_run.module_misses > 0);
+    assert!(first_run.cached_modules > 0);
+    assert!(first_run.cached_module_bytes > 0);
+    // the loaded code takes more memory than its serialized form
+    assert!(first_run.cached_module_memory > first_run.cached_module_bytes);
+    assert!(first_run.cached_functions > 0);
+
+    // everything is loaded already, and nothing gets evicted from an unbounded cache
+    adapter.call_functions();
+    let second_run = adapter.vm.cache_stats();
+    assert_eq!(second_run.module_misses, first_run.module_misses);
+    assert!(second_run.module_hits > first_run.module_hits);
+    assert_eq!(second_run.cached_modules, first_run.cached_modules);
+    assert_eq!(
+        second_run.cached_module_memory,
+        first_run.cached_module_memory
+    );
+    assert_eq!(second_run.modules_evicted, 0);
+    assert!(second_run.module_hit_rate() > first_run.module_hit_rate());
+}
+
+#[test]
+fn load_with_bounded_cache() {
+    let data_store = InMemoryStorage::new();
+    let mut adapter = Adapter::new_with_cache_config(
+        data_store,
+        CacheConfig {
+            max_modules: 2,
+            ..CacheConfig::default()
+        },
+    );
+    let modules = get_modules();
+    adapter.publish_modules(modules);
+    // every call needs more modules than the cache holds, so they get evicted and loaded again
+    adapter.call_functions();
+    adapter.call_functions();
+    let _session = adapter.vm.new_session(&adapter.store);
+    let stats = adapter.vm.cache_stats();
+    assert!(stats.modules_evicted > 0);
+    assert!(stats.cached_modules <= 2);
+}
+
+#[test]
+fn load_with_bounded_cache_size() {
+    let data_store = InMemoryStorage::new();
+    let mut adapter = Adapter::new_with_cache_config(
+        data_store,
+        CacheConfig {
+            max_module_bytes: 1,
+            ..CacheConfig::default()
+        },
+    );
+    let modules = get_modules();
+    adapter.publish_modules(modules);
+    adapter.call_functions();
+    let _session = adapter.vm.new_session(&adapter.store);
+    let stats = adapter.vm.cache_stats();
+    assert!(stats.modules_evicted > 0);
+    assert_eq!(stats.cached_modules, 0);
+    assert_eq!(stats.cached_module_bytes, 0);
+    assert_eq!(stats.cached_module_memory, 0);
+    assert_eq!(stats.cached_structs, 0);
+    assert_eq!(stats.cached_functions, 0);
+    assert_eq!(stats.cached_type_infos, 0);
+    assert_eq!(stats.cached_type_info_memory, 0);
+}
+
+#[test]
+fn load_concurrent_with_bounded_cache() {
+    let data_store = InMemoryStorage::new();
+    let mut adapter = Adapter::new_with_cache_config(
+        data_store,
+        CacheConfig {
+            max_modules: 3,
+            ..CacheConfig::default()
+        },
+    );
+    let modules = get_modules();
+    adapter.publish_modules(modules);
+    // makes 150 threads
+    adapter.call_functions_async(30);
+    adapter.call_functions();
+}
@@ -1,6 +1,11 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+//! The bytecode stepper of debug builds, enabled by the `MOVE_VM_STEP` environment variable.
+//!
+//! Deprecated: the source-level debugger of the `move-debugger` crate (e.g.
+//! `move sandbox run --debug`) supersedes it. It is kept until its users have moved over.
+
 use crate::{
     interpreter::Interpreter,
     loader::{Function, Loader},
@@ -90,6 +95,7 @@ pub(crate) struct DebugContext {
 
 impl DebugContext {
     pub(crate) fn new() -> Self {
+        println!("MOVE_VM_STEP is deprecated, use `move sandbox run --debug` instead");
         Self {
             breakpoints: BTreeSet::new(),
             should_take_input: true,
@@ -0,0 +1,122 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Hooks for interactive debuggers.
+//!
+//! When a `Debugger` is attached to a thread (see `set_debugger`), the interpreter hands it a
+//! `DebugState` before each instruction of the Move code executed by that thread, and when an
+//! instruction fails. Execution resumes once the debugger returns, so a debugger pauses the VM
+//! simply by blocking, e.g. while it waits for user commands.
+//!
+//! The VM only knows about bytecode: mapping code offsets back to source lines and local
+//! indices back to names is left to the debugger, typically by means of the source maps
+//! produced by the compiler.
+
+use move_binary_format::{
+    errors::{PartialVMError, VMResult},
+    file_format::{Bytecode, FunctionDefinitionIndex},
+};
+use move_core_types::{
+    account_address::AccountAddress,
+    language_storage::{ModuleId, StructTag, TypeTag},
+};
+use std::{cell::RefCell, io};
+
+/// A frame of the call stack, as seen by a debugger.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FrameInfo {
+    /// The module defining the function, or None for a script.
+    pub module: Option<ModuleId>,
+    pub function_index: FunctionDefinitionIndex,
+    /// The fully qualified name of the function, e.g. `0x1::Vector::length`.
+    pub function: String,
+    pub type_arguments: Vec<TypeTag>,
+    /// The offset of the instruction being executed, or of the call being made by the frame.
+    pub pc: u16,
+    /// The value of each parameter and local, or None if the local is invalid (not yet assigned
+    /// or moved out).
+    pub locals: Vec<Option<String>>,
+}
+
+/// The state of a paused VM.
+pub trait DebugState {
+    /// The number of frames below the current one on the call stack (0 for the entry function).
+    fn call_depth(&self) -> usize;
+
+    /// The module of the function being executed, or None for a script.
+    fn module(&self) -> Option<&ModuleId>;
+
+    fn function_index(&self) -> FunctionDefinitionIndex;
+
+    /// The name of the function being executed, i.e. `main` for a script.
+    fn function_name(&self) -> &str;
+
+    fn pc(&self) -> u16;
+
+    /// The instruction about to be executed, or the one that failed.
+    fn instruction(&self) -> &Bytecode;
+
+    /// The frames of the call stack, outermost first: the last frame is the current one.
+    fn call_stack(&self) -> Vec<FrameInfo>;
+
+    /// The values on the operand stack, bottom first.
+    fn operand_stack(&self) -> Vec<String>;
+
+    /// Prints the resource of type `resource_type` published under `address`, as seen by the
+    /// code being executed, or returns None if there is no such resource.
+    fn read_resource(
+        &mut self,
+        address: AccountAddress,
+        resource_type: &StructTag,
+    ) -> VMResult<Option<String>>;
+}
+
+/// An interactive debugger driving the execution of Move code.
+pub trait Debugger {
+    /// Called before each instruction is executed.
+    fn on_instruction(&mut self, state: &mut dyn DebugState);
+
+    /// Called when an instruction fails, before the call stack is unwound.
+    fn on_error(&mut self, _state: &mut dyn DebugState, _error: &PartialVMError) {}
+
+    /// Called by the embedder once it is done with the debugger, e.g. to let the user know that
+    /// execution is over. The VM never calls it.
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+thread_local! {
+    static DEBUGGER: RefCell<Option<Box<dyn Debugger>>> = RefCell::new(None);
+}
+
+/// Attaches `debugger` to the Move code executed by the current thread, and returns the
+/// previously attached debugger, if any.
+pub fn set_debugger(debugger: Box<dyn Debugger>) -> Option<Box<dyn Debugger>> {
+    DEBUGGER.with(|current| current.borrow_mut().replace(debugger))
+}
+
+/// Detaches and returns the debugger of the current thread, if any.
+pub fn take_debugger() -> Option<Box<dyn Debugger>> {
+    DEBUGGER.with(|current| current.borrow_mut().take())
+}
+
+pub(crate) fn is_debugging() -> bool {
+    DEBUGGER.with(|current| current.borrow().is_some())
+}
+
+pub(crate) fn on_instruction(state: &mut dyn DebugState) {
+    DEBUGGER.with(|current| {
+        if let Some(debugger) = current.borrow_mut().as_mut() {
+            debugger.on_instruction(state)
+        }
+    })
+}
+
+pub(crate) fn on_error(state: &mut dyn DebugState, error: &PartialVMError) {
+    DEBUGGER.with(|current| {
+        if let Some(debugger) = current.borrow_mut().as_mut() {
+            debugger.on_error(state, error)
+        }
+    })
+}
@@ -0,0 +1,303 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Structured, instruction-level tracing of Move execution.
+//!
+//! When a `TraceSink` is installed on a thread (see `set_trace_sink`), every Move function
+//! executed by that thread emits a `TraceEvent` before each instruction, and one after each
+//! access to global storage. The Move VM has no notion of transactions, so the executor of a
+//! block brackets the events of each of its transactions with `TransactionBegin` and
+//! `TransactionEnd` events (see `record`). The trace can be written in two formats:
+//!
+//! - JSON (`TraceFormat::Json`): one JSON-serialized `TraceEvent` per line (JSON Lines).
+//! - Binary (`TraceFormat::Bcs`): a sequence of records, each made of the length of the
+//!   BCS-serialized `TraceEvent` as a little-endian `u32`, followed by the BCS bytes.
+//!
+//! Tracing is independent of the `MOVE_VM_TRACE` text trace of debug builds, which is kept for
+//! the coverage tools.
+
+use move_core_types::{account_address::AccountAddress, language_storage::TypeTag};
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::RefCell,
+    convert::TryFrom,
+    fmt,
+    io::{self, BufRead, Read, Write},
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+/// The maximum number of values from the top of the operand stack included in a trace event.
+pub const OPERAND_STACK_SUMMARY_SIZE: usize = 4;
+
+/// An event of the execution trace.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum TraceEvent {
+    /// The state of the VM right before an instruction is executed.
+    Instruction(InstructionTrace),
+    /// An access to a resource in global storage, emitted after the instruction performing it.
+    ResourceAccess(ResourceAccessTrace),
+    /// The start of a transaction. The events up to the matching `TransactionEnd` belong to it.
+    TransactionBegin(TransactionTrace),
+    /// The end of a transaction.
+    TransactionEnd(TransactionTrace),
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct InstructionTrace {
+    /// The number of frames below the current one on the call stack (0 for the entry function).
+    pub call_depth: u64,
+    /// The fully qualified name of the function being executed, e.g. `0x1::Vector::length`.
+    pub function: String,
+    pub type_arguments: Vec<TypeTag>,
+    pub pc: u16,
+    pub instruction: String,
+    pub operand_stack_size: u64,
+    /// The values at the top of the operand stack, topmost first. At most
+    /// `OPERAND_STACK_SUMMARY_SIZE` values are included.
+    pub operand_stack_top: Vec<String>,
+    /// The value of each local, or None if the local is invalid (not yet assigned or moved out).
+    pub locals: Vec<Option<String>>,
+    /// The gas remaining before the instruction is charged.
+    pub gas_remaining: u64,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum ResourceAccessKind {
+    Exists,
+    ImmBorrow,
+    MutBorrow,
+    MoveFrom,
+    MoveTo,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ResourceAccessTrace {
+    pub kind: ResourceAccessKind,
+    pub address: AccountAddress,
+    pub resource_type: TypeTag,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TransactionTrace {
+    /// The version of the transaction, if known to the executor of the block.
+    pub version: Option<u64>,
+    /// The hex-encoded hash of the transaction.
+    pub hash: String,
+}
+
+/// A destination for trace events.
+pub trait TraceSink {
+    /// Records an event. Sinks must not fail execution, so errors are reported by `finish`.
+    fn record(&mut self, event: &TraceEvent);
+
+    /// Flushes the recorded events, and returns the first error hit while recording them.
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+thread_local! {
+    static TRACE_SINK: RefCell<Option<Box<dyn TraceSink>>> = RefCell::new(None);
+}
+
+/// Installs `sink` to receive the trace of the Move code executed by the current thread, and
+/// returns the previously installed sink, if any.
+pub fn set_trace_sink(sink: Box<dyn TraceSink>) -> Option<Box<dyn TraceSink>> {
+    TRACE_SINK.with(|current| current.borrow_mut().replace(sink))
+}
+
+/// Uninstalls and returns the sink of the current thread, if any.
+pub fn take_trace_sink() -> Option<Box<dyn TraceSink>> {
+    TRACE_SINK.with(|current| current.borrow_mut().take())
+}
+
+/// Returns true if a sink is installed on the current thread.
+pub fn is_tracing() -> bool {
+    TRACE_SINK.with(|current| current.borrow().is_some())
+}
+
+/// Sends `event` to the sink of the current thread, if any.
+pub fn record(event: TraceEvent) {
+    TRACE_SINK.with(|current| {
+        if let Some(sink) = current.borrow_mut().as_mut() {
+            sink.record(&event)
+        }
+    })
+}
+
+/// The serialization formats of a trace. See the module documentation for their layout.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TraceFormat {
+    Json,
+    Bcs,
+}
+
+impl TraceFormat {
+    /// Returns a sink writing the events to `writer` in this format.
+    pub fn writer<W: Write + 'static>(self, writer: W) -> Box<dyn TraceSink> {
+        match self {
+            TraceFormat::Json => Box::new(JsonTraceWriter::new(writer)),
+            TraceFormat::Bcs => Box::new(BcsTraceWriter::new(writer)),
+        }
+    }
+
+    /// Reads all the events of a trace written in this format.
+    pub fn read<R: BufRead>(self, reader: R) -> io::Result<Vec<TraceEvent>> {
+        match self {
+            TraceFormat::Json => read_json_trace(reader),
+            TraceFormat::Bcs => read_bcs_trace(reader),
+        }
+    }
+}
+
+impl FromStr for TraceFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(TraceFormat::Json),
+            "bcs" => Ok(TraceFormat::Bcs),
+            _ => Err(format!(
+                "Unknown trace format '{}', expected 'json' or 'bcs'",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for TraceFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TraceFormat::Json => write!(f, "json"),
+            TraceFormat::Bcs => write!(f, "bcs"),
+        }
+    }
+}
+
+/// Writes events as JSON Lines.
+pub struct JsonTraceWriter<W> {
+    writer: W,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> JsonTraceWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            error: None,
+        }
+    }
+
+    fn write_event(&mut self, event: &TraceEvent) -> io::Result<()> {
+        serde_json::to_writer(&mut self.writer, event)?;
+        writeln!(self.writer)
+    }
+}
+
+impl<W: Write> TraceSink for JsonTraceWriter<W> {
+    fn record(&mut self, event: &TraceEvent) {
+        if self.error.is_none() {
+            self.error = self.write_event(event).err();
+        }
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        match self.error.take() {
+            Some(err) => Err(err),
+            None => self.writer.flush(),
+        }
+    }
+}
+
+/// Writes events as length-prefixed BCS records.
+pub struct BcsTraceWriter<W> {
+    writer: W,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> BcsTraceWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            error: None,
+        }
+    }
+
+    fn write_event(&mut self, event: &TraceEvent) -> io::Result<()> {
+        let bytes =
+            bcs::to_bytes(event).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        let len = u32::try_from(bytes.len())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        self.writer.write_all(&len.to_le_bytes())?;
+        self.writer.write_all(&bytes)
+    }
+}
+
+impl<W: Write> TraceSink for BcsTraceWriter<W> {
+    fn record(&mut self, event: &TraceEvent) {
+        if self.error.is_none() {
+            self.error = self.write_event(event).err();
+        }
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        match self.error.take() {
+            Some(err) => Err(err),
+            None => self.writer.flush(),
+        }
+    }
+}
+
+/// Keeps events in memory. Clones share the same events, so that they can be inspected after
+/// the sink has been installed.
+#[derive(Clone, Debug, Default)]
+pub struct TraceBuffer(Arc<Mutex<Vec<TraceEvent>>>);
+
+impl TraceBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn events(&self) -> Vec<TraceEvent> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+impl TraceSink for TraceBuffer {
+    fn record(&mut self, event: &TraceEvent) {
+        self.0.lock().unwrap().push(event.clone())
+    }
+}
+
+/// Reads a trace written by `JsonTraceWriter`.
+pub fn read_json_trace<R: BufRead>(reader: R) -> io::Result<Vec<TraceEvent>> {
+    reader
+        .lines()
+        .filter(|line| !matches!(line, Ok(line) if line.trim().is_empty()))
+        .map(|line| {
+            serde_json::from_str(&line?)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+        })
+        .collect()
+}
+
+/// Reads a trace written by `BcsTraceWriter`.
+pub fn read_bcs_trace<R: Read>(mut reader: R) -> io::Result<Vec<TraceEvent>> {
+    let mut events = vec![];
+    let mut len_bytes = [0u8; 4];
+    loop {
+        // The trace ends when the length of the next record cannot be read.
+        match reader.read_exact(&mut len_bytes) {
+            Ok(()) => (),
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(events),
+            Err(err) => return Err(err),
+        }
+        let mut bytes = vec![0u8; u32::from_le_bytes(len_bytes) as usize];
+        reader.read_exact(&mut bytes)?;
+        events.push(
+            bcs::from_bytes(&bytes)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?,
+        );
+    }
+}
@@ -2,6 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    execution_trace::{
+        self, InstructionTrace, ResourceAccessKind, ResourceAccessTrace, TraceEvent,
+    },
     loader::{Function, Loader, Resolver},
     native_functions::NativeContext,
     trace,
@@ -19,6 +22,7 @@ use move_core_types::{
 };
 use move_vm_types::{
     data_store::DataStore,
+    gas_profiler,
     gas_schedule::GasStatus,
     loaded_data::runtime_types::Type,
     values::{
@@ -29,6 +33,14 @@ use move_vm_types::{
 use std::{cmp::min, collections::VecDeque, fmt::Write, mem, sync::Arc};
 use tracing::error;
 
+#[cfg(feature = "debugging")]
+use crate::debugger::{self, DebugState, FrameInfo};
+#[cfg(feature = "debugging")]
+use ::{
+    move_binary_format::file_format::FunctionDefinitionIndex,
+    move_core_types::language_storage::{ModuleId, StructTag, TypeTag},
+};
+
 macro_rules! debug_write {
     ($($toks: tt)*) => {
         write!($($toks)*).map_err(|_|
@@ -63,6 +75,13 @@ pub(crate) struct Interpreter {
     operand_stack: Stack,
     /// The stack of active functions.
     call_stack: CallStack,
+    /// Whether a trace sink is installed on the current thread, checked once per execution.
+    tracing: bool,
+    /// Whether gas is being profiled on the current thread, checked once per execution.
+    profiling: bool,
+    /// Whether a debugger is attached to the current thread, checked once per execution.
+    #[cfg(feature = "debugging")]
+    debugging: bool,
 }
 
 impl Interpreter {
@@ -79,7 +98,13 @@ impl Interpreter {
         // We count the intrinsic cost of the transaction here, since that needs to also cover the
         // setup of the function.
         let mut interp = Self::new();
-        interp.execute(loader, data_store, gas_status, function, ty_args, args)
+        let profiler_depth = gas_profiler::depth();
+        let result = interp.execute(loader, data_store, gas_status, function, ty_args, args);
+        if interp.profiling {
+            // Unwind the frames left over by a failed execution.
+            gas_profiler::truncate(profiler_depth);
+        }
+        result
     }
 
     /// Create a new instance of an `Interpreter` in the context of a transaction with a
@@ -88,6 +113,10 @@ impl Interpreter {
         Interpreter {
             operand_stack: Stack::new(),
             call_stack: CallStack::new(),
+            tracing: execution_trace::is_tracing(),
+            profiling: gas_profiler::is_profiling(),
+            #[cfg(feature = "debugging")]
+            debugging: debugger::is_debugging(),
         }
     }
 
@@ -130,6 +159,7 @@ impl Interpreter {
                 .map_err(|e| self.set_location(e))?;
         }
 
+        self.profile_enter(&function);
         let mut current_frame = Frame::new(function, ty_args, locals);
         loop {
             let resolver = current_frame.resolver(loader);
@@ -138,6 +168,7 @@ impl Interpreter {
                 .map_err(|err| self.maybe_core_dump(err, &current_frame))?;
             match exit_code {
                 ExitCode::Return => {
+                    self.profile_exit();
                     if let Some(frame) = self.call_stack.pop() {
                         current_frame = frame;
                         current_frame.pc += 1; // advance past the Call instruction in the caller
@@ -169,6 +200,7 @@ impl Interpreter {
                         let err = set_err_info!(frame, err);
                         self.maybe_core_dump(err, &frame)
                     })?;
+                    self.profile_enter(&frame.function);
                     current_frame = frame;
                 }
                 ExitCode::CallGeneric(idx) => {
@@ -202,6 +234,7 @@ impl Interpreter {
                         let err = set_err_info!(frame, err);
                         self.maybe_core_dump(err, &frame)
                     })?;
+                    self.profile_enter(&frame.function);
                     current_frame = frame;
                 }
             }
@@ -237,19 +270,20 @@ impl Interpreter {
         ty_args: Vec<Type>,
     ) -> VMResult<()> {
         // Note: refactor if native functions push a frame on the stack
-        self.call_native_impl(resolver, data_store, gas_status, function.clone(), ty_args)
-            .map_err(|e| match function.module_id() {
-                Some(id) => e
-                    .at_code_offset(function.index(), 0)
-                    .finish(Location::Module(id.clone())),
-                None => {
-                    let err = PartialVMError::new(StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR)
-                        .with_message(
-                            "Unexpected native function not located in a module".to_owned(),
-                        );
-                    self.set_location(err)
-                }
-            })
+        self.profile_enter(&function);
+        let result =
+            self.call_native_impl(resolver, data_store, gas_status, function.clone(), ty_args);
+        self.profile_exit();
+        result.map_err(|e| match function.module_id() {
+            Some(id) => e
+                .at_code_offset(function.index(), 0)
+                .finish(Location::Module(id.clone())),
+            None => {
+                let err = PartialVMError::new(StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR)
+                    .with_message("Unexpected native function not located in a module".to_owned());
+                self.set_location(err)
+            }
+        })
     }
 
     fn call_native_impl(
@@ -335,12 +369,20 @@ impl Interpreter {
     fn borrow_global(
         &mut self,
         data_store: &mut impl DataStore,
+        loader: &Loader,
+        is_mut: bool,
         addr: AccountAddress,
         ty: &Type,
     ) -> PartialVMResult<AbstractMemorySize<GasCarrier>> {
         let g = Self::load_resource(data_store, addr, ty)?.borrow_global()?;
         let size = g.size();
         self.operand_stack.push(g)?;
+        let kind = if is_mut {
+            ResourceAccessKind::MutBorrow
+        } else {
+            ResourceAccessKind::ImmBorrow
+        };
+        self.trace_resource_access(loader, kind, addr, ty);
         Ok(size)
     }
 
@@ -348,6 +390,7 @@ impl Interpreter {
     fn exists(
         &mut self,
         data_store: &mut impl DataStore,
+        loader: &Loader,
         addr: AccountAddress,
         ty: &Type,
     ) -> PartialVMResult<AbstractMemorySize<GasCarrier>> {
@@ -355,6 +398,7 @@ impl Interpreter {
         let mem_size = gv.size();
         let exists = gv.exists()?;
         self.operand_stack.push(Value::bool(exists))?;
+        self.trace_resource_access(loader, ResourceAccessKind::Exists, addr, ty);
         Ok(mem_size)
     }
 
@@ -362,12 +406,14 @@ impl Interpreter {
     fn move_from(
         &mut self,
         data_store: &mut impl DataStore,
+        loader: &Loader,
         addr: AccountAddress,
         ty: &Type,
     ) -> PartialVMResult<AbstractMemorySize<GasCarrier>> {
         let resource = Self::load_resource(data_store, addr, ty)?.move_from()?;
         let size = resource.size();
         self.operand_stack.push(resource)?;
+        self.trace_resource_access(loader, ResourceAccessKind::MoveFrom, addr, ty);
         Ok(size)
     }
 
@@ -375,15 +421,136 @@ impl Interpreter {
     fn move_to(
         &mut self,
         data_store: &mut impl DataStore,
+        loader: &Loader,
         addr: AccountAddress,
         ty: &Type,
         resource: Value,
     ) -> PartialVMResult<AbstractMemorySize<GasCarrier>> {
         let size = resource.size();
         Self::load_resource(data_store, addr, ty)?.move_to(resource)?;
+        self.trace_resource_access(loader, ResourceAccessKind::MoveTo, addr, ty);
         Ok(size)
     }
 
+    //
+    // Gas profiling.
+    //
+
+    /// Pushes the profiled frame of a function being called.
+    fn profile_enter(&self, function: &Function) {
+        if self.profiling {
+            gas_profiler::push_frame(function.pretty_string());
+        }
+    }
+
+    /// Pops the profiled frame of the function returning.
+    fn profile_exit(&self) {
+        if self.profiling {
+            gas_profiler::pop_frame();
+        }
+    }
+
+    //
+    // Execution tracing.
+    //
+
+    /// Records the state of the VM before `instruction` is executed by `frame`.
+    fn trace_instruction(
+        &self,
+        frame: &Frame,
+        loader: &Loader,
+        instruction: &Bytecode,
+        gas_status: &GasStatus,
+    ) {
+        execution_trace::record(TraceEvent::Instruction(InstructionTrace {
+            call_depth: self.call_stack.0.len() as u64,
+            function: frame.function.pretty_string(),
+            type_arguments: frame
+                .ty_args()
+                .iter()
+                .filter_map(|ty| loader.type_to_type_tag(ty).ok())
+                .collect(),
+            pc: frame.pc,
+            instruction: format!("{:?}", instruction),
+            operand_stack_size: self.operand_stack.0.len() as u64,
+            operand_stack_top: self
+                .operand_stack
+                .0
+                .iter()
+                .rev()
+                .take(execution_trace::OPERAND_STACK_SUMMARY_SIZE)
+                .map(value_to_string)
+                .collect(),
+            locals: values::debug::locals_to_strings(&frame.locals).unwrap_or_default(),
+            gas_remaining: gas_status.remaining_gas().get(),
+        }))
+    }
+
+    fn trace_resource_access(
+        &self,
+        loader: &Loader,
+        kind: ResourceAccessKind,
+        address: AccountAddress,
+        ty: &Type,
+    ) {
+        if !self.tracing {
+            return;
+        }
+        if let Ok(resource_type) = loader.type_to_type_tag(ty) {
+            execution_trace::record(TraceEvent::ResourceAccess(ResourceAccessTrace {
+                kind,
+                address,
+                resource_type,
+            }))
+        }
+    }
+
+    //
+    // Interactive debugging.
+    //
+
+    /// Hands the state of the VM to the debugger before `instruction` is executed by `frame`.
+    #[cfg(feature = "debugging")]
+    fn debug_instruction<D: DataStore>(
+        &self,
+        frame: &Frame,
+        loader: &Loader,
+        data_store: &mut D,
+        instruction: &Bytecode,
+    ) {
+        debugger::on_instruction(&mut DebugView {
+            interpreter: self,
+            frame,
+            instruction,
+            loader,
+            data_store,
+        })
+    }
+
+    /// Hands the state of the VM to the debugger after the current instruction of `frame`
+    /// failed with `error`.
+    #[cfg(feature = "debugging")]
+    fn debug_error<D: DataStore>(
+        &self,
+        frame: &Frame,
+        loader: &Loader,
+        data_store: &mut D,
+        error: &PartialVMError,
+    ) {
+        if let Some(instruction) = frame.function.code().get(frame.pc as usize) {
+            debugger::on_error(
+                &mut DebugView {
+                    interpreter: self,
+                    frame,
+                    instruction,
+                    loader,
+                    data_store,
+                },
+                error,
+            )
+        }
+    }
+
     //
     // Debugging and logging helpers.
     //
@@ -547,6 +714,89 @@ impl Interpreter {
     }
 }
 
+/// Prints a value for tracing and debugging, falling back on its `Display` implementation.
+fn value_to_string(value: &Value) -> String {
+    let mut buf = String::new();
+    match values::debug::print_value(&mut buf, value) {
+        Ok(()) => buf,
+        Err(_) => format!("{}", value),
+    }
+}
+
+/// The view of a paused interpreter given to a debugger.
+#[cfg(feature = "debugging")]
+struct DebugView<'a, D> {
+    interpreter: &'a Interpreter,
+    frame: &'a Frame,
+    instruction: &'a Bytecode,
+    loader: &'a Loader,
+    data_store: &'a mut D,
+}
+
+#[cfg(feature = "debugging")]
+impl<D: DataStore> DebugState for DebugView<'_, D> {
+    fn call_depth(&self) -> usize {
+        self.interpreter.call_stack.0.len()
+    }
+
+    fn module(&self) -> Option<&ModuleId> {
+        self.frame.function.module_id()
+    }
+
+    fn function_index(&self) -> FunctionDefinitionIndex {
+        self.frame.function.index()
+    }
+
+    fn function_name(&self) -> &str {
+        self.frame.function.name()
+    }
+
+    fn pc(&self) -> u16 {
+        self.frame.pc
+    }
+
+    fn instruction(&self) -> &Bytecode {
+        self.instruction
+    }
+
+    fn call_stack(&self) -> Vec<FrameInfo> {
+        self.interpreter
+            .call_stack
+            .0
+            .iter()
+            .chain(std::iter::once(self.frame))
+            .map(|frame| frame.debug_info(self.loader))
+            .collect()
+    }
+
+    fn operand_stack(&self) -> Vec<String> {
+        self.interpreter
+            .operand_stack
+            .0
+            .iter()
+            .map(value_to_string)
+            .collect()
+    }
+
+    fn read_resource(
+        &mut self,
+        address: AccountAddress,
+        resource_type: &StructTag,
+    ) -> VMResult<Option<String>> {
+        let ty = self
+            .loader
+            .load_type(&TypeTag::Struct(resource_type.clone()), &*self.data_store)?;
+        let resource = self
+            .data_store
+            .load_resource(address, &ty)
+            .map_err(|e| e.finish(Location::Undefined))?;
+        let mut buf = String::new();
+        let exists = values::debug::print_global_value(&mut buf, resource)
+            .map_err(|e| e.finish(Location::Undefined))?;
+        Ok(if exists { Some(buf) } else { None })
+    }
+}
+
 // TODO Determine stack size limits based on gas limit
 const OPERAND_STACK_SIZE_LIMIT: usize = 1024;
 const CALL_STACK_SIZE_LIMIT: usize = 1024;
@@ -671,6 +921,10 @@ impl Frame {
     ) -> VMResult<ExitCode> {
         self.execute_code_impl(resolver, interpreter, data_store, gas_status)
             .map_err(|e| {
+                #[cfg(feature = "debugging")]
+                if interpreter.debugging {
+                    interpreter.debug_error(self, resolver.loader(), data_store, &e);
+                }
                 e.at_code_offset(self.function.index(), self.pc)
                     .finish(self.location())
             })
@@ -694,6 +948,13 @@ impl Frame {
                     resolver,
                     interpreter
                 );
+                #[cfg(feature = "debugging")]
+                if interpreter.debugging {
+                    interpreter.debug_instruction(self, resolver.loader(), data_store, instruction);
+                }
+                if interpreter.tracing {
+                    interpreter.trace_instruction(self, resolver.loader(), instruction, gas_status);
+                }
 
                 fail_point!("move_vm::interpreter_loop", |_| {
                     Err(
@@ -1016,35 +1277,50 @@ impl Frame {
                             .push(Value::bool(!lhs.equals(&rhs)?))?;
                     }
                     Bytecode::MutBorrowGlobal(sd_idx) | Bytecode::ImmBorrowGlobal(sd_idx) => {
+                        let is_mut = matches!(instruction, Bytecode::MutBorrowGlobal(_));
                         let addr = interpreter.operand_stack.pop_as::<AccountAddress>()?;
                         let ty = resolver.get_struct_type(*sd_idx);
-                        let size = interpreter.borrow_global(data_store, addr, &ty)?;
+                        let size = interpreter.borrow_global(
+                            data_store,
+                            resolver.loader(),
+                            is_mut,
+                            addr,
+                            &ty,
+                        )?;
                         gas_status.charge_instr_with_size(Opcodes::MUT_BORROW_GLOBAL, size)?;
                     }
                     Bytecode::MutBorrowGlobalGeneric(si_idx)
                     | Bytecode::ImmBorrowGlobalGeneric(si_idx) => {
+                        let is_mut = matches!(instruction, Bytecode::MutBorrowGlobalGeneric(_));
                         let addr = interpreter.operand_stack.pop_as::<AccountAddress>()?;
                         let ty = resolver.instantiate_generic_type(*si_idx, self.ty_args())?;
-                        let size = interpreter.borrow_global(data_store, addr, &ty)?;
+                        let size = interpreter.borrow_global(
+                            data_store,
+                            resolver.loader(),
+                            is_mut,
+                            addr,
+                            &ty,
+                        )?;
                         gas_status
                             .charge_instr_with_size(Opcodes::MUT_BORROW_GLOBAL_GENERIC, size)?;
                     }
                     Bytecode::Exists(sd_idx) => {
                         let addr = interpreter.operand_stack.pop_as::<AccountAddress>()?;
                         let ty = resolver.get_struct_type(*sd_idx);
-                        let size = interpreter.exists(data_store, addr, &ty)?;
+                        let size = interpreter.exists(data_store, resolver.loader(), addr, &ty)?;
                         gas_status.charge_instr_with_size(Opcodes::EXISTS, size)?;
                     }
                     Bytecode::ExistsGeneric(si_idx) => {
                         let addr = interpreter.operand_stack.pop_as::<AccountAddress>()?;
                         let ty = resolver.instantiate_generic_type(*si_idx, self.ty_args())?;
-                        let size = interpreter.exists(data_store, addr, &ty)?;
+                        let size = interpreter.exists(data_store, resolver.loader(), addr, &ty)?;
                         gas_status.charge_instr_with_size(Opcodes::EXISTS_GENERIC, size)?;
                     }
                     Bytecode::MoveFrom(sd_idx) => {
                         let addr = interpreter.operand_stack.pop_as::<AccountAddress>()?;
                         let ty = resolver.get_struct_type(*sd_idx);
-                        let size = interpreter.move_from(data_store, addr, &ty)?;
+                        let size =
+                            interpreter.move_from(data_store, resolver.loader(), addr, &ty)?;
                         // TODO: Have this calculate before pulling in the data based upon
                         // the size of the data that we are about to read in.
                         gas_status.charge_instr_with_size(Opcodes::MOVE_FROM, size)?;
@@ -1052,7 +1328,8 @@ impl Frame {
                     Bytecode::MoveFromGeneric(si_idx) => {
                         let addr = interpreter.operand_stack.pop_as::<AccountAddress>()?;
                         let ty = resolver.instantiate_generic_type(*si_idx, self.ty_args())?;
-                        let size = interpreter.move_from(data_store, addr, &ty)?;
+                        let size =
+                            interpreter.move_from(data_store, resolver.loader(), addr, &ty)?;
                         // TODO: Have this calculate before pulling in the data based upon
                         // the size of the data that we are about to read in.
                         gas_status.charge_instr_with_size(Opcodes::MOVE_FROM_GENERIC, size)?;
@@ -1067,7 +1344,13 @@ impl Frame {
                             .value_as::<AccountAddress>()?;
                         let ty = resolver.get_struct_type(*sd_idx);
                         // REVIEW: Can we simplify Interpreter::move_to?
-                        let size = interpreter.move_to(data_store, addr, &ty, resource)?;
+                        let size = interpreter.move_to(
+                            data_store,
+                            resolver.loader(),
+                            addr,
+                            &ty,
+                            resource,
+                        )?;
                         gas_status.charge_instr_with_size(Opcodes::MOVE_TO, size)?;
                     }
                     Bytecode::MoveToGeneric(si_idx) => {
@@ -1079,7 +1362,13 @@ impl Frame {
                             .read_ref()?
                             .value_as::<AccountAddress>()?;
                         let ty = resolver.instantiate_generic_type(*si_idx, self.ty_args())?;
-                        let size = interpreter.move_to(data_store, addr, &ty, resource)?;
+                        let size = interpreter.move_to(
+                            data_store,
+                            resolver.loader(),
+                            addr,
+                            &ty,
+                            resource,
+                        )?;
                         gas_status.charge_instr_with_size(Opcodes::MOVE_TO_GENERIC, size)?;
                     }
                     Bytecode::FreezeRef => {
@@ -1184,4 +1473,20 @@ impl Frame {
             Some(id) => Location::Module(id.clone()),
         }
     }
+
+    #[cfg(feature = "debugging")]
+    fn debug_info(&self, loader: &Loader) -> FrameInfo {
+        FrameInfo {
+            module: self.function.module_id().cloned(),
+            function_index: self.function.index(),
+            function: self.function.pretty_string(),
+            type_arguments: self
+                .ty_args()
+                .iter()
+                .filter_map(|ty| loader.type_to_type_tag(ty).ok())
+                .collect(),
+            pc: self.pc,
+            locals: values::debug::locals_to_strings(&self.locals).unwrap_or_default(),
+        }
+    }
 }
@@ -13,9 +13,13 @@
 extern crate mirai_annotations;
 
 pub mod data_cache;
+#[cfg(feature = "debugging")]
+pub mod debugger;
+pub mod execution_trace;
 mod interpreter;
 mod loader;
 pub mod logging;
+mod memory_size;
 pub mod move_vm;
 pub mod native_functions;
 mod runtime;
@@ -3,6 +3,7 @@
 
 use crate::{
     logging::expect_no_verification_errors,
+    memory_size::{memory_size, HeapSize},
     native_functions::{NativeFunction, NativeFunctions},
 };
 use bytecode_verifier::{self, cyclic_dependencies, dependencies, script_signature};
@@ -29,48 +30,204 @@ use move_vm_types::{
     data_store::DataStore,
     loaded_data::runtime_types::{StructType, Type},
 };
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use sha3::{Digest, Sha3_256};
 use std::{
     collections::{BTreeMap, BTreeSet, HashMap},
     fmt::Debug,
     hash::Hash,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
 };
 use tracing::error;
 
 type ScriptHash = [u8; 32];
 
-// A simple cache that offers both a HashMap and a Vector lookup.
+/// Bounds on the code cached by the loader of a `MoveVM`.
+///
+/// When a bound is exceeded the least recently used modules and scripts are evicted, together
+/// with the types and functions they define. Eviction only happens when a session is created
+/// while no other session is alive, so the cache may temporarily exceed its bounds while
+/// sessions are running. The default configuration is unbounded.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CacheConfig {
+    /// The maximum number of modules in the cache.
+    pub max_modules: usize,
+    /// The maximum total size, in serialized bytes, of the modules in the cache.
+    pub max_module_bytes: usize,
+    /// The maximum number of scripts in the cache.
+    pub max_scripts: usize,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            max_modules: usize::MAX,
+            max_module_bytes: usize::MAX,
+            max_scripts: usize::MAX,
+        }
+    }
+}
+
+/// A snapshot of the usage of the loader caches of a `MoveVM`.
+///
+/// Hits and misses count the lookups of modules and scripts being loaded. Bytes are the
+/// serialized sizes of the cached binaries, memory is an estimate of the memory used by the
+/// loaded code (see `HeapSize`), including the types and functions a module defines.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CacheStats {
+    pub module_hits: u64,
+    pub module_misses: u64,
+    pub modules_evicted: u64,
+    pub cached_modules: usize,
+    pub cached_module_bytes: usize,
+    pub cached_module_memory: usize,
+    pub script_hits: u64,
+    pub script_misses: u64,
+    pub scripts_evicted: u64,
+    pub cached_scripts: usize,
+    pub cached_script_bytes: usize,
+    pub cached_script_memory: usize,
+    /// The number of struct types defined by the cached modules.
+    pub cached_structs: usize,
+    /// The number of functions defined by the cached modules.
+    pub cached_functions: usize,
+    /// The number of struct instantiations with a cached type tag or layout.
+    pub cached_type_infos: usize,
+    /// The memory used by the cached type tags and layouts.
+    pub cached_type_info_memory: usize,
+}
+
+impl CacheStats {
+    pub fn module_hit_rate(&self) -> f64 {
+        hit_rate(self.module_hits, self.module_misses)
+    }
+
+    pub fn script_hit_rate(&self) -> f64 {
+        hit_rate(self.script_hits, self.script_misses)
+    }
+}
+
+fn hit_rate(hits: u64, misses: u64) -> f64 {
+    if hits + misses == 0 {
+        0.0
+    } else {
+        hits as f64 / (hits + misses) as f64
+    }
+}
+
+// A simple cache keyed by id.
 // Values are forced into a `Arc` so they can be used from multiple thread.
-// Access to this cache is always under a `RwLock`.
+// Every entry records the size of its binary, the memory it uses and the last time it was used,
+// as a tick of the cache clock, so that the least recently used entries can be evicted.
+// Access to this cache is always under a `RwLock`. Uses and lookups are recorded with atomics,
+// under the read lock.
 struct BinaryCache<K, V> {
-    id_map: HashMap<K, usize>,
-    binaries: Vec<Arc<V>>,
+    entries: HashMap<K, CacheEntry<V>>,
+    clock: AtomicU64,
+    total_size: usize,
+    total_memory: usize,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: u64,
+}
+
+struct CacheEntry<V> {
+    binary: Arc<V>,
+    size: usize,
+    memory: usize,
+    last_used: AtomicU64,
 }
 
 impl<K, V> BinaryCache<K, V>
 where
-    K: Eq + Hash,
+    K: Eq + Hash + Clone + Ord,
 {
     fn new() -> Self {
         Self {
-            id_map: HashMap::new(),
-            binaries: vec![],
+            entries: HashMap::new(),
+            clock: AtomicU64::new(0),
+            total_size: 0,
+            total_memory: 0,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: 0,
         }
     }
 
-    fn insert(&mut self, key: K, binary: V) -> &Arc<V> {
-        self.binaries.push(Arc::new(binary));
-        let idx = self.binaries.len() - 1;
-        self.id_map.insert(key, idx);
-        self.binaries
-            .last()
-            .expect("BinaryCache: last() after push() impossible failure")
+    fn insert(&mut self, key: K, binary: V, size: usize, memory: usize) -> &Arc<V> {
+        let entry = CacheEntry {
+            binary: Arc::new(binary),
+            size,
+            memory,
+            last_used: AtomicU64::new(self.clock.fetch_add(1, Ordering::Relaxed)),
+        };
+        self.total_size += size;
+        self.total_memory += memory;
+        if let Some(old) = self.entries.insert(key.clone(), entry) {
+            self.total_size -= old.size;
+            self.total_memory -= old.memory;
+        }
+        &self.entries[&key].binary
     }
 
+    // Retrieve a binary and mark it as used
     fn get(&self, key: &K) -> Option<&Arc<V>> {
-        self.id_map.get(key).and_then(|idx| self.binaries.get(*idx))
+        self.entries.get(key).map(|entry| {
+            let now = self.clock.fetch_add(1, Ordering::Relaxed);
+            entry.last_used.store(now, Ordering::Relaxed);
+            &entry.binary
+        })
+    }
+
+    // Like `get` but also counts the lookup as a hit or a miss
+    fn lookup(&self, key: &K) -> Option<&Arc<V>> {
+        let binary = self.get(key);
+        match binary {
+            Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+        binary
+    }
+
+    fn contains(&self, key: &K) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    fn last_used(&self, key: &K) -> u64 {
+        self.entries
+            .get(key)
+            .map_or(0, |entry| entry.last_used.load(Ordering::Relaxed))
+    }
+
+    fn remove(&mut self, key: &K) -> Option<Arc<V>> {
+        self.entries.remove(key).map(|entry| {
+            self.total_size -= entry.size;
+            self.total_memory -= entry.memory;
+            self.evictions += 1;
+            entry.binary
+        })
+    }
+
+    // The keys of all the entries, least recently used first
+    fn keys_by_last_use(&self) -> Vec<K> {
+        let mut keys: Vec<_> = self
+            .entries
+            .iter()
+            .map(|(key, entry)| (entry.last_used.load(Ordering::Relaxed), key.clone()))
+            .collect();
+        keys.sort();
+        keys.into_iter().map(|(_, key)| key).collect()
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn values(&self) -> impl Iterator<Item = &Arc<V>> {
+        self.entries.values().map(|entry| &entry.binary)
     }
 }
 
@@ -90,29 +247,70 @@ impl ScriptCache {
 
     fn get(&self, hash: &ScriptHash) -> Option<(Arc<Function>, Vec<Type>)> {
         self.scripts
-            .get(hash)
+            .lookup(hash)
             .map(|script| (script.entry_point(), script.parameter_tys.clone()))
     }
 
-    fn insert(&mut self, hash: ScriptHash, script: Script) -> (Arc<Function>, Vec<Type>) {
-        match self.get(&hash) {
-            Some(cached) => cached,
+    fn insert(
+        &mut self,
+        hash: ScriptHash,
+        script: Script,
+        size: usize,
+    ) -> (Arc<Function>, Vec<Type>) {
+        match self.scripts.get(&hash) {
+            Some(cached) => (cached.entry_point(), cached.parameter_tys.clone()),
             None => {
-                let script = self.scripts.insert(hash, script);
+                let memory = memory_size(&script);
+                let script = self.scripts.insert(hash, script, size, memory);
                 (script.entry_point(), script.parameter_tys.clone())
             }
         }
     }
+
+    // Evict the scripts depending on any of the `evicted_modules`, and then the least recently
+    // used scripts until at most `max_scripts` are left
+    fn evict(&mut self, evicted_modules: &BTreeSet<ModuleId>, max_scripts: usize) {
+        if !evicted_modules.is_empty() {
+            let stale: Vec<_> = self
+                .scripts
+                .entries
+                .iter()
+                .filter(|(_, entry)| {
+                    entry
+                        .binary
+                        .script
+                        .immediate_dependencies()
+                        .iter()
+                        .any(|dep| evicted_modules.contains(dep))
+                })
+                .map(|(hash, _)| *hash)
+                .collect();
+            for hash in stale {
+                self.scripts.remove(&hash);
+            }
+        }
+        if self.scripts.len() > max_scripts {
+            let excess = self.scripts.len() - max_scripts;
+            for hash in self.scripts.keys_by_last_use().into_iter().take(excess) {
+                self.scripts.remove(&hash);
+            }
+        }
+    }
 }
 
 // A ModuleCache is the core structure in the Loader.
 // It holds all Modules, Types and Functions loaded.
 // Types and Functions are pushed globally to the ModuleCache.
 // All accesses to the ModuleCache are under lock (exclusive).
+//
+// Types and Functions are referred to by their index in the global tables, so evicting a module
+// never moves other entries: the slots of its types and functions are only emptied. Trailing
+// empty slots are trimmed and their indices get reused by the next modules loaded, which is fine
+// as nothing refers to the evicted modules anymore (see `Loader::start_session`).
 pub struct ModuleCache {
     modules: BinaryCache<ModuleId, Module>,
-    structs: Vec<Arc<StructType>>,
-    functions: Vec<Arc<Function>>,
+    structs: Vec<Option<Arc<StructType>>>,
+    functions: Vec<Option<Arc<Function>>>,
 }
 
 impl ModuleCache {
@@ -134,14 +332,27 @@ impl ModuleCache {
         self.modules.get(id).map(|module| Arc::clone(module))
     }
 
+    // Same as `module_at` for a module being loaded, the lookup counts towards the cache stats
+    fn lookup_module(&self, id: &ModuleId) -> Option<Arc<Module>> {
+        self.modules.lookup(id).map(|module| Arc::clone(module))
+    }
+
     // Retrieve a function by index
     fn function_at(&self, idx: usize) -> Arc<Function> {
-        Arc::clone(&self.functions[idx])
+        Arc::clone(
+            self.functions[idx]
+                .as_ref()
+                .expect("Function index must refer to a cached module"),
+        )
     }
 
     // Retrieve a struct by index
     fn struct_at(&self, idx: usize) -> Arc<StructType> {
-        Arc::clone(&self.structs[idx])
+        Arc::clone(
+            self.structs[idx]
+                .as_ref()
+                .expect("Struct index must refer to a cached module"),
+        )
     }
 
     //
@@ -154,6 +365,7 @@ impl ModuleCache {
         natives: &NativeFunctions,
         id: ModuleId,
         module: CompiledModule,
+        size: usize,
     ) -> VMResult<Arc<Module>> {
         if let Some(cached) = self.module_at(&id) {
             return Ok(cached);
@@ -163,7 +375,10 @@ impl ModuleCache {
         // leave a clean state
         self.add_module(natives, &module)?;
         match Module::new(module, self) {
-            Ok(module) => Ok(Arc::clone(self.modules.insert(id, module))),
+            Ok(module) => {
+                let memory = self.module_memory(&module);
+                Ok(Arc::clone(self.modules.insert(id, module, size, memory)))
+            }
             Err((err, module)) => {
                 // remove all structs and functions that have been pushed
                 let strut_def_count = module.struct_defs().len();
@@ -176,11 +391,26 @@ impl ModuleCache {
         }
     }
 
+    // The memory used by a module, including the types and functions it defines
+    fn module_memory(&self, module: &Module) -> usize {
+        memory_size(module)
+            + module
+                .struct_map
+                .values()
+                .map(|idx| memory_size(self.struct_at(*idx).as_ref()))
+                .sum::<usize>()
+            + module
+                .function_map
+                .values()
+                .map(|idx| memory_size(self.function_at(*idx).as_ref()))
+                .sum::<usize>()
+    }
+
     fn add_module(&mut self, natives: &NativeFunctions, module: &CompiledModule) -> VMResult<()> {
         let starting_idx = self.structs.len();
         for (idx, struct_def) in module.struct_defs().iter().enumerate() {
             let st = self.make_struct_type(module, struct_def, StructDefinitionIndex(idx as u16));
-            self.structs.push(Arc::new(st));
+            self.structs.push(Some(Arc::new(st)));
         }
         self.load_field_types(module, starting_idx).map_err(|err| {
             // clean up the structs that were cached
@@ -190,7 +420,7 @@ impl ModuleCache {
         for (idx, func) in module.function_defs().iter().enumerate() {
             let findex = FunctionDefinitionIndex(idx as TableIndex);
             let function = Function::new(natives, findex, func, module);
-            self.functions.push(Arc::new(function));
+            self.functions.push(Some(Arc::new(function)));
         }
         Ok(())
     }
@@ -239,7 +469,10 @@ impl ModuleCache {
         }
         let mut struct_idx = starting_idx;
         for fields in field_types {
-            match Arc::get_mut(&mut self.structs[struct_idx]) {
+            let slot = self.structs[struct_idx]
+                .as_mut()
+                .expect("Structs of the module being loaded must be cached");
+            match Arc::get_mut(slot) {
                 Some(struct_type) => struct_type.fields = fields,
                 None => {
                     // we have pending references to the `Arc` which is impossible,
@@ -248,9 +481,9 @@ impl ModuleCache {
                     // So in the spirit of not crashing we just rewrite the entire `Arc`
                     // over and log the issue.
                     error!("Arc<StructType> cannot have any live reference while publishing");
-                    let mut struct_type = (*self.structs[struct_idx]).clone();
+                    let mut struct_type = (**slot).clone();
                     struct_type.fields = fields;
-                    self.structs[struct_idx] = Arc::new(struct_type);
+                    *slot = Arc::new(struct_type);
                 }
             }
             struct_idx += 1;
@@ -281,11 +514,13 @@ impl ModuleCache {
                 if module_id == &self_id {
                     // module has not been published yet, loop through the types
                     for (idx, struct_type) in self.structs.iter().enumerate().rev() {
-                        if &struct_type.module != module_id {
-                            break;
-                        }
-                        if struct_type.name.as_ident_str() == struct_name {
-                            return Ok(idx);
+                        match struct_type {
+                            Some(struct_type) if &struct_type.module == module_id => {
+                                if struct_type.name.as_ident_str() == struct_name {
+                                    return Ok(idx);
+                                }
+                            }
+                            _ => break,
                         }
                     }
                     Err(
@@ -366,7 +601,7 @@ impl ModuleCache {
 
     // Given a module id, returns whether the module cache has the module or not
     fn has_module(&self, module_id: &ModuleId) -> bool {
-        self.modules.id_map.contains_key(module_id)
+        self.modules.contains(module_id)
     }
 
     // Given a ModuleId::struct_name, retrieve the `StructType` and the index associated.
@@ -381,7 +616,7 @@ impl ModuleCache {
             .get(module_id)
             .and_then(|module| module.struct_map.get(struct_name))
         {
-            Some(struct_idx) => Ok((*struct_idx, Arc::clone(&self.structs[*struct_idx]))),
+            Some(struct_idx) => Ok((*struct_idx, self.struct_at(*struct_idx))),
             None => Err(
                 PartialVMError::new(StatusCode::TYPE_RESOLUTION_FAILURE).with_message(format!(
                     "Cannot find {:?}::{:?} in cache",
@@ -412,6 +647,101 @@ impl ModuleCache {
             ),
         }
     }
+
+    //
+    // Eviction
+    //
+
+    fn exceeds(&self, config: &CacheConfig) -> bool {
+        self.modules.len() > config.max_modules || self.modules.total_size > config.max_module_bytes
+    }
+
+    // Evict modules until the cache fits in `config`, returning the ids of the evicted modules
+    // and the indices of the types they defined.
+    //
+    // The loader relies on the dependencies and friends of a cached module being cached as well
+    // (see `load_and_verify_friends`), so a module is always evicted together with the modules
+    // depending on it or declaring it as a friend. Out of those groups, the one whose most
+    // recent use is the oldest goes first.
+    fn evict(&mut self, config: &CacheConfig) -> (BTreeSet<ModuleId>, BTreeSet<usize>) {
+        let mut evicted_modules = BTreeSet::new();
+        let mut evicted_structs = BTreeSet::new();
+        while self.exceeds(config) {
+            let group = match self.least_recently_used_group() {
+                Some(group) => group,
+                None => break,
+            };
+            for id in group {
+                if let Some(module) = self.modules.remove(&id) {
+                    for idx in module.struct_map.values() {
+                        self.structs[*idx] = None;
+                        evicted_structs.insert(*idx);
+                    }
+                    for idx in module.function_map.values() {
+                        self.functions[*idx] = None;
+                    }
+                }
+                evicted_modules.insert(id);
+            }
+        }
+        while let Some(None) = self.structs.last() {
+            self.structs.pop();
+        }
+        while let Some(None) = self.functions.last() {
+            self.functions.pop();
+        }
+        (evicted_modules, evicted_structs)
+    }
+
+    fn least_recently_used_group(&self) -> Option<BTreeSet<ModuleId>> {
+        // modules referring to a module, either as a dependency or as a friend
+        let mut referrers: BTreeMap<ModuleId, Vec<ModuleId>> = BTreeMap::new();
+        for module in self.modules.values() {
+            for referred in module
+                .module
+                .immediate_dependencies()
+                .into_iter()
+                .chain(module.module.immediate_friends())
+            {
+                referrers
+                    .entry(referred)
+                    .or_default()
+                    .push(module.id.clone());
+            }
+        }
+
+        let mut best: Option<(u64, BTreeSet<ModuleId>)> = None;
+        for id in self.modules.keys_by_last_use() {
+            let mut group = BTreeSet::new();
+            let mut last_used = 0;
+            let mut to_visit = vec![id];
+            while let Some(id) = to_visit.pop() {
+                if group.contains(&id) {
+                    continue;
+                }
+                last_used = std::cmp::max(last_used, self.modules.last_used(&id));
+                if let Some(ids) = referrers.get(&id) {
+                    to_visit.extend(ids.iter().cloned());
+                }
+                group.insert(id);
+            }
+            if best.as_ref().map_or(true, |(best, _)| last_used < *best) {
+                best = Some((last_used, group));
+            }
+        }
+        best.map(|(_, group)| group)
+    }
+
+    fn stats(&self, stats: &mut CacheStats) {
+        stats.module_hits = self.modules.hits.load(Ordering::Relaxed);
+        stats.module_misses = self.modules.misses.load(Ordering::Relaxed);
+        stats.modules_evicted = self.modules.evictions;
+        stats.cached_modules = self.modules.len();
+        stats.cached_module_bytes = self.modules.total_size;
+        stats.cached_module_memory = self.modules.total_memory;
+        stats.cached_structs = self.structs.iter().filter(|slot| slot.is_some()).count();
+        stats.cached_functions = self.functions.iter().filter(|slot| slot.is_some()).count();
+    }
 }
 
 //
@@ -427,18 +757,79 @@ pub(crate) struct Loader {
     module_cache: RwLock<ModuleCache>,
     type_cache: RwLock<TypeCache>,
     natives: NativeFunctions,
+    cache_config: CacheConfig,
+    // number of sessions alive, see `SessionGuard`
+    sessions: Mutex<usize>,
 }
 
 impl Loader {
-    pub(crate) fn new(natives: NativeFunctions) -> Self {
+    pub(crate) fn new(natives: NativeFunctions, cache_config: CacheConfig) -> Self {
         Self {
             scripts: RwLock::new(ScriptCache::new()),
             module_cache: RwLock::new(ModuleCache::new()),
             type_cache: RwLock::new(TypeCache::new()),
             natives,
+            cache_config,
+            sessions: Mutex::new(0),
         }
     }
 
+    //
+    // Cache management
+    //
+
+    // Register a new session, trimming the caches to their bounds first if no other session
+    // is alive.
+    // Sessions hold indices into the `ModuleCache` (e.g., in the `Type`s of the values they
+    // load) and `Arc`s to the functions they execute, so nothing can be evicted while a session
+    // is alive. The sessions lock is held while trimming so that no session can start meanwhile.
+    pub(crate) fn start_session(&self) -> SessionGuard {
+        let mut sessions = self.sessions.lock();
+        if *sessions == 0 {
+            self.trim_caches();
+        }
+        *sessions += 1;
+        SessionGuard { loader: self }
+    }
+
+    fn trim_caches(&self) {
+        // locks are taken in the same order as `load_script`
+        let mut scripts = self.scripts.write();
+        let mut module_cache = self.module_cache.write();
+        if !module_cache.exceeds(&self.cache_config)
+            && scripts.scripts.len() <= self.cache_config.max_scripts
+        {
+            return;
+        }
+        let (evicted_modules, evicted_structs) = module_cache.evict(&self.cache_config);
+        scripts.evict(&evicted_modules, self.cache_config.max_scripts);
+        if !evicted_structs.is_empty() {
+            self.type_cache.write().evict(&evicted_structs);
+        }
+    }
+
+    pub(crate) fn cache_stats(&self) -> CacheStats {
+        let mut stats = CacheStats::default();
+        {
+            let scripts = self.scripts.read();
+            stats.script_hits = scripts.scripts.hits.load(Ordering::Relaxed);
+            stats.script_misses = scripts.scripts.misses.load(Ordering::Relaxed);
+            stats.scripts_evicted = scripts.scripts.evictions;
+            stats.cached_scripts = scripts.scripts.len();
+            stats.cached_script_bytes = scripts.scripts.total_size;
+            stats.cached_script_memory = scripts.scripts.total_memory;
+        }
+        self.module_cache.read().stats(&mut stats);
+        let type_cache = self.type_cache.read();
+        stats.cached_type_infos = type_cache
+            .structs
+            .values()
+            .map(|instantiations| instantiations.len())
+            .sum();
+        stats.cached_type_info_memory = type_cache.structs.heap_size();
+        stats
+    }
+
     //
     // Script verification and loading
     //
@@ -468,7 +859,7 @@ impl Loader {
             None => {
                 let ver_script = self.deserialize_and_verify_script(script_blob, data_store)?;
                 let script = Script::new(ver_script, &hash_value, &self.module_cache.read())?;
-                scripts.insert(hash_value, script)
+                scripts.insert(hash_value, script, script_blob.len())
             }
         };
 
@@ -769,7 +1160,11 @@ impl Loader {
     // Helpers for loading and verification
     //
 
-    fn load_type(&self, type_tag: &TypeTag, data_store: &impl DataStore) -> VMResult<Type> {
+    pub(crate) fn load_type(
+        &self,
+        type_tag: &TypeTag,
+        data_store: &impl DataStore,
+    ) -> VMResult<Type> {
         Ok(match type_tag {
             TypeTag::Bool => Type::Bool,
             TypeTag::U8 => Type::U8,
@@ -822,7 +1217,7 @@ impl Loader {
         data_store: &impl DataStore,
     ) -> VMResult<Arc<Module>> {
         // if the module is already in the code cache, load the cached version
-        if let Some(cached) = self.module_cache.read().module_at(id) {
+        if let Some(cached) = self.module_cache.read().lookup_module(id) {
             return Ok(cached);
         }
 
@@ -851,7 +1246,7 @@ impl Loader {
         id: &ModuleId,
         data_store: &impl DataStore,
         allow_loading_failure: bool,
-    ) -> VMResult<CompiledModule> {
+    ) -> VMResult<(CompiledModule, usize)> {
         // bytes fetching, allow loading to fail if the flag is set
         let bytes = match data_store.load_module(id) {
             Ok(bytes) => bytes,
@@ -877,7 +1272,7 @@ impl Loader {
         bytecode_verifier::verify_module(&module).map_err(expect_no_verification_errors)?;
         self.check_natives(&module)
             .map_err(expect_no_verification_errors)?;
-        Ok(module)
+        Ok((module, bytes.len()))
     }
 
     // Everything in `load_and_verify_module` and also recursively load and verify all the
@@ -898,7 +1293,8 @@ impl Loader {
         }
 
         // module self-check
-        let module = self.load_and_verify_module(id, data_store, allow_module_loading_failure)?;
+        let (module, size) =
+            self.load_and_verify_module(id, data_store, allow_module_loading_failure)?;
         visited.insert(id.clone());
         friends_discovered.extend(module.immediate_friends());
 
@@ -915,7 +1311,7 @@ impl Loader {
 
         // if linking goes well, insert the module to the code cache
         let mut locked_cache = self.module_cache.write();
-        let module_ref = locked_cache.insert(&self.natives, id.clone(), module)?;
+        let module_ref = locked_cache.insert(&self.natives, id.clone(), module, size)?;
         drop(locked_cache); // explicit unlock
 
         Ok(module_ref)
@@ -942,7 +1338,7 @@ impl Loader {
                 bundle_deps.push(cached);
             } else {
                 let locked_cache = self.module_cache.read();
-                let loaded = match locked_cache.module_at(&module_id) {
+                let loaded = match locked_cache.lookup_module(&module_id) {
                     None => {
                         drop(locked_cache); // explicit unlock
                         self.load_and_verify_module_and_dependencies(
@@ -1398,13 +1794,18 @@ impl Module {
                     // to types of the module being loaded is going to fail.
                     // So we manually go through the types and find the proper index
                     for (idx, struct_type) in cache.structs.iter().enumerate().rev() {
-                        if struct_type.module != module_id {
-                            return Err(PartialVMError::new(StatusCode::TYPE_RESOLUTION_FAILURE)
+                        let struct_type = match struct_type {
+                            Some(struct_type) if struct_type.module == module_id => struct_type,
+                            _ => {
+                                return Err(PartialVMError::new(
+                                    StatusCode::TYPE_RESOLUTION_FAILURE,
+                                )
                                 .with_message(format!(
                                     "Cannot find {:?}::{:?} in publishing module",
                                     module_id, struct_name
-                                )));
-                        }
+                                )))
+                            }
+                        };
                         if struct_type.name.as_ident_str() == struct_name {
                             struct_refs.push(idx);
                             break;
@@ -1417,7 +1818,7 @@ impl Module {
 
             for struct_def in module.struct_defs() {
                 let idx = struct_refs[struct_def.struct_handle.0 as usize];
-                let field_count = cache.structs[idx].fields.len() as u16;
+                let field_count = cache.struct_at(idx).fields.len() as u16;
                 structs.push(StructDef { field_count, idx });
                 let name =
                     module.identifier_at(module.struct_handle_at(struct_def.struct_handle).name);
@@ -1446,15 +1847,18 @@ impl Module {
                 if module_id == id {
                     // module has not been published yet, loop through the functions
                     for (idx, function) in cache.functions.iter().enumerate().rev() {
-                        if function.module_id() != Some(&module_id) {
-                            return Err(PartialVMError::new(
-                                StatusCode::FUNCTION_RESOLUTION_FAILURE,
-                            )
-                            .with_message(format!(
-                                "Cannot find {:?}::{:?} in publishing module",
-                                module_id, func_name
-                            )));
-                        }
+                        let function = match function {
+                            Some(function) if function.module_id() == Some(&module_id) => function,
+                            _ => {
+                                return Err(PartialVMError::new(
+                                    StatusCode::FUNCTION_RESOLUTION_FAILURE,
+                                )
+                                .with_message(format!(
+                                    "Cannot find {:?}::{:?} in publishing module",
+                                    module_id, func_name
+                                )))
+                            }
+                        };
                         if function.name.as_ident_str() == func_name {
                             function_refs.push(idx);
                             break;
@@ -2008,6 +2412,133 @@ impl TypeCache {
             structs: HashMap::new(),
         }
     }
+
+    // Drop the information of the evicted struct types, and of any instantiation with them
+    fn evict(&mut self, evicted_structs: &BTreeSet<usize>) {
+        self.structs
+            .retain(|gidx, _| !evicted_structs.contains(gidx));
+        for instantiations in self.structs.values_mut() {
+            instantiations
+                .retain(|ty_args, _| !ty_args.iter().any(|ty| refers_to_any(ty, evicted_structs)));
+        }
+        self.structs
+            .retain(|_, instantiations| !instantiations.is_empty());
+    }
+}
+
+fn refers_to_any(ty: &Type, structs: &BTreeSet<usize>) -> bool {
+    match ty {
+        Type::Struct(gidx) => structs.contains(gidx),
+        Type::StructInstantiation(gidx, ty_args) => {
+            structs.contains(gidx) || ty_args.iter().any(|ty| refers_to_any(ty, structs))
+        }
+        Type::Vector(ty) | Type::Reference(ty) | Type::MutableReference(ty) => {
+            refers_to_any(ty, structs)
+        }
+        Type::Bool
+        | Type::U8
+        | Type::U64
+        | Type::U128
+        | Type::Address
+        | Type::Signer
+        | Type::TyParam(_) => false,
+    }
+}
+
+//
+// Memory estimates of the cached code, see `memory_size`
+//
+
+impl HeapSize for Module {
+    fn heap_size(&self) -> usize {
+        self.id.heap_size()
+            + self.module.heap_size()
+            + self.struct_refs.heap_size()
+            + self.structs.heap_size()
+            + self.struct_instantiations.heap_size()
+            + self.function_refs.heap_size()
+            + self.function_instantiations.heap_size()
+            + self.field_handles.heap_size()
+            + self.field_instantiations.heap_size()
+            + self.function_map.heap_size()
+            + self.struct_map.heap_size()
+            + self.single_signature_token_map.heap_size()
+    }
+}
+
+impl HeapSize for Script {
+    fn heap_size(&self) -> usize {
+        self.script.heap_size()
+            + self.struct_refs.heap_size()
+            + self.function_refs.heap_size()
+            + self.function_instantiations.heap_size()
+            + memory_size(self.main.as_ref())
+            + self.parameter_tys.heap_size()
+            + self.single_signature_token_map.heap_size()
+    }
+}
+
+impl HeapSize for Function {
+    fn heap_size(&self) -> usize {
+        let scope = match &self.scope {
+            Scope::Module(id) => id.heap_size(),
+            Scope::Script(_) => 0,
+        };
+        self.code.heap_size()
+            + self.parameters.heap_size()
+            + self.return_.heap_size()
+            + self.locals.heap_size()
+            + self.type_parameters.heap_size()
+            + scope
+            + self.name.heap_size()
+    }
+}
+
+impl HeapSize for FunctionInstantiation {
+    fn heap_size(&self) -> usize {
+        self.instantiation.heap_size()
+    }
+}
+
+impl HeapSize for StructDef {
+    fn heap_size(&self) -> usize {
+        0
+    }
+}
+
+impl HeapSize for StructInstantiation {
+    fn heap_size(&self) -> usize {
+        self.instantiation.heap_size()
+    }
+}
+
+impl HeapSize for FieldHandle {
+    fn heap_size(&self) -> usize {
+        0
+    }
+}
+
+impl HeapSize for FieldInstantiation {
+    fn heap_size(&self) -> usize {
+        0
+    }
+}
+
+impl HeapSize for StructInfo {
+    fn heap_size(&self) -> usize {
+        self.struct_tag.heap_size() + self.struct_layout.heap_size()
+    }
+}
+
+// Keeps a session registered with the `Loader` (see `Loader::start_session`) until dropped.
+pub(crate) struct SessionGuard<'a> {
+    loader: &'a Loader,
+}
+
+impl<'a> Drop for SessionGuard<'a> {
+    fn drop(&mut self) {
+        *self.loader.sessions.lock() -= 1;
+    }
 }
 
 const VALUE_DEPTH_MAX: usize = 256;
@@ -0,0 +1,286 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Estimates of the memory used by the code cached in the loader.
+//!
+//! The estimates count the inline size of values and the heap allocations they own (using the
+//! capacity of vectors and maps), but not the overhead of the allocator. Data shared through an
+//! `Arc` is counted by its owner, which is the cache entry that created it.
+
+use move_binary_format::file_format::{
+    AbilitySet, Bytecode, CompiledModule, CompiledScript, Constant, FieldDefinition, FieldHandle,
+    FieldInstantiation, FunctionDefinition, FunctionHandle, FunctionInstantiation, ModuleHandle,
+    Signature, SignatureIndex, SignatureToken, StructDefInstantiation, StructDefinition,
+    StructDefinitionIndex, StructFieldInformation, StructHandle, StructTypeParameter,
+};
+use move_core_types::{
+    account_address::AccountAddress,
+    identifier::Identifier,
+    language_storage::{ModuleId, StructTag, TypeTag},
+    value::{MoveFieldLayout, MoveStructLayout, MoveTypeLayout},
+};
+use move_vm_types::loaded_data::runtime_types::{StructType, Type};
+use std::{
+    collections::{BTreeMap, HashMap},
+    mem::size_of,
+};
+
+/// The heap allocations owned by a value, in bytes.
+pub(crate) trait HeapSize {
+    fn heap_size(&self) -> usize;
+}
+
+/// The inline size of a value plus the heap allocations it owns, in bytes.
+pub(crate) fn memory_size<T: HeapSize>(value: &T) -> usize {
+    size_of::<T>() + value.heap_size()
+}
+
+// Types owning no heap allocation
+macro_rules! impl_heap_size_zero {
+    ($($ty:ty),*) => {
+        $(
+            impl HeapSize for $ty {
+                fn heap_size(&self) -> usize {
+                    0
+                }
+            }
+        )*
+    };
+}
+
+impl_heap_size_zero!(
+    u8,
+    usize,
+    AbilitySet,
+    AccountAddress,
+    Bytecode,
+    FieldHandle,
+    FieldInstantiation,
+    FunctionInstantiation,
+    ModuleHandle,
+    SignatureIndex,
+    StructDefInstantiation,
+    StructDefinitionIndex,
+    StructTypeParameter
+);
+
+impl<T: HeapSize> HeapSize for Vec<T> {
+    fn heap_size(&self) -> usize {
+        self.capacity() * size_of::<T>() + self.iter().map(HeapSize::heap_size).sum::<usize>()
+    }
+}
+
+impl<T: HeapSize> HeapSize for Box<T> {
+    fn heap_size(&self) -> usize {
+        memory_size(self.as_ref())
+    }
+}
+
+impl<T: HeapSize> HeapSize for Option<T> {
+    fn heap_size(&self) -> usize {
+        self.as_ref().map_or(0, HeapSize::heap_size)
+    }
+}
+
+impl<K: HeapSize, V: HeapSize, S> HeapSize for HashMap<K, V, S> {
+    fn heap_size(&self) -> usize {
+        self.capacity() * (size_of::<K>() + size_of::<V>())
+            + self
+                .iter()
+                .map(|(key, value)| key.heap_size() + value.heap_size())
+                .sum::<usize>()
+    }
+}
+
+impl<K: HeapSize, V: HeapSize> HeapSize for BTreeMap<K, V> {
+    fn heap_size(&self) -> usize {
+        self.iter()
+            .map(|(key, value)| memory_size(key) + memory_size(value))
+            .sum()
+    }
+}
+
+impl HeapSize for Identifier {
+    fn heap_size(&self) -> usize {
+        self.as_str().len()
+    }
+}
+
+impl HeapSize for ModuleId {
+    fn heap_size(&self) -> usize {
+        self.name().as_str().len()
+    }
+}
+
+impl HeapSize for SignatureToken {
+    fn heap_size(&self) -> usize {
+        match self {
+            SignatureToken::Vector(ty)
+            | SignatureToken::Reference(ty)
+            | SignatureToken::MutableReference(ty) => ty.heap_size(),
+            SignatureToken::StructInstantiation(_, ty_args) => ty_args.heap_size(),
+            SignatureToken::Bool
+            | SignatureToken::U8
+            | SignatureToken::U64
+            | SignatureToken::U128
+            | SignatureToken::Address
+            | SignatureToken::Signer
+            | SignatureToken::Struct(_)
+            | SignatureToken::TypeParameter(_) => 0,
+        }
+    }
+}
+
+impl HeapSize for Signature {
+    fn heap_size(&self) -> usize {
+        self.0.heap_size()
+    }
+}
+
+impl HeapSize for Constant {
+    fn heap_size(&self) -> usize {
+        self.type_.heap_size() + self.data.heap_size()
+    }
+}
+
+impl HeapSize for StructHandle {
+    fn heap_size(&self) -> usize {
+        self.type_parameters.heap_size()
+    }
+}
+
+impl HeapSize for FunctionHandle {
+    fn heap_size(&self) -> usize {
+        self.type_parameters.heap_size()
+    }
+}
+
+impl HeapSize for StructDefinition {
+    fn heap_size(&self) -> usize {
+        match &self.field_information {
+            StructFieldInformation::Native => 0,
+            StructFieldInformation::Declared(fields) => {
+                fields.capacity() * size_of::<FieldDefinition>()
+                    + fields
+                        .iter()
+                        .map(|field| field.signature.0.heap_size())
+                        .sum::<usize>()
+            }
+        }
+    }
+}
+
+impl HeapSize for FunctionDefinition {
+    fn heap_size(&self) -> usize {
+        self.acquires_global_resources.heap_size()
+            + self.code.as_ref().map_or(0, |code| code.code.heap_size())
+    }
+}
+
+impl HeapSize for CompiledModule {
+    fn heap_size(&self) -> usize {
+        self.module_handles.heap_size()
+            + self.struct_handles.heap_size()
+            + self.function_handles.heap_size()
+            + self.field_handles.heap_size()
+            + self.friend_decls.heap_size()
+            + self.struct_def_instantiations.heap_size()
+            + self.function_instantiations.heap_size()
+            + self.field_instantiations.heap_size()
+            + self.signatures.heap_size()
+            + self.identifiers.heap_size()
+            + self.address_identifiers.heap_size()
+            + self.constant_pool.heap_size()
+            + self.struct_defs.heap_size()
+            + self.function_defs.heap_size()
+    }
+}
+
+impl HeapSize for CompiledScript {
+    fn heap_size(&self) -> usize {
+        self.module_handles.heap_size()
+            + self.struct_handles.heap_size()
+            + self.function_handles.heap_size()
+            + self.function_instantiations.heap_size()
+            + self.signatures.heap_size()
+            + self.identifiers.heap_size()
+            + self.address_identifiers.heap_size()
+            + self.constant_pool.heap_size()
+            + self.type_parameters.heap_size()
+            + self.code.code.heap_size()
+    }
+}
+
+impl HeapSize for Type {
+    fn heap_size(&self) -> usize {
+        match self {
+            Type::Vector(ty) | Type::Reference(ty) | Type::MutableReference(ty) => ty.heap_size(),
+            Type::StructInstantiation(_, ty_args) => ty_args.heap_size(),
+            Type::Bool
+            | Type::U8
+            | Type::U64
+            | Type::U128
+            | Type::Address
+            | Type::Signer
+            | Type::Struct(_)
+            | Type::TyParam(_) => 0,
+        }
+    }
+}
+
+impl HeapSize for StructType {
+    fn heap_size(&self) -> usize {
+        self.fields.heap_size()
+            + self.type_parameters.heap_size()
+            + self.name.heap_size()
+            + self.module.heap_size()
+    }
+}
+
+impl HeapSize for TypeTag {
+    fn heap_size(&self) -> usize {
+        match self {
+            TypeTag::Vector(ty) => ty.heap_size(),
+            TypeTag::Struct(struct_tag) => struct_tag.heap_size(),
+            TypeTag::Bool
+            | TypeTag::U8
+            | TypeTag::U64
+            | TypeTag::U128
+            | TypeTag::Address
+            | TypeTag::Signer => 0,
+        }
+    }
+}
+
+impl HeapSize for StructTag {
+    fn heap_size(&self) -> usize {
+        self.module.heap_size() + self.name.heap_size() + self.type_params.heap_size()
+    }
+}
+
+impl HeapSize for MoveTypeLayout {
+    fn heap_size(&self) -> usize {
+        match self {
+            MoveTypeLayout::Vector(layout) => layout.heap_size(),
+            MoveTypeLayout::Struct(layout) => layout.heap_size(),
+            MoveTypeLayout::Bool
+            | MoveTypeLayout::U8
+            | MoveTypeLayout::U64
+            | MoveTypeLayout::U128
+            | MoveTypeLayout::Address
+            | MoveTypeLayout::Signer => 0,
+        }
+    }
+}
+
+impl HeapSize for MoveStructLayout {
+    fn heap_size(&self) -> usize {
+        match self {
+            MoveStructLayout::Runtime(fields) => fields.heap_size(),
+            // The fields of a decorated layout are private, only their vector is counted
+            MoveStructLayout::WithFields(fields) => {
+                fields.capacity() * size_of::<MoveFieldLayout>()
+            }
+        }
+    }
+}
@@ -1,6 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+pub use crate::loader::{CacheConfig, CacheStats};
 use crate::{native_functions::NativeFunction, runtime::VMRuntime, session::Session};
 use move_binary_format::errors::{Location, VMResult};
 use move_core_types::{
@@ -13,11 +14,20 @@ pub struct MoveVM {
 
 impl MoveVM {
     pub fn new<I>(natives: I) -> VMResult<Self>
+    where
+        I: IntoIterator<Item = (AccountAddress, Identifier, Identifier, NativeFunction)>,
+    {
+        Self::new_with_cache_config(natives, CacheConfig::default())
+    }
+
+    /// Create a Move VM whose code cache is bounded by `cache_config`.
+    pub fn new_with_cache_config<I>(natives: I, cache_config: CacheConfig) -> VMResult<Self>
     where
         I: IntoIterator<Item = (AccountAddress, Identifier, Identifier, NativeFunction)>,
     {
         Ok(Self {
-            runtime: VMRuntime::new(natives).map_err(|err| err.finish(Location::Undefined))?,
+            runtime: VMRuntime::new(natives, cache_config)
+                .map_err(|err| err.finish(Location::Undefined))?,
         })
     }
 
@@ -35,7 +45,16 @@ impl MoveVM {
     ///     cases where this may not be necessary, with the most notable one being the common module
     ///     publishing flow: you can keep using the same Move VM if you publish some modules in a Session
     ///     and apply the effects to the storage when the Session ends.
+    ///
+    /// Creating a Session while no other Session is alive evicts code from the cache if it
+    /// exceeds the bounds of the `CacheConfig` of the VM. Evicted modules are loaded again from
+    /// storage when used.
     pub fn new_session<'r, S: MoveResolver>(&self, remote: &'r S) -> Session<'r, '_, S> {
         self.runtime.new_session(remote)
     }
+
+    /// Returns the hit rates and sizes of the code cache of the VM.
+    pub fn cache_stats(&self) -> CacheStats {
+        self.runtime.cache_stats()
+    }
 }
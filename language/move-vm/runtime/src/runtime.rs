@@ -4,7 +4,7 @@
 use crate::{
     data_cache::TransactionDataCache,
     interpreter::Interpreter,
-    loader::Loader,
+    loader::{CacheConfig, CacheStats, Loader},
     native_functions::{NativeFunction, NativeFunctions},
     session::Session,
 };
@@ -43,12 +43,12 @@ fn is_signer_reference(s: &Type) -> bool {
 }
 
 impl VMRuntime {
-    pub(crate) fn new<I>(natives: I) -> PartialVMResult<Self>
+    pub(crate) fn new<I>(natives: I, cache_config: CacheConfig) -> PartialVMResult<Self>
     where
         I: IntoIterator<Item = (AccountAddress, Identifier, Identifier, NativeFunction)>,
     {
         Ok(VMRuntime {
-            loader: Loader::new(NativeFunctions::new(natives)?),
+            loader: Loader::new(NativeFunctions::new(natives)?, cache_config),
         })
     }
 
@@ -56,9 +56,14 @@ impl VMRuntime {
         Session {
             runtime: self,
             data_cache: TransactionDataCache::new(remote, &self.loader),
+            _guard: self.loader.start_session(),
         }
     }
 
+    pub(crate) fn cache_stats(&self) -> CacheStats {
+        self.loader.cache_stats()
+    }
+
     pub(crate) fn publish_module_bundle(
         &self,
         modules: Vec<Vec<u8>>,
@@ -1,7 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{data_cache::TransactionDataCache, runtime::VMRuntime};
+use crate::{data_cache::TransactionDataCache, loader::SessionGuard, runtime::VMRuntime};
 use move_binary_format::errors::*;
 use move_core_types::{
     account_address::AccountAddress,
@@ -16,6 +16,8 @@ use move_vm_types::gas_schedule::GasStatus;
 pub struct Session<'r, 'l, S> {
     pub(crate) runtime: &'l VMRuntime,
     pub(crate) data_cache: TransactionDataCache<'r, 'l, S>,
+    // keeps the loader from evicting code while the session is alive
+    pub(crate) _guard: SessionGuard<'l>,
 }
 
 impl<'r, 'l, S: MoveResolver> Session<'r, 'l, S> {
@@ -0,0 +1,262 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Attribution of gas to call stacks.
+//!
+//! While profiling is enabled on a thread (see `start_gas_profiling`), every gas charge made by
+//! a metered `GasStatus` created on that thread is attributed to the current call stack. The
+//! interpreter pushes a frame for each Move function and native function it calls, and a few
+//! pseudo-frames (in square brackets) distinguish the charges that are not made by regular
+//! instructions, such as the intrinsic cost of a transaction or global storage operations.
+//!
+//! All amounts are in internal gas units.
+
+use std::{cell::RefCell, collections::BTreeMap, fmt::Write};
+
+/// The pseudo-frame of the intrinsic gas charged for the size of a transaction.
+pub const INTRINSIC_FRAME: &str = "[intrinsic]";
+/// The prefix of the pseudo-frames of the instructions accessing global storage.
+pub const STORAGE_FRAME_PREFIX: &str = "[storage]";
+/// The pseudo-frame of the gas charged for the writes of a transaction to global storage.
+pub const STORAGE_WRITES_FRAME: &str = "[storage writes]";
+
+/// The gas consumed by each call stack, outermost frame first.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct GasProfile {
+    stacks: BTreeMap<Vec<String>, u64>,
+}
+
+/// The gas consumed by a function over all the stacks it appears in.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FunctionGasSummary {
+    pub function: String,
+    /// The gas consumed by the function and everything it called.
+    pub inclusive: u64,
+    /// The gas consumed by the function itself.
+    pub exclusive: u64,
+}
+
+impl GasProfile {
+    pub fn stacks(&self) -> impl Iterator<Item = (&[String], u64)> {
+        self.stacks
+            .iter()
+            .map(|(stack, gas)| (stack.as_slice(), *gas))
+    }
+
+    pub fn total_gas(&self) -> u64 {
+        self.stacks.values().sum()
+    }
+
+    /// Renders the profile in the folded stacks format understood by flamegraph tools (e.g.,
+    /// `inferno-flamegraph` or `flamegraph.pl`): one `frame;frame;...;frame gas` line per stack.
+    pub fn to_folded_stacks(&self) -> String {
+        let mut out = String::new();
+        for (stack, gas) in &self.stacks {
+            writeln!(out, "{} {}", stack.join(";"), gas).expect("Writing to a String never fails");
+        }
+        out
+    }
+
+    /// Returns the gas consumed by every function, most expensive (inclusively) first.
+    pub fn summary(&self) -> Vec<FunctionGasSummary> {
+        let mut functions: BTreeMap<&str, (u64, u64)> = BTreeMap::new();
+        for (stack, gas) in &self.stacks {
+            // Recursive functions appear several times in a stack but only count once.
+            let mut seen = vec![];
+            for frame in stack {
+                if !seen.contains(&frame) {
+                    functions.entry(frame).or_default().0 += gas;
+                    seen.push(frame);
+                }
+            }
+            if let Some(leaf) = stack.last() {
+                functions.entry(leaf).or_default().1 += gas;
+            }
+        }
+        let mut summary: Vec<_> = functions
+            .into_iter()
+            .map(|(function, (inclusive, exclusive))| FunctionGasSummary {
+                function: function.to_owned(),
+                inclusive,
+                exclusive,
+            })
+            .collect();
+        summary.sort_by(|a, b| b.inclusive.cmp(&a.inclusive));
+        summary
+    }
+
+    /// Renders `summary` as a table, along with the share of the total gas of each function.
+    pub fn summary_table(&self) -> String {
+        let total = std::cmp::max(self.total_gas(), 1) as f64;
+        let summary = self.summary();
+        let width = summary
+            .iter()
+            .map(|entry| entry.function.len())
+            .chain(std::iter::once("Function".len()))
+            .max()
+            .unwrap_or_default();
+
+        let mut out = String::new();
+        let mut line = |function: &str, inclusive: &str, exclusive: &str| {
+            writeln!(
+                out,
+                "{:width$}  {:>20}  {:>20}",
+                function,
+                inclusive,
+                exclusive,
+                width = width
+            )
+            .expect("Writing to a String never fails")
+        };
+        line("Function", "Inclusive gas", "Exclusive gas");
+        for entry in &summary {
+            line(
+                &entry.function,
+                &format!(
+                    "{} ({:.1}%)",
+                    entry.inclusive,
+                    entry.inclusive as f64 * 100.0 / total
+                ),
+                &format!(
+                    "{} ({:.1}%)",
+                    entry.exclusive,
+                    entry.exclusive as f64 * 100.0 / total
+                ),
+            );
+        }
+        line("Total", &self.total_gas().to_string(), "");
+        out
+    }
+}
+
+#[derive(Default)]
+struct GasProfiler {
+    stack: Vec<String>,
+    profile: GasProfile,
+}
+
+thread_local! {
+    static PROFILER: RefCell<Option<GasProfiler>> = RefCell::new(None);
+}
+
+fn with_profiler(f: impl FnOnce(&mut GasProfiler)) {
+    PROFILER.with(|profiler| {
+        if let Some(profiler) = profiler.borrow_mut().as_mut() {
+            f(profiler)
+        }
+    })
+}
+
+/// Starts attributing the gas charged on the current thread, discarding any profile in
+/// progress.
+pub fn start_gas_profiling() {
+    PROFILER.with(|profiler| *profiler.borrow_mut() = Some(GasProfiler::default()))
+}
+
+/// Stops profiling the current thread and returns the profile, if profiling was started.
+pub fn finish_gas_profiling() -> Option<GasProfile> {
+    PROFILER
+        .with(|profiler| profiler.borrow_mut().take())
+        .map(|profiler| profiler.profile)
+}
+
+pub fn is_profiling() -> bool {
+    PROFILER.with(|profiler| profiler.borrow().is_some())
+}
+
+pub fn push_frame(name: String) {
+    with_profiler(|profiler| profiler.stack.push(name))
+}
+
+pub fn pop_frame() {
+    with_profiler(|profiler| {
+        profiler.stack.pop();
+    })
+}
+
+/// Returns the number of frames on the profiled call stack.
+pub fn depth() -> usize {
+    PROFILER.with(|profiler| {
+        profiler
+            .borrow()
+            .as_ref()
+            .map_or(0, |profiler| profiler.stack.len())
+    })
+}
+
+/// Pops frames until the profiled call stack has `depth` frames, e.g. to unwind the frames of
+/// an execution that failed.
+pub fn truncate(depth: usize) {
+    with_profiler(|profiler| profiler.stack.truncate(depth))
+}
+
+/// Attributes `gas` to the current call stack, extended with the pseudo-frame `leaf` if any.
+pub fn record(gas: u64, leaf: Option<&str>) {
+    if gas == 0 {
+        return;
+    }
+    with_profiler(|profiler| {
+        let mut stack = profiler.stack.clone();
+        if let Some(leaf) = leaf {
+            stack.push(leaf.to_owned());
+        }
+        *profiler.profile.stacks.entry(stack).or_default() += gas;
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gas_profile() {
+        assert!(finish_gas_profiling().is_none());
+        start_gas_profiling();
+        record(5, Some(INTRINSIC_FRAME));
+        push_frame("0x1::M::main".to_owned());
+        record(10, None);
+        push_frame("0x1::M::f".to_owned());
+        record(7, None);
+        push_frame("0x1::M::f".to_owned());
+        record(3, Some("[storage] MOVE_TO"));
+        truncate(1);
+        record(1, None);
+        pop_frame();
+        let profile = finish_gas_profiling().unwrap();
+        assert!(!is_profiling());
+
+        assert_eq!(profile.total_gas(), 26);
+        assert_eq!(
+            profile.to_folded_stacks(),
+            "0x1::M::main 11\n\
+             0x1::M::main;0x1::M::f 7\n\
+             0x1::M::main;0x1::M::f;0x1::M::f;[storage] MOVE_TO 3\n\
+             [intrinsic] 5\n"
+        );
+        assert_eq!(
+            profile.summary(),
+            vec![
+                FunctionGasSummary {
+                    function: "0x1::M::main".to_owned(),
+                    inclusive: 21,
+                    exclusive: 11,
+                },
+                FunctionGasSummary {
+                    function: "0x1::M::f".to_owned(),
+                    inclusive: 10,
+                    exclusive: 7,
+                },
+                FunctionGasSummary {
+                    function: "[intrinsic]".to_owned(),
+                    inclusive: 5,
+                    exclusive: 5,
+                },
+                FunctionGasSummary {
+                    function: "[storage] MOVE_TO".to_owned(),
+                    inclusive: 3,
+                    exclusive: 3,
+                },
+            ]
+        );
+    }
+}
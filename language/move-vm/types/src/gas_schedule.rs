@@ -6,6 +6,7 @@
 //! It is important to note that the cost schedule defined in this file does not track hashing
 //! operations or other native operations; the cost of each native operation will be returned by the
 //! native function itself.
+use crate::gas_profiler;
 use mirai_annotations::*;
 use move_binary_format::{
     errors::{Location, PartialVMError, PartialVMResult, VMResult},
@@ -37,6 +38,9 @@ pub struct GasStatus<'a> {
     cost_table: &'a CostTable,
     gas_left: InternalGasUnits<GasCarrier>,
     charge: bool,
+    /// Whether the charges are attributed to call stacks (see `gas_profiler`), decided when the
+    /// `GasStatus` is created.
+    profile: bool,
 }
 
 impl<'a> GasStatus<'a> {
@@ -49,6 +53,7 @@ impl<'a> GasStatus<'a> {
             gas_left: cost_table.gas_constants.to_internal_units(gas_left),
             cost_table,
             charge: true,
+            profile: gas_profiler::is_profiling(),
         }
     }
 
@@ -61,6 +66,7 @@ impl<'a> GasStatus<'a> {
             gas_left: InternalGasUnits::new(0),
             cost_table: &ZERO_COST_SCHEDULE,
             charge: false,
+            profile: false,
         }
     }
 
@@ -78,6 +84,26 @@ impl<'a> GasStatus<'a> {
 
     /// Charge a given amount of gas and fail if not enough gas units are left.
     pub fn deduct_gas(&mut self, amount: InternalGasUnits<GasCarrier>) -> PartialVMResult<()> {
+        self.deduct_gas_impl(amount, None)
+    }
+
+    /// Charge a given amount of gas like `deduct_gas`, but attribute it to the pseudo-frame
+    /// `profiler_frame` on top of the current call stack when profiling (see `gas_profiler`).
+    pub fn deduct_gas_attributed(
+        &mut self,
+        amount: InternalGasUnits<GasCarrier>,
+        profiler_frame: &str,
+    ) -> PartialVMResult<()> {
+        self.deduct_gas_impl(amount, Some(profiler_frame))
+    }
+
+    /// Charge a given amount of gas, attributed to the pseudo-frame `profiler_leaf` (if any) on
+    /// top of the current call stack when profiling.
+    fn deduct_gas_impl(
+        &mut self,
+        amount: InternalGasUnits<GasCarrier>,
+        profiler_leaf: Option<&str>,
+    ) -> PartialVMResult<()> {
         if !self.charge {
             return Ok(());
         }
@@ -86,8 +112,14 @@ impl<'a> GasStatus<'a> {
             .app(&amount, |curr_gas, gas_amt| curr_gas >= gas_amt)
         {
             self.gas_left = self.gas_left.sub(amount);
+            if self.profile {
+                gas_profiler::record(amount.get(), profiler_leaf);
+            }
             Ok(())
         } else {
+            if self.profile {
+                gas_profiler::record(self.gas_left.get(), profiler_leaf);
+            }
             // Zero out the internal gas state
             self.gas_left = InternalGasUnits::new(0);
             Err(PartialVMError::new(StatusCode::OUT_OF_GAS))
@@ -103,12 +135,17 @@ impl<'a> GasStatus<'a> {
         // Make sure that the size is always non-zero
         let size = size.map(|x| std::cmp::max(1, x));
         debug_assert!(size.get() > 0);
-        self.deduct_gas(
-            self.cost_table
-                .instruction_cost(opcode as u8)
-                .total()
-                .mul(size),
-        )
+        let amount = self
+            .cost_table
+            .instruction_cost(opcode as u8)
+            .total()
+            .mul(size);
+        if self.profile && is_global_storage_opcode(opcode) {
+            let leaf = format!("{} {:?}", gas_profiler::STORAGE_FRAME_PREFIX, opcode);
+            self.deduct_gas_impl(amount, Some(&leaf))
+        } else {
+            self.deduct_gas(amount)
+        }
     }
 
     /// Charge an instruction and fail if not enough gas units are left.
@@ -123,7 +160,7 @@ impl<'a> GasStatus<'a> {
         intrinsic_cost: AbstractMemorySize<GasCarrier>,
     ) -> VMResult<()> {
         let cost = calculate_intrinsic_gas(intrinsic_cost, &self.cost_table.gas_constants);
-        self.deduct_gas(cost)
+        self.deduct_gas_impl(cost, Some(gas_profiler::INTRINSIC_FRAME))
             .map_err(|e| e.finish(Location::Undefined))
     }
 
@@ -132,6 +169,22 @@ impl<'a> GasStatus<'a> {
     }
 }
 
+fn is_global_storage_opcode(opcode: Opcodes) -> bool {
+    matches!(
+        opcode,
+        Opcodes::MUT_BORROW_GLOBAL
+            | Opcodes::MUT_BORROW_GLOBAL_GENERIC
+            | Opcodes::IMM_BORROW_GLOBAL
+            | Opcodes::IMM_BORROW_GLOBAL_GENERIC
+            | Opcodes::EXISTS
+            | Opcodes::EXISTS_GENERIC
+            | Opcodes::MOVE_FROM
+            | Opcodes::MOVE_FROM_GENERIC
+            | Opcodes::MOVE_TO
+            | Opcodes::MOVE_TO_GENERIC
+    )
+}
+
 pub fn new_from_instructions(
     mut instrs: Vec<(Bytecode, GasCost)>,
     native_table: Vec<GasCost>,
@@ -423,6 +476,10 @@ pub static INITIAL_GAS_SCHEDULE: Lazy<CostTable> = Lazy::new(|| {
         (N::CREATE_SIGNER, GasCost::new(24, 1)),
         (N::DESTROY_SIGNER, GasCost::new(212, 1)),
         (N::EMIT_EVENT, GasCost::new(52, 1)),
+        (N::STRING_CHECK_UTF8, GasCost::new(4, 1)),
+        (N::STRING_IS_CHAR_BOUNDARY, GasCost::new(4, 1)),
+        (N::STRING_SUB_STRING, GasCost::new(4, 1)),
+        (N::STRING_INDEX_OF, GasCost::new(4, 1)),
     ];
     native_table.sort_by_key(|cost| cost.0 as u64);
     let raw_native_table = native_table
@@ -470,4 +527,8 @@ pub enum NativeCostIndex {
     CREATE_SIGNER = 15,
     DESTROY_SIGNER = 16,
     EMIT_EVENT = 17,
+    STRING_CHECK_UTF8 = 18,
+    STRING_IS_CHAR_BOUNDARY = 19,
+    STRING_SUB_STRING = 20,
+    STRING_INDEX_OF = 21,
 }
@@ -105,16 +105,20 @@ impl NativeResult {
 }
 
 /// Return the native gas entry in `CostTable` for the given key.
-/// The key is the specific native function index known to `CostTable`.
+/// The key is the specific native function index known to `CostTable`. A table without an entry
+/// for the key is an invariant violation.
 pub fn native_gas(
     table: &CostTable,
     key: NativeCostIndex,
     size: usize,
-) -> InternalGasUnits<GasCarrier> {
-    let gas_amt = table.native_cost(key as u8);
+) -> PartialVMResult<InternalGasUnits<GasCarrier>> {
+    let gas_amt = table.native_cost(key as u8).ok_or_else(|| {
+        PartialVMError::new(StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR)
+            .with_message(format!("No gas cost for native function {:?}", key))
+    })?;
     let memory_size = AbstractMemorySize::new(std::cmp::max(1, size) as GasCarrier);
     debug_assert!(memory_size.get() > 0);
-    gas_amt.total().mul(memory_size)
+    Ok(gas_amt.total().mul(memory_size))
 }
 
 /// Return the argument at the top of the stack.
@@ -137,3 +141,26 @@ macro_rules! pop_arg {
         }
     }};
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gas_schedule::INITIAL_GAS_SCHEDULE;
+
+    #[test]
+    fn test_native_gas_without_cost() {
+        // The native schedule of a chain published before the String natives were added.
+        let mut table = INITIAL_GAS_SCHEDULE.clone();
+        table
+            .native_table
+            .truncate(NativeCostIndex::STRING_CHECK_UTF8 as usize);
+
+        assert!(native_gas(&table, NativeCostIndex::SHA2_256, 1).is_ok());
+        assert_eq!(
+            native_gas(&table, NativeCostIndex::STRING_CHECK_UTF8, 1)
+                .unwrap_err()
+                .major_status(),
+            StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR
+        );
+    }
+}
@@ -15,7 +15,7 @@ use move_core_types::{
     vm_status::{sub_status::NFE_VECTOR_ERROR_BASE, StatusCode},
 };
 use std::{
-    cell::RefCell,
+    cell::{Ref, RefCell},
     fmt::{self, Debug, Display},
     iter,
     mem::size_of,
@@ -1580,6 +1580,17 @@ impl VectorRef {
         Ok(Value::u64(len as u64))
     }
 
+    /// Borrows the bytes of a `&vector<u8>`, for the natives operating on byte strings.
+    pub fn as_bytes_ref(&self) -> PartialVMResult<Ref<Vec<u8>>> {
+        let c = self.0.container();
+        check_elem_layout(&Type::U8, c)?;
+
+        match c {
+            Container::VecU8(r) => Ok(r.borrow()),
+            _ => unreachable!(),
+        }
+    }
+
     pub fn push_back(&self, e: Value, type_param: &Type) -> PartialVMResult<()> {
         let c = self.0.container();
         check_elem_layout(type_param, c)?;
@@ -2302,6 +2313,36 @@ pub mod debug {
     pub fn print_value<B: Write>(buf: &mut B, val: &Value) -> PartialVMResult<()> {
         print_value_impl(buf, &val.0)
     }
+
+    /// Prints each local to its own string, or returns None for the locals that are invalid
+    /// (i.e., not yet assigned or moved out).
+    pub fn locals_to_strings(locals: &Locals) -> PartialVMResult<Vec<Option<String>>> {
+        locals
+            .0
+            .borrow()
+            .iter()
+            .map(|val| match val {
+                ValueImpl::Invalid => Ok(None),
+                val => {
+                    let mut buf = String::new();
+                    print_value_impl(&mut buf, val)?;
+                    Ok(Some(buf))
+                }
+            })
+            .collect()
+    }
+
+    /// Prints the resource held by a global value, returning false without printing anything
+    /// if there is no resource in the slot.
+    pub fn print_global_value<B: Write>(buf: &mut B, val: &GlobalValue) -> PartialVMResult<bool> {
+        match &val.0 {
+            GlobalValueImpl::None | GlobalValueImpl::Deleted => Ok(false),
+            GlobalValueImpl::Fresh { fields } | GlobalValueImpl::Cached { fields, .. } => {
+                print_list(buf, "{ ", fields.borrow().iter(), print_value_impl, " }")?;
+                Ok(true)
+            }
+        }
+    }
 }
 
 /***************************************************************************************
@@ -6,6 +6,7 @@ use std::{
     fmt::Display,
     fs::{create_dir_all, read_to_string},
     io::Write,
+    net::SocketAddr,
     path::{Path, PathBuf},
     time::Instant,
 };
@@ -99,6 +100,15 @@ pub enum PackageCommand {
         /// Verbose mode
         #[structopt(long = "verbose")]
         verbose_mode: bool,
+
+        /// Run the tests under the debugger, driven by commands read from stdin.
+        #[structopt(long = "debug")]
+        debug: bool,
+
+        /// Run the tests under the debugger, driven by an editor attached to a Debug Adapter
+        /// Protocol server listening on this address (e.g., 127.0.0.1:4711).
+        #[structopt(long = "dap", conflicts_with = "debug")]
+        dap: Option<SocketAddr>,
     },
 }
 
@@ -174,6 +184,8 @@ pub fn handle_package_commands(
             report_storage_on_error,
             check_stackless_vm,
             verbose_mode,
+            debug,
+            dap,
         } => {
             let unit_test_config = UnitTestingConfig {
                 instruction_execution_bound: *instruction_execution_bound,
@@ -184,6 +196,8 @@ pub fn handle_package_commands(
                 report_storage_on_error: *report_storage_on_error,
                 check_stackless_vm: *check_stackless_vm,
                 verbose: *verbose_mode,
+                debug: *debug,
+                dap: *dap,
                 ..UnitTestingConfig::default_with_bound(None)
             };
 
@@ -9,8 +9,11 @@ use move_core_types::{
     errmap::ErrorMapping, language_storage::TypeTag, parser,
     transaction_argument::TransactionArgument,
 };
+use move_debugger::DebugFrontend;
+use move_vm_runtime::execution_trace::TraceFormat;
 use std::{
     fs,
+    net::SocketAddr,
     path::{Path, PathBuf},
 };
 use structopt::StructOpt;
@@ -80,6 +83,29 @@ pub enum SandboxCommand {
         /// deleted resources) will NOT be committed to disk.
         #[structopt(long = "dry-run", short = "n")]
         dry_run: bool,
+        /// If set, write a structured trace of every executed instruction to this file.
+        #[structopt(long = "trace", parse(from_os_str))]
+        trace_file: Option<PathBuf>,
+        /// The format of the trace: one JSON event per line (`json`), or length-prefixed BCS
+        /// records (`bcs`).
+        #[structopt(long = "trace-format", default_value = "json")]
+        trace_format: TraceFormat,
+        /// If set, attribute the gas consumed to call stacks, write them to this file in the
+        /// folded stacks format of flamegraph tools, and print a per-function summary. Gas is
+        /// metered with the largest budget if `gas-budget` is not set.
+        #[structopt(long = "gas-profile", parse(from_os_str))]
+        gas_profile: Option<PathBuf>,
+        /// If set, run `script_file` under the debugger, driven by commands read from stdin.
+        #[structopt(long = "debug")]
+        debug: bool,
+        /// If set, run `script_file` under the debugger, driven by an editor attached to a Debug
+        /// Adapter Protocol server listening on this address (e.g., 127.0.0.1:4711).
+        #[structopt(long = "dap", conflicts_with = "debug")]
+        dap: Option<SocketAddr>,
+        /// The source files of the modules called by `script_file`, compiled for their source
+        /// maps when debugging.
+        #[structopt(long = "debug-sources", default_value = DEFAULT_SOURCE_DIR)]
+        debug_sources: Vec<String>,
     },
     /// Run expected value tests using the given batch file.
     #[structopt(name = "test")]
@@ -209,7 +235,18 @@ impl SandboxCommand {
                 type_args,
                 gas_budget,
                 dry_run,
+                trace_file,
+                trace_format,
+                gas_profile,
+                debug,
+                dap,
+                debug_sources,
             } => {
+                let frontend = match dap {
+                    Some(address) => Some(DebugFrontend::Dap(*address)),
+                    None if *debug => Some(DebugFrontend::Console),
+                    None => None,
+                };
                 let state = mode.prepare_state(&move_args.build_dir, &move_args.storage_dir)?;
                 sandbox::commands::run(
                     natives,
@@ -223,6 +260,13 @@ impl SandboxCommand {
                     state.get_named_addresses(additional_named_addresses)?,
                     *gas_budget,
                     *dry_run,
+                    trace_file
+                        .as_deref()
+                        .map(|trace_file| (trace_file, *trace_format)),
+                    gas_profile.as_deref(),
+                    frontend
+                        .as_ref()
+                        .map(|frontend| (frontend, debug_sources.as_slice())),
                     move_args.verbose,
                 )
             }
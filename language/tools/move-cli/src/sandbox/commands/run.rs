@@ -4,11 +4,12 @@
 use crate::{
     sandbox::utils::{
         contains_module, explain_execution_effects, explain_execution_error, get_gas_status,
-        is_bytecode_file, maybe_commit_effects, on_disk_state_view::OnDiskStateView,
+        is_bytecode_file, max_gas_budget, maybe_commit_effects,
+        on_disk_state_view::OnDiskStateView,
     },
     NativeFunctionRecord,
 };
-use move_binary_format::file_format::{CompiledModule, CompiledScript};
+use move_binary_format::file_format::CompiledModule;
 use move_core_types::{
     account_address::AccountAddress,
     errmap::ErrorMapping,
@@ -16,14 +17,29 @@ use move_core_types::{
     language_storage::TypeTag,
     transaction_argument::{convert_txn_args, TransactionArgument},
 };
+use move_debugger::{source::DebugInfo, DebugFrontend};
 use move_lang::{
-    self, compiled_unit::AnnotatedCompiledUnit, shared::NumericalAddress, Compiler, Flags,
+    self,
+    compiled_unit::{AnnotatedCompiledUnit, NamedCompiledScript},
+    diagnostics::FilesSourceText,
+    shared::NumericalAddress,
+    Compiler, Flags,
 };
-use move_vm_runtime::move_vm::MoveVM;
+use move_vm_runtime::{
+    execution_trace::{self, TraceFormat},
+    move_vm::MoveVM,
+};
+use move_vm_types::gas_profiler;
 
 use anyhow::{anyhow, bail, Result};
-use std::{collections::BTreeMap, fs, path::Path};
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::BufWriter,
+    path::Path,
+};
 
+#[allow(clippy::too_many_arguments)]
 pub fn run(
     natives: impl IntoIterator<Item = NativeFunctionRecord>,
     error_descriptions: &ErrorMapping,
@@ -36,6 +52,9 @@ pub fn run(
     named_address_mapping: BTreeMap<String, NumericalAddress>,
     gas_budget: Option<u64>,
     dry_run: bool,
+    trace: Option<(&Path, TraceFormat)>,
+    gas_profile: Option<&Path>,
+    debug: Option<(&DebugFrontend, &[String])>,
     verbose: bool,
 ) -> Result<()> {
     fn compile_script(
@@ -43,11 +62,11 @@ pub fn run(
         script_path: &Path,
         named_address_mapping: BTreeMap<String, NumericalAddress>,
         verbose: bool,
-    ) -> Result<Option<CompiledScript>> {
+    ) -> Result<(FilesSourceText, Option<NamedCompiledScript>)> {
         if verbose {
             println!("Compiling transaction script...")
         }
-        let (_files, compiled_units) = Compiler::new(
+        let (files, compiled_units) = Compiler::new(
             &[script_path.to_string_lossy().to_string()],
             &[state.interface_files_dir()?],
         )
@@ -62,7 +81,7 @@ pub fn run(
                     if script_opt.is_some() {
                         bail!("Error: Found more than one script")
                     }
-                    script_opt = Some(annot_script.named_script.script)
+                    script_opt = Some(annot_script.named_script)
                 }
                 AnnotatedCompiledUnit::Module(annot_module) => {
                     if verbose {
@@ -76,12 +95,51 @@ pub fn run(
             }
         }
 
-        Ok(script_opt)
+        Ok((files, script_opt))
+    }
+
+    /// Compiles the modules in `source_files` for their source maps, ignoring the paths that do
+    /// not exist.
+    fn compile_debug_sources(
+        state: &OnDiskStateView,
+        source_files: &[String],
+        named_address_mapping: BTreeMap<String, NumericalAddress>,
+        debug_info: &mut DebugInfo,
+    ) -> Result<()> {
+        let source_files: Vec<_> = source_files
+            .iter()
+            .filter(|path| Path::new(path).exists())
+            .cloned()
+            .collect();
+        if source_files.is_empty() {
+            return Ok(());
+        }
+        let (files, compiled_units) = Compiler::new(&source_files, &[state.interface_files_dir()?])
+            .set_flags(Flags::empty().set_sources_shadow_deps(true))
+            .set_named_address_values(named_address_mapping)
+            .build_and_report()?;
+        debug_info.add_files(&files);
+        for unit in compiled_units {
+            if let AnnotatedCompiledUnit::Module(annot_module) = unit {
+                let module = annot_module.named_module;
+                debug_info.add_source_map(Some(module.module.self_id()), module.source_map);
+            }
+        }
+        Ok(())
     }
 
     if !script_path.exists() {
         bail!("Script file {:?} does not exist", script_path)
     };
+    let mut debug_info = DebugInfo::new();
+    if let Some((_, debug_sources)) = debug {
+        compile_debug_sources(
+            state,
+            debug_sources,
+            named_address_mapping.clone(),
+            &mut debug_info,
+        )?;
+    }
     let bytecode = if is_bytecode_file(script_path) {
         assert!(
             state.is_module_path(script_path) || !contains_module(script_path),
@@ -93,11 +151,14 @@ move run` must be applied to a module inside `storage/`",
         fs::read(script_path)?
     } else {
         // script source file; compile first and then extract bytecode
-        let script_opt = compile_script(state, script_path, named_address_mapping, verbose)?;
+        let (files, script_opt) =
+            compile_script(state, script_path, named_address_mapping, verbose)?;
         match script_opt {
             Some(script) => {
                 let mut script_bytes = vec![];
-                script.serialize(&mut script_bytes)?;
+                script.script.serialize(&mut script_bytes)?;
+                debug_info.add_files(&files);
+                debug_info.add_source_map(None, script.source_map);
                 script_bytes
             }
             None => bail!("Unable to find script in file {:?}", script_path),
@@ -112,9 +173,25 @@ move run` must be applied to a module inside `storage/`",
     let vm_args: Vec<Vec<u8>> = convert_txn_args(txn_args);
 
     let vm = MoveVM::new(natives).unwrap();
+    let gas_budget = if gas_profile.is_some() {
+        gas_profiler::start_gas_profiling();
+        // Gas can only be profiled when it is metered, so default to the largest budget.
+        gas_budget.or_else(|| Some(max_gas_budget() - 1))
+    } else {
+        gas_budget
+    };
     let mut gas_status = get_gas_status(gas_budget)?;
     let mut session = vm.new_session(state);
 
+    if let Some((trace_file, trace_format)) = trace {
+        execution_trace::set_trace_sink(
+            trace_format.writer(BufWriter::new(File::create(trace_file)?)),
+        );
+    }
+    if let Some((frontend, _)) = debug {
+        move_debugger::attach(frontend, debug_info)?;
+    }
+
     let script_type_parameters = vec![];
     let script_parameters = vec![];
     let res = match script_name_opt {
@@ -142,6 +219,21 @@ move run` must be applied to a module inside `storage/`",
         ),
     };
 
+    if trace.is_some() {
+        if let Some(mut sink) = execution_trace::take_trace_sink() {
+            sink.finish()?;
+        }
+    }
+    if debug.is_some() {
+        move_debugger::detach()?;
+    }
+    if let Some(gas_profile) = gas_profile {
+        if let Some(profile) = gas_profiler::finish_gas_profiling() {
+            fs::write(gas_profile, profile.to_folded_stacks())?;
+            print!("{}", profile.summary_table());
+        }
+    }
+
     if let Err(err) = res {
         explain_execution_error(
             error_descriptions,
@@ -36,12 +36,21 @@ use move_binary_format::layout::GetModule;
 pub use on_disk_state_view::*;
 pub use package::*;
 
+/// The (exclusive) upper bound of the gas budget.
+pub fn max_gas_budget() -> u64 {
+    u64::MAX
+        .checked_div(
+            move_vm_types::gas_schedule::INITIAL_GAS_SCHEDULE
+                .gas_constants
+                .gas_unit_scaling_factor,
+        )
+        .unwrap()
+}
+
 pub fn get_gas_status(gas_budget: Option<u64>) -> Result<GasStatus<'static>> {
     let gas_status = if let Some(gas_budget) = gas_budget {
         let gas_schedule = &move_vm_types::gas_schedule::INITIAL_GAS_SCHEDULE;
-        let max_gas_budget = u64::MAX
-            .checked_div(gas_schedule.gas_constants.gas_unit_scaling_factor)
-            .unwrap();
+        let max_gas_budget = max_gas_budget();
         if gas_budget >= max_gas_budget {
             bail!("Gas budget set too high; maximum is {}", max_gas_budget)
         }
@@ -0,0 +1,376 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A command line front-end, in the style of gdb.
+
+use crate::{
+    engine::{Engine, FunctionBreakpoint, ResumeMode, SourceFrame, StopReason},
+    source::SourceLocation,
+    Frontend,
+};
+use anyhow::{anyhow, bail, Result};
+use move_command_line_common::files::FileHash;
+use move_vm_runtime::debugger::DebugState;
+use std::io::{self, BufRead, BufReader, Stdin, Stdout, Write};
+
+const PROMPT: &str = "(move-debug) ";
+
+/// The number of lines shown around the current line by `list`.
+const LIST_CONTEXT: usize = 5;
+
+const HELP: &str = "\
+Breakpoints:
+  break <file>:<line>       stop when the line is entered
+  break <function>          stop when the function is called, e.g. `0x1::Vector::length`
+  delete <file>:<line>      remove a breakpoint
+  delete <function>
+  breakpoints               list the breakpoints
+Execution:
+  continue (c)              run until the next breakpoint
+  step (s)                  step to the next line, entering calls
+  next (n)                  step to the next line, over calls
+  finish (f)                run until the current function returns
+  stepi (si)                execute one instruction
+  detach                    remove all breakpoints and run to completion
+Inspection:
+  backtrace (bt)            print the call stack
+  frame <n>                 select the frame of the call stack to inspect
+  locals                    print the locals of the selected frame
+  print (p) <local>         print a local of the selected frame
+  operands                  print the operand stack
+  resource <address> <type> print a resource from global storage
+  list (l)                  print the source around the current line
+";
+
+pub struct ConsoleFrontend<R, W> {
+    input: R,
+    output: W,
+    /// Whether the user detached, or the input was closed.
+    detached: bool,
+}
+
+impl ConsoleFrontend<BufReader<Stdin>, Stdout> {
+    pub fn stdio() -> Self {
+        Self::new(BufReader::new(io::stdin()), io::stdout())
+    }
+}
+
+impl<R: BufRead, W: Write> ConsoleFrontend<R, W> {
+    pub fn new(input: R, output: W) -> Self {
+        Self {
+            input,
+            output,
+            detached: false,
+        }
+    }
+
+    pub fn into_output(self) -> W {
+        self.output
+    }
+
+    /// Handles commands until the user resumes execution.
+    fn interact(
+        &mut self,
+        engine: &mut Engine,
+        state: &mut dyn DebugState,
+        reason: StopReason,
+    ) -> io::Result<ResumeMode> {
+        let frames = engine.frames(state);
+        let mut selected = 0;
+        self.print_stop(engine, &frames[0], &reason)?;
+        loop {
+            write!(self.output, "{}", PROMPT)?;
+            self.output.flush()?;
+            let mut line = String::new();
+            if self.input.read_line(&mut line)? == 0 {
+                writeln!(self.output)?;
+                return Ok(self.detach(engine));
+            }
+            match self.execute(engine, state, &frames, &mut selected, line.trim()) {
+                Ok(Some(mode)) => return Ok(mode),
+                Ok(None) => (),
+                Err(err) => writeln!(self.output, "{}", err)?,
+            }
+        }
+    }
+
+    /// Executes a command, and returns how execution resumes if the command resumes it.
+    fn execute(
+        &mut self,
+        engine: &mut Engine,
+        state: &mut dyn DebugState,
+        frames: &[SourceFrame],
+        selected: &mut usize,
+        command: &str,
+    ) -> Result<Option<ResumeMode>> {
+        let (name, argument) = match command.find(char::is_whitespace) {
+            Some(idx) => (&command[..idx], command[idx..].trim()),
+            None => (command, ""),
+        };
+        let mode = match name {
+            "" => None,
+            "c" | "continue" => Some(ResumeMode::Continue),
+            "s" | "step" => Some(ResumeMode::StepIn),
+            "n" | "next" => Some(ResumeMode::StepOver),
+            "f" | "finish" => Some(ResumeMode::StepOut),
+            "si" | "stepi" => Some(ResumeMode::StepInstruction),
+            "detach" => Some(self.detach(engine)),
+            "b" | "break" => {
+                self.add_breakpoint(engine, argument)?;
+                None
+            }
+            "d" | "delete" => {
+                self.delete_breakpoint(engine, argument)?;
+                None
+            }
+            "breakpoints" => {
+                self.print_breakpoints(engine)?;
+                None
+            }
+            "bt" | "backtrace" => {
+                for (idx, frame) in frames.iter().enumerate() {
+                    let marker = if idx == *selected { '*' } else { ' ' };
+                    write!(self.output, "{} #{} ", marker, idx)?;
+                    self.print_frame(engine, frame)?;
+                }
+                None
+            }
+            "frame" => {
+                let idx: usize = argument
+                    .parse()
+                    .map_err(|_| anyhow!("Expected: frame <n>"))?;
+                if idx >= frames.len() {
+                    bail!("No frame {}", idx)
+                }
+                *selected = idx;
+                write!(self.output, "#{} ", idx)?;
+                self.print_frame(engine, &frames[idx])?;
+                None
+            }
+            "locals" => {
+                let frame = &frames[*selected];
+                if frame.locals.is_empty() {
+                    writeln!(self.output, "(none)")?;
+                }
+                for (name, value) in &frame.locals {
+                    writeln!(self.output, "{} = {}", name, value)?;
+                }
+                None
+            }
+            "p" | "print" => {
+                let value = engine.evaluate(state, *selected, argument)?;
+                writeln!(self.output, "{} = {}", argument, value)?;
+                None
+            }
+            "resource" => {
+                let value = engine.evaluate(state, *selected, command)?;
+                writeln!(self.output, "{}", value)?;
+                None
+            }
+            "operands" => {
+                let operands = state.operand_stack();
+                if operands.is_empty() {
+                    writeln!(self.output, "(empty)")?;
+                }
+                for (idx, value) in operands.iter().enumerate().rev() {
+                    writeln!(self.output, "[{}] {}", idx, value)?;
+                }
+                None
+            }
+            "l" | "list" => {
+                self.list(engine, &frames[*selected])?;
+                None
+            }
+            "h" | "help" => {
+                write!(self.output, "{}", HELP)?;
+                None
+            }
+            _ => bail!(
+                "Unknown command '{}'. Type 'help' for a list of commands.",
+                name
+            ),
+        };
+        Ok(mode)
+    }
+
+    fn detach(&mut self, engine: &mut Engine) -> ResumeMode {
+        engine.clear_breakpoints();
+        self.detached = true;
+        ResumeMode::Continue
+    }
+
+    fn add_breakpoint(&mut self, engine: &mut Engine, argument: &str) -> Result<()> {
+        match parse_line_breakpoint(engine, argument)? {
+            Some((file, line)) => {
+                let line = engine
+                    .add_line_breakpoint(file, line)
+                    .ok_or_else(|| anyhow!("No code at or after line {}", line))?;
+                let path = engine
+                    .info()
+                    .file(&file)
+                    .map_or("?", |file| file.path.as_str());
+                writeln!(self.output, "Breakpoint set on {}:{}", path, line)?;
+            }
+            None => {
+                let breakpoint: FunctionBreakpoint = argument.parse()?;
+                writeln!(self.output, "Breakpoint set on {}", breakpoint)?;
+                engine.add_function_breakpoint(breakpoint);
+            }
+        }
+        Ok(())
+    }
+
+    fn delete_breakpoint(&mut self, engine: &mut Engine, argument: &str) -> Result<()> {
+        let removed = match parse_line_breakpoint(engine, argument)? {
+            Some((file, line)) => engine.remove_line_breakpoint(file, line),
+            None => engine.remove_function_breakpoint(&argument.parse()?),
+        };
+        if !removed {
+            bail!("No breakpoint on {}", argument)
+        }
+        Ok(())
+    }
+
+    fn print_breakpoints(&mut self, engine: &Engine) -> io::Result<()> {
+        let mut any = false;
+        for (file, line) in engine.line_breakpoints() {
+            let path = engine
+                .info()
+                .file(&file)
+                .map_or("?", |file| file.path.as_str());
+            writeln!(self.output, "{}:{}", path, line)?;
+            any = true;
+        }
+        for breakpoint in engine.function_breakpoints() {
+            writeln!(self.output, "{}", breakpoint)?;
+            any = true;
+        }
+        if !any {
+            writeln!(self.output, "(none)")?;
+        }
+        Ok(())
+    }
+
+    fn print_stop(
+        &mut self,
+        engine: &Engine,
+        frame: &SourceFrame,
+        reason: &StopReason,
+    ) -> io::Result<()> {
+        match reason {
+            StopReason::Entry => write!(self.output, "Stopped on entry in ")?,
+            StopReason::Breakpoint | StopReason::FunctionBreakpoint => {
+                write!(self.output, "Breakpoint hit in ")?
+            }
+            StopReason::Step | StopReason::Pause => write!(self.output, "Stopped in ")?,
+            StopReason::Error(error) => {
+                writeln!(self.output, "Execution failed: {}", error)?;
+                write!(self.output, "Stopped in ")?
+            }
+        }
+        self.print_frame(engine, frame)?;
+        if let Some(location) = &frame.location {
+            self.print_line(engine, location, location.line, true)?;
+        }
+        Ok(())
+    }
+
+    fn print_frame(&mut self, engine: &Engine, frame: &SourceFrame) -> io::Result<()> {
+        write!(self.output, "{}", frame.info.function)?;
+        if !frame.info.type_arguments.is_empty() {
+            let type_arguments: Vec<_> = frame
+                .info
+                .type_arguments
+                .iter()
+                .map(|ty| ty.to_string())
+                .collect();
+            write!(self.output, "<{}>", type_arguments.join(", "))?;
+        }
+        match &frame.location {
+            Some(location) => writeln!(
+                self.output,
+                " at {}:{}:{}",
+                engine
+                    .info()
+                    .file(&location.file)
+                    .map_or("?", |file| file.path.as_str()),
+                location.line,
+                location.column
+            ),
+            None => writeln!(self.output, " at offset {}", frame.info.pc),
+        }
+    }
+
+    fn print_line(
+        &mut self,
+        engine: &Engine,
+        location: &SourceLocation,
+        line: usize,
+        current: bool,
+    ) -> io::Result<()> {
+        if let Some(text) = engine
+            .info()
+            .file(&location.file)
+            .and_then(|file| file.line(line))
+        {
+            let marker = if current { '>' } else { ' ' };
+            writeln!(self.output, "{} {:>4} | {}", marker, line, text)?;
+        }
+        Ok(())
+    }
+
+    fn list(&mut self, engine: &Engine, frame: &SourceFrame) -> Result<()> {
+        let location = frame
+            .location
+            .ok_or_else(|| anyhow!("No source for {}", frame.info.function))?;
+        let line_count = engine
+            .info()
+            .file(&location.file)
+            .map_or(0, |file| file.line_count());
+        let first = location.line.saturating_sub(LIST_CONTEXT).max(1);
+        let last = (location.line + LIST_CONTEXT).min(line_count);
+        for line in first..=last {
+            self.print_line(engine, &location, line, line == location.line)?;
+        }
+        Ok(())
+    }
+}
+
+impl<R: BufRead, W: Write> Frontend for ConsoleFrontend<R, W> {
+    fn stopped(
+        &mut self,
+        engine: &mut Engine,
+        state: &mut dyn DebugState,
+        reason: StopReason,
+    ) -> ResumeMode {
+        if self.detached {
+            return ResumeMode::Continue;
+        }
+        // The debugger cannot fail execution: if the console is gone, just run to completion.
+        self.interact(engine, state, reason)
+            .unwrap_or_else(|_| self.detach(engine))
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if !self.detached {
+            writeln!(self.output, "Execution finished")?;
+        }
+        self.output.flush()
+    }
+}
+
+/// Parses a breakpoint of the form `<file>:<line>`, returning None for function breakpoints.
+fn parse_line_breakpoint(engine: &Engine, argument: &str) -> Result<Option<(FileHash, usize)>> {
+    let (file, line) = match argument.rfind(':') {
+        Some(idx) if !argument[..idx].ends_with(':') => (&argument[..idx], &argument[idx + 1..]),
+        _ => return Ok(None),
+    };
+    let line = line
+        .parse()
+        .map_err(|_| anyhow!("Invalid line number: {}", line))?;
+    let file = engine
+        .info()
+        .find_file(file)
+        .ok_or_else(|| anyhow!("Unknown source file: {}", file))?;
+    Ok(Some((file, line)))
+}
@@ -0,0 +1,442 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A Debug Adapter Protocol server, through which editors drive the debugger.
+//!
+//! The server accepts a single client over TCP, and exchanges JSON messages framed by a
+//! `Content-Length` header with it. Execution starts once the client has sent
+//! `configurationDone`. Move code runs on a single thread, reported as thread 1, and values are
+//! reported as strings without children. The `evaluate` request accepts the name of a local, or
+//! `resource <address> <type>` to read a resource from global storage.
+
+use crate::{
+    engine::{Engine, FunctionBreakpoint, ResumeMode, StopReason},
+    Frontend,
+};
+use anyhow::{anyhow, Result};
+use move_vm_runtime::debugger::DebugState;
+use serde_json::{json, Value};
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::Duration,
+};
+use tracing::info;
+
+/// The id of the only thread reported to the client.
+const THREAD_ID: u64 = 1;
+
+/// The variables reference of the operand stack. The locals of frame `n` have reference
+/// `2 * n + 1`.
+const OPERAND_STACK_REFERENCE: u64 = 2;
+
+/// How long to wait for the client to disconnect once execution is over.
+const DISCONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct DapFrontend {
+    output: TcpStream,
+    requests: Receiver<Value>,
+    seq: u64,
+    configured: bool,
+    disconnected: bool,
+}
+
+impl DapFrontend {
+    /// Waits for a client to connect to `address` and to configure the debugger.
+    pub fn listen(address: SocketAddr, engine: &mut Engine) -> Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        info!(
+            "Waiting for a debugger to attach on {}",
+            listener.local_addr()?
+        );
+        let (stream, _) = listener.accept()?;
+        Self::new(stream, engine)
+    }
+
+    /// Serves the client connected through `stream`, and returns once it has configured the
+    /// debugger.
+    pub fn new(stream: TcpStream, engine: &mut Engine) -> Result<Self> {
+        let mut input = BufReader::new(stream.try_clone()?);
+        let (sender, requests) = mpsc::channel();
+        // Requests are read on their own thread, so that they can be polled while Move code runs.
+        thread::spawn(move || {
+            while let Ok(Some(message)) = read_message(&mut input) {
+                if sender.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+        let mut frontend = Self {
+            output: stream,
+            requests,
+            seq: 0,
+            configured: false,
+            disconnected: false,
+        };
+        while !frontend.configured && !frontend.disconnected {
+            match frontend.requests.recv() {
+                Ok(request) => {
+                    frontend.handle(engine, None, &request)?;
+                }
+                Err(_) => return Err(anyhow!("The client disconnected")),
+            }
+        }
+        Ok(frontend)
+    }
+
+    /// Handles a request, and returns how execution resumes if the request resumes it. `state`
+    /// is None while Move code runs.
+    fn handle(
+        &mut self,
+        engine: &mut Engine,
+        state: Option<&mut dyn DebugState>,
+        request: &Value,
+    ) -> io::Result<Option<ResumeMode>> {
+        let command = request["command"].as_str().unwrap_or_default();
+        let arguments = &request["arguments"];
+        let mut resume = None;
+        let result = match (command, state) {
+            ("initialize", _) => {
+                let capabilities = json!({
+                    "supportsConfigurationDoneRequest": true,
+                    "supportsFunctionBreakpoints": true,
+                    "supportsSteppingGranularity": true,
+                });
+                self.respond(request, Ok(capabilities))?;
+                return self.send_event("initialized", json!({})).map(|_| None);
+            }
+            ("launch", _) | ("attach", _) | ("setExceptionBreakpoints", _) => Ok(json!({})),
+            ("configurationDone", _) => {
+                self.configured = true;
+                Ok(json!({}))
+            }
+            ("setBreakpoints", _) => Ok(set_breakpoints(engine, arguments)),
+            ("setFunctionBreakpoints", _) => Ok(set_function_breakpoints(engine, arguments)),
+            ("threads", _) => Ok(json!({ "threads": [{ "id": THREAD_ID, "name": "Move VM" }] })),
+            ("pause", None) => {
+                engine.pause();
+                Ok(json!({}))
+            }
+            ("pause", Some(_)) => Ok(json!({})),
+            ("disconnect", _) => {
+                engine.clear_breakpoints();
+                self.disconnected = true;
+                resume = Some(ResumeMode::Continue);
+                Ok(json!({}))
+            }
+            ("continue", Some(_)) => {
+                resume = Some(ResumeMode::Continue);
+                Ok(json!({ "allThreadsContinued": true }))
+            }
+            ("next", Some(_)) | ("stepIn", Some(_)) | ("stepOut", Some(_)) => {
+                resume = Some(match (command, arguments["granularity"].as_str()) {
+                    (_, Some("instruction")) => ResumeMode::StepInstruction,
+                    ("next", _) => ResumeMode::StepOver,
+                    ("stepIn", _) => ResumeMode::StepIn,
+                    _ => ResumeMode::StepOut,
+                });
+                Ok(json!({}))
+            }
+            ("stackTrace", Some(state)) => Ok(stack_trace(engine, state)),
+            ("scopes", Some(_)) => Ok(scopes(arguments["frameId"].as_u64().unwrap_or(0))),
+            ("variables", Some(state)) => variables(engine, state, arguments),
+            ("evaluate", Some(state)) => engine
+                .evaluate(
+                    state,
+                    arguments["frameId"].as_u64().unwrap_or(0) as usize,
+                    arguments["expression"].as_str().unwrap_or_default(),
+                )
+                .map(|result| json!({ "result": result, "variablesReference": 0 })),
+            (_, None) => Err(anyhow!("'{}' is only supported while stopped", command)),
+            (_, Some(_)) => Err(anyhow!("Unsupported request '{}'", command)),
+        };
+        self.respond(request, result)?;
+        Ok(resume)
+    }
+
+    fn respond(&mut self, request: &Value, result: Result<Value>) -> io::Result<()> {
+        let mut response = json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+        });
+        match result {
+            Ok(body) => {
+                response["success"] = json!(true);
+                response["body"] = body;
+            }
+            Err(err) => {
+                response["success"] = json!(false);
+                response["message"] = json!(err.to_string());
+            }
+        }
+        self.send(response)
+    }
+
+    fn send_event(&mut self, event: &str, body: Value) -> io::Result<()> {
+        self.send(json!({ "type": "event", "event": event, "body": body }))
+    }
+
+    fn send(&mut self, mut message: Value) -> io::Result<()> {
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+        write_message(&mut self.output, &message)
+    }
+
+    /// Reports a stop, and handles requests until the client resumes execution.
+    fn interact(
+        &mut self,
+        engine: &mut Engine,
+        state: &mut dyn DebugState,
+        reason: StopReason,
+    ) -> io::Result<ResumeMode> {
+        let (reason, text) = match reason {
+            StopReason::Entry => ("entry", None),
+            StopReason::Breakpoint => ("breakpoint", None),
+            StopReason::FunctionBreakpoint => ("function breakpoint", None),
+            StopReason::Step => ("step", None),
+            StopReason::Pause => ("pause", None),
+            StopReason::Error(error) => ("exception", Some(error)),
+        };
+        let mut body = json!({
+            "reason": reason,
+            "threadId": THREAD_ID,
+            "allThreadsStopped": true,
+        });
+        if let Some(text) = text {
+            body["description"] = json!("Execution failed");
+            body["text"] = json!(text);
+        }
+        self.send_event("stopped", body)?;
+        loop {
+            let request = match self.requests.recv() {
+                Ok(request) => request,
+                Err(_) => return Ok(self.detach(engine)),
+            };
+            if let Some(mode) = self.handle(engine, Some(&mut *state), &request)? {
+                return Ok(mode);
+            }
+        }
+    }
+
+    fn detach(&mut self, engine: &mut Engine) -> ResumeMode {
+        engine.clear_breakpoints();
+        self.disconnected = true;
+        ResumeMode::Continue
+    }
+}
+
+impl Frontend for DapFrontend {
+    fn poll(&mut self, engine: &mut Engine) {
+        if self.disconnected {
+            return;
+        }
+        while let Ok(request) = self.requests.try_recv() {
+            if self.handle(engine, None, &request).is_err() {
+                self.detach(engine);
+                return;
+            }
+        }
+    }
+
+    fn stopped(
+        &mut self,
+        engine: &mut Engine,
+        state: &mut dyn DebugState,
+        reason: StopReason,
+    ) -> ResumeMode {
+        if self.disconnected {
+            return ResumeMode::Continue;
+        }
+        // The debugger cannot fail execution: if the client is gone, just run to completion.
+        self.interact(engine, state, reason)
+            .unwrap_or_else(|_| self.detach(engine))
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if self.disconnected {
+            return Ok(());
+        }
+        self.send_event("terminated", json!({}))?;
+        // Acknowledge the disconnection of the client, which follows the termination.
+        loop {
+            match self.requests.recv_timeout(DISCONNECT_TIMEOUT) {
+                Ok(request) if request["command"] == "disconnect" => {
+                    self.disconnected = true;
+                    return self.respond(&request, Ok(json!({})));
+                }
+                Ok(request) => self.respond(&request, Err(anyhow!("Execution is over")))?,
+                Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => {
+                    return Ok(())
+                }
+            }
+        }
+    }
+}
+
+fn set_breakpoints(engine: &mut Engine, arguments: &Value) -> Value {
+    let path = arguments["source"]["path"].as_str().unwrap_or_default();
+    let lines: Vec<usize> = arguments["breakpoints"]
+        .as_array()
+        .map(|breakpoints| {
+            breakpoints
+                .iter()
+                .filter_map(|breakpoint| breakpoint["line"].as_u64())
+                .map(|line| line as usize)
+                .collect()
+        })
+        .unwrap_or_default();
+    let breakpoints: Vec<_> = match engine.info().find_file(path) {
+        Some(file) => engine
+            .set_line_breakpoints(file, &lines)
+            .into_iter()
+            .map(|line| match line {
+                Some(line) => json!({ "verified": true, "line": line }),
+                None => json!({ "verified": false, "message": "No code at or after this line" }),
+            })
+            .collect(),
+        None => lines
+            .iter()
+            .map(|_| json!({ "verified": false, "message": "Unknown source file" }))
+            .collect(),
+    };
+    json!({ "breakpoints": breakpoints })
+}
+
+fn set_function_breakpoints(engine: &mut Engine, arguments: &Value) -> Value {
+    let names: Vec<&str> = arguments["breakpoints"]
+        .as_array()
+        .map(|breakpoints| {
+            breakpoints
+                .iter()
+                .filter_map(|breakpoint| breakpoint["name"].as_str())
+                .collect()
+        })
+        .unwrap_or_default();
+    let mut valid = vec![];
+    let breakpoints: Vec<_> = names
+        .into_iter()
+        .map(|name| match name.parse::<FunctionBreakpoint>() {
+            Ok(breakpoint) => {
+                valid.push(breakpoint);
+                json!({ "verified": true })
+            }
+            Err(err) => json!({ "verified": false, "message": err.to_string() }),
+        })
+        .collect();
+    engine.set_function_breakpoints(valid);
+    json!({ "breakpoints": breakpoints })
+}
+
+fn stack_trace(engine: &Engine, state: &mut dyn DebugState) -> Value {
+    let frames: Vec<_> = engine
+        .frames(state)
+        .into_iter()
+        .enumerate()
+        .map(|(idx, frame)| {
+            let mut stack_frame = json!({
+                "id": idx,
+                "name": frame.info.function,
+                "line": 0,
+                "column": 0,
+                "instructionPointerReference": frame.info.pc.to_string(),
+            });
+            if let Some(location) = &frame.location {
+                if let Some(file) = engine.info().file(&location.file) {
+                    stack_frame["source"] = json!({ "path": file.path });
+                    stack_frame["line"] = json!(location.line);
+                    stack_frame["column"] = json!(location.column);
+                }
+            }
+            stack_frame
+        })
+        .collect();
+    json!({ "totalFrames": frames.len(), "stackFrames": frames })
+}
+
+fn scopes(frame: u64) -> Value {
+    let mut scopes = vec![json!({
+        "name": "Locals",
+        "variablesReference": 2 * frame + 1,
+        "expensive": false,
+    })];
+    if frame == 0 {
+        scopes.push(json!({
+            "name": "Operand Stack",
+            "variablesReference": OPERAND_STACK_REFERENCE,
+            "expensive": false,
+        }));
+    }
+    json!({ "scopes": scopes })
+}
+
+fn variables(engine: &Engine, state: &mut dyn DebugState, arguments: &Value) -> Result<Value> {
+    let reference = arguments["variablesReference"].as_u64().unwrap_or(0);
+    let variables: Vec<_> = if reference == OPERAND_STACK_REFERENCE {
+        state
+            .operand_stack()
+            .into_iter()
+            .enumerate()
+            .rev()
+            .map(|(idx, value)| (format!("[{}]", idx), value))
+            .collect()
+    } else if reference % 2 == 1 {
+        let frame = (reference / 2) as usize;
+        engine
+            .frames(state)
+            .into_iter()
+            .nth(frame)
+            .ok_or_else(|| anyhow!("No frame {}", frame))?
+            .locals
+    } else {
+        return Err(anyhow!("Unknown variables reference {}", reference));
+    };
+    let variables: Vec<_> = variables
+        .into_iter()
+        .map(|(name, value)| json!({ "name": name, "value": value, "variablesReference": 0 }))
+        .collect();
+    Ok(json!({ "variables": variables }))
+}
+
+/// Reads a message framed by a `Content-Length` header, or returns None at the end of the
+/// stream.
+pub fn read_message<R: BufRead>(reader: &mut R) -> io::Result<Option<Value>> {
+    let mut content_length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(length) = line.strip_prefix("Content-Length:") {
+            content_length = Some(length.trim().parse::<usize>().map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidData, "invalid Content-Length header")
+            })?);
+        }
+    }
+    let content_length = content_length.ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length header")
+    })?;
+    let mut content = vec![0; content_length];
+    reader.read_exact(&mut content)?;
+    serde_json::from_slice(&content)
+        .map(Some)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+/// Writes a message framed by a `Content-Length` header.
+pub fn write_message<W: Write>(writer: &mut W, message: &Value) -> io::Result<()> {
+    let content = message.to_string();
+    write!(
+        writer,
+        "Content-Length: {}\r\n\r\n{}",
+        content.len(),
+        content
+    )?;
+    writer.flush()
+}
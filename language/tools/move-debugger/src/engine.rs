@@ -0,0 +1,402 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! The logic shared by the front-ends of the debugger: breakpoints, stepping, and the inspection
+//! of a paused VM.
+//!
+//! Stepping works on source lines: a line is entered when a frame executes its first instruction
+//! on that line, coming from a different line or from the start of the function. Breakpoints hit
+//! when their line is entered, so that a breakpoint stops execution once per visit of its line
+//! rather than once per instruction on it.
+
+use crate::source::{DebugInfo, SourceLocation};
+use anyhow::{anyhow, bail, Result};
+use move_binary_format::file_format::FunctionDefinitionIndex;
+use move_command_line_common::files::FileHash;
+use move_core_types::{
+    account_address::AccountAddress, identifier::Identifier, language_storage::ModuleId,
+    parser::parse_struct_tag,
+};
+use move_vm_runtime::debugger::{DebugState, FrameInfo};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    str::FromStr,
+};
+
+/// How execution resumes after a stop.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ResumeMode {
+    /// Run until a breakpoint is hit.
+    Continue,
+    /// Stop on the next line entered, including in the functions called.
+    StepIn,
+    /// Stop on the next line entered by the current function or its callers.
+    StepOver,
+    /// Stop once the current function returns.
+    StepOut,
+    /// Stop before the next instruction.
+    StepInstruction,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum StopReason {
+    /// Execution stopped before its first instruction.
+    Entry,
+    Breakpoint,
+    FunctionBreakpoint,
+    Step,
+    /// The user asked execution to stop.
+    Pause,
+    /// An instruction failed, with the given error.
+    Error(String),
+}
+
+/// A breakpoint on the entry of the functions with the given name, optionally restricted to the
+/// given module and address. Written `[[<address>::]<module>::]<function>`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FunctionBreakpoint {
+    pub address: Option<AccountAddress>,
+    pub module: Option<Identifier>,
+    pub function: Identifier,
+}
+
+/// A frame of the call stack, mapped back to the source.
+#[derive(Clone, Debug)]
+pub struct SourceFrame {
+    pub info: FrameInfo,
+    pub location: Option<SourceLocation>,
+    /// The name and value of each valid local. Locals are named after the source when a source
+    /// map is available, and `$<index>` otherwise; temporaries introduced by the compiler are
+    /// omitted.
+    pub locals: Vec<(String, String)>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Mode {
+    Run,
+    Pause(StopReason),
+    StepIn,
+    StepOver { depth: usize },
+    StepOut { depth: usize },
+    StepInstruction,
+}
+
+/// The line last executed by a frame.
+struct LineKey {
+    module: Option<ModuleId>,
+    function: FunctionDefinitionIndex,
+    line: Option<usize>,
+}
+
+pub struct Engine {
+    info: DebugInfo,
+    line_breakpoints: BTreeMap<FileHash, BTreeSet<usize>>,
+    function_breakpoints: Vec<FunctionBreakpoint>,
+    mode: Mode,
+    /// The line last executed by each frame of the call stack, outermost first.
+    lines: Vec<LineKey>,
+}
+
+impl Engine {
+    pub fn new(info: DebugInfo, stop_on_entry: bool) -> Self {
+        Self {
+            info,
+            line_breakpoints: BTreeMap::new(),
+            function_breakpoints: vec![],
+            mode: if stop_on_entry {
+                Mode::Pause(StopReason::Entry)
+            } else {
+                Mode::Run
+            },
+            lines: vec![],
+        }
+    }
+
+    pub fn info(&self) -> &DebugInfo {
+        &self.info
+    }
+
+    //
+    // Breakpoints.
+    //
+
+    /// Sets a breakpoint on the first line at or after `line` that has code, and returns that
+    /// line, or None if there is no such line.
+    pub fn add_line_breakpoint(&mut self, file: FileHash, line: usize) -> Option<usize> {
+        let line = self.info.breakable_line(&file, line)?;
+        self.line_breakpoints.entry(file).or_default().insert(line);
+        Some(line)
+    }
+
+    pub fn remove_line_breakpoint(&mut self, file: FileHash, line: usize) -> bool {
+        self.line_breakpoints
+            .get_mut(&file)
+            .map_or(false, |lines| lines.remove(&line))
+    }
+
+    /// Replaces the breakpoints of `file`, and returns the line each breakpoint was set on.
+    pub fn set_line_breakpoints(&mut self, file: FileHash, lines: &[usize]) -> Vec<Option<usize>> {
+        self.line_breakpoints.remove(&file);
+        lines
+            .iter()
+            .map(|line| self.add_line_breakpoint(file, *line))
+            .collect()
+    }
+
+    pub fn line_breakpoints(&self) -> impl Iterator<Item = (FileHash, usize)> + '_ {
+        self.line_breakpoints
+            .iter()
+            .flat_map(|(file, lines)| lines.iter().map(move |line| (*file, *line)))
+    }
+
+    pub fn add_function_breakpoint(&mut self, breakpoint: FunctionBreakpoint) {
+        if !self.function_breakpoints.contains(&breakpoint) {
+            self.function_breakpoints.push(breakpoint)
+        }
+    }
+
+    pub fn remove_function_breakpoint(&mut self, breakpoint: &FunctionBreakpoint) -> bool {
+        let count = self.function_breakpoints.len();
+        self.function_breakpoints
+            .retain(|other| other != breakpoint);
+        self.function_breakpoints.len() != count
+    }
+
+    pub fn set_function_breakpoints(&mut self, breakpoints: Vec<FunctionBreakpoint>) {
+        self.function_breakpoints = breakpoints;
+    }
+
+    pub fn function_breakpoints(&self) -> &[FunctionBreakpoint] {
+        &self.function_breakpoints
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.line_breakpoints.clear();
+        self.function_breakpoints.clear();
+    }
+
+    //
+    // Execution control.
+    //
+
+    /// Stops execution before the next instruction.
+    pub fn pause(&mut self) {
+        self.mode = Mode::Pause(StopReason::Pause);
+    }
+
+    /// Decides whether execution stops before the current instruction of `state`.
+    pub fn check(&mut self, state: &dyn DebugState) -> Option<StopReason> {
+        let depth = state.call_depth();
+        let location = self
+            .info
+            .location(state.module(), state.function_index(), state.pc());
+        let line = location.map(|location| location.line);
+
+        // Frames above the current one have returned.
+        self.lines.truncate(depth + 1);
+        let entered_line = state.pc() == 0
+            || match self.lines.get(depth) {
+                Some(key) => {
+                    key.line != line
+                        || key.function != state.function_index()
+                        || key.module.as_ref() != state.module()
+                }
+                None => true,
+            };
+        if entered_line {
+            let key = LineKey {
+                module: state.module().cloned(),
+                function: state.function_index(),
+                line,
+            };
+            if depth < self.lines.len() {
+                self.lines[depth] = key;
+            } else {
+                self.lines.push(key);
+            }
+        }
+
+        match &self.mode {
+            Mode::Pause(reason) => return Some(reason.clone()),
+            Mode::StepInstruction => return Some(StopReason::Step),
+            Mode::StepOut { depth: from } if depth < *from => return Some(StopReason::Step),
+            _ => (),
+        }
+        if !entered_line {
+            return None;
+        }
+        if state.pc() == 0
+            && self
+                .function_breakpoints
+                .iter()
+                .any(|breakpoint| breakpoint.matches(state.module(), state.function_name()))
+        {
+            return Some(StopReason::FunctionBreakpoint);
+        }
+        let location = location?;
+        if self
+            .line_breakpoints
+            .get(&location.file)
+            .map_or(false, |lines| lines.contains(&location.line))
+        {
+            return Some(StopReason::Breakpoint);
+        }
+        match &self.mode {
+            Mode::StepIn => Some(StopReason::Step),
+            Mode::StepOver { depth: from } if depth <= *from => Some(StopReason::Step),
+            _ => None,
+        }
+    }
+
+    /// Resumes execution after a stop at the current instruction of `state`.
+    pub fn resume(&mut self, mode: ResumeMode, state: &dyn DebugState) {
+        let depth = state.call_depth();
+        self.mode = match mode {
+            ResumeMode::Continue => Mode::Run,
+            ResumeMode::StepIn => Mode::StepIn,
+            ResumeMode::StepOver => Mode::StepOver { depth },
+            ResumeMode::StepOut => Mode::StepOut { depth },
+            ResumeMode::StepInstruction => Mode::StepInstruction,
+        }
+    }
+
+    //
+    // Inspection.
+    //
+
+    /// Returns the frames of the call stack, innermost first.
+    pub fn frames(&self, state: &dyn DebugState) -> Vec<SourceFrame> {
+        state
+            .call_stack()
+            .into_iter()
+            .rev()
+            .map(|info| self.source_frame(info))
+            .collect()
+    }
+
+    /// Evaluates an expression in the context of the frame at `frame` (0 being the innermost).
+    /// The expression is either the name of a local, or `resource <address> <type>` to read
+    /// a resource from global storage.
+    pub fn evaluate(
+        &self,
+        state: &mut dyn DebugState,
+        frame: usize,
+        expression: &str,
+    ) -> Result<String> {
+        let expression = expression.trim();
+        if let Some(arguments) = expression.strip_prefix("resource ") {
+            return read_resource(state, arguments);
+        }
+        let frames = self.frames(state);
+        let frame = frames
+            .get(frame)
+            .ok_or_else(|| anyhow!("No frame {}", frame))?;
+        // Shadowing locals come after the locals they shadow.
+        frame
+            .locals
+            .iter()
+            .rev()
+            .find(|(name, _)| name == expression)
+            .map(|(_, value)| value.clone())
+            .ok_or_else(|| anyhow!("No local named '{}' in {}", expression, frame.info.function))
+    }
+
+    fn source_frame(&self, info: FrameInfo) -> SourceFrame {
+        let location = self
+            .info
+            .location(info.module.as_ref(), info.function_index, info.pc);
+        let names = self
+            .info
+            .local_names(info.module.as_ref(), info.function_index);
+        let locals = info
+            .locals
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, value)| {
+                let value = value.as_ref()?;
+                let name = match &names {
+                    Some(names) => names.get(idx)?.clone()?,
+                    None => format!("${}", idx),
+                };
+                Some((name, value.clone()))
+            })
+            .collect();
+        SourceFrame {
+            info,
+            location,
+            locals,
+        }
+    }
+}
+
+/// Reads the resource described by `<address> <type>`.
+fn read_resource(state: &mut dyn DebugState, arguments: &str) -> Result<String> {
+    let mut arguments = arguments.split_whitespace();
+    let (address, resource_type) = match (arguments.next(), arguments.next(), arguments.next()) {
+        (Some(address), Some(resource_type), None) => (address, resource_type),
+        _ => bail!("Expected: resource <address> <type>"),
+    };
+    let address = AccountAddress::from_hex_literal(address)
+        .map_err(|_| anyhow!("Invalid address: {}", address))?;
+    let resource_type = parse_struct_tag(resource_type)?;
+    match state
+        .read_resource(address, &resource_type)
+        .map_err(|err| anyhow!("Unable to read resource: {}", err))?
+    {
+        Some(resource) => Ok(resource),
+        None => bail!("No resource {} under {}", resource_type, address),
+    }
+}
+
+impl FunctionBreakpoint {
+    fn matches(&self, module: Option<&ModuleId>, function: &str) -> bool {
+        if self.function.as_str() != function {
+            return false;
+        }
+        match (&self.address, &self.module, module) {
+            (_, None, _) => true,
+            (_, Some(_), None) => false,
+            (address, Some(name), Some(module)) => {
+                module.name() == name.as_ident_str()
+                    && address.map_or(true, |address| *module.address() == address)
+            }
+        }
+    }
+}
+
+impl FromStr for FunctionBreakpoint {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let parts: Vec<_> = s.trim().split("::").collect();
+        let (address, module, function) = match parts.as_slice() {
+            [function] => (None, None, function),
+            [module, function] => (None, Some(module), function),
+            [address, module, function] => (Some(address), Some(module), function),
+            _ => bail!("Invalid function: {}", s),
+        };
+        Ok(Self {
+            address: address
+                .map(|address| {
+                    AccountAddress::from_hex_literal(address)
+                        .map_err(|_| anyhow!("Invalid address: {}", address))
+                })
+                .transpose()?,
+            module: module.map(|module| Identifier::new(*module)).transpose()?,
+            function: Identifier::new(*function)?,
+        })
+    }
+}
+
+impl fmt::Display for FunctionBreakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(address) = &self.address {
+            write!(f, "0x{}::", address.short_str_lossless())?;
+        }
+        if let Some(module) = &self.module {
+            write!(f, "{}::", module)?;
+        }
+        write!(f, "{}", self.function)
+    }
+}
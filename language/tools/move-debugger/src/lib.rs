@@ -0,0 +1,128 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+//! A source-level debugger for Move.
+//!
+//! The debugger attaches to the Move VM through the hooks of `move_vm_runtime::debugger`, and
+//! maps the bytecode being executed back to the source with the source maps of the compiler. It
+//! can be driven from two front-ends:
+//!
+//! - a console (`DebugFrontend::Console`), reading commands from stdin;
+//! - a Debug Adapter Protocol server (`DebugFrontend::Dap`), to which editors attach over TCP.
+
+pub mod console;
+pub mod dap;
+pub mod engine;
+pub mod source;
+
+#[cfg(test)]
+mod unit_tests;
+
+use crate::{
+    console::ConsoleFrontend,
+    dap::DapFrontend,
+    engine::{Engine, ResumeMode, StopReason},
+    source::DebugInfo,
+};
+use anyhow::Result;
+use move_binary_format::errors::PartialVMError;
+use move_vm_runtime::debugger::{self, DebugState, Debugger};
+use std::{io, net::SocketAddr};
+
+/// How the user drives the debugger.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DebugFrontend {
+    /// Commands read from stdin. Execution stops before its first instruction.
+    Console,
+    /// A Debug Adapter Protocol server listening on the given address. Execution starts once a
+    /// client has attached and configured its breakpoints.
+    Dap(SocketAddr),
+}
+
+/// A front-end of the debugger, i.e. the way it interacts with the user.
+pub trait Frontend {
+    /// Called before each instruction that does not stop execution, so that the front-end can
+    /// handle the requests sent by the user while the VM is running.
+    fn poll(&mut self, _engine: &mut Engine) {}
+
+    /// Called when execution stops, and returns how it resumes once the user is done inspecting
+    /// the VM.
+    fn stopped(
+        &mut self,
+        engine: &mut Engine,
+        state: &mut dyn DebugState,
+        reason: StopReason,
+    ) -> ResumeMode;
+
+    /// Called once execution is over.
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The `Debugger` driving the VM on behalf of a front-end.
+pub struct MoveDebugger<F> {
+    engine: Engine,
+    frontend: F,
+}
+
+impl<F: Frontend> MoveDebugger<F> {
+    pub fn new(engine: Engine, frontend: F) -> Self {
+        Self { engine, frontend }
+    }
+
+    pub fn into_frontend(self) -> F {
+        self.frontend
+    }
+
+    fn stop(&mut self, state: &mut dyn DebugState, reason: StopReason) {
+        let mode = self.frontend.stopped(&mut self.engine, state, reason);
+        self.engine.resume(mode, state);
+    }
+}
+
+impl<F: Frontend> Debugger for MoveDebugger<F> {
+    fn on_instruction(&mut self, state: &mut dyn DebugState) {
+        self.frontend.poll(&mut self.engine);
+        if let Some(reason) = self.engine.check(state) {
+            self.stop(state, reason)
+        }
+    }
+
+    fn on_error(&mut self, state: &mut dyn DebugState, error: &PartialVMError) {
+        self.stop(state, StopReason::Error(error.to_string()))
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.frontend.finish()
+    }
+}
+
+/// Attaches a debugger to the Move code executed by the current thread. With the DAP front-end,
+/// this blocks until a client has attached.
+pub fn attach(frontend: &DebugFrontend, info: DebugInfo) -> Result<()> {
+    let debugger: Box<dyn Debugger> = match frontend {
+        DebugFrontend::Console => Box::new(MoveDebugger::new(
+            Engine::new(info, true),
+            ConsoleFrontend::stdio(),
+        )),
+        DebugFrontend::Dap(address) => {
+            let mut engine = Engine::new(info, false);
+            let frontend = DapFrontend::listen(*address, &mut engine)?;
+            Box::new(MoveDebugger::new(engine, frontend))
+        }
+    };
+    debugger::set_debugger(debugger);
+    Ok(())
+}
+
+/// Detaches the debugger of the current thread, if any, letting its user know that execution
+/// is over.
+pub fn detach() -> Result<()> {
+    if let Some(mut debugger) = debugger::take_debugger() {
+        debugger.finish()?;
+    }
+    Ok(())
+}
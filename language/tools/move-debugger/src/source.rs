@@ -0,0 +1,223 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Mapping from bytecode back to Move source, by means of the source maps of the compiler.
+
+use bytecode_source_map::source_map::SourceMap;
+use move_binary_format::file_format::{CodeOffset, FunctionDefinitionIndex};
+use move_command_line_common::files::FileHash;
+use move_core_types::language_storage::ModuleId;
+use move_lang::{
+    compiled_unit::{CompiledUnit, CompiledUnitEnum},
+    diagnostics::FilesSourceText,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::Path,
+};
+
+/// A source file, with the offsets at which its lines start.
+#[derive(Clone, Debug)]
+pub struct SourceFile {
+    pub path: String,
+    pub contents: String,
+    line_starts: Vec<usize>,
+}
+
+/// A position in a source file. Lines and columns start at 1.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct SourceLocation {
+    pub file: FileHash,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// The source files and source maps of the code being debugged.
+#[derive(Clone, Debug, Default)]
+pub struct DebugInfo {
+    files: BTreeMap<FileHash, SourceFile>,
+    modules: BTreeMap<ModuleId, SourceMap>,
+    script: Option<SourceMap>,
+}
+
+impl SourceFile {
+    pub fn new(path: String, contents: String) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(contents.match_indices('\n').map(|(idx, _)| idx + 1))
+            .collect();
+        Self {
+            path,
+            contents,
+            line_starts,
+        }
+    }
+
+    /// Returns the line and column of the byte at `offset`.
+    pub fn line_and_column(&self, offset: usize) -> (usize, usize) {
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next_line) => next_line - 1,
+        };
+        (line + 1, offset - self.line_starts[line] + 1)
+    }
+
+    /// Returns the text of `line`, without its line terminator.
+    pub fn line(&self, line: usize) -> Option<&str> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)?;
+        let end = self
+            .line_starts
+            .get(line)
+            .copied()
+            .unwrap_or_else(|| self.contents.len());
+        Some(self.contents[start..end].trim_end_matches(&['\n', '\r'][..]))
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+}
+
+impl DebugInfo {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the files read by the compiler.
+    pub fn add_files(&mut self, files: &FilesSourceText) {
+        for (path, contents) in files.values() {
+            self.add_file(path.to_string(), contents.to_string());
+        }
+    }
+
+    pub fn add_file(&mut self, path: String, contents: String) -> FileHash {
+        let hash = FileHash::new(&contents);
+        self.files.insert(hash, SourceFile::new(path, contents));
+        hash
+    }
+
+    /// Adds the source map of a compiled module or script.
+    pub fn add_compiled_unit(&mut self, unit: &CompiledUnit) {
+        match unit {
+            CompiledUnitEnum::Module(module) => {
+                self.add_source_map(Some(module.module.self_id()), module.source_map.clone())
+            }
+            CompiledUnitEnum::Script(script) => {
+                self.add_source_map(None, script.source_map.clone())
+            }
+        }
+    }
+
+    /// Adds the source map of `module`, or of the script being executed if `module` is None.
+    pub fn add_source_map(&mut self, module: Option<ModuleId>, source_map: SourceMap) {
+        match module {
+            Some(module) => {
+                self.modules.insert(module, source_map);
+            }
+            None => self.script = Some(source_map),
+        }
+    }
+
+    pub fn file(&self, file: &FileHash) -> Option<&SourceFile> {
+        self.files.get(file)
+    }
+
+    /// Finds a source file from a path given by the user, which can be relative to a different
+    /// directory than the paths given to the compiler.
+    pub fn find_file(&self, path: &str) -> Option<FileHash> {
+        let requested = Path::new(path);
+        let canonical = fs::canonicalize(requested).ok();
+        let matches = |file: &SourceFile| {
+            let candidate = Path::new(&file.path);
+            candidate == requested
+                || (canonical.is_some() && fs::canonicalize(candidate).ok() == canonical)
+                || requested.ends_with(candidate)
+                || candidate.ends_with(requested)
+        };
+        self.files
+            .iter()
+            .find(|(_, file)| matches(file))
+            .map(|(hash, _)| *hash)
+    }
+
+    /// Returns the source location of the instruction at `pc` in a function.
+    pub fn location(
+        &self,
+        module: Option<&ModuleId>,
+        function: FunctionDefinitionIndex,
+        pc: CodeOffset,
+    ) -> Option<SourceLocation> {
+        let loc = self
+            .source_map(module)?
+            .get_function_source_map(function)
+            .ok()?
+            .get_code_location(pc)?;
+        let (line, column) = self
+            .files
+            .get(&loc.file_hash())?
+            .line_and_column(loc.start() as usize);
+        Some(SourceLocation {
+            file: loc.file_hash(),
+            line,
+            column,
+        })
+    }
+
+    /// Returns the names of the parameters and locals of a function, in order of their index.
+    /// Compiler-generated temporaries have no name.
+    pub fn local_names(
+        &self,
+        module: Option<&ModuleId>,
+        function: FunctionDefinitionIndex,
+    ) -> Option<Vec<Option<String>>> {
+        let function_source_map = self
+            .source_map(module)?
+            .get_function_source_map(function)
+            .ok()?;
+        Some(
+            function_source_map
+                .parameters
+                .iter()
+                .chain(function_source_map.locals.iter())
+                .map(|(name, _)| display_name(name))
+                .collect(),
+        )
+    }
+
+    /// Returns the first line at or after `line` that has code, which is where a breakpoint
+    /// requested on `line` is actually set.
+    pub fn breakable_line(&self, file: &FileHash, line: usize) -> Option<usize> {
+        let source_file = self.files.get(file)?;
+        let mut lines = BTreeSet::new();
+        for source_map in self.modules.values().chain(self.script.iter()) {
+            for (_, function_source_map) in source_map.function_source_maps() {
+                for loc in function_source_map.code_map.values() {
+                    if loc.file_hash() == *file {
+                        lines.insert(source_file.line_and_column(loc.start() as usize).0);
+                    }
+                }
+            }
+        }
+        lines.range(line..).next().copied()
+    }
+
+    fn source_map(&self, module: Option<&ModuleId>) -> Option<&SourceMap> {
+        match module {
+            Some(module) => self.modules.get(module),
+            None => self.script.as_ref(),
+        }
+    }
+}
+
+/// Strips the suffix that the compiler appends to local names to tell apart the variables that
+/// shadow each other, e.g. `x#0#1`. Temporaries, whose names start with `%`, have no name in the
+/// source.
+fn display_name(name: &str) -> Option<String> {
+    if name.starts_with('%') {
+        return None;
+    }
+    Some(match name.find('#') {
+        Some(idx) => name[..idx].to_string(),
+        None => name.to_string(),
+    })
+}
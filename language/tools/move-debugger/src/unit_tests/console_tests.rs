@@ -0,0 +1,79 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{debug_info, run};
+use crate::{console::ConsoleFrontend, engine::Engine, MoveDebugger};
+use std::io::Cursor;
+
+/// Runs `g` under the console, with `commands` as input, and returns the output.
+fn session(commands: &str) -> String {
+    let mut debugger = MoveDebugger::new(
+        Engine::new(debug_info(), true),
+        ConsoleFrontend::new(Cursor::new(commands.as_bytes()), vec![]),
+    );
+    run(&mut debugger);
+    String::from_utf8(debugger.into_frontend().into_output()).unwrap()
+}
+
+#[test]
+fn breakpoints_and_inspection() {
+    let output = session(
+        "break sources/M.move:2\n\
+         break 0x1::M::g\n\
+         breakpoints\n\
+         continue\n\
+         locals\n\
+         next\n\
+         print y\n\
+         print z\n\
+         bt\n\
+         frame 1\n\
+         locals\n\
+         operands\n\
+         resource 0x1 0x1::M::R\n\
+         delete sources/M.move:3\n\
+         continue\n",
+    );
+    let expected = [
+        "Stopped on entry in 0x1::M::g at sources/M.move:7:9",
+        ">    7 |         f(1);",
+        "Breakpoint set on sources/M.move:3",
+        "Breakpoint set on 0x1::M::g",
+        "sources/M.move:3\n0x1::M::g\n",
+        "Breakpoint hit in 0x1::M::f at sources/M.move:3:9",
+        "x = 1\n",
+        "Stopped in 0x1::M::f at sources/M.move:4:9",
+        "y = 2\n",
+        "No local named 'z' in 0x1::M::f",
+        "* #0 0x1::M::f at sources/M.move:4:9\n  #1 0x1::M::g at sources/M.move:7:9\n",
+        "#1 0x1::M::g at sources/M.move:7:9\n",
+        "(none)\n",
+        "[1] 7\n[0] true\n",
+        "{ 42 }\n",
+        "Execution finished\n",
+    ];
+    let mut rest = output.as_str();
+    for text in &expected {
+        let idx = rest
+            .find(text)
+            .unwrap_or_else(|| panic!("expected '{}' in:\n{}", text, output));
+        rest = &rest[idx + text.len()..];
+    }
+    // The breakpoint on line 3 is deleted before the second call of `f`.
+    assert!(!rest.contains("Breakpoint hit"), "{}", output);
+}
+
+#[test]
+fn errors_do_not_resume() {
+    let output = session("frobnicate\nbreak sources/N.move:1\nbreak sources/M.move:10\nframe 7\n");
+    for text in &[
+        "Unknown command 'frobnicate'",
+        "Unknown source file: sources/N.move",
+        "No code at or after line 10",
+        "No frame 7",
+    ] {
+        assert!(output.contains(text), "expected '{}' in:\n{}", text, output);
+    }
+    // The end of the input detaches the debugger, which lets execution complete.
+    assert!(!output.contains("Execution finished"), "{}", output);
+}
@@ -0,0 +1,166 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{debug_info, run};
+use crate::{
+    dap::{read_message, write_message, DapFrontend},
+    engine::Engine,
+    MoveDebugger,
+};
+use serde_json::{json, Value};
+use std::{
+    io::{BufReader, Cursor},
+    net::{TcpListener, TcpStream},
+    thread,
+};
+
+#[test]
+fn framing() {
+    let messages = vec![
+        json!({ "seq": 1, "type": "request", "command": "threads" }),
+        json!({ "seq": 2, "type": "event", "event": "stopped", "body": { "text": "é\r\n" } }),
+    ];
+    let mut buf = vec![];
+    for message in &messages {
+        write_message(&mut buf, message).unwrap();
+    }
+    assert!(buf.starts_with(b"Content-Length: 46\r\n\r\n{"));
+
+    let mut reader = Cursor::new(buf);
+    for message in &messages {
+        assert_eq!(read_message(&mut reader).unwrap().as_ref(), Some(message));
+    }
+    assert_eq!(read_message(&mut reader).unwrap(), None);
+
+    assert!(read_message(&mut Cursor::new(b"Content-Type: json\r\n\r\n{}".to_vec())).is_err());
+    assert!(read_message(&mut Cursor::new(b"Content-Length: 2\r\n\r\n{".to_vec())).is_err());
+}
+
+/// A client of the server, playing the part of an editor.
+struct Client {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    seq: u64,
+}
+
+impl Client {
+    fn connect(address: std::net::SocketAddr) -> Self {
+        let stream = TcpStream::connect(address).unwrap();
+        Self {
+            reader: BufReader::new(stream.try_clone().unwrap()),
+            writer: stream,
+            seq: 0,
+        }
+    }
+
+    /// Sends a request and returns the body of its response, skipping events.
+    fn request(&mut self, command: &str, arguments: Value) -> Value {
+        self.seq += 1;
+        let request = json!({
+            "seq": self.seq,
+            "type": "request",
+            "command": command,
+            "arguments": arguments,
+        });
+        write_message(&mut self.writer, &request).unwrap();
+        loop {
+            let message = read_message(&mut self.reader).unwrap().unwrap();
+            if message["type"] == "response" && message["request_seq"] == self.seq {
+                assert_eq!(message["command"], command);
+                assert_eq!(message["success"], Value::Bool(true), "{}", message);
+                return message["body"].clone();
+            }
+        }
+    }
+
+    /// Waits for an event and returns its body.
+    fn event(&mut self, event: &str) -> Value {
+        loop {
+            let message = read_message(&mut self.reader).unwrap().unwrap();
+            if message["type"] == "event" && message["event"] == event {
+                return message["body"].clone();
+            }
+        }
+    }
+}
+
+#[test]
+fn session() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let client = thread::spawn(move || {
+        let mut client = Client::connect(address);
+        let capabilities = client.request("initialize", json!({ "adapterID": "move" }));
+        assert_eq!(
+            capabilities["supportsFunctionBreakpoints"],
+            Value::Bool(true)
+        );
+        client.event("initialized");
+        client.request("attach", json!({}));
+        let breakpoints = client.request(
+            "setBreakpoints",
+            json!({
+                "source": { "path": "/work/sources/M.move" },
+                "breakpoints": [{ "line": 2 }, { "line": 10 }],
+            }),
+        );
+        assert_eq!(
+            breakpoints["breakpoints"],
+            json!([
+                { "verified": true, "line": 3 },
+                { "verified": false, "message": "No code at or after this line" },
+            ])
+        );
+        client.request("configurationDone", json!({}));
+
+        // First call of `f`.
+        let stopped = client.event("stopped");
+        assert_eq!(stopped["reason"], "breakpoint");
+        let stack = client.request("stackTrace", json!({ "threadId": 1 }));
+        assert_eq!(stack["totalFrames"], 2);
+        assert_eq!(stack["stackFrames"][0]["name"], "0x1::M::f");
+        assert_eq!(stack["stackFrames"][0]["line"], 3);
+        assert_eq!(stack["stackFrames"][0]["source"]["path"], "sources/M.move");
+        assert_eq!(stack["stackFrames"][1]["line"], 7);
+        let scopes = client.request("scopes", json!({ "frameId": 0 }));
+        let locals = scopes["scopes"][0]["variablesReference"].clone();
+        let variables = client.request("variables", json!({ "variablesReference": locals }));
+        assert_eq!(
+            variables["variables"],
+            json!([{ "name": "x", "value": "1", "variablesReference": 0 }])
+        );
+        let operands = scopes["scopes"][1]["variablesReference"].clone();
+        let variables = client.request("variables", json!({ "variablesReference": operands }));
+        assert_eq!(variables["variables"][0]["name"], "[1]");
+        client.request("next", json!({ "threadId": 1 }));
+
+        // Stepped to line 4.
+        let stopped = client.event("stopped");
+        assert_eq!(stopped["reason"], "step");
+        let result = client.request("evaluate", json!({ "expression": "y", "frameId": 0 }));
+        assert_eq!(result["result"], "2");
+        let result = client.request(
+            "evaluate",
+            json!({ "expression": "resource 0x1 0x1::M::R", "frameId": 0 }),
+        );
+        assert_eq!(result["result"], "{ 42 }");
+        client.request("continue", json!({ "threadId": 1 }));
+
+        // Second call of `f`, then the end of execution.
+        let stopped = client.event("stopped");
+        assert_eq!(stopped["reason"], "breakpoint");
+        client.request(
+            "setBreakpoints",
+            json!({ "source": { "path": "sources/M.move" } }),
+        );
+        client.request("continue", json!({ "threadId": 1 }));
+        client.event("terminated");
+        client.request("disconnect", json!({}));
+    });
+
+    let (stream, _) = listener.accept().unwrap();
+    let mut engine = Engine::new(debug_info(), false);
+    let frontend = DapFrontend::new(stream, &mut engine).unwrap();
+    run(&mut MoveDebugger::new(engine, frontend));
+    client.join().unwrap();
+}
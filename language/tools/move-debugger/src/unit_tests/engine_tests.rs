@@ -0,0 +1,239 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{debug_info, run, FakeState, F, G, PATH};
+use crate::{
+    engine::{Engine, FunctionBreakpoint, ResumeMode, StopReason},
+    Frontend, MoveDebugger,
+};
+use move_vm_runtime::debugger::DebugState;
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+
+type Stop = (StopReason, String, u16);
+
+/// Records where execution stops, and resumes it as scripted.
+struct Scripted {
+    modes: VecDeque<ResumeMode>,
+    stops: Rc<RefCell<Vec<Stop>>>,
+}
+
+impl Frontend for Scripted {
+    fn stopped(
+        &mut self,
+        _engine: &mut Engine,
+        state: &mut dyn DebugState,
+        reason: StopReason,
+    ) -> ResumeMode {
+        self.stops
+            .borrow_mut()
+            .push((reason, state.function_name().to_string(), state.pc()));
+        self.modes.pop_front().unwrap_or(ResumeMode::Continue)
+    }
+}
+
+/// Runs `g` and returns the stops, after setting up the engine with `setup` and resuming
+/// execution with `modes`.
+fn stops(
+    stop_on_entry: bool,
+    setup: impl FnOnce(&mut Engine),
+    modes: Vec<ResumeMode>,
+) -> Vec<Stop> {
+    let mut engine = Engine::new(debug_info(), stop_on_entry);
+    setup(&mut engine);
+    let stops = Rc::new(RefCell::new(vec![]));
+    let mut debugger = MoveDebugger::new(
+        engine,
+        Scripted {
+            modes: modes.into(),
+            stops: stops.clone(),
+        },
+    );
+    run(&mut debugger);
+    drop(debugger);
+    Rc::try_unwrap(stops).unwrap().into_inner()
+}
+
+fn stop(reason: StopReason, function: &str, pc: u16) -> Stop {
+    (reason, function.to_string(), pc)
+}
+
+fn line_breakpoint(line: usize) -> impl FnOnce(&mut Engine) {
+    move |engine| {
+        let file = engine.info().find_file(PATH).unwrap();
+        assert_eq!(engine.add_line_breakpoint(file, line), Some(line));
+    }
+}
+
+#[test]
+fn run_without_breakpoints() {
+    assert!(stops(false, |_| (), vec![]).is_empty());
+}
+
+#[test]
+fn stop_on_entry() {
+    assert_eq!(
+        stops(true, |_| (), vec![]),
+        vec![stop(StopReason::Entry, "g", 0)]
+    );
+}
+
+#[test]
+fn step_over() {
+    use ResumeMode::*;
+    assert_eq!(
+        stops(true, |_| (), vec![StepOver, StepOver, StepOver]),
+        vec![
+            stop(StopReason::Entry, "g", 0),
+            stop(StopReason::Step, "g", 3),
+            stop(StopReason::Step, "g", 6),
+        ]
+    );
+}
+
+#[test]
+fn step_in_and_out() {
+    use ResumeMode::*;
+    assert_eq!(
+        stops(true, |_| (), vec![StepIn, StepIn, StepIn, StepIn, StepOut]),
+        vec![
+            stop(StopReason::Entry, "g", 0),
+            // Into the first call of `f`.
+            stop(StopReason::Step, "f", 0),
+            stop(StopReason::Step, "f", 4),
+            // Back in `g`, the rest of line 7 is skipped.
+            stop(StopReason::Step, "g", 3),
+            // Into the second call of `f`, then out of it right after the call.
+            stop(StopReason::Step, "f", 0),
+            stop(StopReason::Step, "g", 5),
+        ]
+    );
+}
+
+#[test]
+fn step_instruction() {
+    use ResumeMode::*;
+    assert_eq!(
+        stops(true, |_| (), vec![StepInstruction, StepInstruction]),
+        vec![
+            stop(StopReason::Entry, "g", 0),
+            stop(StopReason::Step, "g", 1),
+            stop(StopReason::Step, "f", 0),
+        ]
+    );
+}
+
+#[test]
+fn line_breakpoints() {
+    // The breakpoint is hit once per call, not once per instruction on the line.
+    assert_eq!(
+        stops(false, line_breakpoint(4), vec![]),
+        vec![
+            stop(StopReason::Breakpoint, "f", 4),
+            stop(StopReason::Breakpoint, "f", 4),
+        ]
+    );
+    // Stepping over from a breakpoint leaves `f`.
+    assert_eq!(
+        stops(false, line_breakpoint(8), vec![ResumeMode::StepOver]),
+        vec![
+            stop(StopReason::Breakpoint, "g", 3),
+            stop(StopReason::Step, "g", 6),
+        ]
+    );
+}
+
+#[test]
+fn breakpoints_move_to_lines_with_code() {
+    let mut engine = Engine::new(debug_info(), false);
+    let file = engine.info().find_file(PATH).unwrap();
+    assert_eq!(engine.add_line_breakpoint(file, 2), Some(3));
+    assert_eq!(engine.add_line_breakpoint(file, 5), Some(7));
+    assert_eq!(engine.add_line_breakpoint(file, 10), None);
+    assert_eq!(
+        engine.set_line_breakpoints(file, &[1, 4]),
+        vec![Some(3), Some(4)]
+    );
+    assert_eq!(
+        engine.line_breakpoints().collect::<Vec<_>>(),
+        vec![(file, 3), (file, 4)]
+    );
+    assert!(engine.remove_line_breakpoint(file, 3));
+    assert!(!engine.remove_line_breakpoint(file, 3));
+    // Paths are matched regardless of the directory they are relative to.
+    assert_eq!(
+        engine.info().find_file("/home/user/pkg/sources/M.move"),
+        Some(file)
+    );
+    assert_eq!(engine.info().find_file("sources/N.move"), None);
+}
+
+#[test]
+fn function_breakpoints() {
+    for (name, hits) in &[
+        ("f", 2),
+        ("M::f", 2),
+        ("0x1::M::f", 2),
+        ("0x2::M::f", 0),
+        ("N::f", 0),
+    ] {
+        let breakpoint: FunctionBreakpoint = name.parse().unwrap();
+        assert_eq!(breakpoint.to_string(), *name);
+        let stops = stops(
+            false,
+            |engine| engine.add_function_breakpoint(breakpoint),
+            vec![],
+        );
+        assert_eq!(
+            stops,
+            vec![stop(StopReason::FunctionBreakpoint, "f", 0); *hits],
+            "{}",
+            name
+        );
+    }
+    assert!("0x1::M::f::g".parse::<FunctionBreakpoint>().is_err());
+    assert!("M::".parse::<FunctionBreakpoint>().is_err());
+}
+
+#[test]
+fn pause() {
+    assert_eq!(
+        stops(false, |engine| engine.pause(), vec![]),
+        vec![stop(StopReason::Pause, "g", 0)]
+    );
+}
+
+#[test]
+fn inspect() {
+    let engine = Engine::new(debug_info(), false);
+    let mut state = FakeState::new(&[(G, 4), (F, 5)]);
+    let frames = engine.frames(&state);
+    assert_eq!(frames.len(), 2);
+
+    // Innermost frame first, with temporaries left out.
+    assert_eq!(frames[0].info.function, "0x1::M::f");
+    assert_eq!(frames[0].location.unwrap().line, 4);
+    assert_eq!(frames[0].location.unwrap().column, 9);
+    assert_eq!(
+        frames[0].locals,
+        vec![
+            ("x".to_string(), "1".to_string()),
+            ("y".to_string(), "2".to_string())
+        ]
+    );
+    assert_eq!(frames[1].info.function, "0x1::M::g");
+    assert_eq!(frames[1].location.unwrap().line, 8);
+
+    assert_eq!(engine.evaluate(&mut state, 0, "y").unwrap(), "2");
+    assert!(engine.evaluate(&mut state, 1, "y").is_err());
+    assert!(engine.evaluate(&mut state, 2, "y").is_err());
+    assert_eq!(
+        engine
+            .evaluate(&mut state, 0, "resource 0x1 0x1::M::R")
+            .unwrap(),
+        "{ 42 }"
+    );
+    assert!(engine
+        .evaluate(&mut state, 0, "resource 0x2 0x1::M::R")
+        .is_err());
+    assert!(engine.evaluate(&mut state, 0, "resource 0x1").is_err());
+}
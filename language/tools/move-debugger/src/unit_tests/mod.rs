@@ -0,0 +1,207 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+mod console_tests;
+mod dap_tests;
+mod engine_tests;
+
+use crate::source::DebugInfo;
+use bytecode_source_map::source_map::SourceMap;
+use move_binary_format::{
+    errors::VMResult,
+    file_format::{Bytecode, FunctionDefinitionIndex},
+};
+use move_command_line_common::files::FileHash;
+use move_core_types::{
+    account_address::AccountAddress,
+    identifier::Identifier,
+    language_storage::{ModuleId, StructTag},
+};
+use move_ir_types::location::Loc;
+use move_vm_runtime::debugger::{DebugState, Debugger, FrameInfo};
+
+pub const PATH: &str = "sources/M.move";
+
+pub const SOURCE: &str = "\
+module 0x1::M {
+    fun f(x: u64): u64 {
+        let y = x + 1;
+        y * 2
+    }
+    fun g() {
+        f(1);
+        f(2);
+    }
+}
+";
+
+pub const F: FunctionDefinitionIndex = FunctionDefinitionIndex(0);
+pub const G: FunctionDefinitionIndex = FunctionDefinitionIndex(1);
+
+pub fn module_address() -> AccountAddress {
+    AccountAddress::from_hex_literal("0x1").unwrap()
+}
+
+pub fn module_id() -> ModuleId {
+    ModuleId::new(module_address(), Identifier::new("M").unwrap())
+}
+
+/// The location of the first token of `line`.
+fn line_loc(file: FileHash, line: usize) -> Loc {
+    let start: usize = SOURCE.lines().take(line - 1).map(|l| l.len() + 1).sum();
+    let text = SOURCE.lines().nth(line - 1).unwrap();
+    let start = start + text.len() - text.trim_start().len();
+    Loc::new(file, start as u32, (start + text.trim().len()) as u32)
+}
+
+/// The debug info of module `M`, where:
+/// - `f` runs offsets 0 to 3 on line 3, and 4 to 7 on line 4. Its locals are `x`, `y` and a
+///   temporary.
+/// - `g` runs offsets 0 to 2 on line 7 (calling `f` at 1), 3 to 5 on line 8 (calling `f` at 4),
+///   and 6 on line 9.
+pub fn debug_info() -> DebugInfo {
+    let mut info = DebugInfo::new();
+    let file = info.add_file(PATH.to_string(), SOURCE.to_string());
+    let mut source_map = SourceMap::new(line_loc(file, 1), None);
+    source_map
+        .add_top_level_function_mapping(F, line_loc(file, 2), false)
+        .unwrap();
+    source_map
+        .add_parameter_mapping(F, ("x#0#0".to_string(), line_loc(file, 2)))
+        .unwrap();
+    source_map
+        .add_local_mapping(F, ("y#1#0".to_string(), line_loc(file, 3)))
+        .unwrap();
+    source_map
+        .add_local_mapping(F, ("%#1".to_string(), line_loc(file, 3)))
+        .unwrap();
+    source_map
+        .add_code_mapping(F, 0, line_loc(file, 3))
+        .unwrap();
+    source_map
+        .add_code_mapping(F, 4, line_loc(file, 4))
+        .unwrap();
+    source_map
+        .add_top_level_function_mapping(G, line_loc(file, 6), false)
+        .unwrap();
+    source_map
+        .add_code_mapping(G, 0, line_loc(file, 7))
+        .unwrap();
+    source_map
+        .add_code_mapping(G, 3, line_loc(file, 8))
+        .unwrap();
+    source_map
+        .add_code_mapping(G, 6, line_loc(file, 9))
+        .unwrap();
+    info.add_source_map(Some(module_id()), source_map);
+    info
+}
+
+/// A VM paused with the given call stack of `(function, pc)`, outermost first.
+pub struct FakeState {
+    frames: Vec<FrameInfo>,
+    instruction: Bytecode,
+}
+
+impl FakeState {
+    pub fn new(stack: &[(FunctionDefinitionIndex, u16)]) -> Self {
+        let frames = stack
+            .iter()
+            .map(|(function, pc)| {
+                let (name, locals) = if *function == F {
+                    let y = if *pc >= 4 {
+                        Some("2".to_string())
+                    } else {
+                        None
+                    };
+                    ("f", vec![Some("1".to_string()), y, Some("3".to_string())])
+                } else {
+                    ("g", vec![])
+                };
+                FrameInfo {
+                    module: Some(module_id()),
+                    function_index: *function,
+                    function: format!("0x1::M::{}", name),
+                    type_arguments: vec![],
+                    pc: *pc,
+                    locals,
+                }
+            })
+            .collect();
+        Self {
+            frames,
+            instruction: Bytecode::Nop,
+        }
+    }
+
+    fn current(&self) -> &FrameInfo {
+        self.frames.last().unwrap()
+    }
+}
+
+impl DebugState for FakeState {
+    fn call_depth(&self) -> usize {
+        self.frames.len() - 1
+    }
+
+    fn module(&self) -> Option<&ModuleId> {
+        self.current().module.as_ref()
+    }
+
+    fn function_index(&self) -> FunctionDefinitionIndex {
+        self.current().function_index
+    }
+
+    fn function_name(&self) -> &str {
+        self.current().function.rsplit("::").next().unwrap()
+    }
+
+    fn pc(&self) -> u16 {
+        self.current().pc
+    }
+
+    fn instruction(&self) -> &Bytecode {
+        &self.instruction
+    }
+
+    fn call_stack(&self) -> Vec<FrameInfo> {
+        self.frames.clone()
+    }
+
+    fn operand_stack(&self) -> Vec<String> {
+        vec!["true".to_string(), "7".to_string()]
+    }
+
+    fn read_resource(
+        &mut self,
+        address: AccountAddress,
+        resource_type: &StructTag,
+    ) -> VMResult<Option<String>> {
+        Ok(
+            if address == module_address() && resource_type.name.as_str() == "R" {
+                Some("{ 42 }".to_string())
+            } else {
+                None
+            },
+        )
+    }
+}
+
+/// The call stacks of an execution of `g`, before each instruction.
+pub fn trace() -> Vec<Vec<(FunctionDefinitionIndex, u16)>> {
+    let call_f = |pc| (0..=7).map(move |f_pc| vec![(G, pc), (F, f_pc)]);
+    let mut trace = vec![vec![(G, 0)], vec![(G, 1)]];
+    trace.extend(call_f(1));
+    trace.extend(vec![vec![(G, 2)], vec![(G, 3)], vec![(G, 4)]]);
+    trace.extend(call_f(4));
+    trace.extend(vec![vec![(G, 5)], vec![(G, 6)]]);
+    trace
+}
+
+/// Runs `g` under `debugger`, then lets it know that execution is over.
+pub fn run(debugger: &mut dyn Debugger) {
+    for stack in trace() {
+        debugger.on_instruction(&mut FakeState::new(&stack));
+    }
+    debugger.finish().unwrap();
+}
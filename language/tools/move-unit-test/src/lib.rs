@@ -6,6 +6,7 @@ pub mod test_reporter;
 pub mod test_runner;
 use crate::test_runner::TestRunner;
 use move_core_types::language_storage::ModuleId;
+use move_debugger::DebugFrontend;
 use move_lang::{
     self,
     diagnostics::{self, codes::Severity},
@@ -18,6 +19,7 @@ use std::{
     collections::BTreeMap,
     io::{Result, Write},
     marker::Send,
+    net::SocketAddr,
     sync::Mutex,
 };
 use structopt::*;
@@ -84,6 +86,16 @@ pub struct UnitTestingConfig {
     /// Verbose mode
     #[structopt(short = "v", long = "verbose")]
     pub verbose: bool,
+
+    /// Run the tests under the debugger, driven by commands read from stdin. Tests run one
+    /// after the other when debugging.
+    #[structopt(long = "debug")]
+    pub debug: bool,
+
+    /// Run the tests under the debugger, driven by an editor attached to a Debug Adapter
+    /// Protocol server listening on this address (e.g., 127.0.0.1:4711).
+    #[structopt(long = "dap", conflicts_with = "debug")]
+    pub dap: Option<SocketAddr>,
 }
 
 fn format_module_id(module_id: &ModuleId) -> String {
@@ -109,6 +121,17 @@ impl UnitTestingConfig {
             verbose: false,
             list: false,
             named_address_values: vec![],
+            debug: false,
+            dap: None,
+        }
+    }
+
+    /// The front-end of the debugger the tests run under, if any.
+    pub fn debug_frontend(&self) -> Option<DebugFrontend> {
+        match self.dap {
+            Some(address) => Some(DebugFrontend::Dap(address)),
+            None if self.debug => Some(DebugFrontend::Console),
+            None => None,
         }
     }
 
@@ -198,6 +221,7 @@ impl UnitTestingConfig {
             native_function_table,
             shared::verify_and_create_named_address_mapping(self.named_address_values.clone())
                 .unwrap(),
+            self.debug_frontend(),
         )
         .unwrap();
 
@@ -22,6 +22,7 @@ use move_core_types::{
     value::serialize_values,
     vm_status::StatusCode,
 };
+use move_debugger::{source::DebugInfo, DebugFrontend};
 use move_lang::{
     shared::{Flags, NumericalAddress},
     unit_test::{ExpectedFailure, ModuleTestPlan, TestCase, TestPlan},
@@ -54,6 +55,7 @@ pub struct TestRunner {
     num_threads: usize,
     testing_config: SharedTestingConfig,
     tests: TestPlan,
+    debug_frontend: Option<DebugFrontend>,
 }
 
 /// A gas schedule where every instruction has a cost of "1". This is used to bound execution of a
@@ -121,6 +123,7 @@ impl TestRunner {
         tests: TestPlan,
         native_function_table: Option<NativeFunctionTable>,
         named_address_values: BTreeMap<String, NumericalAddress>,
+        debug_frontend: Option<DebugFrontend>,
     ) -> Result<Self> {
         let source_files = tests
             .files
@@ -146,10 +149,14 @@ impl TestRunner {
             },
             num_threads,
             tests,
+            debug_frontend,
         })
     }
 
     pub fn run<W: Write + Send>(self, writer: &Mutex<W>) -> Result<TestResults> {
+        if let Some(frontend) = self.debug_frontend.clone() {
+            return self.run_under_debugger(&frontend, writer);
+        }
         rayon::ThreadPoolBuilder::new()
             .num_threads(self.num_threads)
             .build()
@@ -166,6 +173,31 @@ impl TestRunner {
             })
     }
 
+    /// Runs the tests under the debugger. The debugger follows the VM of the thread it is attached
+    /// to, so the tests run one after the other on the current thread.
+    fn run_under_debugger<W: Write + Send>(
+        self,
+        frontend: &DebugFrontend,
+        writer: &Mutex<W>,
+    ) -> Result<TestResults> {
+        let mut debug_info = DebugInfo::new();
+        debug_info.add_files(&self.tests.files);
+        for (module_id, module) in &self.tests.module_info {
+            debug_info.add_source_map(Some(module_id.clone()), module.source_map.clone());
+        }
+
+        move_debugger::attach(frontend, debug_info)?;
+        let final_statistics = self
+            .tests
+            .module_tests
+            .iter()
+            .map(|(_, test_plan)| self.testing_config.exec_module_tests(test_plan, writer))
+            .fold(TestStatistics::new(), |acc, stats| acc.combine(stats));
+        move_debugger::detach()?;
+
+        Ok(TestResults::new(final_statistics, self.tests))
+    }
+
     pub fn filter(&mut self, test_name_slice: &str) {
         for (module_id, module_test) in self.tests.module_tests.iter_mut() {
             if module_id.name().as_str().contains(test_name_slice) {
@@ -85,6 +85,8 @@ fn run_test_impl(path: &Path) -> anyhow::Result<()> {
         named_address_values: move_stdlib::move_stdlib_named_addresses()
             .into_iter()
             .collect(),
+        debug: false,
+        dap: None,
     };
 
     let regex = RegexBuilder::new(r"(┌─ ).+/([^/]+)$")
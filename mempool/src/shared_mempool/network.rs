@@ -89,11 +89,19 @@ pub type MempoolNetworkEvents = NetworkEvents<MempoolSyncMsg>;
 #[derive(Clone, Debug)]
 pub struct MempoolNetworkSender {
     inner: NetworkSender<MempoolSyncMsg>,
+    peer_metadata: Option<(NetworkId, Arc<PeerMetadataStorage>)>,
 }
 
+/// Supported direct send protocols in preferred order (from highest priority to lowest).
+pub const DIRECT_SEND: &[ProtocolId] = &[
+    ProtocolId::MempoolDirectSendCompressed,
+    ProtocolId::MempoolDirectSend,
+];
+
 pub fn network_endpoint_config(max_broadcasts_per_peer: usize) -> AppConfig {
+    let protos = DIRECT_SEND.iter().copied().chain([ProtocolId::MempoolRpc]);
     AppConfig::p2p(
-        [ProtocolId::MempoolDirectSend, ProtocolId::MempoolRpc],
+        protos,
         diem_channel::Config::new(max_broadcasts_per_peer)
             .queue_style(QueueStyle::KLAST)
             .counters(&counters::PENDING_MEMPOOL_NETWORK_EVENTS),
@@ -107,17 +115,42 @@ impl NewNetworkSender for MempoolNetworkSender {
     ) -> Self {
         Self {
             inner: NetworkSender::new(peer_mgr_reqs_tx, connection_reqs_tx),
+            peer_metadata: None,
         }
     }
 }
 
+impl MempoolNetworkSender {
+    /// Initialize the peer metadata of the sender's network, which is used to
+    /// pick the preferred protocol of each peer.
+    pub fn initialize(
+        &mut self,
+        network_id: NetworkId,
+        peer_metadata_storage: Arc<PeerMetadataStorage>,
+    ) {
+        self.peer_metadata = Some((network_id, peer_metadata_storage));
+    }
+
+    /// Choose the most preferred direct send protocol supported by the peer,
+    /// falling back to uncompressed messages.
+    fn preferred_direct_send_protocol(&self, peer: PeerId) -> ProtocolId {
+        self.peer_metadata
+            .as_ref()
+            .and_then(|(network_id, peer_metadata_storage)| {
+                peer_metadata_storage
+                    .preferred_protocol(PeerNetworkId::new(*network_id, peer), DIRECT_SEND)
+            })
+            .unwrap_or(ProtocolId::MempoolDirectSend)
+    }
+}
+
 #[async_trait]
 impl ApplicationNetworkSender<MempoolSyncMsg> for MempoolNetworkSender {
     fn send_to(&self, recipient: PeerId, message: MempoolSyncMsg) -> Result<(), NetworkError> {
         fail_point!("mempool::send_to", |_| {
             Err(anyhow::anyhow!("Injected error in mempool::send_to").into())
         });
-        let protocol = ProtocolId::MempoolDirectSend;
+        let protocol = self.preferred_direct_send_protocol(recipient);
         self.inner.send_to(recipient, protocol, message)
     }
 
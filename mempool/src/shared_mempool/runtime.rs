@@ -47,7 +47,8 @@ pub(crate) fn start_shared_mempool<V>(
 {
     let mut all_network_events = vec![];
     let mut network_senders = HashMap::new();
-    for (network_id, network_sender, network_events) in mempool_network_handles.into_iter() {
+    for (network_id, mut network_sender, network_events) in mempool_network_handles.into_iter() {
+        network_sender.initialize(network_id, peer_metadata_storage.clone());
         all_network_events.push((network_id, network_events));
         network_senders.insert(network_id, network_sender);
     }
@@ -18,10 +18,7 @@ use diem_config::{
 };
 use diem_types::{transaction::SignedTransaction, PeerId};
 use netcore::transport::ConnectionOrigin;
-use network::{
-    peer_manager::{PeerManagerNotification, PeerManagerRequest},
-    ProtocolId,
-};
+use network::peer_manager::{PeerManagerNotification, PeerManagerRequest};
 use rand::{rngs::StdRng, SeedableRng};
 use std::collections::{HashMap, HashSet};
 
@@ -335,7 +332,7 @@ impl TestHarness {
         // Handle outgoing message
         match network_req {
             PeerManagerRequest::SendDirectSend(remote_peer_id, msg) => {
-                let decoded_msg = bcs::from_bytes(&msg.mdata).unwrap();
+                let decoded_msg = msg.protocol_id.from_bytes(&msg.mdata).unwrap();
                 match decoded_msg {
                     MempoolSyncMsg::BroadcastTransactionsRequest {
                         transactions,
@@ -367,7 +364,7 @@ impl TestHarness {
 
                         receiver.send_network_req(
                             network_id,
-                            ProtocolId::MempoolDirectSend,
+                            msg.protocol_id,
                             PeerManagerNotification::RecvMessage(sender_peer_id, msg),
                         );
                         receiver.wait_for_event(SharedMempoolNotification::NewTransactions);
@@ -448,7 +445,7 @@ impl TestHarness {
 
         match network_req {
             PeerManagerRequest::SendDirectSend(remote_peer_id, msg) => {
-                let decoded_msg = bcs::from_bytes(&msg.mdata).unwrap();
+                let decoded_msg = msg.protocol_id.from_bytes(&msg.mdata).unwrap();
                 match decoded_msg {
                     MempoolSyncMsg::BroadcastTransactionsResponse { .. } => {
                         // send it to peer
@@ -469,7 +466,7 @@ impl TestHarness {
                         let receiver = self.mut_node(&receiver_id);
                         receiver.send_network_req(
                             network_id,
-                            ProtocolId::MempoolDirectSend,
+                            msg.protocol_id,
                             PeerManagerNotification::RecvMessage(sender_peer_id, msg),
                         );
                     }
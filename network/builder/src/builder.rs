@@ -11,10 +11,11 @@
 //! long as the latter is in its trusted peers set.
 use diem_config::{
     config::{
-        DiscoveryMethod, NetworkConfig, Peer, PeerRole, PeerSet, RateLimitConfig, RoleType,
-        CONNECTION_BACKOFF_BASE, CONNECTIVITY_CHECK_INTERVAL_MS, MAX_CONCURRENT_NETWORK_REQS,
-        MAX_CONNECTION_DELAY_MS, MAX_FRAME_SIZE, MAX_FULLNODE_OUTBOUND_CONNECTIONS,
-        MAX_INBOUND_CONNECTIONS, NETWORK_CHANNEL_SIZE,
+        DiscoveryMethod, NetworkConfig, Peer, PeerQuotaConfig, PeerRole, PeerSet, RateLimitConfig,
+        RoleType, CONNECTION_BACKOFF_BASE, CONNECTIVITY_CHECK_INTERVAL_MS,
+        MAX_CONCURRENT_NETWORK_REQS, MAX_CONNECTION_DELAY_MS, MAX_FRAME_SIZE,
+        MAX_FULLNODE_OUTBOUND_CONNECTIONS, MAX_INBOUND_CONNECTIONS, MAX_PEER_ROTATIONS,
+        NETWORK_CHANNEL_SIZE, SLOW_PEER_LATENCY_MS,
     },
     network_id::NetworkContext,
 };
@@ -39,7 +40,7 @@ use network::{
         network::{AppConfig, NewNetworkEvents, NewNetworkSender},
     },
 };
-use network_discovery::DiscoveryChangeListener;
+use network_discovery::{peer_exchange, DiscoveryChangeListener};
 use std::{
     clone::Clone,
     collections::{HashMap, HashSet},
@@ -90,6 +91,8 @@ impl NetworkBuilder {
         inbound_connection_limit: usize,
         inbound_rate_limit_config: Option<RateLimitConfig>,
         outbound_rate_limit_config: Option<RateLimitConfig>,
+        inbound_peer_quota_config: Option<PeerQuotaConfig>,
+        outbound_peer_quota_config: Option<PeerQuotaConfig>,
     ) -> Self {
         // A network cannot exist without a PeerManager
         // TODO:  construct this in create and pass it to new() as a parameter. The complication is manual construction of NetworkBuilder in various tests.
@@ -108,6 +111,8 @@ impl NetworkBuilder {
             inbound_connection_limit,
             inbound_rate_limit_config,
             outbound_rate_limit_config,
+            inbound_peer_quota_config,
+            outbound_peer_quota_config,
         );
 
         NetworkBuilder {
@@ -150,6 +155,8 @@ impl NetworkBuilder {
             MAX_INBOUND_CONNECTIONS,
             None,
             None,
+            None,
+            None,
         );
 
         builder.add_connectivity_manager(
@@ -161,6 +168,8 @@ impl NetworkBuilder {
             CONNECTIVITY_CHECK_INTERVAL_MS,
             NETWORK_CHANNEL_SIZE,
             mutual_authentication,
+            MAX_PEER_ROTATIONS,
+            SLOW_PEER_LATENCY_MS,
         );
 
         builder
@@ -204,12 +213,8 @@ impl NetworkBuilder {
             config.max_inbound_connections,
             config.inbound_rate_limit_config,
             config.outbound_rate_limit_config,
-        );
-
-        network_builder.add_connection_monitoring(
-            config.ping_interval_ms,
-            config.ping_timeout_ms,
-            config.ping_failures_tolerated,
+            config.inbound_peer_quota_config.clone(),
+            config.outbound_peer_quota_config.clone(),
         );
 
         // Always add a connectivity manager to keep track of known peers
@@ -224,6 +229,16 @@ impl NetworkBuilder {
             config.connectivity_check_interval_ms,
             config.network_channel_size,
             config.mutual_authentication,
+            config.max_peer_rotations,
+            config.slow_peer_latency_ms,
+        );
+
+        // The health checker reports peer latencies to the connectivity manager,
+        // so it's added afterwards
+        network_builder.add_connection_monitoring(
+            config.ping_interval_ms,
+            config.ping_timeout_ms,
+            config.ping_failures_tolerated,
         );
 
         network_builder.discovery_listeners = Some(Vec::new());
@@ -343,6 +358,8 @@ impl NetworkBuilder {
         connectivity_check_interval_ms: u64,
         channel_size: usize,
         mutual_authentication: bool,
+        max_peer_rotations: usize,
+        slow_peer_latency_ms: u64,
     ) -> &mut Self {
         let pm_conn_mgr_notifs_rx = self.peer_manager_builder.add_connection_event_listener();
         let outbound_connection_limit = if !self.network_context.network_id().is_validator_network()
@@ -365,6 +382,8 @@ impl NetworkBuilder {
             pm_conn_mgr_notifs_rx,
             outbound_connection_limit,
             mutual_authentication,
+            max_peer_rotations,
+            slow_peer_latency_ms,
         ));
         self
     }
@@ -399,6 +418,18 @@ impl NetworkBuilder {
                 *interval_duration,
                 self.time_service.clone(),
             ),
+            DiscoveryMethod::PeerExchange(config) => {
+                let (network_tx, network_rx) =
+                    self.add_p2p_service(&peer_exchange::network_endpoint_config());
+                DiscoveryChangeListener::peer_exchange(
+                    self.network_context,
+                    conn_mgr_reqs_tx,
+                    config.clone(),
+                    network_tx,
+                    network_rx,
+                    self.time_service.clone(),
+                )
+            }
             DiscoveryMethod::None => return,
         };
 
@@ -427,6 +458,7 @@ impl NetworkBuilder {
             hc_network_tx,
             hc_network_rx,
             self.peer_metadata_storage.clone(),
+            self.conn_mgr_reqs_tx(),
         ));
         debug!(
             NetworkSchema::new(&self.network_context),
@@ -1,8 +1,16 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{counters::DISCOVERY_COUNTS, file::FileStream, validator_set::ValidatorSetStream};
-use diem_config::{config::PeerSet, network_id::NetworkContext};
+use crate::{
+    counters::DISCOVERY_COUNTS,
+    file::FileStream,
+    peer_exchange::{PeerExchangeNetworkEvents, PeerExchangeNetworkSender, PeerExchangeStream},
+    validator_set::ValidatorSetStream,
+};
+use diem_config::{
+    config::{PeerExchangeConfig, PeerSet},
+    network_id::NetworkContext,
+};
 use diem_crypto::x25519;
 use diem_logger::prelude::*;
 use diem_network_address_encryption::Encryptor;
@@ -25,6 +33,7 @@ use tokio::runtime::Handle;
 
 mod counters;
 mod file;
+pub mod peer_exchange;
 mod validator_set;
 
 #[derive(Debug)]
@@ -44,6 +53,7 @@ pub struct DiscoveryChangeListener {
 enum DiscoveryChangeStream {
     ValidatorSet(ValidatorSetStream),
     File(FileStream),
+    PeerExchange(PeerExchangeStream),
 }
 
 impl Stream for DiscoveryChangeStream {
@@ -53,6 +63,7 @@ impl Stream for DiscoveryChangeStream {
         match self.get_mut() {
             Self::ValidatorSet(stream) => Pin::new(stream).poll_next(cx),
             Self::File(stream) => Pin::new(stream).poll_next(cx),
+            Self::PeerExchange(stream) => Pin::new(stream).poll_next(cx),
         }
     }
 }
@@ -99,6 +110,29 @@ impl DiscoveryChangeListener {
         }
     }
 
+    pub fn peer_exchange(
+        network_context: NetworkContext,
+        update_channel: channel::Sender<ConnectivityRequest>,
+        config: PeerExchangeConfig,
+        network_tx: PeerExchangeNetworkSender,
+        network_rx: PeerExchangeNetworkEvents,
+        time_service: TimeService,
+    ) -> Self {
+        let source_stream = DiscoveryChangeStream::PeerExchange(PeerExchangeStream::new(
+            network_context,
+            config,
+            network_tx,
+            network_rx,
+            time_service,
+        ));
+        DiscoveryChangeListener {
+            discovery_source: DiscoverySource::PeerExchange,
+            network_context,
+            update_channel,
+            source_stream,
+        }
+    }
+
     pub fn start(self, executor: &Handle) {
         executor.spawn(Box::pin(self).run());
     }
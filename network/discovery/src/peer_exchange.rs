@@ -0,0 +1,483 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Peer exchange discovery for fullnode networks.
+//!
+//! Every interval, a node sends the addresses of the peers it managed to dial
+//! (i.e., its outbound connections) to all of its connected peers over
+//! [`ProtocolId::DiscoveryDirectSend`]. The lists received are merged into a
+//! single `PeerSet` for the ConnectivityManager.
+//!
+//! To limit the influence a single peer has over whom we connect to, and make
+//! eclipse attacks harder:
+//! * Only lists from peers we dialed ourselves are accepted, as inbound
+//!   connections are cheap to open for an attacker.
+//! * Each peer's latest list replaces its previous one, and is capped at
+//!   `max_peers_per_source`.
+//! * Discovered peers are picked round-robin across sources, up to
+//!   `max_discovered_peers`.
+//! * Records must be self-certifying: every address must carry the Noise key the
+//!   advertised peer id is derived from. A peer can't be advertised at an address
+//!   it doesn't control, as the Noise handshake fails when dialing it.
+
+use crate::{counters::DISCOVERY_COUNTS, DiscoveryError};
+use channel::{diem_channel, message_queues::QueueStyle};
+use diem_config::{
+    config::{Peer, PeerExchangeConfig, PeerRole, PeerSet},
+    network_id::NetworkContext,
+};
+use diem_logger::prelude::*;
+use diem_time_service::{Interval, TimeService, TimeServiceTrait};
+use diem_types::{
+    account_address::from_identity_public_key, network_address::NetworkAddress, PeerId,
+};
+use futures::{Stream, StreamExt};
+use netcore::transport::ConnectionOrigin;
+use network::{
+    counters::{inc_by_with_context, PENDING_DISCOVERY_NETWORK_EVENTS},
+    logging::NetworkSchema,
+    protocols::network::{AppConfig, Event, NetworkEvents, NetworkSender},
+    transport::ConnectionMetadata,
+    ProtocolId,
+};
+use serde::{Deserialize, Serialize};
+use short_hex_str::AsShortHexStr;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+/// The maximum number of addresses accepted for a single peer.
+const MAX_ADDRESSES_PER_PEER: usize = 4;
+
+pub type PeerExchangeNetworkSender = NetworkSender<PeerExchangeMsg>;
+pub type PeerExchangeNetworkEvents = NetworkEvents<PeerExchangeMsg>;
+
+/// Configuration for the network endpoints to support peer exchange.
+pub fn network_endpoint_config() -> AppConfig {
+    // Only the latest list from each peer matters
+    AppConfig::p2p(
+        [ProtocolId::DiscoveryDirectSend],
+        diem_channel::Config::new(1)
+            .queue_style(QueueStyle::KLAST)
+            .counters(&PENDING_DISCOVERY_NETWORK_EVENTS),
+    )
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum PeerExchangeMsg {
+    /// Peers reachable by the sender
+    Peers(Vec<PeerRecord>),
+}
+
+/// A peer and the addresses it can be reached at.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct PeerRecord {
+    pub peer_id: PeerId,
+    pub addresses: Vec<NetworkAddress>,
+}
+
+impl PeerRecord {
+    /// Check the record is well formed, and that the peer id is derived from
+    /// the Noise key of every address.
+    fn verify(&self) -> Result<(), DiscoveryError> {
+        if self.addresses.is_empty() || self.addresses.len() > MAX_ADDRESSES_PER_PEER {
+            return Err(DiscoveryError::Parsing(format!(
+                "Invalid number of addresses for peer {}: {}",
+                self.peer_id.short_str(),
+                self.addresses.len()
+            )));
+        }
+        for addr in &self.addresses {
+            let derived_peer_id = addr
+                .find_noise_proto()
+                .filter(|_| addr.is_diemnet_addr())
+                .map(from_identity_public_key);
+            if derived_peer_id != Some(self.peer_id) {
+                return Err(DiscoveryError::Parsing(format!(
+                    "Address doesn't match peer {}: {}",
+                    self.peer_id.short_str(),
+                    addr
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn to_peer(&self) -> Peer {
+        let keys: HashSet<_> = self
+            .addresses
+            .iter()
+            .filter_map(NetworkAddress::find_noise_proto)
+            .collect();
+        // Peers learned from other fullnodes are dialed as regular upstreams
+        Peer::new(self.addresses.clone(), keys, PeerRole::Upstream)
+    }
+}
+
+pub struct PeerExchangeStream {
+    network_context: NetworkContext,
+    config: PeerExchangeConfig,
+    network_tx: PeerExchangeNetworkSender,
+    network_rx: PeerExchangeNetworkEvents,
+    interval: Pin<Box<Interval>>,
+    /// Peers we're currently connected to.
+    connected: HashMap<PeerId, ConnectionMetadata>,
+    /// The latest verified records from each of our outbound peers.
+    records_by_source: BTreeMap<PeerId, Vec<PeerRecord>>,
+    /// The peers in our last update.
+    discovered: PeerSet,
+}
+
+impl PeerExchangeStream {
+    pub(crate) fn new(
+        network_context: NetworkContext,
+        config: PeerExchangeConfig,
+        network_tx: PeerExchangeNetworkSender,
+        network_rx: PeerExchangeNetworkEvents,
+        time_service: TimeService,
+    ) -> Self {
+        let interval = Box::pin(time_service.interval(Duration::from_millis(config.interval_ms)));
+        PeerExchangeStream {
+            network_context,
+            config,
+            network_tx,
+            network_rx,
+            interval,
+            connected: HashMap::new(),
+            records_by_source: BTreeMap::new(),
+            discovered: PeerSet::new(),
+        }
+    }
+
+    /// Send the peers we've dialed to all connected peers.
+    fn send_reachable_peers(&mut self) {
+        if self.connected.is_empty() {
+            return;
+        }
+        let records: Vec<_> = self
+            .connected
+            .values()
+            .filter(|metadata| metadata.origin == ConnectionOrigin::Outbound)
+            .take(self.config.max_peers_per_source)
+            .map(|metadata| PeerRecord {
+                peer_id: metadata.remote_peer_id,
+                addresses: vec![metadata.addr.clone()],
+            })
+            .collect();
+        if let Err(err) = self.network_tx.send_to_many(
+            self.connected.keys().copied(),
+            ProtocolId::DiscoveryDirectSend,
+            PeerExchangeMsg::Peers(records),
+        ) {
+            inc_by_with_context(
+                &DISCOVERY_COUNTS,
+                &self.network_context,
+                "peer_exchange_send_failure",
+                1,
+            );
+            warn!(
+                NetworkSchema::new(&self.network_context),
+                error = %err,
+                "{} Failed to send reachable peers: {}",
+                self.network_context,
+                err
+            );
+        }
+    }
+
+    /// Handle an event from the network, returning true if the discovered peers changed.
+    fn handle_event(&mut self, event: Event<PeerExchangeMsg>) -> bool {
+        match event {
+            Event::NewPeer(metadata) => {
+                self.connected.insert(metadata.remote_peer_id, metadata);
+                false
+            }
+            Event::LostPeer(metadata) => {
+                self.connected.remove(&metadata.remote_peer_id);
+                // Forget what the peer told us, it may have been rotated out for a reason
+                self.records_by_source
+                    .remove(&metadata.remote_peer_id)
+                    .is_some()
+            }
+            Event::Message(peer_id, PeerExchangeMsg::Peers(records)) => {
+                self.handle_records(peer_id, records)
+            }
+            Event::RpcRequest(peer_id, ..) => {
+                warn!(
+                    NetworkSchema::new(&self.network_context).remote_peer(&peer_id),
+                    "{} Unexpected peer exchange rpc from {}",
+                    self.network_context,
+                    peer_id.short_str()
+                );
+                false
+            }
+        }
+    }
+
+    fn handle_records(&mut self, source: PeerId, records: Vec<PeerRecord>) -> bool {
+        let is_outbound = self.connected.get(&source).map_or(false, |metadata| {
+            metadata.origin == ConnectionOrigin::Outbound
+        });
+        if !is_outbound {
+            inc_by_with_context(
+                &DISCOVERY_COUNTS,
+                &self.network_context,
+                "peer_exchange_ignored",
+                1,
+            );
+            return false;
+        }
+
+        let self_peer_id = self.network_context.peer_id();
+        let mut verified = Vec::new();
+        for record in records {
+            if verified.len() >= self.config.max_peers_per_source {
+                break;
+            }
+            if record.peer_id == self_peer_id || record.peer_id == source {
+                continue;
+            }
+            if let Err(err) = record.verify() {
+                inc_by_with_context(
+                    &DISCOVERY_COUNTS,
+                    &self.network_context,
+                    "peer_exchange_invalid_record",
+                    1,
+                );
+                warn!(
+                    NetworkSchema::new(&self.network_context).remote_peer(&source),
+                    "{} Invalid peer record from {}: {:?}",
+                    self.network_context,
+                    source.short_str(),
+                    err
+                );
+                continue;
+            }
+            verified.push(record);
+        }
+
+        if self.records_by_source.get(&source) == Some(&verified) {
+            return false;
+        }
+        self.records_by_source.insert(source, verified);
+        true
+    }
+
+    /// Pick the discovered peers round-robin across sources, so that no single
+    /// source can take up all of the `max_discovered_peers`.
+    ///
+    /// Previously discovered peers we're connected to are always kept, otherwise
+    /// the ConnectivityManager would close the connection as soon as the peer
+    /// drops out of its sources' lists.
+    fn update_discovered_peers(&mut self) -> PeerSet {
+        let connected = &self.connected;
+        let mut peers: PeerSet = self
+            .discovered
+            .iter()
+            .filter(|(peer_id, _)| connected.contains_key(peer_id))
+            .map(|(peer_id, peer)| (*peer_id, peer.clone()))
+            .collect();
+        let sources: Vec<_> = self.records_by_source.values().collect();
+        let max_records = sources.iter().map(|records| records.len()).max();
+        let sources = &sources;
+        let round_robin = (0..max_records.unwrap_or(0))
+            .flat_map(move |idx| sources.iter().filter_map(move |records| records.get(idx)));
+        for record in round_robin {
+            if peers.len() >= self.config.max_discovered_peers {
+                break;
+            }
+            peers
+                .entry(record.peer_id)
+                .or_insert_with(|| record.to_peer());
+        }
+        self.discovered = peers.clone();
+        peers
+    }
+}
+
+impl Stream for PeerExchangeStream {
+    type Item = Result<PeerSet, DiscoveryError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        while let Poll::Ready(Some(())) = this.interval.as_mut().poll_next(cx) {
+            this.send_reachable_peers();
+        }
+
+        let mut updated = false;
+        loop {
+            match this.network_rx.poll_next_unpin(cx) {
+                Poll::Ready(Some(event)) => updated |= this.handle_event(event),
+                // The network has shut down
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => break,
+            }
+        }
+
+        if updated {
+            Poll::Ready(Some(Ok(this.update_discovered_peers())))
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diem_config::config::HANDSHAKE_VERSION;
+    use diem_crypto::{x25519, Uniform};
+    use network::{
+        peer_manager::{conn_notifs_channel, ConnectionRequestSender, PeerManagerRequestSender},
+        protocols::{
+            network::{NewNetworkEvents, NewNetworkSender},
+            wire::handshake::v1::{MessagingProtocolVersion, ProtocolIdSet},
+        },
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::str::FromStr;
+
+    fn test_stream(max_peers_per_source: usize, max_discovered_peers: usize) -> PeerExchangeStream {
+        let (peer_mgr_reqs_tx, _) = diem_channel::new(QueueStyle::FIFO, 1, None);
+        let (connection_reqs_tx, _) = diem_channel::new(QueueStyle::FIFO, 1, None);
+        let (_, peer_mgr_notifs_rx) = diem_channel::new(QueueStyle::FIFO, 1, None);
+        let (_, connection_notifs_rx) = conn_notifs_channel::new();
+        let config = PeerExchangeConfig {
+            max_peers_per_source,
+            max_discovered_peers,
+            ..PeerExchangeConfig::default()
+        };
+        PeerExchangeStream::new(
+            NetworkContext::mock(),
+            config,
+            PeerExchangeNetworkSender::new(
+                PeerManagerRequestSender::new(peer_mgr_reqs_tx),
+                ConnectionRequestSender::new(connection_reqs_tx),
+            ),
+            PeerExchangeNetworkEvents::new(peer_mgr_notifs_rx, connection_notifs_rx),
+            TimeService::real(),
+        )
+    }
+
+    fn test_record(rng: &mut StdRng) -> PeerRecord {
+        let pubkey = x25519::PrivateKey::generate(rng).public_key();
+        let addr = NetworkAddress::from_str("/ip4/127.0.0.1/tcp/6180")
+            .unwrap()
+            .append_prod_protos(pubkey, HANDSHAKE_VERSION);
+        PeerRecord {
+            peer_id: from_identity_public_key(pubkey),
+            addresses: vec![addr],
+        }
+    }
+
+    fn connect(stream: &mut PeerExchangeStream, peer_id: PeerId, origin: ConnectionOrigin) {
+        let metadata = ConnectionMetadata::new(
+            peer_id,
+            Default::default(),
+            NetworkAddress::from_str("/ip4/127.0.0.1/tcp/6180").unwrap(),
+            origin,
+            MessagingProtocolVersion::V1,
+            ProtocolIdSet::empty(),
+            PeerRole::Unknown,
+        );
+        assert!(!stream.handle_event(Event::NewPeer(metadata)));
+    }
+
+    fn send_records(
+        stream: &mut PeerExchangeStream,
+        source: PeerId,
+        records: &[PeerRecord],
+    ) -> bool {
+        stream.handle_event(Event::Message(
+            source,
+            PeerExchangeMsg::Peers(records.to_vec()),
+        ))
+    }
+
+    #[test]
+    fn test_verify_record() {
+        let mut rng = StdRng::from_seed([0u8; 32]);
+        let record = test_record(&mut rng);
+        record.verify().unwrap();
+
+        // The peer id must be derived from the address' key
+        let mut spoofed = record.clone();
+        spoofed.peer_id = PeerId::random();
+        spoofed.verify().unwrap_err();
+
+        // Every address must carry the peer's key
+        let mut mixed = record.clone();
+        mixed.addresses.extend(test_record(&mut rng).addresses);
+        mixed.verify().unwrap_err();
+
+        let mut no_key = record.clone();
+        no_key.addresses = vec![NetworkAddress::from_str("/ip4/127.0.0.1/tcp/6180").unwrap()];
+        no_key.verify().unwrap_err();
+
+        let mut no_addresses = record;
+        no_addresses.addresses.clear();
+        no_addresses.verify().unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn test_only_accept_outbound_sources() {
+        let mut rng = StdRng::from_seed([0u8; 32]);
+        let mut stream = test_stream(4, 8);
+        let records = vec![test_record(&mut rng)];
+
+        // Unknown and inbound peers are ignored
+        let inbound = PeerId::random();
+        assert!(!send_records(&mut stream, inbound, &records));
+        connect(&mut stream, inbound, ConnectionOrigin::Inbound);
+        assert!(!send_records(&mut stream, inbound, &records));
+
+        let outbound = PeerId::random();
+        connect(&mut stream, outbound, ConnectionOrigin::Outbound);
+        assert!(send_records(&mut stream, outbound, &records));
+        let peers = stream.update_discovered_peers();
+        assert_eq!(peers.len(), 1);
+        assert_eq!(peers[&records[0].peer_id], records[0].to_peer());
+
+        // The same list again isn't an update
+        assert!(!send_records(&mut stream, outbound, &records));
+    }
+
+    #[tokio::test]
+    async fn test_discovered_peer_limits() {
+        let mut rng = StdRng::from_seed([0u8; 32]);
+        let mut stream = test_stream(2, 3);
+        let source_a = PeerId::new([1; PeerId::LENGTH]);
+        let source_b = PeerId::new([2; PeerId::LENGTH]);
+        connect(&mut stream, source_a, ConnectionOrigin::Outbound);
+        connect(&mut stream, source_b, ConnectionOrigin::Outbound);
+
+        // Each source is capped, and sources are picked round-robin
+        let records_a: Vec<_> = (0..3).map(|_| test_record(&mut rng)).collect();
+        let records_b: Vec<_> = (0..3).map(|_| test_record(&mut rng)).collect();
+        assert!(send_records(&mut stream, source_a, &records_a));
+        assert!(send_records(&mut stream, source_b, &records_b));
+        let peers = stream.update_discovered_peers();
+        assert_eq!(peers.len(), 3);
+        assert!(peers.contains_key(&records_a[0].peer_id));
+        assert!(peers.contains_key(&records_a[1].peer_id));
+        assert!(peers.contains_key(&records_b[0].peer_id));
+
+        // Losing a source forgets its records, but keeps the peers we're connected to
+        connect(
+            &mut stream,
+            records_a[1].peer_id,
+            ConnectionOrigin::Outbound,
+        );
+        let metadata = stream.connected[&source_a].clone();
+        assert!(stream.handle_event(Event::LostPeer(metadata)));
+        let peers = stream.update_discovered_peers();
+        assert_eq!(peers.len(), 3);
+        assert!(peers.contains_key(&records_a[1].peer_id));
+        assert!(peers.contains_key(&records_b[0].peer_id));
+        assert!(peers.contains_key(&records_b[1].peer_id));
+    }
+}
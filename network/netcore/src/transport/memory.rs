@@ -1,7 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::transport::Transport;
+use crate::transport::{NoSubstreams, SocketExt, Transport};
 use diem_types::{
     network_address::{parse_memory, NetworkAddress, Protocol},
     PeerId,
@@ -67,6 +67,10 @@ impl Transport for MemoryTransport {
     }
 }
 
+impl SocketExt for MemorySocket {
+    type Substreams = NoSubstreams;
+}
+
 #[must_use = "streams do nothing unless polled"]
 #[derive(Debug)]
 pub struct Listener {
@@ -12,15 +12,20 @@
 //! [`TransportExt`]: crate::transport::TransportExt
 
 use diem_types::{network_address::NetworkAddress, PeerId};
-use futures::{future::Future, stream::Stream};
+use futures::{
+    future::{BoxFuture, Future},
+    io::{AsyncRead, AsyncWrite},
+    stream::{BoxStream, Stream},
+};
 use serde::Serialize;
-use std::fmt;
+use std::{fmt, io};
 
 pub mod and_then;
 pub mod boxed;
 #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
 pub mod memory;
 pub mod proxy_protocol;
+pub mod quic;
 pub mod tcp;
 
 /// Origin of how a Connection was established.
@@ -114,6 +119,68 @@ pub trait Transport {
         Self: Sized;
 }
 
+/// Properties of an established base transport connection which the upper
+/// layers may rely on, e.g. to bind an authenticated session to the underlying
+/// connection or to open additional streams to the remote peer.
+pub trait SocketExt {
+    /// The handle to the additional streams of the connection, see [`Substreams`].
+    /// Byte-stream transports use [`NoSubstreams`].
+    type Substreams: Substreams;
+
+    /// An identifier of the underlying secure channel which both ends agree on,
+    /// or `None` if the connection has no such channel (e.g. plain TCP).
+    fn channel_binding(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// A handle to open and accept additional streams on the same connection,
+    /// or `None` if the connection is a single byte-stream.
+    fn substreams(&self) -> Option<Self::Substreams> {
+        None
+    }
+}
+
+/// Additional unidirectional streams multiplexed over one connection, so that
+/// unrelated messages are not blocked behind each other.
+///
+/// Clones refer to the same connection.
+pub trait Substreams: Clone + fmt::Debug + Send + Sync + 'static {
+    type SendStream: AsyncWrite + Unpin + Send + 'static;
+    type RecvStream: AsyncRead + Unpin + Send + 'static;
+
+    /// Open a new unidirectional stream to the remote peer.
+    fn open_uni(&self) -> BoxFuture<'static, io::Result<Self::SendStream>>;
+
+    /// Take the stream of unidirectional streams opened by the remote peer.
+    /// Returns `None` if it has already been taken.
+    fn take_incoming_uni(&self) -> Option<BoxStream<'static, io::Result<Self::RecvStream>>>;
+
+    /// Close the whole connection, including all of its streams.
+    fn close(&self);
+}
+
+/// [`Substreams`] of transports which only provide a single byte-stream. It
+/// can't be constructed, so [`SocketExt::substreams`] always returns `None`.
+#[derive(Clone, Debug)]
+pub enum NoSubstreams {}
+
+impl Substreams for NoSubstreams {
+    type SendStream = futures::io::Sink;
+    type RecvStream = futures::io::Empty;
+
+    fn open_uni(&self) -> BoxFuture<'static, io::Result<Self::SendStream>> {
+        match *self {}
+    }
+
+    fn take_incoming_uni(&self) -> Option<BoxStream<'static, io::Result<Self::RecvStream>>> {
+        match *self {}
+    }
+
+    fn close(&self) {
+        match *self {}
+    }
+}
+
 impl<T: ?Sized> TransportExt for T where T: Transport {}
 
 /// An extension trait for [`Transport`]s that provides a variety of convenient
@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 //! TCP Transport
-use crate::transport::Transport;
+use crate::transport::{NoSubstreams, SocketExt, Transport};
 use diem_types::{
     network_address::{parse_dns_tcp, parse_ip_tcp, parse_tcp, IpFilter, NetworkAddress},
     PeerId,
@@ -290,6 +290,10 @@ impl TcpSocket {
     }
 }
 
+impl SocketExt for TcpSocket {
+    type Substreams = NoSubstreams;
+}
+
 impl AsyncRead for TcpSocket {
     fn poll_read(
         mut self: Pin<&mut Self>,
@@ -4,6 +4,7 @@
 use crate::{
     application::types::{PeerError, PeerInfo},
     transport::ConnectionMetadata,
+    ProtocolId,
 };
 use diem_config::network_id::{NetworkId, PeerNetworkId};
 use diem_infallible::{RwLock, RwLockWriteGuard};
@@ -57,6 +58,23 @@ impl PeerMetadataStorage {
         network.read(&peer_network_id.peer_id())
     }
 
+    /// Returns the first of `protocols`, from most to least preferred, supported
+    /// by a connected peer.
+    pub fn preferred_protocol(
+        &self,
+        peer_network_id: PeerNetworkId,
+        protocols: &[ProtocolId],
+    ) -> Option<ProtocolId> {
+        let peer_info = self
+            .storage
+            .get(&peer_network_id.network_id())?
+            .read(&peer_network_id.peer_id())?;
+        protocols
+            .iter()
+            .copied()
+            .find(|protocol| peer_info.supports_protocol(*protocol))
+    }
+
     pub fn read_filtered<F: FnMut(&(&PeerId, &PeerInfo)) -> bool>(
         &self,
         network_id: NetworkId,
@@ -21,6 +21,7 @@ pub struct ConnectivityManagerBuilder {
 }
 
 impl ConnectivityManagerBuilder {
+    #[allow(clippy::too_many_arguments)]
     pub fn create(
         network_context: NetworkContext,
         time_service: TimeService,
@@ -34,6 +35,8 @@ impl ConnectivityManagerBuilder {
         connection_notifs_rx: conn_notifs_channel::Receiver,
         outbound_connection_limit: Option<usize>,
         mutual_authentication: bool,
+        max_peer_rotations: usize,
+        slow_peer_latency_ms: u64,
     ) -> Self {
         let (conn_mgr_reqs_tx, conn_mgr_reqs_rx) = channel::new(
             channel_size,
@@ -55,6 +58,8 @@ impl ConnectivityManagerBuilder {
                 Duration::from_millis(max_connection_delay_ms),
                 outbound_connection_limit,
                 mutual_authentication,
+                max_peer_rotations,
+                Duration::from_millis(slow_peer_latency_ms),
             )),
         }
     }
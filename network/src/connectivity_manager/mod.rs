@@ -9,11 +9,12 @@
 //! Consensus actor informs the ConnectivityManager of eligible nodes.
 //!
 //! Different discovery sources notify the ConnectivityManager of updates to
-//! peers' addresses. Currently, there are 2 discovery sources (ordered by
+//! peers' addresses. Currently, there are 3 discovery sources (ordered by
 //! decreasing dial priority, i.e., first is highest priority):
 //!
 //! 1. Onchain discovery protocol
 //! 2. Seed peers from config
+//! 3. Peer exchange with other fullnodes
 //!
 //! In other words, if a we have some addresses discovered via onchain discovery
 //! and some seed addresses from our local config, we will try the onchain
@@ -25,10 +26,17 @@
 //! absolutely important that we maintain connectivity with all peers and heal
 //! any partitions asap, as we aren't currently gossiping consensus messages or
 //! using a relay protocol.
+//!
+//! When only a limited number of outbound connections are allowed, we prefer
+//! dialing peers with a low healthcheck round trip time and few recent
+//! connection failures. Outbound peers which are consistently slow are
+//! periodically disconnected (up to a configured limit per connectivity check)
+//! so that their slots can go to better peers.
 
 use crate::{
     counters,
     logging::NetworkSchema,
+    peer::DisconnectReason,
     peer_manager::{self, conn_notifs_channel, ConnectionRequestSender, PeerManagerError},
     transport::ConnectionMetadata,
 };
@@ -75,6 +83,18 @@ mod test;
 /// around the same time at startup.
 const MAX_CONNECTION_DELAY_JITTER: Duration = Duration::from_millis(100);
 
+/// The latency assumed for peers we haven't measured yet. This ranks unknown
+/// peers behind known fast ones, but ahead of known slow ones.
+const UNKNOWN_PEER_LATENCY: Duration = Duration::from_millis(250);
+/// The penalty added to a peer's score for each recent connection failure.
+const PEER_FAILURE_PENALTY: Duration = Duration::from_millis(100);
+/// The maximum number of recent connection failures tracked per peer.
+const MAX_PEER_FAILURES: u32 = 10;
+/// The weight of a new round trip sample in a peer's average latency.
+const LATENCY_SAMPLE_WEIGHT: f64 = 0.2;
+/// The number of round trip samples needed before a peer can be considered slow.
+const MIN_LATENCY_SAMPLES: u64 = 10;
+
 /// The ConnectivityManager actor.
 pub struct ConnectivityManager<TBackoff> {
     network_context: NetworkContext,
@@ -113,16 +133,23 @@ pub struct ConnectivityManager<TBackoff> {
     rng: SmallRng,
     /// Whether we are using mutual authentication or not
     mutual_authentication: bool,
+    /// Observed latency and failures of known peers.
+    peer_scores: HashMap<PeerId, PeerScore>,
+    /// Maximum number of slow outbound peers disconnected per connectivity check.
+    max_peer_rotations: usize,
+    /// Average round trip time above which an outbound peer is considered slow.
+    slow_peer_latency: Duration,
 }
 
 /// Different sources for peer addresses, ordered by priority (Onchain=highest,
-/// Config=lowest).
+/// PeerExchange=lowest).
 #[repr(u8)]
 #[derive(Copy, Clone, Eq, Hash, PartialEq, Ord, PartialOrd, NumVariants, Serialize)]
 pub enum DiscoverySource {
     OnChainValidatorSet,
     File,
     Config,
+    PeerExchange,
 }
 
 impl fmt::Debug for DiscoverySource {
@@ -140,6 +167,7 @@ impl fmt::Display for DiscoverySource {
                 DiscoverySource::OnChainValidatorSet => "OnChainValidatorSet",
                 DiscoverySource::File => "File",
                 DiscoverySource::Config => "Config",
+                DiscoverySource::PeerExchange => "PeerExchange",
             }
         )
     }
@@ -156,6 +184,8 @@ pub enum ConnectivityRequest {
     /// Gets current size of dial queue. This is useful in tests.
     #[serde(skip)]
     GetDialQueueSize(oneshot::Sender<usize>),
+    /// Report a healthcheck round trip time to a connected peer
+    UpdatePeerLatency(PeerId, Duration),
 }
 
 #[derive(Clone, Debug, Default, PartialEq, Serialize)]
@@ -230,6 +260,18 @@ enum DialResult {
     Failed(PeerManagerError),
 }
 
+/// The observed quality of a peer. Used to prefer low-latency, stable peers
+/// when dialing and to find slow peers to rotate out.
+#[derive(Clone, Debug, Default)]
+struct PeerScore {
+    /// Moving average of the healthcheck round trip time.
+    latency: Option<Duration>,
+    /// The number of round trip samples in the average.
+    num_samples: u64,
+    /// Recent dial failures and lost connections, halved on every new connection.
+    failures: u32,
+}
+
 /// The state needed to compute the next dial delay and dial addr for a given
 /// peer.
 #[derive(Debug, Clone)]
@@ -250,6 +292,7 @@ where
     TBackoff: Iterator<Item = Duration> + Clone,
 {
     /// Creates a new instance of the [`ConnectivityManager`] actor.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         network_context: NetworkContext,
         time_service: TimeService,
@@ -263,6 +306,8 @@ where
         max_delay: Duration,
         outbound_connection_limit: Option<usize>,
         mutual_authentication: bool,
+        max_peer_rotations: usize,
+        slow_peer_latency: Duration,
     ) -> Self {
         assert!(
             eligible.read().is_empty(),
@@ -293,6 +338,9 @@ where
             outbound_connection_limit,
             rng: SmallRng::from_entropy(),
             mutual_authentication,
+            peer_scores: HashMap::new(),
+            max_peer_rotations,
+            slow_peer_latency,
         };
 
         // set the initial config addresses and pubkeys
@@ -335,7 +383,7 @@ where
                         None => break,
                     }
                 },
-                peer_id = pending_dials.select_next_some() => {
+                (peer_id, dial_result) = pending_dials.select_next_some() => {
                     trace!(
                         NetworkSchema::new(&self.network_context)
                             .remote_peer(&peer_id),
@@ -344,6 +392,11 @@ where
                         peer_id.short_str(),
                     );
                     self.dial_queue.remove(&peer_id);
+                    if let DialResult::Failed(err) = dial_result {
+                        if !matches!(err, PeerManagerError::AlreadyConnected(_)) {
+                            self.peer_scores.entry(peer_id).or_default().record_failure();
+                        }
+                    }
                 },
             }
         }
@@ -428,7 +481,7 @@ where
 
     fn dial_eligible_peers<'a>(
         &'a mut self,
-        pending_dials: &'a mut FuturesUnordered<BoxFuture<'static, PeerId>>,
+        pending_dials: &'a mut FuturesUnordered<BoxFuture<'static, (PeerId, DialResult)>>,
     ) {
         let to_connect = self.choose_peers_to_dial();
         for (peer_id, peer) in to_connect {
@@ -436,26 +489,38 @@ where
         }
     }
 
-    fn choose_peers_to_dial(&mut self) -> Vec<(PeerId, DiscoveredPeer)> {
+    /// Peers which we could dial right now.
+    fn dial_candidates(&self) -> impl Iterator<Item = (&PeerId, &DiscoveredPeer)> + '_ {
         let network_id = self.network_context.network_id();
         let role = self.network_context.role();
         let roles_to_dial = network_id.upstream_roles(&role);
-        let mut eligible: Vec<_> = self
-            .discovered_peers
+        self.discovered_peers
             .0
             .iter()
-            .filter(|(peer_id, peer)| {
+            .filter(move |(peer_id, peer)| {
                 peer.is_eligible_to_be_dialed() // The node is eligible to dial
                 && !self.connected.contains_key(peer_id) // The node is not already connected.
                 && !self.dial_queue.contains_key(peer_id) // There is no pending dial to this node.
                 && roles_to_dial.contains(&peer.role) // We can dial this role
             })
+    }
+
+    fn choose_peers_to_dial(&mut self) -> Vec<(PeerId, DiscoveredPeer)> {
+        let mut eligible: Vec<_> = self
+            .dial_candidates()
+            .map(|(peer_id, peer)| (*peer_id, peer.role))
             .collect();
 
-        // Prioritize by PeerRole
+        // Prioritize by PeerRole, then by score (lower is better)
         // Shuffle so we don't get stuck on certain peers
         eligible.shuffle(&mut self.rng);
-        eligible.sort_by(|(_, peer), (_, other)| peer.role.cmp(&other.role));
+        let peer_scores = &self.peer_scores;
+        eligible.sort_by_key(|(peer_id, role)| {
+            let score = peer_scores
+                .get(peer_id)
+                .map_or(UNKNOWN_PEER_LATENCY, PeerScore::score);
+            (*role, score)
+        });
 
         let num_eligible = eligible.len();
 
@@ -481,7 +546,7 @@ where
         eligible
             .iter()
             .take(to_connect)
-            .map(|(peer_id, peer)| (**peer_id, (*peer).clone()))
+            .map(|(peer_id, _)| (*peer_id, self.discovered_peers.0[peer_id].clone()))
             .collect()
     }
 
@@ -489,7 +554,7 @@ where
         &'a mut self,
         peer_id: PeerId,
         peer: DiscoveredPeer,
-        pending_dials: &'a mut FuturesUnordered<BoxFuture<'static, PeerId>>,
+        pending_dials: &'a mut FuturesUnordered<BoxFuture<'static, (PeerId, DialResult)>>,
     ) {
         // If we're attempting to dial a Peer we must not be connected to it. This ensures that
         // newly eligible, but not connected to peers, have their counter initialized properly.
@@ -552,9 +617,9 @@ where
                 },
                 _ = cancel_rx.fuse() => DialResult::Cancelled,
             };
-            log_dial_result(network_context, peer_id, addr, dial_result);
+            log_dial_result(network_context, peer_id, addr, &dial_result);
             // Send peer_id as future result so it can be removed from dial queue.
-            peer_id
+            (peer_id, dial_result)
         };
         pending_dials.push(f.boxed());
         self.dial_queue.insert(peer_id, cancel_tx);
@@ -565,7 +630,7 @@ where
     // incarnations.
     async fn check_connectivity<'a>(
         &'a mut self,
-        pending_dials: &'a mut FuturesUnordered<BoxFuture<'static, PeerId>>,
+        pending_dials: &'a mut FuturesUnordered<BoxFuture<'static, (PeerId, DialResult)>>,
     ) {
         trace!(
             NetworkSchema::new(&self.network_context),
@@ -586,11 +651,74 @@ where
         self.cancel_stale_dials().await;
         // Disconnect from connected peers that are no longer eligible.
         self.close_stale_connections().await;
+        // Make room for better peers by disconnecting from consistently slow ones.
+        self.rotate_slow_peers().await;
         // Dial peers which are eligible but are neither connected nor queued for dialing in the
         // future.
         self.dial_eligible_peers(pending_dials);
     }
 
+    /// Disconnect from outbound peers which are consistently slow, if there are
+    /// other peers we could dial in their place.
+    ///
+    /// This only applies when the number of outbound connections is limited and
+    /// all outbound slots are taken, otherwise we would rather dial more peers.
+    /// The freed slots are filled on the next connectivity check, where the
+    /// slow peers are ranked behind any faster or unmeasured candidates.
+    async fn rotate_slow_peers(&mut self) {
+        let conn_limit = match self.outbound_connection_limit {
+            Some(conn_limit) if self.max_peer_rotations > 0 => conn_limit,
+            _ => return,
+        };
+        let outbound_peers: Vec<_> = self
+            .connected
+            .iter()
+            .filter(|(_, metadata)| metadata.origin == ConnectionOrigin::Outbound)
+            .map(|(peer_id, _)| *peer_id)
+            .collect();
+        if outbound_peers.len() + self.dial_queue.len() < conn_limit {
+            return;
+        }
+
+        let num_candidates = self.dial_candidates().count();
+        let mut slow_peers: Vec<_> = outbound_peers
+            .into_iter()
+            .filter_map(|peer_id| {
+                let score = self.peer_scores.get(&peer_id)?;
+                if score.is_slow(self.slow_peer_latency) {
+                    Some((peer_id, score.score()))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        // Rotate out the slowest peers first
+        slow_peers.sort_by(|(_, score), (_, other)| other.cmp(score));
+
+        let num_rotations = min(self.max_peer_rotations, num_candidates);
+        for (peer_id, score) in slow_peers.into_iter().take(num_rotations) {
+            info!(
+                NetworkSchema::new(&self.network_context).remote_peer(&peer_id),
+                "{} Disconnecting from slow peer {}, score: {:?}",
+                self.network_context,
+                peer_id.short_str(),
+                score
+            );
+            if let Err(e) = self.connection_reqs_tx.disconnect_peer(peer_id).await {
+                info!(
+                    NetworkSchema::new(&self.network_context).remote_peer(&peer_id),
+                    error = %e,
+                    "{} Failed to disconnect from slow peer {} : {}",
+                    self.network_context,
+                    peer_id.short_str(),
+                    e
+                );
+            } else {
+                counters::peer_rotations(&self.network_context).inc();
+            }
+        }
+    }
+
     fn reset_dial_state(&mut self, peer_id: &PeerId) {
         if let Some(dial_state) = self.dial_states.get_mut(peer_id) {
             *dial_state = DialState::new(self.backoff_strategy.clone());
@@ -621,7 +749,24 @@ where
             ConnectivityRequest::GetConnectedSize(sender) => {
                 sender.send(self.connected.len()).unwrap();
             }
+            ConnectivityRequest::UpdatePeerLatency(peer_id, latency) => {
+                self.handle_update_peer_latency(peer_id, latency);
+            }
+        }
+    }
+
+    fn handle_update_peer_latency(&mut self, peer_id: PeerId, latency: Duration) {
+        // Ignore late measurements from peers that have already disconnected
+        if !self.connected.contains_key(&peer_id) {
+            return;
         }
+        let score = self.peer_scores.entry(peer_id).or_default();
+        score.update_latency(latency);
+        counters::peer_score(
+            &self.network_context,
+            &peer_id,
+            score.score().as_millis() as i64,
+        );
     }
 
     fn handle_update_discovered_peers(
@@ -657,7 +802,11 @@ where
 
         // Remove peers that no longer have state
         for peer_id in peers_to_check_remove {
-            self.discovered_peers.try_remove_empty(&peer_id);
+            if self.discovered_peers.try_remove_empty(&peer_id)
+                && !self.connected.contains_key(&peer_id)
+            {
+                self.peer_scores.remove(&peer_id);
+            }
         }
 
         // Make updates to the peers accordingly
@@ -743,12 +892,16 @@ where
                 let peer_id = metadata.remote_peer_id;
                 counters::peer_connected(&self.network_context, &peer_id, 1);
                 self.connected.insert(peer_id, metadata);
+                self.peer_scores
+                    .entry(peer_id)
+                    .or_default()
+                    .record_success();
 
                 // Cancel possible queued dial to this peer.
                 self.dial_states.remove(&peer_id);
                 self.dial_queue.remove(&peer_id);
             }
-            peer_manager::ConnectionNotification::LostPeer(metadata, _context, _reason) => {
+            peer_manager::ConnectionNotification::LostPeer(metadata, _context, reason) => {
                 let peer_id = metadata.remote_peer_id;
                 if let Some(stored_metadata) = self.connected.get(&peer_id) {
                     // Remove node from connected peers list.
@@ -767,6 +920,16 @@ where
                         metadata
                     );
                     self.connected.remove(&peer_id);
+                    counters::remove_peer_score(&self.network_context, &peer_id);
+                    if !self.discovered_peers.0.contains_key(&peer_id) {
+                        // We won't dial this peer, so there's no need to keep its score
+                        self.peer_scores.remove(&peer_id);
+                    } else if reason == DisconnectReason::ConnectionLost {
+                        self.peer_scores
+                            .entry(peer_id)
+                            .or_default()
+                            .record_failure();
+                    }
                 } else {
                     info!(
                         NetworkSchema::new(&self.network_context)
@@ -787,7 +950,7 @@ fn log_dial_result(
     network_context: NetworkContext,
     peer_id: PeerId,
     addr: NetworkAddress,
-    dial_result: DialResult,
+    dial_result: &DialResult,
 ) {
     match dial_result {
         DialResult::Success => {
@@ -814,7 +977,7 @@ fn log_dial_result(
                 info!(
                     NetworkSchema::new(&network_context)
                         .remote_peer(&peer_id)
-                        .network_address(&a),
+                        .network_address(a),
                     "{} Already connected to peer: {} at address: {}",
                     network_context,
                     peer_id.short_str(),
@@ -948,6 +1111,44 @@ impl fmt::Debug for PublicKeys {
     }
 }
 
+///////////////
+// PeerScore //
+///////////////
+
+impl PeerScore {
+    fn update_latency(&mut self, latency: Duration) {
+        self.latency = Some(match self.latency {
+            Some(average) => {
+                average.mul_f64(1.0 - LATENCY_SAMPLE_WEIGHT)
+                    + latency.mul_f64(LATENCY_SAMPLE_WEIGHT)
+            }
+            None => latency,
+        });
+        self.num_samples = self.num_samples.saturating_add(1);
+    }
+
+    fn record_failure(&mut self) {
+        self.failures = min(self.failures + 1, MAX_PEER_FAILURES);
+    }
+
+    fn record_success(&mut self) {
+        self.failures /= 2;
+    }
+
+    /// The score of a peer, lower is better: its average latency, penalized by
+    /// its recent failures.
+    fn score(&self) -> Duration {
+        self.latency.unwrap_or(UNKNOWN_PEER_LATENCY) + PEER_FAILURE_PENALTY * self.failures
+    }
+
+    /// Whether we have enough samples to say the peer is consistently slower
+    /// than `threshold`.
+    fn is_slow(&self, threshold: Duration) -> bool {
+        self.num_samples >= MIN_LATENCY_SAMPLES
+            && self.latency.map_or(false, |latency| latency > threshold)
+    }
+}
+
 ///////////////
 // DialState //
 ///////////////
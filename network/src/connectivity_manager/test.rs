@@ -24,6 +24,8 @@ const CONNECTIVITY_CHECK_INTERVAL: Duration = Duration::from_secs(5);
 const CONNECTION_DELAY: Duration = Duration::from_millis(100);
 const MAX_CONNECTION_DELAY: Duration = Duration::from_secs(60);
 const DEFAULT_BASE_ADDR: &str = "/ip4/127.0.0.1/tcp/9090";
+const MAX_PEER_ROTATIONS: usize = 1;
+const SLOW_PEER_LATENCY: Duration = Duration::from_millis(500);
 
 // TODO(philiphayes): just use `CONNECTION_DELAY + MAX_CONNNECTION_DELAY_JITTER`
 // when the const adds are stabilized, instead of this weird thing...
@@ -103,6 +105,8 @@ impl TestHarness {
             MAX_CONNECTION_DELAY,
             Some(MAX_TEST_CONNECTIONS),
             true, /* mutual_authentication */
+            MAX_PEER_ROTATIONS,
+            SLOW_PEER_LATENCY,
         );
         let mock = Self {
             trusted_peers,
@@ -301,6 +305,16 @@ impl TestHarness {
         self.wait_until_empty_dial_queue().await;
     }
 
+    async fn send_peer_latency(&mut self, peer_id: PeerId, latency: Duration, num_samples: u64) {
+        info!("Sending UpdatePeerLatency");
+        for _ in 0..num_samples {
+            self.conn_mgr_reqs_tx
+                .send(ConnectivityRequest::UpdatePeerLatency(peer_id, latency))
+                .await
+                .unwrap();
+        }
+    }
+
     async fn send_update_discovered_peers(&mut self, src: DiscoverySource, peers: PeerSet) {
         info!("Sending UpdateDiscoveredPeers");
         self.conn_mgr_reqs_tx
@@ -738,6 +752,97 @@ fn public_connection_limit() {
     block_on(future::join(conn_mgr.start(), test));
 }
 
+#[test]
+fn prefer_peers_without_failures() {
+    let mut seeds = HashMap::new();
+    let mut addrs = HashMap::new();
+    for i in 0..=MAX_TEST_CONNECTIONS {
+        let (peer_id, peer, _, addr) = test_peer(i);
+        seeds.insert(peer_id, peer);
+        addrs.insert(peer_id, addr);
+    }
+
+    let (mut mock, conn_mgr) = TestHarness::new(seeds);
+
+    let test = async move {
+        // Fill all outbound slots, but fail the first dial
+        mock.trigger_connectivity_check().await;
+        mock.trigger_pending_dials().await;
+        let error = PeerManagerError::IoError(io::Error::from(io::ErrorKind::ConnectionRefused));
+        let (failed_peer_id, _) = mock.expect_one_dial_inner(Err(error)).await;
+        let mut dialed = vec![failed_peer_id];
+        for _ in 1..MAX_TEST_CONNECTIONS {
+            let (peer_id, _) = mock.expect_one_dial_inner(Ok(())).await;
+            dialed.push(peer_id);
+        }
+        mock.wait_until_empty_dial_queue().await;
+        assert_eq!(MAX_TEST_CONNECTIONS - 1, mock.get_connected_size().await);
+
+        // The free slot should go to the peer we haven't failed to dial yet
+        let (other_peer_id, other_addr) = addrs
+            .into_iter()
+            .find(|(peer_id, _)| !dialed.contains(peer_id))
+            .unwrap();
+        mock.trigger_connectivity_check().await;
+        mock.trigger_pending_dials().await;
+        mock.expect_one_dial_success(other_peer_id, other_addr)
+            .await;
+    };
+    block_on(future::join(conn_mgr.start(), test));
+}
+
+#[test]
+fn rotate_slow_peers() {
+    let mut seeds = HashMap::new();
+    let mut addrs = HashMap::new();
+    for i in 0..=MAX_TEST_CONNECTIONS {
+        let (peer_id, peer, _, addr) = test_peer(i);
+        seeds.insert(peer_id, peer);
+        addrs.insert(peer_id, addr);
+    }
+
+    let (mut mock, conn_mgr) = TestHarness::new(seeds);
+
+    let test = async move {
+        // Fill all outbound slots
+        mock.trigger_connectivity_check().await;
+        mock.trigger_pending_dials().await;
+        let mut dialed = Vec::new();
+        for _ in 0..MAX_TEST_CONNECTIONS {
+            let (peer_id, addr) = mock.expect_one_dial_inner(Ok(())).await;
+            dialed.push((peer_id, addr));
+        }
+        mock.wait_until_empty_dial_queue().await;
+
+        // A peer that's been a bit slow isn't rotated out yet
+        let (slow_peer_id, slow_addr) = dialed[0].clone();
+        let slow_latency = SLOW_PEER_LATENCY * 2;
+        mock.send_peer_latency(slow_peer_id, slow_latency, MIN_LATENCY_SAMPLES - 1)
+            .await;
+        mock.trigger_connectivity_check().await;
+        assert_eq!(MAX_TEST_CONNECTIONS, mock.get_connected_size().await);
+
+        // Once it's consistently slow, it's disconnected to make room
+        mock.send_peer_latency(slow_peer_id, slow_latency, 1).await;
+        mock.trigger_connectivity_check().await;
+        mock.expect_disconnect_success(slow_peer_id, slow_addr)
+            .await;
+        assert_eq!(MAX_TEST_CONNECTIONS - 1, mock.get_connected_size().await);
+
+        // The free slot goes to the peer we haven't tried yet
+        let (other_peer_id, other_addr) = addrs
+            .into_iter()
+            .find(|(peer_id, _)| dialed.iter().all(|(dialed_id, _)| dialed_id != peer_id))
+            .unwrap();
+        mock.trigger_connectivity_check().await;
+        mock.trigger_pending_dials().await;
+        mock.expect_one_dial_success(other_peer_id, other_addr)
+            .await;
+        assert_eq!(MAX_TEST_CONNECTIONS, mock.get_connected_size().await);
+    };
+    block_on(future::join(conn_mgr.start(), test));
+}
+
 #[test]
 fn basic_update_discovered_peers() {
     let mut rng = StdRng::from_seed(TEST_SEED);
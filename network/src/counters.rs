@@ -1,7 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::protocols::wire::handshake::v1::ProtocolId;
+use crate::{peer::quota::QuotaExceeded, protocols::wire::handshake::v1::ProtocolId};
 use diem_config::network_id::NetworkContext;
 use diem_metrics::{
     register_histogram_vec, register_int_counter_vec, register_int_gauge, register_int_gauge_vec,
@@ -85,6 +85,55 @@ pub fn peer_connected(network_context: &NetworkContext, remote_peer_id: &PeerId,
     }
 }
 
+pub static DIEM_NETWORK_PEER_SCORE: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "diem_network_peer_score_ms",
+        "Score of a connected peer used when choosing peers to dial, lower is better",
+        &["role_type", "network_id", "peer_id", "remote_peer_id"]
+    )
+    .unwrap()
+});
+
+pub fn peer_score(network_context: &NetworkContext, remote_peer_id: &PeerId, score_ms: i64) {
+    DIEM_NETWORK_PEER_SCORE
+        .with_label_values(&[
+            network_context.role().as_str(),
+            network_context.network_id().as_str(),
+            network_context.peer_id().short_str().as_str(),
+            remote_peer_id.short_str().as_str(),
+        ])
+        .set(score_ms)
+}
+
+/// Removes the score of a disconnected peer, so we don't keep a series per
+/// peer ever connected to.
+pub fn remove_peer_score(network_context: &NetworkContext, remote_peer_id: &PeerId) {
+    // The series may not exist if we never received a healthcheck response.
+    let _ = DIEM_NETWORK_PEER_SCORE.remove_label_values(&[
+        network_context.role().as_str(),
+        network_context.network_id().as_str(),
+        network_context.peer_id().short_str().as_str(),
+        remote_peer_id.short_str().as_str(),
+    ]);
+}
+
+pub static DIEM_NETWORK_PEER_ROTATIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_network_peer_rotations",
+        "Number of slow outbound peers disconnected to make room for better ones",
+        &["role_type", "network_id", "peer_id"]
+    )
+    .unwrap()
+});
+
+pub fn peer_rotations(network_context: &NetworkContext) -> IntCounter {
+    DIEM_NETWORK_PEER_ROTATIONS.with_label_values(&[
+        network_context.role().as_str(),
+        network_context.network_id().as_str(),
+        network_context.peer_id().short_str().as_str(),
+    ])
+}
+
 /// Increments the counter based on `NetworkContext`
 pub fn inc_by_with_context(
     counter: &IntCounterVec,
@@ -303,6 +352,38 @@ pub fn direct_send_bytes(
     ])
 }
 
+pub static DIEM_NETWORK_PEER_QUOTA_EXCEEDED: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_network_peer_quota_exceeded",
+        "Number of messages dropped because a peer exceeded its quota",
+        &[
+            "role_type",
+            "network_id",
+            "peer_id",
+            "direction",
+            "protocol_id",
+            "quota"
+        ]
+    )
+    .unwrap()
+});
+
+pub fn peer_quota_exceeded(
+    network_context: &NetworkContext,
+    direction: &'static str,
+    protocol_id: Option<ProtocolId>,
+    quota: QuotaExceeded,
+) -> IntCounter {
+    DIEM_NETWORK_PEER_QUOTA_EXCEEDED.with_label_values(&[
+        network_context.role().as_str(),
+        network_context.network_id().as_str(),
+        network_context.peer_id().short_str().as_str(),
+        direction,
+        protocol_id.map_or("none", ProtocolId::as_str),
+        quota.as_str(),
+    ])
+}
+
 /// Counters(queued,dequeued,dropped) related to inbound network notifications for RPCs and
 /// DirectSends.
 pub static PENDING_NETWORK_NOTIFICATIONS: Lazy<IntCounterVec> = Lazy::new(|| {
@@ -12,6 +12,7 @@ use futures::{
     io::{AsyncRead, AsyncWrite},
     ready,
 };
+use netcore::transport::SocketExt;
 use std::{
     convert::TryInto,
     io,
@@ -421,6 +422,23 @@ where
     }
 }
 
+/// The substreams of the underlying connection are not covered by the noise
+/// session, they are only protected by the base transport (i.e., QUIC's TLS).
+impl<TSocket> SocketExt for NoiseStream<TSocket>
+where
+    TSocket: SocketExt,
+{
+    type Substreams = TSocket::Substreams;
+
+    fn channel_binding(&self) -> Option<Vec<u8>> {
+        self.socket.channel_binding()
+    }
+
+    fn substreams(&self) -> Option<Self::Substreams> {
+        self.socket.substreams()
+    }
+}
+
 //
 // NoiseBuffers
 // ------------
@@ -17,13 +17,14 @@
 use crate::{
     counters::{self, RECEIVED_LABEL, SENT_LABEL},
     logging::NetworkSchema,
+    peer::quota::SharedPeerQuota,
     peer_manager::{PeerManagerError, TransportNotification},
     protocols::{
         direct_send::Message,
-        rpc::{InboundRpcRequest, InboundRpcs, OutboundRpcRequest, OutboundRpcs},
+        rpc::{error::RpcError, InboundRpcRequest, InboundRpcs, OutboundRpcRequest, OutboundRpcs},
         wire::messaging::v1::{
             DirectSendMsg, ErrorCode, NetworkMessage, NetworkMessageSink, NetworkMessageStream,
-            Priority, ReadError, WriteError,
+            Priority, ReadError, RpcResponse, WriteError,
         },
     },
     transport::{self, Connection, ConnectionMetadata},
@@ -38,14 +39,15 @@ use diem_time_service::{TimeService, TimeServiceTrait};
 use diem_types::PeerId;
 use futures::{
     self,
-    channel::oneshot,
+    channel::{mpsc, oneshot},
     io::{AsyncRead, AsyncWrite},
-    stream::StreamExt,
+    stream::{self, Stream, StreamExt},
     FutureExt, SinkExt, TryFutureExt,
 };
+use netcore::transport::{SocketExt, Substreams};
 use serde::Serialize;
 use short_hex_str::AsShortHexStr;
-use std::{fmt, panic, time::Duration};
+use std::{collections::HashMap, fmt, io, panic, time::Duration};
 use tokio::runtime::Handle;
 use tokio_util::compat::{
     FuturesAsyncReadCompatExt, TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt,
@@ -56,6 +58,23 @@ mod test;
 
 #[cfg(any(test, feature = "fuzzing"))]
 pub mod fuzzing;
+pub mod quota;
+
+/// The maximum number of inbound substreams read concurrently. Each substream
+/// carries either a single RPC message or the DirectSend messages of one
+/// protocol.
+const MAX_CONCURRENT_INBOUND_SUBSTREAMS: usize = 100;
+
+/// The maximum number of DirectSend messages queued for the substream of one
+/// protocol. Further messages of that protocol are dropped.
+const MAX_PENDING_DIRECT_SEND_SUBSTREAM_MESSAGES: usize = 1024;
+
+/// A message to write to the remote peer along with the channel to report the
+/// result on.
+type WriteRequest = (
+    NetworkMessage,
+    oneshot::Sender<Result<(), PeerManagerError>>,
+);
 
 /// Requests [`Peer`] receives from the [`PeerManager`](crate::peer_manager::PeerManager).
 #[derive(Debug)]
@@ -104,7 +123,7 @@ enum State {
 
 /// The `Peer` actor manages a single connection to another remote peer after
 /// the initial connection establishment and handshake.
-pub struct Peer<TSocket> {
+pub struct Peer<TSocket: SocketExt> {
     /// The network instance this Peer actor is running under.
     network_context: NetworkContext,
     /// A handle to a tokio executor.
@@ -115,6 +134,10 @@ pub struct Peer<TSocket> {
     connection_metadata: ConnectionMetadata,
     /// Underlying connection.
     connection: Option<TSocket>,
+    /// Additional streams of the underlying connection, if supported by the
+    /// transport. When available, RPC messages are each sent over their own
+    /// substream and DirectSend messages over one substream per protocol.
+    substreams: Option<TSocket::Substreams>,
     /// Channel to notify PeerManager that we've disconnected.
     connection_notifs_tx: channel::Sender<TransportNotification<TSocket>>,
     /// Channel to receive requests from PeerManager to send messages and rpcs.
@@ -134,12 +157,17 @@ pub struct Peer<TSocket> {
     inbound_rate_limiter: Option<SharedBucket>,
     /// Optional outbound rate limiter
     outbound_rate_limiter: Option<SharedBucket>,
+    /// Optional budgets for the messages received from the remote peer
+    inbound_quota: Option<SharedPeerQuota>,
+    /// Optional budgets for the messages sent to the remote peer
+    outbound_quota: Option<SharedPeerQuota>,
 }
 
 impl<TSocket> Peer<TSocket>
 where
-    TSocket: AsyncRead + AsyncWrite + Send + 'static,
+    TSocket: AsyncRead + AsyncWrite + SocketExt + Send + 'static,
 {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         network_context: NetworkContext,
         executor: Handle,
@@ -154,18 +182,23 @@ where
         max_frame_size: usize,
         inbound_rate_limiter: Option<SharedBucket>,
         outbound_rate_limiter: Option<SharedBucket>,
+        inbound_quota: Option<SharedPeerQuota>,
+        outbound_quota: Option<SharedPeerQuota>,
     ) -> Self {
         let Connection {
             metadata: connection_metadata,
             socket,
         } = connection;
+        let substreams = socket.substreams();
         let remote_peer_id = connection_metadata.remote_peer_id;
+        let messaging_protocol = connection_metadata.messaging_protocol;
         Self {
             network_context,
             executor,
             time_service: time_service.clone(),
             connection_metadata,
             connection: Some(socket),
+            substreams,
             connection_notifs_tx,
             peer_reqs_rx,
             peer_notifs_tx,
@@ -173,6 +206,8 @@ where
                 network_context,
                 time_service.clone(),
                 remote_peer_id,
+                messaging_protocol,
+                max_frame_size,
                 inbound_rpc_timeout,
                 max_concurrent_inbound_rpcs,
             ),
@@ -180,12 +215,15 @@ where
                 network_context,
                 time_service,
                 remote_peer_id,
+                messaging_protocol,
                 max_concurrent_outbound_rpcs,
             ),
             state: State::Connected,
             max_frame_size,
             inbound_rate_limiter,
             outbound_rate_limiter,
+            inbound_quota,
+            outbound_quota,
         }
     }
 
@@ -213,6 +251,23 @@ where
             self.inbound_rate_limiter.clone(),
         )
         .fuse();
+        // The messages read from the inbound substreams are merged with the
+        // messages read from the main connection.
+        let mut substream_reader = match self
+            .substreams
+            .as_ref()
+            .and_then(Substreams::take_incoming_uni)
+        {
+            Some(incoming) => Self::start_substream_reader_task(
+                &self.executor,
+                incoming,
+                self.max_frame_size,
+                self.inbound_rate_limiter.clone(),
+            )
+            .left_stream(),
+            None => stream::pending().right_stream(),
+        }
+        .fuse();
         let writer = NetworkMessageSink::new(
             write_socket.compat_write(),
             self.max_frame_size,
@@ -229,6 +284,9 @@ where
             self.connection_metadata.clone(),
             self.network_context,
             writer,
+            self.substreams.take(),
+            self.max_frame_size,
+            self.outbound_rate_limiter.clone(),
         );
 
         // Start main Peer event loop.
@@ -268,10 +326,30 @@ where
                         None => self.shutdown(DisconnectReason::ConnectionLost),
                     }
                 },
+                // Handle a new inbound NetworkMessage read off a substream. A
+                // failed substream doesn't affect the rest of the connection.
+                message = substream_reader.select_next_some() => {
+                    let result = match message {
+                        Err(ReadError::IoError(err)) => Err(PeerManagerError::from(err)),
+                        message => self.handle_inbound_message(message, &mut write_reqs_tx).await,
+                    };
+                    if let Err(err) = result {
+                        warn!(
+                            NetworkSchema::new(&self.network_context)
+                                .connection_metadata(&self.connection_metadata),
+                            error = %err,
+                            "{} Error in handling inbound substream message from peer: {}, error: {}",
+                            self.network_context,
+                            remote_peer_id.short_str(),
+                            err
+                        );
+                    }
+                },
                 // Drive the queue of pending inbound rpcs. When one is fulfilled
                 // by an upstream protocol, send the response to the remote peer.
-                maybe_response = self.inbound_rpcs.next_completed_response() => {
-                    if let Err(err) = self.inbound_rpcs.send_outbound_response(&mut write_reqs_tx, maybe_response).await {
+                (request_id, maybe_response) = self.inbound_rpcs.next_completed_response() => {
+                    let maybe_response = self.charge_outbound_response(maybe_response);
+                    if let Err(err) = self.inbound_rpcs.send_outbound_response(&mut write_reqs_tx, request_id, maybe_response).await {
                         warn!(
                             NetworkSchema::new(&self.network_context).connection_metadata(&self.connection_metadata),
                             error = %err,
@@ -282,7 +360,7 @@ where
                 // Poll the queue of pending outbound rpc tasks for the next
                 // successfully or unsuccessfully completed request.
                 (request_id, maybe_completed_request) = self.outbound_rpcs.next_completed_request() => {
-                    self.outbound_rpcs.handle_completed_request(request_id, maybe_completed_request);
+                    self.outbound_rpcs.handle_completed_request(request_id, maybe_completed_request, &mut write_reqs_tx).await;
                 }
             }
         };
@@ -299,33 +377,89 @@ where
     // 2. The second channel is used to instruct the task to close the connection and terminate.
     // If outbound messages are queued when the task receives a close instruction, it discards
     // them and immediately closes the connection.
+    // If the connection supports substreams, RPC requests and responses are each written on a
+    // new substream by a separate task, so that a large response doesn't delay other messages.
+    // DirectSend messages are written in order on one long-lived substream per protocol, so that
+    // the protocols don't delay each other either.
     fn start_writer_task(
         executor: &Handle,
         time_service: TimeService,
         connection_metadata: ConnectionMetadata,
         network_context: NetworkContext,
         mut writer: NetworkMessageSink<impl AsyncWrite + Unpin + Send + 'static>,
-    ) -> (
-        channel::Sender<(
-            NetworkMessage,
-            oneshot::Sender<Result<(), PeerManagerError>>,
-        )>,
-        oneshot::Sender<()>,
-    ) {
+        substreams: Option<TSocket::Substreams>,
+        max_frame_size: usize,
+        outbound_rate_limiter: Option<SharedBucket>,
+    ) -> (channel::Sender<WriteRequest>, oneshot::Sender<()>) {
         let remote_peer_id = connection_metadata.remote_peer_id;
-        let (write_reqs_tx, mut write_reqs_rx): (
-            channel::Sender<(
-                NetworkMessage,
-                oneshot::Sender<Result<(), PeerManagerError>>,
-            )>,
-            _,
-        ) = channel::new(1024, &counters::PENDING_WIRE_MESSAGES);
+        let (write_reqs_tx, mut write_reqs_rx): (channel::Sender<WriteRequest>, _) =
+            channel::new(1024, &counters::PENDING_WIRE_MESSAGES);
         let (close_tx, close_rx) = oneshot::channel();
+        let substream_executor = executor.clone();
         let writer_task = async move {
             let mut close_rx = close_rx.into_stream();
+            // The queues of the DirectSend substream writer tasks, by protocol.
+            // Dropping them on close terminates the tasks.
+            let mut direct_send_writers: HashMap<ProtocolId, mpsc::Sender<WriteRequest>> =
+                HashMap::new();
             loop {
                 futures::select! {
                     (message, ack_ch) = write_reqs_rx.select_next_some() => {
+                        if let (Some(substreams), NetworkMessage::DirectSendMsg(direct_send)) = (&substreams, &message) {
+                            let direct_send_writer = direct_send_writers
+                                .entry(direct_send.protocol_id)
+                                .or_insert_with(|| {
+                                    let (messages_tx, messages_rx) =
+                                        mpsc::channel(MAX_PENDING_DIRECT_SEND_SUBSTREAM_MESSAGES);
+                                    substream_executor.spawn(Self::direct_send_substream_writer(
+                                        substreams.clone(),
+                                        messages_rx,
+                                        max_frame_size,
+                                        outbound_rate_limiter.clone(),
+                                        network_context,
+                                        connection_metadata.clone(),
+                                    ));
+                                    messages_tx
+                                });
+                            // Don't wait for a slow substream, so that the other
+                            // protocols are not delayed.
+                            if let Err(err) = direct_send_writer.try_send((message, ack_ch)) {
+                                let (_message, ack_ch) = err.into_inner();
+                                let _ = ack_ch.send(Err(PeerManagerError::Error(anyhow::anyhow!(
+                                    "DirectSend substream queue is full"
+                                ))));
+                            }
+                            continue;
+                        }
+                        if let (Some(substreams), NetworkMessage::RpcRequest(_) | NetworkMessage::RpcResponse(_)) = (&substreams, &message) {
+                            let substream_write = Self::write_substream(
+                                substreams.clone(),
+                                message,
+                                max_frame_size,
+                                outbound_rate_limiter.clone(),
+                            );
+                            let connection_metadata = connection_metadata.clone();
+                            substream_executor.spawn(async move {
+                                match substream_write.await {
+                                    Ok(()) => {
+                                        let _ = ack_ch.send(Ok(()));
+                                    }
+                                    Err(err) => {
+                                        warn!(
+                                            NetworkSchema::new(&network_context)
+                                                .connection_metadata(&connection_metadata),
+                                            error = %err,
+                                            "{} Error in sending message to peer on substream: {}, error: {}",
+                                            network_context,
+                                            remote_peer_id.short_str(),
+                                            err
+                                        );
+                                        let _ = ack_ch.send(Err(err.into()));
+                                    }
+                                }
+                            });
+                            continue;
+                        }
                         if let Err(err) = writer
                             .send(&message)
                             .map_ok(|_| ack_ch.send(Ok(())))
@@ -398,6 +532,111 @@ where
         (write_reqs_tx, close_tx)
     }
 
+    /// Write a single message on a new substream and close it.
+    async fn write_substream(
+        substreams: TSocket::Substreams,
+        message: NetworkMessage,
+        max_frame_size: usize,
+        outbound_rate_limiter: Option<SharedBucket>,
+    ) -> Result<(), WriteError> {
+        let substream = substreams.open_uni().await?;
+        let mut writer = NetworkMessageSink::new(substream, max_frame_size, outbound_rate_limiter);
+        writer.send(&message).await?;
+        writer.close().await
+    }
+
+    /// Write the DirectSend messages of one protocol, in order, on a long-lived
+    /// substream. The substream is opened for the first message and reopened
+    /// after a failed write.
+    async fn direct_send_substream_writer(
+        substreams: TSocket::Substreams,
+        mut messages_rx: mpsc::Receiver<WriteRequest>,
+        max_frame_size: usize,
+        outbound_rate_limiter: Option<SharedBucket>,
+        network_context: NetworkContext,
+        connection_metadata: ConnectionMetadata,
+    ) {
+        let mut writer = None;
+        while let Some((message, ack_ch)) = messages_rx.next().await {
+            let result = async {
+                if writer.is_none() {
+                    let substream = substreams.open_uni().await?;
+                    writer = Some(NetworkMessageSink::new(
+                        substream,
+                        max_frame_size,
+                        outbound_rate_limiter.clone(),
+                    ));
+                }
+                writer.as_mut().unwrap().send(&message).await
+            }
+            .await;
+            match result {
+                Ok(()) => {
+                    let _ = ack_ch.send(Ok(()));
+                }
+                Err(err) => {
+                    warn!(
+                        NetworkSchema::new(&network_context)
+                            .connection_metadata(&connection_metadata),
+                        error = %err,
+                        "{} Error in sending message to peer on substream: {}, error: {}",
+                        network_context,
+                        connection_metadata.remote_peer_id.short_str(),
+                        err
+                    );
+                    writer = None;
+                    let _ = ack_ch.send(Err(err.into()));
+                }
+            }
+        }
+        if let Some(mut writer) = writer {
+            let _ = writer.close().await;
+        }
+    }
+
+    /// Start a new task which reads the messages of all the inbound substreams
+    /// and returns them as a single stream. A failed substream is dropped
+    /// without affecting the others.
+    fn start_substream_reader_task<TSubstream>(
+        executor: &Handle,
+        incoming: impl Stream<Item = io::Result<TSubstream>> + Send + 'static,
+        max_frame_size: usize,
+        inbound_rate_limiter: Option<SharedBucket>,
+    ) -> impl Stream<Item = Result<NetworkMessage, ReadError>> + Send + 'static
+    where
+        TSubstream: AsyncRead + Unpin + Send + 'static,
+    {
+        let (messages_tx, messages_rx) = mpsc::channel(MAX_CONCURRENT_INBOUND_SUBSTREAMS);
+        let reader_task = incoming.for_each_concurrent(
+            MAX_CONCURRENT_INBOUND_SUBSTREAMS,
+            move |maybe_substream| {
+                let mut messages_tx = messages_tx.clone();
+                let inbound_rate_limiter = inbound_rate_limiter.clone();
+                async move {
+                    let substream = match maybe_substream {
+                        Ok(substream) => substream,
+                        Err(err) => {
+                            let _ = messages_tx.send(Err(ReadError::IoError(err))).await;
+                            return;
+                        }
+                    };
+                    let mut messages =
+                        NetworkMessageStream::new(substream, max_frame_size, inbound_rate_limiter);
+                    while let Some(message) = messages.next().await {
+                        // Deserialization errors are recoverable, the next
+                        // frames of the substream can still be read.
+                        let is_io_error = matches!(message, Err(ReadError::IoError(_)));
+                        if messages_tx.send(message).await.is_err() || is_io_error {
+                            break;
+                        }
+                    }
+                }
+            },
+        );
+        executor.spawn(reader_task);
+        messages_rx
+    }
+
     async fn handle_inbound_message(
         &mut self,
         message: Result<NetworkMessage, ReadError>,
@@ -438,8 +677,58 @@ where
             },
         };
 
+        // Drop the message if the remote peer is over its budget. Every message
+        // is charged to the budget of the peer, and those which carry their
+        // protocol to the budget of the protocol as well.
+        let (protocol_id, num_bytes) = match &message {
+            NetworkMessage::DirectSendMsg(message) => {
+                (Some(message.protocol_id), message.raw_msg.len())
+            }
+            NetworkMessage::RpcRequest(request) => {
+                (Some(request.protocol_id), request.raw_request.len())
+            }
+            NetworkMessage::RpcResponse(response) => (None, response.raw_response.len()),
+            NetworkMessage::RpcResponseChunk(chunk) => (None, chunk.raw_chunk.len()),
+            NetworkMessage::Error(_) | NetworkMessage::RpcCancel(_) => (None, 0),
+        };
+        if let Some(quota) = &self.inbound_quota {
+            let result = quota.lock().try_acquire(protocol_id, num_bytes);
+            if let Err(exceeded) = result {
+                sample!(
+                    SampleRate::Duration(Duration::from_secs(10)),
+                    warn!(
+                        NetworkSchema::new(&self.network_context)
+                            .connection_metadata(&self.connection_metadata),
+                        "{} Dropping inbound message from peer {}: {}",
+                        self.network_context,
+                        self.remote_peer_id().short_str(),
+                        exceeded
+                    )
+                );
+                // Let the remote peer fail the dropped request right away
+                // instead of waiting for it to time out.
+                if let NetworkMessage::RpcRequest(request) = message {
+                    if self
+                        .connection_metadata
+                        .messaging_protocol
+                        .supports_rpc_cancellation_and_streaming()
+                    {
+                        let error_code = ErrorCode::QuotaExceeded(request.request_id);
+                        let (ack_tx, _) = oneshot::channel();
+                        write_reqs_tx
+                            .send((NetworkMessage::Error(error_code), ack_tx))
+                            .await?;
+                    }
+                }
+                return Ok(());
+            }
+        }
+
         match message {
             NetworkMessage::DirectSendMsg(message) => self.handle_inbound_direct_send(message),
+            NetworkMessage::Error(ErrorCode::QuotaExceeded(request_id)) => {
+                self.outbound_rpcs.handle_inbound_rejection(request_id)
+            }
             NetworkMessage::Error(error_msg) => {
                 warn!(
                     NetworkSchema::new(&self.network_context)
@@ -469,6 +758,10 @@ where
             NetworkMessage::RpcResponse(response) => {
                 self.outbound_rpcs.handle_inbound_response(response)
             }
+            NetworkMessage::RpcCancel(cancel) => self.inbound_rpcs.handle_inbound_cancel(cancel),
+            NetworkMessage::RpcResponseChunk(chunk) => {
+                self.outbound_rpcs.handle_inbound_response_chunk(chunk)
+            }
         };
         Ok(())
     }
@@ -529,6 +822,24 @@ where
             PeerRequest::SendDirectSend(message) => {
                 let message_len = message.mdata.len();
                 let protocol_id = message.protocol_id;
+                if let Some(quota) = &self.outbound_quota {
+                    let result = quota.lock().try_acquire(Some(protocol_id), message_len);
+                    if let Err(exceeded) = result {
+                        sample!(
+                            SampleRate::Duration(Duration::from_secs(10)),
+                            warn!(
+                                NetworkSchema::new(&self.network_context)
+                                    .connection_metadata(&self.connection_metadata),
+                                "{} Dropping direct send message for protocol {} to peer {}: {}",
+                                self.network_context,
+                                protocol_id,
+                                self.remote_peer_id().short_str(),
+                                exceeded
+                            )
+                        );
+                        return;
+                    }
+                }
                 let message = NetworkMessage::DirectSendMsg(DirectSendMsg {
                     protocol_id,
                     priority: Priority::default(),
@@ -557,6 +868,16 @@ where
             }
             PeerRequest::SendRpc(request) => {
                 let protocol_id = request.protocol_id;
+                if let Some(quota) = &self.outbound_quota {
+                    let result = quota
+                        .lock()
+                        .try_acquire(Some(protocol_id), request.data.len());
+                    if let Err(exceeded) = result {
+                        // The caller learns about the dropped request right away.
+                        let _ = request.res_tx.send(Err(RpcError::QuotaExceeded(exceeded)));
+                        return;
+                    }
+                }
                 if let Err(e) = self
                     .outbound_rpcs
                     .handle_outbound_request(request, write_reqs_tx)
@@ -576,6 +897,23 @@ where
         }
     }
 
+    /// Charge a completed rpc response to the outbound budget of the remote peer.
+    /// Responses don't carry their protocol, so they only count against the
+    /// budget of the peer.
+    fn charge_outbound_response(
+        &mut self,
+        maybe_response: Result<RpcResponse, RpcError>,
+    ) -> Result<RpcResponse, RpcError> {
+        let response = maybe_response?;
+        if let Some(quota) = &self.outbound_quota {
+            quota
+                .lock()
+                .try_acquire(None, response.raw_response.len())
+                .map_err(RpcError::QuotaExceeded)?;
+        }
+        Ok(response)
+    }
+
     fn shutdown(&mut self, reason: DisconnectReason) {
         // Set the state of the actor to `State::ShuttingDown` to true ensures that the peer actor
         // will terminate and close the connection.
@@ -0,0 +1,389 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Per-peer message and byte budgets.
+//!
+//! A [`PeerQuota`] tracks the budgets of a single peer in one direction: one
+//! budget shared by all the protocols of the peer and, optionally, an
+//! additional budget per [`ProtocolId`]. A message is only let through if it
+//! fits in every budget it is charged to.
+//!
+//! The budgets are kept in [`PeerQuotas`] by [`PeerId`] rather than per
+//! connection, so that a peer can't reset them by reconnecting.
+
+use crate::{counters, logging::NetworkSchema, ProtocolId};
+use diem_config::{
+    config::{PeerQuotaConfig, QuotaLimits},
+    network_id::NetworkContext,
+};
+use diem_infallible::Mutex;
+use diem_logger::prelude::*;
+use diem_rate_limiter::rate_limit::Bucket;
+use diem_types::PeerId;
+use short_hex_str::AsShortHexStr;
+use std::{cmp::max, collections::HashMap, fmt, sync::Arc};
+
+pub type SharedPeerQuota = Arc<Mutex<PeerQuota>>;
+
+/// The budget which rejected a message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuotaExceeded {
+    PeerBytes,
+    PeerMessages,
+    ProtocolBytes(ProtocolId),
+    ProtocolMessages(ProtocolId),
+}
+
+impl QuotaExceeded {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuotaExceeded::PeerBytes => "peer_bytes",
+            QuotaExceeded::PeerMessages => "peer_messages",
+            QuotaExceeded::ProtocolBytes(_) => "protocol_bytes",
+            QuotaExceeded::ProtocolMessages(_) => "protocol_messages",
+        }
+    }
+}
+
+impl fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuotaExceeded::ProtocolBytes(protocol_id)
+            | QuotaExceeded::ProtocolMessages(protocol_id) => {
+                write!(f, "{} quota exceeded for {}", self.as_str(), protocol_id)
+            }
+            _ => write!(f, "{} quota exceeded", self.as_str()),
+        }
+    }
+}
+
+/// A pair of byte and message budgets.
+struct QuotaBuckets {
+    bytes: Bucket,
+    messages: Bucket,
+}
+
+impl QuotaBuckets {
+    fn new(label: &str, key: String, limits: &QuotaLimits) -> Self {
+        // A bucket can't be smaller than its fill rate.
+        let bucket = |kind: &str, size: usize, rate: usize| {
+            let size = max(size, rate);
+            Bucket::new(
+                format!("{}-{}", label, kind),
+                String::new(),
+                key.clone(),
+                size,
+                size,
+                rate,
+                Some(counters::NETWORK_RATE_LIMIT_METRICS.clone()),
+            )
+        };
+        Self {
+            bytes: bucket("bytes", limits.byte_bucket_size, limits.byte_bucket_rate),
+            messages: bucket(
+                "messages",
+                limits.message_bucket_size,
+                limits.message_bucket_rate,
+            ),
+        }
+    }
+
+    /// Acquire the tokens for one message of `num_bytes`, either all of them or
+    /// none. Returns `Err(true)` if the message exceeded the byte budget and
+    /// `Err(false)` if it exceeded the message budget.
+    fn try_acquire(&mut self, num_bytes: usize) -> Result<(), bool> {
+        self.messages.acquire_all_tokens(1).map_err(|_| false)?;
+        if self.bytes.acquire_all_tokens(num_bytes).is_err() {
+            self.messages.return_tokens(1);
+            return Err(true);
+        }
+        Ok(())
+    }
+
+    fn return_tokens(&mut self, num_bytes: usize) {
+        self.messages.return_tokens(1);
+        self.bytes.return_tokens(num_bytes);
+    }
+
+    fn is_full(&mut self) -> bool {
+        self.messages.is_full() && self.bytes.is_full()
+    }
+}
+
+/// The budgets of all the peers, in one direction.
+pub struct PeerQuotas {
+    network_context: NetworkContext,
+    direction: &'static str,
+    config: PeerQuotaConfig,
+    quotas: HashMap<PeerId, SharedPeerQuota>,
+}
+
+impl PeerQuotas {
+    pub fn new(
+        network_context: NetworkContext,
+        direction: &'static str,
+        config: PeerQuotaConfig,
+    ) -> Self {
+        Self {
+            network_context,
+            direction,
+            config,
+            quotas: HashMap::new(),
+        }
+    }
+
+    /// Retrieve the budgets of a peer, or create them.
+    pub fn quota(&mut self, peer_id: PeerId) -> SharedPeerQuota {
+        let (network_context, direction, config) =
+            (self.network_context, self.direction, &self.config);
+        self.quotas
+            .entry(peer_id)
+            .or_insert_with(|| {
+                Arc::new(Mutex::new(PeerQuota::new(
+                    network_context,
+                    direction,
+                    peer_id,
+                    config,
+                )))
+            })
+            .clone()
+    }
+
+    /// Garbage collects the budgets which are not used by any connection and
+    /// have fully refilled, as new ones would be in the same state. The others
+    /// are kept until the peer reconnects or they have refilled.
+    pub fn garbage_collect(&mut self) {
+        self.quotas
+            .retain(|_, quota| Arc::strong_count(quota) > 1 || !quota.lock().is_full());
+    }
+}
+
+/// The budgets of a single peer, in one direction.
+pub struct PeerQuota {
+    network_context: NetworkContext,
+    direction: &'static str,
+    peer: QuotaBuckets,
+    protocols: HashMap<ProtocolId, QuotaBuckets>,
+}
+
+impl PeerQuota {
+    pub fn new(
+        network_context: NetworkContext,
+        direction: &'static str,
+        remote_peer_id: PeerId,
+        config: &PeerQuotaConfig,
+    ) -> Self {
+        let label = format!("{}-peer", direction);
+        let peer = QuotaBuckets::new(&label, remote_peer_id.short_str().to_string(), &config.peer);
+
+        let mut protocols = HashMap::new();
+        for (name, limits) in config.protocols.iter() {
+            match ProtocolId::all()
+                .iter()
+                .find(|protocol_id| protocol_id.as_str() == name)
+            {
+                Some(protocol_id) => {
+                    let label = format!("{}-{}", direction, protocol_id.as_str());
+                    protocols.insert(
+                        *protocol_id,
+                        QuotaBuckets::new(&label, remote_peer_id.short_str().to_string(), limits),
+                    );
+                }
+                None => warn!(
+                    NetworkSchema::new(&network_context),
+                    "{} Ignoring {} quota of unknown protocol: {}",
+                    network_context,
+                    direction,
+                    name
+                ),
+            }
+        }
+
+        Self {
+            network_context,
+            direction,
+            peer,
+            protocols,
+        }
+    }
+
+    /// Charge a message of `num_bytes` to the budget of the peer and, if
+    /// `protocol_id` is given and has a budget, to the budget of the protocol.
+    /// Nothing is charged if the message is rejected.
+    pub fn try_acquire(
+        &mut self,
+        protocol_id: Option<ProtocolId>,
+        num_bytes: usize,
+    ) -> Result<(), QuotaExceeded> {
+        let result = self.try_acquire_inner(protocol_id, num_bytes);
+        if let Err(exceeded) = result {
+            counters::peer_quota_exceeded(
+                &self.network_context,
+                self.direction,
+                protocol_id,
+                exceeded,
+            )
+            .inc();
+        }
+        result
+    }
+
+    fn is_full(&mut self) -> bool {
+        self.peer.is_full() && self.protocols.values_mut().all(QuotaBuckets::is_full)
+    }
+
+    fn try_acquire_inner(
+        &mut self,
+        protocol_id: Option<ProtocolId>,
+        num_bytes: usize,
+    ) -> Result<(), QuotaExceeded> {
+        let protocol = match protocol_id {
+            Some(protocol_id) => self
+                .protocols
+                .get_mut(&protocol_id)
+                .map(|buckets| (protocol_id, buckets)),
+            None => None,
+        };
+        if let Some((protocol_id, buckets)) = protocol {
+            buckets.try_acquire(num_bytes).map_err(|bytes| {
+                if bytes {
+                    QuotaExceeded::ProtocolBytes(protocol_id)
+                } else {
+                    QuotaExceeded::ProtocolMessages(protocol_id)
+                }
+            })?;
+        }
+
+        if let Err(bytes) = self.peer.try_acquire(num_bytes) {
+            // Give the protocol's tokens back, the message isn't let through.
+            if let Some(buckets) = protocol_id.and_then(|id| self.protocols.get_mut(&id)) {
+                buckets.return_tokens(num_bytes);
+            }
+            return Err(if bytes {
+                QuotaExceeded::PeerBytes
+            } else {
+                QuotaExceeded::PeerMessages
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn limits(bytes: usize, messages: usize) -> QuotaLimits {
+        QuotaLimits {
+            byte_bucket_rate: 1,
+            byte_bucket_size: bytes,
+            message_bucket_rate: 1,
+            message_bucket_size: messages,
+        }
+    }
+
+    fn quota(peer: QuotaLimits, protocols: Vec<(&str, QuotaLimits)>) -> PeerQuota {
+        let config = PeerQuotaConfig {
+            peer,
+            protocols: protocols
+                .into_iter()
+                .map(|(name, limits)| (name.to_string(), limits))
+                .collect(),
+        };
+        PeerQuota::new(NetworkContext::mock(), "inbound", PeerId::random(), &config)
+    }
+
+    #[test]
+    fn test_peer_quota() {
+        let mut quota = quota(limits(100, 3), vec![]);
+
+        // the byte budget is shared by all protocols
+        assert_eq!(
+            quota.try_acquire(Some(ProtocolId::MempoolDirectSend), 60),
+            Ok(())
+        );
+        assert_eq!(
+            quota.try_acquire(Some(ProtocolId::ConsensusRpcBcs), 60),
+            Err(QuotaExceeded::PeerBytes)
+        );
+        assert_eq!(quota.try_acquire(None, 40), Ok(()));
+
+        // the rejected message didn't consume a message token
+        assert_eq!(quota.try_acquire(None, 0), Ok(()));
+        assert_eq!(quota.try_acquire(None, 0), Err(QuotaExceeded::PeerMessages));
+
+        // messages larger than the burst are never let through
+        let mut quota = self::quota(limits(100, 3), vec![]);
+        assert_eq!(quota.try_acquire(None, 101), Err(QuotaExceeded::PeerBytes));
+    }
+
+    #[test]
+    fn test_protocol_quota() {
+        let mut quota = quota(
+            limits(100, 10),
+            vec![
+                ("MempoolDirectSend", limits(50, 10)),
+                ("UnknownProtocol", limits(1, 1)),
+            ],
+        );
+
+        assert_eq!(
+            quota.try_acquire(Some(ProtocolId::MempoolDirectSend), 40),
+            Ok(())
+        );
+        assert_eq!(
+            quota.try_acquire(Some(ProtocolId::MempoolDirectSend), 40),
+            Err(QuotaExceeded::ProtocolBytes(ProtocolId::MempoolDirectSend))
+        );
+
+        // other protocols are only limited by the peer budget
+        assert_eq!(
+            quota.try_acquire(Some(ProtocolId::ConsensusRpcBcs), 55),
+            Ok(())
+        );
+
+        // exceeding the peer budget gives the protocol's tokens back
+        assert_eq!(
+            quota.try_acquire(Some(ProtocolId::MempoolDirectSend), 10),
+            Err(QuotaExceeded::PeerBytes)
+        );
+        assert_eq!(
+            quota.try_acquire(Some(ProtocolId::MempoolDirectSend), 5),
+            Ok(())
+        );
+        assert_eq!(
+            quota.try_acquire(Some(ProtocolId::MempoolDirectSend), 6),
+            Err(QuotaExceeded::ProtocolBytes(ProtocolId::MempoolDirectSend))
+        );
+    }
+
+    #[test]
+    fn test_peer_quotas() {
+        let config = PeerQuotaConfig {
+            peer: limits(100, 10),
+            protocols: HashMap::new(),
+        };
+        let mut quotas = PeerQuotas::new(NetworkContext::mock(), "inbound", config);
+        let (peer_a, peer_b) = (PeerId::random(), PeerId::random());
+
+        // a reconnecting peer gets its budget back in the state it left it
+        let quota = quotas.quota(peer_a);
+        assert_eq!(quota.lock().try_acquire(None, 100), Ok(()));
+        drop(quota);
+        quotas.garbage_collect();
+        assert_eq!(
+            quotas.quota(peer_a).lock().try_acquire(None, 1),
+            Err(QuotaExceeded::PeerBytes)
+        );
+
+        // unused budgets are only collected once they have refilled
+        let _quota_b = quotas.quota(peer_b);
+        quotas.garbage_collect();
+        assert!(quotas.quotas.contains_key(&peer_a));
+        assert!(quotas.quotas.contains_key(&peer_b));
+        drop(_quota_b);
+        quotas.garbage_collect();
+        assert!(quotas.quotas.contains_key(&peer_a));
+        assert!(!quotas.quotas.contains_key(&peer_b));
+    }
+}
@@ -6,16 +6,21 @@ use crate::{
         INBOUND_RPC_TIMEOUT_MS, MAX_CONCURRENT_INBOUND_RPCS, MAX_CONCURRENT_OUTBOUND_RPCS,
         MAX_FRAME_SIZE, NETWORK_CHANNEL_SIZE,
     },
-    peer::{DisconnectReason, Peer, PeerNotification, PeerRequest},
+    peer::{
+        quota::{PeerQuotas, QuotaExceeded},
+        DisconnectReason, Peer, PeerNotification, PeerRequest,
+    },
     peer_manager::TransportNotification,
     protocols::{
         direct_send::Message,
-        rpc::{error::RpcError, InboundRpcRequest, OutboundRpcRequest},
+        rpc::{
+            error::RpcError, InboundRpcRequest, OutboundRpcRequest, MAX_RPC_RESPONSE_CHUNK_SIZE,
+        },
         wire::{
             handshake::v1::{MessagingProtocolVersion, ProtocolIdSet},
             messaging::v1::{
-                DirectSendMsg, NetworkMessage, NetworkMessageSink, NetworkMessageStream,
-                RpcRequest, RpcResponse,
+                DirectSendMsg, ErrorCode, NetworkMessage, NetworkMessageSink, NetworkMessageStream,
+                RpcCancel, RpcRequest, RpcResponse, RpcResponseChunk,
             },
         },
     },
@@ -24,18 +29,24 @@ use crate::{
 };
 use bytes::Bytes;
 use channel::{self, diem_channel, message_queues::QueueStyle};
-use diem_config::{config::PeerRole, network_id::NetworkContext};
+use diem_config::{
+    config::{PeerQuotaConfig, PeerRole, QuotaLimits},
+    network_id::NetworkContext,
+};
 use diem_time_service::{MockTimeService, TimeService};
 use diem_types::{network_address::NetworkAddress, PeerId};
 use futures::{
     channel::oneshot,
     future::{self, FutureExt},
-    io::{AsyncRead, AsyncWrite, AsyncWriteExt},
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
     stream::{StreamExt, TryStreamExt},
     SinkExt,
 };
 use memsocket::MemorySocket;
-use netcore::transport::ConnectionOrigin;
+use netcore::transport::{
+    quic::{QuicSocket, QuicTransport},
+    ConnectionOrigin, SocketExt, Substreams, Transport,
+};
 use std::{collections::HashSet, str::FromStr, time::Duration};
 use tokio::runtime::{Handle, Runtime};
 use tokio_util::compat::{
@@ -54,6 +65,30 @@ fn build_test_peer(
     MemorySocket,
     channel::Receiver<TransportNotification<MemorySocket>>,
     diem_channel::Receiver<ProtocolId, PeerNotification>,
+) {
+    build_test_peer_with_config(
+        executor,
+        time_service,
+        origin,
+        MessagingProtocolVersion::V1,
+        None,
+        None,
+    )
+}
+
+fn build_test_peer_with_config(
+    executor: Handle,
+    time_service: TimeService,
+    origin: ConnectionOrigin,
+    messaging_protocol: MessagingProtocolVersion,
+    inbound_quota_config: Option<PeerQuotaConfig>,
+    outbound_quota_config: Option<PeerQuotaConfig>,
+) -> (
+    Peer<MemorySocket>,
+    PeerHandle,
+    MemorySocket,
+    channel::Receiver<TransportNotification<MemorySocket>>,
+    diem_channel::Receiver<ProtocolId, PeerNotification>,
 ) {
     let (a, b) = MemorySocket::new_pair();
     let peer_id = PeerId::random();
@@ -63,7 +98,7 @@ fn build_test_peer(
             ConnectionId::default(),
             NetworkAddress::from_str("/ip4/127.0.0.1/tcp/8081").unwrap(),
             origin,
-            MessagingProtocolVersion::V1,
+            messaging_protocol,
             ProtocolIdSet::empty(),
             PeerRole::Unknown,
         ),
@@ -90,6 +125,12 @@ fn build_test_peer(
         MAX_FRAME_SIZE,
         None,
         None,
+        inbound_quota_config.map(|config| {
+            PeerQuotas::new(NetworkContext::mock(), "inbound", config).quota(peer_id)
+        }),
+        outbound_quota_config.map(|config| {
+            PeerQuotas::new(NetworkContext::mock(), "outbound", config).quota(peer_id)
+        }),
     );
     let peer_handle = PeerHandle(peer_reqs_tx);
 
@@ -280,6 +321,241 @@ fn peer_recv_message() {
     rt.block_on(future::join3(peer.start(), server, client));
 }
 
+fn peer_quota(num_bytes: usize, num_messages: usize) -> PeerQuotaConfig {
+    PeerQuotaConfig {
+        peer: QuotaLimits {
+            byte_bucket_rate: 1,
+            byte_bucket_size: num_bytes,
+            message_bucket_rate: 1,
+            message_bucket_size: num_messages,
+        },
+        ..PeerQuotaConfig::default()
+    }
+}
+
+// Inbound DirectSendMsgs over the peer's quota should be dropped.
+#[test]
+fn peer_recv_message_over_quota() {
+    ::diem_logger::Logger::init_for_testing();
+    let rt = Runtime::new().unwrap();
+    let (peer, _peer_handle, connection, _connection_notifs_rx, mut peer_notifs_rx) =
+        build_test_peer_with_config(
+            rt.handle().clone(),
+            TimeService::mock(),
+            ConnectionOrigin::Inbound,
+            MessagingProtocolVersion::V1,
+            Some(peer_quota(MAX_FRAME_SIZE, 5)),
+            None,
+        );
+
+    let send_msg = NetworkMessage::DirectSendMsg(DirectSendMsg {
+        protocol_id: PROTOCOL,
+        priority: 0,
+        raw_msg: Vec::from("hello world"),
+    });
+    let recv_msg = PeerNotification::RecvMessage(Message {
+        protocol_id: PROTOCOL,
+        mdata: Bytes::from("hello world"),
+    });
+
+    let client = async move {
+        let mut connection = NetworkMessageSink::new(connection, MAX_FRAME_SIZE, None);
+        for _ in 0..10 {
+            connection.send(&send_msg).await.unwrap();
+        }
+        connection.close().await.unwrap();
+    };
+
+    let server = async move {
+        // Only the messages within the quota are delivered.
+        for _ in 0..5 {
+            let received = peer_notifs_rx.next().await.unwrap();
+            assert_eq!(recv_msg, received);
+        }
+        assert!(peer_notifs_rx.next().await.is_none());
+    };
+    rt.block_on(future::join3(peer.start(), server, client));
+}
+
+// Outbound rpcs over the peer's quota should fail without hitting the wire.
+#[test]
+fn peer_send_rpc_over_quota() {
+    ::diem_logger::Logger::init_for_testing();
+    let rt = Runtime::new().unwrap();
+    let (peer, mut peer_handle, _connection, _connection_notifs_rx, _peer_notifs_rx) =
+        build_test_peer_with_config(
+            rt.handle().clone(),
+            TimeService::mock(),
+            ConnectionOrigin::Inbound,
+            MessagingProtocolVersion::V1,
+            None,
+            Some(peer_quota(5, 10)),
+        );
+    let timeout = Duration::from_millis(10_000);
+
+    let client = async move {
+        let result = peer_handle
+            .send_rpc_request(PROTOCOL, Bytes::from(&b"hello world"[..]), timeout)
+            .await;
+        assert!(matches!(
+            result,
+            Err(RpcError::QuotaExceeded(QuotaExceeded::PeerBytes))
+        ));
+    };
+    rt.block_on(future::join(peer.start(), client));
+}
+
+// Inbound rpcs over the peer's quota should be rejected with an explicit error,
+// so that the remote peer doesn't wait for them to time out.
+#[test]
+fn peer_recv_rpc_over_quota() {
+    ::diem_logger::Logger::init_for_testing();
+    let rt = Runtime::new().unwrap();
+    let (peer, _peer_handle, mut connection, _connection_notifs_rx, mut peer_notifs_rx) =
+        build_test_peer_with_config(
+            rt.handle().clone(),
+            TimeService::mock(),
+            ConnectionOrigin::Inbound,
+            MessagingProtocolVersion::V2,
+            Some(peer_quota(MAX_FRAME_SIZE, 1)),
+            None,
+        );
+    let (mut client_sink, mut client_stream) = build_network_sink_stream(&mut connection);
+
+    let send_msg = |request_id| {
+        NetworkMessage::RpcRequest(RpcRequest {
+            request_id,
+            protocol_id: PROTOCOL,
+            priority: 0,
+            raw_request: Vec::from("hello world"),
+        })
+    };
+    let resp_msg = NetworkMessage::RpcResponse(RpcResponse {
+        request_id: 1,
+        priority: 0,
+        raw_response: Vec::from("goodbye world"),
+    });
+
+    let client = async move {
+        client_sink.send(&send_msg(1)).await.unwrap();
+        client_sink.send(&send_msg(2)).await.unwrap();
+        // The first request is answered and the second one is rejected.
+        let mut received = vec![
+            client_stream.next().await.unwrap().unwrap(),
+            client_stream.next().await.unwrap().unwrap(),
+        ];
+        received.sort_by_key(|message| matches!(message, NetworkMessage::Error(_)));
+        assert_eq!(
+            received,
+            vec![resp_msg, NetworkMessage::Error(ErrorCode::QuotaExceeded(2)),]
+        );
+        client_sink.close().await.unwrap();
+    };
+    let server = async move {
+        match peer_notifs_rx.next().await.unwrap() {
+            PeerNotification::RecvRpc(req) => {
+                let response = Ok(Bytes::from("goodbye world"));
+                req.res_tx.send(response).unwrap()
+            }
+            received => panic!("Unexpected PeerNotification: {:?}", received),
+        }
+        assert!(peer_notifs_rx.next().await.is_none());
+    };
+    rt.block_on(future::join3(peer.start(), server, client));
+}
+
+// Outbound rpcs rejected by the remote peer should fail right away.
+#[test]
+fn peer_send_rpc_rejected_by_peer() {
+    ::diem_logger::Logger::init_for_testing();
+    let rt = Runtime::new().unwrap();
+    let (peer, mut peer_handle, mut connection, _connection_notifs_rx, _peer_notifs_rx) =
+        build_test_peer_with_config(
+            rt.handle().clone(),
+            TimeService::mock(),
+            ConnectionOrigin::Inbound,
+            MessagingProtocolVersion::V2,
+            None,
+            None,
+        );
+    let (mut server_sink, mut server_stream) = build_network_sink_stream(&mut connection);
+    let timeout = Duration::from_millis(10_000);
+
+    let client = async move {
+        let result = peer_handle
+            .send_rpc_request(PROTOCOL, Bytes::from(&b"hello world"[..]), timeout)
+            .await;
+        assert!(matches!(result, Err(RpcError::RemoteQuotaExceeded)));
+    };
+    let server = async move {
+        let request_id = match server_stream.next().await.unwrap().unwrap() {
+            NetworkMessage::RpcRequest(request) => request.request_id,
+            received => panic!("Expected RpcRequest; unexpected: {:?}", received),
+        };
+        let error = NetworkMessage::Error(ErrorCode::QuotaExceeded(request_id));
+        server_sink.send(&error).await.unwrap();
+        assert!(matches!(server_stream.next().await, None));
+    };
+    rt.block_on(future::join3(peer.start(), server, client));
+}
+
+// Inbound rpc responses should be charged to the peer's quota as well.
+#[test]
+fn peer_recv_rpc_response_charged_to_quota() {
+    ::diem_logger::Logger::init_for_testing();
+    let rt = Runtime::new().unwrap();
+    let (peer, mut peer_handle, mut connection, _connection_notifs_rx, mut peer_notifs_rx) =
+        build_test_peer_with_config(
+            rt.handle().clone(),
+            TimeService::mock(),
+            ConnectionOrigin::Inbound,
+            MessagingProtocolVersion::V1,
+            Some(peer_quota(20, 100)),
+            None,
+        );
+    let (mut server_sink, mut server_stream) = build_network_sink_stream(&mut connection);
+    let timeout = Duration::from_millis(10_000);
+
+    let client = async move {
+        let response = peer_handle
+            .send_rpc_request(PROTOCOL, Bytes::from(&b"hello world"[..]), timeout)
+            .await
+            .unwrap();
+        assert_eq!(response, Bytes::from(&b"goodbye world"[..]));
+    };
+    let server = async move {
+        let request_id = match server_stream.next().await.unwrap().unwrap() {
+            NetworkMessage::RpcRequest(request) => request.request_id,
+            received => panic!("Expected RpcRequest; unexpected: {:?}", received),
+        };
+        let response = NetworkMessage::RpcResponse(RpcResponse {
+            request_id,
+            priority: 0,
+            raw_response: Vec::from(&b"goodbye world"[..]),
+        });
+        server_sink.send(&response).await.unwrap();
+        // The response used up most of the byte budget, so only the empty
+        // message still fits in.
+        for raw_msg in [&b"hello world"[..], &b""[..]] {
+            let message = NetworkMessage::DirectSendMsg(DirectSendMsg {
+                protocol_id: PROTOCOL,
+                priority: 0,
+                raw_msg: raw_msg.to_vec(),
+            });
+            server_sink.send(&message).await.unwrap();
+        }
+        let received = peer_notifs_rx.next().await.unwrap();
+        assert_eq!(
+            received,
+            PeerNotification::RecvMessage(Message {
+                protocol_id: PROTOCOL,
+                mdata: Bytes::new(),
+            })
+        );
+    };
+    rt.block_on(future::join3(peer.start(), server, client));
+}
+
 // Two connected Peer actors should be able to send/recv a DirectSend from each
 // other and then shutdown gracefully.
 #[test]
@@ -336,6 +612,114 @@ fn peers_send_message_concurrent() {
     rt.block_on(future::join3(peer_a.start(), peer_b.start(), test));
 }
 
+/// Connect a pair of QUIC sockets over the loopback interface.
+async fn quic_socket_pair() -> (QuicSocket, QuicSocket) {
+    let transport = QuicTransport::default();
+    let (mut listener, addr) = transport
+        .listen_on("/ip4/127.0.0.1/udp/0/quic".parse().unwrap())
+        .unwrap();
+    let mut outbound = transport
+        .dial(PeerId::random(), addr)
+        .unwrap()
+        .await
+        .unwrap();
+    // The primary stream is only announced to the listener once it is used.
+    outbound.write_all(b"x").await.unwrap();
+    let (inbound, _addr) = listener.next().await.unwrap().unwrap();
+    let mut inbound = inbound.await.unwrap();
+    let mut buf = [0; 1];
+    inbound.read_exact(&mut buf).await.unwrap();
+    (outbound, inbound)
+}
+
+// With a transport that supports substreams, the DirectSend messages of each
+// protocol should be written in order on a substream of their own.
+#[test]
+fn peer_send_message_on_substreams() {
+    ::diem_logger::Logger::init_for_testing();
+    let rt = Runtime::new().unwrap();
+    let (socket, remote) = rt.block_on(quic_socket_pair());
+    let connection = Connection {
+        metadata: ConnectionMetadata::new(
+            PeerId::random(),
+            ConnectionId::default(),
+            NetworkAddress::from_str("/ip4/127.0.0.1/udp/8081/quic").unwrap(),
+            ConnectionOrigin::Outbound,
+            MessagingProtocolVersion::V1,
+            ProtocolIdSet::empty(),
+            PeerRole::Unknown,
+        ),
+        socket,
+    };
+    let (connection_notifs_tx, _connection_notifs_rx) = channel::new_test(1);
+    let (peer_reqs_tx, peer_reqs_rx) =
+        diem_channel::new(QueueStyle::FIFO, NETWORK_CHANNEL_SIZE, None);
+    let (peer_notifs_tx, _peer_notifs_rx) =
+        diem_channel::new(QueueStyle::FIFO, NETWORK_CHANNEL_SIZE, None);
+    let peer = Peer::new(
+        NetworkContext::mock(),
+        rt.handle().clone(),
+        TimeService::mock(),
+        connection,
+        connection_notifs_tx,
+        peer_reqs_rx,
+        peer_notifs_tx,
+        Duration::from_millis(INBOUND_RPC_TIMEOUT_MS),
+        MAX_CONCURRENT_INBOUND_RPCS,
+        MAX_CONCURRENT_OUTBOUND_RPCS,
+        MAX_FRAME_SIZE,
+        None,
+        None,
+        None,
+        None,
+    );
+    let mut peer_handle = PeerHandle(peer_reqs_tx);
+    let protocols = [
+        ProtocolId::MempoolDirectSend,
+        ProtocolId::ConsensusDirectSendBcs,
+    ];
+
+    let (done_tx, done_rx) = oneshot::channel();
+
+    let server = async move {
+        for i in 0..30u8 {
+            for protocol_id in protocols.iter() {
+                peer_handle.send_direct_send(Message {
+                    protocol_id: *protocol_id,
+                    mdata: Bytes::from(vec![i]),
+                });
+            }
+        }
+        // Dropping the handle shuts the peer down, which closes the connection,
+        // so keep it until the client has read everything.
+        done_rx.await.unwrap();
+    };
+
+    let client = async move {
+        let incoming = remote.substreams().unwrap().take_incoming_uni().unwrap();
+        let substreams: Vec<_> = incoming.take(2).try_collect().await.unwrap();
+        let mut received_protocols = HashSet::new();
+        for substream in substreams {
+            let mut stream = NetworkMessageStream::new(substream, MAX_FRAME_SIZE, None);
+            let mut substream_protocol = None;
+            for i in 0..30u8 {
+                match stream.next().await.unwrap().unwrap() {
+                    NetworkMessage::DirectSendMsg(message) => {
+                        let protocol_id = *substream_protocol.get_or_insert(message.protocol_id);
+                        assert_eq!(message.protocol_id, protocol_id);
+                        assert_eq!(message.raw_msg, vec![i]);
+                    }
+                    message => panic!("Unexpected message: {:?}", message),
+                }
+            }
+            received_protocols.insert(substream_protocol.unwrap());
+        }
+        assert_eq!(received_protocols.len(), protocols.len());
+        done_tx.send(()).unwrap();
+    };
+    rt.block_on(future::join3(peer.start(), server, client));
+}
+
 #[test]
 fn peer_recv_rpc() {
     ::diem_logger::Logger::init_for_testing();
@@ -826,6 +1210,225 @@ fn peer_send_rpc_timeout() {
     rt.block_on(future::join(peer.start(), test));
 }
 
+// Over V2 connections, a canceled inbound rpc should stop waiting on the
+// application handler and never send a response.
+#[test]
+fn peer_recv_rpc_canceled_by_peer() {
+    ::diem_logger::Logger::init_for_testing();
+    let rt = Runtime::new().unwrap();
+    let (peer, _peer_handle, mut connection, _connection_notifs_rx, mut peer_notifs_rx) =
+        build_test_peer_with_config(
+            rt.handle().clone(),
+            TimeService::mock(),
+            ConnectionOrigin::Inbound,
+            MessagingProtocolVersion::V2,
+            None,
+            None,
+        );
+    let (mut client_sink, client_stream) = build_network_sink_stream(&mut connection);
+
+    let send_msg = NetworkMessage::RpcRequest(RpcRequest {
+        request_id: 123,
+        protocol_id: PROTOCOL,
+        priority: 0,
+        raw_request: Vec::from("hello world"),
+    });
+    let cancel_msg = NetworkMessage::RpcCancel(RpcCancel { request_id: 123 });
+
+    let test = async move {
+        // Client sends the rpc request.
+        client_sink.send(&send_msg).await.unwrap();
+
+        // Server receives the rpc request from client.
+        let mut res_tx = match peer_notifs_rx.next().await.unwrap() {
+            PeerNotification::RecvRpc(req) => req.res_tx,
+            received => panic!("Unexpected PeerNotification: {:?}", received),
+        };
+        assert!(!res_tx.is_canceled());
+
+        // Client cancels the request, so the server stops waiting for a response.
+        client_sink.send(&cancel_msg).await.unwrap();
+        res_tx.cancellation().await;
+
+        // Client then half-closes write side.
+        client_sink.close().await.unwrap();
+
+        // Client shouldn't have received any messages.
+        let messages = client_stream.try_collect::<Vec<_>>().await.unwrap();
+        assert_eq!(messages, vec![]);
+    };
+    rt.block_on(future::join(peer.start(), test));
+}
+
+// Over V2 connections, outbound rpcs that time out or are dropped by the
+// application should be canceled on the remote peer.
+#[test]
+fn peer_send_rpc_cancel_notifies_peer() {
+    ::diem_logger::Logger::init_for_testing();
+    let rt = Runtime::new().unwrap();
+    let mock_time = MockTimeService::new();
+    let (peer, peer_handle, mut connection, _connection_notifs_rx, _peer_notifs_rx) =
+        build_test_peer_with_config(
+            rt.handle().clone(),
+            mock_time.clone().into(),
+            ConnectionOrigin::Inbound,
+            MessagingProtocolVersion::V2,
+            None,
+            None,
+        );
+    let (_server_sink, mut server_stream) = build_network_sink_stream(&mut connection);
+    let timeout = Duration::from_millis(10_000);
+
+    let test = async move {
+        // Client sends two rpc requests.
+        let (response_tx_1, response_rx_1) = oneshot::channel();
+        let (response_tx_2, response_rx_2) = oneshot::channel();
+        for res_tx in [response_tx_1, response_tx_2] {
+            let request = PeerRequest::SendRpc(OutboundRpcRequest {
+                protocol_id: PROTOCOL,
+                data: Bytes::from(&b"hello world"[..]),
+                res_tx,
+                timeout,
+            });
+            peer_handle.0.push(PROTOCOL, request).unwrap();
+        }
+
+        // Server receives both rpc requests from client.
+        let mut request_ids = Vec::new();
+        for _ in 0..2 {
+            match server_stream.next().await.unwrap().unwrap() {
+                NetworkMessage::RpcRequest(request) => request_ids.push(request.request_id),
+                received => panic!("Expected RpcRequest; unexpected: {:?}", received),
+            }
+        }
+
+        // The application drops the first request.
+        drop(response_rx_1);
+        let expected = NetworkMessage::RpcCancel(RpcCancel {
+            request_id: request_ids[0],
+        });
+        assert_eq!(server_stream.next().await.unwrap().unwrap(), expected);
+
+        // The second request times out.
+        mock_time.advance_async(timeout).await;
+        assert!(matches!(response_rx_2.await, Ok(Err(RpcError::TimedOut))));
+        let expected = NetworkMessage::RpcCancel(RpcCancel {
+            request_id: request_ids[1],
+        });
+        assert_eq!(server_stream.next().await.unwrap().unwrap(), expected);
+
+        // Keep the peer_handle alive until the end to avoid prematurely closing
+        // the connection.
+        drop(peer_handle);
+    };
+    rt.block_on(future::join(peer.start(), test));
+}
+
+// Over V2 connections, large rpc responses should be streamed in chunks.
+#[test]
+fn peer_recv_rpc_streamed_response() {
+    ::diem_logger::Logger::init_for_testing();
+    let rt = Runtime::new().unwrap();
+    let (peer, _peer_handle, mut connection, _connection_notifs_rx, mut peer_notifs_rx) =
+        build_test_peer_with_config(
+            rt.handle().clone(),
+            TimeService::mock(),
+            ConnectionOrigin::Inbound,
+            MessagingProtocolVersion::V2,
+            None,
+            None,
+        );
+    let (mut client_sink, mut client_stream) = build_network_sink_stream(&mut connection);
+
+    let send_msg = NetworkMessage::RpcRequest(RpcRequest {
+        request_id: 123,
+        protocol_id: PROTOCOL,
+        priority: 0,
+        raw_request: Vec::from("hello world"),
+    });
+    let response: Vec<u8> = (0..3 * MAX_RPC_RESPONSE_CHUNK_SIZE + 1)
+        .map(|i| i as u8)
+        .collect();
+    let expected_response = response.clone();
+
+    let client = async move {
+        client_sink.send(&send_msg).await.unwrap();
+
+        // Client receives the response in chunks and reassembles it.
+        let mut chunks = Vec::new();
+        loop {
+            let chunk = match client_stream.next().await.unwrap().unwrap() {
+                NetworkMessage::RpcResponseChunk(chunk) => chunk,
+                received => panic!("Expected RpcResponseChunk; unexpected: {:?}", received),
+            };
+            assert_eq!(chunk.request_id, 123);
+            assert!(chunk.raw_chunk.len() <= MAX_RPC_RESPONSE_CHUNK_SIZE);
+            let last = chunk.last;
+            chunks.push(chunk);
+            if last {
+                break;
+            }
+        }
+        assert_eq!(chunks.len(), 4);
+        let reassembled: Vec<u8> = chunks
+            .into_iter()
+            .flat_map(|chunk| chunk.raw_chunk)
+            .collect();
+        assert_eq!(reassembled, expected_response);
+        client_sink.close().await.unwrap();
+    };
+    let server = async move {
+        match peer_notifs_rx.next().await.unwrap() {
+            PeerNotification::RecvRpc(req) => req.res_tx.send(Ok(Bytes::from(response))).unwrap(),
+            received => panic!("Unexpected PeerNotification: {:?}", received),
+        }
+    };
+    rt.block_on(future::join3(peer.start(), server, client));
+}
+
+// Over V2 connections, streamed rpc responses should be reassembled.
+#[test]
+fn peer_send_rpc_streamed_response() {
+    ::diem_logger::Logger::init_for_testing();
+    let rt = Runtime::new().unwrap();
+    let (peer, mut peer_handle, mut connection, _connection_notifs_rx, _peer_notifs_rx) =
+        build_test_peer_with_config(
+            rt.handle().clone(),
+            TimeService::mock(),
+            ConnectionOrigin::Inbound,
+            MessagingProtocolVersion::V2,
+            None,
+            None,
+        );
+    let (mut server_sink, mut server_stream) = build_network_sink_stream(&mut connection);
+    let timeout = Duration::from_millis(10_000);
+
+    let client = async move {
+        let response = peer_handle
+            .send_rpc_request(PROTOCOL, Bytes::from(&b"hello world"[..]), timeout)
+            .await
+            .unwrap();
+        assert_eq!(response, Bytes::from(&b"goodbye world"[..]));
+    };
+    let server = async move {
+        let request_id = match server_stream.next().await.unwrap().unwrap() {
+            NetworkMessage::RpcRequest(request) => request.request_id,
+            received => panic!("Expected RpcRequest; unexpected: {:?}", received),
+        };
+        for (raw_chunk, last) in [(&b"goodbye"[..], false), (&b" world"[..], true)] {
+            let chunk = NetworkMessage::RpcResponseChunk(RpcResponseChunk {
+                request_id,
+                priority: 0,
+                last,
+                raw_chunk: raw_chunk.to_vec(),
+            });
+            server_sink.send(&chunk).await.unwrap();
+        }
+        assert!(matches!(server_stream.next().await, None));
+    };
+    rt.block_on(future::join3(peer.start(), server, client));
+}
+
 // PeerManager can request a Peer to shutdown.
 #[test]
 fn peer_disconnect_request() {
@@ -16,7 +16,7 @@ use crate::{
 };
 use channel::{self, diem_channel, message_queues::QueueStyle};
 use diem_config::{
-    config::{PeerSet, RateLimitConfig, HANDSHAKE_VERSION},
+    config::{PeerQuotaConfig, PeerSet, RateLimitConfig, HANDSHAKE_VERSION},
     network_id::NetworkContext,
 };
 use diem_crypto::x25519;
@@ -28,6 +28,7 @@ use diem_types::{chain_id::ChainId, network_address::NetworkAddress, PeerId};
 #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
 use netcore::transport::memory::MemoryTransport;
 use netcore::transport::{
+    quic::{QuicSocket, QuicTransport},
     tcp::{TcpSocket, TcpTransport},
     Transport,
 };
@@ -81,6 +82,8 @@ struct PeerManagerContext {
     inbound_connection_limit: usize,
     inbound_rate_limit_config: Option<RateLimitConfig>,
     outbound_rate_limit_config: Option<RateLimitConfig>,
+    inbound_peer_quota_config: Option<PeerQuotaConfig>,
+    outbound_peer_quota_config: Option<PeerQuotaConfig>,
 }
 
 impl PeerManagerContext {
@@ -105,6 +108,8 @@ impl PeerManagerContext {
         inbound_connection_limit: usize,
         inbound_rate_limit_config: Option<RateLimitConfig>,
         outbound_rate_limit_config: Option<RateLimitConfig>,
+        inbound_peer_quota_config: Option<PeerQuotaConfig>,
+        outbound_peer_quota_config: Option<PeerQuotaConfig>,
     ) -> Self {
         Self {
             pm_reqs_tx,
@@ -123,6 +128,8 @@ impl PeerManagerContext {
             inbound_connection_limit,
             inbound_rate_limit_config,
             outbound_rate_limit_config,
+            inbound_peer_quota_config,
+            outbound_peer_quota_config,
         }
     }
 
@@ -146,11 +153,13 @@ impl PeerManagerContext {
 type MemoryPeerManager =
     PeerManager<DiemNetTransport<MemoryTransport>, NoiseStream<memsocket::MemorySocket>>;
 type TcpPeerManager = PeerManager<DiemNetTransport<TcpTransport>, NoiseStream<TcpSocket>>;
+type QuicPeerManager = PeerManager<DiemNetTransport<QuicTransport>, NoiseStream<QuicSocket>>;
 
 enum TransportPeerManager {
     #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
     Memory(MemoryPeerManager),
     Tcp(TcpPeerManager),
+    Quic(QuicPeerManager),
 }
 
 pub struct PeerManagerBuilder {
@@ -182,6 +191,8 @@ impl PeerManagerBuilder {
         inbound_connection_limit: usize,
         inbound_rate_limit_config: Option<RateLimitConfig>,
         outbound_rate_limit_config: Option<RateLimitConfig>,
+        inbound_peer_quota_config: Option<PeerQuotaConfig>,
+        outbound_peer_quota_config: Option<PeerQuotaConfig>,
     ) -> Self {
         // Setup channel to send requests to peer manager.
         let (pm_reqs_tx, pm_reqs_rx) = diem_channel::new(
@@ -218,6 +229,8 @@ impl PeerManagerBuilder {
                 inbound_connection_limit,
                 inbound_rate_limit_config,
                 outbound_rate_limit_config,
+                inbound_peer_quota_config,
+                outbound_peer_quota_config,
             )),
             peer_manager: None,
             listen_address,
@@ -290,6 +303,22 @@ impl PeerManagerBuilder {
                     executor,
                 )))
             }
+            [Ip4(_), Udp(_), Quic] | [Ip6(_), Udp(_), Quic] => {
+                Some(TransportPeerManager::Quic(self.build_with_transport(
+                    DiemNetTransport::new(
+                        QuicTransport::default(),
+                        self.network_context,
+                        self.time_service.clone(),
+                        key,
+                        auth_mode,
+                        HANDSHAKE_VERSION,
+                        chain_id,
+                        protos,
+                        enable_proxy_protocol,
+                    ),
+                    executor,
+                )))
+            }
             #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
             [Memory(_)] => Some(TransportPeerManager::Memory(self.build_with_transport(
                 DiemNetTransport::new(
@@ -307,7 +336,8 @@ impl PeerManagerBuilder {
             ))),
             _ => panic!(
                 "{} Unsupported listen_address: '{}', expected '/memory/<port>', \
-                 '/ip4/<addr>/tcp/<port>', or '/ip6/<addr>/tcp/<port>'.",
+                 '/ip4/<addr>/tcp/<port>', '/ip6/<addr>/tcp/<port>', \
+                 '/ip4/<addr>/udp/<port>/quic', or '/ip6/<addr>/udp/<port>/quic'.",
                 self.network_context, self.listen_address
             ),
         };
@@ -360,6 +390,8 @@ impl PeerManagerBuilder {
             pm_context.inbound_connection_limit,
             inbound_rate_limiters,
             outbound_rate_limiters,
+            pm_context.inbound_peer_quota_config,
+            pm_context.outbound_peer_quota_config,
         );
 
         // PeerManager constructor appends a public key to the listen_address.
@@ -390,6 +422,7 @@ impl PeerManagerBuilder {
             #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
             TransportPeerManager::Memory(pm) => self.start_peer_manager(pm, executor),
             TransportPeerManager::Tcp(pm) => self.start_peer_manager(pm, executor),
+            TransportPeerManager::Quic(pm) => self.start_peer_manager(pm, executor),
         }
     }
 
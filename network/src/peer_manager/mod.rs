@@ -14,7 +14,7 @@ use crate::{
     constants,
     counters::{self},
     logging::*,
-    peer::{Peer, PeerNotification, PeerRequest},
+    peer::{quota::PeerQuotas, Peer, PeerNotification, PeerRequest},
     transport::{
         Connection, ConnectionId, ConnectionMetadata, TSocket as TransportTSocket,
         TRANSPORT_TIMEOUT,
@@ -22,7 +22,7 @@ use crate::{
     ProtocolId,
 };
 use channel::{self, diem_channel, message_queues::QueueStyle};
-use diem_config::network_id::NetworkContext;
+use diem_config::{config::PeerQuotaConfig, network_id::NetworkContext};
 use diem_logger::prelude::*;
 use diem_rate_limiter::rate_limit::TokenBucketRateLimiter;
 use diem_time_service::{TimeService, TimeServiceTrait};
@@ -126,6 +126,10 @@ where
     inbound_rate_limiters: IpAddrTokenBucketLimiter,
     /// Keyed storage of all outbound rate limiters
     outbound_rate_limiters: IpAddrTokenBucketLimiter,
+    /// Keyed storage of the budgets applied to each peer's inbound traffic
+    inbound_peer_quotas: Option<PeerQuotas>,
+    /// Keyed storage of the budgets applied to each peer's outbound traffic
+    outbound_peer_quotas: Option<PeerQuotas>,
 }
 
 impl<TTransport, TSocket> PeerManager<TTransport, TSocket>
@@ -156,6 +160,8 @@ where
         inbound_connection_limit: usize,
         inbound_rate_limiters: IpAddrTokenBucketLimiter,
         outbound_rate_limiters: IpAddrTokenBucketLimiter,
+        inbound_peer_quota_config: Option<PeerQuotaConfig>,
+        outbound_peer_quota_config: Option<PeerQuotaConfig>,
     ) -> Self {
         let (transport_notifs_tx, transport_notifs_rx) = channel::new(
             channel_size,
@@ -200,6 +206,10 @@ where
             inbound_connection_limit,
             inbound_rate_limiters,
             outbound_rate_limiters,
+            inbound_peer_quotas: inbound_peer_quota_config
+                .map(|config| PeerQuotas::new(network_context, "inbound", config)),
+            outbound_peer_quotas: outbound_peer_quota_config
+                .map(|config| PeerQuotas::new(network_context, "outbound", config)),
         }
     }
 
@@ -425,6 +435,13 @@ where
                 self.inbound_rate_limiters.try_garbage_collect_key(&ip_addr);
                 self.outbound_rate_limiters
                     .try_garbage_collect_key(&ip_addr);
+                for quotas in self
+                    .inbound_peer_quotas
+                    .iter_mut()
+                    .chain(self.outbound_peer_quotas.iter_mut())
+                {
+                    quotas.garbage_collect();
+                }
             }
         }
     }
@@ -657,6 +674,14 @@ where
             .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
         let inbound_rate_limiter = self.inbound_rate_limiters.bucket(ip_addr);
         let outbound_rate_limiter = self.outbound_rate_limiters.bucket(ip_addr);
+        let inbound_quota = self
+            .inbound_peer_quotas
+            .as_mut()
+            .map(|quotas| quotas.quota(peer_id));
+        let outbound_quota = self
+            .outbound_peer_quotas
+            .as_mut()
+            .map(|quotas| quotas.quota(peer_id));
 
         // TODO: Add label for peer.
         let (peer_reqs_tx, peer_reqs_rx) = diem_channel::new(
@@ -686,6 +711,8 @@ where
             self.max_frame_size,
             Some(inbound_rate_limiter),
             Some(outbound_rate_limiter),
+            inbound_quota,
+            outbound_quota,
         );
         self.executor.spawn(peer.start());
 
@@ -114,6 +114,8 @@ fn build_test_peer_manager(
         MAX_INBOUND_CONNECTIONS,
         TokenBucketRateLimiter::open("inbound"),
         TokenBucketRateLimiter::open("outbound"),
+        None,
+        None,
     );
 
     (
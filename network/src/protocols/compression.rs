@@ -0,0 +1,100 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Payload compression for the compressed-BCS [`ProtocolId`]s.
+//!
+//! Every compressed payload starts with a one byte header: small payloads
+//! aren't worth compressing and are sent as-is behind [`UNCOMPRESSED`], larger
+//! ones are deflated behind [`DEFLATE`]. Decompression is capped at
+//! [`MAX_DECOMPRESSED_SIZE`] so a small frame can't expand into an arbitrarily
+//! large allocation.
+//!
+//! [`ProtocolId`]: crate::ProtocolId
+
+use anyhow::{bail, ensure};
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use std::io::{Read, Write};
+
+/// Payloads smaller than this aren't compressed.
+pub const COMPRESSION_THRESHOLD: usize = 4 * 1024; /* 4 KiB */
+/// The maximum size of a decompressed payload.
+pub const MAX_DECOMPRESSED_SIZE: usize = 64 * 1024 * 1024; /* 64 MiB */
+
+const UNCOMPRESSED: u8 = 0;
+const DEFLATE: u8 = 1;
+
+/// Compress `bytes` if they're larger than the [`COMPRESSION_THRESHOLD`].
+pub fn compress(bytes: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+    let mut compressed = Vec::with_capacity(bytes.len() + 1);
+    if bytes.len() < COMPRESSION_THRESHOLD {
+        compressed.push(UNCOMPRESSED);
+        compressed.extend_from_slice(&bytes);
+        return Ok(compressed);
+    }
+
+    compressed.push(DEFLATE);
+    let mut encoder = DeflateEncoder::new(compressed, Compression::fast());
+    encoder.write_all(&bytes)?;
+    Ok(encoder.finish()?)
+}
+
+/// Decompress a payload produced by [`compress`], failing if it expands past
+/// `max_size` bytes.
+pub fn decompress(bytes: &[u8], max_size: usize) -> anyhow::Result<Vec<u8>> {
+    let (header, payload) = match bytes.split_first() {
+        Some(split) => split,
+        None => bail!("Empty compressed payload"),
+    };
+    match *header {
+        UNCOMPRESSED => Ok(payload.to_vec()),
+        DEFLATE => {
+            let mut decompressed = Vec::new();
+            // Read one byte past the limit to tell a payload of exactly
+            // `max_size` from a larger one.
+            DeflateDecoder::new(payload)
+                .take(max_size as u64 + 1)
+                .read_to_end(&mut decompressed)?;
+            ensure!(
+                decompressed.len() <= max_size,
+                "Decompressed payload exceeds the maximum size: {}",
+                max_size
+            );
+            Ok(decompressed)
+        }
+        header => bail!("Unknown compression header: {}", header),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_small_payloads_are_not_compressed() {
+        let bytes = vec![7u8; COMPRESSION_THRESHOLD - 1];
+        let compressed = compress(bytes.clone()).unwrap();
+        assert_eq!(compressed[0], UNCOMPRESSED);
+        assert_eq!(compressed.len(), bytes.len() + 1);
+        assert_eq!(decompress(&compressed, bytes.len()).unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_compress_roundtrip() {
+        let bytes: Vec<u8> = (0..COMPRESSION_THRESHOLD * 4)
+            .map(|i| (i % 16) as u8)
+            .collect();
+        let compressed = compress(bytes.clone()).unwrap();
+        assert_eq!(compressed[0], DEFLATE);
+        assert!(compressed.len() < bytes.len());
+        assert_eq!(decompress(&compressed, bytes.len()).unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_decompression_limit() {
+        let bytes = vec![0u8; 1024 * 1024];
+        let compressed = compress(bytes.clone()).unwrap();
+        assert!(decompress(&compressed, bytes.len() - 1).is_err());
+        assert!(decompress(&[], bytes.len()).is_err());
+        assert!(decompress(&[2, 0, 0], bytes.len()).is_err());
+    }
+}
@@ -3,6 +3,7 @@
 
 use crate::{
     application::storage::PeerMetadataStorage,
+    connectivity_manager::ConnectivityRequest,
     protocols::health_checker::{
         interface::HealthCheckNetworkInterface, HealthChecker, HealthCheckerNetworkEvents,
         HealthCheckerNetworkSender,
@@ -27,6 +28,7 @@ impl HealthCheckerBuilder {
         network_tx: HealthCheckerNetworkSender,
         network_rx: HealthCheckerNetworkEvents,
         peer_metadata_storage: Arc<PeerMetadataStorage>,
+        conn_mgr_reqs_tx: Option<channel::Sender<ConnectivityRequest>>,
     ) -> Self {
         let service = HealthChecker::new(
             network_context,
@@ -35,6 +37,7 @@ impl HealthCheckerBuilder {
             Duration::from_millis(ping_interval_ms),
             Duration::from_millis(ping_timeout_ms),
             ping_failures_tolerated,
+            conn_mgr_reqs_tx,
         );
         Self {
             service: Some(service),
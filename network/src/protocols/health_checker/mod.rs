@@ -11,6 +11,9 @@
 //! disconnect from the peer. It relies on ConnectivityManager or the remote peer to re-establish
 //! the connection.
 //!
+//! The round trip time of each successful ping is reported to the ConnectivityManager, which uses
+//! it to prefer low-latency peers.
+//!
 //! Future Work
 //! -----------
 //! We can make a few other improvements to the health checker. These are:
@@ -19,6 +22,7 @@
 //! - Ping a peer only in periods of no application-level communication with the peer
 use crate::{
     application::interface::NetworkInterface,
+    connectivity_manager::ConnectivityRequest,
     constants::NETWORK_CHANNEL_SIZE,
     counters,
     error::NetworkError,
@@ -153,6 +157,8 @@ pub struct HealthChecker {
     ping_failures_tolerated: u64,
     /// Counter incremented in each round of health checks
     round: u64,
+    /// Channel to report ping round trip times to the ConnectivityManager.
+    conn_mgr_reqs_tx: Option<channel::Sender<ConnectivityRequest>>,
 }
 
 impl HealthChecker {
@@ -164,6 +170,7 @@ impl HealthChecker {
         ping_interval: Duration,
         ping_timeout: Duration,
         ping_failures_tolerated: u64,
+        conn_mgr_reqs_tx: Option<channel::Sender<ConnectivityRequest>>,
     ) -> Self {
         HealthChecker {
             network_context,
@@ -174,6 +181,7 @@ impl HealthChecker {
             ping_timeout,
             ping_failures_tolerated,
             round: 0,
+            conn_mgr_reqs_tx,
         }
     }
 
@@ -266,6 +274,7 @@ impl HealthChecker {
 
                         tick_handlers.push(Self::ping_peer(
                             self.network_context,
+                            self.time_service.clone(),
                             self.network_interface.sender(),
                             peer_id,
                             self.round,
@@ -319,10 +328,10 @@ impl HealthChecker {
         peer_id: PeerId,
         round: u64,
         req_nonce: u32,
-        ping_result: Result<Pong, RpcError>,
+        ping_result: Result<(Pong, Duration), RpcError>,
     ) {
         match ping_result {
-            Ok(pong) => {
+            Ok((pong, latency)) => {
                 if pong.0 == req_nonce {
                     trace!(
                         NetworkSchema::new(&self.network_context).remote_peer(&peer_id),
@@ -350,6 +359,7 @@ impl HealthChecker {
                         };
                         Ok(())
                     });
+                    self.report_latency(peer_id, latency);
                 } else {
                     warn!(
                         SecurityEvent::InvalidHealthCheckerMsg,
@@ -432,14 +442,34 @@ impl HealthChecker {
         }
     }
 
+    /// Report the round trip time of a successful ping to the ConnectivityManager.
+    fn report_latency(&mut self, peer_id: PeerId, latency: Duration) {
+        if let Some(conn_mgr_reqs_tx) = self.conn_mgr_reqs_tx.as_mut() {
+            // Latency reports are best effort, dropping one is fine if the
+            // ConnectivityManager is backed up.
+            if let Err(err) =
+                conn_mgr_reqs_tx.try_send(ConnectivityRequest::UpdatePeerLatency(peer_id, latency))
+            {
+                trace!(
+                    NetworkSchema::new(&self.network_context).remote_peer(&peer_id),
+                    "{} Failed to report latency for peer: {} with error: {:?}",
+                    self.network_context,
+                    peer_id.short_str(),
+                    err
+                );
+            }
+        }
+    }
+
     async fn ping_peer(
         network_context: NetworkContext,
+        time_service: TimeService,
         network_tx: HealthCheckerNetworkSender,
         peer_id: PeerId,
         round: u64,
         nonce: u32,
         ping_timeout: Duration,
-    ) -> (PeerId, u64, u32, Result<Pong, RpcError>) {
+    ) -> (PeerId, u64, u32, Result<(Pong, Duration), RpcError>) {
         trace!(
             NetworkSchema::new(&network_context).remote_peer(&peer_id),
             round = round,
@@ -449,11 +479,14 @@ impl HealthChecker {
             round,
             nonce
         );
+        let start = time_service.now();
         let res_pong_msg = network_tx
             .send_rpc(peer_id, HealthCheckerMsg::Ping(Ping(nonce)), ping_timeout)
             .await
             .and_then(|msg| match msg {
-                HealthCheckerMsg::Pong(res) => Ok(res),
+                HealthCheckerMsg::Pong(res) => {
+                    Ok((res, time_service.now().saturating_duration_since(start)))
+                }
                 _ => Err(RpcError::InvalidRpcResponse),
             });
         (peer_id, round, nonce, res_pong_msg)
@@ -56,6 +56,7 @@ impl TestHarness {
             PING_INTERVAL,
             PING_TIMEOUT,
             ping_failures_tolerated,
+            None,
         );
 
         (
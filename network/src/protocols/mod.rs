@@ -4,6 +4,7 @@
 //! Protocols used by network module for external APIs and internal functionality
 //!
 //! Each protocol corresponds to a certain order of messages
+pub mod compression;
 pub mod direct_send;
 pub mod network;
 pub mod rpc;
@@ -3,7 +3,7 @@
 
 //! Rpc protocol errors
 
-use crate::peer_manager::PeerManagerError;
+use crate::{peer::quota::QuotaExceeded, peer_manager::PeerManagerError};
 use anyhow::anyhow;
 use diem_types::PeerId;
 use futures::channel::{mpsc, oneshot};
@@ -41,6 +41,15 @@ pub enum RpcError {
 
     #[error("Rpc timed out")]
     TimedOut,
+
+    #[error("Rpc canceled by the remote peer")]
+    CanceledByPeer,
+
+    #[error("Peer quota exceeded: {0}")]
+    QuotaExceeded(QuotaExceeded),
+
+    #[error("Rpc dropped by the remote peer: quota exceeded")]
+    RemoteQuotaExceeded,
 }
 
 impl From<PeerManagerError> for RpcError {
@@ -40,6 +40,15 @@
 //! We limit the number of pending inbound and outbound RPC tasks to ensure that
 //! resource usage is bounded.
 //!
+//! ## Cancellation and streaming:
+//!
+//! Over connections using [`MessagingProtocolVersion::V2`], `OutboundRpcs` sends
+//! an [`RpcCancel`] when a request times out or the application drops it before
+//! the response arrives. `InboundRpcs` then stops waiting on the application
+//! handler, which shows up as a canceled response channel, so handlers can skip
+//! work nobody is waiting for. Responses larger than a single chunk are sent as
+//! a sequence of [`RpcResponseChunk`]s and reassembled by `OutboundRpcs`.
+//!
 //! [DiemNet wire protocol v1]: https://github.com/diem/diem/blob/main/specifications/network/messaging-v1.md
 //! [`Peer`]: crate::peer::Peer
 
@@ -53,7 +62,13 @@ use crate::{
     peer_manager::PeerManagerError,
     protocols::{
         network::SerializedRequest,
-        wire::messaging::v1::{NetworkMessage, Priority, RequestId, RpcRequest, RpcResponse},
+        wire::{
+            handshake::v1::MessagingProtocolVersion,
+            messaging::v1::{
+                NetworkMessage, Priority, RequestId, RpcCancel, RpcRequest, RpcResponse,
+                RpcResponseChunk,
+            },
+        },
     },
     ProtocolId,
 };
@@ -78,6 +93,15 @@ use std::{cmp::PartialEq, collections::HashMap, fmt::Debug, time::Duration};
 
 pub mod error;
 
+/// Responses larger than this are streamed in chunks of at most this size, so
+/// a large response doesn't hold up the connection's other messages while it's
+/// being written.
+pub const MAX_RPC_RESPONSE_CHUNK_SIZE: usize = 1024 * 1024; /* 1 MiB */
+/// The maximum size of a reassembled streamed response.
+pub const MAX_STREAMED_RPC_RESPONSE_SIZE: usize = 64 * 1024 * 1024; /* 64 MiB */
+/// Room left in each frame for the `RpcResponseChunk` header.
+const RPC_RESPONSE_CHUNK_OVERHEAD: usize = 64;
+
 /// A wrapper struct for an inbound rpc request and its associated context.
 #[derive(Debug)]
 pub struct InboundRpcRequest {
@@ -172,9 +196,17 @@ pub struct InboundRpcs {
     time_service: TimeService,
     /// The PeerId of this connection's remote peer. Used for logging.
     remote_peer_id: PeerId,
+    /// The messaging protocol version negotiated for this connection.
+    messaging_protocol: MessagingProtocolVersion,
+    /// Responses larger than this are streamed, if the connection supports it.
+    response_chunk_size: usize,
     /// The core async queue of pending inbound rpc tasks. The tasks are driven
     /// to completion by the `InboundRpcs::next_completed_response()` method.
-    inbound_rpc_tasks: FuturesUnordered<BoxFuture<'static, Result<RpcResponse, RpcError>>>,
+    inbound_rpc_tasks:
+        FuturesUnordered<BoxFuture<'static, (RequestId, Result<RpcResponse, RpcError>)>>,
+    /// Maps the `RequestId` of each pending inbound rpc task to a channel that
+    /// cancels it when the remote peer sends an `RpcCancel`.
+    pending_inbound_rpcs: HashMap<RequestId, oneshot::Sender<()>>,
     /// A blanket timeout on all inbound rpc requests. If the application handler
     /// doesn't respond to the request before this timeout, the request will be
     /// dropped.
@@ -189,14 +221,23 @@ impl InboundRpcs {
         network_context: NetworkContext,
         time_service: TimeService,
         remote_peer_id: PeerId,
+        messaging_protocol: MessagingProtocolVersion,
+        max_frame_size: usize,
         inbound_rpc_timeout: Duration,
         max_concurrent_inbound_rpcs: u32,
     ) -> Self {
+        let response_chunk_size = max_frame_size
+            .saturating_sub(RPC_RESPONSE_CHUNK_OVERHEAD)
+            .min(MAX_RPC_RESPONSE_CHUNK_SIZE)
+            .max(1);
         Self {
             network_context,
             time_service,
             remote_peer_id,
+            messaging_protocol,
+            response_chunk_size,
             inbound_rpc_tasks: FuturesUnordered::new(),
+            pending_inbound_rpcs: HashMap::new(),
             inbound_rpc_timeout,
             max_concurrent_inbound_rpcs,
         }
@@ -249,34 +290,49 @@ impl InboundRpcs {
             return Err(err.into());
         }
 
-        // Create a new task that waits for a response from the upper layer with a timeout.
-        let inbound_rpc_task = self
+        // Wait for a response from the upper layer with a timeout.
+        let wait_for_response = self
             .time_service
             .timeout(self.inbound_rpc_timeout, response_rx)
-            .map(move |result| {
+            .map(|result| {
                 // Flatten the errors
-                let maybe_response = match result {
-                    Ok(Ok(Ok(response_bytes))) => Ok(RpcResponse {
-                        request_id,
-                        priority,
-                        raw_response: Vec::from(response_bytes.as_ref()),
-                    }),
+                match result {
+                    Ok(Ok(Ok(response_bytes))) => Ok(response_bytes),
                     Ok(Ok(Err(err))) => Err(err),
                     Ok(Err(oneshot::Canceled)) => Err(RpcError::UnexpectedResponseChannelCancel),
                     Err(timeout::Elapsed) => Err(RpcError::TimedOut),
-                };
-                // Only record latency of successful requests
-                match maybe_response {
-                    Ok(_) => timer.stop_and_record(),
-                    Err(_) => timer.stop_and_discard(),
-                };
-                maybe_response
-            })
-            .boxed();
+                }
+            });
+
+        // Create a new task that waits for the response, unless the remote peer
+        // cancels the request first. Dropping `wait_for_response` on cancellation
+        // drops `response_rx`, which lets the upper layer see that nobody is
+        // waiting for the response anymore.
+        let (cancel_tx, mut cancel_rx) = oneshot::channel();
+        let inbound_rpc_task = async move {
+            tokio::pin!(wait_for_response);
+            let maybe_response = futures::select! {
+                maybe_response = wait_for_response => {
+                    maybe_response.map(|response_bytes| RpcResponse {
+                        request_id,
+                        priority,
+                        raw_response: Vec::from(response_bytes.as_ref()),
+                    })
+                }
+                _ = cancel_rx => Err(RpcError::CanceledByPeer),
+            };
+            // Only record latency of successful requests
+            match maybe_response {
+                Ok(_) => timer.stop_and_record(),
+                Err(_) => timer.stop_and_discard(),
+            };
+            (request_id, maybe_response)
+        };
 
         // Add that task to the inbound completion queue. These tasks are driven
         // forward by `Peer` awaiting `self.next_completed_response()`.
-        self.inbound_rpc_tasks.push(inbound_rpc_task);
+        self.inbound_rpc_tasks.push(inbound_rpc_task.boxed());
+        self.pending_inbound_rpcs.insert(request_id, cancel_tx);
 
         Ok(())
     }
@@ -286,10 +342,26 @@ impl InboundRpcs {
     /// `futures::select!`.
     pub fn next_completed_response(
         &mut self,
-    ) -> impl Future<Output = Result<RpcResponse, RpcError>> + FusedFuture + '_ {
+    ) -> impl Future<Output = (RequestId, Result<RpcResponse, RpcError>)> + FusedFuture + '_ {
         self.inbound_rpc_tasks.select_next_some()
     }
 
+    /// Handle a new inbound `RpcCancel` message. If the request is still
+    /// pending, its task completes without sending a response.
+    pub fn handle_inbound_cancel(&mut self, cancel: RpcCancel) {
+        let request_id = cancel.request_id;
+        if let Some(cancel_tx) = self.pending_inbound_rpcs.remove(&request_id) {
+            let _ = cancel_tx.send(());
+        }
+        trace!(
+            NetworkSchema::new(&self.network_context).remote_peer(&self.remote_peer_id),
+            "{} Peer {} canceled rpc request with request_id {}",
+            self.network_context,
+            self.remote_peer_id.short_str(),
+            request_id,
+        );
+    }
+
     /// Handle a completed response from the application handler. If successful,
     /// we update the appropriate counters and enqueue the response message onto
     /// the outbound write queue.
@@ -299,11 +371,18 @@ impl InboundRpcs {
             NetworkMessage,
             oneshot::Sender<Result<(), PeerManagerError>>,
         )>,
+        request_id: RequestId,
         maybe_response: Result<RpcResponse, RpcError>,
     ) -> Result<(), RpcError> {
         let network_context = &self.network_context;
+        let _ = self.pending_inbound_rpcs.remove(&request_id);
         let response = match maybe_response {
             Ok(response) => response,
+            Err(RpcError::CanceledByPeer) => {
+                // The remote peer isn't waiting for a response anymore.
+                counters::rpc_messages(network_context, RESPONSE_LABEL, CANCELED_LABEL).inc();
+                return Ok(());
+            }
             Err(err) => {
                 counters::rpc_messages(network_context, RESPONSE_LABEL, FAILED_LABEL).inc();
                 return Err(err);
@@ -319,9 +398,33 @@ impl InboundRpcs {
             self.remote_peer_id.short_str(),
             response.request_id,
         );
-        let message = NetworkMessage::RpcResponse(response);
-        let (ack_tx, _) = oneshot::channel();
-        write_reqs_tx.send((message, ack_tx)).await?;
+        if self
+            .messaging_protocol
+            .supports_rpc_cancellation_and_streaming()
+            && response.raw_response.len() > self.response_chunk_size
+        {
+            // Stream the response in chunks, in order, on the message stream.
+            let RpcResponse {
+                request_id,
+                priority,
+                raw_response,
+            } = response;
+            let mut chunks = raw_response.chunks(self.response_chunk_size).peekable();
+            while let Some(chunk) = chunks.next() {
+                let message = NetworkMessage::RpcResponseChunk(RpcResponseChunk {
+                    request_id,
+                    priority,
+                    last: chunks.peek().is_none(),
+                    raw_chunk: chunk.to_vec(),
+                });
+                let (ack_tx, _) = oneshot::channel();
+                write_reqs_tx.send((message, ack_tx)).await?;
+            }
+        } else {
+            let message = NetworkMessage::RpcResponse(response);
+            let (ack_tx, _) = oneshot::channel();
+            write_reqs_tx.send((message, ack_tx)).await?;
+        }
 
         // Collect counters for sent response.
         counters::rpc_messages(network_context, RESPONSE_LABEL, SENT_LABEL).inc();
@@ -340,13 +443,15 @@ pub struct OutboundRpcs {
     time_service: TimeService,
     /// The PeerId of this connection's remote peer. Used for logging.
     remote_peer_id: PeerId,
+    /// The messaging protocol version negotiated for this connection.
+    messaging_protocol: MessagingProtocolVersion,
     /// Generates the next RequestId to use for the next outbound RPC. Note that
     /// request ids are local to each connection.
     request_id_gen: U32IdGenerator,
     /// A completion queue of pending outbound rpc tasks. Each task waits for
-    /// either a successful `RpcResponse` message, handed to it via the channel
-    /// in `pending_outbound_rpcs`, or waits for a timeout or cancellation
-    /// notification. After completion, the task will yield its `RequestId` and
+    /// either a successful `RpcResponse` message or a rejection of the request,
+    /// handed to it via the channel in `pending_outbound_rpcs`, or waits for a
+    /// timeout or cancellation notification. After completion, the task will yield its `RequestId` and
     /// other metadata (success/failure, success latency, response length) via
     /// the future from `next_completed_request`.
     outbound_rpc_tasks:
@@ -354,7 +459,9 @@ pub struct OutboundRpcs {
     /// Maps a `RequestId` into a handle to a task in the `outbound_rpc_tasks`
     /// completion queue. When a new `RpcResponse` message comes in, we will use
     /// this map to notify the corresponding task that its response has arrived.
-    pending_outbound_rpcs: HashMap<RequestId, oneshot::Sender<RpcResponse>>,
+    pending_outbound_rpcs: HashMap<RequestId, oneshot::Sender<Result<RpcResponse, RpcError>>>,
+    /// Streamed responses that are still being reassembled, by `RequestId`.
+    partial_responses: HashMap<RequestId, RpcResponse>,
     /// Only allow this many concurrent outbound rpcs at one time from this remote
     /// peer. New outbound requests exceeding this limit will be dropped.
     max_concurrent_outbound_rpcs: u32,
@@ -365,15 +472,18 @@ impl OutboundRpcs {
         network_context: NetworkContext,
         time_service: TimeService,
         remote_peer_id: PeerId,
+        messaging_protocol: MessagingProtocolVersion,
         max_concurrent_outbound_rpcs: u32,
     ) -> Self {
         Self {
             network_context,
             time_service,
             remote_peer_id,
+            messaging_protocol,
             request_id_gen: U32IdGenerator::new(),
             outbound_rpc_tasks: FuturesUnordered::new(),
             pending_outbound_rpcs: HashMap::new(),
+            partial_responses: HashMap::new(),
             max_concurrent_outbound_rpcs,
         }
     }
@@ -444,7 +554,7 @@ impl OutboundRpcs {
         counters::rpc_bytes(network_context, REQUEST_LABEL, SENT_LABEL).inc_by(req_len);
 
         // Create channel over which response is delivered to outbound_rpc_task.
-        let (response_tx, response_rx) = oneshot::channel::<RpcResponse>();
+        let (response_tx, response_rx) = oneshot::channel::<Result<RpcResponse, RpcError>>();
 
         // Store send-side in the pending map so we can notify outbound_rpc_task
         // when the rpc response has arrived.
@@ -459,7 +569,8 @@ impl OutboundRpcs {
             .map(|result| {
                 // Flatten errors.
                 match result {
-                    Ok(Ok(response)) => Ok(Bytes::from(response.raw_response)),
+                    Ok(Ok(Ok(response))) => Ok(Bytes::from(response.raw_response)),
+                    Ok(Ok(Err(err))) => Err(err),
                     Ok(Err(oneshot::Canceled)) => Err(RpcError::UnexpectedResponseChannelCancel),
                     Err(timeout::Elapsed) => Err(RpcError::TimedOut),
                 }
@@ -520,23 +631,50 @@ impl OutboundRpcs {
 
     /// Handle a newly completed task from the `self.outbound_rpc_tasks` queue.
     /// At this point, the application layer's request has already been fulfilled;
-    /// we just need to clean up this request and update some counters.
-    pub fn handle_completed_request(
+    /// we just need to clean up this request and update some counters. If the
+    /// request failed before its response arrived, we let the remote peer know
+    /// it can stop working on it.
+    pub async fn handle_completed_request(
         &mut self,
         request_id: RequestId,
         result: Result<(f64, u64), RpcError>,
+        write_reqs_tx: &mut channel::Sender<(
+            NetworkMessage,
+            oneshot::Sender<Result<(), PeerManagerError>>,
+        )>,
     ) {
         // Remove request_id from pending_outbound_rpcs if not already removed.
         //
-        // We don't care about the value from `remove` here. If the request
-        // timed-out or was canceled, it will still be in the pending map.
-        // Otherwise, if we received a response for our request, we will have
-        // removed and triggered the oneshot from the pending map, notifying us.
-        let _ = self.pending_outbound_rpcs.remove(&request_id);
+        // If the request timed-out or was canceled, it will still be in the
+        // pending map. Otherwise, if we received a response for our request, we
+        // will have removed and triggered the oneshot from the pending map,
+        // notifying us.
+        let awaiting_response = self.pending_outbound_rpcs.remove(&request_id).is_some();
+        let _ = self.partial_responses.remove(&request_id);
 
         let network_context = &self.network_context;
         let peer_id = &self.remote_peer_id;
 
+        if awaiting_response
+            && result.is_err()
+            && self
+                .messaging_protocol
+                .supports_rpc_cancellation_and_streaming()
+        {
+            let message = NetworkMessage::RpcCancel(RpcCancel { request_id });
+            let (ack_tx, _) = oneshot::channel();
+            if let Err(err) = write_reqs_tx.send((message, ack_tx)).await {
+                warn!(
+                    NetworkSchema::new(network_context).remote_peer(peer_id),
+                    "{} Failed to cancel rpc request with request_id {} to {}: {}",
+                    network_context,
+                    request_id,
+                    peer_id.short_str(),
+                    err
+                );
+            }
+        }
+
         match result {
             Ok((latency, request_len)) => {
                 counters::rpc_messages(network_context, RESPONSE_LABEL, RECEIVED_LABEL).inc();
@@ -572,6 +710,59 @@ impl OutboundRpcs {
         }
     }
 
+    /// Handle a new inbound `RpcResponseChunk` message. Chunks are appended to
+    /// the partial response for a pending request; the last chunk completes the
+    /// response as if it arrived in a single `RpcResponse`.
+    pub fn handle_inbound_response_chunk(&mut self, chunk: RpcResponseChunk) {
+        let network_context = &self.network_context;
+        let peer_id = &self.remote_peer_id;
+        let request_id = chunk.request_id;
+
+        if !self.pending_outbound_rpcs.contains_key(&request_id) {
+            let _ = self.partial_responses.remove(&request_id);
+            trace!(
+                NetworkSchema::new(network_context).remote_peer(peer_id),
+                request_id = request_id,
+                "{} Received response chunk for expired request_id {} from {}. Discarding.",
+                network_context,
+                request_id,
+                peer_id.short_str(),
+            );
+            return;
+        }
+
+        let response = self
+            .partial_responses
+            .entry(request_id)
+            .or_insert_with(|| RpcResponse {
+                request_id,
+                priority: chunk.priority,
+                raw_response: Vec::new(),
+            });
+        if response.raw_response.len() + chunk.raw_chunk.len() > MAX_STREAMED_RPC_RESPONSE_SIZE {
+            // Dropping the pending response channel fails the request.
+            let _ = self.partial_responses.remove(&request_id);
+            let _ = self.pending_outbound_rpcs.remove(&request_id);
+            warn!(
+                NetworkSchema::new(network_context).remote_peer(peer_id),
+                request_id = request_id,
+                "{} Streamed response for request_id {} from {} exceeds the maximum size: {}",
+                network_context,
+                request_id,
+                peer_id.short_str(),
+                MAX_STREAMED_RPC_RESPONSE_SIZE,
+            );
+            return;
+        }
+        response.raw_response.extend_from_slice(&chunk.raw_chunk);
+
+        if chunk.last {
+            if let Some(response) = self.partial_responses.remove(&request_id) {
+                self.handle_inbound_response(response);
+            }
+        }
+    }
+
     /// Handle a new inbound `RpcResponse` message. If we have a pending request
     /// with a matching request id in the `pending_outbound_rpcs` map, this will
     /// trigger that corresponding task to wake up and complete in
@@ -583,7 +774,7 @@ impl OutboundRpcs {
 
         let is_canceled = if let Some(response_tx) = self.pending_outbound_rpcs.remove(&request_id)
         {
-            response_tx.send(response).is_err()
+            response_tx.send(Ok(response)).is_err()
         } else {
             true
         };
@@ -608,4 +799,22 @@ impl OutboundRpcs {
             );
         }
     }
+
+    /// Handle the rejection of one of our requests by the remote peer, which
+    /// dropped it because we exceeded our quota. The pending request fails
+    /// right away instead of timing out.
+    pub fn handle_inbound_rejection(&mut self, request_id: RequestId) {
+        let _ = self.partial_responses.remove(&request_id);
+        if let Some(response_tx) = self.pending_outbound_rpcs.remove(&request_id) {
+            let _ = response_tx.send(Err(RpcError::RemoteQuotaExceeded));
+        }
+        info!(
+            NetworkSchema::new(&self.network_context).remote_peer(&self.remote_peer_id),
+            request_id = request_id,
+            "{} Rpc request with request_id {} was dropped by {}: quota exceeded",
+            self.network_context,
+            request_id,
+            self.remote_peer_id.short_str(),
+        );
+    }
 }
@@ -12,10 +12,11 @@
 //!
 //! [DiemNet Handshake v1 Specification]: https://github.com/diem/diem/blob/main/specifications/network/handshake-v1.md
 
+use crate::protocols::compression;
 use anyhow::anyhow;
 use diem_config::network_id::NetworkId;
 use diem_types::chain_id::ChainId;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::{
     collections::BTreeMap,
     fmt,
@@ -51,11 +52,17 @@ pub enum ProtocolId {
     ConsensusRpcJson = 7,
     StorageServiceRpc = 8,
     MempoolRpc = 9,
+    // compressed bcs, only used with peers which advertise them in the handshake
+    StateSyncDirectSendCompressed = 10,
+    StorageServiceRpcCompressed = 11,
+    MempoolDirectSendCompressed = 12,
 }
 
 /// The encoding types for Protocols
 enum Encoding {
     Bcs,
+    /// Bcs, compressed when larger than [`compression::COMPRESSION_THRESHOLD`]
+    CompressedBcs,
     Json,
 }
 
@@ -73,6 +80,9 @@ impl ProtocolId {
             ConsensusRpcJson => "ConsensusRpcJson",
             StorageServiceRpc => "StorageServiceRpc",
             MempoolRpc => "MempoolRpc",
+            StateSyncDirectSendCompressed => "StateSyncDirectSendCompressed",
+            StorageServiceRpcCompressed => "StorageServiceRpcCompressed",
+            MempoolDirectSendCompressed => "MempoolDirectSendCompressed",
         }
     }
 
@@ -88,6 +98,9 @@ impl ProtocolId {
             ProtocolId::ConsensusRpcJson,
             ProtocolId::StorageServiceRpc,
             ProtocolId::MempoolRpc,
+            ProtocolId::StateSyncDirectSendCompressed,
+            ProtocolId::StorageServiceRpcCompressed,
+            ProtocolId::MempoolDirectSendCompressed,
         ]
     }
 
@@ -95,6 +108,9 @@ impl ProtocolId {
     fn encoding(self) -> Encoding {
         match self {
             ProtocolId::ConsensusDirectSendJson | ProtocolId::ConsensusRpcJson => Encoding::Json,
+            ProtocolId::StateSyncDirectSendCompressed
+            | ProtocolId::StorageServiceRpcCompressed
+            | ProtocolId::MempoolDirectSendCompressed => Encoding::CompressedBcs,
             _ => Encoding::Bcs,
         }
     }
@@ -108,13 +124,21 @@ impl ProtocolId {
         match self.encoding() {
             Encoding::Json => serde_json::to_vec(value).map_err(|e| anyhow!("{:?}", e)),
             Encoding::Bcs => bcs::to_bytes(value).map_err(|e| anyhow! {"{:?}", e}),
+            Encoding::CompressedBcs => {
+                let bytes = bcs::to_bytes(value).map_err(|e| anyhow! {"{:?}", e})?;
+                compression::compress(bytes)
+            }
         }
     }
 
-    pub fn from_bytes<'a, T: Deserialize<'a>>(&self, bytes: &'a [u8]) -> anyhow::Result<T> {
+    pub fn from_bytes<T: DeserializeOwned>(&self, bytes: &[u8]) -> anyhow::Result<T> {
         match self.encoding() {
             Encoding::Json => serde_json::from_slice(bytes).map_err(|e| anyhow!("{:?}", e)),
             Encoding::Bcs => bcs::from_bytes(bytes).map_err(|e| anyhow! {"{:?}", e}),
+            Encoding::CompressedBcs => {
+                let bytes = compression::decompress(bytes, compression::MAX_DECOMPRESSED_SIZE)?;
+                bcs::from_bytes(&bytes).map_err(|e| anyhow! {"{:?}", e})
+            }
         }
     }
 }
@@ -215,14 +239,23 @@ impl<'a> FromIterator<&'a ProtocolId> for ProtocolIdSet {
 #[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
 pub enum MessagingProtocolVersion {
     V1 = 0,
+    /// Adds rpc cancellation and streamed rpc responses.
+    V2 = 1,
 }
 
 impl MessagingProtocolVersion {
     fn as_str(&self) -> &str {
         match self {
             Self::V1 => "V1",
+            Self::V2 => "V2",
         }
     }
+
+    /// Whether `RpcCancel` and `RpcResponseChunk` messages can be sent over a
+    /// connection using this version.
+    pub fn supports_rpc_cancellation_and_streaming(self) -> bool {
+        self >= Self::V2
+    }
 }
 
 impl fmt::Debug for MessagingProtocolVersion {
@@ -35,6 +35,28 @@ fn test_as_u8_serde_equiv() {
     }
 }
 
+#[test]
+fn test_compressed_encoding() {
+    let small = vec![1u64; 8];
+    let large = vec![1u64; 8 * 1024];
+    for value in [small, large] {
+        let bcs_bytes = ProtocolId::StorageServiceRpc.to_bytes(&value).unwrap();
+        let compressed_bytes = ProtocolId::StorageServiceRpcCompressed
+            .to_bytes(&value)
+            .unwrap();
+        assert!(compressed_bytes.len() <= bcs_bytes.len() + 1);
+        let decoded: Vec<u64> = ProtocolId::StorageServiceRpcCompressed
+            .from_bytes(&compressed_bytes)
+            .unwrap();
+        assert_eq!(decoded, value);
+
+        // peers without the compressed protocols can't decode them
+        ProtocolId::StorageServiceRpc
+            .from_bytes::<Vec<u64>>(&compressed_bytes)
+            .unwrap_err();
+    }
+}
+
 #[test]
 fn represents_same_network() {
     let mut handshake_msg = HandshakeMsg::new_for_testing();
@@ -182,3 +204,30 @@ fn ignore_unknown_protocols() {
         ProtocolIdSet::empty(),
     );
 }
+
+#[test]
+fn highest_common_messaging_protocol() {
+    let protocols = ProtocolIdSet::from_iter([ProtocolId::ConsensusRpcBcs]);
+    let handshake_msg = |versions: &[MessagingProtocolVersion]| HandshakeMsg {
+        chain_id: ChainId::default(),
+        network_id: NetworkId::default(),
+        supported_protocols: versions
+            .iter()
+            .map(|version| (*version, protocols.clone()))
+            .collect(),
+    };
+    let v1 = handshake_msg(&[MessagingProtocolVersion::V1]);
+    let v1_v2 = handshake_msg(&[MessagingProtocolVersion::V1, MessagingProtocolVersion::V2]);
+
+    // Both peers support V2.
+    let (version, _) = v1_v2.perform_handshake(&v1_v2).unwrap();
+    assert_eq!(version, MessagingProtocolVersion::V2);
+    assert!(version.supports_rpc_cancellation_and_streaming());
+
+    // Older peers only support V1, so both sides fall back to it.
+    let (version, _) = v1_v2.perform_handshake(&v1).unwrap();
+    assert_eq!(version, MessagingProtocolVersion::V1);
+    let (version, _) = v1.perform_handshake(&v1_v2).unwrap();
+    assert_eq!(version, MessagingProtocolVersion::V1);
+    assert!(!version.supports_rpc_cancellation_and_streaming());
+}
@@ -43,6 +43,14 @@ pub enum NetworkMessage {
     RpcRequest(RpcRequest),
     RpcResponse(RpcResponse),
     DirectSendMsg(DirectSendMsg),
+    /// Only sent over connections using [`MessagingProtocolVersion::V2`].
+    ///
+    /// [`MessagingProtocolVersion::V2`]: crate::protocols::wire::handshake::v1::MessagingProtocolVersion::V2
+    RpcCancel(RpcCancel),
+    /// Only sent over connections using [`MessagingProtocolVersion::V2`].
+    ///
+    /// [`MessagingProtocolVersion::V2`]: crate::protocols::wire::handshake::v1::MessagingProtocolVersion::V2
+    RpcResponseChunk(RpcResponseChunk),
 }
 
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
@@ -52,6 +60,11 @@ pub enum ErrorCode {
     ParsingError(ParsingErrorType),
     /// A message was received for a protocol that is not supported over this connection.
     NotSupported(NotSupportedType),
+    /// An RpcRequest was dropped because the requesting peer exceeded its quota.
+    /// Only sent over connections using [`MessagingProtocolVersion::V2`].
+    ///
+    /// [`MessagingProtocolVersion::V2`]: crate::protocols::wire::handshake::v1::MessagingProtocolVersion::V2
+    QuotaExceeded(RequestId),
 }
 
 impl ErrorCode {
@@ -111,6 +124,33 @@ pub struct RpcResponse {
     pub raw_response: Vec<u8>,
 }
 
+/// Sent by the requester when it no longer wants the response to a pending
+/// request, e.g., because the caller went away or the request timed out. The
+/// responder stops waiting on the application handler and doesn't reply.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
+pub struct RpcCancel {
+    /// RequestId of the request being canceled.
+    pub request_id: RequestId,
+}
+
+/// A piece of a response that is too large for a single frame. The responder
+/// sends the chunks of a response in order on the connection's message stream
+/// and the requester reassembles them once it sees the `last` chunk.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
+pub struct RpcResponseChunk {
+    /// RequestId for corresponding request. This is copied as is from the RpcRequest.
+    pub request_id: RequestId,
+    /// Response priority in the range 0..=255.
+    pub priority: Priority,
+    /// Whether this is the final chunk of the response.
+    pub last: bool,
+    /// Response payload chunk.
+    #[serde(with = "serde_bytes")]
+    pub raw_chunk: Vec<u8>,
+}
+
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 #[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
 pub struct DirectSendMsg {
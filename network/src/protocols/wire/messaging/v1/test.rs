@@ -46,6 +46,35 @@ fn rpc_request() -> bcs::Result<()> {
     Ok(())
 }
 
+#[test]
+fn rpc_cancel_and_response_chunk() -> bcs::Result<()> {
+    let rpc_cancel = NetworkMessage::RpcCancel(RpcCancel { request_id: 25 });
+    assert_eq!(
+        bcs::to_bytes(&rpc_cancel)?,
+        // [4] -> network message type
+        // [25, 0, 0, 0] -> request_id
+        vec![4, 25, 0, 0, 0]
+    );
+
+    let rpc_response_chunk = NetworkMessage::RpcResponseChunk(RpcResponseChunk {
+        request_id: 25,
+        priority: 0,
+        last: true,
+        raw_chunk: [0, 1, 2, 3].to_vec(),
+    });
+    assert_eq!(
+        bcs::to_bytes(&rpc_response_chunk)?,
+        // [5] -> network message type
+        // [25, 0, 0, 0] -> request_id
+        // [0] -> priority
+        // [1] -> last
+        // [4] -> length of raw_chunk
+        // [0, 1, 2, 3] -> raw_chunk bytes
+        vec![5, 25, 0, 0, 0, 0, 1, 4, 0, 1, 2, 3]
+    );
+    Ok(())
+}
+
 #[test]
 fn libranet_wire_test_vectors() {
     let message = NetworkMessage::DirectSendMsg(DirectSendMsg {
@@ -13,6 +13,7 @@ use futures::{
     task::{Context, Poll},
 };
 use memsocket::MemorySocket;
+use netcore::transport::{NoSubstreams, SocketExt};
 use std::{io, pin::Pin};
 
 //
@@ -203,6 +204,10 @@ impl AsyncRead for ReadOnlyTestSocketVec {
     }
 }
 
+impl SocketExt for ReadOnlyTestSocketVec {
+    type Substreams = NoSubstreams;
+}
+
 //
 // ReadWriteTestSocket
 // ==================
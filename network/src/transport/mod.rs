@@ -9,6 +9,7 @@ use crate::{
         wire::handshake::v1::{HandshakeMsg, MessagingProtocolVersion, ProtocolIdSet},
     },
 };
+use bytes::BytesMut;
 use diem_config::{
     config::{PeerRole, HANDSHAKE_VERSION},
     network_id::{NetworkContext, NetworkId},
@@ -19,15 +20,21 @@ use diem_logger::prelude::*;
 use diem_time_service::{timeout, TimeService, TimeServiceTrait};
 use diem_types::{
     chain_id::ChainId,
-    network_address::{parse_dns_tcp, parse_ip_tcp, parse_memory, NetworkAddress},
+    network_address::{
+        parse_dns_tcp, parse_dns_udp_quic, parse_ip_tcp, parse_ip_udp_quic, parse_memory,
+        NetworkAddress,
+    },
     PeerId,
 };
 use futures::{
     future::{Future, FutureExt},
-    io::{AsyncRead, AsyncWrite},
+    io::{AsyncRead, AsyncWrite, AsyncWriteExt},
     stream::{Stream, StreamExt, TryStreamExt},
 };
-use netcore::transport::{proxy_protocol, tcp, ConnectionOrigin, Transport};
+use netcore::{
+    framing::{read_u16frame, write_u16frame},
+    transport::{proxy_protocol, tcp, ConnectionOrigin, SocketExt, Transport},
+};
 use serde::Serialize;
 use short_hex_str::AsShortHexStr;
 use std::{collections::BTreeMap, convert::TryFrom, fmt, io, pin::Pin, sync::Arc, time::Duration};
@@ -38,9 +45,10 @@ mod test;
 /// A timeout for the connection to open and complete all of the upgrade steps.
 pub const TRANSPORT_TIMEOUT: Duration = Duration::from_secs(30);
 
-/// Currently supported messaging protocol version.
-/// TODO: Add ability to support more than one messaging protocol.
-pub const SUPPORTED_MESSAGING_PROTOCOL: MessagingProtocolVersion = MessagingProtocolVersion::V1;
+/// Currently supported messaging protocol versions. The handshake picks the
+/// highest version both peers support, so older peers keep using V1.
+pub const SUPPORTED_MESSAGING_PROTOCOLS: [MessagingProtocolVersion; 2] =
+    [MessagingProtocolVersion::V1, MessagingProtocolVersion::V2];
 
 /// Global connection-id generator.
 static CONNECTION_ID_GENERATOR: ConnectionIdGenerator = ConnectionIdGenerator::new();
@@ -54,9 +62,15 @@ pub const DIEM_TCP_TRANSPORT: tcp::TcpTransport = tcp::TcpTransport {
 };
 
 /// A trait alias for "socket-like" things.
-pub trait TSocket: AsyncRead + AsyncWrite + Send + fmt::Debug + Unpin + 'static {}
+pub trait TSocket:
+    AsyncRead + AsyncWrite + SocketExt + Send + fmt::Debug + Unpin + 'static
+{
+}
 
-impl<T> TSocket for T where T: AsyncRead + AsyncWrite + Send + fmt::Debug + Unpin + 'static {}
+impl<T> TSocket for T where
+    T: AsyncRead + AsyncWrite + SocketExt + Send + fmt::Debug + Unpin + 'static
+{
+}
 
 /// Unique local identifier for a connection.
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize)]
@@ -225,6 +239,34 @@ fn add_pp_addr(proxy_protocol_enabled: bool, error: io::Error, addr: &NetworkAdd
     }
 }
 
+/// Exchange the channel binding of the base transport connection, if it has
+/// one, over the freshly authenticated Noise stream and check that both ends
+/// observed the same channel. This ties the QUIC session, whose TLS layer isn't
+/// authenticated, to the Noise IK identity of the remote peer.
+async fn verify_channel_binding<T: TSocket>(
+    socket: &mut NoiseStream<T>,
+    channel_binding: Option<Vec<u8>>,
+) -> io::Result<()> {
+    let channel_binding = match channel_binding {
+        Some(channel_binding) => channel_binding,
+        None => return Ok(()),
+    };
+
+    write_u16frame(socket, &channel_binding).await?;
+    socket.flush().await?;
+    let mut remote_channel_binding = BytesMut::new();
+    read_u16frame(socket, &mut remote_channel_binding).await?;
+
+    if remote_channel_binding.as_ref() != channel_binding.as_slice() {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "channel binding mismatch: the base transport connection is not \
+             bound to the authenticated peer",
+        ));
+    }
+    Ok(())
+}
+
 /// Upgrade an inbound connection. This means we run a Noise IK handshake for
 /// authentication and then negotiate common supported protocols. If
 /// `ctxt.trusted_peers` is `Some(_)`, then we will only allow connections from
@@ -256,6 +298,7 @@ async fn upgrade_inbound<T: TSocket>(
     } else {
         addr
     };
+    let channel_binding = socket.channel_binding();
 
     // try authenticating via noise handshake
     let (mut socket, remote_peer_id, peer_role) =
@@ -278,6 +321,10 @@ async fn upgrade_inbound<T: TSocket>(
     let remote_pubkey = socket.get_remote_static();
     let addr = addr.append_prod_protos(remote_pubkey, HANDSHAKE_VERSION);
 
+    verify_channel_binding(&mut socket, channel_binding)
+        .await
+        .map_err(|err| add_pp_addr(proxy_protocol_enabled, err, &addr))?;
+
     // exchange HandshakeMsg
     let handshake_msg = HandshakeMsg {
         supported_protocols: ctxt.supported_protocols.clone(),
@@ -330,6 +377,7 @@ pub async fn upgrade_outbound<T: TSocket>(
 ) -> io::Result<Connection<NoiseStream<T>>> {
     let origin = ConnectionOrigin::Outbound;
     let socket = fut_socket.await?;
+    let channel_binding = socket.channel_binding();
 
     // noise handshake
     let mut socket = ctxt
@@ -355,6 +403,8 @@ pub async fn upgrade_outbound<T: TSocket>(
     // sanity check: Noise IK should always guarantee this is true
     debug_assert_eq!(remote_pubkey, socket.get_remote_static());
 
+    verify_channel_binding(&mut socket, channel_binding).await?;
+
     // exchange HandshakeMsg
     let handshake_msg = HandshakeMsg {
         supported_protocols: ctxt.supported_protocols.clone(),
@@ -393,7 +443,7 @@ pub async fn upgrade_outbound<T: TSocket>(
 ///
 /// The base transport layer is pluggable, so long as it provides a reliable,
 /// ordered, connection-oriented, byte-stream abstraction (e.g., TCP). We currently
-/// use either `MemoryTransport` or `TcpTransport` as this base layer.
+/// use either `MemoryTransport`, `TcpTransport` or `QuicTransport` as this base layer.
 ///
 /// Inbound and outbound connections are first established with the `base_transport`
 /// and then negotiate a secure, authenticated transport layer (currently Noise
@@ -429,8 +479,10 @@ where
         enable_proxy_protocol: bool,
     ) -> Self {
         // build supported protocols
-        let mut supported_protocols = BTreeMap::new();
-        supported_protocols.insert(SUPPORTED_MESSAGING_PROTOCOL, application_protocols);
+        let supported_protocols = SUPPORTED_MESSAGING_PROTOCOLS
+            .iter()
+            .map(|version| (*version, application_protocols.clone()))
+            .collect();
 
         let identity_pubkey = identity_key.public_key();
 
@@ -465,13 +517,15 @@ where
         let (base_transport_protos, base_transport_suffix) = parse_ip_tcp(protos)
             .map(|x| (&protos[..2], x.1))
             .or_else(|| parse_dns_tcp(protos).map(|x| (&protos[..2], x.1)))
+            .or_else(|| parse_ip_udp_quic(protos).map(|x| (&protos[..3], x.1)))
+            .or_else(|| parse_dns_udp_quic(protos).map(|x| (&protos[..3], x.1)))
             .or_else(|| parse_memory(protos).map(|x| (&protos[..1], x.1)))
             .ok_or_else(|| {
                 io::Error::new(
                     io::ErrorKind::InvalidInput,
                     format!(
                         "Unexpected dialing network address: '{}', expected: \
-                         memory, ip+tcp, or dns+tcp",
+                         memory, ip+tcp, dns+tcp, ip+udp+quic, or dns+udp+quic",
                         addr
                     ),
                 )
@@ -517,6 +571,9 @@ where
     /// `/dns/<ipaddr>/tcp/<port>` or
     /// `/dns4/<ipaddr>/tcp/<port>` or
     /// `/dns6/<ipaddr>/tcp/<port>`
+    ///
+    /// If the base transport is `QuicTransport`, then `/<base_transport>` is one
+    /// of the addresses above with `/tcp/<port>` replaced by `/udp/<port>/quic`.
     pub fn dial(
         &self,
         peer_id: PeerId,
@@ -563,6 +620,11 @@ where
     ///
     /// `/ip4/<ipaddr>/tcp/<port>` or
     /// `/ip6/<ipaddr>/tcp/<port>`
+    ///
+    /// If the base transport is `QuicTransport`, then we expect:
+    ///
+    /// `/ip4/<ipaddr>/udp/<port>/quic` or
+    /// `/ip6/<ipaddr>/udp/<port>/quic`
     pub fn listen_on(
         &self,
         addr: NetworkAddress,
@@ -21,7 +21,7 @@ use diem_types::{
 use futures::{future, io::AsyncWriteExt, stream::StreamExt};
 use netcore::{
     framing::{read_u16frame, write_u16frame},
-    transport::{memory, ConnectionOrigin, Transport},
+    transport::{memory, quic::QuicTransport, ConnectionOrigin, Transport},
 };
 use rand::{rngs::StdRng, SeedableRng};
 use std::{collections::HashMap, io, iter::FromIterator, sync::Arc};
@@ -199,6 +199,19 @@ fn expect_memory_noise_addr(addr: &NetworkAddress) {
     );
 }
 
+/// Check that the network address matches the format
+/// `"/ip4/<ipaddr>/udp/<port>/quic/ln-noise-ik/<pubkey>/ln-handshake/<version>"`
+fn expect_ip4_quic_noise_addr(addr: &NetworkAddress) {
+    assert!(
+        matches!(
+            addr.as_slice(),
+            [Ip4(_), Udp(_), Quic, NoiseIK(_), Handshake(_)]
+        ),
+        "addr: '{}'",
+        addr
+    );
+}
+
 /// Check that the network address matches the format
 /// `"/ip4/<ipaddr>/tcp/<port>/ln-noise-ik/<pubkey>/ln-handshake/<version>"`
 fn expect_ip4_tcp_noise_addr(addr: &NetworkAddress) {
@@ -251,7 +264,7 @@ fn test_transport_success<TTransport>(
         assert_eq!(conn.metadata.origin, ConnectionOrigin::Inbound);
         assert_eq!(
             conn.metadata.messaging_protocol,
-            MessagingProtocolVersion::V1
+            MessagingProtocolVersion::V2
         );
         assert_eq!(
             conn.metadata.application_protocols,
@@ -280,7 +293,7 @@ fn test_transport_success<TTransport>(
         assert_eq!(conn.metadata.origin, ConnectionOrigin::Outbound);
         assert_eq!(
             conn.metadata.messaging_protocol,
-            MessagingProtocolVersion::V1
+            MessagingProtocolVersion::V2
         );
         assert_eq!(conn.metadata.application_protocols, supported_protocols);
 
@@ -388,7 +401,7 @@ fn test_transport_maybe_mutual<TTransport>(
         assert_eq!(conn.metadata.origin, ConnectionOrigin::Inbound);
         assert_eq!(
             conn.metadata.messaging_protocol,
-            MessagingProtocolVersion::V1
+            MessagingProtocolVersion::V2
         );
         assert_eq!(
             conn.metadata.application_protocols,
@@ -422,7 +435,7 @@ fn test_transport_maybe_mutual<TTransport>(
         assert_eq!(conn.metadata.origin, ConnectionOrigin::Inbound);
         assert_eq!(
             conn.metadata.messaging_protocol,
-            MessagingProtocolVersion::V1
+            MessagingProtocolVersion::V2
         );
         assert_eq!(
             conn.metadata.application_protocols,
@@ -452,7 +465,7 @@ fn test_transport_maybe_mutual<TTransport>(
         assert_eq!(conn.metadata.origin, ConnectionOrigin::Outbound);
         assert_eq!(
             conn.metadata.messaging_protocol,
-            MessagingProtocolVersion::V1
+            MessagingProtocolVersion::V2
         );
         assert_eq!(conn.metadata.application_protocols, supported_protocols);
 
@@ -476,7 +489,7 @@ fn test_transport_maybe_mutual<TTransport>(
         assert_eq!(conn.metadata.origin, ConnectionOrigin::Outbound);
         assert_eq!(
             conn.metadata.messaging_protocol,
-            MessagingProtocolVersion::V1
+            MessagingProtocolVersion::V2
         );
         assert_eq!(conn.metadata.application_protocols, supported_protocols);
 
@@ -563,3 +576,36 @@ fn test_tcp_transport_rejects_unauthed_dialer() {
         expect_ip4_tcp_noise_addr,
     );
 }
+
+//////////////////////////////////////
+// DiemNetTransport<QuicTransport> //
+//////////////////////////////////////
+
+#[test]
+fn test_quic_transport_mutual_auth() {
+    test_transport_success(
+        QuicTransport::default(),
+        Auth::Mutual,
+        "/ip4/127.0.0.1/udp/0/quic",
+        expect_ip4_quic_noise_addr,
+    );
+}
+
+#[test]
+fn test_quic_transport_server_only_auth() {
+    test_transport_success(
+        QuicTransport::default(),
+        Auth::ServerOnly,
+        "/ip4/127.0.0.1/udp/0/quic",
+        expect_ip4_quic_noise_addr,
+    );
+}
+
+#[test]
+fn test_quic_transport_rejects_unauthed_dialer() {
+    test_transport_rejects_unauthed_dialer(
+        QuicTransport::default(),
+        "/ip4/127.0.0.1/udp/0/quic",
+        expect_ip4_quic_noise_addr,
+    );
+}
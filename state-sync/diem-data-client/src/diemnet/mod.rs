@@ -2,14 +2,14 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    AdvertisedData, DiemDataClient, Error, GlobalDataSummary, OptimalChunkSizes, Response,
+    metrics, AdvertisedData, DiemDataClient, Error, GlobalDataSummary, OptimalChunkSizes, Response,
     ResponseError, Result,
 };
 use async_trait::async_trait;
 use diem_config::network_id::PeerNetworkId;
 use diem_id_generator::{IdGenerator, U64IdGenerator};
 use diem_infallible::RwLock;
-use diem_logger::trace;
+use diem_logger::{debug, trace, warn};
 use diem_time_service::{TimeService, TimeServiceTrait};
 use diem_types::{
     account_state_blob::AccountStatesChunkWithProof,
@@ -21,15 +21,18 @@ use diem_types::{
     },
 };
 use futures::StreamExt;
-use network::{
-    application::interface::NetworkInterface,
-    protocols::{rpc::error::RpcError, wire::handshake::v1::ProtocolId},
-};
+use network::{application::interface::NetworkInterface, protocols::rpc::error::RpcError};
 use rand::seq::SliceRandom;
-use std::{collections::HashMap, convert::TryFrom, sync::Arc, time::Duration};
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::TryFrom,
+    sync::Arc,
+    time::Duration,
+};
 use storage_service_client::StorageServiceClient;
 use storage_service_types::{
-    AccountStatesChunkWithProofRequest, Epoch, EpochEndingLedgerInfoRequest, StorageServerSummary,
+    AccountStatesChunkWithProofRequest, Epoch, EpochEndingLedgerInfoRequest,
+    NewTransactionOutputsWithProofRequest, StorageServerSummary, StorageServiceError,
     StorageServiceRequest, StorageServiceResponse, TransactionOutputsWithProofRequest,
     TransactionsWithProofRequest,
 };
@@ -44,6 +47,46 @@ mod tests;
 const DEFAULT_TIMEOUT: Duration = Duration::from_millis(10_000);
 pub const DATA_SUMMARY_POLL_INTERVAL: Duration = Duration::from_millis(100);
 
+/// Scores for peer rankings based on preferences and behavior.
+const MAX_SCORE: f64 = 100.0;
+const MIN_SCORE: f64 = 0.0;
+const STARTING_SCORE: f64 = 50.0;
+/// Add this score on a successful response.
+const SUCCESSFUL_RESPONSE_DELTA: f64 = 1.0;
+/// Not necessarily a malicious response, but not super useful.
+const NOT_USEFUL_MULTIPLIER: f64 = 0.95;
+/// Likely to be a malicious response.
+const MALICIOUS_MULTIPLIER: f64 = 0.8;
+/// Ignore a peer when their score dips below this threshold.
+const IGNORE_PEER_THRESHOLD: f64 = 25.0;
+
+/// The maximum number of recent response ids (and the peers that sent them)
+/// to remember, so that bad responses can be attributed to peers.
+const MAX_TRACKED_RESPONSE_IDS: usize = 10_000;
+
+/// The type of a bad response, used to determine the score penalty for the
+/// peer responsible.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum ErrorType {
+    /// A response or error that's not actively malicious but also doesn't help
+    /// us make progress, e.g., timeouts, remote errors, invalid data, etc...
+    NotUseful,
+    /// A response or error that appears to be actively hindering progress or
+    /// attempting to deceive us, e.g., invalid proof.
+    Malicious,
+}
+
+impl From<&ResponseError> for ErrorType {
+    fn from(error: &ResponseError) -> Self {
+        match error {
+            ResponseError::InvalidPayloadDataType | ResponseError::ProofVerificationError => {
+                ErrorType::Malicious
+            }
+            ResponseError::MissingData => ErrorType::NotUseful,
+        }
+    }
+}
+
 /// A [`DiemDataClient`] that fulfills requests from remote peers' Storage Service
 /// over DiemNet.
 ///
@@ -101,12 +144,20 @@ impl DiemNetDataClient {
 
     /// Recompute and update the global data summary cache.
     fn update_global_summary_cache(&self) {
-        let aggregate = self.peer_states.read().aggregate_summary();
+        let (aggregate, num_ignored_peers) = {
+            let peer_states = self.peer_states.read();
+            (
+                peer_states.aggregate_summary(),
+                peer_states.num_ignored_peers(),
+            )
+        };
         *self.global_summary_cache.write() = aggregate;
+        metrics::IGNORED_PEERS.set(num_ignored_peers as i64);
     }
 
-    /// Choose a connected peer that can service the given request. Returns an
-    /// error if no such peer can be found.
+    /// Choose a connected peer that can service the given request. Peers with
+    /// low scores are ignored, and the remaining peers are chosen at random,
+    /// weighted by their scores. Returns an error if no such peer can be found.
     fn choose_peer(&self, request: &StorageServiceRequest) -> Result<PeerNetworkId, Error> {
         let all_connected = {
             let network_peer_metadata = self.network_client.peer_metadata_storage();
@@ -116,7 +167,9 @@ impl DiemNetDataClient {
                     network_peer_metadata
                         .read_filtered(network_id, |(_, peer_metadata)| {
                             peer_metadata.is_connected()
-                                && peer_metadata.supports_protocol(ProtocolId::StorageServiceRpc)
+                                && storage_service_client::RPC
+                                    .iter()
+                                    .any(|protocol| peer_metadata.supports_protocol(*protocol))
                         })
                         .into_keys()
                 })
@@ -135,15 +188,22 @@ impl DiemNetDataClient {
             .filter(|peer| internal_peer_states.can_service_request(peer, request))
             .collect::<Vec<_>>();
 
-        all_serviceable
-            .choose(&mut rand::thread_rng())
-            .copied()
-            .ok_or_else(|| {
-                Error::DataIsUnavailable(
-                    "no connected peers are advertising that they can serve this data range"
-                        .to_owned(),
-                )
-            })
+        // data summary requests are sent uniformly at random, so that all peers
+        // (including ignored ones) can keep advertising their data.
+        let mut rng = rand::thread_rng();
+        let chosen_peer = if request.is_get_storage_server_summary() {
+            all_serviceable.choose(&mut rng)
+        } else {
+            all_serviceable
+                .choose_weighted(&mut rng, |peer| internal_peer_states.get_score(peer))
+                .ok()
+        };
+
+        chosen_peer.copied().ok_or_else(|| {
+            Error::DataIsUnavailable(
+                "no connected peers are advertising that they can serve this data range".to_owned(),
+            )
+        })
     }
 
     async fn send_request_and_decode<T, E>(
@@ -188,24 +248,60 @@ impl DiemNetDataClient {
         request: StorageServiceRequest,
     ) -> Result<Response<StorageServiceResponse>, Error> {
         let response_id = self.next_response_id();
+        let is_data_subscription_request = request.is_data_subscription_request();
         let result = self
             .network_client
             .send_request(peer, request, DEFAULT_TIMEOUT)
             .await;
         let storage_response = match result {
-            Ok(response) => Ok(response),
+            Ok(response) => {
+                metrics::increment_response_counter(metrics::SUCCESS_LABEL);
+                let mut peer_states = self.peer_states.write();
+                peer_states.update_score_success(peer);
+                peer_states.track_response(response_id, peer);
+                Ok(response)
+            }
             Err(storage_service_client::Error::RpcError(err)) => match err {
                 RpcError::NotConnected(_) => Err(Error::DataIsUnavailable(err.to_string())),
-                RpcError::TimedOut => Err(Error::TimeoutWaitingForResponse(err.to_string())),
-                _ => Err(Error::UnexpectedErrorEncountered(err.to_string())),
+                RpcError::TimedOut => {
+                    // Subscriptions are expected to time out if no new data
+                    // is committed, so the peer isn't penalized.
+                    if is_data_subscription_request {
+                        metrics::increment_response_counter(metrics::TIMEOUT_LABEL);
+                    } else {
+                        self.notify_bad_peer(peer, ErrorType::NotUseful, metrics::TIMEOUT_LABEL);
+                    }
+                    Err(Error::TimeoutWaitingForResponse(err.to_string()))
+                }
+                _ => {
+                    self.notify_bad_peer(peer, ErrorType::NotUseful, metrics::RPC_ERROR_LABEL);
+                    Err(Error::UnexpectedErrorEncountered(err.to_string()))
+                }
             },
+            Err(storage_service_client::Error::StorageServiceError(
+                err @ (StorageServiceError::SubscriptionExpired
+                | StorageServiceError::SubscriptionSuperseded),
+            )) if is_data_subscription_request => {
+                // The peer had no new data before the subscription expired
+                // (or before it was replaced by a newer subscription).
+                metrics::increment_response_counter(metrics::TIMEOUT_LABEL);
+                Err(Error::TimeoutWaitingForResponse(err.to_string()))
+            }
             Err(storage_service_client::Error::StorageServiceError(err)) => {
+                self.notify_bad_peer(peer, ErrorType::NotUseful, metrics::RPC_ERROR_LABEL);
                 Err(Error::UnexpectedErrorEncountered(err.to_string()))
             }
         }?;
-        // TODO(philiphayes): update peer scores on error
         Ok(Response::new(response_id, storage_response))
     }
+
+    /// Penalizes the given peer for a bad response (or failed request)
+    fn notify_bad_peer(&self, peer: PeerNetworkId, error_type: ErrorType, label: &str) {
+        metrics::increment_response_counter(label);
+        self.peer_states
+            .write()
+            .update_score_error(peer, error_type);
+    }
 }
 
 /// Calculate `(start..=end).len()`. Returns an error if `end < start` or
@@ -227,8 +323,31 @@ impl DiemDataClient for DiemNetDataClient {
         self.global_summary_cache.read().clone()
     }
 
-    fn notify_bad_response(&self, _response_id: u64, _response_error: ResponseError) {
-        todo!()
+    fn notify_bad_response(&self, response_id: u64, response_error: ResponseError) {
+        let label = match response_error {
+            ResponseError::InvalidPayloadDataType => metrics::INVALID_PAYLOAD_DATA_TYPE_LABEL,
+            ResponseError::MissingData => metrics::MISSING_DATA_LABEL,
+            ResponseError::ProofVerificationError => metrics::PROOF_VERIFICATION_ERROR_LABEL,
+        };
+        metrics::increment_response_counter(label);
+
+        let error_type = ErrorType::from(&response_error);
+        let mut peer_states = self.peer_states.write();
+        match peer_states.get_peer_for_response(response_id) {
+            Some(peer) => {
+                debug!(
+                    "Bad response reported for peer: {}, response id: {}, error: {:?}",
+                    peer, response_id, response_error
+                );
+                peer_states.update_score_error(peer, error_type);
+            }
+            None => {
+                warn!(
+                    "Bad response reported for an unknown response id: {}, error: {:?}",
+                    response_id, response_error
+                );
+            }
+        }
     }
 
     async fn get_account_states_with_proof(
@@ -261,6 +380,20 @@ impl DiemDataClient for DiemNetDataClient {
         Ok(response.map(|epoch_change| epoch_change.ledger_info_with_sigs))
     }
 
+    async fn get_new_transaction_outputs_with_proof(
+        &self,
+        known_version: Version,
+        known_epoch: Epoch,
+    ) -> Result<Response<(TransactionOutputListWithProof, LedgerInfoWithSignatures)>> {
+        let request = StorageServiceRequest::GetNewTransactionOutputsWithProof(
+            NewTransactionOutputsWithProofRequest {
+                known_version,
+                known_epoch,
+            },
+        );
+        self.send_request_and_decode(request).await
+    }
+
     async fn get_number_of_account_states(&self, version: Version) -> Result<Response<u64>> {
         let request = StorageServiceRequest::GetNumberOfAccountsAtVersion(version);
         self.send_request_and_decode(request).await
@@ -376,11 +509,40 @@ impl DataSummaryPoller {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 struct PeerState {
+    /// The latest data summary advertised by the peer.
     storage_summary: Option<StorageServerSummary>,
-    // TODO(philiphayes): imagine storing some scoring info here.
-    metadata: (),
+    /// The score of the peer, based on its observed quality of service and
+    /// reports of bad responses. Peers with low scores are ignored.
+    score: f64,
+}
+
+impl Default for PeerState {
+    fn default() -> Self {
+        Self {
+            storage_summary: None,
+            score: STARTING_SCORE,
+        }
+    }
+}
+
+impl PeerState {
+    fn is_ignored(&self) -> bool {
+        self.score <= IGNORE_PEER_THRESHOLD
+    }
+
+    fn update_score_success(&mut self) {
+        self.score = f64::min(self.score + SUCCESSFUL_RESPONSE_DELTA, MAX_SCORE);
+    }
+
+    fn update_score_error(&mut self, error_type: ErrorType) {
+        let multiplier = match error_type {
+            ErrorType::NotUseful => NOT_USEFUL_MULTIPLIER,
+            ErrorType::Malicious => MALICIOUS_MULTIPLIER,
+        };
+        self.score = f64::max(self.score * multiplier, MIN_SCORE);
+    }
 }
 
 /// Contains all of the unbanned peers' most recent [`StorageServerSummary`] data
@@ -388,27 +550,80 @@ struct PeerState {
 #[derive(Debug)]
 struct PeerStates {
     inner: HashMap<PeerNetworkId, PeerState>,
+    /// The peers that sent the most recent responses, keyed by response id.
+    /// Response ids are monotonically increasing, so the oldest entries are
+    /// evicted first.
+    response_id_to_peer: BTreeMap<u64, PeerNetworkId>,
 }
 
 impl PeerStates {
     fn new() -> Self {
         Self {
             inner: HashMap::new(),
+            response_id_to_peer: BTreeMap::new(),
         }
     }
 
+    /// Returns the score of the given peer
+    fn get_score(&self, peer: &PeerNetworkId) -> f64 {
+        self.inner
+            .get(peer)
+            .map(|peer_state| peer_state.score)
+            .unwrap_or(STARTING_SCORE)
+    }
+
+    /// Returns the number of peers that are currently ignored
+    fn num_ignored_peers(&self) -> usize {
+        self.inner
+            .values()
+            .filter(|peer_state| peer_state.is_ignored())
+            .count()
+    }
+
+    /// Remembers the peer that sent the response with the given id
+    fn track_response(&mut self, response_id: u64, peer: PeerNetworkId) {
+        self.response_id_to_peer.insert(response_id, peer);
+        while self.response_id_to_peer.len() > MAX_TRACKED_RESPONSE_IDS {
+            let oldest_response_id = *self
+                .response_id_to_peer
+                .keys()
+                .next()
+                .expect("The response map should not be empty!");
+            self.response_id_to_peer.remove(&oldest_response_id);
+        }
+    }
+
+    /// Returns the peer that sent the response with the given id (if known)
+    fn get_peer_for_response(&self, response_id: u64) -> Option<PeerNetworkId> {
+        self.response_id_to_peer.get(&response_id).copied()
+    }
+
+    fn update_score_success(&mut self, peer: PeerNetworkId) {
+        let peer_state = self.inner.entry(peer).or_default();
+        peer_state.update_score_success();
+        metrics::set_peer_score(&peer.to_string(), peer_state.score);
+    }
+
+    fn update_score_error(&mut self, peer: PeerNetworkId, error_type: ErrorType) {
+        let peer_state = self.inner.entry(peer).or_default();
+        peer_state.update_score_error(error_type);
+        metrics::set_peer_score(&peer.to_string(), peer_state.score);
+    }
+
     /// Returns true if a connected storage service peer can actually fulfill a
     /// request, given our current view of their advertised data summary.
     fn can_service_request(&self, peer: &PeerNetworkId, request: &StorageServiceRequest) -> bool {
         // Storage services can always respond to data advertisement requests.
         // We need this outer check, since we need to be able to send data summary
-        // requests to new peers (who don't have a peer state yet).
+        // requests to new peers (who don't have a peer state yet). This also
+        // allows ignored peers to slowly recover their scores.
         if request.is_get_storage_server_summary() {
             return true;
         }
 
         self.inner
             .get(peer)
+            .filter(|peer_state| !peer_state.is_ignored())
             .and_then(|peer_state| peer_state.storage_summary.as_ref())
             .map(|summary| summary.can_service(request))
             .unwrap_or(false)
@@ -426,9 +641,11 @@ impl PeerStates {
         let mut max_transaction_output_chunk_sizes = vec![];
         let mut max_account_states_chunk_sizes = vec![];
 
+        // ignored peers are excluded, as we won't send them data requests
         let summaries = self
             .inner
             .values()
+            .filter(|state| !state.is_ignored())
             .filter_map(|state| state.storage_summary.as_ref());
 
         // collect each peer's protocol and data advertisements
@@ -3,16 +3,21 @@
 
 use super::{
     DataSummaryPoller, DiemDataClient, DiemNetDataClient, Error, DATA_SUMMARY_POLL_INTERVAL,
+    DEFAULT_TIMEOUT, IGNORE_PEER_THRESHOLD,
 };
+use crate::ResponseError;
 use channel::{diem_channel, message_queues::QueueStyle};
 use claim::assert_matches;
-use diem_config::network_id::{NetworkId, PeerNetworkId};
+use diem_config::{
+    config::StorageServiceConfig,
+    network_id::{NetworkId, PeerNetworkId},
+};
 use diem_time_service::{MockTimeService, TimeService};
 use diem_types::{transaction::TransactionListWithProof, PeerId};
 use futures::StreamExt;
 use maplit::hashmap;
 use network::{
-    application::{interface::MultiNetworkSender, storage::PeerMetadataStorage},
+    application::storage::PeerMetadataStorage,
     peer_manager::{ConnectionRequestSender, PeerManagerRequest, PeerManagerRequestSender},
     protocols::{network::NewNetworkSender, wire::handshake::v1::ProtocolId},
     transport::ConnectionMetadata,
@@ -25,6 +30,25 @@ use storage_service_types::{
     StorageServiceRequest, StorageServiceResponse, TransactionsWithProofRequest,
 };
 
+/// Creates a storage server summary advertising transactions up to `highest_version`
+fn mock_storage_summary(highest_version: u64) -> StorageServerSummary {
+    StorageServerSummary {
+        protocol_metadata: ProtocolMetadata {
+            max_epoch_chunk_size: 1000,
+            max_transaction_chunk_size: 1000,
+            max_transaction_output_chunk_size: 1000,
+            max_account_states_chunk_size: 1000,
+        },
+        data_summary: DataSummary {
+            synced_ledger_info: None,
+            epoch_ending_ledger_infos: None,
+            transactions: Some(CompleteDataRange::from_genesis(highest_version)),
+            transaction_outputs: None,
+            account_states: None,
+        },
+    }
+}
+
 struct MockNetwork {
     peer_mgr_reqs_rx: diem_channel::Receiver<(PeerId, ProtocolId), PeerManagerRequest>,
     peer_infos: Arc<PeerMetadataStorage>,
@@ -36,15 +60,15 @@ impl MockNetwork {
         let (peer_mgr_reqs_tx, peer_mgr_reqs_rx) = queue_cfg.build();
         let (connection_reqs_tx, _connection_reqs_rx) = queue_cfg.build();
 
-        let network_sender = MultiNetworkSender::new(hashmap! {
+        let network_senders = hashmap! {
             NetworkId::Validator => StorageServiceNetworkSender::new(
                 PeerManagerRequestSender::new(peer_mgr_reqs_tx),
                 ConnectionRequestSender::new(connection_reqs_tx),
             )
-        });
+        };
 
         let peer_infos = PeerMetadataStorage::new(&[NetworkId::Validator]);
-        let network_client = StorageServiceClient::new(network_sender, peer_infos.clone());
+        let network_client = StorageServiceClient::new(network_senders, peer_infos.clone());
 
         let mock_time = TimeService::mock();
         let (client, poller) = DiemNetDataClient::new(mock_time.clone(), network_client);
@@ -58,12 +82,17 @@ impl MockNetwork {
 
     /// Add a new random connected peer to the network peer DB
     fn add_connected_peer(&mut self) -> PeerNetworkId {
+        self.add_connected_peer_with_protocols(&[ProtocolId::StorageServiceRpc])
+    }
+
+    /// Add a new random connected peer supporting `protocols` to the network peer DB
+    fn add_connected_peer_with_protocols(&mut self, protocols: &[ProtocolId]) -> PeerNetworkId {
         let network_id = NetworkId::Validator;
         let peer_id = PeerId::random();
         let mut connection_metadata = ConnectionMetadata::mock(peer_id);
-        connection_metadata
-            .application_protocols
-            .insert(ProtocolId::StorageServiceRpc);
+        for protocol in protocols {
+            connection_metadata.application_protocols.insert(*protocol);
+        }
 
         self.peer_infos
             .insert_connection(network_id, connection_metadata);
@@ -78,12 +107,12 @@ impl MockNetwork {
                 let data = network_request.data;
                 let res_tx = network_request.res_tx;
 
-                let message: StorageServiceMessage = bcs::from_bytes(data.as_ref()).unwrap();
+                let message: StorageServiceMessage = protocol.from_bytes(data.as_ref()).unwrap();
                 let request = match message {
                     StorageServiceMessage::Request(request) => request,
                     _ => panic!("unexpected: {:?}", message),
                 };
-                let response_sender = ResponseSender::new(res_tx);
+                let response_sender = ResponseSender::new(protocol, res_tx);
 
                 Some((peer_id, protocol, request, response_sender))
             }
@@ -178,3 +207,139 @@ async fn test_request_works_only_when_data_available() {
 
     assert_eq!(response.payload, TransactionListWithProof::new_empty(),);
 }
+
+#[tokio::test]
+async fn test_compressed_protocol_is_preferred() {
+    ::diem_logger::Logger::init_for_testing();
+    let (mut mock_network, mock_time, client, poller) = MockNetwork::new();
+
+    tokio::spawn(poller.start());
+
+    // add a connected peer supporting both the compressed and the plain protocol
+    let expected_peer = mock_network.add_connected_peer_with_protocols(&[
+        ProtocolId::StorageServiceRpc,
+        ProtocolId::StorageServiceRpcCompressed,
+    ]);
+
+    // advance time so the poller sends a data summary request
+    tokio::task::yield_now().await;
+    mock_time.advance_async(DATA_SUMMARY_POLL_INTERVAL).await;
+
+    // the request should be sent with the compressed protocol
+    let (peer, protocol, request, response_sender) = mock_network.next_request().await.unwrap();
+    assert_eq!(peer, expected_peer.peer_id());
+    assert_eq!(protocol, ProtocolId::StorageServiceRpcCompressed);
+    assert_matches!(request, StorageServiceRequest::GetStorageServerSummary);
+
+    // the response is decoded with the same protocol
+    response_sender.send(Ok(StorageServiceResponse::StorageServerSummary(
+        mock_storage_summary(200),
+    )));
+    tokio::task::yield_now().await;
+    client.update_global_summary_cache();
+    let advertised_transactions = client
+        .get_global_data_summary()
+        .advertised_data
+        .transactions;
+    assert_eq!(
+        advertised_transactions,
+        vec![CompleteDataRange::from_genesis(200)]
+    );
+}
+
+#[tokio::test]
+async fn test_bad_peer_is_ignored() {
+    ::diem_logger::Logger::init_for_testing();
+    let (mut mock_network, _, client, _) = MockNetwork::new();
+
+    // add two connected peers that advertise the same data
+    let good_peer = mock_network.add_connected_peer();
+    let bad_peer = mock_network.add_connected_peer();
+    client.update_summary(good_peer, mock_storage_summary(200));
+    client.update_summary(bad_peer, mock_storage_summary(200));
+
+    // both peers can initially service the request
+    let request = StorageServiceRequest::GetTransactionsWithProof(TransactionsWithProofRequest {
+        proof_version: 100,
+        start_version: 50,
+        expected_num_transactions: 51,
+        include_events: false,
+    });
+    let peer_states = client.peer_states.read();
+    assert!(peer_states.can_service_request(&good_peer, &request));
+    assert!(peer_states.can_service_request(&bad_peer, &request));
+    drop(peer_states);
+
+    // report bad responses from the bad peer until it's ignored
+    while client.peer_states.read().get_score(&bad_peer) > IGNORE_PEER_THRESHOLD {
+        let response_id = client.next_response_id();
+        client
+            .peer_states
+            .write()
+            .track_response(response_id, bad_peer);
+        client.notify_bad_response(response_id, ResponseError::ProofVerificationError);
+    }
+
+    // the bad peer should no longer be chosen to service requests
+    for _ in 0..20 {
+        assert_eq!(client.choose_peer(&request).unwrap(), good_peer);
+    }
+
+    // but it can still be polled for its storage summary
+    let peer_states = client.peer_states.read();
+    assert!(
+        peer_states.can_service_request(&bad_peer, &StorageServiceRequest::GetStorageServerSummary)
+    );
+    assert_eq!(peer_states.num_ignored_peers(), 1);
+}
+
+#[tokio::test]
+async fn test_ignored_peers_excluded_from_global_summary() {
+    ::diem_logger::Logger::init_for_testing();
+    let (mut mock_network, _, client, _) = MockNetwork::new();
+
+    // add two connected peers, where the bad peer advertises more data
+    let good_peer = mock_network.add_connected_peer();
+    let bad_peer = mock_network.add_connected_peer();
+    client.update_summary(good_peer, mock_storage_summary(100));
+    client.update_summary(bad_peer, mock_storage_summary(200));
+    client.update_global_summary_cache();
+    let advertised_transactions = client
+        .get_global_data_summary()
+        .advertised_data
+        .transactions;
+    assert_eq!(advertised_transactions.len(), 2);
+
+    // report bad responses from the bad peer until it's ignored
+    while client.peer_states.read().get_score(&bad_peer) > IGNORE_PEER_THRESHOLD {
+        let response_id = client.next_response_id();
+        client
+            .peer_states
+            .write()
+            .track_response(response_id, bad_peer);
+        client.notify_bad_response(response_id, ResponseError::InvalidPayloadDataType);
+    }
+
+    // the global summary should only contain the good peer's data
+    client.update_global_summary_cache();
+    let advertised_transactions = client
+        .get_global_data_summary()
+        .advertised_data
+        .transactions;
+    assert_eq!(
+        advertised_transactions,
+        vec![CompleteDataRange::from_genesis(100)]
+    );
+
+    // responses from unknown ids are ignored
+    client.notify_bad_response(u64::MAX, ResponseError::MissingData);
+    assert!(client.peer_states.read().get_score(&good_peer) > IGNORE_PEER_THRESHOLD);
+}
+
+#[test]
+fn test_subscription_period_below_request_timeout() {
+    // The storage server must expire data subscriptions (and respond) before
+    // the request times out, otherwise the explicit expiry is never received.
+    let max_subscription_period_ms = StorageServiceConfig::default().max_subscription_period_ms;
+    assert!(max_subscription_period_ms < DEFAULT_TIMEOUT.as_millis() as u64);
+}
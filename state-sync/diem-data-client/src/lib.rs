@@ -18,6 +18,7 @@ use storage_service_types::{self as storage_service, CompleteDataRange, Epoch};
 use thiserror::Error;
 
 pub mod diemnet;
+mod metrics;
 
 pub type Result<T, E = Error> = ::std::result::Result<T, E>;
 
@@ -102,6 +103,18 @@ pub trait DiemDataClient {
         expected_end_epoch: Epoch,
     ) -> Result<Response<Vec<LedgerInfoWithSignatures>>>;
 
+    /// Returns a transaction output list with proof object, with transaction
+    /// outputs from `known_version + 1` up to (at most) the version of the
+    /// returned ledger info. The proof is relative to the returned ledger info.
+    /// The request is a subscription: it is only answered once new data is
+    /// available (or the request times out). If `known_epoch` has ended, the
+    /// returned ledger info is the epoch ending ledger info of `known_epoch`.
+    async fn get_new_transaction_outputs_with_proof(
+        &self,
+        known_version: Version,
+        known_epoch: Epoch,
+    ) -> Result<Response<(TransactionOutputListWithProof, LedgerInfoWithSignatures)>>;
+
     /// Returns the number of account states at the specified version.
     async fn get_number_of_account_states(&self, version: Version) -> Result<Response<u64>>;
 
@@ -181,6 +194,7 @@ impl<T> Response<T> {
 pub enum ResponsePayload {
     AccountStatesWithProof(AccountStatesChunkWithProof),
     EpochEndingLedgerInfos(Vec<LedgerInfoWithSignatures>),
+    NewTransactionOutputsWithProof((TransactionOutputListWithProof, LedgerInfoWithSignatures)),
     NumberOfAccountStates(u64),
     TransactionOutputsWithProof(TransactionOutputListWithProof),
     TransactionsWithProof(TransactionListWithProof),
@@ -198,6 +212,11 @@ impl From<Vec<LedgerInfoWithSignatures>> for ResponsePayload {
         Self::EpochEndingLedgerInfos(inner)
     }
 }
+impl From<(TransactionOutputListWithProof, LedgerInfoWithSignatures)> for ResponsePayload {
+    fn from(inner: (TransactionOutputListWithProof, LedgerInfoWithSignatures)) -> Self {
+        Self::NewTransactionOutputsWithProof(inner)
+    }
+}
 impl From<u64> for ResponsePayload {
     fn from(inner: u64) -> Self {
         Self::NumberOfAccountStates(inner)
@@ -0,0 +1,56 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use diem_metrics::{
+    register_int_counter_vec, register_int_gauge, register_int_gauge_vec, IntCounterVec, IntGauge,
+    IntGaugeVec,
+};
+use once_cell::sync::Lazy;
+
+// Response result labels
+pub const SUCCESS_LABEL: &str = "success";
+pub const INVALID_PAYLOAD_DATA_TYPE_LABEL: &str = "invalid_payload_data_type";
+pub const MISSING_DATA_LABEL: &str = "missing_data";
+pub const PROOF_VERIFICATION_ERROR_LABEL: &str = "proof_verification_error";
+pub const RPC_ERROR_LABEL: &str = "rpc_error";
+pub const TIMEOUT_LABEL: &str = "timeout";
+
+/// Counters for the responses received from peers, labelled by result
+/// (i.e., success or the type of bad response reported).
+pub static RESPONSES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_data_client_responses",
+        "Counters related to responses received from peers",
+        &["result"]
+    )
+    .unwrap()
+});
+
+/// Gauge for the number of peers currently ignored due to low scores
+pub static IGNORED_PEERS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_data_client_ignored_peers",
+        "The number of peers ignored due to low scores"
+    )
+    .unwrap()
+});
+
+/// Gauges for the current score of each peer, labelled by peer
+pub static PEER_SCORES: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "diem_data_client_peer_scores",
+        "The current score of each peer",
+        &["peer"]
+    )
+    .unwrap()
+});
+
+/// Increments the response counter for the given result label
+pub fn increment_response_counter(label: &str) {
+    RESPONSES.with_label_values(&[label]).inc();
+}
+
+/// Sets the score gauge for the given peer
+pub fn set_peer_score(peer: &str, score: f64) {
+    PEER_SCORES.with_label_values(&[peer]).set(score as i64);
+}
@@ -6,6 +6,7 @@
 use channel::{diem_channel, message_queues::QueueStyle};
 use diem_id_generator::{IdGenerator, U64IdGenerator};
 use diem_infallible::RwLock;
+use diem_logger::prelude::*;
 use diem_types::{
     account_state::AccountState,
     contract_event::ContractEvent,
@@ -15,33 +16,52 @@ use diem_types::{
     on_chain_config::{config_address, ConfigID, OnChainConfigPayload},
     transaction::Version,
 };
-use futures::{channel::mpsc::SendError, stream::FusedStream, Stream};
+use futures::{
+    channel::{mpsc, mpsc::SendError},
+    executor::block_on,
+    stream::FusedStream,
+    SinkExt, Stream,
+};
+use move_core_types::language_storage::TypeTag;
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::{HashMap, HashSet},
+    cmp::{max, min},
+    collections::{HashMap, HashSet, VecDeque},
     convert::TryFrom,
     iter::FromIterator,
     ops::Deref,
     pin::Pin,
     sync::Arc,
     task::{Context, Poll},
+    thread,
 };
 use storage_interface::default_protocol::DbReaderWriter;
 use thiserror::Error;
 
+mod metrics;
 #[cfg(test)]
 mod tests;
 
 // Maximum channel sizes for each notification subscriber. If messages are not
 // consumed, they will be dropped (oldest messages first). The remaining messages
-// will be retrieved using FIFO ordering.
+// will be retrieved using FIFO ordering. Event subscribers can override the
+// channel size and overflow behaviour (see `EventSubscriptionOptions`).
 const EVENT_NOTIFICATION_CHANNEL_SIZE: usize = 100;
 const RECONFIG_NOTIFICATION_CHANNEL_SIZE: usize = 1;
 
+// The maximum number of versions to read from storage at once when replaying
+// events to a resumed subscription. Replayed events are read lazily (one batch
+// at a time, on a dedicated thread) as the subscriber consumes them.
+const EVENT_REPLAY_BATCH_SIZE: u64 = 1000;
+
 #[derive(Clone, Debug, Deserialize, Error, PartialEq, Serialize)]
 pub enum Error {
     #[error("Cannot subscribe to zero event keys!")]
     CannotSubscribeToZeroEventKeys,
+    #[error("The event notification channel is full! Subscription ID: {0}")]
+    EventNotificationChannelFull(u64),
+    #[error("Invalid event subscription options: {0}")]
+    InvalidSubscriptionOptions(String),
     #[error("Missing event subscription! Subscription ID: {0}")]
     MissingEventSubscription(u64),
     #[error("Unable to send event notification! Error: {0}")]
@@ -69,11 +89,75 @@ pub trait EventNotificationSender: Send {
     fn notify_initial_configs(&mut self, version: Version) -> Result<(), Error>;
 }
 
+/// A filter for event subscriptions. An event matches the filter if its key is
+/// one of `event_keys` or its type tag is one of `event_type_tags`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct EventFilter {
+    pub event_keys: Vec<EventKey>,
+    pub event_type_tags: Vec<TypeTag>,
+}
+
+impl EventFilter {
+    pub fn new(event_keys: Vec<EventKey>, event_type_tags: Vec<TypeTag>) -> Self {
+        Self {
+            event_keys,
+            event_type_tags,
+        }
+    }
+
+    /// Returns true iff the filter can't match any event
+    pub fn is_empty(&self) -> bool {
+        self.event_keys.is_empty() && self.event_type_tags.is_empty()
+    }
+
+    /// Returns true iff the given event matches the filter
+    pub fn matches(&self, event: &ContractEvent) -> bool {
+        self.event_keys.contains(event.key()) || self.event_type_tags.contains(event.type_tag())
+    }
+}
+
+/// The behaviour of an event subscription when its notification channel is
+/// full (i.e., the subscriber isn't keeping up with new events). Only the
+/// `Block` policy lets a slow subscriber slow down the notifier (i.e., state
+/// sync), so it should be reserved for subscribers that can't miss any event
+/// and that are not driven by the thread notifying them.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OverflowPolicy {
+    DropOldest, // Drop the oldest pending notification to make room for the new one
+    Disconnect, // Drop the subscription (the listener stream ends once all pending notifications are consumed)
+    Block,      // Block the notifier until the subscriber consumes a pending notification
+}
+
+/// The options for a single event subscription.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct EventSubscriptionOptions {
+    // The maximum number of pending notifications for the subscriber
+    pub channel_size: usize,
+
+    // The behaviour when the notification channel is full
+    pub overflow_policy: OverflowPolicy,
+
+    // If set, all matching events committed at or after this version are
+    // replayed from storage (e.g., to resume after the subscriber restarts).
+    pub start_version: Option<Version>,
+}
+
+impl Default for EventSubscriptionOptions {
+    fn default() -> Self {
+        Self {
+            channel_size: EVENT_NOTIFICATION_CHANNEL_SIZE,
+            overflow_policy: OverflowPolicy::DropOldest,
+            start_version: None,
+        }
+    }
+}
+
 /// The subscription service offered by state sync, responsible for notifying
 /// subscribers of on-chain events.
 pub struct EventSubscriptionService {
     // Event subscription registry
     event_key_subscriptions: HashMap<EventKey, HashSet<SubscriptionId>>,
+    event_type_tag_subscriptions: HashMap<TypeTag, HashSet<SubscriptionId>>,
     subscription_id_to_event_subscription: HashMap<SubscriptionId, EventSubscription>,
 
     // Reconfig subscription registry
@@ -93,6 +177,7 @@ impl EventSubscriptionService {
     pub fn new(config_registry: &[ConfigID], storage: Arc<RwLock<DbReaderWriter>>) -> Self {
         Self {
             event_key_subscriptions: HashMap::new(),
+            event_type_tag_subscriptions: HashMap::new(),
             subscription_id_to_event_subscription: HashMap::new(),
             reconfig_subscriptions: HashMap::new(),
             config_registry: config_registry.to_vec(),
@@ -112,17 +197,85 @@ impl EventSubscriptionService {
         &mut self,
         event_keys: Vec<EventKey>,
     ) -> Result<EventNotificationListener, Error> {
-        if event_keys.is_empty() {
+        self.subscribe_to_filtered_events(
+            EventFilter::new(event_keys, vec![]),
+            EventSubscriptionOptions::default(),
+        )
+    }
+
+    /// Returns an EventNotificationListener that can be monitored for events
+    /// matching the given filter (i.e., by event key or event type tag). The
+    /// given options specify the size of the notification buffer and what
+    /// happens when it fills up (see `OverflowPolicy`). If a start version is
+    /// specified, all matching events already committed at or after that
+    /// version are replayed from storage (in batches, as they are consumed)
+    /// before any new events are delivered.
+    pub fn subscribe_to_filtered_events(
+        &mut self,
+        event_filter: EventFilter,
+        options: EventSubscriptionOptions,
+    ) -> Result<EventNotificationListener, Error> {
+        if event_filter.is_empty() {
             return Err(Error::CannotSubscribeToZeroEventKeys);
         }
+        if options.channel_size == 0 {
+            return Err(Error::InvalidSubscriptionOptions(
+                "The channel size must be greater than zero!".into(),
+            ));
+        }
 
-        let (notification_sender, notification_receiver) =
-            diem_channel::new(QueueStyle::KLAST, EVENT_NOTIFICATION_CHANNEL_SIZE, None);
+        // Identify the events to replay before creating the subscription. The
+        // replay covers all versions up to the currently synced version, and
+        // any newer events are delivered by the subscription. This ensures the
+        // subscriber won't miss (or duplicate) any events.
+        let (event_replay, first_live_version) = match options.start_version {
+            Some(start_version) => {
+                let event_replay =
+                    EventReplay::new(self.storage.clone(), event_filter.clone(), start_version)?;
+                let first_live_version = max(start_version, event_replay.end_version + 1);
+                (
+                    Some(event_replay.start(options.channel_size)?),
+                    first_live_version,
+                )
+            }
+            None => (None, 0),
+        };
+
+        // Create the notification channel according to the overflow policy
+        let (notification_sender, notification_receiver) = match options.overflow_policy {
+            OverflowPolicy::DropOldest => {
+                let (notification_sender, notification_receiver) =
+                    diem_channel::new(QueueStyle::KLAST, options.channel_size, None);
+                (
+                    EventNotificationChannelSender::DropOldest(notification_sender),
+                    EventNotificationChannelReceiver::DropOldest(notification_receiver),
+                )
+            }
+            OverflowPolicy::Disconnect => {
+                // The capacity of the channel is the buffer size plus the
+                // number of senders (of which there is only ever one).
+                let (notification_sender, notification_receiver) =
+                    mpsc::channel(options.channel_size - 1);
+                (
+                    EventNotificationChannelSender::Disconnect(notification_sender),
+                    EventNotificationChannelReceiver::Disconnect(notification_receiver),
+                )
+            }
+            OverflowPolicy::Block => {
+                let (notification_sender, notification_receiver) =
+                    mpsc::channel(options.channel_size - 1);
+                (
+                    EventNotificationChannelSender::Block(notification_sender),
+                    EventNotificationChannelReceiver::Block(notification_receiver),
+                )
+            }
+        };
 
         // Create a new event subscription
         let subscription_id = self.get_new_subscription_id();
         let event_subscription = EventSubscription {
             subscription_id,
+            event_filter: event_filter.clone(),
             notification_sender,
             event_buffer: vec![],
         };
@@ -138,8 +291,8 @@ impl EventSubscriptionService {
             );
         }
 
-        // Update the event key subscriptions to include the new subscription
-        for event_key in event_keys {
+        // Update the event key and type tag subscriptions to include the new subscription
+        for event_key in event_filter.event_keys {
             self.event_key_subscriptions
                 .entry(event_key)
                 .and_modify(|subscriptions| {
@@ -147,9 +300,18 @@ impl EventSubscriptionService {
                 })
                 .or_insert_with(|| HashSet::from_iter(vec![subscription_id].iter().cloned()));
         }
+        for event_type_tag in event_filter.event_type_tags {
+            self.event_type_tag_subscriptions
+                .entry(event_type_tag)
+                .or_insert_with(HashSet::new)
+                .insert(subscription_id);
+        }
 
         Ok(EventNotificationListener {
+            event_replay,
+            first_live_version,
             notification_receiver,
+            terminated: false,
         })
     }
 
@@ -191,68 +353,128 @@ impl EventSubscriptionService {
     }
 
     /// This notifies all the event subscribers of the new events found at the
-    /// specified version. If a reconfiguration event (i.e., new epoch) is found,
-    /// this method will return true.
+    /// specified version. Subscribers that can't be notified (e.g., because
+    /// their channel is full or closed) are dropped, without affecting others.
+    /// Note: this blocks while the channel of a `Block` subscriber is full.
     fn notify_event_subscribers(
         &mut self,
         version: Version,
         events: Vec<ContractEvent>,
-    ) -> Result<bool, Error> {
-        let mut reconfig_event_found = false;
+    ) -> Result<(), Error> {
         let mut event_subscription_ids_to_notify = HashSet::new();
 
         for event in events.iter() {
-            let event_key = event.key();
-
-            // Process all subscriptions for the current event
-            if let Some(subscription_ids) = self.event_key_subscriptions.get(event_key) {
-                // Add the event to the subscription's pending event buffer
-                // and store the subscriptions that will need to notified once all
-                // events have been processed.
-                for subscription_id in subscription_ids.iter() {
-                    if let Some(event_subscription) = self
-                        .subscription_id_to_event_subscription
-                        .get_mut(subscription_id)
-                    {
-                        event_subscription.buffer_event(event.clone());
-                        event_subscription_ids_to_notify.insert(*subscription_id);
-                    } else {
-                        return Err(Error::MissingEventSubscription(*subscription_id));
-                    }
+            // Identify all subscriptions for the current event (by key or type
+            // tag). A subscription matching both should only see the event once.
+            let subscription_ids: HashSet<SubscriptionId> = self
+                .event_key_subscriptions
+                .get(event.key())
+                .into_iter()
+                .chain(self.event_type_tag_subscriptions.get(event.type_tag()))
+                .flatten()
+                .cloned()
+                .collect();
+
+            // Add the event to the subscription's pending event buffer
+            // and store the subscriptions that will need to notified once all
+            // events have been processed.
+            for subscription_id in subscription_ids {
+                if let Some(event_subscription) = self
+                    .subscription_id_to_event_subscription
+                    .get_mut(&subscription_id)
+                {
+                    event_subscription.buffer_event(event.clone());
+                    event_subscription_ids_to_notify.insert(subscription_id);
+                } else {
+                    return Err(Error::MissingEventSubscription(subscription_id));
                 }
             }
-
-            // Take note if a reconfiguration (new epoch) has occurred
-            if *event_key == on_chain_config::new_epoch_event_key() {
-                reconfig_event_found = true;
-            }
         }
 
         // Notify event subscribers of the new events
+        let mut failed_subscriptions = vec![];
         for event_subscription_id in event_subscription_ids_to_notify {
             if let Some(event_subscription) = self
                 .subscription_id_to_event_subscription
                 .get_mut(&event_subscription_id)
             {
-                event_subscription.notify_subscriber_of_events(version)?;
+                if let Err(error) = event_subscription.notify_subscriber_of_events(version) {
+                    failed_subscriptions.push((event_subscription_id, error));
+                }
             } else {
                 return Err(Error::MissingEventSubscription(event_subscription_id));
             }
         }
 
-        Ok(reconfig_event_found)
+        // Drop the subscriptions that couldn't be notified
+        for (event_subscription_id, error) in failed_subscriptions {
+            warn!(
+                "Dropping event subscription {} that couldn't be notified at version {}: {:?}",
+                event_subscription_id, version, error
+            );
+            metrics::increment_dropped_subscriptions(metrics::EVENT_SUBSCRIPTION_LABEL);
+            self.remove_event_subscription(event_subscription_id);
+        }
+
+        Ok(())
+    }
+
+    /// Removes the event subscription with the specified ID from the registry
+    fn remove_event_subscription(&mut self, subscription_id: SubscriptionId) {
+        let event_subscription = match self
+            .subscription_id_to_event_subscription
+            .remove(&subscription_id)
+        {
+            Some(event_subscription) => event_subscription,
+            None => return,
+        };
+
+        let event_filter = event_subscription.event_filter;
+        for event_key in event_filter.event_keys {
+            if let Some(subscriptions) = self.event_key_subscriptions.get_mut(&event_key) {
+                subscriptions.remove(&subscription_id);
+                if subscriptions.is_empty() {
+                    self.event_key_subscriptions.remove(&event_key);
+                }
+            }
+        }
+        for event_type_tag in event_filter.event_type_tags {
+            if let Some(subscriptions) = self.event_type_tag_subscriptions.get_mut(&event_type_tag)
+            {
+                subscriptions.remove(&subscription_id);
+                if subscriptions.is_empty() {
+                    self.event_type_tag_subscriptions.remove(&event_type_tag);
+                }
+            }
+        }
     }
 
     /// This notifies all the reconfiguration subscribers of the on-chain
-    /// configurations at the specified version.
+    /// configurations at the specified version. Subscribers that can't be
+    /// notified (i.e., because their channel is closed) are dropped.
     fn notify_reconfiguration_subscribers(&mut self, version: Version) -> Result<(), Error> {
         if self.reconfig_subscriptions.is_empty() {
             return Ok(()); // No reconfiguration subscribers!
         }
 
         let new_configs = self.read_on_chain_configs(version)?;
-        for (_, reconfig_subscription) in self.reconfig_subscriptions.iter_mut() {
-            reconfig_subscription.notify_subscriber_of_configs(version, new_configs.clone())?;
+        let mut failed_subscription_ids = vec![];
+        for (subscription_id, reconfig_subscription) in self.reconfig_subscriptions.iter_mut() {
+            if let Err(error) =
+                reconfig_subscription.notify_subscriber_of_configs(version, new_configs.clone())
+            {
+                warn!(
+                    "Dropping reconfiguration subscription {} that couldn't be notified at version {}: {:?}",
+                    subscription_id, version, error
+                );
+                failed_subscription_ids.push(*subscription_id);
+            }
+        }
+
+        // Drop the subscriptions that couldn't be notified
+        for subscription_id in failed_subscription_ids {
+            metrics::increment_dropped_subscriptions(metrics::RECONFIG_SUBSCRIPTION_LABEL);
+            self.reconfig_subscriptions.remove(&subscription_id);
         }
 
         Ok(())
@@ -343,16 +565,19 @@ impl EventNotificationSender for EventSubscriptionService {
             return Ok(()); // No events!
         }
 
-        // Notify event subscribers and check if a reconfiguration event was processed
-        let reconfig_event_processed = self.notify_event_subscribers(version, events)?;
+        // Check if a reconfiguration event was processed
+        let reconfig_event_processed = events
+            .iter()
+            .any(|event| *event.key() == on_chain_config::new_epoch_event_key());
 
-        // If a reconfiguration event was found, also notify the reconfig subscribers
-        // of the new configuration values.
+        // Notify the event subscribers and, if a reconfiguration event was found,
+        // the reconfig subscribers of the new configuration values. The reconfig
+        // subscribers are notified even if notifying the event subscribers fails.
+        let event_notification_result = self.notify_event_subscribers(version, events);
         if reconfig_event_processed {
-            self.notify_reconfiguration_subscribers(version)
-        } else {
-            Ok(())
+            self.notify_reconfiguration_subscribers(version)?;
         }
+        event_notification_result
     }
 
     fn notify_initial_configs(&mut self, version: Version) -> Result<(), Error> {
@@ -363,13 +588,15 @@ impl EventNotificationSender for EventSubscriptionService {
 /// A unique ID used to identify each subscription.
 type SubscriptionId = u64;
 
-/// A single event subscription, holding the subscription identifier, channel to
-/// send the corresponding notifications and a buffer to hold pending events.
+/// A single event subscription, holding the subscription identifier, event
+/// filter, channel to send the corresponding notifications and a buffer to hold
+/// pending events.
 #[derive(Debug)]
 struct EventSubscription {
     pub subscription_id: SubscriptionId,
+    pub event_filter: EventFilter,
     pub event_buffer: Vec<ContractEvent>,
-    pub notification_sender: channel::diem_channel::Sender<(), EventNotification>,
+    pub notification_sender: EventNotificationChannelSender,
 }
 
 impl EventSubscription {
@@ -383,12 +610,44 @@ impl EventSubscription {
             version,
         };
 
-        self.notification_sender
-            .push((), event_notification)
-            .map_err(|error| Error::UnexpectedErrorEncountered(format!("{:?}", error)))
+        let subscription_id = self.subscription_id;
+        match &mut self.notification_sender {
+            EventNotificationChannelSender::DropOldest(notification_sender) => notification_sender
+                .push((), event_notification)
+                .map_err(|error| Error::UnexpectedErrorEncountered(format!("{:?}", error))),
+            EventNotificationChannelSender::Disconnect(notification_sender) => notification_sender
+                .try_send(event_notification)
+                .map_err(|error| {
+                    if error.is_full() {
+                        Error::EventNotificationChannelFull(subscription_id)
+                    } else {
+                        error.into_send_error().into()
+                    }
+                }),
+            EventNotificationChannelSender::Block(notification_sender) => {
+                block_on(notification_sender.send(event_notification)).map_err(Error::from)
+            }
+        }
     }
 }
 
+/// The sending half of an event notification channel (as determined by the
+/// overflow policy of the subscription).
+#[derive(Debug)]
+enum EventNotificationChannelSender {
+    DropOldest(channel::diem_channel::Sender<(), EventNotification>),
+    Disconnect(mpsc::Sender<EventNotification>),
+    Block(mpsc::Sender<EventNotification>),
+}
+
+/// The receiving half of an event notification channel.
+#[derive(Debug)]
+enum EventNotificationChannelReceiver {
+    DropOldest(channel::diem_channel::Receiver<(), EventNotification>),
+    Disconnect(mpsc::Receiver<EventNotification>),
+    Block(mpsc::Receiver<EventNotification>),
+}
+
 /// A single reconfig subscription, holding the channel to send the
 /// corresponding notifications.
 #[derive(Debug)]
@@ -428,8 +687,221 @@ pub struct ReconfigNotification {
     pub on_chain_configs: OnChainConfigPayload,
 }
 
-/// A subscription listener for on-chain events.
-pub type EventNotificationListener = NotificationListener<EventNotification>;
+/// The replay of committed events (read from storage) for a resumed event
+/// subscription. Events are read in batches of `EVENT_REPLAY_BATCH_SIZE`
+/// versions, up to the version synced when the subscription was created.
+/// Storage is read on a dedicated thread (see `start`), so that polling the
+/// listener never blocks the executor of the subscriber.
+struct EventReplay {
+    event_filter: EventFilter,
+    next_version: Version,
+    end_version: Version,
+    replayed_notifications: VecDeque<EventNotification>,
+    storage: Arc<RwLock<DbReaderWriter>>,
+}
+
+impl EventReplay {
+    fn new(
+        storage: Arc<RwLock<DbReaderWriter>>,
+        event_filter: EventFilter,
+        start_version: Version,
+    ) -> Result<Self, Error> {
+        let end_version = storage
+            .read()
+            .reader
+            .deref()
+            .fetch_synced_version()
+            .map_err(|error| {
+                Error::UnexpectedErrorEncountered(format!(
+                    "Failed to fetch the synced version: {:?}",
+                    error
+                ))
+            })?;
+
+        Ok(Self {
+            event_filter,
+            next_version: start_version,
+            end_version,
+            replayed_notifications: VecDeque::new(),
+            storage,
+        })
+    }
+
+    /// Starts replaying the events on a dedicated thread and returns the
+    /// receiver of the replayed notifications. The thread waits for the
+    /// subscriber to consume the notifications in the channel (of the given
+    /// size) before reading more events, and stops after the first error
+    /// (which is sent to the subscriber) or once the listener is dropped.
+    fn start(
+        mut self,
+        channel_size: usize,
+    ) -> Result<mpsc::Receiver<Result<EventNotification, Error>>, Error> {
+        let (mut replay_sender, replay_receiver) = mpsc::channel(channel_size - 1);
+        thread::Builder::new()
+            .name("event-replay".into())
+            .spawn(move || loop {
+                let replayed_notification = match self.next_notification() {
+                    Ok(Some(event_notification)) => Ok(event_notification),
+                    Ok(None) => return, // The replay has finished
+                    Err(error) => Err(error),
+                };
+                let replay_failed = replayed_notification.is_err();
+                if block_on(replay_sender.send(replayed_notification)).is_err() || replay_failed {
+                    return; // The listener was dropped or the replay failed
+                }
+            })
+            .map_err(|error| {
+                Error::UnexpectedErrorEncountered(format!(
+                    "Failed to spawn the event replay thread: {:?}",
+                    error
+                ))
+            })?;
+        Ok(replay_receiver)
+    }
+
+    /// Returns the next replayed notification (if any), reading the next
+    /// batch of events from storage when required.
+    fn next_notification(&mut self) -> Result<Option<EventNotification>, Error> {
+        while self.replayed_notifications.is_empty() && self.next_version <= self.end_version {
+            self.fetch_next_batch()?;
+        }
+        Ok(self.replayed_notifications.pop_front())
+    }
+
+    /// Reads the next batch of committed events from storage and buffers the
+    /// notifications for all events matching the filter.
+    fn fetch_next_batch(&mut self) -> Result<(), Error> {
+        if self.next_version > self.end_version {
+            return Ok(()); // There's nothing left to replay
+        }
+
+        let num_versions = min(
+            EVENT_REPLAY_BATCH_SIZE,
+            self.end_version - self.next_version + 1,
+        );
+        let transaction_list = self
+            .storage
+            .read()
+            .reader
+            .get_transactions(self.next_version, num_versions, self.end_version, true)
+            .map_err(|error| {
+                Error::UnexpectedErrorEncountered(format!(
+                    "Failed to fetch events to replay at version {:?}: {:?}",
+                    self.next_version, error
+                ))
+            })?;
+        let events = transaction_list.events.ok_or_else(|| {
+            Error::UnexpectedErrorEncountered("Missing events from storage!".into())
+        })?;
+        if events.is_empty() {
+            return Err(Error::UnexpectedErrorEncountered(format!(
+                "No events found to replay at version {:?}!",
+                self.next_version
+            )));
+        }
+
+        let num_versions = events.len() as u64;
+        for (version, events) in (self.next_version..).zip(events) {
+            let subscribed_events: Vec<_> = events
+                .into_iter()
+                .filter(|event| self.event_filter.matches(event))
+                .collect();
+            if !subscribed_events.is_empty() {
+                self.replayed_notifications.push_back(EventNotification {
+                    version,
+                    subscribed_events,
+                });
+            }
+        }
+        self.next_version += num_versions;
+
+        Ok(())
+    }
+}
+
+/// A subscription listener for on-chain events. Any events replayed from
+/// storage (when resuming a subscription) are delivered before new events.
+/// If the replay fails (e.g., storage errors), the stream is terminated so
+/// that the subscriber can resume from the last version it processed.
+#[derive(Debug)]
+pub struct EventNotificationListener {
+    event_replay: Option<mpsc::Receiver<Result<EventNotification, Error>>>,
+    // New notifications at lower versions are skipped (they are replayed)
+    first_live_version: Version,
+    notification_receiver: EventNotificationChannelReceiver,
+    terminated: bool,
+}
+
+impl Stream for EventNotificationListener {
+    type Item = EventNotification;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let listener = self.get_mut();
+        if listener.terminated {
+            return Poll::Ready(None);
+        }
+
+        if let Some(event_replay) = listener.event_replay.as_mut() {
+            match Pin::new(event_replay).poll_next(cx) {
+                Poll::Ready(Some(Ok(event_notification))) => {
+                    return Poll::Ready(Some(event_notification))
+                }
+                Poll::Ready(Some(Err(error))) => {
+                    error!(
+                        "Failed to replay events, terminating the subscription: {:?}",
+                        error
+                    );
+                    listener.event_replay = None;
+                    listener.terminated = true;
+                    return Poll::Ready(None);
+                }
+                Poll::Ready(None) => listener.event_replay = None, // The replay has finished
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+
+        loop {
+            let event_notification = match &mut listener.notification_receiver {
+                EventNotificationChannelReceiver::DropOldest(notification_receiver) => {
+                    Pin::new(notification_receiver).poll_next(cx)
+                }
+                EventNotificationChannelReceiver::Disconnect(notification_receiver)
+                | EventNotificationChannelReceiver::Block(notification_receiver) => {
+                    Pin::new(notification_receiver).poll_next(cx)
+                }
+            };
+            match event_notification {
+                Poll::Ready(Some(event_notification))
+                    if event_notification.version < listener.first_live_version =>
+                {
+                    continue; // This notification has already been replayed
+                }
+                event_notification => return event_notification,
+            }
+        }
+    }
+}
+
+impl FusedStream for EventNotificationListener {
+    fn is_terminated(&self) -> bool {
+        if self.terminated {
+            return true;
+        }
+
+        self.event_replay
+            .as_ref()
+            .map_or(true, |event_replay| event_replay.is_terminated())
+            && match &self.notification_receiver {
+                EventNotificationChannelReceiver::DropOldest(notification_receiver) => {
+                    notification_receiver.is_terminated()
+                }
+                EventNotificationChannelReceiver::Disconnect(notification_receiver)
+                | EventNotificationChannelReceiver::Block(notification_receiver) => {
+                    notification_receiver.is_terminated()
+                }
+            }
+    }
+}
 
 /// A subscription listener for reconfigurations.
 pub type ReconfigNotificationListener = NotificationListener<ReconfigNotification>;
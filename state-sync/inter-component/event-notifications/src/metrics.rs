@@ -0,0 +1,25 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use diem_metrics::{register_int_counter_vec, IntCounterVec};
+use once_cell::sync::Lazy;
+
+// Subscription type labels
+pub const EVENT_SUBSCRIPTION_LABEL: &str = "event";
+pub const RECONFIG_SUBSCRIPTION_LABEL: &str = "reconfig";
+
+/// Counters for the subscriptions dropped because their subscribers couldn't
+/// be notified (e.g., full or closed channels), labelled by subscription type.
+pub static DROPPED_SUBSCRIPTIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_event_notifications_dropped_subscriptions",
+        "Counters related to subscriptions dropped by the event subscription service",
+        &["subscription_type"]
+    )
+    .unwrap()
+});
+
+/// Increments the dropped subscription counter for the given subscription type
+pub fn increment_dropped_subscriptions(label: &str) {
+    DROPPED_SUBSCRIPTIONS.with_label_values(&[label]).inc();
+}
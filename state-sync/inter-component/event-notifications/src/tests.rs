@@ -4,27 +4,55 @@
 #![forbid(unsafe_code)]
 
 use crate::{
-    Error, EventNotificationListener, EventNotificationSender, EventSubscriptionService,
-    ReconfigNotificationListener,
+    Error, EventFilter, EventNotificationListener, EventNotificationSender,
+    EventSubscriptionOptions, EventSubscriptionService, OverflowPolicy,
+    ReconfigNotificationListener, EVENT_REPLAY_BATCH_SIZE,
 };
+use anyhow::Result;
 use claim::{assert_lt, assert_matches, assert_ok};
+use diem_crypto::HashValue;
 use diem_infallible::RwLock;
 use diem_types::{
     account_address::AccountAddress,
-    contract_event::ContractEvent,
+    account_state_blob::{AccountStateBlob, AccountStateWithProof},
+    contract_event::{ContractEvent, EventByVersionWithProof, EventWithProof},
+    epoch_change::EpochChangeProof,
     event::EventKey,
+    ledger_info::LedgerInfoWithSignatures,
     on_chain_config,
     on_chain_config::{OnChainConfig, ON_CHAIN_CONFIG_REGISTRY},
-    transaction::{Transaction, Version, WriteSetPayload},
+    proof::SparseMerkleProof,
+    protocol_spec::DpnProto,
+    state_proof::StateProof,
+    transaction::{
+        default_protocol::{
+            AccountTransactionsWithProof, TransactionListWithProof, TransactionOutputListWithProof,
+            TransactionWithProof,
+        },
+        Transaction, TransactionInfo, TransactionInfoTrait, TransactionToCommit, Version,
+        WriteSetPayload,
+    },
+    vm_status::KeptVMStatus,
 };
 use diem_vm::DiemVM;
 use diemdb::DiemDB;
 use executor_test_helpers::bootstrap_genesis;
-use futures::{FutureExt, StreamExt};
+use futures::{executor::block_on, FutureExt, StreamExt};
 use move_core_types::language_storage::TypeTag;
 use serde::{Deserialize, Serialize};
-use std::{convert::TryInto, sync::Arc};
-use storage_interface::default_protocol::DbReaderWriter;
+use std::{
+    cmp::min,
+    convert::TryInto,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+use storage_interface::{
+    default_protocol::DbReaderWriter, DbReader, DbWriter, Order, StartupInfo, TreeState,
+};
 
 #[test]
 fn test_all_configs_returned() {
@@ -293,6 +321,309 @@ fn test_event_notification_queuing() {
     verify_no_event_notifications(vec![&mut listener_1, &mut listener_2]);
 }
 
+#[test]
+fn test_event_type_tag_subscribers() {
+    // Create subscription service and mock database
+    let mut event_service = create_event_subscription_service();
+
+    // Subscribe to events by type tag, and by both event key and type tag
+    let event_key_1 = create_random_event_key();
+    let mut listener_1 = event_service
+        .subscribe_to_filtered_events(
+            EventFilter::new(vec![], vec![TypeTag::U64]),
+            EventSubscriptionOptions::default(),
+        )
+        .unwrap();
+    let mut listener_2 = event_service
+        .subscribe_to_filtered_events(
+            EventFilter::new(vec![event_key_1], vec![TypeTag::U64]),
+            EventSubscriptionOptions::default(),
+        )
+        .unwrap();
+
+    // Notify the subscription service of an event with a different type tag
+    let event_1 = create_test_event(create_random_event_key());
+    notify_events(&mut event_service, 10, vec![event_1]);
+    verify_no_event_notifications(vec![&mut listener_1, &mut listener_2]);
+
+    // Notify the subscription service of an event with the subscribed type tag
+    let event_2 = create_test_event_with_type_tag(create_random_event_key(), TypeTag::U64);
+    notify_events(&mut event_service, 20, vec![event_2.clone()]);
+    verify_event_notification_received(vec![&mut listener_1, &mut listener_2], 20, vec![event_2]);
+
+    // Notify the subscription service of an event matching both the key and
+    // type tag, and verify listener 2 only receives the event once.
+    let event_3 = create_test_event_with_type_tag(event_key_1, TypeTag::U64);
+    notify_events(&mut event_service, 30, vec![event_3.clone()]);
+    verify_event_notification_received(vec![&mut listener_1, &mut listener_2], 30, vec![event_3]);
+    verify_no_event_notifications(vec![&mut listener_1, &mut listener_2]);
+}
+
+#[test]
+fn test_event_overflow_policy_disconnect() {
+    // Create subscription service and mock database
+    let mut event_service = create_event_subscription_service();
+
+    // Verify a channel size of zero is rejected
+    let event_key = create_random_event_key();
+    let event_filter = EventFilter::new(vec![event_key], vec![]);
+    let options = EventSubscriptionOptions {
+        channel_size: 0,
+        overflow_policy: OverflowPolicy::Disconnect,
+        start_version: None,
+    };
+    assert_matches!(
+        event_service.subscribe_to_filtered_events(event_filter.clone(), options),
+        Err(Error::InvalidSubscriptionOptions(_))
+    );
+
+    // Subscribe to events with a channel of size 2 (that disconnects when full)
+    // and with the default options.
+    let mut listener_1 = event_service
+        .subscribe_to_filtered_events(
+            event_filter.clone(),
+            EventSubscriptionOptions {
+                channel_size: 2,
+                ..options
+            },
+        )
+        .unwrap();
+    let mut listener_2 = event_service
+        .subscribe_to_filtered_events(event_filter, EventSubscriptionOptions::default())
+        .unwrap();
+
+    // Verify the first two notifications are accepted and the third drops
+    // the full subscription only (without failing the notifier).
+    let event = create_test_event(event_key);
+    for version in 0..3 {
+        notify_events(&mut event_service, version, vec![event.clone()]);
+    }
+
+    // Verify the first listener received the first two notifications before
+    // the stream was terminated.
+    verify_event_notification_received(vec![&mut listener_1], 0, vec![event.clone()]);
+    verify_event_notification_received(vec![&mut listener_1], 1, vec![event.clone()]);
+    assert!(listener_1.next().now_or_never().unwrap().is_none());
+
+    // Verify the second listener received all notifications
+    for version in 0..3 {
+        verify_event_notification_received(vec![&mut listener_2], version, vec![event.clone()]);
+    }
+
+    // Verify new notifications are only sent to the remaining listener
+    notify_events(&mut event_service, 3, vec![event.clone()]);
+    verify_event_notification_received(vec![&mut listener_2], 3, vec![event.clone()]);
+    assert!(listener_1.next().now_or_never().unwrap().is_none());
+
+    // Verify notifying a dropped listener doesn't fail
+    drop(listener_2);
+    notify_events(&mut event_service, 4, vec![event]);
+}
+
+#[test]
+fn test_event_overflow_policy_block() {
+    // Create subscription service and mock database
+    let mut event_service = create_event_subscription_service();
+
+    // Subscribe to events with a channel of size 1 (that blocks the notifier when full)
+    let event_key = create_random_event_key();
+    let mut listener = event_service
+        .subscribe_to_filtered_events(
+            EventFilter::new(vec![event_key], vec![]),
+            EventSubscriptionOptions {
+                channel_size: 1,
+                overflow_policy: OverflowPolicy::Block,
+                start_version: None,
+            },
+        )
+        .unwrap();
+
+    // Fill the channel and notify the service of another event on a separate thread
+    let event = create_test_event(event_key);
+    notify_events(&mut event_service, 0, vec![event.clone()]);
+    let notified = Arc::new(AtomicBool::new(false));
+    let notifier = {
+        let event = event.clone();
+        let notified = notified.clone();
+        thread::spawn(move || {
+            notify_events(&mut event_service, 1, vec![event]);
+            notified.store(true, Ordering::SeqCst);
+            event_service
+        })
+    };
+
+    // Verify the notifier is blocked until the subscriber consumes a notification
+    thread::sleep(Duration::from_millis(100));
+    assert!(!notified.load(Ordering::SeqCst));
+    verify_event_notification_received(vec![&mut listener], 0, vec![event.clone()]);
+    let mut event_service = notifier.join().unwrap();
+    assert!(notified.load(Ordering::SeqCst));
+
+    // Verify no notification was dropped
+    verify_event_notification_received(vec![&mut listener], 1, vec![event.clone()]);
+    verify_no_event_notifications(vec![&mut listener]);
+
+    // Verify a dropped listener doesn't block the notifier
+    drop(listener);
+    notify_events(&mut event_service, 2, vec![event.clone()]);
+    notify_events(&mut event_service, 3, vec![event]);
+}
+
+#[test]
+fn test_full_event_subscriber_at_reconfig() {
+    // Create subscription service and mock database
+    let mut event_service = create_event_subscription_service();
+
+    // Subscribe to reconfiguration events with a channel of size 1 (that
+    // disconnects when full) and with the default options.
+    let reconfig_event_key = on_chain_config::new_epoch_event_key();
+    let event_filter = EventFilter::new(vec![reconfig_event_key], vec![]);
+    let mut event_listener_1 = event_service
+        .subscribe_to_filtered_events(
+            event_filter.clone(),
+            EventSubscriptionOptions {
+                channel_size: 1,
+                overflow_policy: OverflowPolicy::Disconnect,
+                start_version: None,
+            },
+        )
+        .unwrap();
+    let mut event_listener_2 = event_service
+        .subscribe_to_filtered_events(event_filter, EventSubscriptionOptions::default())
+        .unwrap();
+    let mut reconfig_listener = event_service.subscribe_to_reconfigurations().unwrap();
+
+    // Notify the service of a reconfiguration event (filling the channel of
+    // the first event listener).
+    let reconfig_event = create_test_event(reconfig_event_key);
+    notify_events(&mut event_service, 0, vec![reconfig_event.clone()]);
+    verify_reconfig_notifications_received(vec![&mut reconfig_listener], 0, 1);
+
+    // Notify the service of another reconfiguration event and verify the
+    // reconfiguration is still sent, even though the first event listener is full.
+    notify_events(&mut event_service, 0, vec![reconfig_event.clone()]);
+    verify_reconfig_notifications_received(vec![&mut reconfig_listener], 0, 1);
+    verify_event_notification_received(
+        vec![&mut event_listener_2],
+        0,
+        vec![reconfig_event.clone()],
+    );
+    verify_event_notification_received(
+        vec![&mut event_listener_2],
+        0,
+        vec![reconfig_event.clone()],
+    );
+
+    // Verify the full listener was dropped after its pending notification
+    verify_event_notification_received(
+        vec![&mut event_listener_1],
+        0,
+        vec![reconfig_event.clone()],
+    );
+    assert!(event_listener_1.next().now_or_never().unwrap().is_none());
+
+    // Verify a dropped reconfiguration listener doesn't prevent notifications
+    let mut reconfig_listener_2 = event_service.subscribe_to_reconfigurations().unwrap();
+    drop(reconfig_listener);
+    notify_events(&mut event_service, 0, vec![reconfig_event]);
+    verify_reconfig_notifications_received(vec![&mut reconfig_listener_2], 0, 1);
+}
+
+#[test]
+fn test_event_subscription_resume() {
+    // Create subscription service and mock database
+    let mut event_service = create_event_subscription_service();
+
+    // Subscribe to reconfiguration events and resume from genesis
+    let reconfig_event_key = on_chain_config::new_epoch_event_key();
+    let event_filter = EventFilter::new(vec![reconfig_event_key], vec![]);
+    let mut listener_1 = event_service
+        .subscribe_to_filtered_events(
+            event_filter.clone(),
+            EventSubscriptionOptions {
+                start_version: Some(0),
+                ..EventSubscriptionOptions::default()
+            },
+        )
+        .unwrap();
+
+    // Subscribe to reconfiguration events and resume after genesis
+    let mut listener_2 = event_service
+        .subscribe_to_filtered_events(
+            event_filter,
+            EventSubscriptionOptions {
+                start_version: Some(1),
+                ..EventSubscriptionOptions::default()
+            },
+        )
+        .unwrap();
+
+    // Verify the genesis reconfiguration event was replayed to listener 1 only
+    let event_notification = block_on(listener_1.select_next_some());
+    assert_eq!(event_notification.version, 0);
+    assert!(!event_notification.subscribed_events.is_empty());
+    for event in event_notification.subscribed_events {
+        assert_eq!(*event.key(), reconfig_event_key);
+    }
+    verify_no_event_notifications(vec![&mut listener_1, &mut listener_2]);
+
+    // Verify new events are delivered to both listeners (once the replay has finished)
+    let reconfig_event = create_test_event(reconfig_event_key);
+    notify_events(&mut event_service, 1, vec![reconfig_event.clone()]);
+    wait_for_event_notification(
+        vec![&mut listener_1, &mut listener_2],
+        1,
+        vec![reconfig_event],
+    );
+}
+
+#[test]
+fn test_event_subscription_resume_batches() {
+    // Create a mock database with more versions than fit in two replay batches
+    let event_key = create_random_event_key();
+    let num_versions = (2 * EVENT_REPLAY_BATCH_SIZE) + 10;
+    let mock_reader = Arc::new(MockEventReader::new(event_key, num_versions));
+    let storage = Arc::new(RwLock::new(DbReaderWriter {
+        reader: mock_reader.clone(),
+        writer: mock_reader.clone(),
+    }));
+    let mut event_service = EventSubscriptionService::new(ON_CHAIN_CONFIG_REGISTRY, storage);
+
+    // Subscribe to the events and resume from a version in the first batch
+    let start_version = 5;
+    let mut listener = event_service
+        .subscribe_to_filtered_events(
+            EventFilter::new(vec![event_key], vec![]),
+            EventSubscriptionOptions {
+                start_version: Some(start_version),
+                ..EventSubscriptionOptions::default()
+            },
+        )
+        .unwrap();
+
+    // Notify the service of the events at the synced version (e.g., the
+    // notification raced with the subscription) and the next version.
+    let event = create_test_event(event_key);
+    notify_events(&mut event_service, num_versions - 1, vec![event.clone()]);
+    notify_events(&mut event_service, num_versions, vec![event.clone()]);
+
+    // Verify only the first batch was read from storage (the replay waits for
+    // the subscriber to consume the replayed notifications).
+    wait_for_event_notification(vec![&mut listener], start_version, vec![event.clone()]);
+    assert_eq!(mock_reader.num_batches_read(), 1);
+
+    // Verify all versions are replayed in order, without any gaps or duplicates
+    // (including at the batch boundaries), followed by the new notification.
+    for version in (start_version + 1)..num_versions {
+        wait_for_event_notification(vec![&mut listener], version, vec![event.clone()]);
+    }
+    wait_for_event_notification(vec![&mut listener], num_versions, vec![event]);
+    verify_no_event_notifications(vec![&mut listener]);
+
+    // Verify the replay was read in bounded batches
+    assert_eq!(mock_reader.num_batches_read(), 3);
+}
+
 #[test]
 fn test_event_subscribers() {
     // Create subscription service and mock database
@@ -492,6 +823,20 @@ fn verify_event_notification_received(
     }
 }
 
+// Waits until the specified listeners receive the expected notifications (e.g.,
+// notifications replayed from storage, which are delivered asynchronously).
+fn wait_for_event_notification(
+    listeners: Vec<&mut EventNotificationListener>,
+    expected_version: Version,
+    expected_events: Vec<ContractEvent>,
+) {
+    for listener in listeners {
+        let event_notification = block_on(listener.select_next_some());
+        assert_eq!(event_notification.version, expected_version);
+        assert_eq!(event_notification.subscribed_events, expected_events);
+    }
+}
+
 // Ensures that the specified listeners have received the expected notifications.
 // Also verifies that the reconfiguration notifications contain all on-chain configs.
 fn verify_reconfig_notifications_received(
@@ -530,7 +875,11 @@ fn notify_events(
 }
 
 fn create_test_event(event_key: EventKey) -> ContractEvent {
-    ContractEvent::new(event_key, 0, TypeTag::Bool, bcs::to_bytes(&0).unwrap())
+    create_test_event_with_type_tag(event_key, TypeTag::Bool)
+}
+
+fn create_test_event_with_type_tag(event_key: EventKey, type_tag: TypeTag) -> ContractEvent {
+    ContractEvent::new(event_key, 0, type_tag, bcs::to_bytes(&0).unwrap())
 }
 
 fn create_random_event_key() -> EventKey {
@@ -556,3 +905,228 @@ fn create_database() -> Arc<RwLock<DbReaderWriter>> {
 
     Arc::new(RwLock::new(db_rw))
 }
+
+/// A mock database reader that holds a single event (with the given key) at
+/// every version, and counts the number of transaction batches read.
+struct MockEventReader {
+    event_key: EventKey,
+    num_versions: u64,
+    num_batches_read: AtomicU64,
+}
+
+impl MockEventReader {
+    fn new(event_key: EventKey, num_versions: u64) -> Self {
+        Self {
+            event_key,
+            num_versions,
+            num_batches_read: AtomicU64::new(0),
+        }
+    }
+
+    fn num_batches_read(&self) -> u64 {
+        self.num_batches_read.load(Ordering::SeqCst)
+    }
+}
+
+impl DbReader<DpnProto> for MockEventReader {
+    fn get_epoch_ending_ledger_infos(
+        &self,
+        _start_epoch: u64,
+        _end_epoch: u64,
+    ) -> Result<EpochChangeProof> {
+        unimplemented!()
+    }
+
+    fn get_transactions(
+        &self,
+        start_version: Version,
+        batch_size: u64,
+        ledger_version: Version,
+        fetch_events: bool,
+    ) -> Result<TransactionListWithProof> {
+        assert!(fetch_events);
+        assert!(batch_size <= EVENT_REPLAY_BATCH_SIZE);
+        assert!(ledger_version < self.num_versions);
+        self.num_batches_read.fetch_add(1, Ordering::SeqCst);
+
+        let end_version = min(start_version + batch_size, ledger_version + 1);
+        let events = (start_version..end_version)
+            .map(|_| {
+                vec![
+                    create_test_event(create_random_event_key()),
+                    create_test_event(self.event_key),
+                ]
+            })
+            .collect();
+        let mut transaction_list = TransactionListWithProof::new_empty();
+        transaction_list.events = Some(events);
+        transaction_list.first_transaction_version = Some(start_version);
+        Ok(transaction_list)
+    }
+
+    fn get_transaction_by_hash(
+        &self,
+        _hash: HashValue,
+        _ledger_version: Version,
+        _fetch_events: bool,
+    ) -> Result<Option<TransactionWithProof>> {
+        unimplemented!()
+    }
+
+    fn get_transaction_by_version(
+        &self,
+        _version: Version,
+        _ledger_version: Version,
+        _fetch_events: bool,
+    ) -> Result<TransactionWithProof> {
+        unimplemented!()
+    }
+
+    fn get_transaction_outputs(
+        &self,
+        _start_version: Version,
+        _limit: u64,
+        _ledger_version: Version,
+    ) -> Result<TransactionOutputListWithProof> {
+        unimplemented!()
+    }
+
+    fn get_events(
+        &self,
+        _event_key: &EventKey,
+        _start: u64,
+        _order: Order,
+        _limit: u64,
+    ) -> Result<Vec<(u64, ContractEvent)>> {
+        unimplemented!()
+    }
+
+    fn get_events_with_proofs(
+        &self,
+        _event_key: &EventKey,
+        _start: u64,
+        _order: Order,
+        _limit: u64,
+        _known_version: Option<u64>,
+    ) -> Result<Vec<EventWithProof<TransactionInfo>>> {
+        unimplemented!()
+    }
+
+    fn get_event_by_version_with_proof(
+        &self,
+        _event_key: &EventKey,
+        _version: u64,
+        _proof_version: u64,
+    ) -> Result<EventByVersionWithProof<TransactionInfo>> {
+        unimplemented!()
+    }
+
+    fn get_block_timestamp(&self, _version: u64) -> Result<u64> {
+        unimplemented!()
+    }
+
+    fn get_latest_account_state(
+        &self,
+        _address: AccountAddress,
+    ) -> Result<Option<AccountStateBlob>> {
+        unimplemented!()
+    }
+
+    fn get_latest_ledger_info(&self) -> Result<LedgerInfoWithSignatures> {
+        unimplemented!()
+    }
+
+    fn get_startup_info(&self) -> Result<Option<StartupInfo>> {
+        unimplemented!()
+    }
+
+    fn get_account_transaction(
+        &self,
+        _address: AccountAddress,
+        _seq_num: u64,
+        _include_events: bool,
+        _ledger_version: Version,
+    ) -> Result<Option<TransactionWithProof>> {
+        unimplemented!()
+    }
+
+    fn get_account_transactions(
+        &self,
+        _address: AccountAddress,
+        _start_seq_num: u64,
+        _limit: u64,
+        _include_events: bool,
+        _ledger_version: Version,
+    ) -> Result<AccountTransactionsWithProof> {
+        unimplemented!()
+    }
+
+    fn get_state_proof_with_ledger_info(
+        &self,
+        _known_version: u64,
+        _ledger_info: LedgerInfoWithSignatures,
+    ) -> Result<StateProof> {
+        unimplemented!()
+    }
+
+    fn get_state_proof(&self, _known_version: u64) -> Result<StateProof> {
+        unimplemented!()
+    }
+
+    fn get_account_state_with_proof(
+        &self,
+        _address: AccountAddress,
+        _version: Version,
+        _ledger_version: Version,
+    ) -> Result<AccountStateWithProof<TransactionInfo>> {
+        unimplemented!()
+    }
+
+    fn get_account_state_with_proof_by_version(
+        &self,
+        _address: AccountAddress,
+        _version: Version,
+    ) -> Result<(
+        Option<AccountStateBlob>,
+        SparseMerkleProof<AccountStateBlob>,
+    )> {
+        unimplemented!()
+    }
+
+    fn get_latest_state_root(&self) -> Result<(Version, HashValue)> {
+        unimplemented!()
+    }
+
+    fn get_latest_tree_state(&self) -> Result<TreeState> {
+        unimplemented!()
+    }
+
+    fn get_epoch_ending_ledger_info(
+        &self,
+        _known_version: u64,
+    ) -> Result<LedgerInfoWithSignatures> {
+        unimplemented!()
+    }
+
+    fn get_latest_transaction_info_option(&self) -> Result<Option<(Version, TransactionInfo)>> {
+        let transaction_info = TransactionInfo::new(
+            HashValue::zero(),
+            HashValue::zero(),
+            HashValue::zero(),
+            0,
+            KeptVMStatus::Executed,
+        );
+        Ok(Some((self.num_versions - 1, transaction_info)))
+    }
+}
+
+impl DbWriter<DpnProto> for MockEventReader {
+    fn save_transactions(
+        &self,
+        _txns_to_commit: &[TransactionToCommit],
+        _first_version: Version,
+        _ledger_info_with_sigs: Option<&LedgerInfoWithSignatures>,
+    ) -> Result<()> {
+        unimplemented!()
+    }
+}
@@ -0,0 +1,152 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+use channel::{diem_channel, message_queues::QueueStyle};
+use diem_types::transaction::Version;
+use futures::{stream::FusedStream, Stream};
+use serde::{Deserialize, Serialize};
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+use thiserror::Error;
+
+// Only the latest commit notification is relevant to the storage service (it
+// only cares about the highest synced version), so older notifications are
+// dropped if the storage service hasn't consumed them yet.
+const STORAGE_SERVICE_NOTIFICATION_CHANNEL_SIZE: usize = 1;
+
+#[derive(Clone, Debug, Deserialize, Error, PartialEq, Serialize)]
+pub enum Error {
+    #[error("Commit notification failed: {0}")]
+    CommitNotificationError(String),
+}
+
+/// This method returns a (StorageServiceNotifier, StorageServiceNotificationListener)
+/// pair that can be used to allow state sync and the storage service to communicate.
+///
+/// Note: state sync should take the notifier and the storage service should
+/// take the listener.
+pub fn new_storage_service_notifier_listener_pair(
+) -> (StorageServiceNotifier, StorageServiceNotificationListener) {
+    let (notification_sender, notification_receiver) = diem_channel::new(
+        QueueStyle::KLAST,
+        STORAGE_SERVICE_NOTIFICATION_CHANNEL_SIZE,
+        None,
+    );
+
+    let storage_service_notifier = StorageServiceNotifier::new(notification_sender);
+    let storage_service_listener = StorageServiceNotificationListener::new(notification_receiver);
+
+    (storage_service_notifier, storage_service_listener)
+}
+
+/// The state sync component responsible for notifying the storage service.
+#[derive(Clone)]
+pub struct StorageServiceNotifier {
+    notification_sender: diem_channel::Sender<(), StorageServiceCommitNotification>,
+}
+
+impl StorageServiceNotifier {
+    fn new(
+        notification_sender: diem_channel::Sender<(), StorageServiceCommitNotification>,
+    ) -> Self {
+        Self {
+            notification_sender,
+        }
+    }
+
+    /// Notify the storage service that new data has been committed and that
+    /// the highest synced version is now `highest_synced_version`. This call
+    /// does not block (i.e., it does not wait for the storage service).
+    pub fn notify_new_commit(&self, highest_synced_version: Version) -> Result<(), Error> {
+        let commit_notification = StorageServiceCommitNotification {
+            highest_synced_version,
+        };
+        self.notification_sender
+            .push((), commit_notification)
+            .map_err(|error| {
+                Error::CommitNotificationError(format!(
+                    "Failed to notify the storage service of the new commit! Error: {:?}",
+                    error
+                ))
+            })
+    }
+}
+
+/// The storage service component responsible for handling state sync notifications.
+pub struct StorageServiceNotificationListener {
+    notification_receiver: diem_channel::Receiver<(), StorageServiceCommitNotification>,
+}
+
+impl StorageServiceNotificationListener {
+    fn new(
+        notification_receiver: diem_channel::Receiver<(), StorageServiceCommitNotification>,
+    ) -> Self {
+        Self {
+            notification_receiver,
+        }
+    }
+}
+
+impl Stream for StorageServiceNotificationListener {
+    type Item = StorageServiceCommitNotification;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.get_mut().notification_receiver).poll_next(cx)
+    }
+}
+
+impl FusedStream for StorageServiceNotificationListener {
+    fn is_terminated(&self) -> bool {
+        self.notification_receiver.is_terminated()
+    }
+}
+
+/// A notification sent by state sync to the storage service when new data
+/// has been committed to storage.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StorageServiceCommitNotification {
+    pub highest_synced_version: Version, // The highest synced version in storage
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Error, StorageServiceCommitNotification};
+    use claim::{assert_matches, assert_ok};
+    use futures::{FutureExt, StreamExt};
+
+    #[test]
+    fn test_only_latest_notification_is_kept() {
+        let (storage_service_notifier, mut storage_service_listener) =
+            crate::new_storage_service_notifier_listener_pair();
+
+        // Send several notifications without consuming them
+        for highest_synced_version in 0..10 {
+            assert_ok!(storage_service_notifier.notify_new_commit(highest_synced_version));
+        }
+
+        // Verify only the latest notification is received
+        let commit_notification = storage_service_listener.select_next_some().now_or_never();
+        assert_eq!(
+            commit_notification,
+            Some(StorageServiceCommitNotification {
+                highest_synced_version: 9
+            })
+        );
+        assert_eq!(storage_service_listener.next().now_or_never(), None);
+    }
+
+    #[test]
+    fn test_storage_service_not_listening() {
+        let (storage_service_notifier, storage_service_listener) =
+            crate::new_storage_service_notifier_listener_pair();
+
+        // Drop the listener and verify the notification fails
+        drop(storage_service_listener);
+        let notify_result = storage_service_notifier.notify_new_commit(10);
+        assert_matches!(notify_result, Err(Error::CommitNotificationError(_)));
+    }
+}
@@ -6,8 +6,10 @@
 use crate::{chunk_request::GetChunkRequest, chunk_response::GetChunkResponse, counters};
 use async_trait::async_trait;
 use channel::{diem_channel, message_queues::QueueStyle};
+use diem_config::network_id::{NetworkId, PeerNetworkId};
 use diem_types::PeerId;
 use network::{
+    application::storage::PeerMetadataStorage,
     error::NetworkError,
     peer_manager::{ConnectionRequestSender, PeerManagerRequestSender},
     protocols::network::{
@@ -17,7 +19,7 @@ use network::{
     ProtocolId,
 };
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 
 const STATE_SYNC_MAX_BUFFER_SIZE: usize = 1;
 
@@ -47,8 +49,15 @@ pub type StateSyncEvents = NetworkEvents<StateSyncMessage>;
 #[derive(Clone)]
 pub struct StateSyncSender {
     inner: NetworkSender<StateSyncMessage>,
+    peer_metadata: Option<(NetworkId, Arc<PeerMetadataStorage>)>,
 }
 
+/// Supported protocols in preferred order (from highest priority to lowest).
+pub const DIRECT_SEND: &[ProtocolId] = &[
+    ProtocolId::StateSyncDirectSendCompressed,
+    ProtocolId::StateSyncDirectSend,
+];
+
 impl NewNetworkSender for StateSyncSender {
     fn new(
         peer_mgr_reqs_tx: PeerManagerRequestSender,
@@ -56,14 +65,39 @@ impl NewNetworkSender for StateSyncSender {
     ) -> Self {
         Self {
             inner: NetworkSender::new(peer_mgr_reqs_tx, connection_reqs_tx),
+            peer_metadata: None,
         }
     }
 }
 
+impl StateSyncSender {
+    /// Initialize the peer metadata of the sender's network, which is used to
+    /// pick the preferred protocol of each peer.
+    pub fn initialize(
+        &mut self,
+        network_id: NetworkId,
+        peer_metadata_storage: Arc<PeerMetadataStorage>,
+    ) {
+        self.peer_metadata = Some((network_id, peer_metadata_storage));
+    }
+
+    /// Choose the most preferred protocol supported by the peer, falling back to
+    /// uncompressed messages.
+    fn preferred_protocol_for_peer(&self, peer: PeerId) -> ProtocolId {
+        self.peer_metadata
+            .as_ref()
+            .and_then(|(network_id, peer_metadata_storage)| {
+                peer_metadata_storage
+                    .preferred_protocol(PeerNetworkId::new(*network_id, peer), DIRECT_SEND)
+            })
+            .unwrap_or(ProtocolId::StateSyncDirectSend)
+    }
+}
+
 #[async_trait]
 impl ApplicationNetworkSender<StateSyncMessage> for StateSyncSender {
     fn send_to(&self, recipient: PeerId, message: StateSyncMessage) -> Result<(), NetworkError> {
-        let protocol = ProtocolId::StateSyncDirectSend;
+        let protocol = self.preferred_protocol_for_peer(recipient);
         self.inner.send_to(recipient, protocol, message)
     }
 
@@ -80,7 +114,7 @@ impl ApplicationNetworkSender<StateSyncMessage> for StateSyncSender {
 /// Configuration for the network endpoints to support state sync.
 pub fn network_endpoint_config() -> AppConfig {
     AppConfig::p2p(
-        [ProtocolId::StateSyncDirectSend],
+        DIRECT_SEND.iter().copied(),
         diem_channel::Config::new(STATE_SYNC_MAX_BUFFER_SIZE)
             .queue_style(QueueStyle::LIFO)
             .counters(&counters::PENDING_STATE_SYNC_NETWORK_EVENTS),
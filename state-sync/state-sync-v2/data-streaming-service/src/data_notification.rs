@@ -51,6 +51,7 @@ pub struct SentDataNotification {
 pub enum DataClientRequest {
     AccountsWithProof(AccountsWithProofRequest),
     EpochEndingLedgerInfos(EpochEndingLedgerInfosRequest),
+    NewTransactionOutputsWithProof(NewTransactionOutputsWithProofRequest),
     NumberOfAccounts(NumberOfAccountsRequest),
     TransactionsWithProof(TransactionsWithProofRequest),
     TransactionOutputsWithProof(TransactionOutputsWithProofRequest),
@@ -71,6 +72,14 @@ pub struct EpochEndingLedgerInfosRequest {
     pub end_epoch: Epoch,
 }
 
+/// A client request for subscribing to new transaction outputs (i.e., those
+/// after the known version).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NewTransactionOutputsWithProofRequest {
+    pub known_version: Version,
+    pub known_epoch: Epoch,
+}
+
 /// A client request for fetching the number of accounts at a version.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct NumberOfAccountsRequest {
@@ -9,7 +9,7 @@ use crate::{
     },
     error::Error,
     stream_progress_tracker::{DataStreamTracker, StreamProgressTracker},
-    streaming_client::StreamRequest,
+    streaming_client::{NotificationFeedback, StreamRequest},
 };
 use channel::{diem_channel, message_queues::QueueStyle};
 use diem_data_client::{
@@ -185,6 +185,18 @@ impl<T: DiemDataClient + Send + Clone + 'static> DataStream<T> {
                     pending_response.lock().client_response = Some(client_response);
                 });
             }
+            DataClientRequest::NewTransactionOutputsWithProof(request) => {
+                tokio::spawn(async move {
+                    let client_response = diem_data_client.get_new_transaction_outputs_with_proof(
+                        request.known_version,
+                        request.known_epoch,
+                    );
+                    let client_response = client_response
+                        .await
+                        .map(|response| response.map(ResponsePayload::from));
+                    pending_response.lock().client_response = Some(client_response);
+                });
+            }
             DataClientRequest::NumberOfAccounts(request) => {
                 tokio::spawn(async move {
                     let client_response =
@@ -248,13 +260,26 @@ impl<T: DiemDataClient + Send + Clone + 'static> DataStream<T> {
                             client_response,
                         ) {
                             // Send a data notification and make the next data client request
-                            self.send_data_notification_to_client(
+                            let result = self.send_data_notification_to_client(
                                 &pending_response.client_request,
                                 client_response,
-                            )?;
+                            );
+                            if let Err(Error::DiemDataClientResponseIsInvalid(_)) = result {
+                                // The stream progress tracker rejected the response
+                                // and will re-request the data. Notify the data client.
+                                self.notify_bad_response(
+                                    client_response,
+                                    ResponseError::InvalidPayloadDataType,
+                                );
+                            } else {
+                                result?;
+                            }
                         } else {
                             // Notify the data client and re-fetch the data
-                            self.notify_bad_response(client_response);
+                            self.notify_bad_response(
+                                client_response,
+                                ResponseError::InvalidPayloadDataType,
+                            );
                             self.resend_data_client_request(&pending_response.client_request)?;
                             break;
                         }
@@ -317,12 +342,47 @@ impl<T: DiemDataClient + Send + Clone + 'static> DataStream<T> {
         Ok(())
     }
 
+    /// Returns true iff the data notification with the given ID was sent
+    /// along this stream.
+    pub fn sent_notification(&self, notification_id: &NotificationId) -> bool {
+        self.sent_notifications.contains_key(notification_id)
+    }
+
+    /// Handles feedback for the data notification with the given ID by
+    /// notifying the Diem data client of the bad response that created it.
+    pub fn handle_notification_feedback(
+        &self,
+        notification_id: &NotificationId,
+        notification_feedback: &NotificationFeedback,
+    ) -> Result<(), Error> {
+        let sent_notification = self
+            .sent_notifications
+            .get(notification_id)
+            .ok_or_else(|| {
+                Error::UnexpectedErrorEncountered(format!(
+                    "Data notification was not sent along this stream! ID: {:?}",
+                    notification_id
+                ))
+            })?;
+
+        let response_error = match notification_feedback {
+            NotificationFeedback::InvalidPayloadData => ResponseError::InvalidPayloadDataType,
+            NotificationFeedback::PayloadTypeIsIncorrect => ResponseError::InvalidPayloadDataType,
+            NotificationFeedback::PayloadProofFailed => ResponseError::ProofVerificationError,
+        };
+        self.notify_bad_response(&sent_notification.client_response, response_error);
+
+        Ok(())
+    }
+
     /// Notifies the Diem data client of a bad client response
-    fn notify_bad_response(&self, data_client_response: &DataClientResponse) {
-        self.diem_data_client.notify_bad_response(
-            data_client_response.id,
-            ResponseError::InvalidPayloadDataType,
-        );
+    fn notify_bad_response(
+        &self,
+        data_client_response: &DataClientResponse,
+        response_error: ResponseError,
+    ) {
+        self.diem_data_client
+            .notify_bad_response(data_client_response.id, response_error);
     }
 
     /// Sends a data notification to the client along the stream
@@ -400,6 +460,12 @@ impl<T: DiemDataClient + Send + Clone + 'static> DataStream<T> {
 
         (sent_requests, sent_notifications)
     }
+
+    #[cfg(test)]
+    /// This is exposed and used only for test purposes.
+    pub fn get_diem_data_client(&self) -> &T {
+        &self.diem_data_client
+    }
 }
 
 /// Allows listening to data streams (i.e., streams of data notifications).
@@ -451,6 +517,12 @@ fn sanity_check_client_response(
                 ResponsePayload::EpochEndingLedgerInfos(_)
             )
         }
+        DataClientRequest::NewTransactionOutputsWithProof(_) => {
+            matches!(
+                data_client_response.payload,
+                ResponsePayload::NewTransactionOutputsWithProof(_)
+            )
+        }
         DataClientRequest::NumberOfAccounts(_) => {
             matches!(
                 data_client_response.payload,
@@ -4,12 +4,12 @@
 #![forbid(unsafe_code)]
 #![allow(dead_code)]
 
-mod data_notification;
-mod data_stream;
-mod error;
+pub mod data_notification;
+pub mod data_stream;
+pub mod error;
 mod stream_progress_tracker;
-mod streaming_client;
-mod streaming_service;
+pub mod streaming_client;
+pub mod streaming_service;
 
 #[cfg(test)]
 mod tests;
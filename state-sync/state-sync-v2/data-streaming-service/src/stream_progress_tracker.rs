@@ -5,11 +5,12 @@ use crate::{
     data_notification::{
         AccountsWithProofRequest, DataClientRequest,
         DataClientRequest::{
-            AccountsWithProof, EpochEndingLedgerInfos, NumberOfAccounts,
-            TransactionOutputsWithProof, TransactionsWithProof,
+            AccountsWithProof, EpochEndingLedgerInfos, NewTransactionOutputsWithProof,
+            NumberOfAccounts, TransactionOutputsWithProof, TransactionsWithProof,
         },
         DataClientResponse, DataNotification, DataPayload, EpochEndingLedgerInfosRequest,
-        NumberOfAccountsRequest, TransactionOutputsWithProofRequest, TransactionsWithProofRequest,
+        NewTransactionOutputsWithProofRequest, NumberOfAccountsRequest,
+        TransactionOutputsWithProofRequest, TransactionsWithProofRequest,
     },
     error::Error,
     streaming_client::{
@@ -147,8 +148,8 @@ impl AccountsStreamTracker {
             request: request.clone(),
             account_num_requested: false,
             number_of_accounts: None,
-            next_stream_index: 0,
-            next_request_index: 0,
+            next_stream_index: request.start_index,
+            next_request_index: request.start_index,
         })
     }
 
@@ -185,10 +186,14 @@ impl DataStreamTracker for AccountsStreamTracker {
         }
 
         if let Some(number_of_accounts) = self.number_of_accounts {
-            // Create the client requests
+            // Create the client requests (up to and including the last account index)
+            let end_index = match number_of_accounts.checked_sub(1) {
+                Some(end_index) => end_index,
+                None => return Ok(vec![]), // There are no accounts to fetch
+            };
             let client_requests = create_data_client_requests(
                 self.next_request_index,
-                number_of_accounts,
+                end_index,
                 max_number_of_requests,
                 global_data_summary
                     .optimal_chunk_sizes
@@ -270,6 +275,10 @@ pub struct ContinuousTransactionStreamTracker {
     // True iff a request has been created to fetch an epoch ending ledger info
     pub end_of_epoch_requested: bool,
 
+    // True iff a subscription request has been created to fetch new data
+    // (i.e., there is currently no new data advertised by the network).
+    pub subscription_requested: bool,
+
     // The next version and epoch that we're waiting to send to the
     // client along the stream. All versions before this have been sent.
     pub next_stream_version_and_epoch: (Version, Epoch),
@@ -287,6 +296,7 @@ impl ContinuousTransactionStreamTracker {
                     request: stream_request.clone(),
                     target_ledger_info: None,
                     end_of_epoch_requested: false,
+                    subscription_requested: false,
                     next_stream_version_and_epoch: (request.start_version, request.start_epoch),
                     next_request_version_and_epoch: (request.start_version, request.start_epoch),
                 })
@@ -296,6 +306,7 @@ impl ContinuousTransactionStreamTracker {
                     request: stream_request.clone(),
                     target_ledger_info: None,
                     end_of_epoch_requested: false,
+                    subscription_requested: false,
                     next_stream_version_and_epoch: (request.start_version, request.start_epoch),
                     next_request_version_and_epoch: (request.start_version, request.start_epoch),
                 })
@@ -324,6 +335,24 @@ impl ContinuousTransactionStreamTracker {
         }
     }
 
+    /// Creates a subscription request for new transaction outputs (i.e., all
+    /// outputs after the last requested version). This is only supported for
+    /// continuous transaction output streams.
+    fn create_subscription_request(&mut self) -> Result<Vec<DataClientRequest>, Error> {
+        let (next_request_version, next_request_epoch) = self.next_request_version_and_epoch;
+        let known_version = next_request_version
+            .checked_sub(1)
+            .ok_or_else(|| Error::IntegerOverflow("Known version has overflown!".into()))?;
+
+        self.subscription_requested = true;
+        Ok(vec![DataClientRequest::NewTransactionOutputsWithProof(
+            NewTransactionOutputsWithProofRequest {
+                known_version,
+                known_epoch: next_request_epoch,
+            },
+        )])
+    }
+
     fn get_target_ledger_info(&self) -> &LedgerInfoWithSignatures {
         self.target_ledger_info
             .as_ref()
@@ -443,13 +472,28 @@ impl DataStreamTracker for ContinuousTransactionStreamTracker {
         if self.target_ledger_info.is_none() && self.end_of_epoch_requested {
             return Ok(vec![]); // We are waiting for the epoch ending ledger info
         }
+        if self.target_ledger_info.is_none() && self.subscription_requested {
+            return Ok(vec![]); // We are waiting for the subscription to be served
+        }
 
         // If we don't have a syncing target, select one.
         let (next_request_version, next_request_epoch) = self.next_request_version_and_epoch;
         if self.target_ledger_info.is_none() {
             // Select a new ledger info from the advertised data
             let target_ledger_info =
-                self.select_target_ledger_info(&global_data_summary.advertised_data)?;
+                match self.select_target_ledger_info(&global_data_summary.advertised_data) {
+                    Ok(target_ledger_info) => target_ledger_info,
+                    Err(Error::NoDataToFetch(_))
+                        if matches!(
+                            self.request,
+                            StreamRequest::ContinuouslyStreamTransactionOutputs(_)
+                        ) =>
+                    {
+                        // There's no new data yet. Subscribe to new outputs.
+                        return self.create_subscription_request();
+                    }
+                    Err(error) => return Err(error),
+                };
             if target_ledger_info.ledger_info().epoch() > next_request_epoch {
                 // There was an epoch change. Request an epoch ending ledger info.
                 self.end_of_epoch_requested = true;
@@ -525,17 +569,68 @@ impl DataStreamTracker for ContinuousTransactionStreamTracker {
                 if let ResponsePayload::EpochEndingLedgerInfos(epoch_ending_ledger_infos) =
                     &client_response.payload
                 {
+                    self.end_of_epoch_requested = false;
                     if let [target_ledger_info] = &epoch_ending_ledger_infos[..] {
                         self.target_ledger_info = Some(target_ledger_info.clone());
+                        Ok(None)
                     } else {
-                        // TODO(joshlind): notify the data client of the bad response
+                        Err(Error::DiemDataClientResponseIsInvalid(format!(
+                            "Expected a single epoch ending ledger info, but got: {:?}",
+                            epoch_ending_ledger_infos.len()
+                        )))
                     }
                 } else {
-                    // TODO(joshlind): notify the data client of the bad response
+                    invalid_response_type!(client_response)
                 }
-                self.end_of_epoch_requested = false;
-                Ok(None)
             }
+            NewTransactionOutputsWithProof(request) => match &self.request {
+                StreamRequest::ContinuouslyStreamTransactionOutputs(_) => {
+                    self.subscription_requested = false;
+                    if let ResponsePayload::NewTransactionOutputsWithProof((
+                        output_list_with_proof,
+                        target_ledger_info,
+                    )) = &client_response.payload
+                    {
+                        // Verify the response contains new data up to the target
+                        let num_outputs =
+                            output_list_with_proof.transactions_and_outputs.len() as u64;
+                        let start_version =
+                            request.known_version.checked_add(1).ok_or_else(|| {
+                                Error::IntegerOverflow("Start version has overflown!".into())
+                            })?;
+                        let end_version = match (start_version + num_outputs).checked_sub(1) {
+                            Some(end_version)
+                                if num_outputs > 0
+                                    && end_version
+                                        <= target_ledger_info.ledger_info().version() =>
+                            {
+                                end_version
+                            }
+                            _ => {
+                                return Err(Error::DiemDataClientResponseIsInvalid(format!(
+                                    "Received {:?} new transaction outputs, but the target is at version: {:?}",
+                                    num_outputs,
+                                    target_ledger_info.ledger_info().version()
+                                )));
+                            }
+                        };
+
+                        // Update the target and the request/stream progress
+                        self.target_ledger_info = Some(target_ledger_info.clone());
+                        self.update_request_version_and_epoch(end_version)?;
+                        self.update_stream_version_and_epoch(start_version, end_version)?;
+                        let data_notification = self.create_data_notification(
+                            end_version,
+                            client_response,
+                            notification_id_generator,
+                        )?;
+                        Ok(Some(data_notification))
+                    } else {
+                        invalid_response_type!(client_response)
+                    }
+                }
+                request => invalid_stream_request!(request),
+            },
             TransactionsWithProof(request) => match &self.request {
                 StreamRequest::ContinuouslyStreamTransactions(_) => {
                     self.update_stream_version_and_epoch(
@@ -1108,6 +1203,13 @@ fn create_data_notification(
                 _ => invalid_response_type!(client_response),
             }
         }
+        ResponsePayload::NewTransactionOutputsWithProof((
+            transactions_output_chunk,
+            target_ledger_info,
+        )) => DataPayload::ContinuousTransactionOutputsWithProof(
+            target_ledger_info.clone(),
+            transactions_output_chunk.clone(),
+        ),
         _ => invalid_response_type!(client_response),
     };
 
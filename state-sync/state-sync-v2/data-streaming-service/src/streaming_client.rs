@@ -25,10 +25,16 @@ pub type Epoch = u64;
 /// assume the transactions are returned in monotonically increasing versions.
 #[async_trait]
 pub trait DataStreamingClient {
-    /// Fetches all account states at the specified version. The specified
-    /// version must be an epoch ending version, otherwise an error will be
-    /// returned. Account state proofs are at the same specified version.
-    async fn get_all_accounts(&self, version: Version) -> Result<DataStreamListener, Error>;
+    /// Fetches all account states at the specified version, starting at the
+    /// account with index `start_index` (e.g., to resume an interrupted account
+    /// state restore). The specified version must be an epoch ending version,
+    /// otherwise an error will be returned. Account state proofs are at the
+    /// same specified version.
+    async fn get_all_accounts(
+        &self,
+        version: Version,
+        start_index: u64,
+    ) -> Result<DataStreamListener, Error>;
 
     /// Fetches all epoch ending ledger infos starting at `start_epoch`
     /// (inclusive) and ending at the last known epoch advertised in the network.
@@ -94,6 +100,20 @@ pub trait DataStreamingClient {
         start_version: Version,
         start_epoch: Epoch,
     ) -> Result<DataStreamListener, Error>;
+
+    /// Terminates the stream that sent the data notification corresponding to
+    /// the specified `notification_id` and provides feedback as to why the
+    /// payload was bad (e.g., a proof failed to verify). The streaming service
+    /// will use the feedback to notify the Diem data client about the bad
+    /// response that created the notification.
+    ///
+    /// Note: the caller should stop listening to the terminated stream and
+    /// create a new stream to continue fetching data.
+    async fn terminate_stream_with_feedback(
+        &self,
+        notification_id: NotificationId,
+        notification_feedback: NotificationFeedback,
+    ) -> Result<(), Error>;
 }
 
 /// Messages used by the data streaming client for communication with the
@@ -115,12 +135,14 @@ pub enum StreamRequest {
     ContinuouslyStreamTransactions(ContinuouslyStreamTransactionsRequest),
     ContinuouslyStreamTransactionOutputs(ContinuouslyStreamTransactionOutputsRequest),
     RefetchNotificationPayload(RefetchNotificationPayloadRequest),
+    TerminateStream(TerminateStreamRequest),
 }
 
 /// A client request for fetching all account states at a specified version.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct GetAllAccountsRequest {
     pub version: Version,
+    pub start_index: u64,
 }
 
 /// A client request for fetching all available epoch ending ledger infos.
@@ -176,6 +198,21 @@ pub enum PayloadRefetchReason {
     ProofVerificationFailed,
 }
 
+/// A client request for terminating a stream and providing payload feedback.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TerminateStreamRequest {
+    pub notification_id: NotificationId,
+    pub notification_feedback: NotificationFeedback,
+}
+
+/// The feedback for a payload in a data notification.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum NotificationFeedback {
+    InvalidPayloadData,
+    PayloadTypeIsIncorrect,
+    PayloadProofFailed,
+}
+
 /// The streaming service client that talks to the streaming service.
 #[derive(Clone)]
 pub struct StreamingServiceClient {
@@ -201,12 +238,33 @@ impl StreamingServiceClient {
         request_sender.send(request_message).await?;
         response_receiver.await?
     }
+
+    /// Sends a stream request to the streaming service without waiting for a
+    /// response (e.g., for requests that don't create a new stream).
+    async fn send_request_and_forget(&self, client_request: StreamRequest) -> Result<(), Error> {
+        let mut request_sender = self.request_sender.clone();
+        let (response_sender, _response_receiver) = oneshot::channel();
+        let request_message = StreamRequestMessage {
+            stream_request: client_request,
+            response_sender,
+        };
+
+        request_sender.send(request_message).await?;
+        Ok(())
+    }
 }
 
 #[async_trait]
 impl DataStreamingClient for StreamingServiceClient {
-    async fn get_all_accounts(&self, version: u64) -> Result<DataStreamListener, Error> {
-        let client_request = StreamRequest::GetAllAccounts(GetAllAccountsRequest { version });
+    async fn get_all_accounts(
+        &self,
+        version: u64,
+        start_index: u64,
+    ) -> Result<DataStreamListener, Error> {
+        let client_request = StreamRequest::GetAllAccounts(GetAllAccountsRequest {
+            version,
+            start_index,
+        });
         self.send_stream_request(client_request).await
     }
 
@@ -293,6 +351,18 @@ impl DataStreamingClient for StreamingServiceClient {
         );
         self.send_stream_request(client_request).await
     }
+
+    async fn terminate_stream_with_feedback(
+        &self,
+        notification_id: u64,
+        notification_feedback: NotificationFeedback,
+    ) -> Result<(), Error> {
+        let client_request = StreamRequest::TerminateStream(TerminateStreamRequest {
+            notification_id,
+            notification_feedback,
+        });
+        self.send_request_and_forget(client_request).await
+    }
 }
 
 /// The component that enables listening to requests from streaming service
@@ -4,7 +4,9 @@
 use crate::{
     data_stream::{DataStream, DataStreamId, DataStreamListener},
     error::Error,
-    streaming_client::{StreamRequestMessage, StreamingServiceListener},
+    streaming_client::{
+        StreamRequest, StreamRequestMessage, StreamingServiceListener, TerminateStreamRequest,
+    },
 };
 use diem_data_client::{DiemDataClient, GlobalDataSummary, OptimalChunkSizes};
 use diem_id_generator::{IdGenerator, U64IdGenerator};
@@ -73,6 +75,14 @@ impl<T: DiemDataClient + Send + Clone + 'static> DataStreamingService<T> {
 
     /// Handles new stream request messages from clients
     fn handle_stream_request_message(&mut self, request_message: StreamRequestMessage) {
+        // Terminate requests don't create a new stream, so no response is sent
+        if let StreamRequest::TerminateStream(request) = &request_message.stream_request {
+            if let Err(_error) = self.process_terminate_stream_request(request) {
+                // TODO(joshlind): once we support logging, log this error!
+            }
+            return;
+        }
+
         // Process the request message
         let response = self.process_new_stream_request(&request_message);
 
@@ -114,6 +124,36 @@ impl<T: DiemDataClient + Send + Clone + 'static> DataStreamingService<T> {
         Ok(stream_listener)
     }
 
+    /// Terminates the data stream that sent the notification with the requested
+    /// ID and forwards the notification feedback to the data stream.
+    fn process_terminate_stream_request(
+        &mut self,
+        terminate_request: &TerminateStreamRequest,
+    ) -> Result<(), Error> {
+        let notification_id = &terminate_request.notification_id;
+        let data_stream_id = self
+            .data_streams
+            .iter()
+            .find(|(_, data_stream)| data_stream.sent_notification(notification_id))
+            .map(|(data_stream_id, _)| *data_stream_id)
+            .ok_or_else(|| {
+                Error::UnexpectedErrorEncountered(format!(
+                    "Unable to find the data stream that sent the notification with ID: {:?}",
+                    notification_id
+                ))
+            })?;
+
+        // Handle the feedback and remove the data stream
+        let data_stream = self.get_data_stream(&data_stream_id);
+        data_stream.handle_notification_feedback(
+            notification_id,
+            &terminate_request.notification_feedback,
+        )?;
+        self.data_streams.remove(&data_stream_id);
+
+        Ok(())
+    }
+
     /// Refreshes the global data summary by communicating with the Diem data client
     fn refresh_global_data_summary(&mut self) -> Result<(), Error> {
         let global_data_summary = self.diem_data_client.get_global_data_summary();
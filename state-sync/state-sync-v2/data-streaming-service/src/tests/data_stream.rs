@@ -6,15 +6,15 @@ use crate::{
         DataClientRequest, DataPayload, EpochEndingLedgerInfosRequest, PendingClientResponse,
     },
     data_stream::{DataStream, DataStreamListener},
-    streaming_client::{GetAllEpochEndingLedgerInfosRequest, StreamRequest},
+    streaming_client::{GetAllEpochEndingLedgerInfosRequest, NotificationFeedback, StreamRequest},
     tests::utils::{
         create_data_client_response, create_ledger_info, MockDiemDataClient, MAX_ADVERTISED_EPOCH,
         MAX_NOTIFICATION_TIMEOUT_SECS, MIN_ADVERTISED_EPOCH,
     },
 };
-use claim::{assert_ge, assert_none};
+use claim::{assert_err, assert_ge, assert_none};
 use diem_data_client::{
-    AdvertisedData, GlobalDataSummary, OptimalChunkSizes, Response, ResponsePayload,
+    AdvertisedData, GlobalDataSummary, OptimalChunkSizes, Response, ResponseError, ResponsePayload,
 };
 use diem_id_generator::U64IdGenerator;
 use diem_infallible::Mutex;
@@ -110,6 +110,55 @@ async fn test_stream_invalid_response() {
     verify_client_request_resubmitted(&mut data_stream, client_request);
 }
 
+#[tokio::test]
+async fn test_stream_notification_feedback() {
+    // Create an epoch ending data stream
+    let (mut data_stream, mut stream_listener) = create_epoch_ending_stream(MIN_ADVERTISED_EPOCH);
+
+    // Initialize the data stream and set a response for the first request
+    let global_data_summary = create_global_data_summary(1);
+    data_stream
+        .initialize_data_requests(global_data_summary.clone())
+        .unwrap();
+    set_epoch_ending_response_in_queue(&mut data_stream, 0);
+
+    // Process the response and verify a notification is sent
+    data_stream
+        .process_data_responses(global_data_summary)
+        .unwrap();
+    let data_notification = stream_listener.select_next_some().now_or_never().unwrap();
+    let notification_id = data_notification.notification_id;
+    assert!(data_stream.sent_notification(&notification_id));
+
+    // Provide feedback for the notification
+    data_stream
+        .handle_notification_feedback(&notification_id, &NotificationFeedback::PayloadProofFailed)
+        .unwrap();
+
+    // Verify the data client was notified about the bad response
+    let (_, sent_notifications) = data_stream.get_sent_requests_and_notifications();
+    let response_id = sent_notifications
+        .get(&notification_id)
+        .unwrap()
+        .client_response
+        .id;
+    let bad_responses = data_stream
+        .get_diem_data_client()
+        .bad_responses
+        .lock()
+        .clone();
+    assert_eq!(
+        bad_responses,
+        vec![(response_id, ResponseError::ProofVerificationError)]
+    );
+
+    // Verify feedback for an unknown notification returns an error
+    assert_err!(data_stream.handle_notification_feedback(
+        &(notification_id + 1),
+        &NotificationFeedback::InvalidPayloadData
+    ));
+}
+
 #[tokio::test]
 async fn test_stream_out_of_order_responses() {
     // Create an epoch ending data stream
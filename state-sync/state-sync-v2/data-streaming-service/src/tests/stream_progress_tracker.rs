@@ -2,14 +2,25 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    data_notification::{DataClientRequest, DataClientResponse, EpochEndingLedgerInfosRequest},
+    data_notification::{
+        DataClientRequest, DataClientResponse, DataPayload, EpochEndingLedgerInfosRequest,
+        NewTransactionOutputsWithProofRequest,
+    },
     error::Error,
-    stream_progress_tracker::{DataStreamTracker, EpochEndingStreamTracker, StreamProgressTracker},
-    streaming_client::{GetAllEpochEndingLedgerInfosRequest, StreamRequest},
+    stream_progress_tracker::{
+        ContinuousTransactionStreamTracker, DataStreamTracker, EpochEndingStreamTracker,
+        StreamProgressTracker,
+    },
+    streaming_client::{
+        ContinuouslyStreamTransactionOutputsRequest, GetAllEpochEndingLedgerInfosRequest,
+        StreamRequest,
+    },
+    tests::utils::{create_ledger_info, create_transaction, create_transaction_output},
 };
 use claim::{assert_matches, assert_ok};
 use diem_data_client::{GlobalDataSummary, OptimalChunkSizes, Response, ResponsePayload};
 use diem_id_generator::U64IdGenerator;
+use diem_types::transaction::default_protocol::TransactionOutputListWithProof;
 use std::{cmp, sync::Arc};
 use storage_service_types::CompleteDataRange;
 
@@ -236,6 +247,169 @@ fn test_update_epoch_ending_stream_panic() {
         .unwrap();
 }
 
+#[test]
+fn test_continuous_outputs_subscription() {
+    // Create a continuous output stream tracker that is already up-to-date
+    let mut stream_tracker = create_continuous_outputs_progress_tracker(101, 5);
+    let global_data_summary = create_continuous_outputs_data_summary(100, 5);
+
+    // Create a batch of client requests and verify a subscription is made
+    let client_requests = stream_tracker
+        .create_data_client_requests(5, &global_data_summary)
+        .unwrap();
+    let subscription_request =
+        DataClientRequest::NewTransactionOutputsWithProof(NewTransactionOutputsWithProofRequest {
+            known_version: 100,
+            known_epoch: 5,
+        });
+    assert_eq!(client_requests, vec![subscription_request.clone()]);
+
+    // Verify no more requests are made while the subscription is pending
+    let client_requests = stream_tracker
+        .create_data_client_requests(5, &global_data_summary)
+        .unwrap();
+    assert!(client_requests.is_empty());
+
+    // Respond to the subscription with new outputs
+    let target_ledger_info = create_ledger_info(120, 5, false);
+    let mut output_list_with_proof = TransactionOutputListWithProof::new_empty();
+    output_list_with_proof.first_transaction_output_version = Some(101);
+    output_list_with_proof.transactions_and_outputs = (101..=120)
+        .map(|_| (create_transaction(), create_transaction_output()))
+        .collect();
+    let client_response = Response::new(
+        0,
+        ResponsePayload::NewTransactionOutputsWithProof((
+            output_list_with_proof,
+            target_ledger_info.clone(),
+        )),
+    );
+    let data_notification = stream_tracker
+        .transform_client_response_into_notification(
+            &subscription_request,
+            &client_response,
+            create_notification_id_generator(),
+        )
+        .unwrap()
+        .unwrap();
+
+    // Verify the notification and the internal state
+    match data_notification.data_payload {
+        DataPayload::ContinuousTransactionOutputsWithProof(ledger_info, output_list_with_proof) => {
+            assert_eq!(ledger_info, target_ledger_info);
+            assert_eq!(output_list_with_proof.transactions_and_outputs.len(), 20);
+        }
+        data_payload => panic!("Unexpected data payload: {:?}", data_payload),
+    }
+    assert!(!stream_tracker.subscription_requested);
+    assert_eq!(stream_tracker.target_ledger_info, None);
+    assert_eq!(stream_tracker.next_request_version_and_epoch, (121, 5));
+    assert_eq!(stream_tracker.next_stream_version_and_epoch, (121, 5));
+
+    // Verify a new subscription is made
+    let client_requests = stream_tracker
+        .create_data_client_requests(5, &global_data_summary)
+        .unwrap();
+    assert_eq!(
+        client_requests,
+        vec![DataClientRequest::NewTransactionOutputsWithProof(
+            NewTransactionOutputsWithProofRequest {
+                known_version: 120,
+                known_epoch: 5,
+            },
+        )]
+    );
+}
+
+#[test]
+fn test_continuous_outputs_subscription_invalid_response() {
+    // Create a continuous output stream tracker that is already up-to-date
+    let mut stream_tracker = create_continuous_outputs_progress_tracker(101, 5);
+    let global_data_summary = create_continuous_outputs_data_summary(100, 5);
+
+    // Create a batch of client requests and verify a subscription is made
+    let client_requests = stream_tracker
+        .create_data_client_requests(5, &global_data_summary)
+        .unwrap();
+    let subscription_request =
+        DataClientRequest::NewTransactionOutputsWithProof(NewTransactionOutputsWithProofRequest {
+            known_version: 100,
+            known_epoch: 5,
+        });
+    assert_eq!(client_requests, vec![subscription_request.clone()]);
+
+    // Respond to the subscription without any new outputs
+    let client_response = Response::new(
+        0,
+        ResponsePayload::NewTransactionOutputsWithProof((
+            TransactionOutputListWithProof::new_empty(),
+            create_ledger_info(120, 5, false),
+        )),
+    );
+    let result = stream_tracker.transform_client_response_into_notification(
+        &subscription_request,
+        &client_response,
+        create_notification_id_generator(),
+    );
+
+    // Verify the response is rejected and the stream hasn't progressed
+    assert_matches!(result, Err(Error::DiemDataClientResponseIsInvalid(_)));
+    assert!(!stream_tracker.subscription_requested);
+    assert_eq!(stream_tracker.next_request_version_and_epoch, (101, 5));
+    assert_eq!(stream_tracker.next_stream_version_and_epoch, (101, 5));
+
+    // Verify the subscription is made again
+    let client_requests = stream_tracker
+        .create_data_client_requests(5, &global_data_summary)
+        .unwrap();
+    assert_eq!(client_requests, vec![subscription_request]);
+}
+
+fn create_continuous_outputs_progress_tracker(
+    start_version: u64,
+    start_epoch: u64,
+) -> ContinuousTransactionStreamTracker {
+    // Create a continuous transaction output stream request
+    let stream_request = StreamRequest::ContinuouslyStreamTransactionOutputs(
+        ContinuouslyStreamTransactionOutputsRequest {
+            start_version,
+            start_epoch,
+        },
+    );
+
+    // Create a new continuous transaction stream progress tracker
+    match StreamProgressTracker::new(&stream_request, &GlobalDataSummary::empty().advertised_data)
+        .unwrap()
+    {
+        StreamProgressTracker::ContinuousTransactionStreamTracker(stream_tracker) => stream_tracker,
+        unexpected_tracker => {
+            panic!(
+                "Expected continuous transaction stream tracker but got {:?}",
+                unexpected_tracker
+            );
+        }
+    }
+}
+
+fn create_continuous_outputs_data_summary(
+    highest_synced_version: u64,
+    highest_synced_epoch: u64,
+) -> GlobalDataSummary {
+    let mut global_data_summary = GlobalDataSummary::empty();
+    global_data_summary.advertised_data.synced_ledger_infos = vec![create_ledger_info(
+        highest_synced_version,
+        highest_synced_epoch,
+        false,
+    )];
+    global_data_summary.advertised_data.transaction_outputs =
+        vec![CompleteDataRange::new(0, highest_synced_version).unwrap()];
+    global_data_summary
+        .optimal_chunk_sizes
+        .transaction_output_chunk_size = 10;
+
+    global_data_summary
+}
+
 fn create_epoch_ending_progress_tracker(
     start_epoch: u64,
     max_advertised_epoch: u64,
@@ -9,8 +9,9 @@ use crate::{
         new_streaming_service_client_listener_pair, ContinuouslyStreamTransactionOutputsRequest,
         ContinuouslyStreamTransactionsRequest, DataStreamingClient, GetAllAccountsRequest,
         GetAllEpochEndingLedgerInfosRequest, GetAllTransactionOutputsRequest,
-        GetAllTransactionsRequest, PayloadRefetchReason, RefetchNotificationPayloadRequest,
-        StreamRequest, StreamingServiceListener,
+        GetAllTransactionsRequest, NotificationFeedback, PayloadRefetchReason,
+        RefetchNotificationPayloadRequest, StreamRequest, StreamingServiceListener,
+        TerminateStreamRequest,
     },
 };
 use channel::{diem_channel, message_queues::QueueStyle};
@@ -42,15 +43,18 @@ fn test_get_all_accounts() {
 
     // Note the request we expect to receive on the streaming service side
     let request_version = 100;
+    let request_start_index = 10;
     let expected_request = StreamRequest::GetAllAccounts(GetAllAccountsRequest {
         version: request_version,
+        start_index: request_start_index,
     });
 
     // Spawn a new server thread to handle any account stream requests
     let _handler = spawn_service_and_expect_request(streaming_service_listener, expected_request);
 
     // Send an account stream request and verify we get a data stream listener
-    let response = block_on(streaming_service_client.get_all_accounts(request_version));
+    let response =
+        block_on(streaming_service_client.get_all_accounts(request_version, request_start_index));
     assert_ok!(response);
 }
 
@@ -218,6 +222,33 @@ fn test_refetch_notification_payloads() {
     assert_ok!(response);
 }
 
+#[test]
+fn test_terminate_stream() {
+    // Create a new streaming service client and listener
+    let (streaming_service_client, mut streaming_service_listener) =
+        new_streaming_service_client_listener_pair();
+
+    // Send a terminate stream request and verify the request is sent successfully
+    let request_notification_id = 19478;
+    let request_notification_feedback = NotificationFeedback::PayloadProofFailed;
+    let response = block_on(streaming_service_client.terminate_stream_with_feedback(
+        request_notification_id,
+        request_notification_feedback.clone(),
+    ));
+    assert_ok!(response);
+
+    // Verify the streaming service receives the expected request
+    let expected_request = StreamRequest::TerminateStream(TerminateStreamRequest {
+        notification_id: request_notification_id,
+        notification_feedback: request_notification_feedback,
+    });
+    let stream_request_message = streaming_service_listener
+        .select_next_some()
+        .now_or_never()
+        .unwrap();
+    assert_eq!(stream_request_message.stream_request, expected_request);
+}
+
 /// Spawns a new thread that listens to the given streaming service listener and
 /// responds successfully to any requests that match the specified `expected_request`.
 /// Otherwise, an error is returned.
@@ -29,7 +29,7 @@ async fn test_notifications_accounts() {
 
     // Request an account stream and get a data stream listener
     let mut stream_listener = streaming_client
-        .get_all_accounts(MAX_ADVERTISED_ACCOUNTS)
+        .get_all_accounts(MAX_ADVERTISED_ACCOUNTS, 0)
         .await
         .unwrap();
 
@@ -66,7 +66,7 @@ async fn test_notifications_accounts() {
                 );
             }
         } else {
-            if next_expected_index == TOTAL_NUM_ACCOUNTS + 1 {
+            if next_expected_index == TOTAL_NUM_ACCOUNTS {
                 return; // We hit the end of the stream!
             }
             panic!(
@@ -375,19 +375,19 @@ async fn test_stream_accounts() {
 
     // Request an account stream and verify we get a data stream listener
     let result = streaming_client
-        .get_all_accounts(MAX_ADVERTISED_ACCOUNTS - 1)
+        .get_all_accounts(MAX_ADVERTISED_ACCOUNTS - 1, 0)
         .await;
     assert_ok!(result);
 
     // Request a stream where accounts are missing (we are lower than advertised)
     let result = streaming_client
-        .get_all_accounts(MIN_ADVERTISED_ACCOUNTS - 1)
+        .get_all_accounts(MIN_ADVERTISED_ACCOUNTS - 1, 0)
         .await;
     assert_matches!(result, Err(Error::DataIsUnavailable(_)));
 
     // Request a stream where accounts are missing (we are lower than advertised)
     let result = streaming_client
-        .get_all_accounts(MAX_ADVERTISED_EPOCH + 1)
+        .get_all_accounts(MAX_ADVERTISED_EPOCH + 1, 0)
         .await;
     assert_matches!(result, Err(Error::DataIsUnavailable(_)));
 }
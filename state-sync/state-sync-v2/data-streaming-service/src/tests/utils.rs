@@ -6,6 +6,7 @@ use diem_crypto::{ed25519::Ed25519PrivateKey, HashValue, PrivateKey, SigningKey,
 use diem_data_client::{
     AdvertisedData, DiemDataClient, GlobalDataSummary, OptimalChunkSizes, Response, ResponseError,
 };
+use diem_infallible::Mutex;
 use diem_types::{
     account_address::AccountAddress,
     account_state_blob::AccountStatesChunkWithProof,
@@ -23,7 +24,9 @@ use diem_types::{
 };
 use rand::{rngs::OsRng, Rng};
 use std::{
+    cmp,
     collections::{BTreeMap, HashMap},
+    sync::Arc,
     thread,
     time::Duration,
 };
@@ -51,6 +54,7 @@ pub const MAX_NOTIFICATION_TIMEOUT_SECS: u64 = 5;
 pub struct MockDiemDataClient {
     pub epoch_ending_ledger_infos: HashMap<Epoch, LedgerInfoWithSignatures>,
     pub synced_ledger_infos: Vec<LedgerInfoWithSignatures>,
+    pub bad_responses: Arc<Mutex<Vec<(u64, ResponseError)>>>,
 }
 
 impl MockDiemDataClient {
@@ -61,6 +65,7 @@ impl MockDiemDataClient {
         Self {
             epoch_ending_ledger_infos,
             synced_ledger_infos,
+            bad_responses: Arc::new(Mutex::new(vec![])),
         }
     }
 
@@ -153,6 +158,53 @@ impl DiemDataClient for MockDiemDataClient {
         }
     }
 
+    async fn get_new_transaction_outputs_with_proof(
+        &self,
+        known_version: Version,
+        known_epoch: Epoch,
+    ) -> Result<
+        Response<(TransactionOutputListWithProof, LedgerInfoWithSignatures)>,
+        diem_data_client::Error,
+    > {
+        self.emulate_network_latencies();
+
+        // Select the target ledger info (the end of the known epoch or the highest)
+        let highest_ledger_info = self.synced_ledger_infos.last().unwrap().clone();
+        let target_ledger_info = if known_epoch < highest_ledger_info.ledger_info().epoch() {
+            self.epoch_ending_ledger_infos
+                .get(&known_epoch)
+                .unwrap()
+                .clone()
+        } else {
+            highest_ledger_info
+        };
+
+        // Emulate a subscription timeout if there's no new data
+        let target_version = target_ledger_info.ledger_info().version();
+        if known_version >= target_version {
+            return Err(diem_data_client::Error::TimeoutWaitingForResponse(
+                "No new data is available!".into(),
+            ));
+        }
+
+        // Create the new transactions and transaction outputs
+        let start_version = known_version + 1;
+        let end_version = cmp::min(target_version, known_version + 100);
+        let mut transactions_and_outputs = vec![];
+        for _ in start_version..=end_version {
+            transactions_and_outputs.push((create_transaction(), create_transaction_output()));
+        }
+
+        // Create a transaction output list with an empty proof
+        let mut output_list_with_proof = TransactionOutputListWithProof::new_empty();
+        output_list_with_proof.first_transaction_output_version = Some(start_version);
+        output_list_with_proof.transactions_and_outputs = transactions_and_outputs;
+        Ok(create_data_client_response((
+            output_list_with_proof,
+            target_ledger_info,
+        )))
+    }
+
     async fn get_number_of_account_states(
         &self,
         _version: Version,
@@ -207,8 +259,10 @@ impl DiemDataClient for MockDiemDataClient {
         Ok(create_data_client_response(transaction_list_with_proof))
     }
 
-    fn notify_bad_response(&self, _response_id: u64, _response_error: ResponseError) {
-        // TODO(joshlind): implement this
+    fn notify_bad_response(&self, response_id: u64, response_error: ResponseError) {
+        self.bad_responses
+            .lock()
+            .push((response_id, response_error));
     }
 }
 
@@ -318,7 +372,7 @@ fn create_synced_ledger_infos(
 }
 
 /// Creates a simple test transaction
-fn create_transaction() -> Transaction {
+pub fn create_transaction() -> Transaction {
     let private_key = Ed25519PrivateKey::generate_for_testing();
     let public_key = private_key.public_key();
 
@@ -340,7 +394,7 @@ fn create_transaction() -> Transaction {
 }
 
 /// Creates an empty transaction output
-fn create_transaction_output() -> TransactionOutput {
+pub fn create_transaction_output() -> TransactionOutput {
     TransactionOutput::new(WriteSet::default(), vec![], 0, TransactionStatus::Retry)
 }
 
@@ -0,0 +1,858 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    driver::DriverConfiguration,
+    error::Error,
+    logging::{LogEntry, LogSchema},
+    notification_handlers::CommitNotificationHandler,
+    storage_synchronizer::{CommittedChunk, StorageSynchronizerInterface},
+    utils,
+};
+use data_streaming_service::{
+    data_notification::{DataNotification, DataPayload},
+    data_stream::DataStreamListener,
+    streaming_client::{DataStreamingClient, Epoch},
+};
+use diem_config::config::BootstrappingMode;
+use diem_crypto::hash::SPARSE_MERKLE_PLACEHOLDER_HASH;
+use diem_data_client::GlobalDataSummary;
+use diem_logger::prelude::*;
+use diem_types::{
+    account_state_blob::AccountStatesChunkWithProof,
+    epoch_change::Verifier,
+    epoch_state::EpochState,
+    ledger_info::LedgerInfoWithSignatures,
+    on_chain_config::new_epoch_event_key,
+    protocol_spec::DpnProto,
+    transaction::{
+        default_protocol::TransactionOutputListWithProof, TransactionInfoTrait, Version,
+    },
+    waypoint::Waypoint,
+};
+use mempool_notifications::MempoolNotificationSender;
+use std::{cmp::Ordering, collections::BTreeMap, sync::Arc};
+use storage_interface::{DbReader, StateSnapshotReceiver};
+
+/// A simple container for verified epoch states and epoch ending ledger infos
+/// that have been fetched from the network.
+pub(crate) struct VerifiedEpochStates {
+    // If all advertised epoch ending ledger infos have been fetched
+    fetched_epoch_ending_ledger_infos: bool,
+
+    // The latest epoch state that has been verified by the node
+    latest_epoch_state: EpochState,
+
+    // A map from versions to epoch ending ledger infos fetched from the network
+    new_epoch_ending_ledger_infos: BTreeMap<Version, LedgerInfoWithSignatures>,
+
+    // If the node has successfully verified the waypoint
+    verified_waypoint: bool,
+}
+
+impl VerifiedEpochStates {
+    pub fn new(latest_epoch_state: EpochState, verified_waypoint: bool) -> Self {
+        Self {
+            fetched_epoch_ending_ledger_infos: false,
+            latest_epoch_state,
+            new_epoch_ending_ledger_infos: BTreeMap::new(),
+            verified_waypoint,
+        }
+    }
+
+    /// Returns true iff all advertised epoch ending ledger infos have been fetched
+    pub fn fetched_epoch_ending_ledger_infos(&self) -> bool {
+        self.fetched_epoch_ending_ledger_infos
+    }
+
+    /// Marks all advertised epoch ending ledger infos as fetched
+    pub fn set_fetched_epoch_ending_ledger_infos(&mut self) {
+        self.fetched_epoch_ending_ledger_infos = true;
+    }
+
+    /// Returns true iff the waypoint has been verified
+    pub fn verified_waypoint(&self) -> bool {
+        self.verified_waypoint
+    }
+
+    /// Returns the next epoch for which we require an epoch ending ledger info
+    pub fn next_epoch_to_fetch(&self) -> Epoch {
+        self.latest_epoch_state.epoch
+    }
+
+    /// Verifies the given epoch ending ledger info (using the latest verified
+    /// epoch state and the waypoint) and updates the latest epoch state.
+    pub fn update_verified_epoch_states(
+        &mut self,
+        epoch_ending_ledger_info: &LedgerInfoWithSignatures,
+        waypoint: &Waypoint,
+    ) -> Result<(), Error> {
+        let ledger_info = epoch_ending_ledger_info.ledger_info();
+
+        // Verify the ledger info against the latest epoch state
+        self.latest_epoch_state
+            .verify(epoch_ending_ledger_info)
+            .map_err(|error| {
+                Error::VerificationError(format!(
+                    "Ledger info failed verification against the latest epoch state: {:?}",
+                    error
+                ))
+            })?;
+
+        // Verify the ledger info against the waypoint (if required)
+        if !self.verified_waypoint {
+            let waypoint_version = waypoint.version();
+            match ledger_info.version().cmp(&waypoint_version) {
+                Ordering::Equal => {
+                    waypoint.verify(ledger_info).map_err(|error| {
+                        Error::VerificationError(format!(
+                            "Ledger info failed verification against the waypoint: {:?}",
+                            error
+                        ))
+                    })?;
+                    self.verified_waypoint = true;
+                }
+                Ordering::Greater => {
+                    return Err(Error::VerificationError(format!(
+                        "Failed to verify the waypoint: ledger info version is too high! Waypoint version: {:?}, ledger info version: {:?}",
+                        waypoint_version,
+                        ledger_info.version()
+                    )));
+                }
+                Ordering::Less => {} // The waypoint is verified by a later ledger info
+            }
+        }
+
+        // Update the latest epoch state
+        let next_epoch_state = ledger_info.next_epoch_state().ok_or_else(|| {
+            Error::VerificationError(format!(
+                "The ledger info does not end the epoch: {:?}",
+                ledger_info
+            ))
+        })?;
+        self.latest_epoch_state = next_epoch_state.clone();
+        self.new_epoch_ending_ledger_infos
+            .insert(ledger_info.version(), epoch_ending_ledger_info.clone());
+
+        Ok(())
+    }
+
+    /// Returns the first verified epoch ending ledger info with a version
+    /// greater than or equal to the given `version` (if any).
+    pub fn next_epoch_ending_ledger_info(
+        &self,
+        version: Version,
+    ) -> Option<LedgerInfoWithSignatures> {
+        self.new_epoch_ending_ledger_infos
+            .range(version..)
+            .next()
+            .map(|(_, ledger_info)| ledger_info.clone())
+    }
+
+    /// Returns the verified epoch ending ledger info with the highest version
+    /// (if any).
+    pub fn highest_epoch_ending_ledger_info(&self) -> Option<LedgerInfoWithSignatures> {
+        self.new_epoch_ending_ledger_infos
+            .values()
+            .next_back()
+            .cloned()
+    }
+
+    /// Returns the verified epoch ending ledger info at exactly the given
+    /// `version` (if any).
+    pub fn get_epoch_ending_ledger_info(
+        &self,
+        version: Version,
+    ) -> Option<LedgerInfoWithSignatures> {
+        self.new_epoch_ending_ledger_infos.get(&version).cloned()
+    }
+}
+
+/// A simple container for the state of an account state snapshot sync, i.e.,
+/// the download and restore of all account states at a single epoch ending
+/// version.
+struct AccountStateSyncer {
+    // The epoch ending ledger info at the version of the snapshot
+    ledger_info_to_sync: Option<LedgerInfoWithSignatures>,
+
+    // The next account index expected on the account states stream
+    next_account_index_to_process: u64,
+
+    // The receiver that restores the account states into storage
+    state_snapshot_receiver: Option<Box<dyn StateSnapshotReceiver>>,
+
+    // The (verified) transaction output at the version of the snapshot
+    transaction_output_to_sync: Option<TransactionOutputListWithProof>,
+}
+
+impl AccountStateSyncer {
+    fn new() -> Self {
+        Self {
+            ledger_info_to_sync: None,
+            next_account_index_to_process: 0,
+            state_snapshot_receiver: None,
+            transaction_output_to_sync: None,
+        }
+    }
+}
+
+/// A simple component that manages the bootstrapping of the node. The node is
+/// bootstrapped once it has verified the waypoint and synced up to the latest
+/// epoch ending ledger info advertised by the network.
+///
+/// If the node is configured to download the latest account states (and has
+/// only synced genesis), it first restores a snapshot of all account states at
+/// the latest verified epoch ending version. Restore progress is persisted by
+/// storage, so an interrupted snapshot resumes from the last restored account.
+/// Otherwise, nodes replay all transaction outputs (or transactions) from
+/// their latest synced version.
+pub struct Bootstrapper<StorageSyncer, StreamingClient> {
+    // The state of the account state snapshot sync (if any)
+    account_state_syncer: AccountStateSyncer,
+
+    // The currently active data stream (provided by the data streaming service)
+    active_data_stream: Option<DataStreamListener>,
+
+    // The verified ledger info that the active transaction (or output) stream
+    // syncs to. The proofs of the streamed data are relative to this ledger info.
+    active_stream_target: Option<LedgerInfoWithSignatures>,
+
+    // If the node has completed bootstrapping
+    bootstrapped: bool,
+
+    // The config of the state sync driver
+    driver_configuration: DriverConfiguration,
+
+    // The highest epoch requested by the active epoch ending ledger info stream
+    highest_epoch_to_fetch: Option<Epoch>,
+
+    // The storage to read from
+    storage: Arc<dyn DbReader<DpnProto>>,
+
+    // The storage synchronizer used to update local storage
+    storage_synchronizer: StorageSyncer,
+
+    // The client through which to stream data from the Diem network
+    streaming_service_client: StreamingClient,
+
+    // The epoch states verified by this node (held in memory)
+    verified_epoch_states: VerifiedEpochStates,
+}
+
+impl<
+        StorageSyncer: StorageSynchronizerInterface,
+        StreamingClient: DataStreamingClient + Send + Sync,
+    > Bootstrapper<StorageSyncer, StreamingClient>
+{
+    pub fn new(
+        driver_configuration: DriverConfiguration,
+        storage: Arc<dyn DbReader<DpnProto>>,
+        storage_synchronizer: StorageSyncer,
+        streaming_service_client: StreamingClient,
+    ) -> Self {
+        // Load the latest epoch state and ledger info from storage
+        let latest_epoch_state = utils::fetch_latest_epoch_state(&storage)
+            .expect("Unable to fetch the latest epoch state from storage!");
+        let latest_ledger_info = utils::fetch_latest_ledger_info(&storage)
+            .expect("Unable to fetch the latest ledger info from storage!");
+
+        // If storage is already beyond the waypoint, there's nothing to verify
+        let verified_waypoint =
+            latest_ledger_info.ledger_info().version() >= driver_configuration.waypoint.version();
+        let verified_epoch_states = VerifiedEpochStates::new(latest_epoch_state, verified_waypoint);
+
+        Self {
+            account_state_syncer: AccountStateSyncer::new(),
+            active_data_stream: None,
+            active_stream_target: None,
+            bootstrapped: false,
+            driver_configuration,
+            highest_epoch_to_fetch: None,
+            storage,
+            storage_synchronizer,
+            streaming_service_client,
+            verified_epoch_states,
+        }
+    }
+
+    /// Returns true iff the node has completed bootstrapping
+    pub fn is_bootstrapped(&self) -> bool {
+        self.bootstrapped
+    }
+
+    /// Checks if the bootstrapper is making progress and drives it forward
+    /// (e.g., by processing new data notifications or creating new streams).
+    pub async fn drive_progress<M: MempoolNotificationSender>(
+        &mut self,
+        global_data_summary: &GlobalDataSummary,
+        commit_notification_handler: &mut CommitNotificationHandler<M>,
+    ) -> Result<(), Error> {
+        if self.bootstrapped {
+            return Ok(());
+        }
+
+        if self.active_data_stream.is_some() {
+            self.process_active_stream_notifications(commit_notification_handler)
+                .await
+        } else {
+            self.initialize_active_data_stream(global_data_summary, commit_notification_handler)
+                .await
+        }
+    }
+
+    /// Creates a new data stream for the next set of data we need to fetch
+    /// (or marks the node as bootstrapped if there is nothing left to fetch).
+    async fn initialize_active_data_stream<M: MempoolNotificationSender>(
+        &mut self,
+        global_data_summary: &GlobalDataSummary,
+        commit_notification_handler: &mut CommitNotificationHandler<M>,
+    ) -> Result<(), Error> {
+        // Fetch and verify all epoch ending ledger infos first
+        if !self
+            .verified_epoch_states
+            .fetched_epoch_ending_ledger_infos()
+        {
+            return self
+                .fetch_epoch_ending_ledger_infos(global_data_summary)
+                .await;
+        }
+
+        // Download the latest account states (if the node has only synced genesis)
+        let next_version_to_sync = utils::fetch_next_version_to_sync(&self.storage)?;
+        let bootstrapping_mode = self.driver_configuration.config.bootstrapping_mode;
+        if bootstrapping_mode == BootstrappingMode::DownloadLatestAccountStates
+            && next_version_to_sync == 1
+        {
+            if let Some(ledger_info_to_sync) = self.get_account_states_target()? {
+                return self
+                    .fetch_account_states(ledger_info_to_sync, commit_notification_handler)
+                    .await;
+            }
+        }
+
+        // Sync to the next verified epoch ending ledger info
+        let target_ledger_info = match self
+            .verified_epoch_states
+            .next_epoch_ending_ledger_info(next_version_to_sync)
+        {
+            Some(target_ledger_info) => target_ledger_info,
+            None => {
+                info!(LogSchema::new(LogEntry::Bootstrapper)
+                    .synced_version(next_version_to_sync.saturating_sub(1))
+                    .message("The node has completed bootstrapping!"));
+                self.bootstrapped = true;
+                return Ok(());
+            }
+        };
+
+        let target_version = target_ledger_info.ledger_info().version();
+        let data_stream = match bootstrapping_mode {
+            BootstrappingMode::ApplyTransactionOutputsFromGenesis
+            | BootstrappingMode::DownloadLatestAccountStates => {
+                self.streaming_service_client
+                    .get_all_transaction_outputs(
+                        next_version_to_sync,
+                        target_version,
+                        target_version,
+                    )
+                    .await?
+            }
+            BootstrappingMode::ExecuteTransactionsFromGenesis => {
+                self.streaming_service_client
+                    .get_all_transactions(
+                        next_version_to_sync,
+                        target_version,
+                        target_version,
+                        false,
+                    )
+                    .await?
+            }
+        };
+        self.active_data_stream = Some(data_stream);
+        self.active_stream_target = Some(target_ledger_info);
+
+        Ok(())
+    }
+
+    /// Returns the epoch ending ledger info at which to download the account
+    /// states. If a previous restore was interrupted at a verified epoch ending
+    /// version, that version is resumed. Otherwise, the highest verified epoch
+    /// ending ledger info is used (if it's beyond genesis).
+    fn get_account_states_target(&self) -> Result<Option<LedgerInfoWithSignatures>, Error> {
+        if let Some(ledger_info_to_sync) = &self.account_state_syncer.ledger_info_to_sync {
+            return Ok(Some(ledger_info_to_sync.clone()));
+        }
+
+        let restore_version =
+            self.storage
+                .get_state_snapshot_restore_version()
+                .map_err(|error| {
+                    Error::StorageError(format!(
+                        "Failed to get the state snapshot restore version: {:?}",
+                        error
+                    ))
+                })?;
+        let ledger_info_to_sync = restore_version
+            .and_then(|version| {
+                self.verified_epoch_states
+                    .get_epoch_ending_ledger_info(version)
+            })
+            .or_else(|| {
+                self.verified_epoch_states
+                    .highest_epoch_ending_ledger_info()
+            })
+            .filter(|ledger_info| ledger_info.ledger_info().version() > 0);
+        Ok(ledger_info_to_sync)
+    }
+
+    /// Creates a new stream for the next piece of data required by the account
+    /// state snapshot at `ledger_info_to_sync`: first the transaction output at
+    /// the snapshot version (to learn the expected state root), then the
+    /// account states themselves (starting at the first unrestored account).
+    /// If all account states were restored before the snapshot could be
+    /// finalized (e.g., the node restarted), the snapshot is finalized directly.
+    async fn fetch_account_states<M: MempoolNotificationSender>(
+        &mut self,
+        ledger_info_to_sync: LedgerInfoWithSignatures,
+        commit_notification_handler: &mut CommitNotificationHandler<M>,
+    ) -> Result<(), Error> {
+        let version = ledger_info_to_sync.ledger_info().version();
+        self.account_state_syncer.ledger_info_to_sync = Some(ledger_info_to_sync.clone());
+
+        let data_stream = match &self.account_state_syncer.transaction_output_to_sync {
+            None => {
+                self.streaming_service_client
+                    .get_all_transaction_outputs(version, version, version)
+                    .await?
+            }
+            Some(transaction_output_to_sync) => {
+                if self.account_state_syncer.state_snapshot_receiver.is_none() {
+                    let expected_root_hash = transaction_output_to_sync
+                        .proof
+                        .transaction_infos
+                        .first()
+                        .map(|transaction_info| transaction_info.state_root_hash())
+                        .ok_or_else(|| {
+                            Error::UnexpectedError(
+                                "The transaction output to sync is missing a transaction info!"
+                                    .into(),
+                            )
+                        })?;
+                    let state_snapshot_receiver = self
+                        .storage_synchronizer
+                        .get_state_snapshot_receiver(version, expected_root_hash)?;
+                    if state_snapshot_receiver.is_finished() {
+                        return self
+                            .finalize_account_state_snapshot(
+                                commit_notification_handler,
+                                ledger_info_to_sync,
+                            )
+                            .await;
+                    }
+                    self.account_state_syncer.state_snapshot_receiver =
+                        Some(state_snapshot_receiver);
+                }
+                let start_index = self
+                    .account_state_syncer
+                    .state_snapshot_receiver
+                    .as_ref()
+                    .map(|receiver| receiver.num_accounts_restored())
+                    .unwrap_or(0);
+                info!(LogSchema::new(LogEntry::Bootstrapper)
+                    .ledger_info(&ledger_info_to_sync)
+                    .message(&format!(
+                        "Fetching the account states, starting at account index: {:?}",
+                        start_index
+                    )));
+                self.account_state_syncer.next_account_index_to_process = start_index;
+                self.streaming_service_client
+                    .get_all_accounts(version, start_index)
+                    .await?
+            }
+        };
+        self.active_data_stream = Some(data_stream);
+        self.active_stream_target = Some(ledger_info_to_sync);
+
+        Ok(())
+    }
+
+    /// Creates a new stream to fetch the epoch ending ledger infos advertised
+    /// by the network that the node hasn't yet verified.
+    async fn fetch_epoch_ending_ledger_infos(
+        &mut self,
+        global_data_summary: &GlobalDataSummary,
+    ) -> Result<(), Error> {
+        let highest_advertised_epoch = global_data_summary
+            .advertised_data
+            .epoch_ending_ledger_infos
+            .iter()
+            .map(|epoch_range| epoch_range.highest())
+            .max();
+        let next_epoch_to_fetch = self.verified_epoch_states.next_epoch_to_fetch();
+
+        match highest_advertised_epoch {
+            Some(highest_advertised_epoch) if next_epoch_to_fetch <= highest_advertised_epoch => {
+                let data_stream = self
+                    .streaming_service_client
+                    .get_all_epoch_ending_ledger_infos(next_epoch_to_fetch)
+                    .await?;
+                self.active_data_stream = Some(data_stream);
+                self.highest_epoch_to_fetch = Some(highest_advertised_epoch);
+                Ok(())
+            }
+            _ => {
+                // There are no new epoch ending ledger infos to fetch
+                if self.verified_epoch_states.verified_waypoint() {
+                    self.verified_epoch_states
+                        .set_fetched_epoch_ending_ledger_infos();
+                    Ok(())
+                } else {
+                    Err(Error::AdvertisedDataError(format!(
+                        "Our waypoint is unverified, but there's no higher epoch ending ledger infos \
+                        advertised! Waypoint version: {:?}, next epoch to fetch: {:?}",
+                        self.driver_configuration.waypoint.version(),
+                        next_epoch_to_fetch
+                    )))
+                }
+            }
+        }
+    }
+
+    /// Processes any notifications already received on the active stream,
+    /// waiting up to the maximum stream wait time for the first notification.
+    async fn process_active_stream_notifications<M: MempoolNotificationSender>(
+        &mut self,
+        commit_notification_handler: &mut CommitNotificationHandler<M>,
+    ) -> Result<(), Error> {
+        let max_stream_wait_time_ms = self.driver_configuration.config.max_stream_wait_time_ms;
+        let mut data_notification = match self.active_data_stream.as_mut() {
+            Some(active_data_stream) => {
+                let data_notification =
+                    utils::get_data_notification(max_stream_wait_time_ms, active_data_stream).await;
+                if data_notification.is_err() {
+                    self.reset_active_stream();
+                }
+                data_notification?
+            }
+            None => return Ok(()),
+        };
+
+        loop {
+            let notification_id = data_notification.notification_id;
+            let result = self
+                .process_data_notification(data_notification, commit_notification_handler)
+                .await;
+            if let Err(error) = &result {
+                utils::terminate_stream_with_feedback(
+                    &self.streaming_service_client,
+                    notification_id,
+                    error,
+                )
+                .await;
+                self.reset_active_stream();
+                return result;
+            }
+
+            // Process any other notifications that are already available
+            match self
+                .active_data_stream
+                .as_mut()
+                .and_then(utils::get_ready_data_notification)
+            {
+                Some(next_data_notification) => data_notification = next_data_notification,
+                None => return Ok(()),
+            }
+        }
+    }
+
+    /// Processes a single data notification received on the active stream
+    async fn process_data_notification<M: MempoolNotificationSender>(
+        &mut self,
+        data_notification: DataNotification,
+        commit_notification_handler: &mut CommitNotificationHandler<M>,
+    ) -> Result<(), Error> {
+        let bootstrapping_mode = self.driver_configuration.config.bootstrapping_mode;
+        match data_notification.data_payload {
+            DataPayload::EpochEndingLedgerInfos(epoch_ending_ledger_infos) => {
+                self.process_epoch_ending_ledger_infos(epoch_ending_ledger_infos)
+            }
+            DataPayload::AccountStatesWithProof(account_states_chunk_with_proof)
+                if self.account_state_syncer.state_snapshot_receiver.is_some() =>
+            {
+                self.process_account_states(
+                    commit_notification_handler,
+                    account_states_chunk_with_proof,
+                )
+                .await
+            }
+            DataPayload::TransactionOutputsWithProof(output_list_with_proof)
+                if self.account_state_syncer.ledger_info_to_sync.is_some()
+                    && self
+                        .account_state_syncer
+                        .transaction_output_to_sync
+                        .is_none() =>
+            {
+                self.process_snapshot_transaction_output(output_list_with_proof)
+            }
+            DataPayload::TransactionOutputsWithProof(output_list_with_proof)
+                if bootstrapping_mode == BootstrappingMode::ApplyTransactionOutputsFromGenesis
+                    || bootstrapping_mode == BootstrappingMode::DownloadLatestAccountStates =>
+            {
+                let target_ledger_info = self.get_active_stream_target()?;
+                let chunk_end_version = utils::apply_and_commit_transaction_outputs(
+                    &self.storage,
+                    &mut self.storage_synchronizer,
+                    commit_notification_handler,
+                    output_list_with_proof,
+                    &target_ledger_info,
+                )
+                .await?;
+                self.check_target_reached(&target_ledger_info, chunk_end_version);
+                Ok(())
+            }
+            DataPayload::TransactionsWithProof(transaction_list_with_proof)
+                if bootstrapping_mode == BootstrappingMode::ExecuteTransactionsFromGenesis =>
+            {
+                let target_ledger_info = self.get_active_stream_target()?;
+                let chunk_end_version = utils::execute_and_commit_transactions(
+                    &self.storage,
+                    &mut self.storage_synchronizer,
+                    commit_notification_handler,
+                    transaction_list_with_proof,
+                    &target_ledger_info,
+                )
+                .await?;
+                self.check_target_reached(&target_ledger_info, chunk_end_version);
+                Ok(())
+            }
+            _ => Err(Error::InvalidPayload(format!(
+                "Received an unexpected data payload type while bootstrapping! Notification ID: {:?}",
+                data_notification.notification_id
+            ))),
+        }
+    }
+
+    /// Verifies the given epoch ending ledger infos and updates the verified
+    /// epoch states. Resets the active stream once all epochs have been fetched.
+    fn process_epoch_ending_ledger_infos(
+        &mut self,
+        epoch_ending_ledger_infos: Vec<LedgerInfoWithSignatures>,
+    ) -> Result<(), Error> {
+        if self.highest_epoch_to_fetch.is_none() {
+            return Err(Error::InvalidPayload(
+                "Received epoch ending ledger infos, but we're not fetching epochs!".into(),
+            ));
+        }
+
+        for epoch_ending_ledger_info in &epoch_ending_ledger_infos {
+            self.verified_epoch_states.update_verified_epoch_states(
+                epoch_ending_ledger_info,
+                &self.driver_configuration.waypoint,
+            )?;
+        }
+
+        // Once we've fetched all requested epochs, the stream is complete
+        if let Some(highest_epoch_to_fetch) = self.highest_epoch_to_fetch {
+            if self.verified_epoch_states.next_epoch_to_fetch() > highest_epoch_to_fetch {
+                self.reset_active_stream();
+            }
+        }
+        Ok(())
+    }
+
+    /// Verifies the transaction output at the version of the account state
+    /// snapshot and holds on to it until the snapshot can be finalized.
+    fn process_snapshot_transaction_output(
+        &mut self,
+        output_list_with_proof: TransactionOutputListWithProof,
+    ) -> Result<(), Error> {
+        let target_ledger_info = self.get_active_stream_target()?;
+        let version = target_ledger_info.ledger_info().version();
+        if output_list_with_proof.transactions_and_outputs.len() != 1 {
+            return Err(Error::InvalidPayload(format!(
+                "Expected a single transaction output for the account state snapshot! Found: {:?}",
+                output_list_with_proof.transactions_and_outputs.len()
+            )));
+        }
+        output_list_with_proof
+            .verify(target_ledger_info.ledger_info(), Some(version))
+            .map_err(|error| {
+                Error::VerificationError(format!(
+                    "Transaction output for the account state snapshot failed verification: {:?}",
+                    error
+                ))
+            })?;
+
+        self.account_state_syncer.transaction_output_to_sync = Some(output_list_with_proof);
+        self.reset_active_stream();
+        Ok(())
+    }
+
+    /// Adds the given account states to the state snapshot receiver. Once all
+    /// account states have been restored, the snapshot is finalized in storage
+    /// and the relevant components are notified of the commit.
+    async fn process_account_states<M: MempoolNotificationSender>(
+        &mut self,
+        commit_notification_handler: &mut CommitNotificationHandler<M>,
+        account_states_chunk_with_proof: AccountStatesChunkWithProof,
+    ) -> Result<(), Error> {
+        // Verify the chunk starts at the expected account index
+        let next_account_index = self.account_state_syncer.next_account_index_to_process;
+        if account_states_chunk_with_proof.first_index != next_account_index {
+            return Err(Error::InvalidPayload(format!(
+                "Received an account states chunk with an unexpected first index! Expected: {:?}, found: {:?}",
+                next_account_index, account_states_chunk_with_proof.first_index
+            )));
+        }
+
+        // Restore the account states in storage (this verifies the proof)
+        let all_accounts_restored = account_states_chunk_with_proof
+            .proof
+            .right_siblings()
+            .iter()
+            .all(|sibling| *sibling == *SPARSE_MERKLE_PLACEHOLDER_HASH);
+        let state_snapshot_receiver = self
+            .account_state_syncer
+            .state_snapshot_receiver
+            .as_mut()
+            .ok_or_else(|| {
+                Error::UnexpectedError("The state snapshot receiver is missing!".into())
+            })?;
+        state_snapshot_receiver
+            .add_chunk(
+                account_states_chunk_with_proof.account_blobs,
+                account_states_chunk_with_proof.proof,
+            )
+            .map_err(|error| {
+                Error::StorageError(format!(
+                    "Failed to add the account states chunk to the snapshot: {:?}",
+                    error
+                ))
+            })?;
+        self.account_state_syncer.next_account_index_to_process = account_states_chunk_with_proof
+            .last_index
+            .checked_add(1)
+            .ok_or_else(|| {
+                Error::IntegerOverflow("The next account index to process has overflown!".into())
+            })?;
+        if !all_accounts_restored {
+            return Ok(());
+        }
+
+        // All account states have been restored. Finish the restore.
+        let state_snapshot_receiver = self
+            .account_state_syncer
+            .state_snapshot_receiver
+            .take()
+            .ok_or_else(|| {
+                Error::UnexpectedError("The state snapshot receiver is missing!".into())
+            })?;
+        state_snapshot_receiver.finish_box().map_err(|error| {
+            Error::StorageError(format!(
+                "Failed to finish the account state snapshot: {:?}",
+                error
+            ))
+        })?;
+        let ledger_info_to_sync = self.get_active_stream_target()?;
+        self.finalize_account_state_snapshot(commit_notification_handler, ledger_info_to_sync)
+            .await
+    }
+
+    /// Finalizes the account state snapshot at `ledger_info_to_sync` (once
+    /// all account states have been restored) and notifies the relevant
+    /// components of the commit.
+    async fn finalize_account_state_snapshot<M: MempoolNotificationSender>(
+        &mut self,
+        commit_notification_handler: &mut CommitNotificationHandler<M>,
+        ledger_info_to_sync: LedgerInfoWithSignatures,
+    ) -> Result<(), Error> {
+        let transaction_output_to_sync = self
+            .account_state_syncer
+            .transaction_output_to_sync
+            .take()
+            .ok_or_else(|| {
+                Error::UnexpectedError("The transaction output to sync is missing!".into())
+            })?;
+        let (committed_transactions, reconfiguration_events) = transaction_output_to_sync
+            .transactions_and_outputs
+            .iter()
+            .fold(
+                (vec![], vec![]),
+                |(mut transactions, mut events), (transaction, output)| {
+                    transactions.push(transaction.clone());
+                    events.extend(
+                        output
+                            .events()
+                            .iter()
+                            .filter(|event| *event.key() == new_epoch_event_key())
+                            .cloned(),
+                    );
+                    (transactions, events)
+                },
+            );
+        let version = ledger_info_to_sync.ledger_info().version();
+        self.storage_synchronizer.finalize_state_snapshot(
+            version,
+            transaction_output_to_sync,
+            ledger_info_to_sync.clone(),
+        )?;
+        info!(LogSchema::new(LogEntry::Bootstrapper)
+            .ledger_info(&ledger_info_to_sync)
+            .synced_version(version)
+            .message("Finished restoring the account state snapshot!"));
+        self.account_state_syncer = AccountStateSyncer::new();
+        self.reset_active_stream();
+
+        // Notify the relevant components of the new commit
+        if let Err(error) = commit_notification_handler
+            .handle_committed_chunk(CommittedChunk {
+                committed_transactions,
+                reconfiguration_events,
+            })
+            .await
+        {
+            error!(LogSchema::new(LogEntry::NotificationHandler)
+                .error(&error)
+                .synced_version(version)
+                .message("Failed to handle the notifications for the state snapshot!"));
+        }
+        Ok(())
+    }
+
+    /// Returns the target ledger info of the active transaction (or output) stream
+    fn get_active_stream_target(&self) -> Result<LedgerInfoWithSignatures, Error> {
+        self.active_stream_target.clone().ok_or_else(|| {
+            Error::InvalidPayload(
+                "Received a data chunk, but there's no active stream target!".into(),
+            )
+        })
+    }
+
+    /// Resets the active stream if we've synced to the stream target
+    fn check_target_reached(
+        &mut self,
+        target_ledger_info: &LedgerInfoWithSignatures,
+        chunk_end_version: Version,
+    ) {
+        if chunk_end_version >= target_ledger_info.ledger_info().version() {
+            debug!(LogSchema::new(LogEntry::Bootstrapper)
+                .ledger_info(target_ledger_info)
+                .synced_version(chunk_end_version)
+                .message("Synced to the epoch ending ledger info!"));
+            self.reset_active_stream();
+        }
+    }
+
+    /// Drops the active data stream (if any), so that a new stream will be
+    /// created on the next progress check.
+    fn reset_active_stream(&mut self) {
+        // The state snapshot receiver may hold account states that haven't yet
+        // been persisted. Drop it so that the next receiver resumes from the
+        // last persisted account (and not from the middle of a chunk).
+        self.account_state_syncer.state_snapshot_receiver = None;
+
+        self.active_data_stream = None;
+        self.active_stream_target = None;
+        self.highest_epoch_to_fetch = None;
+    }
+}
@@ -0,0 +1,350 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    driver::DriverConfiguration,
+    error::Error,
+    logging::{LogEntry, LogSchema},
+    notification_handlers::CommitNotificationHandler,
+    storage_synchronizer::StorageSynchronizerInterface,
+    utils,
+};
+use data_streaming_service::{
+    data_notification::{DataNotification, DataPayload},
+    data_stream::DataStreamListener,
+    streaming_client::DataStreamingClient,
+};
+use diem_config::config::ContinuousSyncingMode;
+use diem_logger::prelude::*;
+use diem_types::{
+    epoch_change::Verifier, ledger_info::LedgerInfoWithSignatures, protocol_spec::DpnProto,
+    transaction::Version,
+};
+use mempool_notifications::MempoolNotificationSender;
+use std::sync::Arc;
+use storage_interface::DbReader;
+
+/// A simple component that manages the continuous syncing of the node once
+/// bootstrapping has completed. If consensus has requested that we sync to a
+/// specific target, the syncer will stop at that target.
+pub struct ContinuousSyncer<StorageSyncer, StreamingClient> {
+    // The currently active data stream (provided by the data streaming service)
+    active_data_stream: Option<DataStreamListener>,
+
+    // The consensus sync target that the active stream was created for (if any)
+    active_sync_target: Option<LedgerInfoWithSignatures>,
+
+    // The verified target of the active stream. This is only set for streams
+    // bounded by a consensus sync target (continuous streams provide a target
+    // ledger info with each notification).
+    bounded_stream_target: Option<LedgerInfoWithSignatures>,
+
+    // The config of the state sync driver
+    driver_configuration: DriverConfiguration,
+
+    // The storage to read from
+    storage: Arc<dyn DbReader<DpnProto>>,
+
+    // The storage synchronizer used to update local storage
+    storage_synchronizer: StorageSyncer,
+
+    // The client through which to stream data from the Diem network
+    streaming_service_client: StreamingClient,
+}
+
+impl<
+        StorageSyncer: StorageSynchronizerInterface,
+        StreamingClient: DataStreamingClient + Send + Sync,
+    > ContinuousSyncer<StorageSyncer, StreamingClient>
+{
+    pub fn new(
+        driver_configuration: DriverConfiguration,
+        storage: Arc<dyn DbReader<DpnProto>>,
+        storage_synchronizer: StorageSyncer,
+        streaming_service_client: StreamingClient,
+    ) -> Self {
+        Self {
+            active_data_stream: None,
+            active_sync_target: None,
+            bounded_stream_target: None,
+            driver_configuration,
+            storage,
+            storage_synchronizer,
+            streaming_service_client,
+        }
+    }
+
+    /// Checks if the continuous syncer is making progress and drives it
+    /// forward. If `sync_target` is specified, the node will only sync up to
+    /// the target (and not beyond it).
+    pub async fn drive_progress<M: MempoolNotificationSender>(
+        &mut self,
+        sync_target: Option<LedgerInfoWithSignatures>,
+        commit_notification_handler: &mut CommitNotificationHandler<M>,
+    ) -> Result<(), Error> {
+        // If the sync target has changed, the active stream is no longer valid
+        if self.active_data_stream.is_some() && self.active_sync_target != sync_target {
+            self.reset_active_stream();
+        }
+
+        if self.active_data_stream.is_some() {
+            self.process_active_stream_notifications(commit_notification_handler)
+                .await
+        } else {
+            self.initialize_active_data_stream(sync_target).await
+        }
+    }
+
+    /// Creates a new data stream starting at the next version to sync. If
+    /// the sync target is within the current epoch, the stream is bounded by
+    /// the target. Otherwise, a continuous stream is created.
+    async fn initialize_active_data_stream(
+        &mut self,
+        sync_target: Option<LedgerInfoWithSignatures>,
+    ) -> Result<(), Error> {
+        let next_version_to_sync = utils::fetch_next_version_to_sync(&self.storage)?;
+        let latest_epoch_state = utils::fetch_latest_epoch_state(&self.storage)?;
+        let syncing_mode = self.driver_configuration.config.continuous_syncing_mode;
+
+        let bounded_stream_target = match &sync_target {
+            Some(sync_target) if sync_target.ledger_info().epoch() == latest_epoch_state.epoch => {
+                let target_version = sync_target.ledger_info().version();
+                if target_version < next_version_to_sync {
+                    return Ok(()); // We've already synced to the target
+                }
+
+                latest_epoch_state.verify(sync_target).map_err(|error| {
+                    Error::VerificationError(format!(
+                        "The sync target failed verification: {:?}",
+                        error
+                    ))
+                })?;
+                Some(sync_target.clone())
+            }
+            _ => None,
+        };
+
+        let data_stream = match (&bounded_stream_target, syncing_mode) {
+            (Some(target), ContinuousSyncingMode::ApplyTransactionOutputs) => {
+                let target_version = target.ledger_info().version();
+                self.streaming_service_client
+                    .get_all_transaction_outputs(
+                        next_version_to_sync,
+                        target_version,
+                        target_version,
+                    )
+                    .await?
+            }
+            (Some(target), ContinuousSyncingMode::ExecuteTransactions) => {
+                let target_version = target.ledger_info().version();
+                self.streaming_service_client
+                    .get_all_transactions(
+                        next_version_to_sync,
+                        target_version,
+                        target_version,
+                        false,
+                    )
+                    .await?
+            }
+            (None, ContinuousSyncingMode::ApplyTransactionOutputs) => {
+                self.streaming_service_client
+                    .continuously_stream_transaction_outputs(
+                        next_version_to_sync,
+                        latest_epoch_state.epoch,
+                    )
+                    .await?
+            }
+            (None, ContinuousSyncingMode::ExecuteTransactions) => {
+                self.streaming_service_client
+                    .continuously_stream_transactions(
+                        next_version_to_sync,
+                        latest_epoch_state.epoch,
+                        false,
+                    )
+                    .await?
+            }
+        };
+        self.active_data_stream = Some(data_stream);
+        self.active_sync_target = sync_target;
+        self.bounded_stream_target = bounded_stream_target;
+
+        Ok(())
+    }
+
+    /// Processes any notifications already received on the active stream,
+    /// waiting up to the maximum stream wait time for the first notification.
+    async fn process_active_stream_notifications<M: MempoolNotificationSender>(
+        &mut self,
+        commit_notification_handler: &mut CommitNotificationHandler<M>,
+    ) -> Result<(), Error> {
+        let max_stream_wait_time_ms = self.driver_configuration.config.max_stream_wait_time_ms;
+        let mut data_notification = match self.active_data_stream.as_mut() {
+            Some(active_data_stream) => {
+                let data_notification =
+                    utils::get_data_notification(max_stream_wait_time_ms, active_data_stream).await;
+                if data_notification.is_err() {
+                    self.reset_active_stream();
+                }
+                data_notification?
+            }
+            None => return Ok(()),
+        };
+
+        loop {
+            let notification_id = data_notification.notification_id;
+            let result = self
+                .process_data_notification(data_notification, commit_notification_handler)
+                .await;
+            if let Err(error) = &result {
+                utils::terminate_stream_with_feedback(
+                    &self.streaming_service_client,
+                    notification_id,
+                    error,
+                )
+                .await;
+                self.reset_active_stream();
+                return result;
+            }
+
+            // Process any other notifications that are already available
+            match self
+                .active_data_stream
+                .as_mut()
+                .and_then(utils::get_ready_data_notification)
+            {
+                Some(next_data_notification) => data_notification = next_data_notification,
+                None => return Ok(()),
+            }
+        }
+    }
+
+    /// Processes a single data notification received on the active stream
+    async fn process_data_notification<M: MempoolNotificationSender>(
+        &mut self,
+        data_notification: DataNotification,
+        commit_notification_handler: &mut CommitNotificationHandler<M>,
+    ) -> Result<(), Error> {
+        let syncing_mode = self.driver_configuration.config.continuous_syncing_mode;
+        let (target_ledger_info, chunk_end_version) = match data_notification.data_payload {
+            DataPayload::ContinuousTransactionOutputsWithProof(
+                ledger_info_with_signatures,
+                output_list_with_proof,
+            ) if syncing_mode == ContinuousSyncingMode::ApplyTransactionOutputs => {
+                self.verify_ledger_info(&ledger_info_with_signatures)?;
+                let chunk_end_version = utils::apply_and_commit_transaction_outputs(
+                    &self.storage,
+                    &mut self.storage_synchronizer,
+                    commit_notification_handler,
+                    output_list_with_proof,
+                    &ledger_info_with_signatures,
+                )
+                .await?;
+                (ledger_info_with_signatures, chunk_end_version)
+            }
+            DataPayload::ContinuousTransactionsWithProof(
+                ledger_info_with_signatures,
+                transaction_list_with_proof,
+            ) if syncing_mode == ContinuousSyncingMode::ExecuteTransactions => {
+                self.verify_ledger_info(&ledger_info_with_signatures)?;
+                let chunk_end_version = utils::execute_and_commit_transactions(
+                    &self.storage,
+                    &mut self.storage_synchronizer,
+                    commit_notification_handler,
+                    transaction_list_with_proof,
+                    &ledger_info_with_signatures,
+                )
+                .await?;
+                (ledger_info_with_signatures, chunk_end_version)
+            }
+            DataPayload::TransactionOutputsWithProof(output_list_with_proof)
+                if syncing_mode == ContinuousSyncingMode::ApplyTransactionOutputs =>
+            {
+                let target_ledger_info = self.get_bounded_stream_target()?;
+                let chunk_end_version = utils::apply_and_commit_transaction_outputs(
+                    &self.storage,
+                    &mut self.storage_synchronizer,
+                    commit_notification_handler,
+                    output_list_with_proof,
+                    &target_ledger_info,
+                )
+                .await?;
+                (target_ledger_info, chunk_end_version)
+            }
+            DataPayload::TransactionsWithProof(transaction_list_with_proof)
+                if syncing_mode == ContinuousSyncingMode::ExecuteTransactions =>
+            {
+                let target_ledger_info = self.get_bounded_stream_target()?;
+                let chunk_end_version = utils::execute_and_commit_transactions(
+                    &self.storage,
+                    &mut self.storage_synchronizer,
+                    commit_notification_handler,
+                    transaction_list_with_proof,
+                    &target_ledger_info,
+                )
+                .await?;
+                (target_ledger_info, chunk_end_version)
+            }
+            _ => {
+                return Err(Error::InvalidPayload(format!(
+                    "Received an unexpected data payload type while continuously syncing! Notification ID: {:?}",
+                    data_notification.notification_id
+                )))
+            }
+        };
+
+        self.check_stream_progress(&target_ledger_info, chunk_end_version);
+        Ok(())
+    }
+
+    /// Verifies the given ledger info against the latest epoch state in storage
+    fn verify_ledger_info(&self, ledger_info: &LedgerInfoWithSignatures) -> Result<(), Error> {
+        let latest_epoch_state = utils::fetch_latest_epoch_state(&self.storage)?;
+        latest_epoch_state.verify(ledger_info).map_err(|error| {
+            Error::VerificationError(format!(
+                "Ledger info failed verification against the latest epoch state: {:?}",
+                error
+            ))
+        })
+    }
+
+    /// Returns the verified target of the active bounded stream
+    fn get_bounded_stream_target(&self) -> Result<LedgerInfoWithSignatures, Error> {
+        self.bounded_stream_target.clone().ok_or_else(|| {
+            Error::InvalidPayload(
+                "Received a bounded data chunk, but the active stream is continuous!".into(),
+            )
+        })
+    }
+
+    /// Resets the active stream if it has completed (i.e., we've reached the
+    /// bounded stream target), or if we've crossed an epoch boundary while
+    /// syncing to a consensus target (so that a bounded stream can be created
+    /// once the node reaches the epoch of the target).
+    fn check_stream_progress(
+        &mut self,
+        target_ledger_info: &LedgerInfoWithSignatures,
+        chunk_end_version: Version,
+    ) {
+        let reached_target = chunk_end_version >= target_ledger_info.ledger_info().version();
+        let bounded_stream_completed = self.bounded_stream_target.is_some() && reached_target;
+        let crossed_epoch_with_sync_target = self.active_sync_target.is_some()
+            && reached_target
+            && target_ledger_info.ledger_info().ends_epoch();
+
+        if bounded_stream_completed || crossed_epoch_with_sync_target {
+            debug!(LogSchema::new(LogEntry::ContinuousSyncer)
+                .ledger_info(target_ledger_info)
+                .synced_version(chunk_end_version)
+                .message("Synced to the stream target! Resetting the active stream."));
+            self.reset_active_stream();
+        }
+    }
+
+    /// Drops the active data stream (if any), so that a new stream will be
+    /// created on the next progress check.
+    pub fn reset_active_stream(&mut self) {
+        self.active_data_stream = None;
+        self.active_sync_target = None;
+        self.bounded_stream_target = None;
+    }
+}
@@ -0,0 +1,320 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    bootstrapper::Bootstrapper,
+    continuous_syncer::ContinuousSyncer,
+    driver_client::{ClientNotificationListener, DriverNotification},
+    error::Error,
+    logging::{LogEntry, LogSchema},
+    notification_handlers::{CommitNotificationHandler, ConsensusNotificationHandler},
+    storage_synchronizer::{CommittedChunk, StorageSynchronizerInterface},
+    utils,
+};
+use consensus_notifications::{
+    ConsensusCommitNotification, ConsensusNotification, ConsensusSyncNotification,
+};
+use data_streaming_service::streaming_client::DataStreamingClient;
+use diem_config::config::{RoleType, StateSyncDriverConfig};
+use diem_data_client::{DiemDataClient, GlobalDataSummary};
+use diem_logger::prelude::*;
+use diem_types::{protocol_spec::DpnProto, waypoint::Waypoint};
+use futures::{channel::oneshot, StreamExt};
+use mempool_notifications::MempoolNotificationSender;
+use std::{sync::Arc, time::Duration};
+use storage_interface::DbReader;
+use tokio::time::interval;
+use tokio_stream::wrappers::IntervalStream;
+
+/// The configuration of the state sync driver
+#[derive(Clone)]
+pub struct DriverConfiguration {
+    // The config file of the driver
+    pub config: StateSyncDriverConfig,
+
+    // The role of the node
+    pub role: RoleType,
+
+    // The trusted waypoint for the node
+    pub waypoint: Waypoint,
+}
+
+impl DriverConfiguration {
+    pub fn new(config: StateSyncDriverConfig, role: RoleType, waypoint: Waypoint) -> Self {
+        Self {
+            config,
+            role,
+            waypoint,
+        }
+    }
+}
+
+/// The state sync driver that drives synchronization progress. The driver
+/// first bootstraps the node (i.e., verifies the waypoint and syncs to the
+/// latest epoch advertised by the network) and then continuously syncs to
+/// stay up-to-date. Validators only continuously sync when consensus
+/// requests it (e.g., when the validator falls behind the network).
+pub struct StateSyncDriver<DataClient, MempoolNotifier, StorageSyncer, StreamingClient> {
+    // The notifiers to respond to once the node has bootstrapped
+    bootstrap_notifiers: Vec<oneshot::Sender<Result<(), Error>>>,
+
+    // The component that manages the initial bootstrapping of the node
+    bootstrapper: Bootstrapper<StorageSyncer, StreamingClient>,
+
+    // The listener for client notifications
+    client_notification_listener: ClientNotificationListener,
+
+    // The handler for notifications to mempool and event subscribers
+    commit_notification_handler: CommitNotificationHandler<MempoolNotifier>,
+
+    // The handler for notifications from consensus
+    consensus_notification_handler: ConsensusNotificationHandler,
+
+    // The component that manages the continuous syncing of the node
+    continuous_syncer: ContinuousSyncer<StorageSyncer, StreamingClient>,
+
+    // The client for checking the global data summary of our peers
+    diem_data_client: DataClient,
+
+    // The configuration for the driver
+    driver_configuration: DriverConfiguration,
+
+    // The storage to read from
+    storage: Arc<dyn DbReader<DpnProto>>,
+}
+
+impl<
+        DataClient: DiemDataClient + Send + Clone + 'static,
+        MempoolNotifier: MempoolNotificationSender,
+        StorageSyncer: StorageSynchronizerInterface + Clone,
+        StreamingClient: DataStreamingClient + Clone + Send + Sync,
+    > StateSyncDriver<DataClient, MempoolNotifier, StorageSyncer, StreamingClient>
+{
+    pub fn new(
+        client_notification_listener: ClientNotificationListener,
+        commit_notification_handler: CommitNotificationHandler<MempoolNotifier>,
+        consensus_notification_handler: ConsensusNotificationHandler,
+        diem_data_client: DataClient,
+        driver_configuration: DriverConfiguration,
+        storage: Arc<dyn DbReader<DpnProto>>,
+        storage_synchronizer: StorageSyncer,
+        streaming_service_client: StreamingClient,
+    ) -> Self {
+        let bootstrapper = Bootstrapper::new(
+            driver_configuration.clone(),
+            storage.clone(),
+            storage_synchronizer.clone(),
+            streaming_service_client.clone(),
+        );
+        let continuous_syncer = ContinuousSyncer::new(
+            driver_configuration.clone(),
+            storage.clone(),
+            storage_synchronizer,
+            streaming_service_client,
+        );
+
+        Self {
+            bootstrap_notifiers: vec![],
+            bootstrapper,
+            client_notification_listener,
+            commit_notification_handler,
+            consensus_notification_handler,
+            continuous_syncer,
+            diem_data_client,
+            driver_configuration,
+            storage,
+        }
+    }
+
+    /// Starts the state sync driver
+    pub async fn start_driver(mut self) {
+        let mut progress_check_interval = IntervalStream::new(interval(Duration::from_millis(
+            self.driver_configuration.config.progress_check_interval_ms,
+        )))
+        .fuse();
+
+        loop {
+            ::futures::select! {
+                notification = self.client_notification_listener.select_next_some() => {
+                    self.handle_client_notification(notification);
+                }
+                notification = self.consensus_notification_handler.select_next_some() => {
+                    self.handle_consensus_notification(notification).await;
+                }
+                _ = progress_check_interval.select_next_some() => {
+                    self.drive_progress().await;
+                }
+            }
+        }
+    }
+
+    /// Handles a notification sent by a driver client
+    fn handle_client_notification(&mut self, notification: DriverNotification) {
+        match notification {
+            DriverNotification::NotifyOnceBootstrapped(notifier) => {
+                self.bootstrap_notifiers.push(notifier);
+                self.notify_bootstrap_listeners();
+            }
+        }
+    }
+
+    /// Responds to all bootstrap notifiers if the node has bootstrapped
+    fn notify_bootstrap_listeners(&mut self) {
+        if !self.bootstrapper.is_bootstrapped() {
+            return;
+        }
+        for notifier in self.bootstrap_notifiers.drain(..) {
+            if notifier.send(Ok(())).is_err() {
+                warn!(LogSchema::new(LogEntry::Driver)
+                    .message("Failed to notify a client that the node has bootstrapped!"));
+            }
+        }
+    }
+
+    /// Handles a notification sent by consensus
+    async fn handle_consensus_notification(&mut self, notification: ConsensusNotification) {
+        let result = match notification {
+            ConsensusNotification::NotifyCommit(commit_notification) => {
+                self.handle_consensus_commit_notification(commit_notification)
+                    .await
+            }
+            ConsensusNotification::SyncToTarget(sync_notification) => {
+                self.handle_consensus_sync_notification(sync_notification)
+                    .await
+            }
+        };
+
+        if let Err(error) = result {
+            error!(LogSchema::new(LogEntry::ConsensusNotification)
+                .error(&error)
+                .message("Error encountered when handling the consensus notification!"));
+        }
+    }
+
+    /// Handles a commit notification sent by consensus by forwarding the
+    /// committed transactions and events to mempool and event subscribers.
+    async fn handle_consensus_commit_notification(
+        &mut self,
+        commit_notification: ConsensusCommitNotification,
+    ) -> Result<(), Error> {
+        if !self.bootstrapper.is_bootstrapped() {
+            let error = Err(Error::BootstrapNotComplete(
+                "Received a consensus commit notification!".into(),
+            ));
+            self.consensus_notification_handler
+                .respond_to_commit_notification(commit_notification, error.clone())
+                .await?;
+            return error;
+        }
+
+        let committed_chunk = CommittedChunk {
+            committed_transactions: commit_notification.transactions.clone(),
+            reconfiguration_events: commit_notification.reconfiguration_events.clone(),
+        };
+        let result = self
+            .commit_notification_handler
+            .handle_committed_chunk(committed_chunk)
+            .await;
+        self.consensus_notification_handler
+            .respond_to_commit_notification(commit_notification, result.clone())
+            .await?;
+
+        // Check if the commit has satisfied any active sync request
+        self.check_sync_request_progress().await?;
+
+        result
+    }
+
+    /// Handles a request by consensus to sync to a specific target
+    async fn handle_consensus_sync_notification(
+        &mut self,
+        sync_notification: ConsensusSyncNotification,
+    ) -> Result<(), Error> {
+        info!(LogSchema::new(LogEntry::ConsensusNotification)
+            .ledger_info(&sync_notification.target)
+            .message("Received a consensus sync notification!"));
+
+        if !self.bootstrapper.is_bootstrapped() {
+            let error = Err(Error::BootstrapNotComplete(
+                "Received a consensus sync notification!".into(),
+            ));
+            self.consensus_notification_handler
+                .respond_to_sync_notification(sync_notification, error.clone())
+                .await?;
+            return error;
+        }
+
+        let latest_synced_version = utils::fetch_latest_synced_version(&self.storage)?;
+        self.consensus_notification_handler
+            .initialize_sync_request(sync_notification, latest_synced_version)
+            .await
+    }
+
+    /// Checks if the active sync request (if any) has been satisfied
+    async fn check_sync_request_progress(&mut self) -> Result<(), Error> {
+        if !self.consensus_notification_handler.active_sync_request() {
+            return Ok(());
+        }
+
+        let latest_synced_version = utils::fetch_latest_synced_version(&self.storage)?;
+        self.consensus_notification_handler
+            .check_sync_request_progress(latest_synced_version)
+            .await?;
+
+        // If the sync request was satisfied, validators stop syncing
+        if !self.consensus_notification_handler.active_sync_request()
+            && self.driver_configuration.role.is_validator()
+        {
+            self.continuous_syncer.reset_active_stream();
+        }
+        Ok(())
+    }
+
+    /// Returns true iff the node should continuously sync. Fullnodes always
+    /// sync, while validators only sync when consensus has requested it.
+    fn should_continuously_sync(&self) -> bool {
+        !self.driver_configuration.role.is_validator()
+            || self.consensus_notification_handler.active_sync_request()
+    }
+
+    /// Checks the progress of state sync and drives it forward
+    async fn drive_progress(&mut self) {
+        if let Err(error) = self.check_sync_request_progress().await {
+            error!(LogSchema::new(LogEntry::Driver)
+                .error(&error)
+                .message("Error found when checking the sync request progress!"));
+        }
+
+        // Wait until our peers have advertised data before syncing
+        let global_data_summary = self.diem_data_client.get_global_data_summary();
+        if global_data_summary == GlobalDataSummary::empty() {
+            trace!(LogSchema::new(LogEntry::Driver)
+                .message("The global data summary is empty! Waiting for peers to advertise data."));
+            return;
+        }
+
+        let result = if !self.bootstrapper.is_bootstrapped() {
+            let result = self
+                .bootstrapper
+                .drive_progress(&global_data_summary, &mut self.commit_notification_handler)
+                .await;
+            self.notify_bootstrap_listeners();
+            result
+        } else if self.should_continuously_sync() {
+            self.continuous_syncer
+                .drive_progress(
+                    self.consensus_notification_handler.get_sync_target(),
+                    &mut self.commit_notification_handler,
+                )
+                .await
+        } else {
+            Ok(())
+        };
+
+        if let Err(error) = result {
+            warn!(LogSchema::new(LogEntry::Driver)
+                .error(&error)
+                .message("Error found when driving progress of state sync!"));
+        }
+    }
+}
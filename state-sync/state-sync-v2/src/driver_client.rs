@@ -0,0 +1,83 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::error::Error;
+use futures::{
+    channel::{mpsc, oneshot},
+    stream::FusedStream,
+    SinkExt, Stream,
+};
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Notifications that can be sent to the state sync driver
+pub enum DriverNotification {
+    NotifyOnceBootstrapped(oneshot::Sender<Result<(), Error>>),
+}
+
+/// A client for sending notifications to the state sync driver
+#[derive(Clone)]
+pub struct DriverClient {
+    notification_sender: mpsc::UnboundedSender<DriverNotification>,
+}
+
+impl DriverClient {
+    pub fn new(notification_sender: mpsc::UnboundedSender<DriverNotification>) -> Self {
+        Self {
+            notification_sender,
+        }
+    }
+
+    /// Waits until the node has completed bootstrapping. Returns immediately
+    /// if the node is already bootstrapped.
+    pub async fn notify_once_bootstrapped(&self) -> Result<(), Error> {
+        let (notifier, listener) = oneshot::channel();
+
+        let mut notification_sender = self.notification_sender.clone();
+        notification_sender
+            .send(DriverNotification::NotifyOnceBootstrapped(notifier))
+            .await
+            .map_err(|error| Error::CallbackSendFailed(error.to_string()))?;
+        listener
+            .await
+            .map_err(|error| Error::CallbackSendFailed(error.to_string()))?
+    }
+}
+
+/// A simple listener for client notifications sent to the driver
+pub struct ClientNotificationListener {
+    notification_receiver: mpsc::UnboundedReceiver<DriverNotification>,
+}
+
+impl ClientNotificationListener {
+    pub fn new(notification_receiver: mpsc::UnboundedReceiver<DriverNotification>) -> Self {
+        Self {
+            notification_receiver,
+        }
+    }
+}
+
+impl Stream for ClientNotificationListener {
+    type Item = DriverNotification;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.get_mut().notification_receiver).poll_next(cx)
+    }
+}
+
+impl FusedStream for ClientNotificationListener {
+    fn is_terminated(&self) -> bool {
+        self.notification_receiver.is_terminated()
+    }
+}
+
+/// Creates a new client and listener pair for driver notifications
+pub fn new_driver_client_listener_pair() -> (DriverClient, ClientNotificationListener) {
+    let (notification_sender, notification_receiver) = mpsc::unbounded();
+    (
+        DriverClient::new(notification_sender),
+        ClientNotificationListener::new(notification_receiver),
+    )
+}
@@ -0,0 +1,109 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    driver::{DriverConfiguration, StateSyncDriver},
+    driver_client::{new_driver_client_listener_pair, DriverClient},
+    notification_handlers::{
+        CommitNotificationHandler, ConsensusNotificationHandler, MempoolNotificationHandler,
+    },
+    storage_synchronizer::StorageSynchronizer,
+};
+use consensus_notifications::ConsensusNotificationListener;
+use data_streaming_service::{
+    streaming_client::new_streaming_service_client_listener_pair,
+    streaming_service::DataStreamingService,
+};
+use diem_config::config::NodeConfig;
+use diem_data_client::DiemDataClient;
+use diem_types::waypoint::Waypoint;
+use event_notifications::EventSubscriptionService;
+use executor_types::ChunkExecutor;
+use mempool_notifications::MempoolNotificationSender;
+use std::sync::Arc;
+use storage_interface::default_protocol::DbReaderWriter;
+use storage_service_notifications::StorageServiceNotifier;
+use tokio::runtime::{Builder, Runtime};
+
+/// Creates a new state sync driver (and data streaming service) and spawns
+/// them on a dedicated runtime.
+pub struct DriverFactory {
+    client: DriverClient,
+    _driver_runtime: Runtime,
+}
+
+impl DriverFactory {
+    pub fn create_and_spawn_driver<
+        DataClient: DiemDataClient + Send + Clone + 'static,
+        MempoolNotifier: MempoolNotificationSender,
+    >(
+        node_config: &NodeConfig,
+        waypoint: Waypoint,
+        storage: DbReaderWriter,
+        chunk_executor: Box<dyn ChunkExecutor>,
+        mempool_notifier: MempoolNotifier,
+        storage_service_notifier: StorageServiceNotifier,
+        consensus_listener: ConsensusNotificationListener,
+        event_subscription_service: EventSubscriptionService,
+        diem_data_client: DataClient,
+    ) -> Self {
+        let driver_runtime = Builder::new_multi_thread()
+            .thread_name("state-sync-driver")
+            .enable_all()
+            .build()
+            .expect("Failed to create the state sync driver runtime!");
+
+        // Create the notification handlers and notify all reconfiguration
+        // subscribers of the initial on-chain configurations.
+        let state_sync_driver_config = node_config.state_sync.state_sync_driver.clone();
+        let mempool_notification_handler = MempoolNotificationHandler::new(
+            mempool_notifier,
+            state_sync_driver_config.mempool_commit_ack_timeout_ms,
+        );
+        let mut commit_notification_handler = CommitNotificationHandler::new(
+            event_subscription_service,
+            mempool_notification_handler,
+            storage.reader.clone(),
+            storage_service_notifier,
+        );
+        commit_notification_handler
+            .notify_initial_configs()
+            .expect("Failed to notify reconfiguration subscribers on initialization!");
+        let consensus_notification_handler = ConsensusNotificationHandler::new(consensus_listener);
+
+        // Create and spawn the data streaming service
+        let (streaming_service_client, streaming_service_listener) =
+            new_streaming_service_client_listener_pair();
+        let data_streaming_service =
+            DataStreamingService::new(diem_data_client.clone(), streaming_service_listener);
+        driver_runtime.spawn(data_streaming_service.start_service());
+
+        // Create and spawn the state sync driver
+        let (client, client_notification_listener) = new_driver_client_listener_pair();
+        let driver_configuration =
+            DriverConfiguration::new(state_sync_driver_config, node_config.base.role, waypoint);
+        let storage_synchronizer =
+            StorageSynchronizer::new(Arc::from(chunk_executor), storage.writer.clone());
+        let state_sync_driver = StateSyncDriver::new(
+            client_notification_listener,
+            commit_notification_handler,
+            consensus_notification_handler,
+            diem_data_client,
+            driver_configuration,
+            storage.reader,
+            storage_synchronizer,
+            streaming_service_client,
+        );
+        driver_runtime.spawn(state_sync_driver.start_driver());
+
+        Self {
+            client,
+            _driver_runtime: driver_runtime,
+        }
+    }
+
+    /// Returns a new client that can be used to communicate with the driver
+    pub fn create_driver_client(&self) -> DriverClient {
+        self.client.clone()
+    }
+}
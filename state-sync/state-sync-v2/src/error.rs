@@ -0,0 +1,61 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Clone, Debug, Deserialize, Error, PartialEq, Serialize)]
+pub enum Error {
+    #[error("The advertised data in the network is insufficient: {0}")]
+    AdvertisedDataError(String),
+    #[error("State sync has not yet finished bootstrapping: {0}")]
+    BootstrapNotComplete(String),
+    #[error("Failed to send callback: {0}")]
+    CallbackSendFailed(String),
+    #[error("Timed out waiting for a data stream notification: {0}")]
+    DataStreamNotificationTimeout(String),
+    #[error("Error returned by the data streaming service: {0}")]
+    DataStreamingServiceError(String),
+    #[error("Error found when notifying event subscribers: {0}")]
+    EventNotificationError(String),
+    #[error("An integer overflow has occurred: {0}")]
+    IntegerOverflow(String),
+    #[error("The data payload received is invalid: {0}")]
+    InvalidPayload(String),
+    #[error("Error found when notifying mempool: {0}")]
+    MempoolNotificationError(String),
+    #[error("Received an old sync request for version {0}, but our synced version is: {1}")]
+    OldSyncRequest(u64, u64),
+    #[error("Unexpected storage error: {0}")]
+    StorageError(String),
+    #[error("Error found when notifying the storage service: {0}")]
+    StorageServiceNotificationError(String),
+    #[error("Unexpected error: {0}")]
+    UnexpectedError(String),
+    #[error("Failed to verify a ledger info or proof: {0}")]
+    VerificationError(String),
+}
+
+impl From<data_streaming_service::error::Error> for Error {
+    fn from(error: data_streaming_service::error::Error) -> Self {
+        Error::DataStreamingServiceError(error.to_string())
+    }
+}
+
+impl From<event_notifications::Error> for Error {
+    fn from(error: event_notifications::Error) -> Self {
+        Error::EventNotificationError(error.to_string())
+    }
+}
+
+impl From<mempool_notifications::Error> for Error {
+    fn from(error: mempool_notifications::Error) -> Self {
+        Error::MempoolNotificationError(error.to_string())
+    }
+}
+
+impl From<storage_service_notifications::Error> for Error {
+    fn from(error: storage_service_notifications::Error) -> Self {
+        Error::StorageServiceNotificationError(error.to_string())
+    }
+}
@@ -1,7 +1,24 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-//! This is an empty skeleton library for state sync v2.
-//!
-//! TODO(joshlind): update this description when we start implementing state sync v2.
 #![forbid(unsafe_code)]
+
+//! State sync v2 is the next iteration of state sync. The state sync driver
+//! bootstraps the node from its waypoint (by fetching and verifying all epoch
+//! ending ledger infos advertised by the network and syncing transaction
+//! outputs or transactions up to the latest epoch) and then continuously syncs
+//! to stay up-to-date. All data is fetched through the data streaming service.
+
+pub mod bootstrapper;
+pub mod continuous_syncer;
+pub mod driver;
+pub mod driver_client;
+pub mod driver_factory;
+pub mod error;
+mod logging;
+pub mod notification_handlers;
+pub mod storage_synchronizer;
+mod utils;
+
+#[cfg(test)]
+mod tests;
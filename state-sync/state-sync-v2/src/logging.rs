@@ -0,0 +1,42 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::error::Error;
+use diem_logger::Schema;
+use diem_types::ledger_info::LedgerInfoWithSignatures;
+use serde::Serialize;
+
+#[derive(Schema)]
+pub struct LogSchema<'a> {
+    name: LogEntry,
+    #[schema(debug)]
+    error: Option<&'a Error>,
+    #[schema(display)]
+    ledger_info: Option<&'a LedgerInfoWithSignatures>,
+    message: Option<&'a str>,
+    synced_version: Option<u64>,
+    target_version: Option<u64>,
+}
+
+impl<'a> LogSchema<'a> {
+    pub fn new(name: LogEntry) -> Self {
+        Self {
+            name,
+            error: None,
+            ledger_info: None,
+            message: None,
+            synced_version: None,
+            target_version: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogEntry {
+    Bootstrapper,
+    ConsensusNotification,
+    ContinuousSyncer,
+    Driver,
+    NotificationHandler,
+}
@@ -0,0 +1,284 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    error::Error,
+    logging::{LogEntry, LogSchema},
+    storage_synchronizer::CommittedChunk,
+    utils,
+};
+use consensus_notifications::{
+    ConsensusCommitNotification, ConsensusNotification, ConsensusNotificationListener,
+    ConsensusSyncNotification,
+};
+use diem_logger::prelude::*;
+use diem_types::{
+    ledger_info::LedgerInfoWithSignatures,
+    protocol_spec::DpnProto,
+    transaction::{Transaction, Version},
+};
+use event_notifications::{EventNotificationSender, EventSubscriptionService};
+use futures::{stream::FusedStream, Stream};
+use mempool_notifications::MempoolNotificationSender;
+use std::{
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use storage_interface::DbReader;
+use storage_service_notifications::StorageServiceNotifier;
+
+/// A simple handler for consensus notifications. This handler tracks the
+/// currently active sync request (if any) and responds to consensus once
+/// the sync request has been satisfied.
+pub struct ConsensusNotificationHandler {
+    // The listener for notifications from consensus
+    consensus_listener: ConsensusNotificationListener,
+
+    // The latest consensus sync request that has been received
+    sync_request: Option<ConsensusSyncNotification>,
+}
+
+impl ConsensusNotificationHandler {
+    pub fn new(consensus_listener: ConsensusNotificationListener) -> Self {
+        Self {
+            consensus_listener,
+            sync_request: None,
+        }
+    }
+
+    /// Returns true iff there is a sync request currently blocking consensus
+    pub fn active_sync_request(&self) -> bool {
+        self.sync_request.is_some()
+    }
+
+    /// Returns the sync target of the currently active sync request (if any)
+    pub fn get_sync_target(&self) -> Option<LedgerInfoWithSignatures> {
+        self.sync_request
+            .as_ref()
+            .map(|sync_request| sync_request.target.clone())
+    }
+
+    /// Initializes the sync request received from consensus. If the request
+    /// has already been satisfied (or is for an old version), consensus is
+    /// notified immediately.
+    pub async fn initialize_sync_request(
+        &mut self,
+        sync_notification: ConsensusSyncNotification,
+        latest_synced_version: Version,
+    ) -> Result<(), Error> {
+        let sync_target_version = sync_notification.target.ledger_info().version();
+        if sync_target_version < latest_synced_version {
+            let error = Err(Error::OldSyncRequest(
+                sync_target_version,
+                latest_synced_version,
+            ));
+            self.respond_to_sync_notification(sync_notification, error.clone())
+                .await?;
+            return error;
+        }
+        if sync_target_version == latest_synced_version {
+            return self
+                .respond_to_sync_notification(sync_notification, Ok(()))
+                .await;
+        }
+
+        // Replace any existing sync request (the old one is superseded)
+        if let Some(old_sync_request) = self.sync_request.take() {
+            let error = Err(Error::UnexpectedError(
+                "The sync request was superseded by a new request!".into(),
+            ));
+            self.respond_to_sync_notification(old_sync_request, error)
+                .await?;
+        }
+        self.sync_request = Some(sync_notification);
+        Ok(())
+    }
+
+    /// Checks if the currently active sync request has been satisfied and, if
+    /// so, notifies consensus.
+    pub async fn check_sync_request_progress(
+        &mut self,
+        latest_synced_version: Version,
+    ) -> Result<(), Error> {
+        let sync_target_version = match &self.sync_request {
+            Some(sync_request) => sync_request.target.ledger_info().version(),
+            None => return Ok(()),
+        };
+
+        if latest_synced_version > sync_target_version {
+            return Err(Error::UnexpectedError(format!(
+                "We've synced beyond the target! Target version: {:?}, synced version: {:?}",
+                sync_target_version, latest_synced_version
+            )));
+        }
+        if latest_synced_version == sync_target_version {
+            if let Some(sync_request) = self.sync_request.take() {
+                self.respond_to_sync_notification(sync_request, Ok(()))
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Responds to consensus for the given commit notification
+    pub async fn respond_to_commit_notification(
+        &mut self,
+        commit_notification: ConsensusCommitNotification,
+        result: Result<(), Error>,
+    ) -> Result<(), Error> {
+        debug!(
+            LogSchema::new(LogEntry::NotificationHandler).message(&format!(
+                "Responding to consensus commit notification: {:?}",
+                result
+            ))
+        );
+
+        self.consensus_listener
+            .respond_to_commit_notification(commit_notification, map_result(result))
+            .await
+            .map_err(|error| Error::CallbackSendFailed(error.to_string()))
+    }
+
+    /// Responds to consensus for the given sync notification
+    pub async fn respond_to_sync_notification(
+        &mut self,
+        sync_notification: ConsensusSyncNotification,
+        result: Result<(), Error>,
+    ) -> Result<(), Error> {
+        info!(LogSchema::new(LogEntry::NotificationHandler)
+            .target_version(sync_notification.target.ledger_info().version())
+            .message(&format!(
+                "Responding to consensus sync notification: {:?}",
+                result
+            )));
+
+        self.consensus_listener
+            .respond_to_sync_notification(sync_notification, map_result(result))
+            .await
+            .map_err(|error| Error::CallbackSendFailed(error.to_string()))
+    }
+}
+
+/// Converts a state sync result into a result understood by consensus
+fn map_result(result: Result<(), Error>) -> Result<(), consensus_notifications::Error> {
+    result.map_err(|error| consensus_notifications::Error::NotificationError(error.to_string()))
+}
+
+impl Stream for ConsensusNotificationHandler {
+    type Item = ConsensusNotification;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.get_mut().consensus_listener).poll_next(cx)
+    }
+}
+
+impl FusedStream for ConsensusNotificationHandler {
+    fn is_terminated(&self) -> bool {
+        self.consensus_listener.is_terminated()
+    }
+}
+
+/// A simple wrapper around the mempool notification sender that notifies
+/// mempool of newly committed transactions.
+#[derive(Clone)]
+pub struct MempoolNotificationHandler<M> {
+    mempool_commit_ack_timeout_ms: u64,
+    mempool_notification_sender: M,
+}
+
+impl<M: MempoolNotificationSender> MempoolNotificationHandler<M> {
+    pub fn new(mempool_notification_sender: M, mempool_commit_ack_timeout_ms: u64) -> Self {
+        Self {
+            mempool_commit_ack_timeout_ms,
+            mempool_notification_sender,
+        }
+    }
+
+    /// Notifies mempool that transactions have been committed
+    pub async fn notify_mempool_of_committed_transactions(
+        &mut self,
+        committed_transactions: Vec<Transaction>,
+        block_timestamp_usecs: u64,
+    ) -> Result<(), Error> {
+        self.mempool_notification_sender
+            .notify_new_commit(
+                committed_transactions,
+                block_timestamp_usecs,
+                self.mempool_commit_ack_timeout_ms,
+            )
+            .await
+            .map_err(|error| error.into())
+    }
+}
+
+/// Handles the notifications that must be sent to other components (i.e.,
+/// mempool and event subscribers) once new data has been committed.
+pub struct CommitNotificationHandler<M> {
+    event_subscription_service: EventSubscriptionService,
+    mempool_notification_handler: MempoolNotificationHandler<M>,
+    storage: Arc<dyn DbReader<DpnProto>>,
+    storage_service_notifier: StorageServiceNotifier,
+}
+
+impl<M: MempoolNotificationSender> CommitNotificationHandler<M> {
+    pub fn new(
+        event_subscription_service: EventSubscriptionService,
+        mempool_notification_handler: MempoolNotificationHandler<M>,
+        storage: Arc<dyn DbReader<DpnProto>>,
+        storage_service_notifier: StorageServiceNotifier,
+    ) -> Self {
+        Self {
+            event_subscription_service,
+            mempool_notification_handler,
+            storage,
+            storage_service_notifier,
+        }
+    }
+
+    /// Notifies all reconfiguration subscribers of the initial on-chain
+    /// configurations at the latest synced version.
+    pub fn notify_initial_configs(&mut self) -> Result<(), Error> {
+        let latest_synced_version = utils::fetch_latest_synced_version(&self.storage)?;
+        self.event_subscription_service
+            .notify_initial_configs(latest_synced_version)
+            .map_err(|error| error.into())
+    }
+
+    /// Notifies mempool, event subscribers and the storage service of the newly
+    /// committed chunk. All components are always notified, even if notifying
+    /// one of them fails.
+    pub async fn handle_committed_chunk(
+        &mut self,
+        committed_chunk: CommittedChunk,
+    ) -> Result<(), Error> {
+        let latest_synced_version = utils::fetch_latest_synced_version(&self.storage)?;
+        let block_timestamp_usecs = self
+            .storage
+            .get_block_timestamp(latest_synced_version)
+            .map_err(|error| {
+                Error::StorageError(format!("Failed to get the block timestamp: {:?}", error))
+            })?;
+
+        let mempool_result = self
+            .mempool_notification_handler
+            .notify_mempool_of_committed_transactions(
+                committed_chunk.committed_transactions,
+                block_timestamp_usecs,
+            )
+            .await;
+        let event_result: Result<(), Error> = self
+            .event_subscription_service
+            .notify_events(
+                latest_synced_version,
+                committed_chunk.reconfiguration_events,
+            )
+            .map_err(|error| error.into());
+        let storage_service_result: Result<(), Error> = self
+            .storage_service_notifier
+            .notify_new_commit(latest_synced_version)
+            .map_err(|error| error.into());
+
+        mempool_result.and(event_result).and(storage_service_result)
+    }
+}
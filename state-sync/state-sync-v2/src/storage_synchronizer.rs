@@ -0,0 +1,194 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::error::Error;
+use diem_crypto::HashValue;
+use diem_types::{
+    contract_event::ContractEvent,
+    ledger_info::LedgerInfoWithSignatures,
+    protocol_spec::DpnProto,
+    transaction::{
+        default_protocol::{TransactionListWithProof, TransactionOutputListWithProof},
+        Transaction, Version,
+    },
+};
+use executor_types::ChunkExecutor;
+use std::sync::Arc;
+use storage_interface::{DbWriter, StateSnapshotReceiver};
+
+/// The data committed by the storage synchronizer for a single chunk. This
+/// is used by the driver to notify other components (e.g., mempool and event
+/// subscribers) of the new commit.
+#[derive(Clone, Debug)]
+pub struct CommittedChunk {
+    pub committed_transactions: Vec<Transaction>,
+    pub reconfiguration_events: Vec<ContractEvent>,
+}
+
+/// Synchronizes the storage of the node by verifying, executing (or applying)
+/// and committing chunks of data received from the data streaming service.
+pub trait StorageSynchronizerInterface: Send {
+    /// Applies and commits the given transaction outputs. The proofs are
+    /// relative to `target_ledger_info`, which must have already been verified.
+    /// If the chunk ends the current epoch, `end_of_epoch_ledger_info` must
+    /// hold the corresponding epoch ending ledger info.
+    fn apply_and_commit_transaction_outputs(
+        &mut self,
+        output_list_with_proof: TransactionOutputListWithProof,
+        target_ledger_info: LedgerInfoWithSignatures,
+        end_of_epoch_ledger_info: Option<LedgerInfoWithSignatures>,
+    ) -> Result<CommittedChunk, Error>;
+
+    /// Executes and commits the given transactions. The proofs are relative to
+    /// `target_ledger_info`, which must have already been verified. If the
+    /// chunk ends the current epoch, `end_of_epoch_ledger_info` must hold the
+    /// corresponding epoch ending ledger info.
+    fn execute_and_commit_transactions(
+        &mut self,
+        transaction_list_with_proof: TransactionListWithProof,
+        target_ledger_info: LedgerInfoWithSignatures,
+        end_of_epoch_ledger_info: Option<LedgerInfoWithSignatures>,
+    ) -> Result<CommittedChunk, Error>;
+
+    /// Returns a receiver for the account states at the given version. If a
+    /// previous restore at the same version was interrupted, the receiver
+    /// resumes from where it left off (see `num_accounts_restored()`).
+    fn get_state_snapshot_receiver(
+        &mut self,
+        version: Version,
+        expected_root_hash: HashValue,
+    ) -> Result<Box<dyn StateSnapshotReceiver>, Error>;
+
+    /// Finalizes a state snapshot (once all account states have been
+    /// restored) by committing the transaction output at `version` and the
+    /// ledger info that proves it. The proof must be relative to
+    /// `target_ledger_info`, which must have already been verified.
+    fn finalize_state_snapshot(
+        &mut self,
+        version: Version,
+        output_list_with_proof: TransactionOutputListWithProof,
+        target_ledger_info: LedgerInfoWithSignatures,
+    ) -> Result<(), Error>;
+}
+
+/// The default storage synchronizer that proxies all requests to the chunk
+/// executor (or directly to storage, for state snapshots). The synchronizer
+/// is cheap to clone so that it can be shared between the bootstrapper and
+/// the continuous syncer.
+#[derive(Clone)]
+pub struct StorageSynchronizer {
+    chunk_executor: Arc<dyn ChunkExecutor>,
+    storage: Arc<dyn DbWriter<DpnProto>>,
+}
+
+impl StorageSynchronizer {
+    pub fn new(
+        chunk_executor: Arc<dyn ChunkExecutor>,
+        storage: Arc<dyn DbWriter<DpnProto>>,
+    ) -> Self {
+        Self {
+            chunk_executor,
+            storage,
+        }
+    }
+}
+
+impl StorageSynchronizerInterface for StorageSynchronizer {
+    fn apply_and_commit_transaction_outputs(
+        &mut self,
+        output_list_with_proof: TransactionOutputListWithProof,
+        target_ledger_info: LedgerInfoWithSignatures,
+        end_of_epoch_ledger_info: Option<LedgerInfoWithSignatures>,
+    ) -> Result<CommittedChunk, Error> {
+        let committed_transactions = output_list_with_proof
+            .transactions_and_outputs
+            .iter()
+            .map(|(transaction, _)| transaction.clone())
+            .collect();
+
+        let (output, transactions_to_commit, events) = self
+            .chunk_executor
+            .apply_chunk(output_list_with_proof, target_ledger_info.clone())
+            .map_err(|error| {
+                Error::VerificationError(format!("Apply transaction outputs failed: {}", error))
+            })?;
+        let reconfiguration_events = self
+            .chunk_executor
+            .commit_chunk(
+                target_ledger_info,
+                end_of_epoch_ledger_info,
+                output,
+                transactions_to_commit,
+                events,
+            )
+            .map_err(|error| {
+                Error::StorageError(format!("Commit transaction outputs failed: {}", error))
+            })?;
+
+        Ok(CommittedChunk {
+            committed_transactions,
+            reconfiguration_events,
+        })
+    }
+
+    fn execute_and_commit_transactions(
+        &mut self,
+        transaction_list_with_proof: TransactionListWithProof,
+        target_ledger_info: LedgerInfoWithSignatures,
+        end_of_epoch_ledger_info: Option<LedgerInfoWithSignatures>,
+    ) -> Result<CommittedChunk, Error> {
+        let committed_transactions = transaction_list_with_proof.transactions.clone();
+
+        let (output, transactions_to_commit, events) = self
+            .chunk_executor
+            .execute_chunk(transaction_list_with_proof, target_ledger_info.clone())
+            .map_err(|error| {
+                Error::VerificationError(format!("Execute transactions failed: {}", error))
+            })?;
+        let reconfiguration_events = self
+            .chunk_executor
+            .commit_chunk(
+                target_ledger_info,
+                end_of_epoch_ledger_info,
+                output,
+                transactions_to_commit,
+                events,
+            )
+            .map_err(|error| {
+                Error::StorageError(format!("Commit transactions failed: {}", error))
+            })?;
+
+        Ok(CommittedChunk {
+            committed_transactions,
+            reconfiguration_events,
+        })
+    }
+
+    fn get_state_snapshot_receiver(
+        &mut self,
+        version: Version,
+        expected_root_hash: HashValue,
+    ) -> Result<Box<dyn StateSnapshotReceiver>, Error> {
+        self.storage
+            .get_state_snapshot_receiver(version, expected_root_hash)
+            .map_err(|error| {
+                Error::StorageError(format!(
+                    "Failed to create the state snapshot receiver: {}",
+                    error
+                ))
+            })
+    }
+
+    fn finalize_state_snapshot(
+        &mut self,
+        version: Version,
+        output_list_with_proof: TransactionOutputListWithProof,
+        target_ledger_info: LedgerInfoWithSignatures,
+    ) -> Result<(), Error> {
+        self.storage
+            .finalize_state_snapshot(version, output_list_with_proof, &[target_ledger_info])
+            .map_err(|error| {
+                Error::StorageError(format!("Failed to finalize the state snapshot: {}", error))
+            })
+    }
+}
@@ -0,0 +1,59 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    error::Error,
+    tests::utils::{create_epoch_state, create_ledger_info},
+    utils::{get_end_of_epoch_ledger_info, verify_chunk_versions},
+};
+use claim::assert_matches;
+
+#[test]
+fn test_verify_chunk_versions() {
+    // Verify a valid chunk returns the end version
+    assert_eq!(verify_chunk_versions(10, Some(10), 5), Ok(14));
+    assert_eq!(verify_chunk_versions(0, Some(0), 1), Ok(0));
+
+    // Verify empty chunks are rejected
+    assert_matches!(
+        verify_chunk_versions(10, None, 0),
+        Err(Error::InvalidPayload(_))
+    );
+    assert_matches!(
+        verify_chunk_versions(10, Some(10), 0),
+        Err(Error::InvalidPayload(_))
+    );
+
+    // Verify chunks that don't start at the expected version are rejected
+    assert_matches!(
+        verify_chunk_versions(10, Some(9), 5),
+        Err(Error::InvalidPayload(_))
+    );
+    assert_matches!(
+        verify_chunk_versions(10, Some(11), 5),
+        Err(Error::InvalidPayload(_))
+    );
+
+    // Verify overflows are caught
+    assert_matches!(
+        verify_chunk_versions(u64::MAX, Some(u64::MAX), 2),
+        Err(Error::IntegerOverflow(_))
+    );
+}
+
+#[test]
+fn test_end_of_epoch_ledger_info() {
+    let (signers, epoch_state) = create_epoch_state(1);
+
+    // Verify a ledger info within an epoch is never returned
+    let ledger_info = create_ledger_info(&signers, 0, 100, None);
+    assert_eq!(get_end_of_epoch_ledger_info(&ledger_info, 100), None);
+
+    // Verify an epoch ending ledger info is only returned at the end of the epoch
+    let ledger_info = create_ledger_info(&signers, 0, 100, Some(epoch_state));
+    assert_eq!(get_end_of_epoch_ledger_info(&ledger_info, 99), None);
+    assert_eq!(
+        get_end_of_epoch_ledger_info(&ledger_info, 100),
+        Some(ledger_info)
+    );
+}
@@ -0,0 +1,6 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+mod chunk_verification;
+mod utils;
+mod verified_epoch_states;
@@ -0,0 +1,44 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use diem_crypto::HashValue;
+use diem_types::{
+    block_info::BlockInfo,
+    epoch_state::EpochState,
+    ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    transaction::Version,
+    validator_signer::ValidatorSigner,
+    validator_verifier::random_validator_verifier,
+};
+use std::collections::BTreeMap;
+
+/// Creates a set of validator signers and the corresponding epoch state
+pub fn create_epoch_state(epoch: u64) -> (Vec<ValidatorSigner>, EpochState) {
+    let (signers, verifier) = random_validator_verifier(4, None, true);
+    (signers, EpochState { epoch, verifier })
+}
+
+/// Creates a ledger info at the given epoch and version, signed by the given
+/// signers. If `next_epoch_state` is specified, the ledger info ends the epoch.
+pub fn create_ledger_info(
+    signers: &[ValidatorSigner],
+    epoch: u64,
+    version: Version,
+    next_epoch_state: Option<EpochState>,
+) -> LedgerInfoWithSignatures {
+    let block_info = BlockInfo::new(
+        epoch,
+        0,
+        HashValue::zero(),
+        HashValue::zero(),
+        version,
+        0,
+        next_epoch_state,
+    );
+    let ledger_info = LedgerInfo::new(block_info, HashValue::zero());
+    let signatures = signers
+        .iter()
+        .map(|signer| (signer.author(), signer.sign(&ledger_info)))
+        .collect::<BTreeMap<_, _>>();
+    LedgerInfoWithSignatures::new(ledger_info, signatures)
+}
@@ -0,0 +1,120 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    bootstrapper::VerifiedEpochStates,
+    error::Error,
+    tests::utils::{create_epoch_state, create_ledger_info},
+};
+use claim::{assert_matches, assert_ok};
+use diem_types::waypoint::Waypoint;
+
+#[test]
+fn test_verify_epoch_ending_ledger_infos() {
+    // Create the epoch states for epochs 0 -> 3
+    let epoch_states: Vec<_> = (0..4).map(create_epoch_state).collect();
+
+    // Create the epoch ending ledger infos for epochs 0 -> 2
+    let epoch_ending_ledger_infos: Vec<_> = (0..3)
+        .map(|epoch| {
+            let (signers, _) = &epoch_states[epoch];
+            let (_, next_epoch_state) = &epoch_states[epoch + 1];
+            create_ledger_info(
+                signers,
+                epoch as u64,
+                (epoch as u64 + 1) * 100,
+                Some(next_epoch_state.clone()),
+            )
+        })
+        .collect();
+
+    // Create a waypoint at the end of epoch 1
+    let waypoint =
+        Waypoint::new_epoch_boundary(epoch_ending_ledger_infos[1].ledger_info()).unwrap();
+
+    // Verify all ledger infos and the waypoint
+    let mut verified_epoch_states = VerifiedEpochStates::new(epoch_states[0].1.clone(), false);
+    for epoch_ending_ledger_info in &epoch_ending_ledger_infos {
+        assert_ok!(
+            verified_epoch_states.update_verified_epoch_states(epoch_ending_ledger_info, &waypoint)
+        );
+    }
+    assert!(verified_epoch_states.verified_waypoint());
+    assert_eq!(verified_epoch_states.next_epoch_to_fetch(), 3);
+
+    // Verify the next epoch ending ledger infos are returned by version
+    assert_eq!(
+        verified_epoch_states.next_epoch_ending_ledger_info(0),
+        Some(epoch_ending_ledger_infos[0].clone())
+    );
+    assert_eq!(
+        verified_epoch_states.next_epoch_ending_ledger_info(101),
+        Some(epoch_ending_ledger_infos[1].clone())
+    );
+    assert_eq!(
+        verified_epoch_states.next_epoch_ending_ledger_info(300),
+        Some(epoch_ending_ledger_infos[2].clone())
+    );
+    assert_eq!(
+        verified_epoch_states.next_epoch_ending_ledger_info(301),
+        None
+    );
+
+    // Verify the epoch ending ledger infos used for account state snapshots
+    assert_eq!(
+        verified_epoch_states.highest_epoch_ending_ledger_info(),
+        Some(epoch_ending_ledger_infos[2].clone())
+    );
+    assert_eq!(
+        verified_epoch_states.get_epoch_ending_ledger_info(200),
+        Some(epoch_ending_ledger_infos[1].clone())
+    );
+    assert_eq!(
+        verified_epoch_states.get_epoch_ending_ledger_info(201),
+        None
+    );
+}
+
+#[test]
+fn test_verify_invalid_epoch_ending_ledger_infos() {
+    let (signers_0, epoch_state_0) = create_epoch_state(0);
+    let (signers_1, epoch_state_1) = create_epoch_state(1);
+    let waypoint_ledger_info = create_ledger_info(&signers_0, 0, 100, Some(epoch_state_1.clone()));
+    let waypoint = Waypoint::new_epoch_boundary(waypoint_ledger_info.ledger_info()).unwrap();
+
+    // Verify a ledger info for the wrong epoch is rejected
+    let mut verified_epoch_states = VerifiedEpochStates::new(epoch_state_0.clone(), false);
+    let ledger_info = create_ledger_info(&signers_1, 1, 200, Some(epoch_state_1.clone()));
+    assert_matches!(
+        verified_epoch_states.update_verified_epoch_states(&ledger_info, &waypoint),
+        Err(Error::VerificationError(_))
+    );
+
+    // Verify a ledger info that doesn't end the epoch is rejected
+    let ledger_info = create_ledger_info(&signers_0, 0, 50, None);
+    assert_matches!(
+        verified_epoch_states.update_verified_epoch_states(&ledger_info, &waypoint),
+        Err(Error::VerificationError(_))
+    );
+
+    // Verify a ledger info that doesn't match the waypoint is rejected
+    let ledger_info = create_ledger_info(&signers_0, 0, 100, Some(epoch_state_0));
+    assert_matches!(
+        verified_epoch_states.update_verified_epoch_states(&ledger_info, &waypoint),
+        Err(Error::VerificationError(_))
+    );
+
+    // Verify a ledger info beyond the (unverified) waypoint is rejected
+    let ledger_info = create_ledger_info(&signers_0, 0, 101, Some(epoch_state_1));
+    assert_matches!(
+        verified_epoch_states.update_verified_epoch_states(&ledger_info, &waypoint),
+        Err(Error::VerificationError(_))
+    );
+    assert!(!verified_epoch_states.verified_waypoint());
+    assert_eq!(verified_epoch_states.next_epoch_to_fetch(), 0);
+
+    // Verify the waypoint ledger info is accepted
+    assert_ok!(verified_epoch_states.update_verified_epoch_states(&waypoint_ledger_info, &waypoint));
+    assert!(verified_epoch_states.verified_waypoint());
+    assert_eq!(verified_epoch_states.next_epoch_to_fetch(), 1);
+}
@@ -0,0 +1,260 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    error::Error,
+    logging::{LogEntry, LogSchema},
+    notification_handlers::CommitNotificationHandler,
+    storage_synchronizer::{CommittedChunk, StorageSynchronizerInterface},
+};
+use data_streaming_service::{
+    data_notification::{DataNotification, NotificationId},
+    data_stream::DataStreamListener,
+    streaming_client::{DataStreamingClient, NotificationFeedback},
+};
+use diem_logger::prelude::*;
+use diem_types::{
+    epoch_state::EpochState,
+    ledger_info::LedgerInfoWithSignatures,
+    move_resource::MoveStorage,
+    protocol_spec::DpnProto,
+    transaction::{
+        default_protocol::{TransactionListWithProof, TransactionOutputListWithProof},
+        Version,
+    },
+};
+use futures::{FutureExt, StreamExt};
+use mempool_notifications::MempoolNotificationSender;
+use std::{sync::Arc, time::Duration};
+use storage_interface::DbReader;
+use tokio::time::timeout;
+
+/// Terminates the data stream that sent the notification with the given ID if
+/// processing the notification failed because of a bad payload (e.g., a proof
+/// failed to verify). This allows the streaming service to notify the data
+/// client of the bad response, so that the peer that sent it is penalized.
+pub async fn terminate_stream_with_feedback<StreamingClient: DataStreamingClient>(
+    streaming_service_client: &StreamingClient,
+    notification_id: NotificationId,
+    error: &Error,
+) {
+    let notification_feedback = match error {
+        Error::InvalidPayload(_) => NotificationFeedback::InvalidPayloadData,
+        Error::VerificationError(_) => NotificationFeedback::PayloadProofFailed,
+        _ => return,
+    };
+    if let Err(error) = streaming_service_client
+        .terminate_stream_with_feedback(notification_id, notification_feedback)
+        .await
+    {
+        error!(LogSchema::new(LogEntry::Driver)
+            .error(&error.into())
+            .message("Failed to terminate the data stream with feedback!"));
+    }
+}
+
+/// Fetches a data notification from the given data stream listener. Returns an
+/// error if the data stream times out after `max_stream_wait_time_ms` or if the
+/// stream has been terminated.
+pub async fn get_data_notification(
+    max_stream_wait_time_ms: u64,
+    active_data_stream: &mut DataStreamListener,
+) -> Result<DataNotification, Error> {
+    let timeout_ms = Duration::from_millis(max_stream_wait_time_ms);
+    match timeout(timeout_ms, active_data_stream.next()).await {
+        Ok(Some(data_notification)) => Ok(data_notification),
+        Ok(None) => Err(Error::DataStreamingServiceError(
+            "The data stream was unexpectedly terminated!".into(),
+        )),
+        Err(_) => Err(Error::DataStreamNotificationTimeout(format!(
+            "{:?}",
+            timeout_ms
+        ))),
+    }
+}
+
+/// Returns the next data notification that is already available on the given
+/// data stream listener (if any). This call never blocks.
+pub fn get_ready_data_notification(
+    active_data_stream: &mut DataStreamListener,
+) -> Option<DataNotification> {
+    active_data_stream.next().now_or_never().flatten()
+}
+
+/// Verifies, applies and commits the given transaction outputs (with proofs
+/// relative to the already verified `target_ledger_info`) and notifies the
+/// relevant components of the commit. Returns the last version of the chunk.
+pub async fn apply_and_commit_transaction_outputs<
+    StorageSyncer: StorageSynchronizerInterface,
+    M: MempoolNotificationSender,
+>(
+    storage: &Arc<dyn DbReader<DpnProto>>,
+    storage_synchronizer: &mut StorageSyncer,
+    commit_notification_handler: &mut CommitNotificationHandler<M>,
+    output_list_with_proof: TransactionOutputListWithProof,
+    target_ledger_info: &LedgerInfoWithSignatures,
+) -> Result<Version, Error> {
+    let chunk_end_version = verify_chunk_versions(
+        fetch_next_version_to_sync(storage)?,
+        output_list_with_proof.first_transaction_output_version,
+        output_list_with_proof.transactions_and_outputs.len(),
+    )?;
+    let end_of_epoch_ledger_info =
+        get_end_of_epoch_ledger_info(target_ledger_info, chunk_end_version);
+
+    let committed_chunk = storage_synchronizer.apply_and_commit_transaction_outputs(
+        output_list_with_proof,
+        target_ledger_info.clone(),
+        end_of_epoch_ledger_info,
+    )?;
+    handle_committed_chunk(
+        commit_notification_handler,
+        committed_chunk,
+        chunk_end_version,
+    )
+    .await;
+
+    Ok(chunk_end_version)
+}
+
+/// Verifies, executes and commits the given transactions (with proofs
+/// relative to the already verified `target_ledger_info`) and notifies the
+/// relevant components of the commit. Returns the last version of the chunk.
+pub async fn execute_and_commit_transactions<
+    StorageSyncer: StorageSynchronizerInterface,
+    M: MempoolNotificationSender,
+>(
+    storage: &Arc<dyn DbReader<DpnProto>>,
+    storage_synchronizer: &mut StorageSyncer,
+    commit_notification_handler: &mut CommitNotificationHandler<M>,
+    transaction_list_with_proof: TransactionListWithProof,
+    target_ledger_info: &LedgerInfoWithSignatures,
+) -> Result<Version, Error> {
+    let chunk_end_version = verify_chunk_versions(
+        fetch_next_version_to_sync(storage)?,
+        transaction_list_with_proof.first_transaction_version,
+        transaction_list_with_proof.transactions.len(),
+    )?;
+    let end_of_epoch_ledger_info =
+        get_end_of_epoch_ledger_info(target_ledger_info, chunk_end_version);
+
+    let committed_chunk = storage_synchronizer.execute_and_commit_transactions(
+        transaction_list_with_proof,
+        target_ledger_info.clone(),
+        end_of_epoch_ledger_info,
+    )?;
+    handle_committed_chunk(
+        commit_notification_handler,
+        committed_chunk,
+        chunk_end_version,
+    )
+    .await;
+
+    Ok(chunk_end_version)
+}
+
+/// Notifies the relevant components of a newly committed chunk. Failures are
+/// only logged: the chunk has already been committed to storage.
+async fn handle_committed_chunk<M: MempoolNotificationSender>(
+    commit_notification_handler: &mut CommitNotificationHandler<M>,
+    committed_chunk: CommittedChunk,
+    chunk_end_version: Version,
+) {
+    if let Err(error) = commit_notification_handler
+        .handle_committed_chunk(committed_chunk)
+        .await
+    {
+        error!(LogSchema::new(LogEntry::NotificationHandler)
+            .error(&error)
+            .synced_version(chunk_end_version)
+            .message("Failed to handle the notifications for a committed chunk!"));
+    }
+}
+
+/// Returns the given target ledger info if it ends the epoch at the given
+/// `chunk_end_version`. Otherwise, `None` is returned. This is required by the
+/// chunk executor which refuses to commit epoch ending chunks without the
+/// corresponding epoch ending ledger info.
+pub fn get_end_of_epoch_ledger_info(
+    target_ledger_info: &LedgerInfoWithSignatures,
+    chunk_end_version: Version,
+) -> Option<LedgerInfoWithSignatures> {
+    let ledger_info = target_ledger_info.ledger_info();
+    if ledger_info.ends_epoch() && ledger_info.version() == chunk_end_version {
+        Some(target_ledger_info.clone())
+    } else {
+        None
+    }
+}
+
+/// Verifies that the first version of a received chunk matches the next version
+/// we expect to sync, and returns the last version of the chunk.
+pub fn verify_chunk_versions(
+    expected_first_version: Version,
+    first_version: Option<Version>,
+    num_items: usize,
+) -> Result<Version, Error> {
+    let first_version = first_version.ok_or_else(|| {
+        Error::InvalidPayload("Received an empty chunk of transactions or outputs!".into())
+    })?;
+    if first_version != expected_first_version || num_items == 0 {
+        return Err(Error::InvalidPayload(format!(
+            "Received a chunk with an unexpected first version! Expected: {:?}, found: {:?}",
+            expected_first_version, first_version
+        )));
+    }
+    first_version
+        .checked_add(num_items as u64 - 1)
+        .ok_or_else(|| Error::IntegerOverflow("The chunk end version has overflown!".into()))
+}
+
+/// Returns the latest epoch state from storage, i.e., the trusted validator
+/// set that must sign the ledger infos of the current epoch.
+pub fn fetch_latest_epoch_state(
+    storage: &Arc<dyn DbReader<DpnProto>>,
+) -> Result<EpochState, Error> {
+    let startup_info = storage
+        .get_startup_info()
+        .map_err(|error| {
+            Error::StorageError(format!(
+                "Failed to get startup info from storage: {:?}",
+                error
+            ))
+        })?
+        .ok_or_else(|| Error::StorageError("Missing startup info from storage!".into()))?;
+    Ok(startup_info.get_epoch_state().clone())
+}
+
+/// Returns the latest ledger info committed in storage
+pub fn fetch_latest_ledger_info(
+    storage: &Arc<dyn DbReader<DpnProto>>,
+) -> Result<LedgerInfoWithSignatures, Error> {
+    storage.get_latest_ledger_info().map_err(|error| {
+        Error::StorageError(format!(
+            "Failed to get the latest ledger info from storage: {:?}",
+            error
+        ))
+    })
+}
+
+/// Returns the latest version synced in storage. Note: this may be higher than
+/// the version of the latest ledger info, e.g., if a chunk has been committed
+/// without a corresponding ledger info.
+pub fn fetch_latest_synced_version(
+    storage: &Arc<dyn DbReader<DpnProto>>,
+) -> Result<Version, Error> {
+    (&**storage).fetch_synced_version().map_err(|error| {
+        Error::StorageError(format!(
+            "Failed to fetch the latest synced version: {:?}",
+            error
+        ))
+    })
+}
+
+/// Returns the next version to sync, i.e., the version after the latest
+/// synced version in storage.
+pub fn fetch_next_version_to_sync(storage: &Arc<dyn DbReader<DpnProto>>) -> Result<Version, Error> {
+    fetch_latest_synced_version(storage)?
+        .checked_add(1)
+        .ok_or_else(|| Error::IntegerOverflow("The next version to sync has overflown!".into()))
+}
@@ -4,7 +4,7 @@
 #![forbid(unsafe_code)]
 
 use async_trait::async_trait;
-use diem_config::network_id::PeerNetworkId;
+use diem_config::network_id::{NetworkId, PeerNetworkId};
 use diem_types::PeerId;
 use network::{
     application::{
@@ -18,7 +18,7 @@ use network::{
     },
     ProtocolId,
 };
-use std::{sync::Arc, time::Duration};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 use storage_service_types::{
     StorageServiceError, StorageServiceMessage, StorageServiceRequest, StorageServiceResponse,
 };
@@ -45,11 +45,18 @@ pub struct StorageServiceClient {
 
 impl StorageServiceClient {
     pub fn new(
-        network_sender: StorageServiceMultiSender,
+        network_senders: HashMap<NetworkId, StorageServiceNetworkSender>,
         peer_metadata: Arc<PeerMetadataStorage>,
     ) -> Self {
+        let network_senders = network_senders
+            .into_iter()
+            .map(|(network_id, mut network_sender)| {
+                network_sender.initialize(network_id, peer_metadata.clone());
+                (network_id, network_sender)
+            })
+            .collect();
         Self {
-            network_sender,
+            network_sender: MultiNetworkSender::new(network_senders),
             peer_metadata,
         }
     }
@@ -98,8 +105,14 @@ impl NetworkInterface<StorageServiceMessage, StorageServiceMultiSender> for Stor
 pub type StorageServiceMultiSender =
     MultiNetworkSender<StorageServiceMessage, StorageServiceNetworkSender>;
 
+/// Supported protocols in preferred order (from highest priority to lowest).
+pub const RPC: &[ProtocolId] = &[
+    ProtocolId::StorageServiceRpcCompressed,
+    ProtocolId::StorageServiceRpc,
+];
+
 pub fn network_endpoint_config() -> AppConfig {
-    AppConfig::client([ProtocolId::StorageServiceRpc])
+    AppConfig::client(RPC.iter().copied())
 }
 
 // TODO(philiphayes): this is a lot of boilerplate for what is effectively a
@@ -109,6 +122,7 @@ pub fn network_endpoint_config() -> AppConfig {
 #[derive(Clone, Debug)]
 pub struct StorageServiceNetworkSender {
     inner: NetworkSender<StorageServiceMessage>,
+    peer_metadata: Option<(NetworkId, Arc<PeerMetadataStorage>)>,
 }
 
 impl NewNetworkSender for StorageServiceNetworkSender {
@@ -118,10 +132,34 @@ impl NewNetworkSender for StorageServiceNetworkSender {
     ) -> Self {
         Self {
             inner: NetworkSender::new(peer_mgr_reqs_tx, connection_reqs_tx),
+            peer_metadata: None,
         }
     }
 }
 
+impl StorageServiceNetworkSender {
+    /// Initialize the peer metadata of the sender's network, which is used to
+    /// pick the preferred protocol of each peer.
+    pub fn initialize(
+        &mut self,
+        network_id: NetworkId,
+        peer_metadata_storage: Arc<PeerMetadataStorage>,
+    ) {
+        self.peer_metadata = Some((network_id, peer_metadata_storage));
+    }
+
+    /// Choose the most preferred protocol supported by the peer, falling back to
+    /// uncompressed messages.
+    fn preferred_protocol_for_peer(&self, peer: PeerId) -> ProtocolId {
+        self.peer_metadata
+            .as_ref()
+            .and_then(|(network_id, peer_metadata_storage)| {
+                peer_metadata_storage.preferred_protocol(PeerNetworkId::new(*network_id, peer), RPC)
+            })
+            .unwrap_or(ProtocolId::StorageServiceRpc)
+    }
+}
+
 #[async_trait]
 impl ApplicationNetworkSender<StorageServiceMessage> for StorageServiceNetworkSender {
     fn send_to(
@@ -132,16 +170,15 @@ impl ApplicationNetworkSender<StorageServiceMessage> for StorageServiceNetworkSe
         unimplemented!()
     }
 
-    // TODO(philiphayes): a lot of boilerplate just to add `ProtocolId::StorageServiceRpc`
-    // by default.
     async fn send_rpc(
         &self,
         recipient: PeerId,
         message: StorageServiceMessage,
         timeout: Duration,
     ) -> Result<StorageServiceMessage, RpcError> {
+        let protocol = self.preferred_protocol_for_peer(recipient);
         self.inner
-            .send_rpc(recipient, ProtocolId::StorageServiceRpc, message, timeout)
+            .send_rpc(recipient, protocol, message, timeout)
             .await
     }
 }
@@ -8,38 +8,51 @@ pub mod network;
 #[cfg(test)]
 mod tests;
 
-use crate::network::StorageServiceNetworkEvents;
+use crate::network::{NetworkRequest, ResponseSender, StorageServiceNetworkEvents};
 use bounded_executor::BoundedExecutor;
+use diem_config::config::StorageServiceConfig;
+use diem_infallible::Mutex;
+use diem_logger::prelude::*;
+use diem_time_service::{TimeService, TimeServiceTrait};
 use diem_types::{
     account_state_blob::AccountStatesChunkWithProof,
     epoch_change::EpochChangeProof,
+    ledger_info::LedgerInfoWithSignatures,
     protocol_spec::DpnProto,
     transaction::{
         default_protocol::{TransactionListWithProof, TransactionOutputListWithProof},
         Version,
     },
+    PeerId,
 };
-use futures::stream::StreamExt;
+use futures::stream::{Fuse, StreamExt};
+use lru::LruCache;
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::{
+    cmp::min,
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use storage_interface::DbReader;
+use storage_service_notifications::StorageServiceNotificationListener;
 use storage_service_types::{
     AccountStatesChunkWithProofRequest, CompleteDataRange, DataSummary,
-    EpochEndingLedgerInfoRequest, ProtocolMetadata, Result, ServerProtocolVersion,
-    StorageServerSummary, StorageServiceError, StorageServiceRequest, StorageServiceResponse,
-    TransactionOutputsWithProofRequest, TransactionsWithProofRequest,
+    EpochEndingLedgerInfoRequest, NewTransactionOutputsWithProofRequest, ProtocolMetadata, Result,
+    ServerProtocolVersion, StorageServerSummary, StorageServiceError, StorageServiceRequest,
+    StorageServiceResponse, TransactionOutputsWithProofRequest, TransactionsWithProofRequest,
 };
 use thiserror::Error;
-use tokio::runtime::Handle;
+use tokio::{runtime::Handle, time::interval};
+use tokio_stream::wrappers::IntervalStream;
 
-// TODO(joshlind): make these configurable.
-/// Storage server constants.
+/// Storage server constants. The chunk sizes are the defaults advertised by
+/// the server (see the `StorageServiceConfig`).
 pub const MAX_EPOCH_CHUNK_SIZE: u64 = 1000;
 pub const MAX_TRANSACTION_CHUNK_SIZE: u64 = 1000;
 pub const MAX_TRANSACTION_OUTPUT_CHUNK_SIZE: u64 = 1000;
 pub const MAX_ACCOUNT_STATES_CHUNK_SIZE: u64 = 1000;
 pub const STORAGE_SERVER_VERSION: u64 = 1;
-pub const MAX_CONCURRENT_REQUESTS: u64 = 100;
 
 // TODO(philiphayes): is this error type providing enough value?
 #[derive(Clone, Debug, Deserialize, Error, PartialEq, Serialize)]
@@ -50,43 +63,207 @@ pub enum Error {
     UnexpectedErrorEncountered(String),
 }
 
+/// A cache of recently served responses, keyed by the request that produced
+/// them. Many peers request the same (recent) data ranges, so this avoids
+/// repeatedly reading (and serializing) the same chunks from storage.
+pub type ResponseCache = Arc<Mutex<LruCache<StorageServiceRequest, StorageServiceResponse>>>;
+
+/// A map of active data subscriptions (i.e., long-poll requests), keyed by
+/// the peer that sent them. Each peer may only have a single active subscription.
+pub type DataSubscriptions = Arc<Mutex<HashMap<PeerId, DataSubscriptionRequest>>>;
+
+/// A subscription request for new data that the server holds until the data
+/// becomes available (or the subscription expires).
+pub struct DataSubscriptionRequest {
+    expiry_time: Instant,
+    request: NewTransactionOutputsWithProofRequest,
+    response_sender: ResponseSender,
+}
+
+impl DataSubscriptionRequest {
+    pub fn new(
+        request: NewTransactionOutputsWithProofRequest,
+        response_sender: ResponseSender,
+        max_subscription_period_ms: u64,
+        time_service: &TimeService,
+    ) -> Self {
+        Self {
+            expiry_time: time_service.now() + Duration::from_millis(max_subscription_period_ms),
+            request,
+            response_sender,
+        }
+    }
+
+    /// Returns true iff the subscription has expired or the client has
+    /// stopped waiting for a response.
+    fn is_expired(&self, time_service: &TimeService) -> bool {
+        time_service.now() >= self.expiry_time || self.response_sender.is_canceled()
+    }
+}
+
 /// The server-side actor for the storage service. Handles inbound storage
 /// service requests from clients.
 pub struct StorageServiceServer<T> {
     bounded_executor: BoundedExecutor,
+    config: StorageServiceConfig,
+    data_subscriptions: DataSubscriptions,
+    lru_response_cache: ResponseCache,
     storage: T,
-    // TODO(philiphayes): would like a "multi-network" stream here, so we only
-    // need one service for all networks.
-    network_requests: StorageServiceNetworkEvents,
+    time_service: TimeService,
+    // The listener for commit notifications sent by state sync. These are
+    // used to respond to data subscriptions as soon as new data is committed.
+    storage_service_listener: StorageServiceNotificationListener,
+    // The requests of all networks (see `StorageServiceNetworkEvents::merge`)
+    network_requests: Fuse<StorageServiceNetworkEvents>,
 }
 
 impl<T: StorageReaderInterface> StorageServiceServer<T> {
     pub fn new(
+        config: StorageServiceConfig,
         executor: Handle,
         storage: T,
+        time_service: TimeService,
+        storage_service_listener: StorageServiceNotificationListener,
         network_requests: StorageServiceNetworkEvents,
     ) -> Self {
+        let bounded_executor =
+            BoundedExecutor::new(config.max_concurrent_requests as usize, executor);
+        let lru_response_cache = Arc::new(Mutex::new(LruCache::new(
+            config.max_lru_cache_size as usize,
+        )));
+
         Self {
-            bounded_executor: BoundedExecutor::new(MAX_CONCURRENT_REQUESTS as usize, executor),
+            bounded_executor,
+            config,
+            data_subscriptions: Arc::new(Mutex::new(HashMap::new())),
+            lru_response_cache,
             storage,
-            network_requests,
+            time_service,
+            storage_service_listener,
+            network_requests: network_requests.fuse(),
         }
     }
 
     pub async fn start(mut self) {
-        while let Some(request) = self.network_requests.next().await {
-            let storage = self.storage.clone();
-
-            // All handler methods are currently CPU-bound and synchronous
-            // I/O-bound, so we want to spawn on the blocking thread pool to
-            // avoid starving other async tasks on the same runtime.
-            self.bounded_executor
-                .spawn_blocking(move || {
-                    let (_peer, _protocol, request, response_sender) = request;
-                    let response = Handler::new(storage).call(request);
-                    response_sender.send(response);
-                })
-                .await;
+        let mut subscription_refresh_interval = IntervalStream::new(interval(
+            Duration::from_millis(self.config.subscription_refresh_interval_ms),
+        ))
+        .fuse();
+
+        loop {
+            ::futures::select! {
+                network_request = self.network_requests.next() => {
+                    match network_request {
+                        Some(network_request) => self.handle_network_request(network_request).await,
+                        None => break, // The network request stream has terminated
+                    }
+                }
+                _ = self.storage_service_listener.select_next_some() => {
+                    // New data has been committed. Respond to any subscriptions
+                    // that can now be satisfied.
+                    self.refresh_data_subscriptions().await;
+                }
+                _ = subscription_refresh_interval.select_next_some() => {
+                    // Periodically remove any expired subscriptions (and
+                    // respond to those that can now be satisfied).
+                    self.refresh_data_subscriptions().await;
+                }
+            }
+        }
+    }
+
+    /// Handles a single inbound network request. Data subscriptions are
+    /// stored until new data is available, while all other requests are
+    /// handled (and responded to) immediately.
+    async fn handle_network_request(&mut self, network_request: NetworkRequest) {
+        let (peer, _protocol, request, response_sender) = network_request;
+        if let StorageServiceRequest::GetNewTransactionOutputsWithProof(request) = request {
+            let data_subscription = DataSubscriptionRequest::new(
+                request,
+                response_sender,
+                self.config.max_subscription_period_ms,
+                &self.time_service,
+            );
+            let stale_subscription = self
+                .data_subscriptions
+                .lock()
+                .insert(peer, data_subscription);
+
+            // If the peer already had an active subscription, it is replaced.
+            // Notify the client so it doesn't wait for the RPC timeout.
+            if let Some(stale_subscription) = stale_subscription {
+                stale_subscription
+                    .response_sender
+                    .send(Err(StorageServiceError::SubscriptionSuperseded));
+            }
+
+            // The new data may already be available
+            self.refresh_data_subscriptions().await;
+            return;
+        }
+
+        let config = self.config.clone();
+        let lru_response_cache = self.lru_response_cache.clone();
+        let storage = self.storage.clone();
+
+        // All handler methods are currently CPU-bound and synchronous
+        // I/O-bound, so we want to spawn on the blocking thread pool to
+        // avoid starving other async tasks on the same runtime.
+        self.bounded_executor
+            .spawn_blocking(move || {
+                // Skip requests the client stopped waiting for while they
+                // were queued.
+                if response_sender.is_canceled() {
+                    return;
+                }
+                let response = Handler::new(config, lru_response_cache, storage).call(request);
+                response_sender.send(response);
+            })
+            .await;
+    }
+
+    /// Spawns a blocking task to respond to all data subscriptions that can
+    /// be satisfied with the data currently in storage.
+    async fn refresh_data_subscriptions(&mut self) {
+        self.expire_data_subscriptions();
+        if self.data_subscriptions.lock().is_empty() {
+            return; // There's nothing to refresh
+        }
+
+        let config = self.config.clone();
+        let data_subscriptions = self.data_subscriptions.clone();
+        let lru_response_cache = self.lru_response_cache.clone();
+        let storage = self.storage.clone();
+
+        self.bounded_executor
+            .spawn_blocking(move || {
+                Handler::new(config, lru_response_cache, storage)
+                    .process_data_subscriptions(data_subscriptions);
+            })
+            .await;
+    }
+
+    /// Removes all expired data subscriptions and notifies the clients that
+    /// the subscriptions expired (so they don't wait for the RPC timeout).
+    fn expire_data_subscriptions(&mut self) {
+        let time_service = self.time_service.clone();
+        let expired_subscriptions = {
+            let mut data_subscriptions = self.data_subscriptions.lock();
+            let expired_peers: Vec<PeerId> = data_subscriptions
+                .iter()
+                .filter(|(_, data_subscription)| data_subscription.is_expired(&time_service))
+                .map(|(peer, _)| *peer)
+                .collect();
+            expired_peers
+                .iter()
+                .filter_map(|peer| data_subscriptions.remove(peer))
+                .collect::<Vec<_>>()
+        };
+
+        for data_subscription in expired_subscriptions {
+            data_subscription
+                .response_sender
+                .send(Err(StorageServiceError::SubscriptionExpired));
         }
     }
 }
@@ -96,22 +273,54 @@ impl<T: StorageReaderInterface> StorageServiceServer<T> {
 /// request. We usually clone/create a new handler for every request.
 #[derive(Clone)]
 pub struct Handler<T> {
+    config: StorageServiceConfig,
+    lru_response_cache: ResponseCache,
     storage: T,
 }
 
 impl<T: StorageReaderInterface> Handler<T> {
-    pub fn new(storage: T) -> Self {
-        Self { storage }
+    pub fn new(
+        config: StorageServiceConfig,
+        lru_response_cache: ResponseCache,
+        storage: T,
+    ) -> Self {
+        Self {
+            config,
+            lru_response_cache,
+            storage,
+        }
     }
 
     pub fn call(&self, request: StorageServiceRequest) -> Result<StorageServiceResponse> {
-        let response = match request {
+        // Only data requests are cached. The protocol version is trivial to
+        // compute and the storage summary changes as the node syncs.
+        let cacheable_request = !matches!(
+            request,
+            StorageServiceRequest::GetNewTransactionOutputsWithProof(_)
+                | StorageServiceRequest::GetServerProtocolVersion
+                | StorageServiceRequest::GetStorageServerSummary
+        );
+        if cacheable_request {
+            if let Some(response) = self.lru_response_cache.lock().get(&request) {
+                return Ok(response.clone());
+            }
+        }
+
+        let response = match request.clone() {
             StorageServiceRequest::GetAccountStatesChunkWithProof(request) => {
                 self.get_account_states_chunk_with_proof(request)
             }
             StorageServiceRequest::GetEpochEndingLedgerInfos(request) => {
                 self.get_epoch_ending_ledger_infos(request)
             }
+            StorageServiceRequest::GetNewTransactionOutputsWithProof(request) => {
+                // Subscriptions are held by the server until new data is
+                // available, so they can't be handled synchronously.
+                Err(Error::UnexpectedErrorEncountered(format!(
+                    "Data subscriptions must be handled by the storage server! Request: {:?}",
+                    request
+                )))
+            }
             StorageServiceRequest::GetNumberOfAccountsAtVersion(version) => {
                 self.get_number_of_accounts_at_version(version)
             }
@@ -127,21 +336,115 @@ impl<T: StorageReaderInterface> Handler<T> {
 
         // If any requests resulted in an unexpected error, return an InternalStorageError to the
         // client and log the actual error.
-        response.map_err(|_err| {
-            // TODO(joshlind): add logging support to this library so we can log _error
+        let response = response.map_err(|error| {
+            error!("Failed to handle the storage service request: {}", error);
             StorageServiceError::InternalError
-        })
+        })?;
+
+        if cacheable_request {
+            self.lru_response_cache
+                .lock()
+                .put(request, response.clone());
+        }
+        Ok(response)
+    }
+
+    /// Responds to all data subscriptions that can be satisfied by the data
+    /// currently in storage (i.e., those where the known version is lower
+    /// than the highest synced version).
+    pub fn process_data_subscriptions(&self, data_subscriptions: DataSubscriptions) {
+        // Fetch the highest synced ledger info
+        let synced_ledger_info = match self.storage.get_data_summary() {
+            Ok(DataSummary {
+                synced_ledger_info: Some(synced_ledger_info),
+                ..
+            }) => synced_ledger_info,
+            Ok(_) => return, // There is no synced data to serve yet
+            Err(error) => {
+                error!(
+                    "Failed to fetch the data summary for the data subscriptions: {}",
+                    error
+                );
+                return;
+            }
+        };
+
+        // Identify (and remove) the subscriptions that are ready to be served
+        let synced_version = synced_ledger_info.ledger_info().version();
+        let ready_subscriptions = {
+            let mut data_subscriptions = data_subscriptions.lock();
+            let ready_peers: Vec<PeerId> = data_subscriptions
+                .iter()
+                .filter(|(_, data_subscription)| {
+                    data_subscription.request.known_version < synced_version
+                })
+                .map(|(peer, _)| *peer)
+                .collect();
+            ready_peers
+                .iter()
+                .filter_map(|peer| data_subscriptions.remove(peer))
+                .collect::<Vec<_>>()
+        };
+
+        // Respond to each ready subscription
+        for data_subscription in ready_subscriptions {
+            let response = self
+                .get_new_transaction_outputs_with_proof(
+                    data_subscription.request,
+                    &synced_ledger_info,
+                )
+                .map_err(|error| {
+                    error!(
+                        "Failed to fetch the new transaction outputs of a data subscription: {}",
+                        error
+                    );
+                    StorageServiceError::InternalError
+                });
+            data_subscription.response_sender.send(response);
+        }
+    }
+
+    /// Fetches a data chunk of at most `max_num_items` using the given
+    /// `fetch_chunk` function. If the serialized chunk exceeds the maximum
+    /// network chunk size, the number of items is halved and the chunk is
+    /// re-fetched. A chunk containing a single item is always returned.
+    fn fetch_chunk_within_network_limit<D: Serialize>(
+        &self,
+        max_num_items: u64,
+        fetch_chunk: impl Fn(u64) -> Result<D, Error>,
+    ) -> Result<D, Error> {
+        let mut num_items_to_fetch = max_num_items;
+        loop {
+            let chunk = fetch_chunk(num_items_to_fetch)?;
+            let num_bytes = bcs::serialized_size(&chunk).map_err(|error| {
+                Error::UnexpectedErrorEncountered(format!(
+                    "Failed to compute the serialized chunk size: {:?}",
+                    error
+                ))
+            })?;
+            if num_bytes as u64 <= self.config.max_network_chunk_bytes || num_items_to_fetch <= 1 {
+                return Ok(chunk);
+            }
+            num_items_to_fetch /= 2;
+        }
     }
 
     fn get_account_states_chunk_with_proof(
         &self,
         request: AccountStatesChunkWithProofRequest,
     ) -> Result<StorageServiceResponse, Error> {
-        let account_states_chunk_with_proof = self.storage.get_account_states_chunk_with_proof(
-            request.version,
-            request.start_account_index,
+        let max_num_account_states = min(
             request.expected_num_account_states,
-        )?;
+            self.config.max_account_states_chunk_size,
+        );
+        let account_states_chunk_with_proof =
+            self.fetch_chunk_within_network_limit(max_num_account_states, |num_account_states| {
+                self.storage.get_account_states_chunk_with_proof(
+                    request.version,
+                    request.start_account_index,
+                    num_account_states,
+                )
+            })?;
 
         Ok(StorageServiceResponse::AccountStatesChunkWithProof(
             account_states_chunk_with_proof,
@@ -152,15 +455,84 @@ impl<T: StorageReaderInterface> Handler<T> {
         &self,
         request: EpochEndingLedgerInfoRequest,
     ) -> Result<StorageServiceResponse, Error> {
-        let epoch_change_proof = self
-            .storage
-            .get_epoch_ending_ledger_infos(request.start_epoch, request.expected_end_epoch)?;
+        let start_epoch = request.start_epoch;
+        let num_requested_epochs = request
+            .expected_end_epoch
+            .checked_sub(start_epoch)
+            .and_then(|num_epochs| num_epochs.checked_add(1))
+            .ok_or_else(|| {
+                Error::UnexpectedErrorEncountered(format!(
+                    "Invalid epoch range requested! Start: {:?}, end: {:?}",
+                    start_epoch, request.expected_end_epoch
+                ))
+            })?;
+        let max_num_epochs = min(num_requested_epochs, self.config.max_epoch_chunk_size);
+        let epoch_change_proof =
+            self.fetch_chunk_within_network_limit(max_num_epochs, |num_epochs| {
+                self.storage
+                    .get_epoch_ending_ledger_infos(start_epoch, start_epoch + num_epochs - 1)
+            })?;
 
         Ok(StorageServiceResponse::EpochEndingLedgerInfos(
             epoch_change_proof,
         ))
     }
 
+    fn get_new_transaction_outputs_with_proof(
+        &self,
+        request: NewTransactionOutputsWithProofRequest,
+        synced_ledger_info: &LedgerInfoWithSignatures,
+    ) -> Result<StorageServiceResponse, Error> {
+        // If the known epoch has ended, the outputs must be proven relative to
+        // the epoch ending ledger info (so that the client can verify them).
+        let target_ledger_info = if request.known_epoch < synced_ledger_info.ledger_info().epoch() {
+            let epoch_change_proof = self
+                .storage
+                .get_epoch_ending_ledger_infos(request.known_epoch, request.known_epoch)?;
+            epoch_change_proof
+                .ledger_info_with_sigs
+                .first()
+                .cloned()
+                .ok_or_else(|| {
+                    Error::UnexpectedErrorEncountered(format!(
+                        "Failed to fetch the epoch ending ledger info for epoch: {:?}",
+                        request.known_epoch
+                    ))
+                })?
+        } else {
+            synced_ledger_info.clone()
+        };
+
+        // Fetch the new transaction outputs (up to the target version)
+        let target_version = target_ledger_info.ledger_info().version();
+        let num_new_outputs = target_version
+            .checked_sub(request.known_version)
+            .filter(|num_new_outputs| *num_new_outputs > 0)
+            .ok_or_else(|| {
+                Error::UnexpectedErrorEncountered(format!(
+                    "No new transaction outputs to serve! Known version: {:?}, target version: {:?}",
+                    request.known_version, target_version
+                ))
+            })?;
+        let max_num_outputs = min(
+            num_new_outputs,
+            self.config.max_transaction_output_chunk_size,
+        );
+        let transaction_output_list_with_proof =
+            self.fetch_chunk_within_network_limit(max_num_outputs, |num_outputs| {
+                self.storage.get_transaction_outputs_with_proof(
+                    target_version,
+                    request.known_version + 1,
+                    num_outputs,
+                )
+            })?;
+
+        Ok(StorageServiceResponse::NewTransactionOutputsWithProof((
+            transaction_output_list_with_proof,
+            target_ledger_info,
+        )))
+    }
+
     fn get_number_of_accounts_at_version(
         &self,
         version: Version,
@@ -184,10 +556,10 @@ impl<T: StorageReaderInterface> Handler<T> {
     fn get_storage_server_summary(&self) -> Result<StorageServiceResponse, Error> {
         let storage_server_summary = StorageServerSummary {
             protocol_metadata: ProtocolMetadata {
-                max_epoch_chunk_size: MAX_EPOCH_CHUNK_SIZE,
-                max_transaction_chunk_size: MAX_TRANSACTION_CHUNK_SIZE,
-                max_transaction_output_chunk_size: MAX_TRANSACTION_OUTPUT_CHUNK_SIZE,
-                max_account_states_chunk_size: MAX_ACCOUNT_STATES_CHUNK_SIZE,
+                max_epoch_chunk_size: self.config.max_epoch_chunk_size,
+                max_transaction_chunk_size: self.config.max_transaction_chunk_size,
+                max_transaction_output_chunk_size: self.config.max_transaction_output_chunk_size,
+                max_account_states_chunk_size: self.config.max_account_states_chunk_size,
             },
             data_summary: self.storage.get_data_summary()?,
         };
@@ -201,11 +573,18 @@ impl<T: StorageReaderInterface> Handler<T> {
         &self,
         request: TransactionOutputsWithProofRequest,
     ) -> Result<StorageServiceResponse, Error> {
-        let transaction_output_list_with_proof = self.storage.get_transaction_outputs_with_proof(
-            request.proof_version,
-            request.start_version,
+        let max_num_outputs = min(
             request.expected_num_outputs,
-        )?;
+            self.config.max_transaction_output_chunk_size,
+        );
+        let transaction_output_list_with_proof =
+            self.fetch_chunk_within_network_limit(max_num_outputs, |num_outputs| {
+                self.storage.get_transaction_outputs_with_proof(
+                    request.proof_version,
+                    request.start_version,
+                    num_outputs,
+                )
+            })?;
 
         Ok(StorageServiceResponse::TransactionOutputsWithProof(
             transaction_output_list_with_proof,
@@ -216,12 +595,19 @@ impl<T: StorageReaderInterface> Handler<T> {
         &self,
         request: TransactionsWithProofRequest,
     ) -> Result<StorageServiceResponse, Error> {
-        let transactions_with_proof = self.storage.get_transactions_with_proof(
-            request.proof_version,
-            request.start_version,
+        let max_num_transactions = min(
             request.expected_num_transactions,
-            request.include_events,
-        )?;
+            self.config.max_transaction_chunk_size,
+        );
+        let transactions_with_proof =
+            self.fetch_chunk_within_network_limit(max_num_transactions, |num_transactions| {
+                self.storage.get_transactions_with_proof(
+                    request.proof_version,
+                    request.start_version,
+                    num_transactions,
+                    request.include_events,
+                )
+            })?;
 
         Ok(StorageServiceResponse::TransactionsWithProof(
             transactions_with_proof,
@@ -350,14 +736,19 @@ impl StorageReaderInterface for StorageReader {
 
     fn get_transaction_outputs_with_proof(
         &self,
-        _proof_version: u64,
-        _start_version: u64,
-        _expected_num_transaction_outputs: u64,
+        proof_version: u64,
+        start_version: u64,
+        expected_num_transaction_outputs: u64,
     ) -> Result<TransactionOutputListWithProof, Error> {
-        // TODO(joshlind): implement this once the transaction outputs are persisted in the DB.
-        Err(Error::UnexpectedErrorEncountered(
-            "Unimplemented! This API call needs to be implemented!".into(),
-        ))
+        let transaction_output_list_with_proof = self
+            .storage
+            .get_transaction_outputs(
+                start_version,
+                expected_num_transaction_outputs,
+                proof_version,
+            )
+            .map_err(|error| Error::StorageErrorEncountered(error.to_string()))?;
+        Ok(transaction_output_list_with_proof)
     }
 
     fn get_account_states_chunk_with_proof(
@@ -7,7 +7,7 @@ use diem_types::PeerId;
 use futures::{
     channel::oneshot,
     future,
-    stream::{BoxStream, Stream, StreamExt},
+    stream::{self, BoxStream, Stream, StreamExt},
 };
 use network::{
     peer_manager::{ConnectionNotification, PeerManagerNotification},
@@ -26,7 +26,10 @@ const INBOUND_CHANNEL_SIZE: usize = 100;
 
 pub fn network_endpoint_config() -> AppConfig {
     AppConfig::service(
-        [ProtocolId::StorageServiceRpc],
+        [
+            ProtocolId::StorageServiceRpc,
+            ProtocolId::StorageServiceRpcCompressed,
+        ],
         diem_channel::Config::new(INBOUND_CHANNEL_SIZE).queue_style(QueueStyle::FIFO),
     )
 }
@@ -51,6 +54,12 @@ impl NewNetworkEvents for StorageServiceNetworkEvents {
 }
 
 impl StorageServiceNetworkEvents {
+    /// Merges the request streams of several networks into a single stream,
+    /// so that one server can handle the requests of all networks.
+    pub fn merge(network_events: Vec<StorageServiceNetworkEvents>) -> Self {
+        Self(stream::select_all(network_events).boxed())
+    }
+
     /// Filters out everything except Rpc requests
     fn event_to_request(event: Event<StorageServiceMessage>) -> Option<NetworkRequest> {
         // TODO(philiphayes): logging
@@ -61,7 +70,7 @@ impl StorageServiceNetworkEvents {
                 protocol_id,
                 response_tx,
             ) => {
-                let response_tx = ResponseSender::new(response_tx);
+                let response_tx = ResponseSender::new(protocol_id, response_tx);
                 Some((peer_id, protocol_id, request, response_tx))
             }
             // We don't use DirectSend and don't care about connection events.
@@ -80,20 +89,36 @@ impl Stream for StorageServiceNetworkEvents {
 
 /// A channel for fulfilling a pending StorageService RPC request.
 /// Provides a more strongly typed interface around the raw RPC response channel.
+/// Responses are encoded with the protocol of the request.
 pub struct ResponseSender {
+    protocol_id: ProtocolId,
     response_tx: oneshot::Sender<Result<Bytes, RpcError>>,
 }
 
 impl ResponseSender {
-    pub fn new(response_tx: oneshot::Sender<Result<Bytes, RpcError>>) -> Self {
-        Self { response_tx }
+    pub fn new(
+        protocol_id: ProtocolId,
+        response_tx: oneshot::Sender<Result<Bytes, RpcError>>,
+    ) -> Self {
+        Self {
+            protocol_id,
+            response_tx,
+        }
+    }
+
+    /// Whether the client has stopped waiting for the response, e.g., because
+    /// the request timed out or was canceled by the remote peer.
+    pub fn is_canceled(&self) -> bool {
+        self.response_tx.is_canceled()
     }
 
     pub fn send(self, response: Result<StorageServiceResponse>) {
         let msg = StorageServiceMessage::Response(response);
-        let result = bcs::to_bytes(&msg)
+        let result = self
+            .protocol_id
+            .to_bytes(&msg)
             .map(Bytes::from)
-            .map_err(RpcError::BcsError);
+            .map_err(RpcError::Error);
         let _ = self.response_tx.send(result);
     }
 }
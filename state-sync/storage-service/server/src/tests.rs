@@ -3,11 +3,15 @@
 
 #![forbid(unsafe_code)]
 
-use crate::{network::StorageServiceNetworkEvents, StorageReader, StorageServiceServer};
+use crate::{network::StorageServiceNetworkEvents, Handler, StorageReader, StorageServiceServer};
 use anyhow::Result;
+use bytes::Bytes;
 use channel::diem_channel;
-use claim::{assert_matches, assert_none, assert_some};
+use claim::{assert_err, assert_matches, assert_none, assert_some};
+use diem_config::config::StorageServiceConfig;
 use diem_crypto::{ed25519::Ed25519PrivateKey, HashValue, PrivateKey, SigningKey, Uniform};
+use diem_infallible::Mutex;
+use diem_time_service::{MockTimeService, TimeService};
 use diem_types::{
     account_address::AccountAddress,
     account_state_blob::{default_protocol::AccountStateWithProof, AccountStateBlob},
@@ -28,26 +32,37 @@ use diem_types::{
             AccountTransactionsWithProof, TransactionListWithProof, TransactionOutputListWithProof,
             TransactionWithProof,
         },
-        RawTransaction, Script, SignedTransaction, Transaction, TransactionPayload, Version,
+        RawTransaction, Script, SignedTransaction, Transaction, TransactionOutput,
+        TransactionPayload, TransactionStatus, Version,
     },
+    vm_status::KeptVMStatus,
+    write_set::WriteSet,
     PeerId,
 };
 use futures::channel::oneshot;
+use lru::LruCache;
 use move_core_types::language_storage::TypeTag;
 use network::{
     peer_manager::PeerManagerNotification,
     protocols::{
-        network::NewNetworkEvents, rpc::InboundRpcRequest, wire::handshake::v1::ProtocolId,
+        network::NewNetworkEvents,
+        rpc::{error::RpcError, InboundRpcRequest},
+        wire::handshake::v1::ProtocolId,
     },
 };
-use std::{collections::BTreeMap, sync::Arc};
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
 use storage_interface::{DbReader, Order, StartupInfo, TreeState};
+use storage_service_notifications::{
+    new_storage_service_notifier_listener_pair, StorageServiceNotifier,
+};
 use storage_service_types::{
     AccountStatesChunkWithProofRequest, CompleteDataRange, DataSummary,
-    EpochEndingLedgerInfoRequest, ProtocolMetadata, ServerProtocolVersion, StorageServerSummary,
-    StorageServiceError, StorageServiceMessage, StorageServiceRequest, StorageServiceResponse,
-    TransactionOutputsWithProofRequest, TransactionsWithProofRequest,
+    EpochEndingLedgerInfoRequest, NewTransactionOutputsWithProofRequest, ProtocolMetadata,
+    ServerProtocolVersion, StorageServerSummary, StorageServiceError, StorageServiceMessage,
+    StorageServiceRequest, StorageServiceResponse, TransactionOutputsWithProofRequest,
+    TransactionsWithProofRequest,
 };
+use tokio::time::timeout;
 
 // TODO(joshlind): Expand these test cases to better test storage interaction
 // and functionality. This will likely require a better mock db abstraction.
@@ -219,18 +234,154 @@ async fn test_get_transaction_outputs_with_proof() {
     tokio::spawn(service.start());
 
     // Create a request to fetch transaction outputs with a proof
+    let start_version = 0;
+    let expected_num_outputs = 10;
     let request =
         StorageServiceRequest::GetTransactionOutputsWithProof(TransactionOutputsWithProofRequest {
             proof_version: 1000,
-            start_version: 0,
-            expected_num_outputs: 10,
+            start_version,
+            expected_num_outputs,
         });
 
     // Process the request
-    let error = mock_client.send_request(request).await.unwrap_err();
+    let response = mock_client.send_request(request).await.unwrap();
 
-    // Verify the response is correct (the API call is currently unsupported)
-    assert_matches!(error, StorageServiceError::InternalError);
+    // Verify the response is correct
+    match response {
+        StorageServiceResponse::TransactionOutputsWithProof(outputs_with_proof) => {
+            assert_eq!(
+                outputs_with_proof.transactions_and_outputs.len(),
+                expected_num_outputs as usize
+            );
+            assert_eq!(
+                outputs_with_proof.first_transaction_output_version,
+                Some(start_version)
+            );
+        }
+        _ => {
+            panic!(
+                "Expected transaction outputs with proof but got: {:?}",
+                response
+            );
+        }
+    };
+}
+
+#[tokio::test]
+async fn test_data_subscription_new_data() {
+    let (mut mock_client, service) = MockClient::new();
+    tokio::spawn(service.start());
+
+    // Subscribe to new transaction outputs (the mock storage is at version 100)
+    let known_version = 90;
+    let request = StorageServiceRequest::GetNewTransactionOutputsWithProof(
+        NewTransactionOutputsWithProofRequest {
+            known_version,
+            known_epoch: 10,
+        },
+    );
+
+    // Process the request
+    let response = mock_client.send_request(request).await.unwrap();
+
+    // Verify the new outputs are returned with the latest ledger info
+    match response {
+        StorageServiceResponse::NewTransactionOutputsWithProof((
+            outputs_with_proof,
+            ledger_info,
+        )) => {
+            assert_eq!(outputs_with_proof.transactions_and_outputs.len(), 10);
+            assert_eq!(
+                outputs_with_proof.first_transaction_output_version,
+                Some(known_version + 1)
+            );
+            assert_eq!(ledger_info, create_test_ledger_info_with_sigs(10, 100));
+        }
+        _ => {
+            panic!("Expected new transaction outputs but got: {:?}", response);
+        }
+    };
+}
+
+#[tokio::test]
+async fn test_data_subscription_expiry() {
+    let config = StorageServiceConfig {
+        max_subscription_period_ms: 100,
+        subscription_refresh_interval_ms: 10,
+        ..Default::default()
+    };
+    let (mut mock_client, service) = MockClient::new_with_config(config);
+    tokio::spawn(service.start());
+
+    // Subscribe to new transaction outputs (there is no new data to serve)
+    let request = StorageServiceRequest::GetNewTransactionOutputsWithProof(
+        NewTransactionOutputsWithProofRequest {
+            known_version: 100,
+            known_epoch: 10,
+        },
+    );
+    let mut response_receiver = mock_client.send_request_without_waiting(request);
+
+    // Verify the subscription doesn't expire while the (mock) time is frozen
+    assert_err!(timeout(Duration::from_millis(500), &mut response_receiver).await);
+
+    // Advance the time until the subscription expires
+    let mut response = None;
+    for _ in 0..100 {
+        mock_client.time_service.advance_ms(200);
+        if let Ok(result) = timeout(Duration::from_millis(100), &mut response_receiver).await {
+            response = Some(result);
+            break;
+        }
+    }
+
+    // Verify the client is explicitly notified that the subscription expired
+    let response = response
+        .expect("The subscription should have expired!")
+        .unwrap()
+        .unwrap();
+    let response = ProtocolId::StorageServiceRpc
+        .from_bytes::<StorageServiceMessage>(&response)
+        .unwrap();
+    assert_matches!(
+        response,
+        StorageServiceMessage::Response(Err(StorageServiceError::SubscriptionExpired))
+    );
+}
+
+#[tokio::test]
+async fn test_data_subscription_superseded() {
+    let (mut mock_client, service) = MockClient::new();
+    tokio::spawn(service.start());
+
+    // Subscribe to new transaction outputs (there is no new data to serve)
+    let request = StorageServiceRequest::GetNewTransactionOutputsWithProof(
+        NewTransactionOutputsWithProofRequest {
+            known_version: 100,
+            known_epoch: 10,
+        },
+    );
+    let stale_response_receiver = mock_client.send_request_without_waiting(request.clone());
+
+    // Subscribe again from the same peer
+    let mut response_receiver = mock_client.send_request_without_waiting(request);
+
+    // Verify the client is explicitly notified that the first subscription was replaced
+    let response = timeout(Duration::from_secs(10), stale_response_receiver)
+        .await
+        .expect("The stale subscription should have been answered!")
+        .unwrap()
+        .unwrap();
+    let response = ProtocolId::StorageServiceRpc
+        .from_bytes::<StorageServiceMessage>(&response)
+        .unwrap();
+    assert_matches!(
+        response,
+        StorageServiceMessage::Response(Err(StorageServiceError::SubscriptionSuperseded))
+    );
+
+    // Verify the new subscription is still active
+    assert_err!(timeout(Duration::from_millis(500), &mut response_receiver).await);
 }
 
 #[tokio::test]
@@ -272,14 +423,188 @@ async fn test_get_epoch_ending_ledger_infos() {
     };
 }
 
+#[tokio::test]
+async fn test_get_transactions_with_proof_chunk_size_limit() {
+    let max_transaction_chunk_size = 5;
+    let config = StorageServiceConfig {
+        max_transaction_chunk_size,
+        ..Default::default()
+    };
+    let (mut mock_client, service) = MockClient::new_with_config(config);
+    tokio::spawn(service.start());
+
+    // Request more transactions than the server is willing to serve
+    let request = StorageServiceRequest::GetTransactionsWithProof(TransactionsWithProofRequest {
+        proof_version: 100,
+        start_version: 0,
+        expected_num_transactions: 10,
+        include_events: false,
+    });
+    let response = mock_client.send_request(request).await.unwrap();
+
+    // Verify the chunk was capped at the max transaction chunk size
+    match response {
+        StorageServiceResponse::TransactionsWithProof(transactions_with_proof) => {
+            assert_eq!(
+                transactions_with_proof.transactions.len(),
+                max_transaction_chunk_size as usize
+            );
+        }
+        _ => {
+            panic!("Expected transactions with proof but got: {:?}", response);
+        }
+    };
+}
+
+#[tokio::test]
+async fn test_get_transactions_with_proof_network_limit() {
+    let config = StorageServiceConfig {
+        max_network_chunk_bytes: 1,
+        ..Default::default()
+    };
+    let (mut mock_client, service) = MockClient::new_with_config(config);
+    tokio::spawn(service.start());
+
+    // Request a chunk that can't fit in a single network message
+    let start_version = 50;
+    let request = StorageServiceRequest::GetTransactionsWithProof(TransactionsWithProofRequest {
+        proof_version: 100,
+        start_version,
+        expected_num_transactions: 10,
+        include_events: true,
+    });
+    let response = mock_client.send_request(request).await.unwrap();
+
+    // Verify the server shrunk the chunk down to a single transaction
+    match response {
+        StorageServiceResponse::TransactionsWithProof(transactions_with_proof) => {
+            assert_eq!(transactions_with_proof.transactions.len(), 1);
+            assert_eq!(
+                transactions_with_proof.first_transaction_version,
+                Some(start_version)
+            );
+        }
+        _ => {
+            panic!("Expected transactions with proof but got: {:?}", response);
+        }
+    };
+}
+
+#[tokio::test]
+async fn test_get_epoch_ending_ledger_infos_network_limit() {
+    // Calculate the size of a chunk holding two epoch ending ledger infos
+    let start_epoch = 5;
+    let two_epoch_chunk = MockDbReader
+        .get_epoch_ending_ledger_infos(start_epoch, start_epoch + 1)
+        .unwrap();
+    let max_network_chunk_bytes = bcs::serialized_size(&two_epoch_chunk).unwrap() as u64;
+
+    let config = StorageServiceConfig {
+        max_network_chunk_bytes,
+        ..Default::default()
+    };
+    let (mut mock_client, service) = MockClient::new_with_config(config);
+    tokio::spawn(service.start());
+
+    // Request more epochs than fit in a single network message
+    let request = StorageServiceRequest::GetEpochEndingLedgerInfos(EpochEndingLedgerInfoRequest {
+        start_epoch,
+        expected_end_epoch: start_epoch + 9,
+    });
+    let response = mock_client.send_request(request).await.unwrap();
+
+    // Verify the server halved the chunk until it fit (10 -> 5 -> 2)
+    match response {
+        StorageServiceResponse::EpochEndingLedgerInfos(epoch_change_proof) => {
+            assert_eq!(epoch_change_proof.ledger_info_with_sigs.len(), 2);
+            assert_eq!(
+                epoch_change_proof.ledger_info_with_sigs[0]
+                    .ledger_info()
+                    .epoch(),
+                start_epoch
+            );
+        }
+        _ => {
+            panic!("Expected epoch ending ledger infos but got: {:?}", response);
+        }
+    };
+}
+
+#[test]
+fn test_cached_responses() {
+    let lru_response_cache = Arc::new(Mutex::new(LruCache::new(10)));
+    let handler = Handler::new(
+        StorageServiceConfig::default(),
+        lru_response_cache.clone(),
+        StorageReader::new(Arc::new(MockDbReader)),
+    );
+
+    // Process the same data request twice and verify the response is cached
+    let request = StorageServiceRequest::GetEpochEndingLedgerInfos(EpochEndingLedgerInfoRequest {
+        start_epoch: 0,
+        expected_end_epoch: 5,
+    });
+    let response = handler.call(request.clone()).unwrap();
+    assert_eq!(
+        lru_response_cache.lock().get(&request).cloned(),
+        Some(response.clone())
+    );
+    assert_eq!(handler.call(request).unwrap(), response);
+    assert_eq!(lru_response_cache.lock().len(), 1);
+
+    // Verify storage summary requests are never cached
+    let request = StorageServiceRequest::GetStorageServerSummary;
+    handler.call(request.clone()).unwrap();
+    assert_eq!(lru_response_cache.lock().get(&request), None);
+    assert_eq!(lru_response_cache.lock().len(), 1);
+}
+
+#[test]
+fn test_cache_eviction() {
+    let lru_response_cache = Arc::new(Mutex::new(LruCache::new(2)));
+    let handler = Handler::new(
+        StorageServiceConfig::default(),
+        lru_response_cache.clone(),
+        StorageReader::new(Arc::new(MockDbReader)),
+    );
+
+    // Process more unique requests than the cache can hold
+    let requests: Vec<_> = (0..3)
+        .map(|start_epoch| {
+            StorageServiceRequest::GetEpochEndingLedgerInfos(EpochEndingLedgerInfoRequest {
+                start_epoch,
+                expected_end_epoch: start_epoch + 1,
+            })
+        })
+        .collect();
+    for request in &requests {
+        handler.call(request.clone()).unwrap();
+    }
+
+    // Verify the least recently used response was evicted
+    let lru_response_cache = lru_response_cache.lock();
+    assert_eq!(lru_response_cache.len(), 2);
+    assert!(!lru_response_cache.contains(&requests[0]));
+    assert!(lru_response_cache.contains(&requests[1]));
+    assert!(lru_response_cache.contains(&requests[2]));
+}
+
 /// A wrapper around the inbound network interface/channel for easily sending
 /// mock client requests to a [`StorageServiceServer`].
 struct MockClient {
     peer_mgr_notifs_tx: diem_channel::Sender<(PeerId, ProtocolId), PeerManagerNotification>,
+    time_service: MockTimeService,
+    _storage_service_notifier: StorageServiceNotifier,
 }
 
 impl MockClient {
     fn new() -> (Self, StorageServiceServer<StorageReader>) {
+        Self::new_with_config(StorageServiceConfig::default())
+    }
+
+    fn new_with_config(
+        config: StorageServiceConfig,
+    ) -> (Self, StorageServiceServer<StorageReader>) {
         let storage = StorageReader::new(Arc::new(MockDbReader));
 
         let queue_cfg = crate::network::network_endpoint_config()
@@ -290,16 +615,51 @@ impl MockClient {
         let network_requests =
             StorageServiceNetworkEvents::new(peer_mgr_notifs_rx, connection_notifs_rx);
 
-        let executor = tokio::runtime::Handle::current();
-        let storage_server = StorageServiceServer::new(executor, storage, network_requests);
+        let (storage_service_notifier, storage_service_listener) =
+            new_storage_service_notifier_listener_pair();
 
-        (Self { peer_mgr_notifs_tx }, storage_server)
+        let executor = tokio::runtime::Handle::current();
+        let time_service = TimeService::mock();
+        let storage_server = StorageServiceServer::new(
+            config,
+            executor,
+            storage,
+            time_service.clone(),
+            storage_service_listener,
+            network_requests,
+        );
+
+        let mock_client = Self {
+            peer_mgr_notifs_tx,
+            time_service: time_service.into_mock(),
+            _storage_service_notifier: storage_service_notifier,
+        };
+        (mock_client, storage_server)
     }
 
     async fn send_request(
         &mut self,
         request: StorageServiceRequest,
     ) -> Result<StorageServiceResponse, StorageServiceError> {
+        let res_rx = self.send_request_without_waiting(request);
+
+        // wait for the response and deserialize
+        let response = res_rx.await.unwrap().unwrap();
+        let response = ProtocolId::StorageServiceRpc
+            .from_bytes::<StorageServiceMessage>(&response)
+            .unwrap();
+        match response {
+            StorageServiceMessage::Response(response) => response,
+            _ => panic!("Unexpected response message: {:?}", response),
+        }
+    }
+
+    /// Sends the request to the storage service and returns the (raw) response
+    /// receiver without waiting for the response.
+    fn send_request_without_waiting(
+        &mut self,
+        request: StorageServiceRequest,
+    ) -> oneshot::Receiver<Result<Bytes, RpcError>> {
         // craft the inbound Rpc notification
         let peer_id = PeerId::ZERO;
         let protocol_id = ProtocolId::StorageServiceRpc;
@@ -319,15 +679,7 @@ impl MockClient {
             .push((peer_id, protocol_id), notif)
             .unwrap();
 
-        // wait for the response and deserialize
-        let response = res_rx.await.unwrap().unwrap();
-        let response = protocol_id
-            .from_bytes::<StorageServiceMessage>(&response)
-            .unwrap();
-        match response {
-            StorageServiceMessage::Response(response) => response,
-            _ => panic!("Unexpected response message: {:?}", response),
-        }
+        res_rx
     }
 }
 
@@ -454,11 +806,27 @@ impl DbReader<DpnProto> for MockDbReader {
 
     fn get_transaction_outputs(
         &self,
-        _start_version: Version,
-        _limit: u64,
+        start_version: Version,
+        limit: u64,
         _ledger_version: Version,
     ) -> Result<TransactionOutputListWithProof> {
-        unimplemented!()
+        // Create mock transactions and outputs
+        let mut transactions_and_outputs = vec![];
+        for i in 0..limit {
+            let transaction_output = TransactionOutput::new(
+                WriteSet::default(),
+                vec![create_test_event(i)],
+                0,
+                TransactionStatus::Keep(KeptVMStatus::Executed),
+            );
+            transactions_and_outputs.push((create_test_transaction(i), transaction_output));
+        }
+
+        Ok(TransactionOutputListWithProof::new(
+            transactions_and_outputs,
+            Some(start_version),
+            TransactionInfoListWithProof::new_empty(),
+        ))
     }
 
     /// Returns events by given event key
@@ -26,6 +26,10 @@ pub type Result<T, E = StorageServiceError> = ::std::result::Result<T, E>;
 pub enum StorageServiceError {
     #[error("Internal service error")]
     InternalError,
+    #[error("The data subscription expired before new data was available")]
+    SubscriptionExpired,
+    #[error("The data subscription was replaced by a newer subscription from the same peer")]
+    SubscriptionSuperseded,
 }
 
 /// A single storage service message sent or received over DiemNet.
@@ -41,10 +45,11 @@ pub enum StorageServiceMessage {
 }
 
 /// A storage service request.
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub enum StorageServiceRequest {
     GetAccountStatesChunkWithProof(AccountStatesChunkWithProofRequest), // Fetches a list of account states with a proof
     GetEpochEndingLedgerInfos(EpochEndingLedgerInfoRequest), // Fetches a list of epoch ending ledger infos
+    GetNewTransactionOutputsWithProof(NewTransactionOutputsWithProofRequest), // Subscribes to new transaction outputs with a proof
     GetNumberOfAccountsAtVersion(Version), // Fetches the number of accounts at the specified version
     GetServerProtocolVersion,              // Fetches the protocol version run by the server
     GetStorageServerSummary,               // Fetches a summary of the storage server state
@@ -56,6 +61,12 @@ impl StorageServiceRequest {
     pub fn is_get_storage_server_summary(&self) -> bool {
         matches!(self, &Self::GetStorageServerSummary)
     }
+
+    /// Returns true iff the request is a data subscription (i.e., a long-poll
+    /// request that the server only responds to once new data is available).
+    pub fn is_data_subscription_request(&self) -> bool {
+        matches!(self, &Self::GetNewTransactionOutputsWithProof(_))
+    }
 }
 
 /// A storage service response.
@@ -65,6 +76,7 @@ impl StorageServiceRequest {
 pub enum StorageServiceResponse {
     AccountStatesChunkWithProof(AccountStatesChunkWithProof),
     EpochEndingLedgerInfos(EpochChangeProof),
+    NewTransactionOutputsWithProof((TransactionOutputListWithProof, LedgerInfoWithSignatures)),
     NumberOfAccountsAtVersion(u64),
     ServerProtocolVersion(ServerProtocolVersion),
     StorageServerSummary(StorageServerSummary),
@@ -78,6 +90,7 @@ impl StorageServiceResponse {
         match self {
             Self::AccountStatesChunkWithProof(_) => "AccountStatesChunkWithProof",
             Self::EpochEndingLedgerInfos(_) => "EpochEndingLedgerInfos",
+            Self::NewTransactionOutputsWithProof(_) => "NewTransactionOutputsWithProof",
             Self::NumberOfAccountsAtVersion(_) => "NumberOfAccountsAtVersion",
             Self::ServerProtocolVersion(_) => "ServerProtocolVersion",
             Self::StorageServerSummary(_) => "StorageServerSummary",
@@ -120,6 +133,21 @@ impl TryFrom<StorageServiceResponse> for EpochChangeProof {
     }
 }
 
+impl TryFrom<StorageServiceResponse>
+    for (TransactionOutputListWithProof, LedgerInfoWithSignatures)
+{
+    type Error = UnexpectedResponseError;
+    fn try_from(response: StorageServiceResponse) -> Result<Self, Self::Error> {
+        match response {
+            StorageServiceResponse::NewTransactionOutputsWithProof(inner) => Ok(inner),
+            _ => Err(UnexpectedResponseError(format!(
+                "expected NewTransactionOutputsWithProof found {}",
+                response.name()
+            ))),
+        }
+    }
+}
+
 impl TryFrom<StorageServiceResponse> for u64 {
     type Error = UnexpectedResponseError;
     fn try_from(response: StorageServiceResponse) -> Result<Self, Self::Error> {
@@ -187,16 +215,27 @@ impl TryFrom<StorageServiceResponse> for TransactionListWithProof {
 
 /// A storage service request for fetching a list of account states at a
 /// specified version.
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub struct AccountStatesChunkWithProofRequest {
     pub version: u64,                     // The version to fetch the account states at
     pub start_account_index: u64,         // The account index to start fetching account states
     pub expected_num_account_states: u64, // Expected number of account states to fetch
 }
 
+/// A storage service request for subscribing to new transaction outputs (i.e.,
+/// those after the `known_version`). The server holds the request until new
+/// data has been committed (or the subscription expires) and responds with
+/// the outputs and a ledger info that proves them. If `known_epoch` has ended,
+/// the proof is relative to the epoch ending ledger info of `known_epoch`.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct NewTransactionOutputsWithProofRequest {
+    pub known_version: u64, // The highest known output version
+    pub known_epoch: u64,   // The highest known epoch
+}
+
 /// A storage service request for fetching a transaction output list with a
 /// corresponding proof.
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub struct TransactionOutputsWithProofRequest {
     pub proof_version: u64,        // The version the proof should be relative to
     pub start_version: u64,        // The starting version of the transaction output list
@@ -205,7 +244,7 @@ pub struct TransactionOutputsWithProofRequest {
 
 /// A storage service request for fetching a transaction list with a
 /// corresponding proof.
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub struct TransactionsWithProofRequest {
     pub proof_version: u64, // The version the proof should be relative to
     pub start_version: u64, // The starting version of the transaction list
@@ -214,7 +253,7 @@ pub struct TransactionsWithProofRequest {
 }
 
 /// A storage service request for fetching a list of epoch ending ledger infos.
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub struct EpochEndingLedgerInfoRequest {
     pub start_epoch: u64,
     pub expected_end_epoch: u64,
@@ -175,6 +175,100 @@ fn test_sync_transactions_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoW
     }
 }
 
+fn test_state_snapshot_restore_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) {
+    // Commit all blocks to the source db
+    let tmp_dir1 = TempPath::new();
+    let db1 = DiemDB::new_for_test(&tmp_dir1);
+    let mut cur_ver = 0;
+    let mut account_states = HashMap::new();
+    for (txns_to_commit, ledger_info_with_sigs) in &input {
+        db1.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        for txn_to_commit in txns_to_commit {
+            account_states.extend(txn_to_commit.account_states().clone());
+        }
+        cur_ver += txns_to_commit.len() as u64;
+    }
+    let latest_ledger_info = input.last().unwrap().1.clone();
+    let version = latest_ledger_info.ledger_info().version();
+    let expected_root_hash = db1.state_store.get_root_hash(version).unwrap();
+
+    let mut ordered_account_states: Vec<_> = account_states
+        .into_iter()
+        .map(|(address, blob)| (address.hash(), blob))
+        .collect();
+    ordered_account_states.sort_unstable_by_key(|(key, _blob)| *key);
+    let num_accounts = ordered_account_states.len();
+    let get_chunk = |skip: usize, take: usize| {
+        let chunk: Vec<_> = ordered_account_states
+            .iter()
+            .skip(skip)
+            .take(take)
+            .cloned()
+            .collect();
+        let rightmost_key = chunk.last().map(|(key, _blob)| *key).unwrap();
+        let proof = db1
+            .state_store
+            .get_account_state_range_proof(rightmost_key, version)
+            .unwrap();
+        (chunk, proof)
+    };
+
+    // Restore the first half of the accounts and interrupt the restore
+    let tmp_dir2 = TempPath::new();
+    {
+        let db2 = DiemDB::new_for_test(&tmp_dir2);
+        let mut receiver = db2
+            .get_state_snapshot_receiver(version, expected_root_hash)
+            .unwrap();
+        let (chunk, proof) = get_chunk(0, (num_accounts / 2).max(1));
+        receiver.add_chunk(chunk, proof).unwrap();
+        assert_eq!(
+            db2.get_state_snapshot_restore_version().unwrap(),
+            Some(version)
+        );
+    }
+
+    // Resume the restore and restart before the snapshot is finalized
+    {
+        let db2 = DiemDB::new_for_test(&tmp_dir2);
+        let mut receiver = db2
+            .get_state_snapshot_receiver(version, expected_root_hash)
+            .unwrap();
+        assert!(!receiver.is_finished());
+        let num_accounts_restored = receiver.num_accounts_restored() as usize;
+        if num_accounts_restored < num_accounts {
+            let (chunk, proof) = get_chunk(num_accounts_restored, num_accounts);
+            receiver.add_chunk(chunk, proof).unwrap();
+        }
+        receiver.finish_box().unwrap();
+    }
+
+    // Finalize the snapshot with the transaction output at the snapshot version
+    let db2 = DiemDB::new_for_test(&tmp_dir2);
+    let receiver = db2
+        .get_state_snapshot_receiver(version, expected_root_hash)
+        .unwrap();
+    assert!(receiver.is_finished());
+    assert_eq!(receiver.num_accounts_restored() as usize, num_accounts);
+    let output_with_proof = db1.get_transaction_outputs(version, 1, version).unwrap();
+    db2.finalize_state_snapshot(version, output_with_proof, &[latest_ledger_info.clone()])
+        .unwrap();
+
+    assert_eq!(db2.get_state_snapshot_restore_version().unwrap(), None);
+    assert_eq!(db2.get_latest_ledger_info().unwrap(), latest_ledger_info);
+    assert_eq!(
+        db2.get_latest_state_root().unwrap(),
+        (version, expected_root_hash)
+    );
+    assert_eq!(
+        db2.ledger_store.get_root_hash(version).unwrap(),
+        db1.ledger_store.get_root_hash(version).unwrap()
+    );
+}
+
 fn get_events_by_event_key(
     db: &DiemDB,
     ledger_info: &LedgerInfo,
@@ -576,6 +670,11 @@ proptest! {
     fn test_sync_transactions(input in arb_blocks_to_commit()) {
         test_sync_transactions_impl(input);
     }
+
+    #[test]
+    fn test_state_snapshot_restore(input in arb_blocks_to_commit()) {
+        test_state_snapshot_restore_impl(input);
+    }
 }
 
 #[test]
@@ -24,8 +24,9 @@ use diem_types::{
     epoch_state::EpochState,
     ledger_info::LedgerInfoWithSignatures,
     proof::{
-        definition::LeafCount, position::Position, AccumulatorConsistencyProof,
-        TransactionAccumulatorProof, TransactionAccumulatorRangeProof,
+        definition::LeafCount,
+        position::{FrozenSubTreeIterator, Position},
+        AccumulatorConsistencyProof, TransactionAccumulatorProof, TransactionAccumulatorRangeProof,
     },
     transaction::{
         default_protocol::TransactionInfoWithProof, TransactionInfo, TransactionInfoTrait, Version,
@@ -33,7 +34,7 @@ use diem_types::{
 };
 use itertools::Itertools;
 use schemadb::{ReadOptions, SchemaIterator, DB};
-use std::{ops::Deref, sync::Arc};
+use std::{collections::HashMap, ops::Deref, sync::Arc};
 use storage_interface::{StartupInfo, TreeState};
 
 #[derive(Debug)]
@@ -350,6 +351,53 @@ impl LedgerStore {
         Ok(root_hash)
     }
 
+    /// Write the frozen subtree roots of the accumulator with `first_version` leaves (from left to
+    /// right) and `txn_infos` to `cs`. Unlike `put_transaction_infos`, the frozen subtrees aren't
+    /// read from the db, so they can be persisted in the same batch as the transaction infos
+    /// (e.g., when finalizing a state snapshot).
+    pub fn put_frozen_subtrees_and_transaction_infos(
+        &self,
+        first_version: u64,
+        frozen_subtrees: &[HashValue],
+        txn_infos: &[TransactionInfo],
+        cs: &mut ChangeSet,
+    ) -> Result<HashValue> {
+        let positions: Vec<_> = FrozenSubTreeIterator::new(first_version).collect();
+        ensure!(
+            positions.len() == frozen_subtrees.len(),
+            "Number of frozen subtree roots not expected. Expected: {}, actual: {}",
+            positions.len(),
+            frozen_subtrees.len(),
+        );
+        let frozen_subtrees: HashMap<Position, HashValue> = positions
+            .into_iter()
+            .zip(frozen_subtrees.iter().cloned())
+            .collect();
+        frozen_subtrees
+            .iter()
+            .try_for_each(|(pos, hash)| cs.batch.put::<TransactionAccumulatorSchema>(pos, hash))?;
+
+        // write txn_info
+        (first_version..first_version + txn_infos.len() as u64)
+            .zip_eq(txn_infos.iter())
+            .try_for_each(|(version, txn_info)| {
+                cs.batch.put::<TransactionInfoSchema>(&version, txn_info)
+            })?;
+
+        // write hash of txn_info into the accumulator
+        let txn_hashes: Vec<HashValue> = txn_infos.iter().map(TransactionInfo::hash).collect();
+        let (root_hash, writes) =
+            MerkleAccumulator::<FrozenSubtreeReader, TransactionAccumulatorHasher>::append(
+                &FrozenSubtreeReader(frozen_subtrees),
+                first_version, /* num_existing_leaves */
+                &txn_hashes,
+            )?;
+        writes
+            .iter()
+            .try_for_each(|(pos, hash)| cs.batch.put::<TransactionAccumulatorSchema>(pos, hash))?;
+        Ok(root_hash)
+    }
+
     /// Write `ledger_info` to `cs`.
     pub fn put_ledger_info(
         &self,
@@ -382,6 +430,18 @@ impl HashReader for LedgerStore {
     }
 }
 
+/// Reads the frozen subtree roots of an accumulator that haven't been persisted yet.
+struct FrozenSubtreeReader(HashMap<Position, HashValue>);
+
+impl HashReader for FrozenSubtreeReader {
+    fn get(&self, position: Position) -> Result<HashValue> {
+        self.0
+            .get(&position)
+            .cloned()
+            .ok_or_else(|| format_err!("{} is not a frozen subtree root.", position))
+    }
+}
+
 pub struct TransactionInfoIter<'a> {
     inner: SchemaIterator<'a, TransactionInfoSchema>,
     expected_next_version: Version,
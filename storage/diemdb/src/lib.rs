@@ -51,7 +51,7 @@ use crate::{
     },
     pruner::Pruner,
     schema::*,
-    state_store::StateStore,
+    state_store::{snapshot_restore, snapshot_restore::StateSnapshotRestore, StateStore},
     system_store::SystemStore,
     transaction_store::TransactionStore,
 };
@@ -101,7 +101,9 @@ use std::{
     thread::{self, JoinHandle},
     time::{Duration, Instant},
 };
-use storage_interface::{DbReader, DbWriter, MoveDbReader, Order, StartupInfo, TreeState};
+use storage_interface::{
+    DbReader, DbWriter, MoveDbReader, Order, StartupInfo, StateSnapshotReceiver, TreeState,
+};
 
 const MAX_LIMIT: u64 = 1000;
 
@@ -238,6 +240,7 @@ impl DiemDB {
             JELLYFISH_MERKLE_NODE_CF_NAME,
             LEDGER_COUNTERS_CF_NAME,
             STALE_NODE_INDEX_CF_NAME,
+            STATE_SNAPSHOT_PROGRESS_CF_NAME,
             TRANSACTION_CF_NAME,
             TRANSACTION_ACCUMULATOR_CF_NAME,
             TRANSACTION_BY_ACCOUNT_CF_NAME,
@@ -574,12 +577,17 @@ impl DiemDB {
             .iter()
             .map(|txn_to_commit| txn_to_commit.jf_node_hashes())
             .collect::<Option<Vec<_>>>();
-        let state_root_hashes = self.state_store.put_account_state_sets(
-            account_state_sets,
-            node_hashes,
-            first_version,
-            &mut cs,
-        )?;
+        let state_root_hashes = {
+            let _timer = DIEM_STORAGE_OTHER_TIMERS_SECONDS
+                .with_label_values(&["save_transactions_jmt_update"])
+                .start_timer();
+            self.state_store.put_account_state_sets(
+                account_state_sets,
+                node_hashes,
+                first_version,
+                &mut cs,
+            )?
+        };
 
         // Event updates. Gather event accumulator root hashes.
         let event_root_hashes = zip_eq(first_version..=last_version, txns_to_commit)
@@ -1160,6 +1168,12 @@ impl DbReader<DpnProto> for DiemDB {
                 .get_consistency_proof(client_known_version, ledger_version)
         })
     }
+
+    fn get_state_snapshot_restore_version(&self) -> Result<Option<Version>> {
+        gauged_api("get_state_snapshot_restore_version", || {
+            snapshot_restore::get_restore_version(&self.db)
+        })
+    }
 }
 
 impl ModuleResolver for DiemDB {
@@ -1287,6 +1301,109 @@ impl DbWriter<DpnProto> for DiemDB {
             Ok(())
         })
     }
+
+    fn get_state_snapshot_receiver(
+        &self,
+        version: Version,
+        expected_root_hash: HashValue,
+    ) -> Result<Box<dyn StateSnapshotReceiver>> {
+        gauged_api("get_state_snapshot_receiver", || {
+            Ok(Box::new(StateSnapshotRestore::new(
+                &self.state_store,
+                version,
+                expected_root_hash,
+            )?) as Box<dyn StateSnapshotReceiver>)
+        })
+    }
+
+    /// `output_with_proof` must hold exactly the transaction (and output) at `version`, and the
+    /// account state tree at `version` must have been fully restored.
+    fn finalize_state_snapshot(
+        &self,
+        version: Version,
+        output_with_proof: TransactionOutputListWithProof,
+        ledger_infos: &[LedgerInfoWithSignatures],
+    ) -> Result<()> {
+        gauged_api("finalize_state_snapshot", || {
+            // Verify the transaction output against the latest ledger info
+            let latest_ledger_info = ledger_infos
+                .last()
+                .ok_or_else(|| format_err!("No ledger infos to save."))?;
+            ensure!(
+                output_with_proof.transactions_and_outputs.len() == 1,
+                "Expected a single transaction output at version {}, found: {}.",
+                version,
+                output_with_proof.transactions_and_outputs.len(),
+            );
+            output_with_proof.verify(latest_ledger_info.ledger_info(), Some(version))?;
+
+            // Verify the account state tree has been restored (the root node is only written once
+            // the restore has finished).
+            let (transaction, output) = &output_with_proof.transactions_and_outputs[0];
+            let transaction_info = &output_with_proof.proof.transaction_infos[0];
+            let state_root_hash = self.state_store.get_root_hash(version)?;
+            ensure!(
+                state_root_hash == transaction_info.state_root_hash(),
+                "Restored state root hash doesn't match expected. {:?} vs {:?}",
+                state_root_hash,
+                transaction_info.state_root_hash(),
+            );
+
+            // Save the frozen subtrees of the transaction accumulator before `version` (these
+            // are exactly the left siblings in the proof of the transaction info), the
+            // transaction, output, transaction info and ledger infos.
+            let frozen_subtrees: Vec<_> = output_with_proof
+                .proof
+                .ledger_info_to_transaction_infos_proof
+                .left_siblings()
+                .iter()
+                .rev()
+                .cloned()
+                .collect();
+            let mut cs = ChangeSet::new();
+            self.transaction_store
+                .put_transaction(version, transaction, &mut cs)?;
+            self.transaction_store
+                .put_write_set(version, output.write_set(), &mut cs)?;
+            self.event_store
+                .put_events(version, output.events(), &mut cs)?;
+            let root_hash = self
+                .ledger_store
+                .put_frozen_subtrees_and_transaction_infos(
+                    version,
+                    &frozen_subtrees,
+                    &[transaction_info.clone()],
+                    &mut cs,
+                )?;
+            ensure!(
+                latest_ledger_info.ledger_info().version() != version
+                    || root_hash
+                        == latest_ledger_info
+                            .ledger_info()
+                            .transaction_accumulator_hash(),
+                "Root hash calculated doesn't match expected. {:?} vs {:?}",
+                root_hash,
+                latest_ledger_info
+                    .ledger_info()
+                    .transaction_accumulator_hash(),
+            );
+            for ledger_info in ledger_infos {
+                self.ledger_store.put_ledger_info(ledger_info, &mut cs)?;
+            }
+
+            // Remove the restore progress and persist everything in a single batch
+            cs.batch.delete::<StateSnapshotProgressSchema>(&version)?;
+            self.db.write_schemas(cs.batch)?;
+
+            // Once everything is successfully persisted, update the latest in-memory ledger info.
+            self.ledger_store
+                .set_latest_ledger_info(latest_ledger_info.clone());
+            DIEM_STORAGE_LEDGER_VERSION.set(latest_ledger_info.ledger_info().version() as i64);
+            DIEM_STORAGE_LATEST_TXN_VERSION.set(version as i64);
+
+            Ok(())
+        })
+    }
 }
 
 // Convert requested range and order to a range in ascending order.
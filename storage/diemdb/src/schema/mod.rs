@@ -15,6 +15,7 @@ pub(crate) mod jellyfish_merkle_node;
 pub(crate) mod ledger_counters;
 pub(crate) mod ledger_info;
 pub(crate) mod stale_node_index;
+pub(crate) mod state_snapshot_progress;
 pub(crate) mod transaction;
 pub(crate) mod transaction_accumulator;
 pub(crate) mod transaction_by_account;
@@ -33,6 +34,7 @@ pub const EVENT_CF_NAME: ColumnFamilyName = "event";
 pub const JELLYFISH_MERKLE_NODE_CF_NAME: ColumnFamilyName = "jellyfish_merkle_node";
 pub const LEDGER_COUNTERS_CF_NAME: ColumnFamilyName = "ledger_counters";
 pub const STALE_NODE_INDEX_CF_NAME: ColumnFamilyName = "stale_node_index";
+pub const STATE_SNAPSHOT_PROGRESS_CF_NAME: ColumnFamilyName = "state_snapshot_progress";
 pub const TRANSACTION_CF_NAME: ColumnFamilyName = "transaction";
 pub const TRANSACTION_ACCUMULATOR_CF_NAME: ColumnFamilyName = "transaction_accumulator";
 pub const TRANSACTION_BY_ACCOUNT_CF_NAME: ColumnFamilyName = "transaction_by_account";
@@ -86,6 +88,10 @@ pub mod fuzzing {
             decode_key_value!(super::ledger_counters::LedgerCountersSchema, data);
             decode_key_value!(super::ledger_info::LedgerInfoSchema, data);
             decode_key_value!(super::stale_node_index::StaleNodeIndexSchema, data);
+            decode_key_value!(
+                super::state_snapshot_progress::StateSnapshotProgressSchema,
+                data
+            );
             decode_key_value!(super::transaction::TransactionSchema, data);
             decode_key_value!(
                 super::transaction_accumulator::TransactionAccumulatorSchema,
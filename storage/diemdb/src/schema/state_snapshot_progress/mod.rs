@@ -0,0 +1,57 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines physical storage schema for the progress of account state snapshot
+//! restores. A record (`version`, `num_accounts`) indicates that the snapshot of the account state
+//! tree at `version` is being restored, and that the first `num_accounts` accounts (i.e., leaves)
+//! have already been persisted. The record is removed once the restore completes.
+//!
+//! ```text
+//! |<--key-->|<----value--->|
+//! | version | num_accounts |
+//! ```
+//!
+//! `version` is serialized in big endian so that records in RocksDB will be in order of their
+//! numeric value.
+
+use crate::schema::{ensure_slice_len_eq, STATE_SNAPSHOT_PROGRESS_CF_NAME};
+use anyhow::Result;
+use byteorder::{BigEndian, ReadBytesExt};
+use diem_types::transaction::Version;
+use schemadb::{
+    define_schema,
+    schema::{KeyCodec, ValueCodec},
+};
+use std::mem::size_of;
+
+define_schema!(
+    StateSnapshotProgressSchema,
+    Version,
+    u64, // num_accounts
+    STATE_SNAPSHOT_PROGRESS_CF_NAME
+);
+
+impl KeyCodec<StateSnapshotProgressSchema> for Version {
+    fn encode_key(&self) -> Result<Vec<u8>> {
+        Ok(self.to_be_bytes().to_vec())
+    }
+
+    fn decode_key(mut data: &[u8]) -> Result<Self> {
+        ensure_slice_len_eq(data, size_of::<Self>())?;
+        Ok(data.read_u64::<BigEndian>()?)
+    }
+}
+
+impl ValueCodec<StateSnapshotProgressSchema> for u64 {
+    fn encode_value(&self) -> Result<Vec<u8>> {
+        Ok(self.to_be_bytes().to_vec())
+    }
+
+    fn decode_value(mut data: &[u8]) -> Result<Self> {
+        ensure_slice_len_eq(data, size_of::<Self>())?;
+        Ok(data.read_u64::<BigEndian>()?)
+    }
+}
+
+#[cfg(test)]
+mod test;
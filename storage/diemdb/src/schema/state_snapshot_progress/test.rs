@@ -0,0 +1,16 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use proptest::prelude::*;
+use schemadb::schema::assert_encode_decode;
+
+proptest! {
+    #[test]
+    fn test_encode_decode(
+        version in any::<Version>(),
+        num_accounts in any::<u64>(),
+    ) {
+        assert_encode_decode::<StateSnapshotProgressSchema>(&version, &num_accounts);
+    }
+}
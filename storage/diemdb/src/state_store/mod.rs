@@ -3,6 +3,7 @@
 
 //! This file defines state store APIs that are related account state Merkle tree.
 
+pub(crate) mod snapshot_restore;
 #[cfg(test)]
 mod state_store_test;
 
@@ -162,18 +163,7 @@ impl TreeReader<AccountStateBlob> for StateStore {
         self.db.get::<JellyfishMerkleNodeSchema>(node_key)
     }
 
-    fn get_rightmost_leaf(&self) -> Result<Option<(NodeKey, LeafNode)>> {
-        // Since everything has the same version during restore, we seek to the first node and get
-        // its version.
-        let mut iter = self
-            .db
-            .iter::<JellyfishMerkleNodeSchema>(Default::default())?;
-        iter.seek_to_first();
-        let version = match iter.next().transpose()? {
-            Some((node_key, _node)) => node_key.version(),
-            None => return Ok(None),
-        };
-
+    fn get_rightmost_leaf(&self, version: Version) -> Result<Option<(NodeKey, LeafNode)>> {
         // The encoding of key and value in DB looks like:
         //
         // | <-------------- key --------------> | <- value -> |
@@ -204,7 +194,10 @@ impl TreeReader<AccountStateBlob> for StateStore {
             iter.seek_for_prev(&seek_key)?;
 
             if let Some((node_key, node)) = iter.next().transpose()? {
-                debug_assert_eq!(node_key.version(), version);
+                // The node may belong to a different (i.e., earlier) version of the tree
+                if node_key.version() != version {
+                    continue;
+                }
                 debug_assert!(node_key.nibble_path().num_nibbles() < num_nibbles);
 
                 if let Node::Leaf(leaf_node) = node {
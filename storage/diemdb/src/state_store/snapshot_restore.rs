@@ -0,0 +1,174 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This file defines the receiver used to restore (and resume the restore of) an account state
+//! snapshot, e.g., when a node bootstraps by downloading the account states from its peers.
+
+use crate::{
+    schema::state_snapshot_progress::StateSnapshotProgressSchema,
+    state_store::{add_node_batch, LeafNode, Node, NodeBatch, StateStore},
+};
+use anyhow::{bail, Result};
+use diem_crypto::HashValue;
+use diem_infallible::Mutex;
+use diem_jellyfish_merkle::{
+    node_type::NodeKey, restore::JellyfishMerkleRestore, TreeReader, TreeWriter,
+};
+use diem_types::{
+    account_state_blob::AccountStateBlob, proof::SparseMerkleRangeProof, transaction::Version,
+};
+use schemadb::{SchemaBatch, DB};
+use std::sync::Arc;
+use storage_interface::StateSnapshotReceiver;
+
+/// Returns the version of the account state snapshot being restored (if any).
+pub(crate) fn get_restore_version(db: &DB) -> Result<Option<Version>> {
+    let mut iter = db.iter::<StateSnapshotProgressSchema>(Default::default())?;
+    iter.seek_to_last();
+    Ok(iter
+        .next()
+        .transpose()?
+        .map(|(version, _num_accounts)| version))
+}
+
+/// A tree writer that persists the number of restored accounts (i.e., leaves) atomically with
+/// each node batch. The restore progress is only removed once the snapshot has been finalized
+/// (see `DiemDB::finalize_state_snapshot`), so that it is removed atomically with the ledger data.
+struct ProgressTrackingTreeWriter {
+    state_store: Arc<StateStore>,
+    version: Version,
+    num_accounts_restored: Mutex<u64>,
+    finished: Mutex<bool>,
+}
+
+impl TreeReader<AccountStateBlob> for ProgressTrackingTreeWriter {
+    fn get_node_option(&self, node_key: &NodeKey) -> Result<Option<Node>> {
+        self.state_store.get_node_option(node_key)
+    }
+
+    fn get_rightmost_leaf(&self, version: Version) -> Result<Option<(NodeKey, LeafNode)>> {
+        self.state_store.get_rightmost_leaf(version)
+    }
+}
+
+impl TreeWriter<AccountStateBlob> for ProgressTrackingTreeWriter {
+    fn write_node_batch(&self, node_batch: &NodeBatch) -> Result<()> {
+        let mut batch = SchemaBatch::new();
+        add_node_batch(&mut batch, node_batch)?;
+
+        // Update the restore progress in the same batch as the nodes
+        let mut num_accounts_restored = self.num_accounts_restored.lock();
+        let num_new_accounts = node_batch
+            .values()
+            .filter(|node| matches!(node, Node::Leaf(_)))
+            .count() as u64;
+        let new_num_accounts_restored = *num_accounts_restored + num_new_accounts;
+        batch.put::<StateSnapshotProgressSchema>(&self.version, &new_num_accounts_restored)?;
+        self.state_store.db.write_schemas(batch)?;
+
+        *num_accounts_restored = new_num_accounts_restored;
+        Ok(())
+    }
+}
+
+/// Restores the account state snapshot at a single version. If a previous restore of the same
+/// version was interrupted, the restore resumes from the last persisted account. If the previous
+/// restore was already finished (i.e., the root node was written), there is nothing to restore.
+pub(crate) struct StateSnapshotRestore {
+    restore: Option<JellyfishMerkleRestore<AccountStateBlob>>,
+    tree_writer: Arc<ProgressTrackingTreeWriter>,
+}
+
+impl StateSnapshotRestore {
+    pub fn new(
+        state_store: &Arc<StateStore>,
+        version: Version,
+        expected_root_hash: HashValue,
+    ) -> Result<Self> {
+        let db = &state_store.db;
+        let num_accounts_restored = db.get::<StateSnapshotProgressSchema>(&version)?;
+
+        // Only a single snapshot is restored at a time, so discard the progress of any other
+        // (interrupted) restore. Its nodes will never be referenced by a root node.
+        let mut batch = SchemaBatch::new();
+        let mut iter = db.iter::<StateSnapshotProgressSchema>(Default::default())?;
+        iter.seek_to_first();
+        for result in iter {
+            let (restore_version, _) = result?;
+            if restore_version != version {
+                batch.delete::<StateSnapshotProgressSchema>(&restore_version)?;
+            }
+        }
+        db.write_schemas(batch)?;
+
+        // The root node is only written once the restore has finished
+        let finished = num_accounts_restored.is_some()
+            && state_store.get_root_hash_option(version)? == Some(expected_root_hash);
+        let tree_writer = Arc::new(ProgressTrackingTreeWriter {
+            state_store: Arc::clone(state_store),
+            version,
+            num_accounts_restored: Mutex::new(num_accounts_restored.unwrap_or(0)),
+            finished: Mutex::new(finished),
+        });
+        let leaf_count_migration = state_store.account_count_migration;
+        let restore = if finished {
+            None
+        } else if num_accounts_restored.is_some() {
+            Some(JellyfishMerkleRestore::new(
+                Arc::clone(&tree_writer),
+                version,
+                expected_root_hash,
+                leaf_count_migration,
+            )?)
+        } else {
+            Some(JellyfishMerkleRestore::new_overwrite(
+                Arc::clone(&tree_writer),
+                version,
+                expected_root_hash,
+                leaf_count_migration,
+            )?)
+        };
+
+        Ok(Self {
+            restore,
+            tree_writer,
+        })
+    }
+}
+
+impl StateSnapshotReceiver for StateSnapshotRestore {
+    fn num_accounts_restored(&self) -> u64 {
+        *self.tree_writer.num_accounts_restored.lock()
+    }
+
+    fn is_finished(&self) -> bool {
+        *self.tree_writer.finished.lock()
+    }
+
+    fn add_chunk(
+        &mut self,
+        chunk: Vec<(HashValue, AccountStateBlob)>,
+        proof: SparseMerkleRangeProof,
+    ) -> Result<()> {
+        match self.restore.as_mut() {
+            Some(restore) => restore.add_chunk(chunk, proof),
+            None => bail!(
+                "The account state snapshot at version {} has already been restored.",
+                self.tree_writer.version
+            ),
+        }
+    }
+
+    fn finish_box(self: Box<Self>) -> Result<()> {
+        let Self {
+            restore,
+            tree_writer,
+        } = *self;
+        // There is nothing to finish if the restore was already finished
+        if let Some(restore) = restore {
+            restore.finish()?;
+        }
+        *tree_writer.finished.lock() = true;
+        Ok(())
+    }
+}
@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use super::*;
-use crate::{pruner, DiemDB};
+use crate::{pruner, state_store::snapshot_restore::StateSnapshotRestore, DiemDB};
 use diem_config::config::RocksdbConfig;
 use diem_jellyfish_merkle::restore::JellyfishMerkleRestore;
 use diem_temppath::TempPath;
@@ -15,6 +15,7 @@ use proptest::{
     prelude::*,
 };
 use std::collections::HashSet;
+use storage_interface::StateSnapshotReceiver;
 
 fn put_account_state_set(
     store: &StateStore,
@@ -322,6 +323,82 @@ proptest! {
         prop_assert_eq!(actual_root_hash, expected_root_hash);
     }
 
+    #[test]
+    fn test_snapshot_restore_with_interruption(
+        (input, batch1_size) in hash_map(any::<AccountAddress>(), any::<AccountStateBlob>(), 2..1000)
+            .prop_flat_map(|input| {
+                let len = input.len();
+                (Just(input), 1..len)
+            })
+    ) {
+        let tmp_dir1 = TempPath::new();
+        let db1 = DiemDB::new_for_test(&tmp_dir1);
+        let store1 = &db1.state_store;
+        init_store(store1, input.clone().into_iter());
+
+        let version = (input.len() - 1) as Version;
+        let expected_root_hash = store1.get_root_hash(version).unwrap();
+
+        // Initialize the target store with a tree at version 0 (e.g., genesis)
+        let tmp_dir2 = TempPath::new();
+        let db2 = DiemDB::new_for_test(&tmp_dir2);
+        let store2 = &db2.state_store;
+        init_store(store2, std::iter::once((AccountAddress::random(), AccountStateBlob::from(vec![0]))));
+
+        let mut ordered_input: Vec<_> = input
+            .into_iter()
+            .map(|(addr, value)| (addr.hash(), value))
+            .collect();
+        ordered_input.sort_unstable_by_key(|(key, _value)| *key);
+
+        // Restore the first batch and drop the receiver without finishing the restore
+        {
+            let mut receiver =
+                StateSnapshotRestore::new(store2, version, expected_root_hash).unwrap();
+            prop_assert_eq!(receiver.num_accounts_restored(), 0);
+
+            let batch1: Vec<_> = ordered_input
+                .clone()
+                .into_iter()
+                .take(batch1_size)
+                .collect();
+            let rightmost_of_batch1 = batch1.last().map(|(key, _value)| *key).unwrap();
+            let proof_of_batch1 = store1
+                .get_account_state_range_proof(rightmost_of_batch1, version)
+                .unwrap();
+            receiver.add_chunk(batch1, proof_of_batch1).unwrap();
+            prop_assert_eq!(snapshot_restore::get_restore_version(&store2.db).unwrap(), Some(version));
+        }
+
+        // Resume the restore from the persisted progress
+        let mut receiver = StateSnapshotRestore::new(store2, version, expected_root_hash).unwrap();
+        let num_accounts_restored = receiver.num_accounts_restored() as usize;
+        prop_assert!(num_accounts_restored <= batch1_size);
+
+        let num_accounts = ordered_input.len() as u64;
+        let batch2: Vec<_> = ordered_input
+            .into_iter()
+            .skip(num_accounts_restored)
+            .collect();
+        let rightmost_of_batch2 = batch2.last().map(|(key, _value)| *key).unwrap();
+        let proof_of_batch2 = store1
+            .get_account_state_range_proof(rightmost_of_batch2, version)
+            .unwrap();
+        receiver.add_chunk(batch2, proof_of_batch2).unwrap();
+        prop_assert!(!receiver.is_finished());
+        Box::new(receiver).finish_box().unwrap();
+
+        // The progress is kept until the snapshot is finalized, so a new receiver finds the
+        // restore already finished
+        prop_assert_eq!(snapshot_restore::get_restore_version(&store2.db).unwrap(), Some(version));
+        prop_assert_eq!(store2.get_root_hash(version).unwrap(), expected_root_hash);
+        let receiver = StateSnapshotRestore::new(store2, version, expected_root_hash).unwrap();
+        prop_assert!(receiver.is_finished());
+        prop_assert_eq!(receiver.num_accounts_restored(), num_accounts);
+        Box::new(receiver).finish_box().unwrap();
+        prop_assert_eq!(store2.get_root_hash(version).unwrap(), expected_root_hash);
+    }
+
     #[test]
     fn test_restore_account_count_migration(
         // When the tree has 17 or more nodes, it's not possible that the root node has all children
@@ -420,7 +497,7 @@ proptest! {
         restore.add_chunk(batch1, proof_of_batch1).unwrap();
 
         let expected = store2.get_rightmost_leaf_naive().unwrap();
-        let actual = store2.get_rightmost_leaf().unwrap();
+        let actual = store2.get_rightmost_leaf(version).unwrap();
         prop_assert_eq!(actual, expected);
     }
 
@@ -122,9 +122,9 @@ pub trait TreeReader<V> {
     /// Gets node given a node key. Returns `None` if the node does not exist.
     fn get_node_option(&self, node_key: &NodeKey) -> Result<Option<Node<V>>>;
 
-    /// Gets the rightmost leaf. Note that this assumes we are in the process of restoring the tree
-    /// and all nodes are at the same version.
-    fn get_rightmost_leaf(&self) -> Result<Option<(NodeKey, LeafNode<V>)>>;
+    /// Gets the rightmost leaf at the given version. Note that this assumes we are in the process
+    /// of restoring the tree at `version`.
+    fn get_rightmost_leaf(&self, version: Version) -> Result<Option<(NodeKey, LeafNode<V>)>>;
 }
 
 pub trait TreeWriter<V> {
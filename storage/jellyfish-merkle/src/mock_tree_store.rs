@@ -32,11 +32,11 @@ where
         Ok(self.data.read().0.get(node_key).cloned())
     }
 
-    fn get_rightmost_leaf(&self) -> Result<Option<(NodeKey, LeafNode<V>)>> {
+    fn get_rightmost_leaf(&self, version: Version) -> Result<Option<(NodeKey, LeafNode<V>)>> {
         let locked = self.data.read();
         let mut node_key_and_node: Option<(NodeKey, LeafNode<V>)> = None;
 
-        for (key, value) in locked.0.iter() {
+        for (key, value) in locked.0.iter().filter(|(key, _)| key.version() == version) {
             if let Node::Leaf(leaf_node) = value {
                 if node_key_and_node.is_none()
                     || leaf_node.account_key() > node_key_and_node.as_ref().unwrap().1.account_key()
@@ -116,7 +116,7 @@ where
 
 pub struct JellyfishMerkleRestore<V> {
     /// The underlying storage.
-    store: Arc<dyn TreeWriter<V>>,
+    store: Arc<dyn TreeWriter<V> + Send + Sync>,
 
     /// The version of the tree we are restoring.
     version: Version,
@@ -174,7 +174,7 @@ impl<V> JellyfishMerkleRestore<V>
 where
     V: crate::Value,
 {
-    pub fn new<D: 'static + TreeReader<V> + TreeWriter<V>>(
+    pub fn new<D: 'static + TreeReader<V> + TreeWriter<V> + Send + Sync>(
         store: Arc<D>,
         version: Version,
         expected_root_hash: HashValue,
@@ -182,8 +182,7 @@ where
     ) -> Result<Self> {
         let tree_reader = Arc::clone(&store);
         let (partial_nodes, previous_leaf) =
-            if let Some((node_key, leaf_node)) = tree_reader.get_rightmost_leaf()? {
-                // TODO: confirm rightmost leaf is at the desired version
+            if let Some((node_key, leaf_node)) = tree_reader.get_rightmost_leaf(version)? {
                 // If the system crashed in the middle of the previous restoration attempt, we need
                 // to recover the partial nodes to the state right before the crash.
                 (
@@ -209,7 +208,7 @@ where
         })
     }
 
-    pub fn new_overwrite<D: 'static + TreeWriter<V>>(
+    pub fn new_overwrite<D: 'static + TreeWriter<V> + Send + Sync>(
         store: Arc<D>,
         version: Version,
         expected_root_hash: HashValue,
@@ -50,7 +50,7 @@ proptest! {
         }
 
         {
-            let rightmost_key = match restore_db.get_rightmost_leaf().unwrap() {
+            let rightmost_key = match restore_db.get_rightmost_leaf(version).unwrap() {
                 None => {
                     // Sometimes the batch is too small so nothing is written to DB.
                     return Ok(());
@@ -16,7 +16,7 @@ use diem_types::{
     move_resource::MoveStorage,
     proof::{
         definition::LeafCount, AccumulatorConsistencyProof, SparseMerkleProof,
-        TransactionAccumulatorSummary,
+        SparseMerkleRangeProof, TransactionAccumulatorSummary,
     },
     protocol_spec::ProtocolSpec,
     state_proof::StateProof,
@@ -402,6 +402,14 @@ pub trait DbReader<PS: ProtocolSpec>: Send + Sync {
         unimplemented!()
     }
 
+    /// Returns the version of the account state snapshot that is currently
+    /// being restored (if any), i.e., a restore that was started by a
+    /// [`StateSnapshotReceiver`] but hasn't yet been finalized (see
+    /// [`DbWriter::finalize_state_snapshot`]).
+    fn get_state_snapshot_restore_version(&self) -> Result<Option<Version>> {
+        unimplemented!()
+    }
+
     /// A convenience function for building a [`TransactionAccumulatorSummary`]
     /// at the given `ledger_version`.
     ///
@@ -495,6 +503,61 @@ pub trait DbWriter<PS: ProtocolSpec>: Send + Sync {
         first_version: Version,
         ledger_info_with_sigs: Option<&LedgerInfoWithSignatures>,
     ) -> Result<()>;
+
+    /// Returns a receiver that restores the account state snapshot at `version`
+    /// (with the given root hash) from chunks of accounts. If a previous restore
+    /// of the same version was interrupted, the receiver resumes from the last
+    /// persisted account (see [`StateSnapshotReceiver::num_accounts_restored`]).
+    fn get_state_snapshot_receiver(
+        &self,
+        _version: Version,
+        _expected_root_hash: HashValue,
+    ) -> Result<Box<dyn StateSnapshotReceiver>> {
+        unimplemented!()
+    }
+
+    /// Finalizes a restored account state snapshot at `version`. This persists
+    /// the transaction (and output) at `version`, the frozen subtrees of the
+    /// transaction accumulator and the given ledger infos, so that the node can
+    /// continue syncing from `version + 1`. The output proof is verified against
+    /// the last ledger info. All data (including the removal of the restore
+    /// progress) is written atomically.
+    fn finalize_state_snapshot(
+        &self,
+        _version: Version,
+        _output_with_proof: TransactionOutputListWithProof<PS::TransactionInfo>,
+        _ledger_infos: &[LedgerInfoWithSignatures],
+    ) -> Result<()> {
+        unimplemented!()
+    }
+}
+
+/// Restores an account state snapshot (i.e., the account state tree at a single
+/// version) from chunks of accounts received in key order. The restore progress
+/// is persisted with every chunk, so it survives crashes and restarts.
+pub trait StateSnapshotReceiver: Send + Sync {
+    /// Returns the number of accounts that have been persisted so far. This is
+    /// the index of the next account that should be added to the receiver.
+    fn num_accounts_restored(&self) -> u64;
+
+    /// Returns true iff the restore has already been finished (see
+    /// `finish_box`), e.g., if the node restarted after restoring all accounts
+    /// but before the snapshot was finalized. No more chunks can be added.
+    fn is_finished(&self) -> bool;
+
+    /// Verifies the given chunk of accounts (using the range proof and the
+    /// expected root hash) and persists it. The chunk must start with the
+    /// account that follows the last account added to the receiver.
+    fn add_chunk(
+        &mut self,
+        chunk: Vec<(HashValue, AccountStateBlob)>,
+        proof: SparseMerkleRangeProof,
+    ) -> Result<()>;
+
+    /// Completes the restore by writing the remaining nodes (and the root) of
+    /// the account state tree. Must only be called once all accounts are added.
+    /// The restore progress is kept until the snapshot is finalized.
+    fn finish_box(self: Box<Self>) -> Result<()>;
 }
 
 pub trait MoveDbReader<PS: ProtocolSpec>:
@@ -18,8 +18,12 @@ use diem_types::{
     network_address::NetworkAddress,
     on_chain_config::{ConsensusConfigV1, ConsensusConfigV2},
 };
-use forge::{LocalSwarm, Node, NodeExt, Swarm};
-use std::{convert::TryInto, str::FromStr};
+use forge::{LocalSwarm, Node, NodeExt, Swarm, SwarmExt};
+use std::{
+    convert::TryInto,
+    str::FromStr,
+    time::{Duration, Instant},
+};
 
 #[test]
 fn test_consensus_observer_mode_storage_error() {
@@ -117,6 +121,29 @@ fn test_2chain_upgrade() {
     }));
 }
 
+#[test]
+fn test_2chain_liveness_with_failed_validator() {
+    // genesis starts with 2-chain already, the rounds led by the stopped validator time out
+    // and are skipped with 2-chain timeout certificates.
+    let num_nodes = 4;
+    let mut swarm = new_local_swarm(num_nodes);
+    let validator_peer_ids = swarm.validators().map(|v| v.peer_id()).collect::<Vec<_>>();
+    let stopped_validator = validator_peer_ids[num_nodes - 1];
+
+    swarm.validator_mut(stopped_validator).unwrap().stop();
+    check_create_mint_transfer(&mut swarm);
+
+    swarm
+        .validator_mut(stopped_validator)
+        .unwrap()
+        .start()
+        .unwrap();
+    swarm
+        .wait_for_all_nodes_to_catchup(Instant::now() + Duration::from_secs(60))
+        .unwrap();
+    check_create_mint_transfer(&mut swarm);
+}
+
 #[test]
 fn test_decoupled_execution_upgrade() {
     test_onchain_upgrade(OnChainConsensusConfig::V2(ConsensusConfigV2 {
@@ -8,6 +8,7 @@ use crate::{
         assert_balance, create_and_fund_account, diem_swarm_utils::insert_waypoint, transfer_coins,
     },
 };
+use diem_config::config::NodeConfig;
 use diem_types::{
     account_address::AccountAddress,
     epoch_change::EpochChangeProof,
@@ -128,6 +129,96 @@ fn test_basic_state_synchronization() {
     assert_balance(&client_0, &account_1, 31);
 }
 
+#[test]
+fn test_basic_state_synchronization_with_state_sync_v2() {
+    // - Start a swarm of 4 nodes (and a validator fullnode) using state sync v2.
+    // - Verify that the fullnode syncs the submitted transactions.
+    // - Kill one node and continue submitting transactions to the others.
+    // - Restart the node and wait for all the nodes to catch up.
+    // - Verify that the restarted node has synced up with the submitted transactions.
+    let mut swarm = new_local_swarm(4);
+    for validator in swarm.validators_mut() {
+        let mut config = validator.config().clone();
+        config.state_sync.state_sync_driver.enable_state_sync_v2 = true;
+        config.save(validator.config_path()).unwrap();
+        validator.restart().unwrap();
+    }
+    swarm.launch().unwrap(); // Make sure all nodes are healthy and live
+    let validator_peer_ids = swarm.validators().map(|v| v.peer_id()).collect::<Vec<_>>();
+
+    // Add a validator fullnode that continuously syncs from its validator
+    let version = swarm.versions().max().unwrap();
+    let mut vfn_config = NodeConfig::default_for_validator_full_node();
+    vfn_config.state_sync.state_sync_driver.enable_state_sync_v2 = true;
+    let vfn_peer_id = swarm
+        .add_validator_fullnode(&version, vfn_config, validator_peer_ids[1])
+        .unwrap();
+    swarm
+        .full_node_mut(vfn_peer_id)
+        .unwrap()
+        .wait_until_healthy(Instant::now() + Duration::from_secs(10))
+        .unwrap();
+
+    let client_1 = swarm
+        .validator(validator_peer_ids[1])
+        .unwrap()
+        .json_rpc_client();
+    let vfn_client = swarm.full_node(vfn_peer_id).unwrap().json_rpc_client();
+    let transaction_factory = swarm.chain_info().transaction_factory();
+
+    let mut account_0 = create_and_fund_account(&mut swarm, 100);
+    let account_1 = create_and_fund_account(&mut swarm, 10);
+    transfer_coins(
+        &client_1,
+        &transaction_factory,
+        &mut account_0,
+        &account_1,
+        10,
+    );
+    swarm
+        .wait_for_all_nodes_to_catchup(Instant::now() + Duration::from_secs(60))
+        .unwrap();
+    assert_balance(&vfn_client, &account_0, 90);
+    assert_balance(&vfn_client, &account_1, 20);
+
+    // Stop a node and do a few transfers
+    let node_to_restart = validator_peer_ids[0];
+    swarm.validator_mut(node_to_restart).unwrap().stop();
+    for _ in 0..10 {
+        transfer_coins(
+            &client_1,
+            &transaction_factory,
+            &mut account_0,
+            &account_1,
+            1,
+        );
+    }
+    assert_balance(&client_1, &account_0, 80);
+    assert_balance(&client_1, &account_1, 30);
+
+    // Restart killed node and wait for all nodes to catchup
+    swarm
+        .validator_mut(node_to_restart)
+        .unwrap()
+        .start()
+        .unwrap();
+    swarm
+        .validator_mut(node_to_restart)
+        .unwrap()
+        .wait_until_healthy(Instant::now() + Duration::from_secs(10))
+        .unwrap();
+    swarm
+        .wait_for_all_nodes_to_catchup(Instant::now() + Duration::from_secs(60))
+        .unwrap();
+
+    // Connect to the newly recovered node and verify its state
+    let client_0 = swarm.validator(node_to_restart).unwrap().json_rpc_client();
+    assert_balance(&client_0, &account_0, 80);
+    assert_balance(&client_0, &account_1, 30);
+    assert_balance(&vfn_client, &account_0, 80);
+    assert_balance(&vfn_client, &account_1, 30);
+}
+
 #[test]
 fn test_startup_sync_state() {
     let mut swarm = new_local_swarm(4);
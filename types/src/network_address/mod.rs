@@ -48,6 +48,11 @@ const MAX_DNS_NAME_SIZE: usize = 255;
 ///    connection with the peer.
 /// 4. Perform a DiemNet version negotiation handshake (version 1).
 ///
+/// Validators may also advertise a QUIC address, where the TCP connection is
+/// replaced by a QUIC connection over UDP:
+///
+/// `/dns/example.com/udp/6180/quic/ln-noise-ik/<x25519-pubkey>/ln-handshake/1`
+///
 /// ## Self-describing, Upgradable
 ///
 /// One key concept behind `NetworkAddress` is that it is fully self-describing,
@@ -128,6 +133,8 @@ pub enum Protocol {
     // probably need to move network wire into its own crate to avoid circular
     // dependency b/w network and types.
     Handshake(u8),
+    Udp(u16),
+    Quic,
 }
 
 /// A minimally parsed DNS name. We don't really do any checking other than
@@ -495,6 +502,16 @@ pub fn arb_diemnet_addr() -> impl Strategy<Value = NetworkAddress> {
             .prop_map(|(name, port)| vec![Protocol::Dns4(name), Protocol::Tcp(port)]),
         any::<(DnsName, u16)>()
             .prop_map(|(name, port)| vec![Protocol::Dns6(name), Protocol::Tcp(port)]),
+        any::<(Ipv4Addr, u16)>().prop_map(|(addr, port)| vec![
+            Protocol::Ip4(addr),
+            Protocol::Udp(port),
+            Protocol::Quic
+        ]),
+        any::<(DnsName, u16)>().prop_map(|(name, port)| vec![
+            Protocol::Dns(name),
+            Protocol::Udp(port),
+            Protocol::Quic
+        ]),
     ];
     let arb_diemnet_protos = any::<(x25519::PublicKey, u8)>()
         .prop_map(|(pubkey, hs)| vec![Protocol::NoiseIK(pubkey), Protocol::Handshake(hs)]);
@@ -530,6 +547,8 @@ impl fmt::Display for Protocol {
                     .expect("ValidCryptoMaterialStringExt::to_encoded_string is infallible")
             ),
             Handshake(version) => write!(f, "/ln-handshake/{}", version),
+            Udp(port) => write!(f, "/udp/{}", port),
+            Quic => write!(f, "/quic"),
         }
     }
 }
@@ -560,6 +579,8 @@ impl Protocol {
                 args.next().ok_or(ParseError::UnexpectedEnd)?,
             )?),
             "ln-handshake" => Protocol::Handshake(parse_one(args)?),
+            "udp" => Protocol::Udp(parse_one(args)?),
+            "quic" => Protocol::Quic,
             unknown => return Err(ParseError::UnknownProtocolType(unknown.to_string())),
         };
         Ok(protocol)
@@ -752,6 +773,42 @@ pub fn parse_tcp(protos: &[Protocol]) -> Option<((String, u16), &[Protocol])> {
     }
 }
 
+/// parse the `&[Protocol]` into the `"/ip4/<addr>/udp/<port>/quic"` or
+/// `"/ip6/<addr>/udp/<port>/quic"` prefix and unparsed `&[Protocol]` suffix.
+pub fn parse_ip_udp_quic(protos: &[Protocol]) -> Option<((IpAddr, u16), &[Protocol])> {
+    use Protocol::*;
+
+    if protos.len() < 3 {
+        return None;
+    }
+
+    let (prefix, suffix) = protos.split_at(3);
+    match prefix {
+        [Ip4(ip), Udp(port), Quic] => Some(((IpAddr::V4(*ip), *port), suffix)),
+        [Ip6(ip), Udp(port), Quic] => Some(((IpAddr::V6(*ip), *port), suffix)),
+        _ => None,
+    }
+}
+
+/// parse the `&[Protocol]` into the `"/dns/<domain>/udp/<port>/quic"`,
+/// `"/dns4/<domain>/udp/<port>/quic"`, or `"/dns6/<domain>/udp/<port>/quic"`
+/// prefix and unparsed `&[Protocol]` suffix.
+pub fn parse_dns_udp_quic(protos: &[Protocol]) -> Option<((IpFilter, &DnsName, u16), &[Protocol])> {
+    use Protocol::*;
+
+    if protos.len() < 3 {
+        return None;
+    }
+
+    let (prefix, suffix) = protos.split_at(3);
+    match prefix {
+        [Dns(name), Udp(port), Quic] => Some(((IpFilter::Any, name, *port), suffix)),
+        [Dns4(name), Udp(port), Quic] => Some(((IpFilter::OnlyIp4, name, *port), suffix)),
+        [Dns6(name), Udp(port), Quic] => Some(((IpFilter::OnlyIp6, name, *port), suffix)),
+        _ => None,
+    }
+}
+
 /// parse the `&[Protocol]` into the `"/ln-noise-ik/<pubkey>"` prefix and
 /// unparsed `&[Protocol]` suffix.
 pub fn parse_noise_ik(protos: &[Protocol]) -> Option<(&x25519::PublicKey, &[Protocol])> {
@@ -778,11 +835,15 @@ fn parse_diemnet_protos(protos: &[Protocol]) -> Option<&[Protocol]> {
     // ---
     // parse_ip_tcp
     // <or> parse_dns_tcp
+    // <or> parse_ip_udp_quic
+    // <or> parse_dns_udp_quic
     // <or> cfg!(test) parse_memory
 
     let transport_suffix = parse_ip_tcp(protos)
         .map(|x| x.1)
         .or_else(|| parse_dns_tcp(protos).map(|x| x.1))
+        .or_else(|| parse_ip_udp_quic(protos).map(|x| x.1))
+        .or_else(|| parse_dns_udp_quic(protos).map(|x| x.1))
         .or_else(|| {
             if cfg!(test) {
                 parse_memory(protos).map(|x| x.1)
@@ -866,6 +927,10 @@ mod test {
                 "/dns/example.com/tcp/80",
                 vec![Dns(DnsName("example.com".to_owned())), Tcp(80)],
             ),
+            (
+                "/ip4/12.34.56.78/udp/1234/quic",
+                vec![Ip4(Ipv4Addr::new(12, 34, 56, 78)), Udp(1234), Quic],
+            ),
             (
                 &noise_addr_str,
                 vec![
@@ -995,6 +1060,50 @@ mod test {
         assert_eq!(None, parse_dns_tcp(addr.as_slice()));
     }
 
+    #[test]
+    fn test_parse_ip_udp_quic() {
+        let addr = NetworkAddress::from_str("/ip4/1.2.3.4/udp/123/quic").unwrap();
+        let expected_suffix: &[Protocol] = &[];
+        assert_eq!(
+            parse_ip_udp_quic(addr.as_slice()).unwrap(),
+            ((IpAddr::from_str("1.2.3.4").unwrap(), 123), expected_suffix)
+        );
+
+        let addr = NetworkAddress::from_str("/ip6/::1/udp/123/quic/memory/999").unwrap();
+        let expected_suffix: &[Protocol] = &[Protocol::Memory(999)];
+        assert_eq!(
+            parse_ip_udp_quic(addr.as_slice()).unwrap(),
+            ((IpAddr::from_str("::1").unwrap(), 123), expected_suffix)
+        );
+
+        // QUIC runs over UDP only
+        let addr = NetworkAddress::from_str("/ip4/1.2.3.4/tcp/123/quic").unwrap();
+        assert_eq!(None, parse_ip_udp_quic(addr.as_slice()));
+        let addr = NetworkAddress::from_str("/ip4/1.2.3.4/udp/123").unwrap();
+        assert_eq!(None, parse_ip_udp_quic(addr.as_slice()));
+    }
+
+    #[test]
+    fn test_parse_dns_udp_quic() {
+        let dns_name = DnsName::from_str("example.com").unwrap();
+        let addr = NetworkAddress::from_str("/dns/example.com/udp/123/quic").unwrap();
+        let expected_suffix: &[Protocol] = &[];
+        assert_eq!(
+            parse_dns_udp_quic(addr.as_slice()).unwrap(),
+            ((IpFilter::Any, &dns_name, 123), expected_suffix)
+        );
+
+        let addr = NetworkAddress::from_str("/dns6/example.com/udp/123/quic/memory/44").unwrap();
+        let expected_suffix: &[Protocol] = &[Protocol::Memory(44)];
+        assert_eq!(
+            parse_dns_udp_quic(addr.as_slice()).unwrap(),
+            ((IpFilter::OnlyIp6, &dns_name, 123), expected_suffix)
+        );
+
+        let addr = NetworkAddress::from_str("/dns/example.com/tcp/123").unwrap();
+        assert_eq!(None, parse_dns_udp_quic(addr.as_slice()));
+    }
+
     #[test]
     fn test_parse_noise_ik() {
         let pubkey_str = "080e287879c918794170e258bfaddd75acac5b3e350419044655e4983a487120";